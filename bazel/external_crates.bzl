@@ -1156,6 +1156,9 @@ def external_crates_repository(name, cargo_lockfile, lockfile, sanitizers_enable
             "scraper": crate.spec(
                 version = "^0.17.1",
             ),
+            "scrypt": crate.spec(
+                version = "^0.11.0",
+            ),
             "semver": crate.spec(
                 version = "^1.0.9",
                 features = [
@@ -53,6 +53,7 @@ component_files = {
     Label("misc/chrony/chrony.conf"): "/etc/chrony/chrony.conf",
     Label("misc/chrony/chrony-var.service"): "/etc/systemd/system/chrony-var.service",
     Label("misc/vsock/10-vhost-vsock.rules"): "/etc/udev/rules.d/10-vhost-vsock.rules",
+    Label("misc/vsock/vsock-guestos-health.service"): "/etc/systemd/system/vsock-guestos-health.service",
     Label("misc/guestos/ic-node.conf"): "/etc/tmpfiles.d/ic-node.conf",
     Label("misc/guestos/sudoers"): "/etc/sudoers",
     Label("misc/guestos/crypttab"): "/etc/crypttab",
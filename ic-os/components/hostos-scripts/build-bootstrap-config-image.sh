@@ -81,6 +81,10 @@ options may be specified:
   --node_operator_private_key path
     Should point to a file containing a Node Provider private key PEM.
 
+  --guestos_config path
+    Should point to the GuestOS configuration object generated by
+    "hostos_tool generate-guestos-config".
+
   --backup_retention_time seconds
     How long the backed up consensus artifacts should stay on the spool
     before they get purged.
@@ -122,7 +126,7 @@ function build_ic_bootstrap_tar() {
 
     local IPV6_ADDRESS IPV6_GATEWAY DOMAIN HOSTNAME
     local IC_CRYPTO IC_STATE IC_REGISTRY_LOCAL_STORE
-    local NNS_URL NNS_PUBLIC_KEY NODE_OPERATOR_PRIVATE_KEY
+    local NNS_URL NNS_PUBLIC_KEY NODE_OPERATOR_PRIVATE_KEY GUESTOS_CONFIG
     local BACKUP_RETENTION_TIME_SECS BACKUP_PURGING_INTERVAL_SECS
     local ELASTICSEARCH_HOSTS ELASTICSEARCH_TAGS
     local ACCOUNTS_SSH_AUTHORIZED_KEYS
@@ -182,6 +186,9 @@ function build_ic_bootstrap_tar() {
             --node_operator_private_key)
                 NODE_OPERATOR_PRIVATE_KEY="$2"
                 ;;
+            --guestos_config)
+                GUESTOS_CONFIG="$2"
+                ;;
             --backup_retention_time)
                 BACKUP_RETENTION_TIME_SECS="$2"
                 ;;
@@ -274,6 +281,9 @@ EOF
     if [ "${NODE_OPERATOR_PRIVATE_KEY}" != "" ]; then
         cp "${NODE_OPERATOR_PRIVATE_KEY}" "${BOOTSTRAP_TMPDIR}/node_operator_private_key.pem"
     fi
+    if [ "${GUESTOS_CONFIG}" != "" ]; then
+        cp "${GUESTOS_CONFIG}" "${BOOTSTRAP_TMPDIR}/config.json"
+    fi
 
     tar cf "${OUT_FILE}" \
         --sort=name \
@@ -66,6 +66,7 @@ DEPLOYMENT="${DEPLOYMENT:=/boot/config/deployment.json}"
 INPUT="${INPUT:=/opt/ic/share/guestos.xml.template}"
 MEDIA="${MEDIA:=/run/ic-node/config.img}"
 OUTPUT="${OUTPUT:=/var/lib/libvirt/guestos.xml}"
+GUESTOS_CONFIG="/boot/config/config-guestos.json"
 
 function read_variables() {
     # Read limited set of keys. Be extra-careful quoting values as it could
@@ -98,6 +99,12 @@ function assemble_config_media() {
     if [ -f "/boot/config/node_operator_private_key.pem" ]; then
         cmd+=(--node_operator_private_key "/boot/config/node_operator_private_key.pem")
     fi
+    # GuestOS still reads the files above, so a missing GuestOS config object is not fatal.
+    if /opt/ic/bin/hostos_tool generate-guestos-config --output "${GUESTOS_CONFIG}"; then
+        cmd+=(--guestos_config "${GUESTOS_CONFIG}")
+    else
+        write_log "Unable to generate the GuestOS config object, continuing without it"
+    fi
 
     # Run the above command
     "${cmd[@]}"
@@ -53,9 +53,17 @@ function monitor_guestos() {
     fi
 }
 
+function monitor_guestos_health() {
+    # Query the replica status, heights and disk usage from the GuestOS over the vsock.
+    if ! /opt/ic/bin/vsock_host --query-guestos-health --metrics "${METRICS_DIR}/hostos_guestos_health.prom" >/dev/null; then
+        write_log "WARNING: Unable to query GuestOS health."
+    fi
+}
+
 function main() {
     # Establish run order
     monitor_guestos
+    monitor_guestos_health
 }
 
 main
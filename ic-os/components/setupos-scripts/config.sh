@@ -12,6 +12,7 @@ CONFIG_INI="${CONFIG_DIR}/config.ini"
 CONFIG_INI_CLONE="${CONFIG_TMP}/config.ini"
 SSH_AUTHORIZED_KEYS="${CONFIG_DIR}/ssh_authorized_keys"
 SSH_AUTHORIZED_KEYS_CLONE="${CONFIG_TMP}/ssh_authorized_keys"
+CONFIG_OBJECT="${CONFIG_TMP}/config.json"
 
 # Define empty variables so they are not unset
 ipv6_prefix=""
@@ -104,6 +105,11 @@ function verify_variables() {
     fi
 }
 
+function create_config_object() {
+    /opt/ic/bin/setupos_tool create-setupos-config --config "${CONFIG_INI_CLONE}" --output "${CONFIG_OBJECT}"
+    log_and_halt_installation_on_error "${?}" "Invalid configuration. Please check 'config.ini' and 'deployment.json'."
+}
+
 # Establish run order
 main() {
     source /opt/ic/bin/functions.sh
@@ -114,6 +120,7 @@ main() {
     normalize_config
     read_variables
     verify_variables
+    create_config_object
     log_end "$(basename $0)"
 }
 
@@ -49,6 +49,10 @@ function copy_config_files() {
     echo "* Copying NNS public key to hostOS config partition..."
     cp /data/nns_public_key.pem /media/
     log_and_halt_installation_on_error "${?}" "Unable to copy NNS public key to hostOS config partition."
+
+    echo "* Creating hostOS config object..."
+    /opt/ic/bin/setupos_tool create-hostos-config --output /media/config.json
+    log_and_halt_installation_on_error "${?}" "Unable to create hostOS config object on hostOS config partition."
 }
 
 function insert_hsm_if_necessary() {
@@ -139,6 +139,12 @@ Usage:
       has been confirmed previously already (safe to call as many times
       as you like, will not initiate I/O if nothing to be written).
 
+    rollback
+      Reboot into the previous system without confirming the current one.
+      This is only possible while the boot of a newly installed upgrade has
+      not been confirmed yet, in which case the bootloader falls back to the
+      previous installation.
+
     current
       Output currently booted system (A or B) on stdout and exit.
 
@@ -340,6 +346,26 @@ case "${ACTION}" in
                 "gauge"
         fi
         ;;
+    rollback)
+        if [ "${boot_cycle}" != "failsafe_check" ]; then
+            write_log "${SYSTEM_TYPE} attempted to roll back in state ${boot_cycle}"
+            echo "Cannot roll back a system that is not in its first boot after an upgrade." >&2
+            exit 1
+        fi
+
+        write_log "${SYSTEM_TYPE} rolling back from slot ${CURRENT_ALTERNATIVE} to slot ${NEXT_BOOT}"
+        write_metric_attr "${SYSTEM_TYPE}_boot_action" \
+            "{rollback=\"${CURRENT_BOOT}\",version=\"${VERSION}\"}" \
+            "1" \
+            "${SYSTEM_TYPE} boot action" \
+            "gauge"
+
+        sync
+        # Ignore termination signals from the following reboot, so that
+        # the script exits without error.
+        trap -- '' SIGTERM
+        reboot
+        ;;
     current)
         echo "${CURRENT_ALTERNATIVE}"
         ;;
@@ -1,4 +1,5 @@
 use crate::{
+    cold_storage::{download_dir, get_manifest, upload_dir, ColdStorage},
     notification_client::NotificationClient,
     util::{block_on, sleep_secs},
 };
@@ -45,6 +46,8 @@ pub(crate) struct BackupHelper {
     pub(crate) hot_disk_resource_threshold_percentage: u32,
     pub(crate) cold_disk_resource_threshold_percentage: u32,
     pub(crate) cold_storage_dir: PathBuf,
+    /// If set, used as the cold storage instead of `cold_storage_dir`.
+    pub(crate) object_storage: Option<Arc<dyn ColdStorage>>,
     pub(crate) versions_hot: usize,
     pub(crate) artifacts_guard: Mutex<bool>,
     pub(crate) daily_replays: usize,
@@ -663,18 +666,20 @@ impl BackupHelper {
 
     pub(crate) fn log_disk_stats(&self, notify_if_exceeds_threshold: bool) -> Result<(), String> {
         let mut stats = Vec::new();
-        for (dir, threshold, storage_type) in [
-            (
-                &self.root_dir,
-                self.hot_disk_resource_threshold_percentage,
-                "hot",
-            ),
-            (
+        let mut dirs = vec![(
+            &self.root_dir,
+            self.hot_disk_resource_threshold_percentage,
+            "hot",
+        )];
+        // The capacity of an object storage is not limited by a local disk.
+        if self.object_storage.is_none() {
+            dirs.push((
                 &self.cold_storage_dir,
                 self.cold_disk_resource_threshold_percentage,
                 "cold",
-            ),
-        ] {
+            ));
+        }
+        for (dir, threshold, storage_type) in dirs {
             let space = self.get_disk_stats(
                 dir,
                 threshold,
@@ -755,7 +760,13 @@ impl BackupHelper {
         // so don't need the mutex guard anymore
         drop(guard);
 
-        if self.do_cold_storage {
+        if let Some(storage) = self
+            .object_storage
+            .as_ref()
+            .filter(|_| self.do_cold_storage)
+        {
+            self.upload_artifacts(storage.as_ref(), &work_dir)?;
+        } else if self.do_cold_storage {
             // process moved artifact directories
             let cold_storage_artifacts_dir = self.cold_storage_artifacts_dir();
             let work_dir_str = work_dir
@@ -804,6 +815,31 @@ impl BackupHelper {
         Ok(max_height)
     }
 
+    /// Uploads the artifact directories moved to `work_dir` to the object storage.
+    fn upload_artifacts(&self, storage: &dyn ColdStorage, work_dir: &Path) -> Result<(), String> {
+        for pack_dir in collect_only_dirs(&work_dir.to_path_buf())? {
+            let replica_version = pack_dir
+                .file_name()
+                .into_string()
+                .expect("replica version entry in work directory is missing or invalid");
+            let timestamp = Utc::now().timestamp();
+            let (top_height, _) = fetch_top_height(&pack_dir);
+            let manifest_key =
+                artifacts_manifest_key(&self.subnet_id, timestamp, top_height, &replica_version);
+            info!(self.log, "Upload artifacts of {}", replica_version);
+            let stats = upload_dir(storage, &pack_dir.path(), &manifest_key, timestamp)?;
+            info!(
+                self.log,
+                "Uploaded {} of {} files ({} bytes) of {}",
+                stats.uploaded_files,
+                stats.files,
+                stats.uploaded_bytes,
+                replica_version
+            );
+        }
+        Ok(())
+    }
+
     /// Moves some of the states to the cold storage, such that the states from the last
     /// [DAYS_TO_KEEP_STATES_IN_HOT_STORAGE] days remain in the hot storage.
     fn maybe_cold_store_states(&self) -> Result<bool, String> {
@@ -858,7 +894,30 @@ impl BackupHelper {
         });
 
         for dir in old_state_dirs.values() {
-            if self.should_cold_store(dir) {
+            if !self.should_cold_store(dir) {
+                continue;
+            }
+            if let Some(storage) = &self.object_storage {
+                info!(self.log, "Will upload to cold storage: {:?}", dir);
+                let height = dir.file_name().and_then(OsStr::to_str).unwrap_or_default();
+                let timestamp = state_dir_timestamp(dir)
+                    .unwrap_or_else(|_| Utc::now())
+                    .timestamp();
+                let stats = upload_dir(
+                    storage.as_ref(),
+                    dir,
+                    &state_manifest_key(&self.subnet_id, height),
+                    timestamp,
+                )?;
+                info!(
+                    self.log,
+                    "Uploaded {} of {} files ({} bytes) of state {}",
+                    stats.uploaded_files,
+                    stats.files,
+                    stats.uploaded_bytes,
+                    height
+                );
+            } else {
                 info!(self.log, "Will copy to cold storage: {:?}", dir);
                 let mut cmd = Command::new("rsync");
                 cmd.arg("-a");
@@ -922,6 +981,17 @@ impl BackupHelper {
     }
 
     fn cold_storage_newest_state_timestamp(&self) -> anyhow::Result<DateTime<Utc>> {
+        if let Some(storage) = &self.object_storage {
+            let (_, manifest_key) = stored_state_heights(storage.as_ref(), &self.subnet_id)
+                .map_err(anyhow::Error::msg)?
+                .pop_last()
+                .context("No state in the cold storage")?;
+            let manifest =
+                get_manifest(storage.as_ref(), &manifest_key).map_err(anyhow::Error::msg)?;
+            return DateTime::from_timestamp(manifest.timestamp, 0)
+                .context("Invalid timestamp in the manifest");
+        }
+
         let last_height = last_dir_height(&self.cold_storage_states_dir(), 10);
 
         state_dir_timestamp(&self.cold_storage_states_dir().join(last_height.to_string()))
@@ -1055,6 +1125,97 @@ pub(crate) fn retrieve_replica_version_last_replayed(
     current_replica_version
 }
 
+fn artifacts_manifest_key(
+    subnet_id: &SubnetId,
+    timestamp: i64,
+    top_height: u64,
+    replica_version: &str,
+) -> String {
+    format!(
+        "{}/artifacts/{:010}_{:012}_{}.json",
+        subnet_id, timestamp, top_height, replica_version
+    )
+}
+
+fn state_manifest_key(subnet_id: &SubnetId, height: &str) -> String {
+    format!("{}/states/{}.json", subnet_id, height)
+}
+
+/// Returns the manifest keys of the states of the subnet in the object storage, by height.
+fn stored_state_heights(
+    storage: &dyn ColdStorage,
+    subnet_id: &SubnetId,
+) -> Result<BTreeMap<u64, String>, String> {
+    let prefix = format!("{}/states/", subnet_id);
+    Ok(storage
+        .list(&prefix)?
+        .into_iter()
+        .filter_map(|key| {
+            let height = key
+                .strip_prefix(&prefix)?
+                .strip_suffix(".json")?
+                .parse::<u64>()
+                .ok()?;
+            Some((height, key))
+        })
+        .collect())
+}
+
+/// Restores the state of the subnet with the given height, or the newest state if no height is
+/// given, from the object storage into `target_dir`. Returns the height of the restored state.
+pub(crate) fn restore_state_from_cold_storage(
+    log: &Logger,
+    storage: &dyn ColdStorage,
+    subnet_id: &SubnetId,
+    height: Option<u64>,
+    target_dir: &Path,
+) -> Result<u64, String> {
+    let mut states = stored_state_heights(storage, subnet_id)?;
+    let (height, manifest_key) = match height {
+        Some(height) => states
+            .remove_entry(&height)
+            .ok_or_else(|| format!("No state at height {} in the cold storage", height))?,
+        None => states
+            .pop_last()
+            .ok_or_else(|| "No state in the cold storage".to_string())?,
+    };
+    let state_dir = target_dir.join(height.to_string());
+    info!(log, "Restoring state {} to {:?}", height, state_dir);
+    let files = download_dir(storage, &manifest_key, &state_dir)?;
+    info!(log, "Restored {} files of state {}", files, height);
+    Ok(height)
+}
+
+/// Restores all artifacts of the subnet from the given replica version from the object storage
+/// into the spool layout under `target_dir`. Returns the number of restored files.
+pub(crate) fn restore_artifacts_from_cold_storage(
+    log: &Logger,
+    storage: &dyn ColdStorage,
+    subnet_id: &SubnetId,
+    replica_version: &ReplicaVersion,
+    target_dir: &Path,
+) -> Result<usize, String> {
+    let suffix = format!("_{}.json", replica_version);
+    let manifest_keys: Vec<_> = storage
+        .list(&format!("{}/artifacts/", subnet_id))?
+        .into_iter()
+        .filter(|key| key.ends_with(&suffix))
+        .collect();
+    if manifest_keys.is_empty() {
+        return Err(format!(
+            "No artifacts of replica version {} in the cold storage",
+            replica_version
+        ));
+    }
+    let version_dir = target_dir.join(replica_version.to_string());
+    let mut files = 0;
+    for manifest_key in manifest_keys {
+        info!(log, "Restoring {} to {:?}", manifest_key, version_dir);
+        files += download_dir(storage, &manifest_key, &version_dir)?;
+    }
+    Ok(files)
+}
+
 fn state_dir_timestamp(dir: &Path) -> anyhow::Result<DateTime<Utc>> {
     let path = dir.join(TIMESTAMP_FILE_NAME);
     let timestamp_str =
@@ -1079,6 +1240,7 @@ fn write_timestamp(dir: &Path, timestamp: DateTime<Utc>) -> anyhow::Result<()> {
 mod tests {
     use std::str::FromStr;
 
+    use crate::cold_storage::LocalColdStorage;
     use ic_registry_local_store::LocalStoreImpl;
     use ic_test_utilities_tmpdir::tmpdir;
     use ic_types::PrincipalId;
@@ -1301,6 +1463,131 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cold_store_to_object_storage_and_restore_test() {
+        let dir = tmpdir("test_dir");
+
+        let mut backup_helper = fake_backup_helper(
+            dir.as_ref(),
+            /*versions_hot=*/ 2,
+            /*daily_replays=*/ 2,
+        );
+        let object_storage = Arc::new(LocalColdStorage::new(dir.as_ref().join("object_storage")));
+        backup_helper.object_storage = Some(object_storage.clone());
+
+        let mut fake_timestamp = DateTime::UNIX_EPOCH;
+        for height in [0, 10, 20, 30] {
+            let dir = backup_helper.archive_dir().join(height.to_string());
+            create_dir_all(dir.join("ic_state")).unwrap();
+            std::fs::write(dir.join("ic_state/canister.pbuf"), "unchanged").unwrap();
+
+            fake_timestamp += std::time::Duration::from_secs(24 * 60 * 60);
+            write_timestamp(&dir, fake_timestamp).unwrap();
+        }
+
+        backup_helper
+            .cold_store_states(20)
+            .expect("should execute successfully");
+
+        let subnet_id = backup_helper.subnet_id;
+        assert_eq!(
+            stored_state_heights(object_storage.as_ref(), &subnet_id)
+                .unwrap()
+                .into_keys()
+                .collect::<Vec<_>>(),
+            vec![0, 10, 20]
+        );
+        // The unchanged file is stored once, next to the three timestamp files.
+        assert_eq!(object_storage.list("blobs/").unwrap().len(), 4);
+        assert_eq!(
+            backup_helper.cold_storage_newest_state_timestamp().unwrap(),
+            fake_timestamp - std::time::Duration::from_secs(24 * 60 * 60)
+        );
+
+        let restore_dir = dir.as_ref().join("restore");
+        let height = restore_state_from_cold_storage(
+            &backup_helper.log,
+            object_storage.as_ref(),
+            &subnet_id,
+            /*height=*/ None,
+            &restore_dir,
+        )
+        .expect("should restore the newest state");
+
+        assert_eq!(height, 20);
+        assert_eq!(
+            std::fs::read_to_string(restore_dir.join("20/ic_state/canister.pbuf")).unwrap(),
+            "unchanged"
+        );
+        assert!(restore_state_from_cold_storage(
+            &backup_helper.log,
+            object_storage.as_ref(),
+            &subnet_id,
+            Some(30),
+            &restore_dir,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn cold_store_artifacts_to_object_storage_and_restore_test() {
+        let dir = tmpdir("test_dir");
+
+        let mut backup_helper = fake_backup_helper(
+            dir.as_ref(),
+            /*versions_hot=*/ 1,
+            /*daily_replays=*/ 2,
+        );
+        let object_storage = Arc::new(LocalColdStorage::new(dir.as_ref().join("object_storage")));
+        backup_helper.object_storage = Some(object_storage.clone());
+
+        for (replica_version, heights) in [
+            ("replica_version_1", vec![0, 50]),
+            ("replica_version_2", vec![100]),
+        ] {
+            let replica_version_dir = backup_helper.spool_dir().join(replica_version);
+            create_artifacts_dir_with_heights(&replica_version_dir, heights.clone());
+            for height in heights {
+                let shard = 100 * (height / 100);
+                std::fs::write(
+                    replica_version_dir
+                        .join(shard.to_string())
+                        .join(height.to_string())
+                        .join("finalization.bin"),
+                    height.to_string(),
+                )
+                .unwrap();
+            }
+        }
+
+        let max_height = backup_helper
+            .cold_store_artifacts()
+            .expect("should execute successfully");
+
+        assert_eq!(max_height, 50);
+        assert_eq!(
+            collect_and_sort_dir_entries(&backup_helper.spool_dir()),
+            vec!["replica_version_2".to_string()]
+        );
+
+        let restore_dir = dir.as_ref().join("restore");
+        let files = restore_artifacts_from_cold_storage(
+            &backup_helper.log,
+            object_storage.as_ref(),
+            &backup_helper.subnet_id,
+            &ReplicaVersion::try_from("replica_version_1").unwrap(),
+            &restore_dir,
+        )
+        .expect("should restore the artifacts");
+
+        assert_eq!(files, 2);
+        assert_eq!(
+            std::fs::read_to_string(restore_dir.join("replica_version_1/0/50/finalization.bin"))
+                .unwrap(),
+            "50"
+        );
+    }
+
     // Utility functions below
 
     fn create_artifacts_dir_with_heights(replica_version_dir: &Path, heights: Vec<u64>) {
@@ -1352,6 +1639,7 @@ mod tests {
             hot_disk_resource_threshold_percentage: 75,
             cold_disk_resource_threshold_percentage: 95,
             cold_storage_dir: temp_dir.join("cold_storage"),
+            object_storage: None,
             versions_hot,
             artifacts_guard: Mutex::new(true),
             daily_replays,
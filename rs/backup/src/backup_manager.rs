@@ -21,8 +21,12 @@ use slog::{error, info, o, Logger};
 use tokio::runtime::Handle;
 
 use crate::{
-    backup_helper::{retrieve_replica_version_last_replayed, BackupHelper},
+    backup_helper::{
+        restore_artifacts_from_cold_storage, restore_state_from_cold_storage,
+        retrieve_replica_version_last_replayed, BackupHelper,
+    },
     cmd::BackupArgs,
+    cold_storage,
     config::{ColdStorage, Config, SubnetConfig},
     notification_client::NotificationClient,
     util::{block_on, sleep_secs},
@@ -61,10 +65,12 @@ impl BackupManager {
         let ColdStorage {
             cold_storage_dir,
             versions_hot,
+            object_storage,
         } = match config.cold_storage {
             Some(cs) => cs,
             None => panic!("Cold storage and cleanup are not configured"),
         };
+        let object_storage = object_storage.as_ref().map(cold_storage::object_storage);
         let ssh_credentials_file = match config.ssh_private_key.into_os_string().into_string() {
             Ok(f) => f,
             Err(e) => panic!("Bad file name for ssh credentials: {:?}", e),
@@ -140,6 +146,7 @@ impl BackupManager {
                 cold_disk_resource_threshold_percentage: config
                     .cold_disk_resource_threshold_percentage,
                 cold_storage_dir: cold_storage_dir.clone(),
+                object_storage: object_storage.clone(),
                 versions_hot,
                 artifacts_guard: Mutex::new(true),
                 daily_replays,
@@ -176,6 +183,57 @@ impl BackupManager {
         println!("{}", replica_version)
     }
 
+    /// Restores a state of the subnet from the object storage into `target_dir`.
+    pub fn restore_state(
+        log: Logger,
+        config_file: PathBuf,
+        subnet_id: SubnetId,
+        height: Option<u64>,
+        target_dir: PathBuf,
+    ) {
+        let storage = BackupManager::object_storage(config_file);
+        let height = restore_state_from_cold_storage(
+            &log,
+            storage.as_ref(),
+            &subnet_id,
+            height,
+            &target_dir,
+        )
+        .expect("Failed to restore the state");
+        println!("{}", target_dir.join(height.to_string()).display())
+    }
+
+    /// Restores the artifacts of the subnet from the given replica version from the object
+    /// storage into `target_dir`.
+    pub fn restore_artifacts(
+        log: Logger,
+        config_file: PathBuf,
+        subnet_id: SubnetId,
+        replica_version: String,
+        target_dir: PathBuf,
+    ) {
+        let replica_version =
+            ReplicaVersion::try_from(replica_version).expect("Invalid replica version");
+        let storage = BackupManager::object_storage(config_file);
+        let files = restore_artifacts_from_cold_storage(
+            &log,
+            storage.as_ref(),
+            &subnet_id,
+            &replica_version,
+            &target_dir,
+        )
+        .expect("Failed to restore the artifacts");
+        info!(log, "Restored {} files", files);
+    }
+
+    fn object_storage(config_file: PathBuf) -> Arc<dyn cold_storage::ColdStorage> {
+        let config = Config::load_config(config_file).expect("Config file can't be loaded");
+        match config.cold_storage.and_then(|cs| cs.object_storage) {
+            Some(object_storage) => cold_storage::object_storage(&object_storage),
+            None => panic!("Object storage is not configured"),
+        }
+    }
+
     pub fn upgrade(log: Logger, config_file: PathBuf) {
         let config = Config::load_config(config_file.clone()).expect("Config file can't be loaded");
         config
@@ -311,6 +369,7 @@ impl BackupManager {
         config.cold_storage = Some(ColdStorage {
             cold_storage_dir,
             versions_hot,
+            object_storage: None,
         });
 
         config
@@ -572,6 +631,7 @@ mod tests {
             cold_storage: Some(ColdStorage {
                 cold_storage_dir: fake_cold_storage_path,
                 versions_hot: FAKE_VERSIONS_HOT,
+                object_storage: None,
             }),
             slack_token: FAKE_SLACK_TOKEN.to_string(),
             ..serde_json::from_str(&fake_input_config).unwrap()
@@ -42,4 +42,26 @@ pub enum SubCommand {
         /// The ID of the target subnet
         subnet_id: ClapSubnetId,
     },
+    /// Restore a state of a subnet from the object storage
+    RestoreState {
+        /// The ID of the target subnet
+        subnet_id: ClapSubnetId,
+        /// The height of the state to restore, the newest state if omitted
+        #[clap(long)]
+        height: Option<u64>,
+        /// The directory to restore the state into
+        #[clap(long)]
+        target_dir: PathBuf,
+    },
+    /// Restore the artifacts of a replica version of a subnet from the object storage
+    RestoreArtifacts {
+        /// The ID of the target subnet
+        subnet_id: ClapSubnetId,
+        /// The replica version of the artifacts to restore
+        #[clap(long)]
+        replica_version: String,
+        /// The directory to restore the artifacts into
+        #[clap(long)]
+        target_dir: PathBuf,
+    },
 }
@@ -0,0 +1,414 @@
+//! Backends for the cold storage of artifacts and states.
+//!
+//! A [`ColdStorage`] is a flat key-value store of objects, such as a local
+//! directory or an S3-compatible bucket. Directories are stored in it
+//! content-addressed: each file is stored once under `blobs/<sha256>`, and a
+//! manifest lists the files of a directory together with their hashes. Files
+//! that don't change between replica versions or between states, like most of
+//! the files of a checkpoint, are therefore uploaded and stored only once.
+
+use crate::config::ObjectStorage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs::{create_dir_all, File},
+    io,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use walkdir::WalkDir;
+
+const BLOBS_PREFIX: &str = "blobs/";
+
+pub trait ColdStorage: Send + Sync {
+    /// Stores the content of the file `src` under `key`, replacing an existing object.
+    fn put_file(&self, key: &str, src: &Path) -> Result<(), String>;
+
+    /// Stores `bytes` under `key`, replacing an existing object.
+    fn put_bytes(&self, key: &str, bytes: Vec<u8>) -> Result<(), String>;
+
+    /// Writes the object stored under `key` to the file `dst`.
+    fn get_file(&self, key: &str, dst: &Path) -> Result<(), String>;
+
+    /// Returns the object stored under `key`.
+    fn get_bytes(&self, key: &str) -> Result<Vec<u8>, String>;
+
+    /// Returns whether an object is stored under `key`.
+    fn exists(&self, key: &str) -> Result<bool, String>;
+
+    /// Returns all keys starting with `prefix`, in lexicographic order.
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String>;
+}
+
+/// Creates the cold storage backend for the given object storage config.
+pub fn object_storage(config: &ObjectStorage) -> Arc<dyn ColdStorage> {
+    Arc::new(crate::s3::S3ColdStorage::new(config.clone()))
+}
+
+/// A cold storage that keeps each object as a file in a local directory, e.g.
+/// a mounted network file system.
+pub struct LocalColdStorage {
+    root: PathBuf,
+}
+
+impl LocalColdStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, String> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)
+                .map_err(|err| format!("Error creating directory {:?}: {}", parent, err))?;
+        }
+        Ok(path)
+    }
+}
+
+impl ColdStorage for LocalColdStorage {
+    fn put_file(&self, key: &str, src: &Path) -> Result<(), String> {
+        std::fs::copy(src, self.path(key)?)
+            .map(|_| ())
+            .map_err(|err| format!("Error storing {:?} as {}: {}", src, key, err))
+    }
+
+    fn put_bytes(&self, key: &str, bytes: Vec<u8>) -> Result<(), String> {
+        std::fs::write(self.path(key)?, bytes)
+            .map_err(|err| format!("Error storing {}: {}", key, err))
+    }
+
+    fn get_file(&self, key: &str, dst: &Path) -> Result<(), String> {
+        std::fs::copy(self.root.join(key), dst)
+            .map(|_| ())
+            .map_err(|err| format!("Error fetching {} to {:?}: {}", key, dst, err))
+    }
+
+    fn get_bytes(&self, key: &str) -> Result<Vec<u8>, String> {
+        std::fs::read(self.root.join(key)).map_err(|err| format!("Error fetching {}: {}", key, err))
+    }
+
+    fn exists(&self, key: &str) -> Result<bool, String> {
+        Ok(self.root.join(key).is_file())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+        let mut keys = Vec::new();
+        for entry in WalkDir::new(&self.root) {
+            let entry = entry.map_err(|err| format!("Error listing {:?}: {}", self.root, err))?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let key = relative_key(&self.root, entry.path())?;
+            if key.starts_with(prefix) {
+                keys.push(key);
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// The content of a directory stored in the cold storage.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    /// When the directory was stored, in seconds since the Unix epoch.
+    pub(crate) timestamp: i64,
+    /// The SHA-256 hash of each file of the directory, by its path relative
+    /// to the directory.
+    pub(crate) files: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct UploadStats {
+    pub(crate) files: usize,
+    /// The number of files whose content was not in the cold storage yet.
+    pub(crate) uploaded_files: usize,
+    pub(crate) uploaded_bytes: u64,
+}
+
+fn blob_key(hash: &str) -> String {
+    format!("{}{}", BLOBS_PREFIX, hash)
+}
+
+fn relative_key(root: &Path, path: &Path) -> Result<String, String> {
+    let relative = path
+        .strip_prefix(root)
+        .map_err(|err| format!("Error resolving {:?}: {}", path, err))?;
+    let components = relative
+        .iter()
+        .map(|c| {
+            c.to_str()
+                .ok_or_else(|| format!("Non UTF-8 file name: {:?}", path))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(components.join("/"))
+}
+
+/// Resolves a file path of a manifest against `dir`. Manifests are read from
+/// the cold storage, so only plain relative paths are accepted: a path with
+/// `..`, root or prefix components could otherwise point outside of `dir`.
+fn restore_path(dir: &Path, relative_path: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative_path);
+    let is_plain = relative.components().next().is_some()
+        && relative
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)));
+    if !is_plain {
+        return Err(format!("Invalid path in manifest: {:?}", relative_path));
+    }
+    Ok(dir.join(relative))
+}
+
+/// Checks that a hash of a manifest is a hex-encoded SHA-256 hash, so that the
+/// blob key derived from it stays under [`BLOBS_PREFIX`].
+fn check_hash(hash: &str) -> Result<(), String> {
+    if hash.len() != 64 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(format!("Invalid hash in manifest: {:?}", hash));
+    }
+    Ok(())
+}
+
+fn file_hash(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|err| format!("Error opening {:?}: {}", path, err))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher).map_err(|err| format!("Error reading {:?}: {}", path, err))?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Stores all files of `dir` and a manifest of them under `manifest_key`.
+/// Files whose content is already in the cold storage are not uploaded again.
+pub(crate) fn upload_dir(
+    storage: &dyn ColdStorage,
+    dir: &Path,
+    manifest_key: &str,
+    timestamp: i64,
+) -> Result<UploadStats, String> {
+    let mut manifest = Manifest {
+        timestamp,
+        files: BTreeMap::new(),
+    };
+    let mut stats = UploadStats::default();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(|err| format!("Error listing {:?}: {}", dir, err))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let hash = file_hash(entry.path())?;
+        let key = blob_key(&hash);
+        if !storage.exists(&key)? {
+            storage.put_file(&key, entry.path())?;
+            stats.uploaded_files += 1;
+            stats.uploaded_bytes += entry
+                .metadata()
+                .map_err(|err| format!("Error reading {:?}: {}", entry.path(), err))?
+                .len();
+        }
+        stats.files += 1;
+        manifest
+            .files
+            .insert(relative_key(dir, entry.path())?, hash);
+    }
+
+    let bytes = serde_json::to_vec_pretty(&manifest)
+        .map_err(|err| format!("Error serializing manifest: {:?}", err))?;
+    storage.put_bytes(manifest_key, bytes)?;
+    Ok(stats)
+}
+
+pub(crate) fn get_manifest(
+    storage: &dyn ColdStorage,
+    manifest_key: &str,
+) -> Result<Manifest, String> {
+    let bytes = storage.get_bytes(manifest_key)?;
+    serde_json::from_slice(&bytes)
+        .map_err(|err| format!("Error parsing manifest {}: {:?}", manifest_key, err))
+}
+
+/// Restores the directory stored under `manifest_key` to `dir`, verifying
+/// the hash of every file. Returns the number of restored files.
+pub(crate) fn download_dir(
+    storage: &dyn ColdStorage,
+    manifest_key: &str,
+    dir: &Path,
+) -> Result<usize, String> {
+    let manifest = get_manifest(storage, manifest_key)?;
+    for (relative_path, hash) in &manifest.files {
+        let path = restore_path(dir, relative_path)?;
+        check_hash(hash)?;
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent)
+                .map_err(|err| format!("Error creating directory {:?}: {}", parent, err))?;
+        }
+        storage.get_file(&blob_key(hash), &path)?;
+        let actual_hash = file_hash(&path)?;
+        if actual_hash != *hash {
+            return Err(format!(
+                "Hash mismatch of {:?}: expected {}, got {}",
+                path, hash, actual_hash
+            ));
+        }
+    }
+    Ok(manifest.files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_tmpdir::tmpdir;
+
+    fn write_file(path: &Path, content: &str) {
+        create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn upload_dir_deduplicates_files_test() {
+        let dir = tmpdir("test_dir");
+        let storage = LocalColdStorage::new(dir.as_ref().join("cold_storage"));
+
+        let first = dir.as_ref().join("first");
+        write_file(&first.join("checkpoints/64/canister.pbuf"), "canister");
+        write_file(
+            &first.join("checkpoints/64/system_metadata.pbuf"),
+            "metadata_1",
+        );
+
+        let second = dir.as_ref().join("second");
+        write_file(&second.join("checkpoints/c8/canister.pbuf"), "canister");
+        write_file(
+            &second.join("checkpoints/c8/system_metadata.pbuf"),
+            "metadata_2",
+        );
+
+        let stats = upload_dir(&storage, &first, "subnet/states/100.json", 1).unwrap();
+        assert_eq!(
+            stats,
+            UploadStats {
+                files: 2,
+                uploaded_files: 2,
+                uploaded_bytes: 18,
+            }
+        );
+
+        // Only the changed file is uploaded for the second state.
+        let stats = upload_dir(&storage, &second, "subnet/states/200.json", 2).unwrap();
+        assert_eq!(
+            stats,
+            UploadStats {
+                files: 2,
+                uploaded_files: 1,
+                uploaded_bytes: 10,
+            }
+        );
+
+        assert_eq!(storage.list(BLOBS_PREFIX).unwrap().len(), 3);
+        assert_eq!(
+            storage.list("subnet/states/").unwrap(),
+            vec![
+                "subnet/states/100.json".to_string(),
+                "subnet/states/200.json".to_string()
+            ]
+        );
+        assert_eq!(
+            get_manifest(&storage, "subnet/states/200.json")
+                .unwrap()
+                .files
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![
+                "checkpoints/c8/canister.pbuf".to_string(),
+                "checkpoints/c8/system_metadata.pbuf".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn download_dir_restores_uploaded_dir_test() {
+        let dir = tmpdir("test_dir");
+        let storage = LocalColdStorage::new(dir.as_ref().join("cold_storage"));
+
+        let original = dir.as_ref().join("original");
+        write_file(&original.join("a/b/file_1"), "content_1");
+        write_file(&original.join("file_2"), "content_2");
+        upload_dir(&storage, &original, "manifest.json", 0).unwrap();
+
+        let restored = dir.as_ref().join("restored");
+        assert_eq!(
+            download_dir(&storage, "manifest.json", &restored).unwrap(),
+            2
+        );
+        assert_eq!(
+            std::fs::read_to_string(restored.join("a/b/file_1")).unwrap(),
+            "content_1"
+        );
+        assert_eq!(
+            std::fs::read_to_string(restored.join("file_2")).unwrap(),
+            "content_2"
+        );
+    }
+
+    #[test]
+    fn download_dir_detects_corrupted_blobs_test() {
+        let dir = tmpdir("test_dir");
+        let storage = LocalColdStorage::new(dir.as_ref().join("cold_storage"));
+
+        let original = dir.as_ref().join("original");
+        write_file(&original.join("file"), "content");
+        upload_dir(&storage, &original, "manifest.json", 0).unwrap();
+
+        let blob = storage.list(BLOBS_PREFIX).unwrap().pop().unwrap();
+        storage.put_bytes(&blob, b"corrupted".to_vec()).unwrap();
+
+        let err =
+            download_dir(&storage, "manifest.json", &dir.as_ref().join("restored")).unwrap_err();
+        assert!(err.contains("Hash mismatch"));
+    }
+
+    #[test]
+    fn download_dir_rejects_paths_outside_of_the_target_test() {
+        let dir = tmpdir("test_dir");
+        let storage = LocalColdStorage::new(dir.as_ref().join("cold_storage"));
+        let restored = dir.as_ref().join("restored");
+        let hash = hex::encode(Sha256::digest(b"content"));
+        storage
+            .put_bytes(&blob_key(&hash), b"content".to_vec())
+            .unwrap();
+
+        for path in ["../escaped", "a/../../escaped", "/tmp/escaped", ""] {
+            let manifest = Manifest {
+                timestamp: 0,
+                files: BTreeMap::from([(path.to_string(), hash.clone())]),
+            };
+            storage
+                .put_bytes("manifest.json", serde_json::to_vec(&manifest).unwrap())
+                .unwrap();
+
+            let err = download_dir(&storage, "manifest.json", &restored).unwrap_err();
+            assert!(err.contains("Invalid path"), "{}: {}", path, err);
+        }
+        assert!(!dir.as_ref().join("escaped").exists());
+    }
+
+    #[test]
+    fn download_dir_rejects_invalid_hashes_test() {
+        let dir = tmpdir("test_dir");
+        let storage = LocalColdStorage::new(dir.as_ref().join("cold_storage"));
+        let manifest = Manifest {
+            timestamp: 0,
+            files: BTreeMap::from([("file".to_string(), "../../manifest.json".to_string())]),
+        };
+        storage
+            .put_bytes("manifest.json", serde_json::to_vec(&manifest).unwrap())
+            .unwrap();
+
+        let err =
+            download_dir(&storage, "manifest.json", &dir.as_ref().join("restored")).unwrap_err();
+        assert!(err.contains("Invalid hash"));
+    }
+}
@@ -23,6 +23,20 @@ pub struct SubnetConfig {
 pub struct ColdStorage {
     pub cold_storage_dir: PathBuf,
     pub versions_hot: usize,
+    /// If set, artifacts and states are moved to this object storage instead
+    /// of `cold_storage_dir`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_storage: Option<ObjectStorage>,
+}
+
+/// An S3-compatible object storage, e.g. AWS S3 or MinIO.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ObjectStorage {
+    pub endpoint: Url,
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
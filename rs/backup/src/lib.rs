@@ -1,6 +1,8 @@
 pub mod backup_helper;
 pub mod backup_manager;
 pub mod cmd;
+pub mod cold_storage;
 pub mod config;
 mod notification_client;
+mod s3;
 mod util;
@@ -29,7 +29,14 @@ use tokio::{runtime::Handle, task::spawn_blocking};
 //     "slack_token": "ABCD1234",
 //     "cold_storage": {
 //         "cold_storage_dir": "/var/cold_storage",
-//         "versions_hot": 2
+//         "versions_hot": 2,
+//         "object_storage": {
+//             "endpoint": "https://s3.eu-central-1.amazonaws.com",
+//             "bucket": "ic-backup",
+//             "region": "eu-central-1",
+//             "access_key_id": "AKIA1234",
+//             "secret_access_key": "ABCD1234"
+//         }
 //     },
 //     "subnets": [
 //       {
@@ -80,6 +87,24 @@ async fn main() {
             Some(SubCommand::GetReplicaVersion { subnet_id }) => {
                 BackupManager::get_version(log, args.config_file, subnet_id.0)
             }
+            Some(SubCommand::RestoreState {
+                subnet_id,
+                height,
+                target_dir,
+            }) => {
+                BackupManager::restore_state(log, args.config_file, subnet_id.0, height, target_dir)
+            }
+            Some(SubCommand::RestoreArtifacts {
+                subnet_id,
+                replica_version,
+                target_dir,
+            }) => BackupManager::restore_artifacts(
+                log,
+                args.config_file,
+                subnet_id.0,
+                replica_version,
+                target_dir,
+            ),
             _ => {
                 let bm = BackupManager::new(log, args, &rt);
                 Arc::new(bm).do_backups();
@@ -79,7 +79,7 @@ fn prepare(
 
 fn e2e(criterion: &mut Criterion) {
     let mut config = Config {
-        network: Network::Regtest,
+        network: Network::Regtest.into(),
         ..Default::default()
     };
 
@@ -138,7 +138,7 @@ fn e2e(criterion: &mut Criterion) {
             .iter()
             .map(|h| h[..].to_vec())
             .collect::<Vec<Vec<u8>>>(),
-        network: ic_btc_interface::Network::Regtest,
+        network: ic_btc_replica_types::Network::Regtest,
     };
 
     let wrapped = BitcoinAdapterRequestWrapper::GetSuccessorsRequest(get_successors_request);
@@ -1,8 +1,13 @@
 //! The module is responsible for keeping track of the blockchain state.
 //!
-use crate::{common::BlockHeight, config::Config, metrics::BlockchainStateMetrics};
-use bitcoin::{blockdata::constants::genesis_block, Block, BlockHash, BlockHeader, Network};
-use ic_btc_validation::{validate_header, HeaderStore, ValidateHeaderError};
+use crate::{
+    common::BlockHeight,
+    config::Config,
+    metrics::BlockchainStateMetrics,
+    network::{AdapterNetwork, ValidateHeaderError},
+};
+use bitcoin::{Block, BlockHash, BlockHeader};
+use ic_btc_validation::HeaderStore;
 use ic_metrics::MetricsRegistry;
 use std::collections::HashMap;
 use thiserror::Error;
@@ -98,7 +103,7 @@ pub struct BlockchainState {
     tips: Vec<Tip>,
 
     /// Used to determine how validation should be handled with `validate_header`.
-    network: Network,
+    network: AdapterNetwork,
     metrics: BlockchainStateMetrics,
 }
 
@@ -106,7 +111,7 @@ impl BlockchainState {
     /// This function is used to create a new BlockChainState object.  
     pub fn new(config: &Config, metrics_registry: &MetricsRegistry) -> Self {
         // Create a header cache and inserting dummy header corresponding the `adapter_genesis_hash`.
-        let genesis_block_header = genesis_block(config.network).header;
+        let genesis_block_header = config.network.genesis_block_header();
         let header_cache = init_cache_with_genesis(genesis_block_header);
         let block_cache = HashMap::new();
         let tips = vec![Tip {
@@ -179,7 +184,7 @@ impl BlockchainState {
             return Ok(AddHeaderResult::HeaderAlreadyExists);
         }
 
-        if let Err(err) = validate_header(&self.network, self, &header) {
+        if let Err(err) = self.network.validate_header(self, &header) {
             return Err(AddHeaderError::InvalidHeader(block_hash, err));
         }
 
@@ -349,7 +354,7 @@ impl HeaderStore for BlockchainState {
 
 #[cfg(test)]
 mod test {
-    use bitcoin::TxMerkleNode;
+    use bitcoin::{Network, TxMerkleNode};
     use ic_metrics::MetricsRegistry;
 
     use super::*;
@@ -547,7 +552,7 @@ mod test {
 
         assert_eq!(added_headers.len(), 10);
         assert!(
-            matches!(maybe_err, Some(AddHeaderError::InvalidHeader(block_hash, err)) if block_hash == last_hash && matches!(err, ValidateHeaderError::PrevHeaderNotFound))
+            matches!(maybe_err, Some(AddHeaderError::InvalidHeader(block_hash, err)) if block_hash == last_hash && matches!(err, ValidateHeaderError::Bitcoin(ic_btc_validation::ValidateHeaderError::PrevHeaderNotFound)))
         );
 
         let tip = state.get_active_chain_tip();
@@ -568,7 +573,7 @@ mod test {
         let block_2_hash = block_2.header.block_hash();
         let result = state.add_block(block_2.clone());
         assert!(
-            matches!(result, Err(AddBlockError::Header(AddHeaderError::InvalidHeader(stop_hash, err))) if stop_hash == block_2_hash && matches!(err, ValidateHeaderError::PrevHeaderNotFound)),
+            matches!(result, Err(AddBlockError::Header(AddHeaderError::InvalidHeader(stop_hash, err))) if stop_hash == block_2_hash && matches!(err, ValidateHeaderError::Bitcoin(ic_btc_validation::ValidateHeaderError::PrevHeaderNotFound))),
         );
 
         let result = state.add_block(block_1);
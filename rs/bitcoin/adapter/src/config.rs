@@ -1,4 +1,4 @@
-use bitcoin::Network;
+use crate::network::AdapterNetwork;
 use ic_config::logger::Config as LoggerConfig;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
@@ -18,9 +18,9 @@ pub enum IncomingSource {
 /// This struct contains configuration options for the BTC Adapter.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Config {
-    /// The type of Bitcoin network we plan to communicate to (e.g. Mainnet, Testnet, etc.).
-    pub network: Network,
-    /// A list of DNS seeds for address discovery.
+    /// The type of network we plan to communicate to (e.g. Bitcoin Mainnet, Testnet, Dogecoin, etc.).
+    pub network: AdapterNetwork,
+    /// A list of DNS seeds for address discovery. If empty, the default seeds of the network are used.
     #[serde(default)]
     pub dns_seeds: Vec<String>,
     /// Addresses of nodes to connect to (in case discovery from seeds is not possible/sufficient)
@@ -57,24 +57,15 @@ fn default_idle_seconds() -> u64 {
 }
 
 /// This function is used to get the address limits for the `AddressBook`
-/// based on the provided `Network`.
-pub(crate) fn address_limits(network: Network) -> (usize, usize) {
-    match network {
-        Network::Bitcoin => (500, 2000),
-        Network::Testnet => (100, 1000),
-        Network::Signet => (1, 1),
-        Network::Regtest => (1, 1),
-    }
+/// based on the provided `AdapterNetwork`.
+pub(crate) fn address_limits(network: AdapterNetwork) -> (usize, usize) {
+    network.address_limits()
 }
 
 impl Config {
-    /// This function returns the port to use based on the Bitcoin network provided.
+    /// This function returns the port to use based on the network provided.
     pub fn network_port(&self) -> u16 {
-        match self.network {
-            Network::Bitcoin => 8333,
-            Network::Testnet => 18333,
-            _ => 8333,
-        }
+        self.network.default_port()
     }
 }
 
@@ -82,14 +73,14 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             dns_seeds: Default::default(),
-            network: Network::Bitcoin,
+            network: AdapterNetwork::Bitcoin,
             socks_proxy: Default::default(),
             nodes: vec![],
             idle_seconds: default_idle_seconds(),
             ipv6_only: false,
             logger: LoggerConfig::default(),
             incoming_source: Default::default(),
-            address_limits: address_limits(Network::Bitcoin), // Address limits used for Bitcoin mainnet
+            address_limits: address_limits(AdapterNetwork::Bitcoin), // Address limits used for Bitcoin mainnet
         }
     }
 }
@@ -121,7 +112,8 @@ pub mod test {
             self
         }
 
-        pub fn with_network(mut self, network: Network) -> Self {
+        pub fn with_network(mut self, network: impl Into<AdapterNetwork>) -> Self {
+            let network = network.into();
             self.config.network = network;
             self.config.address_limits = address_limits(network);
             self
@@ -82,6 +82,8 @@ pub struct ConnectionManager {
     /// This field is used to provide the magic value to the raw network message.
     /// The magic number is used to identity the type of Bitcoin network being accessed.
     magic: u32,
+    /// This field is set when headers received from the network may carry an AuxPoW proof.
+    auxpow: bool,
     /// This field contains the number of connections the connection manager can manage at one time.
     max_connections: usize,
     /// This field contains the number of connections the connection manager must have in order to send messages.
@@ -123,6 +125,7 @@ impl ConnectionManager {
             address_book,
             logger,
             magic: config.network.magic(),
+            auxpow: config.network.supports_auxpow(),
             max_connections,
             min_connections,
             current_height: 0,
@@ -309,6 +312,7 @@ impl ConnectionManager {
             address,
             logger: self.logger.clone(),
             magic: self.magic,
+            auxpow: self.auxpow,
             network_message_receiver,
             socks_proxy: self.socks_proxy.clone(),
             stream_event_sender,
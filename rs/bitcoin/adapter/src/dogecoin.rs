@@ -0,0 +1,838 @@
+//! The module contains the Dogecoin-specific consensus rules: scrypt proof-of-work,
+//! the Dogecoin difficulty adjustment (including DigiShield) and merge-mined (AuxPoW) headers.
+use crate::{common::BlockHeight, network::AdapterNetwork};
+use bitcoin::{
+    consensus::{encode, Decodable},
+    hashes::{sha256d, Hash},
+    network::message::{NetworkMessage, RawNetworkMessage},
+    util::uint::Uint256,
+    Block, BlockHash, BlockHeader, Transaction, VarInt,
+};
+use ic_btc_validation::HeaderStore;
+use std::io::{self, Cursor};
+use thiserror::Error;
+
+/// The flag in the block version signalling that the header carries an AuxPoW proof.
+const VERSION_AUXPOW: i32 = 1 << 8;
+
+/// The chain ID Dogecoin uses for merged mining.
+const DOGECOIN_CHAIN_ID: i32 = 0x0062;
+
+/// The merged mining header that may precede the chain merkle root in the coinbase script.
+const MERGED_MINING_HEADER: [u8; 4] = [0xfa, 0xbe, b'm', b'm'];
+
+/// The maximum depth of the chain merkle branch of an AuxPoW proof.
+const MAX_CHAIN_MERKLE_BRANCH_LENGTH: usize = 30;
+
+/// The maximum number of hashes accepted in a decoded merkle branch.
+const MAX_DECODED_MERKLE_BRANCH_LENGTH: usize = 64;
+
+/// The number of blocks used to compute the median time past.
+const MEDIAN_TIME_SPAN: usize = 11;
+
+/// The expected time between two blocks in seconds.
+const TARGET_SPACING: i64 = 60;
+
+/// The retarget timespan before DigiShield was activated (4 hours).
+const PRE_DIGISHIELD_TARGET_TIMESPAN: i64 = 4 * 60 * 60;
+
+/// The number of blocks between two difficulty adjustments before DigiShield was activated.
+const PRE_DIGISHIELD_ADJUSTMENT_INTERVAL: BlockHeight =
+    (PRE_DIGISHIELD_TARGET_TIMESPAN / TARGET_SPACING) as BlockHeight;
+
+/// The height from which the difficulty is adjusted on every block with DigiShield.
+const DIGISHIELD_HEIGHT: BlockHeight = 145_000;
+
+/// Size of the header of a raw network message (magic, command, length and checksum).
+const MESSAGE_HEADER_SIZE: usize = 24;
+
+/// Messages with a larger payload are rejected (same limit as `bitcoin::consensus`).
+const MAX_MESSAGE_PAYLOAD_SIZE: usize = 32 * 1024 * 1024;
+
+/// The consensus parameters that differ between the Dogecoin networks.
+struct ConsensusParams {
+    /// The easiest target allowed, in compact form.
+    pow_limit_bits: u32,
+    /// The first height at which merge-mined blocks are accepted.
+    auxpow_start_height: BlockHeight,
+    /// The first height at which a minimum difficulty block may follow a slow block.
+    min_difficulty_height: Option<BlockHeight>,
+    /// Set when the network does not adjust its difficulty at all.
+    no_retargeting: bool,
+}
+
+impl ConsensusParams {
+    fn for_network(network: &AdapterNetwork) -> Self {
+        match network {
+            AdapterNetwork::DogecoinTestnet => Self {
+                pow_limit_bits: 0x1e0fffff,
+                auxpow_start_height: 158_100,
+                min_difficulty_height: Some(157_500),
+                no_retargeting: false,
+            },
+            AdapterNetwork::DogecoinRegtest => Self {
+                pow_limit_bits: 0x207fffff,
+                auxpow_start_height: 20,
+                min_difficulty_height: None,
+                no_retargeting: true,
+            },
+            _ => Self {
+                pow_limit_bits: 0x1e0fffff,
+                auxpow_start_height: 371_337,
+                min_difficulty_height: None,
+                no_retargeting: false,
+            },
+        }
+    }
+
+    /// Returns true if a block at the given height may use the minimum difficulty when
+    /// it was found more than twice the target spacing after its parent.
+    fn allows_min_difficulty_at(&self, height: BlockHeight) -> bool {
+        self.min_difficulty_height
+            .map_or(false, |min_height| height >= min_height)
+    }
+}
+
+/// The error returned when a header violates the Dogecoin consensus rules.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ValidateDogecoinHeaderError {
+    /// The parent of the header is not known.
+    #[error("The previous header was not found")]
+    PrevHeaderNotFound,
+    /// The timestamp is not greater than the median of the past 11 blocks.
+    #[error("The header timestamp {0} is not greater than the median time past")]
+    TimestampTooOld(u32),
+    /// The header is not tagged with the Dogecoin chain ID.
+    #[error("The header has chain ID {0} instead of the Dogecoin chain ID")]
+    WrongChainId(i32),
+    /// The header claims to be merge-mined below the AuxPoW activation height.
+    #[error("AuxPoW headers are not allowed at height {0}")]
+    AuxPowNotAllowed(BlockHeight),
+    /// The scrypt hash of the header does not meet its target.
+    #[error("The header does not satisfy its proof-of-work target")]
+    InvalidProofOfWork,
+    /// The target of the header is easier than the network allows.
+    #[error("The header target is above the proof-of-work limit")]
+    TargetAbovePowLimit,
+    /// The target of the header does not match the difficulty adjustment rules.
+    #[error("The header has bits {actual:#x} while {expected:#x} was expected")]
+    UnexpectedTarget { expected: u32, actual: u32 },
+}
+
+/// The error returned when the AuxPoW proof of a merge-mined header is invalid.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuxPowError {
+    #[error("The header is not flagged as merge-mined")]
+    NotAuxPowHeader,
+    #[error("The AuxPoW coinbase transaction is not at index 0")]
+    CoinbaseNotFirst,
+    #[error("The AuxPoW parent block has the Dogecoin chain ID")]
+    ParentHasOwnChainId,
+    #[error("The AuxPoW chain merkle branch is too long")]
+    ChainMerkleBranchTooLong,
+    #[error("The AuxPoW coinbase transaction is not committed to by the parent block")]
+    CoinbaseNotInParentBlock,
+    #[error("The AuxPoW coinbase transaction has no inputs")]
+    MissingCoinbaseInput,
+    #[error("The chain merkle root is missing from the coinbase script")]
+    MissingChainMerkleRoot,
+    #[error("The merged mining header is not unique or misplaced in the coinbase script")]
+    InvalidMergedMiningHeader,
+    #[error("The chain merkle root starts too late in the coinbase script")]
+    ChainMerkleRootTooLate,
+    #[error("The coinbase script is missing the merkle tree size and nonce")]
+    MissingSizeAndNonce,
+    #[error("The merkle tree size in the coinbase script does not match the chain merkle branch")]
+    InvalidMerkleTreeSize,
+    #[error("The chain index does not match the expected slot")]
+    WrongChainIndex,
+    #[error("The AuxPoW parent block does not satisfy the proof-of-work target")]
+    InvalidProofOfWork,
+}
+
+/// A proof that a header was merge-mined as part of a parent block on another chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuxPow {
+    /// The coinbase transaction of the parent block committing to the Dogecoin header.
+    pub coinbase_tx: Transaction,
+    /// The hash of the parent block (unused, kept for serialization).
+    pub parent_hash: BlockHash,
+    /// The merkle branch linking the coinbase transaction to the parent merkle root.
+    pub coinbase_branch: Vec<sha256d::Hash>,
+    /// The index of the coinbase transaction in the parent block.
+    pub coinbase_index: i32,
+    /// The merkle branch linking the Dogecoin header to the chain merkle root.
+    pub chain_branch: Vec<sha256d::Hash>,
+    /// The index of the Dogecoin header in the chain merkle tree.
+    pub chain_index: i32,
+    /// The header of the parent block.
+    pub parent_header: BlockHeader,
+}
+
+impl Decodable for AuxPow {
+    fn consensus_decode<D: io::Read>(mut d: D) -> Result<Self, encode::Error> {
+        Ok(Self {
+            coinbase_tx: Transaction::consensus_decode(&mut d)?,
+            parent_hash: BlockHash::consensus_decode(&mut d)?,
+            coinbase_branch: decode_hashes(&mut d)?,
+            coinbase_index: i32::consensus_decode(&mut d)?,
+            chain_branch: decode_hashes(&mut d)?,
+            chain_index: i32::consensus_decode(&mut d)?,
+            parent_header: BlockHeader::consensus_decode(&mut d)?,
+        })
+    }
+}
+
+impl AuxPow {
+    /// Checks that the proof commits to the given header and that the parent block
+    /// satisfies the target of the header.
+    pub fn check(&self, header: &BlockHeader) -> Result<(), AuxPowError> {
+        if !is_auxpow(header.version) {
+            return Err(AuxPowError::NotAuxPowHeader);
+        }
+        if self.coinbase_index != 0 {
+            return Err(AuxPowError::CoinbaseNotFirst);
+        }
+        if chain_id(self.parent_header.version) == DOGECOIN_CHAIN_ID {
+            return Err(AuxPowError::ParentHasOwnChainId);
+        }
+        if self.chain_branch.len() > MAX_CHAIN_MERKLE_BRANCH_LENGTH {
+            return Err(AuxPowError::ChainMerkleBranchTooLong);
+        }
+
+        let chain_root = compute_merkle_root(
+            header.block_hash().as_hash(),
+            &self.chain_branch,
+            self.chain_index,
+        );
+        // The root is committed to in the script in reversed byte order.
+        let mut chain_root_bytes = chain_root.into_inner();
+        chain_root_bytes.reverse();
+
+        let coinbase_root = compute_merkle_root(
+            self.coinbase_tx.txid().as_hash(),
+            &self.coinbase_branch,
+            self.coinbase_index,
+        );
+        if coinbase_root != self.parent_header.merkle_root.as_hash() {
+            return Err(AuxPowError::CoinbaseNotInParentBlock);
+        }
+
+        let script = self
+            .coinbase_tx
+            .input
+            .first()
+            .ok_or(AuxPowError::MissingCoinbaseInput)?
+            .script_sig
+            .as_bytes();
+        let root_position =
+            find(script, &chain_root_bytes).ok_or(AuxPowError::MissingChainMerkleRoot)?;
+        match find(script, &MERGED_MINING_HEADER) {
+            Some(header_position) => {
+                let after_header = header_position + MERGED_MINING_HEADER.len();
+                if find(&script[after_header..], &MERGED_MINING_HEADER).is_some()
+                    || after_header != root_position
+                {
+                    return Err(AuxPowError::InvalidMergedMiningHeader);
+                }
+            }
+            // Without the merged mining header the root has to be at the start of the script.
+            None if root_position > 20 => return Err(AuxPowError::ChainMerkleRootTooLate),
+            None => {}
+        }
+
+        let size_and_nonce = script
+            .get(root_position + chain_root_bytes.len()..)
+            .and_then(|rest| rest.get(..8))
+            .ok_or(AuxPowError::MissingSizeAndNonce)?;
+        let size = u32::from_le_bytes(size_and_nonce[..4].try_into().unwrap());
+        let nonce = u32::from_le_bytes(size_and_nonce[4..].try_into().unwrap());
+        if size != 1 << self.chain_branch.len() {
+            return Err(AuxPowError::InvalidMerkleTreeSize);
+        }
+        if self.chain_index as u32 != expected_chain_index(nonce, self.chain_branch.len()) {
+            return Err(AuxPowError::WrongChainIndex);
+        }
+
+        if !satisfies_target(&scrypt_hash(&self.parent_header), header.bits) {
+            return Err(AuxPowError::InvalidProofOfWork);
+        }
+        Ok(())
+    }
+}
+
+/// Validates a Dogecoin header against the header store.
+///
+/// The proof-of-work of merge-mined headers is checked when their AuxPoW proof is decoded
+/// (see [deserialize_partial_raw_message]), as the proof is not kept alongside the header.
+pub fn validate_header(
+    network: &AdapterNetwork,
+    store: &impl HeaderStore,
+    header: &BlockHeader,
+) -> Result<(), ValidateDogecoinHeaderError> {
+    let params = ConsensusParams::for_network(network);
+    let (prev_header, prev_height) = store
+        .get_header(&header.prev_blockhash)
+        .ok_or(ValidateDogecoinHeaderError::PrevHeaderNotFound)?;
+    let height = prev_height + 1;
+
+    if header.time <= median_time_past(store, &prev_header) {
+        return Err(ValidateDogecoinHeaderError::TimestampTooOld(header.time));
+    }
+
+    if !is_legacy(header.version) && chain_id(header.version) != DOGECOIN_CHAIN_ID {
+        return Err(ValidateDogecoinHeaderError::WrongChainId(chain_id(
+            header.version,
+        )));
+    }
+
+    let target = BlockHeader::u256_from_compact_target(header.bits);
+    if target > BlockHeader::u256_from_compact_target(params.pow_limit_bits) {
+        return Err(ValidateDogecoinHeaderError::TargetAbovePowLimit);
+    }
+
+    if is_auxpow(header.version) {
+        if height < params.auxpow_start_height {
+            return Err(ValidateDogecoinHeaderError::AuxPowNotAllowed(height));
+        }
+    } else if !satisfies_target(&scrypt_hash(header), header.bits) {
+        return Err(ValidateDogecoinHeaderError::InvalidProofOfWork);
+    }
+
+    let expected = next_work_required(&params, store, &prev_header, prev_height, header);
+    if header.bits != expected {
+        return Err(ValidateDogecoinHeaderError::UnexpectedTarget {
+            expected,
+            actual: header.bits,
+        });
+    }
+
+    Ok(())
+}
+
+/// Computes the compact target the header following `prev_header` has to use.
+fn next_work_required(
+    params: &ConsensusParams,
+    store: &impl HeaderStore,
+    prev_header: &BlockHeader,
+    prev_height: BlockHeight,
+    header: &BlockHeader,
+) -> u32 {
+    let height = prev_height + 1;
+    if params.no_retargeting {
+        return prev_header.bits;
+    }
+
+    if params.allows_min_difficulty_at(height)
+        && header.time as i64 > prev_header.time as i64 + 2 * TARGET_SPACING
+    {
+        return params.pow_limit_bits;
+    }
+
+    let adjustment_interval = if prev_height >= DIGISHIELD_HEIGHT {
+        1
+    } else {
+        PRE_DIGISHIELD_ADJUSTMENT_INTERVAL
+    };
+    if height % adjustment_interval != 0 {
+        return prev_header.bits;
+    }
+
+    // Go back the full period unless it's the first retarget after genesis.
+    let blocks_to_go_back = if height == adjustment_interval {
+        adjustment_interval - 1
+    } else {
+        adjustment_interval
+    };
+    let mut first_header = *prev_header;
+    for _ in 0..blocks_to_go_back {
+        match store.get_header(&first_header.prev_blockhash) {
+            Some((header, _)) => first_header = header,
+            None => break,
+        }
+    }
+
+    calculate_next_work_required(params, prev_header, height, first_header.time as i64)
+}
+
+/// Retargets the difficulty based on the time it took to mine the last adjustment interval.
+fn calculate_next_work_required(
+    params: &ConsensusParams,
+    prev_header: &BlockHeader,
+    height: BlockHeight,
+    first_block_time: i64,
+) -> u32 {
+    let actual_timespan = prev_header.time as i64 - first_block_time;
+    let (target_timespan, modulated_timespan, min_timespan, max_timespan) =
+        if height >= DIGISHIELD_HEIGHT {
+            // DigiShield: dampen the adjustment and retarget against a single block.
+            let target_timespan = TARGET_SPACING;
+            (
+                target_timespan,
+                target_timespan + (actual_timespan - target_timespan) / 8,
+                target_timespan - target_timespan / 4,
+                target_timespan + target_timespan / 2,
+            )
+        } else {
+            let target_timespan = PRE_DIGISHIELD_TARGET_TIMESPAN;
+            let min_timespan = if height > 10_000 {
+                target_timespan / 4
+            } else if height > 5_000 {
+                target_timespan / 8
+            } else {
+                target_timespan / 16
+            };
+            (
+                target_timespan,
+                actual_timespan,
+                min_timespan,
+                target_timespan * 4,
+            )
+        };
+    let modulated_timespan = modulated_timespan.clamp(min_timespan, max_timespan);
+
+    let pow_limit = BlockHeader::u256_from_compact_target(params.pow_limit_bits);
+    let target = BlockHeader::u256_from_compact_target(prev_header.bits)
+        .mul_u32(modulated_timespan as u32)
+        / Uint256::from_u64(target_timespan as u64).expect("the timespan fits into 256 bits");
+    BlockHeader::compact_target_from_u256(&std::cmp::min(target, pow_limit))
+}
+
+/// Returns the median timestamp of the last blocks up to and including `prev_header`.
+fn median_time_past(store: &impl HeaderStore, prev_header: &BlockHeader) -> u32 {
+    let mut times = vec![prev_header.time];
+    let mut current = *prev_header;
+    while times.len() < MEDIAN_TIME_SPAN {
+        match store.get_header(&current.prev_blockhash) {
+            Some((header, _)) => {
+                times.push(header.time);
+                current = header;
+            }
+            None => break,
+        }
+    }
+    times.sort_unstable();
+    times[times.len() / 2]
+}
+
+/// Decodes a raw network message from the front of `data`, accepting `headers` and `block`
+/// messages whose headers carry an AuxPoW proof. The proofs are verified and stripped so that
+/// the rest of the adapter only deals with plain 80-byte headers.
+///
+/// Like `encode::deserialize_partial`, an `UnexpectedEof` I/O error is returned if `data` does
+/// not yet contain a complete message.
+pub fn deserialize_partial_raw_message(
+    data: &[u8],
+) -> Result<(RawNetworkMessage, usize), encode::Error> {
+    let message_header = match data.get(..MESSAGE_HEADER_SIZE) {
+        Some(message_header) => message_header,
+        None => return Err(unexpected_eof()),
+    };
+    let command = &message_header[4..16];
+    let is_headers = command == b"headers\0\0\0\0\0";
+    let is_block = command == b"block\0\0\0\0\0\0\0";
+    if !is_headers && !is_block {
+        return encode::deserialize_partial::<RawNetworkMessage>(data);
+    }
+
+    let magic = u32::from_le_bytes(message_header[..4].try_into().unwrap());
+    let length = u32::from_le_bytes(message_header[16..20].try_into().unwrap()) as usize;
+    if length > MAX_MESSAGE_PAYLOAD_SIZE {
+        return Err(encode::Error::OversizedVectorAllocation {
+            requested: length,
+            max: MAX_MESSAGE_PAYLOAD_SIZE,
+        });
+    }
+    let payload = data
+        .get(MESSAGE_HEADER_SIZE..MESSAGE_HEADER_SIZE + length)
+        .ok_or_else(unexpected_eof)?;
+
+    let expected: [u8; 4] = message_header[20..24].try_into().unwrap();
+    let actual: [u8; 4] = sha256d::Hash::hash(payload)[..4].try_into().unwrap();
+    if expected != actual {
+        return Err(encode::Error::InvalidChecksum { expected, actual });
+    }
+
+    let mut cursor = Cursor::new(payload);
+    let message = if is_headers {
+        let count = VarInt::consensus_decode(&mut cursor)?.0;
+        let mut headers = vec![];
+        for _ in 0..count {
+            headers.push(decode_header(&mut cursor)?);
+            if VarInt::consensus_decode(&mut cursor)?.0 != 0 {
+                return Err(encode::Error::ParseFailed(
+                    "Headers message should not contain transactions",
+                ));
+            }
+        }
+        NetworkMessage::Headers(headers)
+    } else {
+        let header = decode_header(&mut cursor)?;
+        let txdata = Vec::<Transaction>::consensus_decode(&mut cursor)?;
+        NetworkMessage::Block(Block { header, txdata })
+    };
+    if cursor.position() as usize != payload.len() {
+        return Err(encode::Error::ParseFailed("data not consumed entirely"));
+    }
+
+    Ok((
+        RawNetworkMessage {
+            magic,
+            payload: message,
+        },
+        MESSAGE_HEADER_SIZE + length,
+    ))
+}
+
+/// Decodes a header and, if it is merge-mined, the AuxPoW proof following it.
+fn decode_header<D: io::Read>(mut d: D) -> Result<BlockHeader, encode::Error> {
+    let header = BlockHeader::consensus_decode(&mut d)?;
+    if is_auxpow(header.version) {
+        AuxPow::consensus_decode(&mut d)?
+            .check(&header)
+            .map_err(|_| encode::Error::ParseFailed("invalid AuxPoW proof"))?;
+    }
+    Ok(header)
+}
+
+fn decode_hashes<D: io::Read>(mut d: D) -> Result<Vec<sha256d::Hash>, encode::Error> {
+    let count = VarInt::consensus_decode(&mut d)?.0 as usize;
+    if count > MAX_DECODED_MERKLE_BRANCH_LENGTH {
+        return Err(encode::Error::ParseFailed("merkle branch is too long"));
+    }
+    (0..count)
+        .map(|_| sha256d::Hash::consensus_decode(&mut d))
+        .collect()
+}
+
+fn unexpected_eof() -> encode::Error {
+    encode::Error::Io(io::Error::from(io::ErrorKind::UnexpectedEof))
+}
+
+/// Returns the scrypt (N = 1024, r = 1, p = 1) proof-of-work hash of the header.
+pub fn scrypt_hash(header: &BlockHeader) -> [u8; 32] {
+    let serialized = encode::serialize(header);
+    let params = scrypt::Params::new(10, 1, 1, 32).expect("valid scrypt parameters");
+    let mut hash = [0u8; 32];
+    scrypt::scrypt(&serialized, &serialized, &params, &mut hash)
+        .expect("the output length is valid");
+    hash
+}
+
+/// Returns true if the little-endian hash is at most the target encoded in `bits`.
+fn satisfies_target(hash: &[u8; 32], bits: u32) -> bool {
+    let mut words = [0u64; 4];
+    for (word, chunk) in words.iter_mut().zip(hash.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    Uint256(words) <= BlockHeader::u256_from_compact_target(bits)
+}
+
+fn compute_merkle_root(
+    mut hash: sha256d::Hash,
+    branch: &[sha256d::Hash],
+    mut index: i32,
+) -> sha256d::Hash {
+    for node in branch {
+        let mut concatenated = Vec::with_capacity(64);
+        if index & 1 == 1 {
+            concatenated.extend_from_slice(&node[..]);
+            concatenated.extend_from_slice(&hash[..]);
+        } else {
+            concatenated.extend_from_slice(&hash[..]);
+            concatenated.extend_from_slice(&node[..]);
+        }
+        hash = sha256d::Hash::hash(&concatenated);
+        index >>= 1;
+    }
+    hash
+}
+
+/// Computes the slot of the Dogecoin chain in the chain merkle tree for the given nonce.
+fn expected_chain_index(nonce: u32, merkle_height: usize) -> u32 {
+    let mut rand = nonce;
+    rand = rand.wrapping_mul(1103515245).wrapping_add(12345);
+    rand = rand.wrapping_add(DOGECOIN_CHAIN_ID as u32);
+    rand = rand.wrapping_mul(1103515245).wrapping_add(12345);
+    rand % (1 << merkle_height)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn is_auxpow(version: i32) -> bool {
+    version & VERSION_AUXPOW != 0
+}
+
+fn chain_id(version: i32) -> i32 {
+    version >> 16
+}
+
+fn is_legacy(version: i32) -> bool {
+    version == 1 || (version == 2 && chain_id(version) == 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::BlockHeight;
+    use bitcoin::{consensus::serialize, network::message::NetworkMessage};
+    use std::collections::HashMap;
+
+    struct TestStore {
+        headers: HashMap<BlockHash, (BlockHeader, BlockHeight)>,
+        genesis: BlockHash,
+    }
+
+    impl TestStore {
+        fn new(network: &AdapterNetwork) -> Self {
+            let genesis = network.genesis_block_header();
+            let mut headers = HashMap::new();
+            headers.insert(genesis.block_hash(), (genesis, 0));
+            Self {
+                headers,
+                genesis: genesis.block_hash(),
+            }
+        }
+    }
+
+    impl HeaderStore for TestStore {
+        fn get_header(&self, hash: &BlockHash) -> Option<(BlockHeader, BlockHeight)> {
+            self.headers.get(hash).copied()
+        }
+
+        fn get_height(&self) -> BlockHeight {
+            self.headers
+                .values()
+                .map(|(_, height)| *height)
+                .max()
+                .unwrap()
+        }
+
+        fn get_initial_hash(&self) -> BlockHash {
+            self.genesis
+        }
+    }
+
+    fn mine(mut header: BlockHeader) -> BlockHeader {
+        while !satisfies_target(&scrypt_hash(&header), header.bits) {
+            header.nonce += 1;
+        }
+        header
+    }
+
+    #[test]
+    fn test_genesis_satisfies_scrypt_pow() {
+        for network in [AdapterNetwork::Dogecoin, AdapterNetwork::DogecoinTestnet] {
+            let genesis = network.genesis_block_header();
+            assert!(satisfies_target(&scrypt_hash(&genesis), genesis.bits));
+        }
+    }
+
+    #[test]
+    fn test_validate_regtest_header() {
+        let network = AdapterNetwork::DogecoinRegtest;
+        let store = TestStore::new(&network);
+        let genesis = network.genesis_block_header();
+        let header = mine(BlockHeader {
+            version: DOGECOIN_CHAIN_ID << 16 | 4,
+            prev_blockhash: genesis.block_hash(),
+            merkle_root: Default::default(),
+            time: genesis.time + 60,
+            bits: genesis.bits,
+            nonce: 0,
+        });
+        assert_eq!(validate_header(&network, &store, &header), Ok(()));
+
+        let wrong_chain_id = BlockHeader {
+            version: 0x10 << 16 | 4,
+            ..header
+        };
+        assert_eq!(
+            validate_header(&network, &store, &wrong_chain_id),
+            Err(ValidateDogecoinHeaderError::WrongChainId(0x10))
+        );
+
+        let too_old = BlockHeader {
+            time: genesis.time,
+            ..header
+        };
+        assert_eq!(
+            validate_header(&network, &store, &too_old),
+            Err(ValidateDogecoinHeaderError::TimestampTooOld(genesis.time))
+        );
+
+        let auxpow_too_early = BlockHeader {
+            version: header.version | VERSION_AUXPOW,
+            ..header
+        };
+        assert_eq!(
+            validate_header(&network, &store, &auxpow_too_early),
+            Err(ValidateDogecoinHeaderError::AuxPowNotAllowed(1))
+        );
+    }
+
+    #[test]
+    fn test_digishield_retarget_is_dampened_and_clamped() {
+        let params = ConsensusParams::for_network(&AdapterNetwork::Dogecoin);
+        let prev_header = BlockHeader {
+            version: 1,
+            prev_blockhash: Default::default(),
+            merkle_root: Default::default(),
+            time: 1_000_000,
+            bits: 0x1b0ffff0,
+            nonce: 0,
+        };
+        let height = DIGISHIELD_HEIGHT + 1;
+        let prev_target = BlockHeader::u256_from_compact_target(prev_header.bits);
+
+        // A block found exactly on time keeps the difficulty.
+        let on_time = calculate_next_work_required(
+            &params,
+            &prev_header,
+            height,
+            prev_header.time as i64 - TARGET_SPACING,
+        );
+        assert_eq!(on_time, prev_header.bits);
+
+        // A very slow block can at most increase the target by 50%.
+        let slow = calculate_next_work_required(&params, &prev_header, height, 0);
+        let expected = prev_target.mul_u32(90) / Uint256::from_u64(60).unwrap();
+        assert_eq!(slow, BlockHeader::compact_target_from_u256(&expected));
+
+        // A very fast block can at most decrease the target by 25%.
+        let fast = calculate_next_work_required(
+            &params,
+            &prev_header,
+            height,
+            prev_header.time as i64 + 10_000,
+        );
+        let expected = prev_target.mul_u32(45) / Uint256::from_u64(60).unwrap();
+        assert_eq!(fast, BlockHeader::compact_target_from_u256(&expected));
+    }
+
+    fn merge_mined_header() -> (BlockHeader, AuxPow) {
+        let header = BlockHeader {
+            version: DOGECOIN_CHAIN_ID << 16 | VERSION_AUXPOW | 4,
+            prev_blockhash: Default::default(),
+            merkle_root: Default::default(),
+            time: 1_000_000,
+            bits: 0x207fffff,
+            nonce: 0,
+        };
+        let mut chain_root = header.block_hash().into_inner();
+        chain_root.reverse();
+        let mut script = MERGED_MINING_HEADER.to_vec();
+        script.extend_from_slice(&chain_root);
+        script.extend_from_slice(&1u32.to_le_bytes());
+        script.extend_from_slice(&0u32.to_le_bytes());
+        let coinbase_tx = Transaction {
+            version: 1,
+            lock_time: 0,
+            input: vec![bitcoin::TxIn {
+                script_sig: script.into(),
+                ..Default::default()
+            }],
+            output: vec![],
+        };
+        let parent_header = mine(BlockHeader {
+            version: 4,
+            prev_blockhash: Default::default(),
+            merkle_root: bitcoin::TxMerkleNode::from_hash(coinbase_tx.txid().as_hash()),
+            time: 1_000_000,
+            bits: 0x207fffff,
+            nonce: 0,
+        });
+        let auxpow = AuxPow {
+            coinbase_tx,
+            parent_hash: parent_header.block_hash(),
+            coinbase_branch: vec![],
+            coinbase_index: 0,
+            chain_branch: vec![],
+            chain_index: 0,
+            parent_header,
+        };
+        (header, auxpow)
+    }
+
+    fn headers_message(header: &BlockHeader, auxpow: &AuxPow) -> Vec<u8> {
+        let mut payload = vec![1];
+        payload.extend(serialize(header));
+        payload.extend(serialize(&auxpow.coinbase_tx));
+        payload.extend(serialize(&auxpow.parent_hash));
+        payload.extend([0]);
+        payload.extend(serialize(&auxpow.coinbase_index));
+        payload.extend([0]);
+        payload.extend(serialize(&auxpow.chain_index));
+        payload.extend(serialize(&auxpow.parent_header));
+        payload.extend([0]);
+
+        let mut message = AdapterNetwork::DogecoinRegtest
+            .magic()
+            .to_le_bytes()
+            .to_vec();
+        message.extend(b"headers\0\0\0\0\0");
+        message.extend((payload.len() as u32).to_le_bytes());
+        message.extend(&sha256d::Hash::hash(&payload)[..4]);
+        message.extend(payload);
+        message
+    }
+
+    #[test]
+    fn test_auxpow_headers_are_verified_and_stripped() {
+        let (header, auxpow) = merge_mined_header();
+        assert_eq!(auxpow.check(&header), Ok(()));
+
+        let bytes = headers_message(&header, &auxpow);
+        let (decoded, consumed) = deserialize_partial_raw_message(&bytes).unwrap();
+        assert_eq!(decoded.payload, NetworkMessage::Headers(vec![header]));
+        assert_eq!(consumed, bytes.len());
+    }
+
+    #[test]
+    fn test_invalid_auxpow_is_rejected() {
+        let (header, mut auxpow) = merge_mined_header();
+        auxpow.coinbase_tx.lock_time = 1;
+        assert_eq!(
+            auxpow.check(&header),
+            Err(AuxPowError::CoinbaseNotInParentBlock)
+        );
+        assert!(matches!(
+            deserialize_partial_raw_message(&headers_message(&header, &auxpow)),
+            Err(encode::Error::ParseFailed(_))
+        ));
+
+        let (header, auxpow) = merge_mined_header();
+        let other_header = BlockHeader {
+            time: header.time + 1,
+            ..header
+        };
+        assert_eq!(
+            auxpow.check(&other_header),
+            Err(AuxPowError::MissingChainMerkleRoot)
+        );
+    }
+
+    #[test]
+    fn test_plain_messages_are_decoded_as_bitcoin_messages() {
+        let header = AdapterNetwork::Dogecoin.genesis_block_header();
+        let message = RawNetworkMessage {
+            magic: AdapterNetwork::Dogecoin.magic(),
+            payload: NetworkMessage::Headers(vec![header]),
+        };
+        let bytes = serialize(&message);
+
+        let (decoded, consumed) = deserialize_partial_raw_message(&bytes).unwrap();
+        assert_eq!(decoded, message);
+        assert_eq!(consumed, bytes.len());
+
+        // Partial messages ask for more data.
+        let err = deserialize_partial_raw_message(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert!(
+            matches!(err, encode::Error::Io(ref err) if err.kind() == io::ErrorKind::UnexpectedEof)
+        );
+    }
+}
@@ -3,15 +3,14 @@ use std::{
     sync::Arc,
 };
 
-use bitcoin::{Block, BlockHash, BlockHeader, Network};
-use ic_btc_validation::is_beyond_last_checkpoint;
+use bitcoin::{Block, BlockHash, BlockHeader};
 use ic_metrics::MetricsRegistry;
 use tokio::sync::{mpsc::Sender, Mutex};
 use tonic::{Code, Status};
 
 use crate::{
-    common::BlockHeight, config::Config, metrics::GetSuccessorMetrics, BlockchainManagerRequest,
-    BlockchainState,
+    common::BlockHeight, config::Config, metrics::GetSuccessorMetrics, network::AdapterNetwork,
+    BlockchainManagerRequest, BlockchainState,
 };
 
 // Max size of the `GetSuccessorsResponse` message.
@@ -62,7 +61,7 @@ pub struct GetSuccessorsResponse {
 pub struct GetSuccessorsHandler {
     state: Arc<Mutex<BlockchainState>>,
     blockchain_manager_tx: Sender<BlockchainManagerRequest>,
-    network: Network,
+    network: AdapterNetwork,
     metrics: GetSuccessorMetrics,
 }
 
@@ -105,7 +104,10 @@ impl GetSuccessorsHandler {
 
             // Wait with downloading blocks until we synced the header chain above the last checkpoint
             // to make sure we are following the correct chain.
-            if !is_beyond_last_checkpoint(&self.network, state.get_active_chain_tip().height) {
+            if !self
+                .network
+                .is_beyond_last_checkpoint(state.get_active_chain_tip().height)
+            {
                 return Err(Status::new(
                     Code::Unavailable,
                     "Header chain not yet synced past last checkpoint",
@@ -246,10 +248,15 @@ fn get_next_headers(
 }
 
 /// Helper used to determine if multiple blocks should be returned.
-fn are_multiple_blocks_allowed(network: Network, anchor_height: BlockHeight) -> bool {
+fn are_multiple_blocks_allowed(network: AdapterNetwork, anchor_height: BlockHeight) -> bool {
     match network {
-        Network::Bitcoin => anchor_height <= MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT,
-        Network::Testnet | Network::Signet | Network::Regtest => true,
+        AdapterNetwork::Bitcoin => anchor_height <= MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT,
+        AdapterNetwork::Testnet
+        | AdapterNetwork::Signet
+        | AdapterNetwork::Regtest
+        | AdapterNetwork::Dogecoin
+        | AdapterNetwork::DogecoinTestnet
+        | AdapterNetwork::DogecoinRegtest => true,
     }
 }
 
@@ -799,37 +806,40 @@ mod test {
     fn test_are_multiple_blocks_allowed() {
         // Mainnet
         assert!(
-            are_multiple_blocks_allowed(Network::Bitcoin, 100_500),
+            are_multiple_blocks_allowed(AdapterNetwork::Bitcoin, 100_500),
             "Multiple blocks are allowed at 100_500"
         );
         assert!(
-            are_multiple_blocks_allowed(Network::Bitcoin, MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT),
+            are_multiple_blocks_allowed(
+                AdapterNetwork::Bitcoin,
+                MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT
+            ),
             "Multiple blocks are allowed at {}",
             MAINNET_MAX_MULTI_BLOCK_ANCHOR_HEIGHT
         );
         assert!(
-            !are_multiple_blocks_allowed(Network::Bitcoin, 900_000),
+            !are_multiple_blocks_allowed(AdapterNetwork::Bitcoin, 900_000),
             "Multiple blocks are not allowed at 900_000"
         );
 
         // Testnet
         assert!(
-            are_multiple_blocks_allowed(Network::Testnet, 1_000_000),
+            are_multiple_blocks_allowed(AdapterNetwork::Testnet, 1_000_000),
             "Multiple blocks are allowed at 1_000_000"
         );
         assert!(
-            are_multiple_blocks_allowed(Network::Testnet, u32::MAX),
+            are_multiple_blocks_allowed(AdapterNetwork::Testnet, u32::MAX),
             "Multiple blocks are allowed at {}",
             u32::MAX
         );
 
         // Regtest
         assert!(
-            are_multiple_blocks_allowed(Network::Regtest, 1),
+            are_multiple_blocks_allowed(AdapterNetwork::Regtest, 1),
             "Multiple blocks are allowed at 1"
         );
         assert!(
-            are_multiple_blocks_allowed(Network::Regtest, u32::MAX),
+            are_multiple_blocks_allowed(AdapterNetwork::Regtest, u32::MAX),
             "Multiple blocks are allowed at {}",
             u32::MAX
         );
@@ -0,0 +1,241 @@
+//! The module describes the networks the adapter is able to connect to and the
+//! chain-specific rules (magic bytes, ports, genesis, header validation) that come with them.
+use crate::{
+    common::BlockHeight,
+    dogecoin::{self, ValidateDogecoinHeaderError},
+};
+use bitcoin::{
+    blockdata::constants::genesis_block, hashes::hex::FromHex, BlockHash, BlockHeader, Network,
+    TxMerkleNode,
+};
+use ic_btc_validation::{is_beyond_last_checkpoint, validate_header, HeaderStore};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The merkle root shared by the genesis blocks of all Dogecoin networks.
+const DOGECOIN_GENESIS_MERKLE_ROOT: &str =
+    "5b2a3f53f605d62c53e62932dac6925e3d74afa5a4b459745c36d42d0ed26a69";
+
+/// The network the adapter connects to.
+///
+/// The Bitcoin variants serialize to the same names as [bitcoin::Network] so existing
+/// configuration files keep working.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AdapterNetwork {
+    /// Bitcoin mainnet.
+    Bitcoin,
+    /// Bitcoin testnet.
+    Testnet,
+    /// Bitcoin signet.
+    Signet,
+    /// Bitcoin regtest.
+    Regtest,
+    /// Dogecoin mainnet.
+    Dogecoin,
+    /// Dogecoin testnet.
+    DogecoinTestnet,
+    /// Dogecoin regtest.
+    DogecoinRegtest,
+}
+
+/// The error returned when a header does not satisfy the consensus rules of the network.
+#[derive(Debug, Error)]
+pub enum ValidateHeaderError {
+    /// The header violates the Bitcoin consensus rules.
+    #[error("{0:?}")]
+    Bitcoin(ic_btc_validation::ValidateHeaderError),
+    /// The header violates the Dogecoin consensus rules.
+    #[error("{0}")]
+    Dogecoin(ValidateDogecoinHeaderError),
+}
+
+impl From<Network> for AdapterNetwork {
+    fn from(network: Network) -> Self {
+        match network {
+            Network::Bitcoin => AdapterNetwork::Bitcoin,
+            Network::Testnet => AdapterNetwork::Testnet,
+            Network::Signet => AdapterNetwork::Signet,
+            Network::Regtest => AdapterNetwork::Regtest,
+        }
+    }
+}
+
+impl AdapterNetwork {
+    /// Returns the corresponding [bitcoin::Network] if this is a Bitcoin network.
+    pub fn bitcoin_network(&self) -> Option<Network> {
+        match self {
+            AdapterNetwork::Bitcoin => Some(Network::Bitcoin),
+            AdapterNetwork::Testnet => Some(Network::Testnet),
+            AdapterNetwork::Signet => Some(Network::Signet),
+            AdapterNetwork::Regtest => Some(Network::Regtest),
+            AdapterNetwork::Dogecoin
+            | AdapterNetwork::DogecoinTestnet
+            | AdapterNetwork::DogecoinRegtest => None,
+        }
+    }
+
+    /// Returns true if this is one of the Dogecoin networks.
+    pub fn is_dogecoin(&self) -> bool {
+        self.bitcoin_network().is_none()
+    }
+
+    /// Returns the magic value that prefixes every raw network message on this network.
+    pub fn magic(&self) -> u32 {
+        match self.bitcoin_network() {
+            Some(network) => network.magic(),
+            // The message start bytes are encoded as a little-endian u32.
+            None => match self {
+                AdapterNetwork::DogecoinTestnet => 0xdcb7c1fc,
+                AdapterNetwork::DogecoinRegtest => 0xdab5bffa,
+                _ => 0xc0c0c0c0,
+            },
+        }
+    }
+
+    /// Returns the default P2P port of the network.
+    pub fn default_port(&self) -> u16 {
+        match self {
+            AdapterNetwork::Bitcoin => 8333,
+            AdapterNetwork::Testnet => 18333,
+            AdapterNetwork::Dogecoin => 22556,
+            AdapterNetwork::DogecoinTestnet => 44556,
+            AdapterNetwork::DogecoinRegtest => 18444,
+            _ => 8333,
+        }
+    }
+
+    /// Returns the DNS seeds used for address discovery if none are provided in the config.
+    pub fn default_dns_seeds(&self) -> Vec<String> {
+        let seeds: &[&str] = match self {
+            AdapterNetwork::Dogecoin => &["seed.multidoge.org", "seed2.multidoge.org"],
+            AdapterNetwork::DogecoinTestnet => &["testseed.jrn.me.uk"],
+            _ => &[],
+        };
+        seeds.iter().map(|seed| seed.to_string()).collect()
+    }
+
+    /// Returns the address limits used by the `AddressBook` for the network.
+    pub fn address_limits(&self) -> (usize, usize) {
+        match self {
+            AdapterNetwork::Bitcoin | AdapterNetwork::Dogecoin => (500, 2000),
+            AdapterNetwork::Testnet | AdapterNetwork::DogecoinTestnet => (100, 1000),
+            AdapterNetwork::Signet | AdapterNetwork::Regtest | AdapterNetwork::DogecoinRegtest => {
+                (1, 1)
+            }
+        }
+    }
+
+    /// Returns the header of the genesis block of the network.
+    pub fn genesis_block_header(&self) -> BlockHeader {
+        if let Some(network) = self.bitcoin_network() {
+            return genesis_block(network).header;
+        }
+
+        let (time, bits, nonce) = match self {
+            AdapterNetwork::DogecoinTestnet => (1391503289, 0x1e0ffff0, 997879),
+            AdapterNetwork::DogecoinRegtest => (1296688602, 0x207fffff, 2),
+            _ => (1386325540, 0x1e0ffff0, 99943),
+        };
+        BlockHeader {
+            version: 1,
+            prev_blockhash: BlockHash::default(),
+            merkle_root: TxMerkleNode::from_hex(DOGECOIN_GENESIS_MERKLE_ROOT)
+                .expect("the genesis merkle root is a valid hash"),
+            time,
+            bits,
+            nonce,
+        }
+    }
+
+    /// Returns true if headers on this network may carry an AuxPoW proof.
+    pub fn supports_auxpow(&self) -> bool {
+        self.is_dogecoin()
+    }
+
+    /// Validates the header against the consensus rules of the network.
+    pub fn validate_header(
+        &self,
+        store: &impl HeaderStore,
+        header: &BlockHeader,
+    ) -> Result<(), ValidateHeaderError> {
+        match self.bitcoin_network() {
+            Some(network) => {
+                validate_header(&network, store, header).map_err(ValidateHeaderError::Bitcoin)
+            }
+            None => dogecoin::validate_header(self, store, header)
+                .map_err(ValidateHeaderError::Dogecoin),
+        }
+    }
+
+    /// Returns true if the given height is beyond the last checkpoint of the network.
+    /// Networks without checkpoints are always considered to be beyond them.
+    pub fn is_beyond_last_checkpoint(&self, height: BlockHeight) -> bool {
+        match self.bitcoin_network() {
+            Some(network) => is_beyond_last_checkpoint(&network, height),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bitcoin_networks_keep_their_config_names() {
+        for (name, network) in [
+            ("\"bitcoin\"", AdapterNetwork::Bitcoin),
+            ("\"testnet\"", AdapterNetwork::Testnet),
+            ("\"signet\"", AdapterNetwork::Signet),
+            ("\"regtest\"", AdapterNetwork::Regtest),
+            ("\"dogecoin_testnet\"", AdapterNetwork::DogecoinTestnet),
+        ] {
+            let parsed: AdapterNetwork = serde_json::from_str(name).unwrap();
+            assert_eq!(parsed, network);
+            if let Some(bitcoin_network) = network.bitcoin_network() {
+                assert_eq!(serde_json::to_string(&bitcoin_network).unwrap(), name);
+                assert_eq!(network.magic(), bitcoin_network.magic());
+            }
+        }
+    }
+
+    #[test]
+    fn test_dogecoin_genesis_block_hashes() {
+        for (network, expected) in [
+            (
+                AdapterNetwork::Dogecoin,
+                "1a91e3dace36e2be3bf030a65679fe821aa1d6ef92e7c9902eb318182c355691",
+            ),
+            (
+                AdapterNetwork::DogecoinTestnet,
+                "bb0a78264637406b6360aad926284d544d7049f45189db5664f3c4d07350559e",
+            ),
+            (
+                AdapterNetwork::DogecoinRegtest,
+                "3d2160a3b5dc4a9d62e7e66a295f70313ac808440ef7400d6c0772171ce973a5",
+            ),
+        ] {
+            assert_eq!(
+                network.genesis_block_header().block_hash(),
+                BlockHash::from_hex(expected).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_dogecoin_magic_matches_message_start() {
+        assert_eq!(
+            AdapterNetwork::Dogecoin.magic().to_le_bytes(),
+            [0xc0, 0xc0, 0xc0, 0xc0]
+        );
+        assert_eq!(
+            AdapterNetwork::DogecoinTestnet.magic().to_le_bytes(),
+            [0xfc, 0xc1, 0xb7, 0xdc]
+        );
+        assert_eq!(
+            AdapterNetwork::DogecoinRegtest.magic().to_le_bytes(),
+            [0xfa, 0xbf, 0xb5, 0xda]
+        );
+    }
+}
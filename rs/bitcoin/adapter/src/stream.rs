@@ -1,3 +1,4 @@
+use crate::dogecoin;
 use bitcoin::{
     consensus::serialize,
     network::message::RawNetworkMessage,
@@ -70,6 +71,9 @@ pub struct StreamConfig {
     /// This field is used to provide the magic value to the raw network message.
     /// The magic number is used to identity the type of Bitcoin network being accessed.
     pub magic: u32,
+    /// This field is set when headers and blocks received from the node may carry an
+    /// AuxPoW proof (merge-mined networks like Dogecoin).
+    pub auxpow: bool,
     /// This field is used to receive network messages to send out to the connected
     /// BTC node.
     pub network_message_receiver: UnboundedReceiver<NetworkMessage>,
@@ -118,6 +122,9 @@ pub struct Stream {
     /// This field is used to provide the magic value to the raw network message.
     /// The magic number is used to identity the type of Bitcoin network being accessed.
     magic: u32,
+    /// This field is set when headers and blocks received from the node may carry an
+    /// AuxPoW proof.
+    auxpow: bool,
     /// This field contains the receiver used to intake messages that are to be
     /// sent to the connected node.
     network_message_receiver: UnboundedReceiver<NetworkMessage>,
@@ -134,6 +141,7 @@ impl Stream {
             address,
             socks_proxy,
             magic,
+            auxpow,
             network_message_receiver,
             network_message_sender,
             ..
@@ -203,6 +211,7 @@ impl Stream {
             read_half,
             write_half,
             magic,
+            auxpow,
             network_message_receiver,
             network_message_sender,
             unparsed,
@@ -220,7 +229,12 @@ impl Stream {
             }
             // The stream may only a message partial from the Bitcoin node.
             // Due to this, the stream must attempt to deserialize partial messages.
-            match encode::deserialize_partial::<RawNetworkMessage>(&self.unparsed) {
+            let result = if self.auxpow {
+                dogecoin::deserialize_partial_raw_message(&self.unparsed)
+            } else {
+                encode::deserialize_partial::<RawNetworkMessage>(&self.unparsed)
+            };
+            match result {
                 // If there was an I/O error found in the unparsed message and it was an unexpected
                 // end-of-file, then the stream should try to read again. If the read fails, the stream
                 // exits the read message with the error. The stream later looks at this error, if the
@@ -392,6 +406,7 @@ pub mod test {
             address,
             logger: no_op_logger(),
             magic: network.magic(),
+            auxpow: false,
             network_message_receiver: adapter_rx,
             socks_proxy: None,
             stream_event_sender: stream_tx,
@@ -449,6 +464,7 @@ pub mod test {
             address,
             logger: no_op_logger(),
             magic: network.magic(),
+            auxpow: false,
             network_message_receiver: adapter_rx,
             socks_proxy: None,
             stream_event_sender: stream_tx,
@@ -475,6 +491,7 @@ pub mod test {
             address,
             logger: no_op_logger(),
             magic: network.magic(),
+            auxpow: false,
             network_message_receiver: adapter_rx,
             socks_proxy: None,
             stream_event_sender: stream_tx,
@@ -6,10 +6,9 @@ use ic_btc_adapter::{
     start_grpc_server_and_router, AdapterState,
 };
 use ic_btc_adapter_client::setup_bitcoin_adapter_clients;
-use ic_btc_interface::Network;
 use ic_btc_replica_types::{
     BitcoinAdapterRequestWrapper, BitcoinAdapterResponseWrapper, GetSuccessorsRequestInitial,
-    SendTransactionRequest,
+    Network, SendTransactionRequest,
 };
 use ic_config::adapters::AdaptersConfig;
 use ic_config::bitcoin_payload_builder_config::Config as BitcoinPayloadBuilderConfig;
@@ -94,7 +93,7 @@ async fn start_adapter(
     network: bitcoin::Network,
 ) {
     let config = Config {
-        network,
+        network: network.into(),
         incoming_source: IncomingSource::Path(uds_path.to_path_buf()),
         nodes,
         ipv6_only: true,
@@ -130,6 +129,10 @@ async fn start_client(
         bitcoin_mainnet_uds_metrics_path: None,
         bitcoin_testnet_uds_path: None,
         bitcoin_testnet_uds_metrics_path: None,
+        dogecoin_mainnet_uds_path: None,
+        dogecoin_mainnet_uds_metrics_path: None,
+        dogecoin_testnet_uds_path: None,
+        dogecoin_testnet_uds_metrics_path: None,
         https_outcalls_uds_path: None,
         https_outcalls_uds_metrics_path: None,
     };
@@ -1,9 +1,8 @@
 use std::sync::Arc;
 
-use ic_btc_interface::Network;
 use ic_btc_replica_types::{
     BitcoinAdapterRequestWrapper, BitcoinAdapterResponse, BitcoinAdapterResponseWrapper,
-    GetSuccessorsRequestInitial, GetSuccessorsResponseComplete, SendTransactionResponse,
+    GetSuccessorsRequestInitial, GetSuccessorsResponseComplete, Network, SendTransactionResponse,
 };
 use ic_config::bitcoin_payload_builder_config::Config;
 use ic_interfaces::batch_payload::{BatchPayloadBuilder, ProposalContext};
@@ -65,6 +64,8 @@ fn proptest_round(
         &MetricsRegistry::new(),
         Box::new(MockBitcoinAdapterClient::new()),
         Box::new(adapter_client),
+        Box::new(MockBitcoinAdapterClient::new()),
+        Box::new(MockBitcoinAdapterClient::new()),
         subnet_test_id(0),
         Arc::new(mock_registry_client(NumBytes::new(
             MAX_BTC_BLOCK_SIZE as u64,
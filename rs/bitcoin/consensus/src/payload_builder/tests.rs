@@ -1,8 +1,7 @@
 use crate::{payload_builder::parse, BitcoinPayloadBuilder};
-use ic_btc_interface::Network;
 use ic_btc_replica_types::{
     BitcoinAdapterRequestWrapper, BitcoinAdapterResponse, BitcoinAdapterResponseWrapper,
-    BitcoinReject, GetSuccessorsRequestInitial, GetSuccessorsResponseComplete,
+    BitcoinReject, GetSuccessorsRequestInitial, GetSuccessorsResponseComplete, Network,
 };
 use ic_config::bitcoin_payload_builder_config::Config;
 use ic_error_types::RejectCode;
@@ -117,6 +116,8 @@ fn bitcoin_payload_builder_test(
             &MetricsRegistry::new(),
             Box::new(bitcoin_mainnet_adapter_client),
             Box::new(bitcoin_testnet_adapter_client),
+            Box::new(MockBitcoinAdapterClient::new()),
+            Box::new(MockBitcoinAdapterClient::new()),
             subnet_test_id(0),
             Arc::new(registry_client),
             Config::default(),
@@ -1,19 +1,43 @@
 use bitcoin::{
     address::FromScriptError,
     consensus::{encode, Decodable},
-    Address, Network, Transaction,
+    Address, Network, Transaction, Txid,
 };
-use futures::future::try_join_all;
-use ic_cdk::api::call::RejectionCode;
+use futures::future::{join_all, try_join_all};
+use ic_cdk::api::call::{msg_cycles_accept128, msg_cycles_available128, RejectionCode};
 use ic_cdk::api::management_canister::http_request::{
     http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext,
     TransformFunc,
 };
 
 pub mod blocklist;
+mod providers;
+mod state;
 mod types;
+pub use providers::Provider;
+pub use state::FetchedTx;
 pub use types::*;
 
+/// The minimum number of cycles a caller has to attach to `check_transaction`.
+pub const CHECK_TRANSACTION_CYCLES_REQUIRED: u128 = 40_000_000_000;
+
+/// The number of cycles charged for every `check_transaction` call, on top of
+/// the cycles spent on HTTPS outcalls.
+pub const CHECK_TRANSACTION_CYCLES_SERVICE_FEE: u128 = 100_000_000;
+
+// The max_response_bytes is set to 400KiB because:
+// - The maximum size of a standard non-taproot transaction is 400k vBytes.
+// - Taproot transactions could be as big as full block size (4MiB).
+// - Currently a subnet's maximum response size is only 2MiB.
+// - Transactions bigger than 2MiB are very rare.
+//
+// TODO(XC-171): Transactions between 400k and 2MiB are uncommon but may need to be handled.
+const MAX_RESPONSE_BYTES: u64 = 400 * 1024;
+
+/// The number of cycles attached to a single HTTPS outcall fetching a transaction
+/// (1 KiB request, 400 KiB response).
+pub const GET_TX_CYCLES: u128 = 49_140_000 + 1024 * 5_200 + 10_400 * MAX_RESPONSE_BYTES as u128;
+
 #[derive(Debug)]
 pub enum BitcoinTxError {
     Address(FromScriptError),
@@ -26,6 +50,24 @@ pub enum BitcoinTxError {
         code: RejectionCode,
         message: String,
     },
+    HttpStatus(candid::Nat),
+    NotEnoughCycles,
+}
+
+impl From<BitcoinTxError> for CheckTransactionStatus {
+    fn from(err: BitcoinTxError) -> Self {
+        match err {
+            BitcoinTxError::NotEnoughCycles => CheckTransactionStatus::NotEnoughCycles,
+            BitcoinTxError::Rejected { .. } | BitcoinTxError::HttpStatus(_) => {
+                CheckTransactionStatus::Retriable(format!("{:?}", err))
+            }
+            BitcoinTxError::Address(_)
+            | BitcoinTxError::Encoding(_)
+            | BitcoinTxError::TxIdMismatch { .. } => {
+                CheckTransactionStatus::Error(format!("{:?}", err))
+            }
+        }
+    }
 }
 
 pub fn blocklist_contains(address: &Address) -> bool {
@@ -35,7 +77,7 @@ pub fn blocklist_contains(address: &Address) -> bool {
 }
 
 pub async fn get_inputs_internal(tx_id: String) -> Result<Vec<String>, BitcoinTxError> {
-    let tx = get_tx(tx_id).await?;
+    let tx = get_tx(Provider::Btcscan, tx_id).await?;
 
     let mut addresses = vec![];
     let mut futures = vec![];
@@ -43,7 +85,10 @@ pub async fn get_inputs_internal(tx_id: String) -> Result<Vec<String>, BitcoinTx
 
     for input in tx.input.iter() {
         vouts.push(input.previous_output.vout as usize);
-        futures.push(get_tx(input.previous_output.txid.to_string()));
+        futures.push(get_tx(
+            Provider::Btcscan,
+            input.previous_output.txid.to_string(),
+        ));
     }
     let input_txs = try_join_all(futures).await?;
 
@@ -57,9 +102,81 @@ pub async fn get_inputs_internal(tx_id: String) -> Result<Vec<String>, BitcoinTx
     Ok(addresses)
 }
 
-async fn get_tx(tx_id: String) -> Result<Transaction, BitcoinTxError> {
-    // TODO(XC-159): Support multiple providers
-    let host = "btcscan.org";
+/// Checks the addresses of all the outputs spent by the given transaction
+/// against the blocklist.
+pub async fn check_transaction_internal(txid: Txid) -> CheckTransactionResponse {
+    let tx = match fetch_tx(txid).await {
+        Ok(tx) => tx,
+        Err(err) => return CheckTransactionResponse::Unknown(err.into()),
+    };
+
+    // Wait for all fetches to finish, even if one of them fails, so that
+    // every fetched transaction ends up in the cache for the next attempt.
+    let input_txs = join_all(tx.inputs.iter().map(|input| fetch_tx(input.txid))).await;
+
+    let mut blocked_addresses = vec![];
+    for (input, input_tx) in tx.inputs.iter().zip(input_txs) {
+        let input_tx = match input_tx {
+            Ok(input_tx) => input_tx,
+            Err(err) => return CheckTransactionResponse::Unknown(err.into()),
+        };
+        match input_tx.output_addresses.get(input.vout as usize) {
+            Some(Some(address)) => {
+                if blocklist_contains(address) {
+                    blocked_addresses.push(address.to_string());
+                }
+            }
+            // Outputs without an address cannot be on the blocklist.
+            Some(None) => {}
+            None => {
+                return CheckTransactionResponse::Unknown(CheckTransactionStatus::Error(format!(
+                    "Transaction {} has no output {}",
+                    input.txid, input.vout
+                )))
+            }
+        }
+    }
+
+    if blocked_addresses.is_empty() {
+        CheckTransactionResponse::Passed
+    } else {
+        CheckTransactionResponse::Failed(blocked_addresses)
+    }
+}
+
+/// Returns the transaction with the given ID from the cache, or fetches it
+/// from the providers, charging the cycles of each outcall to the caller.
+async fn fetch_tx(txid: Txid) -> Result<FetchedTx, BitcoinTxError> {
+    if let Some(tx) = state::get_fetched_tx(&txid) {
+        return Ok(tx);
+    }
+
+    let mut last_error = None;
+    for provider in Provider::rotated(state::next_provider_offset()) {
+        if msg_cycles_available128() < GET_TX_CYCLES {
+            return Err(BitcoinTxError::NotEnoughCycles);
+        }
+        msg_cycles_accept128(GET_TX_CYCLES);
+        match get_tx(provider, txid.to_string()).await {
+            Ok(tx) => {
+                let fetched = FetchedTx::new(&tx);
+                state::insert_fetched_tx(txid, fetched.clone());
+                return Ok(fetched);
+            }
+            Err(err) => {
+                println!(
+                    "Failed to fetch transaction {txid} from {}: {err:?}",
+                    provider.host()
+                );
+                last_error = Some(err);
+            }
+        }
+    }
+    Err(last_error.expect("BUG: there is at least one provider"))
+}
+
+async fn get_tx(provider: Provider, tx_id: String) -> Result<Transaction, BitcoinTxError> {
+    let host = provider.host();
     let url = format!("https://{}/api/tx/{}/raw", host, tx_id);
     let request_headers = vec![
         HttpHeader {
@@ -71,18 +188,11 @@ async fn get_tx(tx_id: String) -> Result<Transaction, BitcoinTxError> {
             value: "bitcoin_inputs_collector".to_string(),
         },
     ];
-    // The max_response_bytes is set to 400KiB because:
-    // - The maximum size of a standard non-taproot transaction is 400k vBytes.
-    // - Taproot transactions could be as big as full block size (4MiB).
-    // - Currently a subnet's maximum response size is only 2MiB.
-    // - Transactions bigger than 2MiB are very rare.
-    //
-    // TODO(XC-171): Transactions between 400k and 2MiB are uncommon but may need to be handled.
     let request = CanisterHttpRequestArgument {
         url: url.to_string(),
         method: HttpMethod::GET,
         body: None,
-        max_response_bytes: Some(400 * 1024), // 400 KiB
+        max_response_bytes: Some(MAX_RESPONSE_BYTES),
         transform: Some(TransformContext {
             function: TransformFunc(candid::Func {
                 principal: ic_cdk::api::id(),
@@ -92,10 +202,11 @@ async fn get_tx(tx_id: String) -> Result<Transaction, BitcoinTxError> {
         }),
         headers: request_headers,
     };
-    let cycles = 49_140_000 + 1024 * 5_200 + 10_400 * 400 * 1024; // 1 KiB request, 400 KiB response
-    match http_request(request, cycles).await {
+    match http_request(request, GET_TX_CYCLES).await {
         Ok((response,)) => {
-            // TODO(XC-158): ensure response is 200 before decoding
+            if response.status != candid::Nat::from(200_u32) {
+                return Err(BitcoinTxError::HttpStatus(response.status));
+            }
             let tx = Transaction::consensus_decode(&mut response.body.as_slice())
                 .map_err(BitcoinTxError::Encoding)?;
             // Verify the correctness of the transaction by recomputing the transaction ID.
@@ -109,7 +220,6 @@ async fn get_tx(tx_id: String) -> Result<Transaction, BitcoinTxError> {
             Ok(tx)
         }
         Err((r, m)) => {
-            // TODO(XC-158): maybe try other providers and also log the error.
             println!("The http_request resulted into error. RejectionCode: {r:?}, Error: {m}");
             Err(BitcoinTxError::Rejected {
                 code: r,
@@ -130,4 +240,43 @@ mod test {
             assert!(l < r, "the block list is not sorted: {} >= {}", l, r);
         }
     }
+
+    #[test]
+    fn providers_are_rotated() {
+        use crate::Provider;
+        assert_eq!(
+            Provider::rotated(0).collect::<Vec<_>>(),
+            Provider::ALL.to_vec()
+        );
+        assert_eq!(
+            Provider::rotated(4).collect::<Vec<_>>(),
+            vec![
+                Provider::Blockstream,
+                Provider::MempoolSpace,
+                Provider::Btcscan
+            ]
+        );
+    }
+
+    #[test]
+    fn fetched_tx_cache_evicts_oldest_entry() {
+        use crate::state::{FetchedTx, FetchedTxCache};
+        use bitcoin::{hashes::Hash, Txid};
+
+        let txid = |n: u8| Txid::from_byte_array([n; 32]);
+        let tx = FetchedTx {
+            inputs: vec![],
+            output_addresses: vec![None],
+        };
+        let mut cache = FetchedTxCache::new(2);
+        cache.insert(txid(1), tx.clone());
+        cache.insert(txid(2), tx.clone());
+        // Re-inserting an entry does not change the eviction order.
+        cache.insert(txid(1), tx.clone());
+        cache.insert(txid(3), tx.clone());
+
+        assert_eq!(cache.get(&txid(1)), None);
+        assert_eq!(cache.get(&txid(2)), Some(&tx));
+        assert_eq!(cache.get(&txid(3)), Some(&tx));
+    }
 }
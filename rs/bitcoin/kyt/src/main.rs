@@ -1,5 +1,10 @@
-use bitcoin::{Address, Network};
-use ic_btc_kyt::{blocklist_contains, get_inputs_internal, CheckAddressArgs, CheckAddressResponse};
+use bitcoin::{Address, Network, Txid};
+use ic_btc_kyt::{
+    blocklist_contains, check_transaction_internal, get_inputs_internal, CheckAddressArgs,
+    CheckAddressResponse, CheckTransactionArgs, CheckTransactionResponse, CheckTransactionStatus,
+    CHECK_TRANSACTION_CYCLES_REQUIRED, CHECK_TRANSACTION_CYCLES_SERVICE_FEE,
+};
+use ic_cdk::api::call::{msg_cycles_accept128, msg_cycles_available128};
 use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
 use std::str::FromStr;
 
@@ -31,6 +36,26 @@ fn check_address(args: CheckAddressArgs) -> CheckAddressResponse {
     }
 }
 
+#[ic_cdk::update]
+/// Return `Passed` if none of the addresses whose funds are spent by the given
+/// transaction is in the blocklist, `Failed` with the blocklisted addresses
+/// otherwise, or `Unknown` if the check could not be completed.
+/// The caller has to attach at least `CHECK_TRANSACTION_CYCLES_REQUIRED` cycles.
+/// A service fee and the cycles spent on HTTPS outcalls are charged, the rest
+/// is refunded.
+/// May throw error (trap) if the given transaction ID is malformed.
+async fn check_transaction(args: CheckTransactionArgs) -> CheckTransactionResponse {
+    let txid = Txid::from_str(args.txid.trim())
+        .unwrap_or_else(|err| ic_cdk::trap(&format!("Invalid transaction ID: {}", err)));
+
+    if msg_cycles_available128() < CHECK_TRANSACTION_CYCLES_REQUIRED {
+        return CheckTransactionResponse::Unknown(CheckTransactionStatus::NotEnoughCycles);
+    }
+    msg_cycles_accept128(CHECK_TRANSACTION_CYCLES_SERVICE_FEE);
+
+    check_transaction_internal(txid).await
+}
+
 #[ic_cdk::query(hidden = true)]
 fn transform(raw: TransformArgs) -> HttpResponse {
     HttpResponse {
@@ -0,0 +1,34 @@
+/// The Bitcoin explorer APIs the canister fetches raw transactions from.
+/// All of them implement the Esplora `/api/tx/{txid}/raw` endpoint.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Provider {
+    Btcscan,
+    Blockstream,
+    MempoolSpace,
+}
+
+impl Provider {
+    pub const ALL: &[Provider] = &[
+        Provider::Btcscan,
+        Provider::Blockstream,
+        Provider::MempoolSpace,
+    ];
+
+    pub fn host(&self) -> &'static str {
+        match self {
+            Provider::Btcscan => "btcscan.org",
+            Provider::Blockstream => "blockstream.info",
+            Provider::MempoolSpace => "mempool.space",
+        }
+    }
+
+    /// Returns all providers, starting with the one at the given offset, so that
+    /// consecutive requests spread their load over the providers.
+    pub fn rotated(offset: usize) -> impl Iterator<Item = Provider> {
+        let start = offset % Self::ALL.len();
+        Self::ALL[start..]
+            .iter()
+            .chain(Self::ALL[..start].iter())
+            .copied()
+    }
+}
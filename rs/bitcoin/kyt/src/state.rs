@@ -0,0 +1,93 @@
+use bitcoin::{Address, Network, OutPoint, Transaction, Txid};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+
+/// The maximum number of fetched transactions kept in the cache.
+pub const MAX_FETCHED_TX_ENTRIES: usize = 10_000;
+
+/// The parts of a fetched transaction that are needed to check other
+/// transactions spending its outputs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FetchedTx {
+    /// The outputs spent by the inputs of the transaction.
+    pub inputs: Vec<OutPoint>,
+    /// The address of each output, or `None` if the output script does not
+    /// correspond to an address (e.g. P2PK or OP_RETURN outputs).
+    pub output_addresses: Vec<Option<Address>>,
+}
+
+impl FetchedTx {
+    pub fn new(tx: &Transaction) -> Self {
+        let inputs = if tx.is_coinbase() {
+            // The input of a coinbase transaction does not spend any output.
+            vec![]
+        } else {
+            tx.input.iter().map(|input| input.previous_output).collect()
+        };
+        Self {
+            inputs,
+            output_addresses: tx
+                .output
+                .iter()
+                .map(|output| Address::from_script(&output.script_pubkey, Network::Bitcoin).ok())
+                .collect(),
+        }
+    }
+}
+
+/// A bounded cache of fetched transactions. The oldest entry is evicted first.
+pub struct FetchedTxCache {
+    entries: BTreeMap<Txid, FetchedTx>,
+    insertion_order: VecDeque<Txid>,
+    capacity: usize,
+}
+
+impl FetchedTxCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            insertion_order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn get(&self, txid: &Txid) -> Option<&FetchedTx> {
+        self.entries.get(txid)
+    }
+
+    pub fn insert(&mut self, txid: Txid, tx: FetchedTx) {
+        if self.entries.insert(txid, tx).is_some() {
+            return;
+        }
+        self.insertion_order.push_back(txid);
+        while self.insertion_order.len() > self.capacity {
+            if let Some(oldest) = self.insertion_order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static FETCHED_TXS: RefCell<FetchedTxCache> =
+        RefCell::new(FetchedTxCache::new(MAX_FETCHED_TX_ENTRIES));
+    static PROVIDER_OFFSET: RefCell<usize> = const { RefCell::new(0) };
+}
+
+pub fn get_fetched_tx(txid: &Txid) -> Option<FetchedTx> {
+    FETCHED_TXS.with(|cache| cache.borrow().get(txid).cloned())
+}
+
+pub fn insert_fetched_tx(txid: Txid, tx: FetchedTx) {
+    FETCHED_TXS.with(|cache| cache.borrow_mut().insert(txid, tx))
+}
+
+/// Returns the offset of the provider to try first and advances it.
+pub fn next_provider_offset() -> usize {
+    PROVIDER_OFFSET.with(|offset| {
+        let mut offset = offset.borrow_mut();
+        let current = *offset;
+        *offset = offset.wrapping_add(1);
+        current
+    })
+}
@@ -12,3 +12,30 @@ pub enum CheckAddressResponse {
     Passed,
     Failed,
 }
+
+#[derive(CandidType, Debug, Deserialize, Serialize)]
+pub struct CheckTransactionArgs {
+    /// The ID of the Bitcoin transaction to be checked.
+    pub txid: String,
+}
+
+#[derive(CandidType, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum CheckTransactionResponse {
+    /// None of the input addresses of the transaction is in the blocklist.
+    Passed,
+    /// The transaction spends funds from the returned blocklisted addresses.
+    Failed(Vec<String>),
+    /// The check could not be completed.
+    Unknown(CheckTransactionStatus),
+}
+
+#[derive(CandidType, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum CheckTransactionStatus {
+    /// Not enough cycles were attached to fetch all the required transactions.
+    /// The transactions fetched so far are cached, so the call can be retried.
+    NotEnoughCycles,
+    /// None of the providers returned the transaction, the call can be retried.
+    Retriable(String),
+    /// The transaction or one of its inputs could not be processed.
+    Error(String),
+}
@@ -5,7 +5,6 @@
 //! only for serialization/deserialization of the ReplicatedState.
 
 use candid::CandidType;
-use ic_btc_interface::Network;
 use ic_error_types::RejectCode;
 use ic_protobuf::{
     bitcoin::v1,
@@ -16,6 +15,74 @@ use serde::{Deserialize, Serialize};
 use std::convert::{TryFrom, TryInto};
 use std::mem::size_of_val;
 
+/// The network an adapter request is for.
+///
+/// The Bitcoin variants are encoded the same way as `ic_btc_interface::Network`.
+///
+/// ```text
+/// variant {
+///   mainnet;
+///   testnet;
+///   regtest;
+///   dogecoin_mainnet;
+///   dogecoin_testnet;
+///   dogecoin_regtest;
+/// };
+/// ```
+#[derive(CandidType, Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Network {
+    #[serde(rename = "mainnet")]
+    Mainnet,
+    #[serde(rename = "testnet")]
+    Testnet,
+    #[serde(rename = "regtest")]
+    Regtest,
+    #[serde(rename = "dogecoin_mainnet")]
+    DogecoinMainnet,
+    #[serde(rename = "dogecoin_testnet")]
+    DogecoinTestnet,
+    #[serde(rename = "dogecoin_regtest")]
+    DogecoinRegtest,
+}
+
+impl From<ic_btc_interface::Network> for Network {
+    fn from(network: ic_btc_interface::Network) -> Self {
+        match network {
+            ic_btc_interface::Network::Mainnet => Network::Mainnet,
+            ic_btc_interface::Network::Testnet => Network::Testnet,
+            ic_btc_interface::Network::Regtest => Network::Regtest,
+        }
+    }
+}
+
+impl From<Network> for i32 {
+    fn from(network: Network) -> Self {
+        let network = match network {
+            Network::Testnet => v1::Network::Testnet,
+            Network::Mainnet => v1::Network::Mainnet,
+            Network::Regtest => v1::Network::Regtest,
+            Network::DogecoinMainnet => v1::Network::DogecoinMainnet,
+            Network::DogecoinTestnet => v1::Network::DogecoinTestnet,
+            Network::DogecoinRegtest => v1::Network::DogecoinRegtest,
+        };
+        network as i32
+    }
+}
+
+impl Network {
+    fn try_from_proto(network: i32, field: &'static str) -> Result<Self, ProxyDecodeError> {
+        match v1::Network::try_from(network) {
+            Ok(v1::Network::Testnet) => Ok(Network::Testnet),
+            Ok(v1::Network::Mainnet) => Ok(Network::Mainnet),
+            Ok(v1::Network::Regtest) => Ok(Network::Regtest),
+            Ok(v1::Network::DogecoinMainnet) => Ok(Network::DogecoinMainnet),
+            Ok(v1::Network::DogecoinTestnet) => Ok(Network::DogecoinTestnet),
+            Ok(v1::Network::DogecoinRegtest) => Ok(Network::DogecoinRegtest),
+            Ok(v1::Network::Unspecified) | Err(_) => Err(ProxyDecodeError::MissingField(field)),
+        }
+    }
+}
+
 #[derive(CandidType, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SendTransactionRequest {
     pub network: Network,
@@ -26,11 +93,7 @@ pub struct SendTransactionRequest {
 impl From<&SendTransactionRequest> for v1::SendTransactionRequest {
     fn from(request: &SendTransactionRequest) -> Self {
         Self {
-            network: match request.network {
-                Network::Testnet => 1,
-                Network::Mainnet => 2,
-                Network::Regtest => 3,
-            },
+            network: request.network.into(),
             transaction: request.transaction.clone(),
         }
     }
@@ -40,16 +103,7 @@ impl TryFrom<v1::SendTransactionRequest> for SendTransactionRequest {
     type Error = ProxyDecodeError;
     fn try_from(request: v1::SendTransactionRequest) -> Result<Self, Self::Error> {
         Ok(SendTransactionRequest {
-            network: match request.network {
-                1 => Network::Testnet,
-                2 => Network::Mainnet,
-                3 => Network::Regtest,
-                _ => {
-                    return Err(ProxyDecodeError::MissingField(
-                        "SendTransactionRequest::network",
-                    ))
-                }
-            },
+            network: Network::try_from_proto(request.network, "SendTransactionRequest::network")?,
             transaction: request.transaction,
         })
     }
@@ -645,11 +699,7 @@ pub struct GetSuccessorsRequestInitial {
 impl From<&GetSuccessorsRequestInitial> for v1::GetSuccessorsRequestInitial {
     fn from(request: &GetSuccessorsRequestInitial) -> Self {
         Self {
-            network: match request.network {
-                Network::Testnet => 1,
-                Network::Mainnet => 2,
-                Network::Regtest => 3,
-            },
+            network: request.network.into(),
             anchor: request.anchor.clone(),
             processed_block_hashes: request.processed_block_hashes.clone(),
         }
@@ -660,16 +710,10 @@ impl TryFrom<v1::GetSuccessorsRequestInitial> for GetSuccessorsRequestInitial {
     type Error = ProxyDecodeError;
     fn try_from(request: v1::GetSuccessorsRequestInitial) -> Result<Self, Self::Error> {
         Ok(GetSuccessorsRequestInitial {
-            network: match request.network {
-                1 => Network::Testnet,
-                2 => Network::Mainnet,
-                3 => Network::Regtest,
-                _ => {
-                    return Err(ProxyDecodeError::MissingField(
-                        "GetSuccessorsRequestInitial::network",
-                    ))
-                }
-            },
+            network: Network::try_from_proto(
+                request.network,
+                "GetSuccessorsRequestInitial::network",
+            )?,
             anchor: request.anchor,
             processed_block_hashes: request.processed_block_hashes,
         })
@@ -786,4 +830,48 @@ mod test {
             12
         );
     }
+
+    #[test]
+    fn network_proto_round_trip() {
+        for network in [
+            Network::Mainnet,
+            Network::Testnet,
+            Network::Regtest,
+            Network::DogecoinMainnet,
+            Network::DogecoinTestnet,
+            Network::DogecoinRegtest,
+        ] {
+            let request = GetSuccessorsRequestInitial {
+                network,
+                anchor: vec![],
+                processed_block_hashes: vec![],
+            };
+            let proto = v1::GetSuccessorsRequestInitial::from(&request);
+            assert_eq!(
+                GetSuccessorsRequestInitial::try_from(proto).unwrap(),
+                request
+            );
+        }
+
+        let proto = v1::SendTransactionRequest {
+            network: v1::Network::Unspecified as i32,
+            transaction: vec![],
+        };
+        assert!(SendTransactionRequest::try_from(proto).is_err());
+    }
+
+    #[test]
+    fn bitcoin_networks_keep_their_candid_encoding() {
+        for network in [
+            ic_btc_interface::Network::Mainnet,
+            ic_btc_interface::Network::Testnet,
+            ic_btc_interface::Network::Regtest,
+        ] {
+            let encoded = candid::Encode!(&network).unwrap();
+            assert_eq!(
+                candid::Decode!(&encoded, Network).unwrap(),
+                Network::from(network)
+            );
+        }
+    }
 }
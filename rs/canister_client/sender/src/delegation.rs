@@ -0,0 +1,332 @@
+//! Delegation chains, as used to sign requests on behalf of a principal
+//! authenticated with e.g. Internet Identity.
+//!
+//! A delegation chain starts with the public key the principal of the sender
+//! is derived from. Each delegation of the chain is signed by the key of the
+//! previous one (or the start key for the first one) and delegates to a new key
+//! until an expiration time, optionally only for calls to a set of target
+//! canisters. Requests are signed with the session key the last delegation
+//! delegates to.
+
+use ic_base_types::{CanisterId, PrincipalId};
+use ic_crypto_standalone_sig_verifier::{
+    user_public_key_from_bytes, verify_basic_sig_by_public_key, verify_canister_sig,
+    KeyBytesContentType,
+};
+use ic_types::{
+    crypto::{threshold_sig::IcRootOfTrust, Signable},
+    messages::{Delegation, SignedDelegation},
+    time::Time,
+};
+use serde::Deserialize;
+use std::{collections::BTreeSet, error::Error, fmt};
+
+/// The maximum number of delegations of a chain accepted by the IC.
+pub const MAXIMUM_NUMBER_OF_DELEGATIONS: usize = 20;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DelegationError {
+    EmptyChain,
+    ChainTooLong {
+        length: usize,
+        maximum: usize,
+    },
+    /// The last delegation doesn't delegate to the session key.
+    SessionKeyMismatch,
+    Expired {
+        index: usize,
+        expiration: Time,
+        now: Time,
+    },
+    CanisterNotInTargets(CanisterId),
+    InvalidTargets {
+        index: usize,
+        error: String,
+    },
+    InvalidSignature {
+        index: usize,
+        error: String,
+    },
+    MalformedJson(String),
+}
+
+impl fmt::Display for DelegationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptyChain => write!(f, "The chain of delegations is empty"),
+            Self::ChainTooLong { length, maximum } => write!(
+                f,
+                "The chain of delegations is too long: got {} delegations, but at most {} are allowed",
+                length, maximum
+            ),
+            Self::SessionKeyMismatch => write!(
+                f,
+                "The last delegation of the chain doesn't delegate to the session key"
+            ),
+            Self::Expired {
+                index,
+                expiration,
+                now,
+            } => write!(
+                f,
+                "Delegation {} expired at {}, current time is {}",
+                index, expiration, now
+            ),
+            Self::CanisterNotInTargets(canister_id) => write!(
+                f,
+                "Canister {} is not a target of the chain of delegations",
+                canister_id
+            ),
+            Self::InvalidTargets { index, error } => {
+                write!(f, "Invalid targets of delegation {}: {}", index, error)
+            }
+            Self::InvalidSignature { index, error } => {
+                write!(f, "Invalid signature of delegation {}: {}", index, error)
+            }
+            Self::MalformedJson(error) => {
+                write!(f, "Malformed chain of delegations: {}", error)
+            }
+        }
+    }
+}
+
+impl Error for DelegationError {}
+
+/// A chain of signed delegations from the key the principal of the sender is
+/// derived from to a session key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DelegationChain {
+    /// DER encoded public key at the start of the chain.
+    public_key: Vec<u8>,
+    delegations: Vec<SignedDelegation>,
+}
+
+impl DelegationChain {
+    pub fn new(
+        public_key: Vec<u8>,
+        delegations: Vec<SignedDelegation>,
+    ) -> Result<Self, DelegationError> {
+        if delegations.is_empty() {
+            return Err(DelegationError::EmptyChain);
+        }
+        if delegations.len() > MAXIMUM_NUMBER_OF_DELEGATIONS {
+            return Err(DelegationError::ChainTooLong {
+                length: delegations.len(),
+                maximum: MAXIMUM_NUMBER_OF_DELEGATIONS,
+            });
+        }
+        Ok(Self {
+            public_key,
+            delegations,
+        })
+    }
+
+    /// Parses a chain of delegations in the JSON format produced by
+    /// `DelegationChain.toJSON()` of the JavaScript agent, as e.g. stored by
+    /// Internet Identity clients. Binary values are hex encoded, and the
+    /// expiration is the hex encoded number of nanoseconds since the Unix
+    /// epoch.
+    pub fn from_json(json: &str) -> Result<Self, DelegationError> {
+        let chain: JsonDelegationChain = serde_json::from_str(json)
+            .map_err(|err| DelegationError::MalformedJson(err.to_string()))?;
+        let delegations = chain
+            .delegations
+            .into_iter()
+            .map(JsonSignedDelegation::try_into_signed_delegation)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(DelegationError::MalformedJson)?;
+        Self::new(
+            decode_hex(&chain.public_key).map_err(DelegationError::MalformedJson)?,
+            delegations,
+        )
+    }
+
+    /// The DER encoded public key at the start of the chain.
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    pub fn delegations(&self) -> &[SignedDelegation] {
+        &self.delegations
+    }
+
+    /// The principal requests signed with the chain are sent on behalf of.
+    pub fn principal(&self) -> PrincipalId {
+        PrincipalId::new_self_authenticating(&self.public_key)
+    }
+
+    /// The DER encoded public key the chain delegates to.
+    pub fn session_public_key(&self) -> &[u8] {
+        self.delegations
+            .last()
+            .expect("The chain is not empty")
+            .delegation()
+            .pubkey()
+    }
+
+    /// The earliest expiration of the delegations of the chain.
+    pub fn expiration(&self) -> Time {
+        self.delegations
+            .iter()
+            .map(|signed| signed.delegation().expiration())
+            .min()
+            .expect("The chain is not empty")
+    }
+
+    /// The canisters that can be called with the chain, i.e. the intersection
+    /// of the targets of all delegations. `None` if no delegation restricts
+    /// the targets.
+    pub fn targets(&self) -> Result<Option<BTreeSet<CanisterId>>, DelegationError> {
+        let mut targets: Option<BTreeSet<CanisterId>> = None;
+        for (index, signed) in self.delegations.iter().enumerate() {
+            let delegation_targets = signed
+                .delegation()
+                .targets()
+                .map_err(|error| DelegationError::InvalidTargets { index, error })?;
+            targets = match (targets, delegation_targets) {
+                (None, other) | (other, None) => other,
+                (Some(targets), Some(other)) => {
+                    Some(targets.intersection(&other).cloned().collect())
+                }
+            };
+        }
+        Ok(targets)
+    }
+
+    /// Checks that no delegation of the chain expired at `now` and, if
+    /// given, that `canister_id` is a target of all delegations, the same way
+    /// the IC does when validating a request.
+    pub fn check(
+        &self,
+        canister_id: Option<&CanisterId>,
+        now: Time,
+    ) -> Result<(), DelegationError> {
+        for (index, signed) in self.delegations.iter().enumerate() {
+            let expiration = signed.delegation().expiration();
+            if expiration < now {
+                return Err(DelegationError::Expired {
+                    index,
+                    expiration,
+                    now,
+                });
+            }
+        }
+        if let Some(canister_id) = canister_id {
+            if let Some(targets) = self.targets()? {
+                if !targets.contains(canister_id) {
+                    return Err(DelegationError::CanisterNotInTargets(*canister_id));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Verifies the signature of each delegation of the chain with the key of
+    /// the previous one. The root of trust is needed to verify canister
+    /// signatures, e.g. the signature of Internet Identity on the first
+    /// delegation.
+    pub fn verify_signatures(
+        &self,
+        root_of_trust: Option<&IcRootOfTrust>,
+    ) -> Result<(), DelegationError> {
+        let mut signer_key = self.public_key.as_slice();
+        for (index, signed) in self.delegations.iter().enumerate() {
+            verify_delegation_signature(
+                signed.delegation(),
+                &signed.signature().0,
+                signer_key,
+                root_of_trust,
+            )
+            .map_err(|error| DelegationError::InvalidSignature { index, error })?;
+            signer_key = signed.delegation().pubkey();
+        }
+        Ok(())
+    }
+}
+
+fn verify_delegation_signature(
+    delegation: &Delegation,
+    signature: &[u8],
+    public_key_der: &[u8],
+    root_of_trust: Option<&IcRootOfTrust>,
+) -> Result<(), String> {
+    let (public_key, content_type) =
+        user_public_key_from_bytes(public_key_der).map_err(|err| err.to_string())?;
+    let message = delegation.as_signed_bytes();
+    match content_type {
+        KeyBytesContentType::Ed25519PublicKeyDer
+        | KeyBytesContentType::EcdsaP256PublicKeyDer
+        | KeyBytesContentType::EcdsaSecp256k1PublicKeyDer
+        | KeyBytesContentType::RsaSha256PublicKeyDer => verify_basic_sig_by_public_key(
+            public_key.algorithm_id,
+            &message,
+            signature,
+            &public_key.key,
+        )
+        .map_err(|err| err.to_string()),
+        KeyBytesContentType::IcCanisterSignatureAlgPublicKeyDer => {
+            let root_of_trust =
+                root_of_trust.ok_or("A root of trust is needed to verify canister signatures")?;
+            verify_canister_sig(&message, signature, &public_key.key, root_of_trust)
+                .map_err(|err| err.to_string())
+        }
+        KeyBytesContentType::EcdsaP256PublicKeyDerWrappedCose
+        | KeyBytesContentType::RsaSha256PublicKeyDerWrappedCose => {
+            Err("WebAuthn signatures can't be verified locally".to_string())
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonDelegationChain {
+    delegations: Vec<JsonSignedDelegation>,
+    public_key: String,
+}
+
+#[derive(Deserialize)]
+struct JsonSignedDelegation {
+    delegation: JsonDelegation,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct JsonDelegation {
+    pubkey: String,
+    expiration: String,
+    #[serde(default)]
+    targets: Option<Vec<String>>,
+}
+
+impl JsonSignedDelegation {
+    fn try_into_signed_delegation(self) -> Result<SignedDelegation, String> {
+        let pubkey = decode_hex(&self.delegation.pubkey)?;
+        let expiration = u64::from_str_radix(&self.delegation.expiration, 16)
+            .map(Time::from_nanos_since_unix_epoch)
+            .map_err(|err| format!("Invalid expiration {}: {}", self.delegation.expiration, err))?;
+        let delegation = match self.delegation.targets {
+            None => Delegation::new(pubkey, expiration),
+            Some(targets) => Delegation::new_with_targets(
+                pubkey,
+                expiration,
+                targets
+                    .iter()
+                    .map(|target| {
+                        let bytes = decode_hex(target)?;
+                        PrincipalId::try_from(bytes.as_slice())
+                            .map(CanisterId::unchecked_from_principal)
+                            .map_err(|err| format!("Invalid target {}: {}", target, err))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+        };
+        Ok(SignedDelegation::new(
+            delegation,
+            decode_hex(&self.signature)?,
+        ))
+    }
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>, String> {
+    hex::decode(value).map_err(|err| format!("Invalid hex value {}: {}", value, err))
+}
@@ -1,10 +1,14 @@
+mod delegation;
 mod secp256k1_conversions;
 #[cfg(test)]
 mod tests;
 
-use ic_base_types::PrincipalId;
+pub use delegation::{DelegationChain, DelegationError, MAXIMUM_NUMBER_OF_DELEGATIONS};
+
+use ic_base_types::{CanisterId, PrincipalId};
 use ic_types::crypto::DOMAIN_IC_REQUEST;
-use ic_types::messages::MessageId;
+use ic_types::messages::{MessageId, SignedDelegation};
+use ic_types::time::Time;
 use rand::{CryptoRng, Rng, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 use std::{error::Error, sync::Arc};
@@ -92,6 +96,21 @@ impl SigKeys {
             Err("unsupported or malformed secret key pem")
         }
     }
+
+    /// The DER encoded public key.
+    pub fn public_key_der(&self) -> Vec<u8> {
+        match self {
+            SigKeys::Ed25519(key_pair) => ed25519_public_key_to_der(key_pair.public_key.to_vec()),
+            SigKeys::EcdsaSecp256k1(key_pair) => key_pair.pk.serialize_der(),
+        }
+    }
+
+    pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        match self {
+            SigKeys::Ed25519(key_pair) => key_pair.sign(msg).to_vec(),
+            SigKeys::EcdsaSecp256k1(key_pair) => key_pair.sign(msg),
+        }
+    }
 }
 
 /// Represents the identity of the sender.
@@ -121,6 +140,13 @@ pub enum Sender {
         /// Function that signs the message id
         sign: SignMessageId,
     },
+    /// The sender is the principal at the start of a chain of delegations,
+    /// e.g. an Internet Identity user, and requests are signed with the
+    /// session key the chain delegates to.
+    Delegated {
+        session_key: SigKeys,
+        chain: DelegationChain,
+    },
 }
 
 impl Sender {
@@ -152,6 +178,19 @@ impl Sender {
         Sender::PrincipalId(principal_id)
     }
 
+    /// Creates a sender signing with `session_key` on behalf of the principal
+    /// `chain` starts with. Fails if the chain doesn't delegate to the session
+    /// key.
+    pub fn from_delegation_chain(
+        session_key: SigKeys,
+        chain: DelegationChain,
+    ) -> Result<Self, DelegationError> {
+        if chain.session_public_key() != session_key.public_key_der().as_slice() {
+            return Err(DelegationError::SessionKeyMismatch);
+        }
+        Ok(Sender::Delegated { session_key, chain })
+    }
+
     pub fn get_principal_id(&self) -> PrincipalId {
         match self {
             Self::SigKeys(sig_keys) => match sig_keys {
@@ -168,6 +207,7 @@ impl Sender {
             Self::Node { pub_key, .. } => {
                 PrincipalId::new_self_authenticating(&ed25519_public_key_to_der(pub_key.clone()))
             }
+            Self::Delegated { chain, .. } => chain.principal(),
         }
     }
 
@@ -187,31 +227,45 @@ impl Sender {
         msg.extend_from_slice(DOMAIN_IC_REQUEST);
         msg.extend_from_slice(raw_msg);
         match self {
-            Self::SigKeys(sig_keys) => match sig_keys {
-                SigKeys::Ed25519(key_pair) => Ok(Some(key_pair.sign(&msg).to_vec())),
-                SigKeys::EcdsaSecp256k1(key_pair) => {
-                    Ok(Some(key_pair.sk.sign_message_with_ecdsa(&msg).to_vec()))
-                }
-            },
+            Self::SigKeys(sig_keys) => Ok(Some(sig_keys.sign(&msg))),
             Self::ExternalHsm { sign, .. } => sign(&msg).map(Some),
             Self::Anonymous => Ok(None),
             Self::PrincipalId(_) => Ok(None),
             Self::Node { .. } => unreachable!("Wrong case of agent.sign()"),
+            Self::Delegated { session_key, .. } => Ok(Some(session_key.sign(&msg))),
         }
     }
 
     pub fn sender_pubkey_der(&self) -> Option<Vec<u8>> {
         match self {
-            Self::SigKeys(sig_keys) => match sig_keys {
-                SigKeys::Ed25519(key_pair) => {
-                    Some(ed25519_public_key_to_der(key_pair.public_key.to_vec()))
-                }
-                SigKeys::EcdsaSecp256k1(key_pair) => Some(key_pair.pk.serialize_der()),
-            },
+            Self::SigKeys(sig_keys) => Some(sig_keys.public_key_der()),
             Self::ExternalHsm { pub_key, .. } => Some(pub_key.clone()),
             Self::Anonymous => None,
             Self::PrincipalId(_) => None,
             Self::Node { pub_key, .. } => Some(ed25519_public_key_to_der(pub_key.clone())),
+            Self::Delegated { chain, .. } => Some(chain.public_key().to_vec()),
+        }
+    }
+
+    /// The delegations to put in the envelope of requests.
+    pub fn sender_delegation(&self) -> Option<Vec<SignedDelegation>> {
+        match self {
+            Self::Delegated { chain, .. } => Some(chain.delegations().to_vec()),
+            _ => None,
+        }
+    }
+
+    /// Checks that the delegations of the sender, if any, allow sending a
+    /// request to `canister_id` at time `now`, so that requests which the IC
+    /// would reject are not sent.
+    pub fn check_delegation(
+        &self,
+        canister_id: Option<&CanisterId>,
+        now: Time,
+    ) -> Result<(), DelegationError> {
+        match self {
+            Self::Delegated { chain, .. } => chain.check(canister_id, now),
+            _ => Ok(()),
         }
     }
 }
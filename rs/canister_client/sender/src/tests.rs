@@ -64,3 +64,183 @@ fn should_fail_to_read_key_for_wrong_curve() {
         .map(|_| ())
         .expect_err("The base64 payload should be a secp256k1 key");
 }
+
+mod delegation {
+    use crate::{
+        ed25519_public_key_to_der, DelegationChain, DelegationError, Ed25519KeyPair, Sender,
+        SigKeys,
+    };
+    use ic_base_types::CanisterId;
+    use ic_types::{
+        crypto::Signable,
+        messages::{Delegation, SignedDelegation},
+        time::Time,
+    };
+    use rand::SeedableRng;
+    use rand_chacha::ChaChaRng;
+
+    const NOW: Time = Time::from_nanos_since_unix_epoch(1_700_000_000_000_000_000);
+    const LATER: Time = Time::from_nanos_since_unix_epoch(1_700_000_100_000_000_000);
+
+    fn key_pair(seed: u64) -> Ed25519KeyPair {
+        Ed25519KeyPair::generate(&mut ChaChaRng::seed_from_u64(seed))
+    }
+
+    fn der(key_pair: &Ed25519KeyPair) -> Vec<u8> {
+        ed25519_public_key_to_der(key_pair.public_key.to_vec())
+    }
+
+    fn sign(signer: &Ed25519KeyPair, delegation: Delegation) -> SignedDelegation {
+        let signature = signer.sign(&delegation.as_signed_bytes()).to_vec();
+        SignedDelegation::new(delegation, signature)
+    }
+
+    /// A chain identity -> intermediate -> session, where only the first
+    /// delegation restricts the targets.
+    fn chain(identity: &Ed25519KeyPair, session: &Ed25519KeyPair) -> DelegationChain {
+        let intermediate = key_pair(2);
+        DelegationChain::new(
+            der(identity),
+            vec![
+                sign(
+                    identity,
+                    Delegation::new_with_targets(
+                        der(&intermediate),
+                        LATER,
+                        vec![CanisterId::from_u64(1), CanisterId::from_u64(2)],
+                    ),
+                ),
+                sign(&intermediate, Delegation::new(der(session), LATER)),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn should_sign_on_behalf_of_the_start_of_the_chain() {
+        let identity = key_pair(1);
+        let session = key_pair(3);
+        let chain = chain(&identity, &session);
+        chain.verify_signatures(None).unwrap();
+
+        let sender =
+            Sender::from_delegation_chain(SigKeys::Ed25519(session), chain.clone()).unwrap();
+        assert_eq!(
+            sender.get_principal_id(),
+            Sender::from_keypair(&identity).get_principal_id()
+        );
+        assert_eq!(sender.sender_pubkey_der(), Some(der(&identity)));
+        assert_eq!(
+            sender.sender_delegation(),
+            Some(chain.delegations().to_vec())
+        );
+        assert_eq!(Sender::from_keypair(&identity).sender_delegation(), None);
+    }
+
+    #[test]
+    fn should_reject_session_key_not_in_chain() {
+        let chain = chain(&key_pair(1), &key_pair(3));
+        assert_eq!(
+            Sender::from_delegation_chain(SigKeys::Ed25519(key_pair(4)), chain).err(),
+            Some(DelegationError::SessionKeyMismatch)
+        );
+    }
+
+    #[test]
+    fn should_enforce_expiry_and_targets() {
+        let sender = Sender::from_delegation_chain(
+            SigKeys::Ed25519(key_pair(3)),
+            chain(&key_pair(1), &key_pair(3)),
+        )
+        .unwrap();
+        assert_eq!(
+            sender.check_delegation(Some(&CanisterId::from_u64(2)), NOW),
+            Ok(())
+        );
+        assert_eq!(sender.check_delegation(None, NOW), Ok(()));
+        assert_eq!(
+            sender.check_delegation(Some(&CanisterId::from_u64(3)), NOW),
+            Err(DelegationError::CanisterNotInTargets(CanisterId::from_u64(
+                3
+            )))
+        );
+        let after_expiry = LATER + std::time::Duration::from_secs(1);
+        assert!(matches!(
+            sender.check_delegation(None, after_expiry),
+            Err(DelegationError::Expired { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn should_detect_invalid_signatures() {
+        let identity = key_pair(1);
+        let session = key_pair(3);
+        let mut delegations = chain(&identity, &session).delegations().to_vec();
+        // Sign the second delegation with the wrong key.
+        delegations[1] = sign(&key_pair(5), Delegation::new(der(&session), LATER));
+        let chain = DelegationChain::new(der(&identity), delegations).unwrap();
+        assert!(matches!(
+            chain.verify_signatures(None),
+            Err(DelegationError::InvalidSignature { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn should_reject_empty_and_too_long_chains() {
+        assert_eq!(
+            DelegationChain::new(vec![], vec![]).err(),
+            Some(DelegationError::EmptyChain)
+        );
+        let identity = key_pair(1);
+        let delegation = sign(&identity, Delegation::new(der(&identity), LATER));
+        assert!(matches!(
+            DelegationChain::new(der(&identity), vec![delegation; 21]),
+            Err(DelegationError::ChainTooLong { length: 21, .. })
+        ));
+    }
+
+    #[test]
+    fn should_parse_json_chain() {
+        let identity = key_pair(1);
+        let session = key_pair(3);
+        let chain = chain(&identity, &session);
+        let delegations: Vec<String> = chain
+            .delegations()
+            .iter()
+            .map(|signed| {
+                let delegation = signed.delegation();
+                let targets = match delegation.targets().unwrap() {
+                    None => String::new(),
+                    Some(targets) => format!(
+                        r#", "targets": [{}]"#,
+                        targets
+                            .iter()
+                            .map(|target| format!("\"{}\"", hex::encode(target.get().as_slice())))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                };
+                format!(
+                    r#"{{"delegation": {{"pubkey": "{}", "expiration": "{:x}"{}}}, "signature": "{}"}}"#,
+                    hex::encode(delegation.pubkey()),
+                    delegation.expiration().as_nanos_since_unix_epoch(),
+                    targets,
+                    hex::encode(&signed.signature().0)
+                )
+            })
+            .collect();
+        let json = format!(
+            r#"{{"delegations": [{}], "publicKey": "{}"}}"#,
+            delegations.join(", "),
+            hex::encode(der(&identity))
+        );
+
+        let parsed = DelegationChain::from_json(&json).unwrap();
+        assert_eq!(parsed, chain);
+        parsed.verify_signatures(None).unwrap();
+        assert!(matches!(
+            DelegationChain::from_json("{}"),
+            Err(DelegationError::MalformedJson(_))
+        ));
+    }
+}
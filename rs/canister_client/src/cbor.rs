@@ -8,7 +8,7 @@ use ic_types::{
         HttpReadStateContent, HttpReadStateResponse, HttpRequestEnvelope, HttpUserQuery, MessageId,
         SignedRequestBytes,
     },
-    time::expiry_time_from_now,
+    time::{current_time, expiry_time_from_now},
     CanisterId, SubnetId, Time,
 };
 use serde::Deserialize;
@@ -198,6 +198,7 @@ pub fn prepare_update<S: ToString>(
     ingress_expiry: Time,
     sender_field: Blob,
 ) -> Result<(SignedRequestBytes, MessageId), Box<dyn Error>> {
+    sender.check_delegation(Some(canister_id), current_time())?;
     let content = HttpCallContent::Call {
         update: HttpCanisterUpdate {
             canister_id: to_blob(canister_id),
@@ -222,6 +223,7 @@ pub fn prepare_query(
     arguments: Vec<u8>,
     sender_field: Blob,
 ) -> Result<SignedRequestBytes, Box<dyn Error>> {
+    sender.check_delegation(Some(canister_id), current_time())?;
     let content = HttpQueryContent::Query {
         query: HttpUserQuery {
             canister_id: to_blob(canister_id),
@@ -243,6 +245,7 @@ pub fn prepare_read_state(
     paths: &[Path],
     sender_field: Blob,
 ) -> Result<SignedRequestBytes, Box<dyn Error>> {
+    sender.check_delegation(None, current_time())?;
     let content = HttpReadStateContent::ReadState {
         read_state: HttpReadState {
             sender: sender_field,
@@ -283,7 +286,7 @@ fn sign_submit(
         content,
         sender_pubkey: pub_key_der,
         sender_sig,
-        sender_delegation: None,
+        sender_delegation: sender.sender_delegation(),
     };
     Ok((envelope, message_id))
 }
@@ -305,7 +308,7 @@ fn sign_read_state(
         content,
         sender_pubkey: pub_key_der,
         sender_sig,
-        sender_delegation: None,
+        sender_delegation: sender.sender_delegation(),
     })
 }
 
@@ -326,21 +329,25 @@ fn sign_query(
         content,
         sender_pubkey: pub_key_der,
         sender_sig,
-        sender_delegation: None,
+        sender_delegation: sender.sender_delegation(),
     })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ic_canister_client_sender::{ed25519_public_key_to_der, Ed25519KeyPair};
+    use ic_canister_client_sender::{
+        ed25519_public_key_to_der, DelegationChain, Ed25519KeyPair, SigKeys,
+    };
     use ic_certification_test_utils::{CertificateBuilder, CertificateData};
     use ic_crypto_test_utils_root_of_trust::MockRootOfTrustProvider;
     use ic_crypto_tree_hash::{Digest, Label, MixedHashTree};
     use ic_test_utilities::crypto::temp_crypto_component_with_fake_registry;
     use ic_test_utilities_types::ids::node_test_id;
+    use ic_types::crypto::Signable;
     use ic_types::messages::{
-        HttpCanisterUpdate, HttpReadStateResponse, HttpRequest, HttpUserQuery, Query,
+        Delegation, HttpCanisterUpdate, HttpReadStateResponse, HttpRequest, HttpUserQuery, Query,
+        SignedDelegation,
     };
     use ic_types::time::current_time;
     use ic_types::{PrincipalId, UserId};
@@ -445,6 +452,86 @@ mod tests {
             .contains(&request.content().canister_id()));
     }
 
+    /// Returns a sender signing with a session key on behalf of a principal
+    /// that delegated to it for calls to `targets` until `expiration`.
+    fn delegated_sender(expiration: Time, targets: Vec<CanisterId>) -> Sender {
+        let mut rng = ChaChaRng::seed_from_u64(456_u64);
+        let identity = Ed25519KeyPair::generate(&mut rng);
+        let session_key = SigKeys::Ed25519(Ed25519KeyPair::generate(&mut rng));
+        let delegation =
+            Delegation::new_with_targets(session_key.public_key_der(), expiration, targets);
+        let signature = identity.sign(&delegation.as_signed_bytes()).to_vec();
+        let chain = DelegationChain::new(
+            ed25519_public_key_to_der(identity.public_key.to_vec()),
+            vec![SignedDelegation::new(delegation, signature)],
+        )
+        .unwrap();
+        Sender::from_delegation_chain(session_key, chain).unwrap()
+    }
+
+    /// Create an HttpRequest signed with a session key on behalf of the
+    /// principal at the start of a delegation chain and then verify that
+    /// `validate_message` manages to authenticate it.
+    #[test]
+    fn sign_and_verify_submit_content_with_delegation() {
+        let test_start_time = current_time();
+        let expiry_time = test_start_time + Duration::from_secs(4 * 60);
+        let canister_id =
+            CanisterId::unchecked_from_principal(PrincipalId::try_from(&[51][..]).unwrap());
+        let sender = delegated_sender(expiry_time, vec![canister_id]);
+        let content = HttpCallContent::Call {
+            update: HttpCanisterUpdate {
+                canister_id: Blob(vec![51]),
+                method_name: "foo".to_string(),
+                arg: Blob(vec![12, 13, 99]),
+                nonce: None,
+                sender: Blob(sender.get_principal_id().into_vec()),
+                ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+            },
+        };
+        let (submit, id) = sign_submit(content.clone(), &sender).unwrap();
+
+        // The envelope carries the delegations of the sender
+        assert_eq!(submit.sender_delegation, sender.sender_delegation());
+
+        // The message id matches one that can be reconstructed from the output
+        let request = HttpRequest::try_from(submit).unwrap();
+        assert_eq!(id, request.id());
+
+        // The envelope can be successfully authenticated
+        assert!(request_validator()
+            .validate_request(&request, test_start_time, &MockRootOfTrustProvider::new())
+            .unwrap()
+            .contains(&canister_id));
+    }
+
+    #[test]
+    fn prepare_update_checks_delegation_locally() {
+        let now = current_time();
+        let target = CanisterId::from_u64(1);
+        let sender = delegated_sender(now + Duration::from_secs(60), vec![target]);
+        let sender_field = Blob(sender.get_principal_id().into_vec());
+        let prepare = |sender: &Sender, canister_id: &CanisterId| {
+            prepare_update(
+                sender,
+                canister_id,
+                "foo",
+                vec![],
+                vec![],
+                expiry_time_from_now(),
+                sender_field.clone(),
+            )
+        };
+
+        assert_ok!(prepare(&sender, &target));
+        let err = prepare(&sender, &CanisterId::from_u64(2)).unwrap_err();
+        assert!(err.to_string().contains("is not a target"));
+
+        let expired = delegated_sender(now.saturating_sub(Duration::from_secs(60)), vec![target]);
+        let err = prepare(&expired, &target).unwrap_err();
+        assert!(err.to_string().contains("expired"));
+    }
+
     /// Create an HttpRequest with an explicit anonymous user and then
     /// verify that `validate_message` manages to authenticate it.
     #[test]
@@ -0,0 +1,490 @@
+//! Confinement of the canister sandbox process.
+//!
+//! After start-up the sandbox process only needs to exchange messages with
+//! the replica over its socket, map the memory file descriptors it receives
+//! and run Wasmtime. This module restricts the process to exactly that:
+//!
+//! - Landlock rules deny all filesystem access except reading a few
+//!   `/proc` and `/sys` entries used by the Rust standard library.
+//! - A seccomp-bpf filter allows only the system calls that the sandbox
+//!   server and Wasmtime make. Any other system call delivers `SIGSYS`,
+//!   whose handler reports the system call number on stderr and terminates
+//!   the process with `SIGSYS`. The launcher recognizes this exit and reports
+//!   it to the replica, which logs it and increments a critical error counter.
+//!
+//! The confinement applies to the calling thread and every thread it spawns
+//! afterwards, so it must be installed before any other thread is started.
+
+use std::{ffi::CString, io, os::unix::ffi::OsStrExt, path::Path};
+
+/// The outcome of applying the Landlock rules.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LandlockStatus {
+    /// The rules are enforced by the kernel with the given Landlock ABI version.
+    Enforced { abi_version: u32 },
+    /// The kernel does not support Landlock or it is disabled at boot.
+    NotSupported,
+}
+
+/// Paths that the sandbox may read after start-up. Paths that do not exist on
+/// the host are skipped.
+const READ_ONLY_PATHS: &[&str] = &[
+    // `std::thread::available_parallelism()` and `/proc/self/exe` for backtraces.
+    "/proc/self",
+    "/sys/fs/cgroup",
+    "/sys/devices/system/cpu",
+];
+
+/// Applies the Landlock rules and installs the seccomp filter for the calling
+/// process.
+///
+/// Returns an error if the seccomp filter cannot be installed. A kernel
+/// without Landlock support is not an error, since the seccomp filter alone
+/// already prevents the sandbox from opening new network connections or
+/// executing other programs.
+pub fn confine_canister_sandbox() -> io::Result<LandlockStatus> {
+    // Required by both Landlock and seccomp for unprivileged processes.
+    // SAFETY: `prctl` with `PR_SET_NO_NEW_PRIVS` does not access memory.
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let landlock_status = landlock::restrict_self(READ_ONLY_PATHS)?;
+    seccomp::install_sigsys_handler()?;
+    seccomp::install_filter(&seccomp::sandbox_filter())?;
+    Ok(landlock_status)
+}
+
+mod landlock {
+    use super::*;
+
+    const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+    const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+    const LANDLOCK_ACCESS_FS_EXECUTE: u64 = 1 << 0;
+    const LANDLOCK_ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+    const LANDLOCK_ACCESS_FS_READ_FILE: u64 = 1 << 2;
+    const LANDLOCK_ACCESS_FS_READ_DIR: u64 = 1 << 3;
+    const LANDLOCK_ACCESS_FS_REMOVE_DIR: u64 = 1 << 4;
+    const LANDLOCK_ACCESS_FS_REMOVE_FILE: u64 = 1 << 5;
+    const LANDLOCK_ACCESS_FS_MAKE_CHAR: u64 = 1 << 6;
+    const LANDLOCK_ACCESS_FS_MAKE_DIR: u64 = 1 << 7;
+    const LANDLOCK_ACCESS_FS_MAKE_REG: u64 = 1 << 8;
+    const LANDLOCK_ACCESS_FS_MAKE_SOCK: u64 = 1 << 9;
+    const LANDLOCK_ACCESS_FS_MAKE_FIFO: u64 = 1 << 10;
+    const LANDLOCK_ACCESS_FS_MAKE_BLOCK: u64 = 1 << 11;
+    const LANDLOCK_ACCESS_FS_MAKE_SYM: u64 = 1 << 12;
+
+    /// All filesystem accesses known to Landlock ABI version 1, which every
+    /// kernel supporting Landlock understands.
+    const HANDLED_ACCESS_FS: u64 = LANDLOCK_ACCESS_FS_EXECUTE
+        | LANDLOCK_ACCESS_FS_WRITE_FILE
+        | LANDLOCK_ACCESS_FS_READ_FILE
+        | LANDLOCK_ACCESS_FS_READ_DIR
+        | LANDLOCK_ACCESS_FS_REMOVE_DIR
+        | LANDLOCK_ACCESS_FS_REMOVE_FILE
+        | LANDLOCK_ACCESS_FS_MAKE_CHAR
+        | LANDLOCK_ACCESS_FS_MAKE_DIR
+        | LANDLOCK_ACCESS_FS_MAKE_REG
+        | LANDLOCK_ACCESS_FS_MAKE_SOCK
+        | LANDLOCK_ACCESS_FS_MAKE_FIFO
+        | LANDLOCK_ACCESS_FS_MAKE_BLOCK
+        | LANDLOCK_ACCESS_FS_MAKE_SYM;
+
+    const READ_ACCESS_FS: u64 = LANDLOCK_ACCESS_FS_READ_FILE | LANDLOCK_ACCESS_FS_READ_DIR;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: libc::c_int,
+    }
+
+    /// Closes the wrapped file descriptor when dropped.
+    struct Fd(libc::c_int);
+
+    impl Drop for Fd {
+        fn drop(&mut self) {
+            // SAFETY: the file descriptor is owned by this struct.
+            unsafe { libc::close(self.0) };
+        }
+    }
+
+    fn abi_version() -> Option<u32> {
+        // SAFETY: querying the ABI version takes no attributes.
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        (version > 0).then_some(version as u32)
+    }
+
+    pub(super) fn restrict_self(read_only_paths: &[&str]) -> io::Result<LandlockStatus> {
+        let abi_version = match abi_version() {
+            Some(version) => version,
+            None => return Ok(LandlockStatus::NotSupported),
+        };
+
+        let attr = RulesetAttr {
+            handled_access_fs: HANDLED_ACCESS_FS,
+        };
+        // SAFETY: `attr` is a valid ruleset attribute of the given size.
+        let ruleset = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0,
+            )
+        };
+        if ruleset < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let ruleset = Fd(ruleset as libc::c_int);
+
+        for path in read_only_paths {
+            let path = Path::new(path);
+            if !path.exists() {
+                continue;
+            }
+            let c_path = CString::new(path.as_os_str().as_bytes())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            // SAFETY: `c_path` is a valid NUL-terminated string.
+            let parent_fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+            if parent_fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let parent_fd = Fd(parent_fd);
+            let rule = PathBeneathAttr {
+                allowed_access: READ_ACCESS_FS,
+                parent_fd: parent_fd.0,
+            };
+            // SAFETY: `rule` is a valid path-beneath attribute and `ruleset`
+            // is an open ruleset file descriptor.
+            let result = unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    ruleset.0,
+                    LANDLOCK_RULE_PATH_BENEATH,
+                    &rule as *const PathBeneathAttr,
+                    0,
+                )
+            };
+            if result != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        // SAFETY: `ruleset` is an open ruleset file descriptor.
+        if unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset.0, 0) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(LandlockStatus::Enforced { abi_version })
+    }
+}
+
+mod seccomp {
+    use super::*;
+
+    // Classic BPF instruction classes and fields, see `linux/bpf_common.h`.
+    const BPF_LD: u16 = 0x00;
+    const BPF_W: u16 = 0x00;
+    const BPF_ABS: u16 = 0x20;
+    const BPF_JMP: u16 = 0x05;
+    const BPF_JEQ: u16 = 0x10;
+    const BPF_JSET: u16 = 0x40;
+    const BPF_K: u16 = 0x00;
+    const BPF_RET: u16 = 0x06;
+
+    /// `AUDIT_ARCH_X86_64` from `linux/audit.h`.
+    const AUDIT_ARCH_X86_64: u32 = 0xc000_003e;
+
+    // Offsets of the fields of `struct seccomp_data`.
+    const SECCOMP_DATA_NR_OFFSET: u32 = 0;
+    const SECCOMP_DATA_ARCH_OFFSET: u32 = 4;
+    const SECCOMP_DATA_ARG0_LOW_OFFSET: u32 = 16;
+
+    /// Offset of `si_syscall` in the `siginfo_t` of a `SIGSYS` signal.
+    const SIGINFO_SYSCALL_OFFSET: usize = 24;
+
+    /// The system calls made by the sandbox server, its RPC threads and
+    /// Wasmtime. `clone` is handled separately to only allow new threads.
+    pub(super) const ALLOWED_SYSCALLS: &[libc::c_long] = &[
+        // Socket and file descriptor I/O.
+        libc::SYS_read,
+        libc::SYS_write,
+        libc::SYS_readv,
+        libc::SYS_writev,
+        libc::SYS_pread64,
+        libc::SYS_pwrite64,
+        libc::SYS_recvmsg,
+        libc::SYS_sendmsg,
+        libc::SYS_recvfrom,
+        libc::SYS_sendto,
+        libc::SYS_shutdown,
+        libc::SYS_poll,
+        libc::SYS_ppoll,
+        libc::SYS_close,
+        libc::SYS_dup,
+        libc::SYS_dup3,
+        libc::SYS_fcntl,
+        libc::SYS_fstat,
+        libc::SYS_newfstatat,
+        libc::SYS_statx,
+        libc::SYS_lseek,
+        libc::SYS_ftruncate,
+        libc::SYS_fallocate,
+        // Restricted to the Landlock read-only paths.
+        libc::SYS_openat,
+        libc::SYS_readlink,
+        libc::SYS_getdents64,
+        // Memory management of Wasm memories, page maps and the allocator.
+        libc::SYS_mmap,
+        libc::SYS_munmap,
+        libc::SYS_mremap,
+        libc::SYS_mprotect,
+        libc::SYS_madvise,
+        libc::SYS_msync,
+        libc::SYS_mincore,
+        libc::SYS_brk,
+        libc::SYS_memfd_create,
+        libc::SYS_membarrier,
+        // Signal handling of Wasmtime and the memory tracker.
+        libc::SYS_rt_sigaction,
+        libc::SYS_rt_sigprocmask,
+        libc::SYS_rt_sigreturn,
+        libc::SYS_sigaltstack,
+        libc::SYS_tgkill,
+        // Threads and synchronization.
+        libc::SYS_futex,
+        libc::SYS_set_robust_list,
+        libc::SYS_rseq,
+        libc::SYS_set_tid_address,
+        libc::SYS_sched_yield,
+        libc::SYS_sched_getaffinity,
+        libc::SYS_prctl,
+        libc::SYS_arch_prctl,
+        libc::SYS_getpid,
+        libc::SYS_gettid,
+        // Time and randomness.
+        libc::SYS_clock_gettime,
+        libc::SYS_clock_nanosleep,
+        libc::SYS_nanosleep,
+        libc::SYS_gettimeofday,
+        libc::SYS_getrandom,
+        // Resource limits and process exit.
+        libc::SYS_getrlimit,
+        libc::SYS_prlimit64,
+        libc::SYS_exit,
+        libc::SYS_exit_group,
+    ];
+
+    const fn stmt(code: u16, k: u32) -> libc::sock_filter {
+        libc::sock_filter {
+            code,
+            jt: 0,
+            jf: 0,
+            k,
+        }
+    }
+
+    const fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter { code, jt, jf, k }
+    }
+
+    /// Builds the seccomp filter of the canister sandbox.
+    pub(super) fn sandbox_filter() -> Vec<libc::sock_filter> {
+        let mut filter = vec![
+            // Kill the process if it makes a system call for another
+            // architecture, e.g. through the 32-bit `int 0x80` entry point.
+            stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_ARCH_OFFSET),
+            jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH_X86_64, 1, 0),
+            stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_KILL_PROCESS),
+            stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_NR_OFFSET),
+            // `clone3` passes its flags in memory, which the filter cannot
+            // inspect. Failing with `ENOSYS` makes glibc fall back to `clone`.
+            jump(BPF_JMP | BPF_JEQ | BPF_K, libc::SYS_clone3 as u32, 0, 1),
+            stmt(
+                BPF_RET | BPF_K,
+                libc::SECCOMP_RET_ERRNO | libc::ENOSYS as u32,
+            ),
+            // Only allow `clone` to create threads, not new processes.
+            jump(BPF_JMP | BPF_JEQ | BPF_K, libc::SYS_clone as u32, 0, 4),
+            stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_ARG0_LOW_OFFSET),
+            jump(BPF_JMP | BPF_JSET | BPF_K, libc::CLONE_THREAD as u32, 0, 1),
+            stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW),
+            stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_TRAP),
+        ];
+        for syscall in ALLOWED_SYSCALLS {
+            filter.push(jump(BPF_JMP | BPF_JEQ | BPF_K, *syscall as u32, 0, 1));
+            filter.push(stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_ALLOW));
+        }
+        filter.push(stmt(BPF_RET | BPF_K, libc::SECCOMP_RET_TRAP));
+        filter
+    }
+
+    pub(super) fn install_filter(filter: &[libc::sock_filter]) -> io::Result<()> {
+        let program = libc::sock_fprog {
+            len: filter.len() as libc::c_ushort,
+            filter: filter.as_ptr() as *mut libc::sock_filter,
+        };
+        // SAFETY: `program` points to `filter`, which outlives the call. The
+        // kernel copies the program.
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER,
+                0,
+                &program as *const libc::sock_fprog,
+            )
+        };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Writes `bytes` to stderr. Safe to call from a signal handler.
+    fn write_stderr(bytes: &[u8]) {
+        // SAFETY: `bytes` is a valid buffer of the given length.
+        unsafe {
+            libc::write(
+                libc::STDERR_FILENO,
+                bytes.as_ptr() as *const libc::c_void,
+                bytes.len(),
+            )
+        };
+    }
+
+    extern "C" fn handle_sigsys(
+        _signal: libc::c_int,
+        info: *mut libc::siginfo_t,
+        _context: *mut libc::c_void,
+    ) {
+        // SAFETY: the kernel passes a valid `siginfo_t` of a `SIGSYS` signal,
+        // which stores the number of the blocked system call at this offset.
+        let syscall =
+            unsafe { *((info as *const u8).add(SIGINFO_SYSCALL_OFFSET) as *const libc::c_int) };
+
+        // Format the number without allocating.
+        let mut digits = [0u8; 10];
+        let mut start = digits.len();
+        let mut n = syscall.unsigned_abs();
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (n % 10) as u8;
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        write_stderr(b"Canister sandbox violated its seccomp policy with system call ");
+        write_stderr(&digits[start..]);
+        write_stderr(b"\n");
+
+        // The handler was reset to the default action on entry, so this
+        // terminates the process with `SIGSYS`.
+        // SAFETY: `raise` is async-signal-safe.
+        unsafe { libc::raise(libc::SIGSYS) };
+    }
+
+    pub(super) fn install_sigsys_handler() -> io::Result<()> {
+        // SAFETY: an all-zero `sigaction` is valid and is fully initialized
+        // below.
+        let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+        action.sa_sigaction = handle_sigsys as usize;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_RESETHAND | libc::SA_NODEFER;
+        // SAFETY: `action` is a valid `sigaction` and the handler only calls
+        // async-signal-safe functions.
+        if unsafe { libc::sigaction(libc::SIGSYS, &action, std::ptr::null_mut()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nix::{
+        sys::{
+            signal::Signal,
+            wait::{waitpid, WaitStatus},
+        },
+        unistd::Pid,
+    };
+
+    #[test]
+    fn filter_fits_into_a_bpf_program() {
+        // The kernel rejects programs with more than `BPF_MAXINSNS` (4096)
+        // instructions.
+        let filter = seccomp::sandbox_filter();
+        assert!(filter.len() < 4096);
+        assert_eq!(
+            filter.last().unwrap().k,
+            libc::SECCOMP_RET_TRAP,
+            "system calls that are not allowed must trap"
+        );
+    }
+
+    #[test]
+    fn allowed_syscalls_exclude_process_and_network_creation() {
+        for syscall in [
+            libc::SYS_execve,
+            libc::SYS_execveat,
+            libc::SYS_fork,
+            libc::SYS_vfork,
+            libc::SYS_clone,
+            libc::SYS_clone3,
+            libc::SYS_socket,
+            libc::SYS_connect,
+            libc::SYS_ptrace,
+        ] {
+            assert!(
+                !seccomp::ALLOWED_SYSCALLS.contains(&syscall),
+                "system call {} must not be allowed unconditionally",
+                syscall
+            );
+        }
+    }
+
+    #[test]
+    fn disallowed_syscall_terminates_process_with_sigsys() {
+        // Build the filter before forking, since the child of a multi-threaded
+        // test process may only call async-signal-safe functions.
+        let filter = seccomp::sandbox_filter();
+
+        // SAFETY: the child only makes raw system calls before it exits.
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0, "fork failed: {}", io::Error::last_os_error());
+        if pid == 0 {
+            // SAFETY: the calls below neither allocate nor take locks.
+            unsafe {
+                if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0
+                    || seccomp::install_sigsys_handler().is_err()
+                    || seccomp::install_filter(&filter).is_err()
+                {
+                    libc::_exit(1);
+                }
+                // Opening a socket is not allowed by the filter.
+                libc::syscall(libc::SYS_socket, libc::AF_INET, libc::SOCK_STREAM, 0);
+                libc::_exit(0);
+            }
+        }
+
+        let status = waitpid(Pid::from_raw(pid), None).unwrap();
+        assert!(
+            matches!(status, WaitStatus::Signaled(_, Signal::SIGSYS, _)),
+            "unexpected exit status {:?}",
+            status
+        );
+        assert!(crate::launcher::is_confinement_violation(&status));
+    }
+}
@@ -25,7 +25,10 @@ use crate::{
 use ic_types::CanisterId;
 use nix::{
     errno::Errno,
-    sys::wait::{wait, WaitStatus},
+    sys::{
+        signal::Signal,
+        wait::{wait, WaitStatus},
+    },
     unistd::Pid,
 };
 
@@ -98,6 +101,12 @@ pub struct LauncherServer {
     embedder_config_arg: String,
 }
 
+/// Returns whether a sandbox process was terminated by the `SIGSYS` that its
+/// seccomp filter delivers for a system call it does not allow.
+pub(crate) fn is_confinement_violation(status: &WaitStatus) -> bool {
+    matches!(status, WaitStatus::Signaled(_, Signal::SIGSYS, _))
+}
+
 impl LauncherServer {
     fn new(controller: ControllerLauncherClientStub, embedder_config_arg: String) -> Self {
         let pid_to_process_info = Arc::new(Mutex::new(HashMap::<Pid, ProcessInfo>::new()));
@@ -145,8 +154,12 @@ impl LauncherServer {
                         if should_panic {
                             // If we have a canister id, tell the replica process to print its history.
                             if let Some(canister_id) = process_info.and_then(|x| x.canister_id) {
+                                let confinement_violation = is_confinement_violation(&status);
                                 controller
-                                    .sandbox_exited(SandboxExitedRequest { canister_id })
+                                    .sandbox_exited(SandboxExitedRequest {
+                                        canister_id,
+                                        confinement_violation,
+                                    })
                                     .sync()
                                     .unwrap();
                             }
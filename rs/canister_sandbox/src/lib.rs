@@ -1,4 +1,6 @@
 pub mod compiler_sandbox;
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+pub mod confinement;
 pub mod controller_client_stub;
 pub mod controller_launcher_client_stub;
 pub mod controller_launcher_service;
@@ -37,8 +39,8 @@ use protocol::{
     },
 };
 
-use ic_config::embedders::Config as EmbeddersConfig;
-use ic_logger::new_replica_logger_from_config;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+use ic_logger::{new_replica_logger_from_config, warn};
 use std::{
     os::unix::{net::UnixStream, prelude::FromRawFd},
     sync::Arc,
@@ -171,6 +173,14 @@ pub fn canister_sandbox_main() {
     let embedder_config = embedder_config_arg
         .expect("Error from the sandbox process due to unknown embedder config.");
 
+    // Confine the process before any other thread is started, so that all
+    // threads inherit the restrictions.
+    let confinement_warning = if embedder_config.sandbox_confinement == FlagStatus::Enabled {
+        confine_canister_sandbox()
+    } else {
+        None
+    };
+
     // Currently Wasmtime uses the default rayon thread-pool with a thread per core.
     // In production this results in 64 threads. The number of threads is set to 8,
     // which is used for parallel page copying in the page allocator.
@@ -180,7 +190,26 @@ pub fn canister_sandbox_main() {
         .build_global()
         .unwrap();
 
-    run_canister_sandbox(socket, embedder_config);
+    run_canister_sandbox(socket, embedder_config, confinement_warning);
+}
+
+/// Confines the calling process and returns a warning to log if the
+/// confinement is incomplete. The warning is logged once the logger exists,
+/// because the logger starts a thread that must inherit the confinement.
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn confine_canister_sandbox() -> Option<&'static str> {
+    match confinement::confine_canister_sandbox() {
+        Ok(confinement::LandlockStatus::Enforced { .. }) => None,
+        Ok(confinement::LandlockStatus::NotSupported) => Some(
+            "Landlock is not supported by the kernel, the canister sandbox has unrestricted filesystem access",
+        ),
+        Err(err) => panic!("Failed to confine the canister sandbox process: {}", err),
+    }
+}
+
+#[cfg(not(all(target_os = "linux", target_arch = "x86_64")))]
+fn confine_canister_sandbox() -> Option<&'static str> {
+    Some("Canister sandbox confinement is only supported on Linux x86_64")
 }
 
 /// Runs the canister sandbox service in the calling thread. The service
@@ -191,6 +220,7 @@ pub fn canister_sandbox_main() {
 pub fn run_canister_sandbox(
     socket: std::os::unix::net::UnixStream,
     embedder_config: EmbeddersConfig,
+    confinement_warning: Option<&str>,
 ) {
     // TODO(RUN-204): Get the logger config from the replica instead of
     // hardcoding the parameters.
@@ -200,6 +230,9 @@ pub fn run_canister_sandbox(
         ..Default::default()
     };
     let (log, _log_guard) = new_replica_logger_from_config(&logger_config);
+    if let Some(warning) = confinement_warning {
+        warn!(log, "{}", warning);
+    }
 
     let socket = Arc::new(socket);
 
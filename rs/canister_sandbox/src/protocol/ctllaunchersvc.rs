@@ -8,6 +8,9 @@ use crate::fdenum::EnumerateInnerFileDescriptors;
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SandboxExitedRequest {
     pub canister_id: CanisterId,
+    /// Whether the sandbox process was terminated by `SIGSYS` for making a
+    /// system call that its seccomp filter does not allow.
+    pub confinement_violation: bool,
 }
 
 impl EnumerateInnerFileDescriptors for SandboxExitedRequest {
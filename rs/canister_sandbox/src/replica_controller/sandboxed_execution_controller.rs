@@ -61,6 +61,7 @@ const SANDBOX_PROCESS_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
 const SANDBOX_PROCESS_EVICTION_PERCENT: usize = 20;
 
 const SANDBOXED_EXECUTION_INVALID_MEMORY_SIZE: &str = "sandboxed_execution_invalid_memory_size";
+const SANDBOXED_EXECUTION_CONFINEMENT_VIOLATION: &str = "sandboxed_execution_confinement_violation";
 
 // Metric labels for the different outcomes of a wasm cache lookup. Stored in
 // the metric
@@ -93,6 +94,8 @@ struct SandboxedExecutionMetrics {
     sandboxed_execution_subprocess_active_last_used: Histogram,
     sandboxed_execution_subprocess_evicted_last_used: Histogram,
     sandboxed_execution_critical_error_invalid_memory_size: IntCounter,
+    // Critical error for sandbox processes killed for violating their seccomp filter.
+    sandboxed_execution_critical_error_confinement_violation: IntCounter,
     sandboxed_execution_replica_create_exe_state_duration: Histogram,
     sandboxed_execution_replica_create_exe_state_wait_compile_duration: Histogram,
     sandboxed_execution_replica_create_exe_state_wait_deserialize_duration: Histogram,
@@ -199,6 +202,8 @@ impl SandboxedExecutionMetrics {
             ),
             sandboxed_execution_critical_error_invalid_memory_size: metrics_registry.error_counter(
                 SANDBOXED_EXECUTION_INVALID_MEMORY_SIZE),
+            sandboxed_execution_critical_error_confinement_violation: metrics_registry.error_counter(
+                SANDBOXED_EXECUTION_CONFINEMENT_VIOLATION),
             sandboxed_execution_replica_create_exe_state_duration: metrics_registry.histogram(
                 "sandboxed_execution_replica_create_exe_state_duration_seconds",
                 "The total create execution state duration in the replica controller",
@@ -1081,6 +1086,7 @@ impl SandboxedExecutionController {
         let exit_watcher = Arc::new(ExitWatcher {
             logger: logger.clone(),
             backends: Arc::clone(&backends),
+            metrics: Arc::clone(&metrics),
         });
 
         let (launcher_service, mut child) = spawn_launcher_process(
@@ -1764,6 +1770,7 @@ pub fn panic_due_to_exit(output: ExitStatus, pid: u32) {
 struct ExitWatcher {
     logger: ReplicaLogger,
     backends: Arc<Mutex<HashMap<CanisterId, Backend>>>,
+    metrics: Arc<SandboxedExecutionMetrics>,
 }
 
 impl ControllerLauncherService for ExitWatcher {
@@ -1771,6 +1778,17 @@ impl ControllerLauncherService for ExitWatcher {
         &self,
         req: protocol::ctllaunchersvc::SandboxExitedRequest,
     ) -> crate::rpc::Call<protocol::ctllaunchersvc::SandboxExitedReply> {
+        if req.confinement_violation {
+            error!(
+                self.logger,
+                "{}: Sandbox process of canister {} was killed for making a system call that is not allowed by its seccomp filter",
+                SANDBOXED_EXECUTION_CONFINEMENT_VIOLATION,
+                req.canister_id,
+            );
+            self.metrics
+                .sandboxed_execution_critical_error_confinement_violation
+                .inc();
+        }
         let guard = self.backends.lock().unwrap();
         let sandbox_process = match guard.get(&req.canister_id).unwrap_or_else(|| {
             panic!(
@@ -1812,6 +1830,7 @@ mod tests {
         let exit_watcher = Arc::new(ExitWatcher {
             logger: no_op_logger(),
             backends: Arc::new(Mutex::new(HashMap::new())),
+            metrics: Arc::new(SandboxedExecutionMetrics::new(&MetricsRegistry::new())),
         });
 
         let (_launcher_service, mut child) = spawn_launcher_process(
@@ -1830,6 +1849,32 @@ mod tests {
         panic_due_to_exit(output, pid);
     }
 
+    #[test]
+    fn confinement_violation_is_reported_as_critical_error() {
+        let canister_id = canister_test_id(0);
+        let metrics = Arc::new(SandboxedExecutionMetrics::new(&MetricsRegistry::new()));
+        let exit_watcher = ExitWatcher {
+            logger: no_op_logger(),
+            backends: Arc::new(Mutex::new(HashMap::from([(canister_id, Backend::Empty)]))),
+            metrics: Arc::clone(&metrics),
+        };
+
+        exit_watcher
+            .sandbox_exited(protocol::ctllaunchersvc::SandboxExitedRequest {
+                canister_id,
+                confinement_violation: true,
+            })
+            .sync()
+            .unwrap();
+
+        assert_eq!(
+            metrics
+                .sandboxed_execution_critical_error_confinement_violation
+                .get(),
+            1
+        );
+    }
+
     #[test]
     fn sandbox_history_logged_on_sandbox_crash() {
         let tempdir = tempfile::tempdir().unwrap();
@@ -207,6 +207,12 @@ impl ConfigValidate for ConfigOptional {
             if let Some(uds_path) = &adapters_config.bitcoin_testnet_uds_path {
                 same_uds_paths |= !uds_paths.insert(uds_path.clone());
             }
+            if let Some(uds_path) = &adapters_config.dogecoin_mainnet_uds_path {
+                same_uds_paths |= !uds_paths.insert(uds_path.clone());
+            }
+            if let Some(uds_path) = &adapters_config.dogecoin_testnet_uds_path {
+                same_uds_paths |= !uds_paths.insert(uds_path.clone());
+            }
             if let Some(uds_path) = &adapters_config.https_outcalls_uds_path {
                 same_uds_paths |= !uds_paths.insert(uds_path.clone());
             }
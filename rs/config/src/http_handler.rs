@@ -42,6 +42,9 @@ pub struct Config {
     /// Serving at most `max_read_state_concurrent_requests` requests concurrently for endpoint `/api/v2/read_state`.
     pub max_read_state_concurrent_requests: usize,
 
+    /// Keeping at most `max_subscribe_concurrent_connections` streams open concurrently for endpoint `/api/v2/canister/<id>/subscribe`.
+    pub max_subscribe_concurrent_connections: usize,
+
     /// Serving at most `max_status_concurrent_requests` requests concurrently for endpoint `/api/v2/status`.
     pub max_status_concurrent_requests: usize,
 
@@ -81,6 +84,7 @@ impl Default for Config {
             max_request_size_bytes: 5 * 1024 * 1024, // 5MB
             max_delegation_certificate_size_bytes: 1024 * 1024, // 1MB
             max_read_state_concurrent_requests: 100,
+            max_subscribe_concurrent_connections: 100,
             max_catch_up_package_concurrent_requests: 100,
             max_dashboard_concurrent_requests: 100,
             max_status_concurrent_requests: 100,
@@ -24,7 +24,8 @@ use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_management_canister_types::{
     CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterSnapshotResponse,
     CanisterStatusResultV2, CanisterStatusType, ChunkHash, InstallChunkedCodeArgs,
-    InstallCodeArgsV2, Method as Ic00Method, StoredChunksReply, UploadChunkReply,
+    InstallCodeArgsV2, MemoryMetrics, Method as Ic00Method, MethodQueryStats, QueryStats,
+    StoredChunksReply, UploadChunkReply,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
@@ -614,6 +615,9 @@ impl CanisterManager {
         if let Some(wasm_memory_threshold) = settings.wasm_memory_threshold() {
             canister.system_state.wasm_memory_threshold = wasm_memory_threshold;
         }
+        if let Some(multiplier) = settings.low_cycles_threshold_multiplier() {
+            canister.system_state.low_cycles_threshold_multiplier = multiplier;
+        }
         if let Some(limit) = settings.reserved_cycles_limit() {
             canister.system_state.set_reserved_balance_limit(limit);
         }
@@ -1166,12 +1170,39 @@ impl CanisterManager {
 
         let canister_memory_usage = canister.memory_usage();
         let canister_message_memory_usage = canister.message_memory_usage();
+        let memory_metrics = MemoryMetrics::new(
+            canister.wasm_memory_usage(),
+            canister.stable_memory_usage(),
+            canister.globals_memory_usage(),
+            canister.wasm_binary_memory_usage(),
+            canister.wasm_custom_sections_memory_usage(),
+            canister.canister_history_memory_usage(),
+            canister.wasm_chunk_store_memory_usage(),
+            canister.snapshots_memory_usage(),
+            canister_message_memory_usage,
+        );
         let compute_allocation = canister.scheduler_state.compute_allocation;
         let memory_allocation = canister.memory_allocation();
         let freeze_threshold = canister.system_state.freeze_threshold;
         let reserved_cycles_limit = canister.system_state.reserved_balance_limit();
         let log_visibility = canister.system_state.log_visibility.clone();
         let wasm_memory_limit = canister.system_state.wasm_memory_limit;
+        let method_query_stats = canister
+            .scheduler_state
+            .total_method_query_stats
+            .iter()
+            .map(|(method_name, stats)| {
+                MethodQueryStats::new(
+                    method_name.clone(),
+                    QueryStats::new(
+                        stats.num_calls,
+                        stats.num_instructions,
+                        stats.ingress_payload_size,
+                        stats.egress_payload_size,
+                    ),
+                )
+            })
+            .collect();
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -1182,6 +1213,7 @@ impl CanisterManager {
             *controller,
             controllers,
             canister_memory_usage,
+            memory_metrics,
             canister.system_state.balance().get(),
             compute_allocation.as_percent(),
             Some(memory_allocation.bytes().get()),
@@ -1208,6 +1240,7 @@ impl CanisterManager {
                 .scheduler_state
                 .total_query_stats
                 .egress_payload_size,
+            method_query_stats,
             wasm_memory_limit.map(|x| x.get()),
         ))
     }
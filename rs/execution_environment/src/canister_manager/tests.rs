@@ -67,6 +67,7 @@ use ic_test_utilities_types::{
     messages::{IngressBuilder, RequestBuilder, SignedIngressBuilder},
 };
 use ic_types::{
+    batch::TotalQueryStats,
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{CallbackId, CanisterCall, StopCanisterCallId, StopCanisterContext, NO_DEADLINE},
     nominal_cycles::NominalCycles,
@@ -7040,6 +7041,101 @@ fn canister_status_contains_reserved_cycles_limit() {
     );
 }
 
+#[test]
+fn canister_status_contains_memory_metrics() {
+    let mut test = ExecutionTestBuilder::new().build();
+
+    let wat = r#"
+        (module
+            (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+            (func (export "canister_init")
+                (drop (call $stable_grow (i32.const 2)))
+            )
+            (global (export "g") (mut i32) (i32.const 0))
+            (memory 3)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.canister_status(canister_id);
+    let reply = get_reply(result);
+    let status = CanisterStatusResultV2::decode(&reply).unwrap();
+    let metrics = status.memory_metrics();
+    let canister = test.canister_state(canister_id);
+
+    assert_eq!(
+        metrics.wasm_memory_size(),
+        NumBytes::new(3 * WASM_PAGE_SIZE_IN_BYTES)
+    );
+    assert_eq!(
+        metrics.stable_memory_size(),
+        NumBytes::new(2 * WASM_PAGE_SIZE_IN_BYTES)
+    );
+    assert_eq!(
+        metrics.global_memory_size(),
+        canister.globals_memory_usage()
+    );
+    assert_eq!(
+        metrics.wasm_binary_size(),
+        canister.wasm_binary_memory_usage()
+    );
+    assert_eq!(
+        metrics.canister_history_size(),
+        canister.canister_history_memory_usage()
+    );
+    assert_eq!(
+        metrics.message_memory_size(),
+        canister.message_memory_usage()
+    );
+    assert_eq!(
+        metrics.wasm_memory_size()
+            + metrics.stable_memory_size()
+            + metrics.global_memory_size()
+            + metrics.wasm_binary_size()
+            + metrics.custom_sections_size()
+            + metrics.canister_history_size()
+            + metrics.wasm_chunk_store_size()
+            + metrics.snapshots_size(),
+        status.memory_size()
+    );
+}
+
+#[test]
+fn canister_status_contains_method_query_stats() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+
+    let method_stats = TotalQueryStats {
+        num_calls: 4,
+        num_instructions: 1000,
+        ingress_payload_size: 20,
+        egress_payload_size: 40,
+    };
+    test.canister_state_mut(canister_id)
+        .scheduler_state
+        .total_method_query_stats = btreemap! {"query".to_string() => method_stats.clone()};
+
+    let result = test.canister_status(canister_id);
+    let reply = get_reply(result);
+    let status = CanisterStatusResultV2::decode(&reply).unwrap();
+
+    let method_query_stats = status.method_query_stats();
+    assert_eq!(method_query_stats.len(), 1);
+    assert_eq!(method_query_stats[0].method_name(), "query");
+    let query_stats = method_query_stats[0].query_stats();
+    assert_eq!(query_stats.num_calls_total(), method_stats.num_calls);
+    assert_eq!(
+        query_stats.num_instructions_total(),
+        method_stats.num_instructions
+    );
+    assert_eq!(
+        query_stats.request_payload_bytes_total(),
+        method_stats.ingress_payload_size
+    );
+    assert_eq!(
+        query_stats.response_payload_bytes_total(),
+        method_stats.egress_payload_size
+    );
+}
+
 #[test]
 fn upload_chunk_works_from_white_list() {
     const CYCLES: Cycles = Cycles::new(1_000_000_000_000_000);
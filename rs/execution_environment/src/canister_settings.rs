@@ -27,6 +27,9 @@ pub(crate) struct CanisterSettings {
     pub(crate) reserved_cycles_limit: Option<Cycles>,
     pub(crate) log_visibility: Option<LogVisibilityV2>,
     pub(crate) wasm_memory_limit: Option<NumBytes>,
+    /// Multiple of the freezing threshold below which the
+    /// canister_on_low_cycles hook is activated.
+    pub(crate) low_cycles_threshold_multiplier: Option<u64>,
 }
 
 impl CanisterSettings {
@@ -39,6 +42,7 @@ impl CanisterSettings {
         reserved_cycles_limit: Option<Cycles>,
         log_visibility: Option<LogVisibilityV2>,
         wasm_memory_limit: Option<NumBytes>,
+        low_cycles_threshold_multiplier: Option<u64>,
     ) -> Self {
         Self {
             controllers,
@@ -49,6 +53,7 @@ impl CanisterSettings {
             reserved_cycles_limit,
             log_visibility,
             wasm_memory_limit,
+            low_cycles_threshold_multiplier,
         }
     }
 
@@ -83,6 +88,10 @@ impl CanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn low_cycles_threshold_multiplier(&self) -> Option<u64> {
+        self.low_cycles_threshold_multiplier
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -146,6 +155,15 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             None => None,
         };
 
+        let low_cycles_threshold_multiplier = match input.low_cycles_threshold_multiplier {
+            Some(multiplier) => Some(multiplier.0.to_u64().ok_or(
+                UpdateSettingsError::LowCyclesThresholdMultiplierOutOfRange {
+                    provided: multiplier,
+                },
+            )?),
+            None => None,
+        };
+
         Ok(CanisterSettings::new(
             input
                 .controllers
@@ -157,6 +175,7 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             reserved_cycles_limit,
             input.log_visibility,
             wasm_memory_limit,
+            low_cycles_threshold_multiplier,
         ))
     }
 }
@@ -181,6 +200,7 @@ pub(crate) struct CanisterSettingsBuilder {
     reserved_cycles_limit: Option<Cycles>,
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<NumBytes>,
+    low_cycles_threshold_multiplier: Option<u64>,
 }
 
 #[allow(dead_code)]
@@ -195,6 +215,7 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: None,
             log_visibility: None,
             wasm_memory_limit: None,
+            low_cycles_threshold_multiplier: None,
         }
     }
 
@@ -208,6 +229,7 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
+            low_cycles_threshold_multiplier: self.low_cycles_threshold_multiplier,
         }
     }
 
@@ -266,6 +288,13 @@ impl CanisterSettingsBuilder {
             ..self
         }
     }
+
+    pub fn with_low_cycles_threshold_multiplier(self, multiplier: u64) -> Self {
+        Self {
+            low_cycles_threshold_multiplier: Some(multiplier),
+            ..self
+        }
+    }
 }
 
 pub enum UpdateSettingsError {
@@ -275,6 +304,7 @@ pub enum UpdateSettingsError {
     ReservedCyclesLimitOutOfRange { provided: candid::Nat },
     WasmMemoryLimitOutOfRange { provided: candid::Nat },
     WasmMemoryThresholdOutOfRange { provided: candid::Nat },
+    LowCyclesThresholdMultiplierOutOfRange { provided: candid::Nat },
 }
 
 impl From<UpdateSettingsError> for UserError {
@@ -324,6 +354,15 @@ impl From<UpdateSettingsError> for UserError {
                     provided
                 ),
             ),
+            UpdateSettingsError::LowCyclesThresholdMultiplierOutOfRange { provided } => {
+                UserError::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Low cycles threshold multiplier expected to be in the range of [0..2^64-1], got {}",
+                        provided
+                    ),
+                )
+            }
         }
     }
 }
@@ -350,6 +389,7 @@ pub(crate) struct ValidatedCanisterSettings {
     reservation_cycles: Cycles,
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<NumBytes>,
+    low_cycles_threshold_multiplier: Option<u64>,
 }
 
 impl ValidatedCanisterSettings {
@@ -388,6 +428,10 @@ impl ValidatedCanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn low_cycles_threshold_multiplier(&self) -> Option<u64> {
+        self.low_cycles_threshold_multiplier
+    }
 }
 
 /// Validates the new canisters settings:
@@ -595,5 +639,6 @@ pub(crate) fn validate_canister_settings(
         reservation_cycles,
         log_visibility: settings.log_visibility().cloned(),
         wasm_memory_limit: settings.wasm_memory_limit(),
+        low_cycles_threshold_multiplier: settings.low_cycles_threshold_multiplier(),
     })
 }
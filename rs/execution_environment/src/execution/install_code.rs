@@ -513,6 +513,7 @@ impl InstallCodeHelper {
                 reserved_cycles_limit: None,
                 log_visibility: None,
                 wasm_memory_limit: None,
+                low_cycles_threshold_multiplier: None,
             },
             self.canister.memory_usage(),
             self.canister.message_memory_usage(),
@@ -155,6 +155,12 @@ pub fn execute_update(
             time,
             helper.call_context_id(),
         ),
+        CanisterCallOrTask::Task(CanisterTask::OnLowCycles) => ApiType::system_task(
+            IC_00.get(),
+            SystemMethod::CanisterOnLowCycles,
+            time,
+            helper.call_context_id(),
+        ),
     };
 
     let memory_usage = helper.canister().memory_usage();
@@ -341,7 +347,8 @@ impl UpdateHelper {
         match original.call_or_task {
             CanisterCallOrTask::Call(_)
             | CanisterCallOrTask::Task(CanisterTask::Heartbeat)
-            | CanisterCallOrTask::Task(CanisterTask::OnLowWasmMemory) => {}
+            | CanisterCallOrTask::Task(CanisterTask::OnLowWasmMemory)
+            | CanisterCallOrTask::Task(CanisterTask::OnLowCycles) => {}
             CanisterCallOrTask::Task(CanisterTask::GlobalTimer) => {
                 // The global timer is one-off.
                 canister.system_state.global_timer = CanisterTimer::Inactive;
@@ -12,6 +12,7 @@ use crate::{
     execution_environment_metrics::{
         ExecutionEnvironmentMetrics, SUBMITTED_OUTCOME_LABEL, SUCCESS_STATUS_LABEL,
     },
+    execution_trace::{ExecutionTraceEntry, ExecutionTracer, ExecutionTracing},
     hypervisor::Hypervisor,
     ic00_permissions::Ic00MethodPermissions,
     metrics::{CallTreeMetrics, CallTreeMetricsImpl, IngressFilterMetrics},
@@ -47,7 +48,10 @@ use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::system_state::PausedExecutionId,
-    canister_state::{system_state::CyclesUseCase, NextExecution},
+    canister_state::{
+        system_state::{CyclesUseCase, OnLowCyclesHookStatus},
+        NextExecution,
+    },
     metadata_state::subnet_call_context_manager::{
         EcdsaArguments, IDkgDealingsContext, InstallCodeCall, InstallCodeCallId, SchnorrArguments,
         SetupInitialDkgContext, SignWithThresholdContext, StopCanisterCall, SubnetCallContext,
@@ -335,6 +339,7 @@ pub struct ExecutionEnvironment {
     // parallel and potentially reserving resources. It should be initialized to
     // the number of scheduler cores.
     resource_saturation_scaling: usize,
+    execution_tracing: Option<ExecutionTracing>,
 }
 
 /// This is a helper enum that indicates whether the current DTS execution of
@@ -421,9 +426,22 @@ impl ExecutionEnvironment {
             own_subnet_type,
             paused_execution_registry: Default::default(),
             resource_saturation_scaling,
+            execution_tracing: None,
         }
     }
 
+    /// Reports every finished execution of a message or task to `tracer`.
+    pub fn with_execution_tracer(mut self, tracer: Arc<dyn ExecutionTracer>) -> Self {
+        self.execution_tracing = Some(ExecutionTracing::new(tracer));
+        self
+    }
+
+    pub fn execution_tracer(&self) -> Option<&dyn ExecutionTracer> {
+        self.execution_tracing
+            .as_ref()
+            .map(|tracing| tracing.tracer())
+    }
+
     pub fn state_changes_error(&self) -> &IntCounter {
         &self.metrics.state_changes_error
     }
@@ -3103,6 +3121,7 @@ impl ExecutionEnvironment {
             ExecutionTask::Heartbeat
             | ExecutionTask::GlobalTimer
             | ExecutionTask::OnLowWasmMemory
+            | ExecutionTask::OnLowCycles
             | ExecutionTask::PausedExecution { .. }
             | ExecutionTask::AbortedExecution { .. } => {
                 panic!(
@@ -3200,7 +3219,8 @@ impl ExecutionEnvironment {
                     | ExecutionTask::AbortedInstallCode { .. }
                     | ExecutionTask::Heartbeat
                     | ExecutionTask::GlobalTimer
-                    | ExecutionTask::OnLowWasmMemory => task,
+                    | ExecutionTask::OnLowWasmMemory
+                    | ExecutionTask::OnLowCycles => task,
                     ExecutionTask::PausedExecution { id, .. } => {
                         let paused = self.take_paused_execution(id).unwrap();
                         let (input, prepaid_execution_cycles) = paused.abort(log);
@@ -3651,6 +3671,8 @@ pub struct ExecuteCanisterResult {
     pub ingress_status: Option<(MessageId, IngressStatus)>,
     // The description of the executed task or message.
     pub description: Option<String>,
+    /// The trace of the finished execution, if an execution tracer is set.
+    pub trace: Option<ExecutionTraceEntry>,
 }
 
 /// Executes the given input message or task.
@@ -3668,6 +3690,10 @@ fn execute_canister_input(
     subnet_size: usize,
 ) -> ExecuteCanisterResult {
     let info = input.to_string();
+    let trace_start = exec_env
+        .execution_tracing
+        .as_ref()
+        .map(|tracing| tracing.start(&canister, &input, prepaid_execution_cycles.is_some()));
     let result = exec_env.execute_canister_input(
         canister,
         instruction_limits,
@@ -3679,6 +3705,10 @@ fn execute_canister_input(
         round_limits,
         subnet_size,
     );
+    let trace = match (&exec_env.execution_tracing, trace_start) {
+        (Some(tracing), Some(start)) => tracing.finish(start, &result),
+        _ => None,
+    };
     let (canister, instructions_used, heap_delta, ingress_status) = exec_env.process_result(result);
     ExecuteCanisterResult {
         canister,
@@ -3686,6 +3716,7 @@ fn execute_canister_input(
         heap_delta,
         ingress_status,
         description: Some(info),
+        trace,
     }
 }
 
@@ -3709,6 +3740,7 @@ pub fn execute_canister(
                 heap_delta: NumBytes::from(0),
                 ingress_status: None,
                 description: None,
+                trace: None,
             };
         }
         NextExecution::StartNew | NextExecution::ContinueLong => {}
@@ -3736,6 +3768,10 @@ pub fn execute_canister(
                     log: &exec_env.log,
                     time,
                 };
+                let trace_start = exec_env
+                    .execution_tracing
+                    .as_ref()
+                    .map(|tracing| tracing.start(&canister, &paused.input(), true));
                 let result = paused.resume(
                     canister,
                     round_context,
@@ -3743,6 +3779,10 @@ pub fn execute_canister(
                     subnet_size,
                     &exec_env.call_tree_metrics,
                 );
+                let trace = match (&exec_env.execution_tracing, trace_start) {
+                    (Some(tracing), Some(start)) => tracing.finish(start, &result),
+                    _ => None,
+                };
                 let (canister, instructions_used, heap_delta, ingress_status) =
                     exec_env.process_result(result);
                 return ExecuteCanisterResult {
@@ -3751,6 +3791,7 @@ pub fn execute_canister(
                     heap_delta,
                     ingress_status,
                     description: Some("paused execution".to_string()),
+                    trace,
                 };
             }
             ExecutionTask::Heartbeat => {
@@ -3765,6 +3806,13 @@ pub fn execute_canister(
                 let task = CanisterMessageOrTask::Task(CanisterTask::OnLowWasmMemory);
                 (task, None)
             }
+            ExecutionTask::OnLowCycles => {
+                // The hook runs at most once per low cycles episode, so it is
+                // marked as executed as soon as it is taken off the queue.
+                canister.system_state.on_low_cycles_hook_status = OnLowCyclesHookStatus::Executed;
+                let task = CanisterMessageOrTask::Task(CanisterTask::OnLowCycles);
+                (task, None)
+            }
             ExecutionTask::AbortedExecution {
                 input,
                 prepaid_execution_cycles,
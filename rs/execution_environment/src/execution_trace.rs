@@ -0,0 +1,359 @@
+//! Tracing of the messages and tasks executed by the scheduler.
+//!
+//! The replicated state only keeps aggregated metrics of executions. A tracer
+//! registered via `ExecutionServices::setup_execution_with_tracer()` receives
+//! an [`ExecutionTraceEntry`] for every executed message and task instead,
+//! which tools like `ic-replay` use to attribute state changes to messages.
+
+use crate::execution_environment::{ExecuteMessageResult, ExecutionResponse};
+use ic_base_types::PrincipalId;
+use ic_error_types::{ErrorCode, RejectCode};
+use ic_replicated_state::CanisterState;
+use ic_types::{
+    ingress::{IngressState, IngressStatus, WasmResult},
+    messages::{CanisterMessage, CanisterMessageOrTask, CanisterTask, Payload},
+    nominal_cycles::NominalCycles,
+    CanisterId, Cycles, ExecutionRound, NumInstructions,
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+/// Receives the executions of messages and tasks.
+pub trait ExecutionTracer: Send + Sync {
+    /// Called after the execution of a message or task finished in `round`.
+    ///
+    /// Executions of different canisters run on several threads, so entries
+    /// of the same round are recorded in no particular order.
+    fn record(&self, round: ExecutionRound, entry: ExecutionTraceEntry);
+}
+
+/// The kind of an executed message or task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionTraceKind {
+    Ingress,
+    Request,
+    Response,
+    Heartbeat,
+    GlobalTimer,
+    OnLowWasmMemory,
+    OnLowCycles,
+}
+
+/// The outcome of an executed message or task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExecutionTraceResult {
+    /// The call was replied to.
+    Reply,
+    /// The call was rejected with the given code.
+    Reject(RejectCode),
+    /// The ingress message failed with the given error.
+    Error(ErrorCode),
+    /// The execution produced no reply, e.g. because the call is still
+    /// waiting for the responses of downstream calls or because it was a
+    /// response or a task.
+    None,
+}
+
+/// A single message or task whose execution finished.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutionTraceEntry {
+    pub canister_id: CanisterId,
+    pub kind: ExecutionTraceKind,
+    /// The called method, for ingress messages and requests.
+    pub method: Option<String>,
+    /// The sender of an ingress message or request, or the respondent of a
+    /// response.
+    pub caller: Option<PrincipalId>,
+    /// The instructions used by the execution, including all slices of a long
+    /// execution.
+    pub instructions_used: NumInstructions,
+    /// The net amount of cycles consumed by the canister during the
+    /// execution, i.e. the charged cycles minus the refunded ones.
+    pub cycles_charged: Cycles,
+    pub result: ExecutionTraceResult,
+}
+
+/// The part of an [`ExecutionTraceEntry`] known before the execution.
+pub(crate) struct ExecutionTraceStart {
+    canister_id: CanisterId,
+    kind: ExecutionTraceKind,
+    method: Option<String>,
+    caller: Option<PrincipalId>,
+    consumed_cycles: NominalCycles,
+}
+
+/// Builds the trace entries of the executions for a registered tracer.
+pub(crate) struct ExecutionTracing {
+    tracer: Arc<dyn ExecutionTracer>,
+    // The consumed cycles of canisters at the start of their long executions
+    // that are paused or aborted.
+    long_executions: Mutex<BTreeMap<CanisterId, NominalCycles>>,
+}
+
+impl ExecutionTracing {
+    pub(crate) fn new(tracer: Arc<dyn ExecutionTracer>) -> Self {
+        Self {
+            tracer,
+            long_executions: Default::default(),
+        }
+    }
+
+    pub(crate) fn tracer(&self) -> &dyn ExecutionTracer {
+        self.tracer.as_ref()
+    }
+
+    /// Captures the state before executing `input` on `canister`. `resumed`
+    /// indicates that the input continues a paused or aborted execution.
+    pub(crate) fn start(
+        &self,
+        canister: &CanisterState,
+        input: &CanisterMessageOrTask,
+        resumed: bool,
+    ) -> ExecutionTraceStart {
+        let canister_id = canister.canister_id();
+        let current_consumed_cycles = canister.system_state.canister_metrics.consumed_cycles;
+        let mut long_executions = self.long_executions.lock().unwrap();
+        let consumed_cycles = if resumed {
+            long_executions
+                .remove(&canister_id)
+                .unwrap_or(current_consumed_cycles)
+        } else {
+            long_executions.remove(&canister_id);
+            current_consumed_cycles
+        };
+        let (kind, method, caller) = match input {
+            CanisterMessageOrTask::Message(CanisterMessage::Ingress(ingress)) => (
+                ExecutionTraceKind::Ingress,
+                Some(ingress.method_name.clone()),
+                Some(ingress.source.get()),
+            ),
+            CanisterMessageOrTask::Message(CanisterMessage::Request(request)) => (
+                ExecutionTraceKind::Request,
+                Some(request.method_name.clone()),
+                Some(request.sender.get()),
+            ),
+            CanisterMessageOrTask::Message(CanisterMessage::Response(response)) => (
+                ExecutionTraceKind::Response,
+                None,
+                Some(response.respondent.get()),
+            ),
+            CanisterMessageOrTask::Task(CanisterTask::Heartbeat) => {
+                (ExecutionTraceKind::Heartbeat, None, None)
+            }
+            CanisterMessageOrTask::Task(CanisterTask::GlobalTimer) => {
+                (ExecutionTraceKind::GlobalTimer, None, None)
+            }
+            CanisterMessageOrTask::Task(CanisterTask::OnLowWasmMemory) => {
+                (ExecutionTraceKind::OnLowWasmMemory, None, None)
+            }
+            CanisterMessageOrTask::Task(CanisterTask::OnLowCycles) => {
+                (ExecutionTraceKind::OnLowCycles, None, None)
+            }
+        };
+        ExecutionTraceStart {
+            canister_id,
+            kind,
+            method,
+            caller,
+            consumed_cycles,
+        }
+    }
+
+    /// Returns the trace entry of a finished execution. For a paused
+    /// execution, remembers the captured state until the execution resumes.
+    pub(crate) fn finish(
+        &self,
+        start: ExecutionTraceStart,
+        result: &ExecuteMessageResult,
+    ) -> Option<ExecutionTraceEntry> {
+        match result {
+            ExecuteMessageResult::Finished {
+                canister,
+                response,
+                instructions_used,
+                ..
+            } => {
+                let consumed_cycles = canister.system_state.canister_metrics.consumed_cycles;
+                Some(ExecutionTraceEntry {
+                    canister_id: start.canister_id,
+                    kind: start.kind,
+                    method: start.method,
+                    caller: start.caller,
+                    instructions_used: *instructions_used,
+                    cycles_charged: Cycles::from(
+                        consumed_cycles
+                            .get()
+                            .saturating_sub(start.consumed_cycles.get()),
+                    ),
+                    result: trace_result(response),
+                })
+            }
+            ExecuteMessageResult::Paused { .. } => {
+                self.long_executions
+                    .lock()
+                    .unwrap()
+                    .insert(start.canister_id, start.consumed_cycles);
+                None
+            }
+        }
+    }
+}
+
+fn trace_result(response: &ExecutionResponse) -> ExecutionTraceResult {
+    match response {
+        ExecutionResponse::Ingress((_, IngressStatus::Known { state, .. })) => match state {
+            IngressState::Completed(WasmResult::Reply(_)) => ExecutionTraceResult::Reply,
+            IngressState::Completed(WasmResult::Reject(_)) => {
+                ExecutionTraceResult::Reject(RejectCode::CanisterReject)
+            }
+            IngressState::Failed(err) => ExecutionTraceResult::Error(err.code()),
+            IngressState::Received | IngressState::Processing | IngressState::Done => {
+                ExecutionTraceResult::None
+            }
+        },
+        ExecutionResponse::Ingress((_, IngressStatus::Unknown)) => ExecutionTraceResult::None,
+        ExecutionResponse::Request(response) => match &response.response_payload {
+            Payload::Data(_) => ExecutionTraceResult::Reply,
+            Payload::Reject(context) => ExecutionTraceResult::Reject(context.code()),
+        },
+        ExecutionResponse::Empty => ExecutionTraceResult::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_base_types::NumBytes;
+    use ic_test_utilities_state::CanisterStateBuilder;
+    use ic_test_utilities_types::{
+        ids::{canister_test_id, message_test_id, user_test_id},
+        messages::{IngressBuilder, RequestBuilder, ResponseBuilder},
+    };
+    use ic_types::{messages::RejectContext, time::UNIX_EPOCH};
+
+    struct NoOpTracer;
+
+    impl ExecutionTracer for NoOpTracer {
+        fn record(&self, _round: ExecutionRound, _entry: ExecutionTraceEntry) {}
+    }
+
+    fn finished(
+        mut canister: CanisterState,
+        consumed_cycles: u128,
+        response: ExecutionResponse,
+    ) -> ExecuteMessageResult {
+        canister.system_state.canister_metrics.consumed_cycles =
+            NominalCycles::from(consumed_cycles);
+        ExecuteMessageResult::Finished {
+            canister,
+            response,
+            instructions_used: NumInstructions::from(1_000),
+            heap_delta: NumBytes::from(0),
+            call_duration: None,
+        }
+    }
+
+    #[test]
+    fn traces_ingress_message() {
+        let tracing = ExecutionTracing::new(Arc::new(NoOpTracer));
+        let canister = CanisterStateBuilder::new()
+            .with_canister_id(canister_test_id(1))
+            .build();
+        let ingress = IngressBuilder::new()
+            .source(user_test_id(2))
+            .receiver(canister_test_id(1))
+            .method_name("update")
+            .build();
+        let input = CanisterMessageOrTask::Message(CanisterMessage::Ingress(Arc::new(ingress)));
+
+        let start = tracing.start(&canister, &input, false);
+        let status = IngressStatus::Known {
+            receiver: canister_test_id(1).get(),
+            user_id: user_test_id(2),
+            time: UNIX_EPOCH,
+            state: IngressState::Completed(WasmResult::Reply(vec![])),
+        };
+        let result = finished(
+            canister,
+            500,
+            ExecutionResponse::Ingress((message_test_id(1), status)),
+        );
+
+        assert_eq!(
+            tracing.finish(start, &result),
+            Some(ExecutionTraceEntry {
+                canister_id: canister_test_id(1),
+                kind: ExecutionTraceKind::Ingress,
+                method: Some("update".to_string()),
+                caller: Some(user_test_id(2).get()),
+                instructions_used: NumInstructions::from(1_000),
+                cycles_charged: Cycles::new(500),
+                result: ExecutionTraceResult::Reply,
+            })
+        );
+    }
+
+    #[test]
+    fn traces_rejected_request() {
+        let tracing = ExecutionTracing::new(Arc::new(NoOpTracer));
+        let canister = CanisterStateBuilder::new()
+            .with_canister_id(canister_test_id(1))
+            .build();
+        let request = RequestBuilder::new()
+            .sender(canister_test_id(3))
+            .receiver(canister_test_id(1))
+            .method_name("transfer")
+            .build();
+        let input = CanisterMessageOrTask::Message(CanisterMessage::Request(Arc::new(request)));
+
+        let start = tracing.start(&canister, &input, false);
+        let response = ResponseBuilder::new()
+            .originator(canister_test_id(3))
+            .respondent(canister_test_id(1))
+            .response_payload(Payload::Reject(RejectContext::new(
+                RejectCode::CanisterReject,
+                "rejected",
+            )))
+            .build();
+        let result = finished(canister, 0, ExecutionResponse::Request(response));
+
+        let entry = tracing.finish(start, &result).unwrap();
+        assert_eq!(entry.kind, ExecutionTraceKind::Request);
+        assert_eq!(entry.method, Some("transfer".to_string()));
+        assert_eq!(entry.caller, Some(canister_test_id(3).get()));
+        assert_eq!(
+            entry.result,
+            ExecutionTraceResult::Reject(RejectCode::CanisterReject)
+        );
+    }
+
+    #[test]
+    fn traces_cycles_from_the_start_of_a_long_execution() {
+        let tracing = ExecutionTracing::new(Arc::new(NoOpTracer));
+        let mut canister = CanisterStateBuilder::new()
+            .with_canister_id(canister_test_id(1))
+            .build();
+        let input = CanisterMessageOrTask::Task(CanisterTask::GlobalTimer);
+
+        // The first slice of the execution was paused after consuming 100
+        // cycles.
+        canister.system_state.canister_metrics.consumed_cycles = NominalCycles::from(100);
+        tracing
+            .long_executions
+            .lock()
+            .unwrap()
+            .insert(canister_test_id(1), NominalCycles::from(0));
+
+        let start = tracing.start(&canister, &input, true);
+        let result = finished(canister, 300, ExecutionResponse::Empty);
+
+        let entry = tracing.finish(start, &result).unwrap();
+        assert_eq!(entry.kind, ExecutionTraceKind::GlobalTimer);
+        assert_eq!(entry.caller, None);
+        assert_eq!(entry.cycles_charged, Cycles::new(300));
+        assert_eq!(entry.result, ExecutionTraceResult::None);
+        assert!(tracing.long_executions.lock().unwrap().is_empty());
+    }
+}
@@ -4,6 +4,7 @@ mod canister_settings;
 pub mod execution;
 mod execution_environment;
 mod execution_environment_metrics;
+mod execution_trace;
 mod history;
 mod hypervisor;
 mod ic00_permissions;
@@ -19,6 +20,9 @@ pub use execution_environment::{
     as_num_instructions, as_round_instructions, execute_canister, CompilationCostHandling,
     ExecuteMessageResult, ExecutionEnvironment, ExecutionResponse, RoundInstructions, RoundLimits,
 };
+pub use execution_trace::{
+    ExecutionTraceEntry, ExecutionTraceKind, ExecutionTraceResult, ExecutionTracer,
+};
 pub use history::{IngressHistoryReaderImpl, IngressHistoryWriterImpl};
 pub use hypervisor::{Hypervisor, HypervisorMetrics};
 use ic_base_types::PrincipalId;
@@ -78,6 +82,31 @@ pub enum NonReplicatedQueryKind {
     Pure { caller: PrincipalId },
 }
 
+/// The components that `ExecutionServices::into_parts()` splits the execution
+/// services into.
+pub type ExecutionServicesParts = (
+    IngressFilterService,
+    Arc<dyn IngressHistoryWriter<State = ReplicatedState>>,
+    Box<dyn IngressHistoryReader>,
+    QueryExecutionService,
+    Box<dyn Scheduler<State = ReplicatedState>>,
+);
+
+/// The components and configuration that the execution services are set up
+/// from, see `ExecutionServices::setup_execution_with_tracer()`.
+pub struct ExecutionSetup<'a> {
+    pub logger: ReplicaLogger,
+    pub metrics_registry: &'a MetricsRegistry,
+    pub own_subnet_id: SubnetId,
+    pub own_subnet_type: SubnetType,
+    pub scheduler_config: SchedulerConfig,
+    pub config: Config,
+    pub cycles_account_manager: Arc<CyclesAccountManager>,
+    pub state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    pub fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+    pub completed_execution_messages_tx: Sender<(MessageId, Height)>,
+}
+
 // This struct holds public facing components that are created by Execution.
 pub struct ExecutionServices {
     pub ingress_filter: IngressFilterService,
@@ -104,6 +133,42 @@ impl ExecutionServices {
         fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
         completed_execution_messages_tx: Sender<(MessageId, Height)>,
     ) -> ExecutionServices {
+        Self::setup_execution_with_tracer(
+            ExecutionSetup {
+                logger,
+                metrics_registry,
+                own_subnet_id,
+                own_subnet_type,
+                scheduler_config,
+                config,
+                cycles_account_manager,
+                state_reader,
+                fd_factory,
+                completed_execution_messages_tx,
+            },
+            None,
+        )
+    }
+
+    /// Like `setup_execution()`, but reports every finished execution of a
+    /// message or task to the given tracer.
+    pub fn setup_execution_with_tracer(
+        setup: ExecutionSetup<'_>,
+        execution_tracer: Option<Arc<dyn ExecutionTracer>>,
+    ) -> ExecutionServices {
+        let ExecutionSetup {
+            logger,
+            metrics_registry,
+            own_subnet_id,
+            own_subnet_type,
+            scheduler_config,
+            config,
+            cycles_account_manager,
+            state_reader,
+            fd_factory,
+            completed_execution_messages_tx,
+        } = setup;
+
         let hypervisor = Arc::new(Hypervisor::new(
             config.clone(),
             metrics_registry,
@@ -128,7 +193,7 @@ impl ExecutionServices {
         let (query_stats_collector, query_stats_payload_builder) =
             ic_query_stats::init_query_stats(logger.clone(), &config, metrics_registry);
 
-        let exec_env = Arc::new(ExecutionEnvironment::new(
+        let mut exec_env = ExecutionEnvironment::new(
             logger.clone(),
             Arc::clone(&hypervisor),
             Arc::clone(&ingress_history_writer) as Arc<_>,
@@ -143,7 +208,11 @@ impl ExecutionServices {
             scheduler_config.heap_delta_rate_limit,
             scheduler_config.upload_wasm_chunk_instructions,
             scheduler_config.canister_snapshot_baseline_instructions,
-        ));
+        );
+        if let Some(tracer) = execution_tracer {
+            exec_env = exec_env.with_execution_tracer(tracer);
+        }
+        let exec_env = Arc::new(exec_env);
         let sync_query_handler = Arc::new(InternalHttpQueryHandler::new(
             logger.clone(),
             hypervisor,
@@ -204,16 +273,7 @@ impl ExecutionServices {
         }
     }
 
-    #[allow(clippy::type_complexity)]
-    pub fn into_parts(
-        self,
-    ) -> (
-        IngressFilterService,
-        Arc<dyn IngressHistoryWriter<State = ReplicatedState>>,
-        Box<dyn IngressHistoryReader>,
-        QueryExecutionService,
-        Box<dyn Scheduler<State = ReplicatedState>>,
-    ) {
+    pub fn into_parts(self) -> ExecutionServicesParts {
         (
             self.ingress_filter,
             self.ingress_history_writer,
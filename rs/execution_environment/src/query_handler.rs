@@ -175,7 +175,23 @@ impl InternalHttpQueryHandler {
             .lock()
             .unwrap()
             .get(canister_id)
-            .cloned()
+            .map(|canister_stats| canister_stats.stats.clone())
+    }
+
+    /// Get query stats for the given method of the given canister from query stats collector.
+    ///
+    /// This is used in testing.
+    pub fn method_query_stats_for_testing(
+        &self,
+        canister_id: &CanisterId,
+        method_name: &str,
+    ) -> Option<QueryStats> {
+        self.local_query_execution_stats
+            .current_query_stats
+            .lock()
+            .unwrap()
+            .get(canister_id)
+            .and_then(|canister_stats| canister_stats.method_stats.get(method_name).cloned())
     }
 
     /// Set current epoch in query stats collector
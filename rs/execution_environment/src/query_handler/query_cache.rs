@@ -5,7 +5,7 @@ use ic_metrics::MetricsRegistry;
 use ic_query_stats::QueryStatsCollector;
 use ic_replicated_state::ReplicatedState;
 use ic_types::{
-    batch::QueryStats,
+    batch::CanisterQueryStats,
     ingress::WasmResult,
     messages::{Query, QuerySource},
     CountBytes, Cycles, PrincipalId, Time, UserId,
@@ -170,14 +170,14 @@ pub(crate) struct EntryEnv {
     /// The consensus-determined time when the query is executed.
     pub batch_time: Time,
     /// A vector of evaluated canister IDs with their versions, balances and stats.
-    pub canisters_versions_balances_stats: Vec<(CanisterId, u64, Cycles, QueryStats)>,
+    pub canisters_versions_balances_stats: Vec<(CanisterId, u64, Cycles, CanisterQueryStats)>,
 }
 
 impl EntryEnv {
     // Capture a state (canister version and balance) of the evaluated canisters.
     fn try_new(
         state: &ReplicatedState,
-        evaluated_stats: &BTreeMap<CanisterId, QueryStats>,
+        evaluated_stats: &BTreeMap<CanisterId, CanisterQueryStats>,
     ) -> Result<Self, UserError> {
         let mut canisters_versions_balances_stats = Vec::with_capacity(evaluated_stats.len());
         for (id, stats) in evaluated_stats.iter() {
@@ -257,7 +257,7 @@ impl EntryValue {
                 metrics.validation_errors.inc();
                 return false;
             };
-            canisters_stats.push((id, stats));
+            canisters_stats.push(stats);
 
             if &canister.system_state.canister_version != version {
                 all_canister_versions_are_valid = false;
@@ -283,10 +283,10 @@ impl EntryValue {
             // The value is still valid.
             metrics.hits.inc();
             // Apply query stats.
-            for (id, stats) in canisters_stats {
+            for stats in canisters_stats {
                 // Add query statistics to the query aggregator.
                 if let Some(query_stats_collector) = query_stats_collector {
-                    query_stats_collector.register_query_statistics(*id, stats);
+                    query_stats_collector.register_canister_query_statistics(stats);
                 }
             }
             // Several factors might cause ignoring behavior simultaneously.
@@ -435,7 +435,7 @@ impl QueryCache {
         result: &Result<WasmResult, UserError>,
         state: &ReplicatedState,
         system_api_counters: &SystemApiCallCounters,
-        evaluated_stats: &BTreeMap<CanisterId, QueryStats>,
+        evaluated_stats: &BTreeMap<CanisterId, CanisterQueryStats>,
         transient_errors: usize,
     ) {
         let now = state.metadata.batch_time;
@@ -13,7 +13,7 @@ use ic_test_utilities::universal_canister::wasm;
 use ic_test_utilities_execution_environment::{ExecutionTest, ExecutionTestBuilder};
 use ic_test_utilities_types::ids::user_test_id;
 use ic_types::{
-    batch::QueryStats,
+    batch::CanisterQueryStats,
     ingress::WasmResult,
     messages::{CanisterTask, Query, QuerySource},
     time, CountBytes,
@@ -232,7 +232,7 @@ fn query_cache_reports_count_bytes_metric_on_invalidation() {
     let big_result = Ok(WasmResult::Reply(vec![0; BIG_REPLY_SIZE]));
     let query_cache = &query_handler(&test).query_cache;
     let mut evaluated_stats = BTreeMap::new();
-    evaluated_stats.insert(a_id, QueryStats::default());
+    evaluated_stats.insert(a_id, CanisterQueryStats::new(a_id));
     query_cache.push(
         key.clone(),
         &big_result,
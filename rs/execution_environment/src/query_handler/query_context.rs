@@ -28,7 +28,7 @@ use ic_replicated_state::{
 };
 use ic_system_api::{ApiType, ExecutionParameters, InstructionLimits};
 use ic_types::{
-    batch::QueryStats,
+    batch::{CanisterQueryStats, QueryStats},
     ingress::WasmResult,
     messages::{
         CallContextId, CallbackId, Payload, Query, QuerySource, RejectContext, Request,
@@ -108,7 +108,7 @@ pub(super) struct QueryContext<'a> {
     system_api_call_counters: SystemApiCallCounters,
     /// A map of canister IDs evaluated and executed at least once in this query context
     /// with their stats. The information is used by the query cache for composite queries.
-    evaluated_canister_stats: BTreeMap<CanisterId, QueryStats>,
+    evaluated_canister_stats: BTreeMap<CanisterId, CanisterQueryStats>,
     /// The number of transient errors.
     transient_errors: usize,
     cycles_account_manager: Arc<CyclesAccountManager>,
@@ -164,7 +164,10 @@ impl<'a> QueryContext<'a> {
             system_api_call_counters: SystemApiCallCounters::default(),
             // If the `context.run()` returns an error and hence the empty evaluated IDs set,
             // the original canister ID should always be tracked for changes.
-            evaluated_canister_stats: BTreeMap::from([(canister_id, QueryStats::default())]),
+            evaluated_canister_stats: BTreeMap::from([(
+                canister_id,
+                CanisterQueryStats::new(canister_id),
+            )]),
             transient_errors: 0,
             cycles_account_manager,
         }
@@ -417,6 +420,16 @@ impl<'a> QueryContext<'a> {
         let execution_parameters = self.execution_parameters(&canister, instruction_limits);
 
         let data_certificate = self.get_data_certificate(&canister.canister_id());
+        // Only methods that are exported as query or composite query methods are tracked
+        // individually in the query stats.
+        let query_method_name = match &method_name {
+            WasmMethod::Query(name) | WasmMethod::CompositeQuery(name)
+                if canister.exports_method(&method_name) =>
+            {
+                Some(name.clone())
+            }
+            _ => None,
+        };
         let (mut canister, instructions_left, result, call_context_id, system_api_call_counters) =
             execute_non_replicated_query(
                 query_kind,
@@ -444,17 +457,27 @@ impl<'a> QueryContext<'a> {
             Err(_) => 0,
         };
 
-        // Add query statistics to the query aggregator.
+        // Add query statistics to the query aggregator. Calls that failed only count towards
+        // the stats of the whole canister.
+        let query_method_name = query_method_name.filter(|_| result.is_ok());
         let stats = QueryStats {
             num_calls: 1,
             num_instructions: instructions_executed.get(),
             ingress_payload_size: ingress_payload_size as u64,
             egress_payload_size: egress_payload_size as u64,
         };
-        self.add_evaluated_canister_stats(canister.canister_id(), &stats);
+        self.add_evaluated_canister_stats(
+            canister.canister_id(),
+            query_method_name.as_deref(),
+            &stats,
+        );
         if let Some(query_stats) = self.local_query_execution_stats {
             query_stats.set_epoch_from_height(self.state.height());
-            query_stats.register_query_statistics(canister.canister_id(), &stats);
+            query_stats.register_query_statistics(
+                canister.canister_id(),
+                query_method_name.as_deref(),
+                &stats,
+            );
         }
 
         measurement_scope.add(
@@ -482,11 +505,29 @@ impl<'a> QueryContext<'a> {
     }
 
     /// Adds a canister ID into a set of actually executed canisters.
-    fn add_evaluated_canister_stats(&mut self, canister_id: CanisterId, stats: &QueryStats) {
+    fn add_evaluated_canister(&mut self, canister_id: CanisterId) {
         self.evaluated_canister_stats
             .entry(canister_id)
-            .and_modify(|s| s.saturating_accumulate(stats))
-            .or_insert(stats.clone());
+            .or_insert_with(|| CanisterQueryStats::new(canister_id));
+    }
+
+    /// Adds the stats of executing `method_name` to the stats of an executed canister.
+    ///
+    /// If `method_name` is `None`, the stats only count towards the stats of the whole canister.
+    fn add_evaluated_canister_stats(
+        &mut self,
+        canister_id: CanisterId,
+        method_name: Option<&str>,
+        stats: &QueryStats,
+    ) {
+        let canister_stats = self
+            .evaluated_canister_stats
+            .entry(canister_id)
+            .or_insert_with(|| CanisterQueryStats::new(canister_id));
+        match method_name {
+            Some(method_name) => canister_stats.saturating_accumulate_method(method_name, stats),
+            None => canister_stats.stats.saturating_accumulate(stats),
+        }
     }
 
     /// Accumulates transient errors from result.
@@ -807,7 +848,7 @@ impl<'a> QueryContext<'a> {
 
         let canister_id = request.receiver;
         // Add the canister to the set of evaluated canisters early, i.e. before any errors.
-        self.add_evaluated_canister_stats(canister_id, &QueryStats::default());
+        self.add_evaluated_canister(canister_id);
 
         let canister = match self.state.get_ref().get_active_canister(&canister_id) {
             Ok(canister) => canister,
@@ -1095,7 +1136,7 @@ impl<'a> QueryContext<'a> {
     }
 
     /// Returns a list of actually executed canisters with their stats.
-    pub fn evaluated_canister_stats(&self) -> &BTreeMap<CanisterId, QueryStats> {
+    pub fn evaluated_canister_stats(&self) -> &BTreeMap<CanisterId, CanisterQueryStats> {
         &self.evaluated_canister_stats
     }
 
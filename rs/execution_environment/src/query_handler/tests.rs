@@ -1448,6 +1448,41 @@ fn query_stats_are_collected() {
     }
 }
 
+#[test]
+fn query_stats_only_track_methods_that_executed_successfully() {
+    let mut test = ExecutionTestBuilder::new().with_query_stats().build();
+    let canister = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    let query = |payload: Vec<u8>| {
+        let _ = test.query(
+            Query {
+                source: QuerySource::User {
+                    user_id: user_test_id(2),
+                    ingress_expiry: 0,
+                    nonce: None,
+                },
+                receiver: canister,
+                method_name: "query".to_string(),
+                method_payload: payload,
+            },
+            Arc::new(test.state().clone()),
+            vec![],
+        );
+    };
+    query(wasm().reply_data(b"ignore".as_ref()).build());
+    query(wasm().trap().build());
+
+    // Both calls count towards the stats of the canister, but only the successful one
+    // counts towards the stats of the method.
+    let canister_query_stats = test.query_stats_for_testing(&canister).unwrap();
+    assert_eq!(canister_query_stats.num_calls, 2);
+    let method_query_stats = test
+        .method_query_stats_for_testing(&canister, "query")
+        .unwrap();
+    assert_eq!(method_query_stats.num_calls, 1);
+    assert_eq!(method_query_stats.egress_payload_size, 6);
+}
+
 #[test]
 fn test_incorrect_query_name() {
     let test = ExecutionTestBuilder::new().build();
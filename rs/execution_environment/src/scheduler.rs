@@ -568,13 +568,14 @@ impl SchedulerImpl {
         (new_state, message_instructions)
     }
 
-    /// Invoked in the first iteration of the inner round to add the `Heartbeat`
-    /// and `GlobalTimer` tasks that are carried out prior to processing
-    /// any input messages.
+    /// Invoked in the first iteration of the inner round to add the `Heartbeat`,
+    /// `GlobalTimer` and `OnLowCycles` tasks that are carried out prior to
+    /// processing any input messages.
     /// It also returns the list of canisters that have non-zero priority credit.
     fn initialize_inner_round(
         &self,
         state: &mut ReplicatedState,
+        subnet_size: usize,
     ) -> (BTreeSet<CanisterId>, BTreeSet<CanisterId>) {
         let _timer = self
             .metrics
@@ -591,7 +592,7 @@ impl SchedulerImpl {
                 non_zero_priority_credit_canister_ids.insert(canister.system_state.canister_id);
             }
 
-            // Add `Heartbeat` or `GlobalTimer` for running canisters only.
+            // Add `Heartbeat`, `GlobalTimer` or `OnLowCycles` for running canisters only.
             match canister.system_state.status {
                 CanisterStatus::Running { .. } => {}
                 CanisterStatus::Stopping { .. } | CanisterStatus::Stopped => {
@@ -599,6 +600,21 @@ impl SchedulerImpl {
                 }
             }
 
+            if self.update_on_low_cycles_hook_status(canister, subnet_size) {
+                match canister.next_execution() {
+                    NextExecution::ContinueLong | NextExecution::ContinueInstallCode => {
+                        // The hook stays ready and is retried in a later round.
+                    }
+                    NextExecution::None | NextExecution::StartNew => {
+                        canister
+                            .system_state
+                            .task_queue
+                            .push_front(ExecutionTask::OnLowCycles);
+                        heartbeat_and_timer_canister_ids.insert(canister.canister_id());
+                    }
+                }
+            }
+
             let may_schedule_heartbeat = canister.exports_heartbeat_method();
             let may_schedule_global_timer = canister.exports_global_timer_method()
                 && canister.system_state.global_timer.has_reached_deadline(now);
@@ -637,6 +653,41 @@ impl SchedulerImpl {
         )
     }
 
+    /// Updates the `canister_on_low_cycles` hook status of the canister based
+    /// on its current balance and returns true if the hook should be executed.
+    ///
+    /// The hook is due when the balance is below `low_cycles_threshold_multiplier`
+    /// times the freezing threshold in cycles. It runs at most once until the
+    /// balance recovers above that threshold.
+    fn update_on_low_cycles_hook_status(
+        &self,
+        canister: &mut CanisterState,
+        subnet_size: usize,
+    ) -> bool {
+        let multiplier = canister.system_state.low_cycles_threshold_multiplier;
+        if multiplier == 0 || !canister.exports_on_low_cycles() {
+            return false;
+        }
+        let freeze_threshold_cycles = self.cycles_account_manager.freeze_threshold_cycles(
+            canister.system_state.freeze_threshold,
+            canister.memory_allocation(),
+            canister.memory_usage(),
+            canister.message_memory_usage(),
+            canister.compute_allocation(),
+            subnet_size,
+            canister.system_state.reserved_balance(),
+        );
+        let low_cycles_threshold = Cycles::new(
+            freeze_threshold_cycles
+                .get()
+                .saturating_mul(multiplier as u128),
+        );
+        let is_below_threshold = canister.system_state.balance() < low_cycles_threshold;
+        let status = &mut canister.system_state.on_low_cycles_hook_status;
+        status.update(is_below_threshold);
+        status.is_ready()
+    }
+
     /// Performs multiple iterations of canister execution until the instruction
     /// limit per round is reached or the canisters become idle. The canisters
     /// are executed in parallel using the thread pool.
@@ -719,7 +770,7 @@ impl SchedulerImpl {
                 (
                     heartbeat_and_timer_canister_ids,
                     non_zero_priority_credit_canister_ids,
-                ) = self.initialize_inner_round(&mut state)
+                ) = self.initialize_inner_round(&mut state, registry_settings.subnet_size)
             }
 
             // Update subnet available memory before taking out the canisters.
@@ -818,14 +869,15 @@ impl SchedulerImpl {
                 .metrics
                 .round_inner_heartbeat_overhead_duration
                 .start_timer();
-            // Remove all remaining `Heartbeat`, `GlobalTimer`, and `OnLowWasmMemory` tasks
-            // because they will be added again in the next round.
+            // Remove all remaining `Heartbeat`, `GlobalTimer`, `OnLowWasmMemory`, and
+            // `OnLowCycles` tasks because they will be added again in the next round.
             for canister_id in &heartbeat_and_timer_canister_ids {
                 let canister = state.canister_state_mut(canister_id).unwrap();
                 canister.system_state.task_queue.retain(|task| match task {
                     ExecutionTask::Heartbeat
                     | ExecutionTask::GlobalTimer
-                    | ExecutionTask::OnLowWasmMemory => false,
+                    | ExecutionTask::OnLowWasmMemory
+                    | ExecutionTask::OnLowCycles => false,
                     ExecutionTask::PausedExecution { .. }
                     | ExecutionTask::PausedInstallCode(..)
                     | ExecutionTask::AbortedExecution { .. }
@@ -1386,8 +1438,8 @@ impl SchedulerImpl {
             .iter()
             .filter(|(_, canister)| !canister.system_state.task_queue.is_empty());
 
-        // 1. Heartbeat, GlobalTimer, and OnLowWasmMemory tasks exist only during the round
-        //    and must not exist after the round.
+        // 1. Heartbeat, GlobalTimer, OnLowWasmMemory, and OnLowCycles tasks exist only during
+        //    the round and must not exist after the round.
         // 2. Paused executions can exist only in ordinary rounds (not checkpoint rounds).
         // 3. If deterministic time slicing is disabled, then there are no paused tasks.
         //    Aborted tasks may still exist if DTS was disabled in recent checkpoints.
@@ -1418,6 +1470,12 @@ impl SchedulerImpl {
                             id
                         );
                     }
+                    ExecutionTask::OnLowCycles => {
+                        panic!(
+                            "Unexpected on low cycles task after a round in canister {:?}",
+                            id
+                        );
+                    }
                     ExecutionTask::PausedExecution { .. } | ExecutionTask::PausedInstallCode(_) => {
                         assert_eq!(
                             self.deterministic_time_slicing,
@@ -2035,6 +2093,7 @@ fn execute_canisters_on_thread(
                 heap_delta,
                 ingress_status,
                 description,
+                trace,
             } = execute_canister(
                 exec_env,
                 canister,
@@ -2046,6 +2105,9 @@ fn execute_canisters_on_thread(
                 subnet_size,
             );
             ingress_results.extend(ingress_status);
+            if let (Some(tracer), Some(trace)) = (exec_env.execution_tracer(), trace) {
+                tracer.record(round_id, trace);
+            }
             let round_instructions_executed =
                 as_num_instructions(instructions_before - round_limits.instructions);
             let messages = NumMessages::from(instructions_used.map(|_| 1).unwrap_or(0));
@@ -2176,6 +2238,7 @@ fn observe_replicated_state_metrics(
             Some(&ExecutionTask::Heartbeat)
             | Some(&ExecutionTask::GlobalTimer)
             | Some(&ExecutionTask::OnLowWasmMemory)
+            | Some(&ExecutionTask::OnLowCycles)
             | None => {}
         }
         consumed_cycles_total += canister.system_state.canister_metrics.consumed_cycles;
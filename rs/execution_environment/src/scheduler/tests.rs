@@ -23,7 +23,9 @@ use ic_management_canister_types::{
 };
 use ic_registry_routing_table::CanisterIdRange;
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::canister_state::system_state::{CyclesUseCase, PausedExecutionId};
+use ic_replicated_state::canister_state::system_state::{
+    CyclesUseCase, OnLowCyclesHookStatus, PausedExecutionId,
+};
 use ic_replicated_state::testing::{CanisterQueuesTesting, SystemStateTesting};
 use ic_state_machine_tests::{PayloadBuilder, StateMachineBuilder};
 use ic_test_utilities_metrics::{
@@ -2034,6 +2036,62 @@ fn global_timer_is_not_scheduled_if_global_timer_method_is_not_exported() {
     assert_eq!(metrics.round_inner.messages.get_sample_sum(), 1.0);
 }
 
+#[test]
+fn execute_on_low_cycles_once_when_balance_is_below_threshold() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let canister = test.create_canister_with(
+        Cycles::new(1_000_000_000_000),
+        ComputeAllocation::try_from(10).unwrap(),
+        MemoryAllocation::BestEffort,
+        Some(SystemMethod::CanisterOnLowCycles),
+        None,
+        None,
+    );
+    test.canister_state_mut(canister)
+        .system_state
+        .low_cycles_threshold_multiplier = 1_000_000;
+
+    test.expect_on_low_cycles(canister, instructions(1));
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    let metrics = &test.scheduler().metrics;
+    assert_eq!(metrics.round_inner.messages.get_sample_sum(), 1.0);
+    assert_eq!(
+        test.canister_state(canister)
+            .system_state
+            .on_low_cycles_hook_status,
+        OnLowCyclesHookStatus::Executed
+    );
+
+    // The balance is still below the threshold, so the hook must not run again.
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    let metrics = &test.scheduler().metrics;
+    assert_eq!(metrics.round_inner.messages.get_sample_sum(), 1.0);
+}
+
+#[test]
+fn on_low_cycles_is_not_scheduled_if_threshold_multiplier_is_zero() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let canister = test.create_canister_with(
+        Cycles::new(1_000_000_000_000),
+        ComputeAllocation::try_from(10).unwrap(),
+        MemoryAllocation::BestEffort,
+        Some(SystemMethod::CanisterOnLowCycles),
+        None,
+        None,
+    );
+
+    test.send_ingress(canister, ingress(1));
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    let metrics = &test.scheduler().metrics;
+    assert_eq!(metrics.round_inner.messages.get_sample_sum(), 1.0);
+    assert_eq!(
+        test.canister_state(canister)
+            .system_state
+            .on_low_cycles_hook_status,
+        OnLowCyclesHookStatus::ConditionNotSatisfied
+    );
+}
+
 #[test]
 fn heartbeat_is_not_scheduled_if_the_canister_is_stopped() {
     let mut test = SchedulerTestBuilder::new().build();
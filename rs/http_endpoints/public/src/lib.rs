@@ -14,6 +14,7 @@ mod pprof;
 mod query;
 mod read_state;
 mod status;
+mod subscribe;
 mod tracing_flamegraph;
 
 cfg_if::cfg_if! {
@@ -45,6 +46,7 @@ use crate::{
     },
     pprof::{PprofFlamegraphService, PprofHomeService, PprofProfileService},
     status::StatusService,
+    subscribe::CanisterSubscribeServiceBuilder,
     tracing_flamegraph::TracingFlamegraphService,
 };
 
@@ -150,6 +152,7 @@ struct HttpHandler {
     status_router: Router,
     canister_read_state_router: Router,
     subnet_read_state_router: Router,
+    canister_subscribe_router: Router,
     pprof_home_router: Router,
     pprof_profile_router: Router,
     pprof_flamegraph_router: Router,
@@ -400,6 +403,14 @@ pub fn start_server(
         SubnetReadStateServiceBuilder::builder(delegation_from_nns.clone(), state_reader.clone())
             .with_health_status(health_status.clone())
             .build_router();
+    let canister_subscribe_router = CanisterSubscribeServiceBuilder::builder(
+        log.clone(),
+        state_reader.clone(),
+        delegation_from_nns.clone(),
+        config.max_subscribe_concurrent_connections,
+    )
+    .with_health_status(health_status.clone())
+    .build_router();
     let status_router = StatusService::build_router(
         log.clone(),
         nns_subnet_id,
@@ -448,6 +459,7 @@ pub fn start_server(
         dashboard_router,
         canister_read_state_router,
         subnet_read_state_router,
+        canister_subscribe_router,
         pprof_home_router,
         pprof_profile_router,
         pprof_flamegraph_router,
@@ -662,6 +674,9 @@ fn make_router(
                     )),
             ),
         )
+        // The number of open subscriptions is limited by the subscribe service
+        // itself, for as long as each stream is open.
+        .merge(http_handler.canister_subscribe_router)
         .merge(
             http_handler.catchup_router.layer(
                 ServiceBuilder::new()
@@ -1107,6 +1122,7 @@ async fn get_random_node_from_nns_subnet(
 #[cfg(test)]
 mod tests {
     use crate::read_state::subnet::SubnetReadStateService;
+    use crate::subscribe::CanisterSubscribeService;
     use bytes::Bytes;
     use futures_util::{future::select_all, stream::pending, FutureExt};
     use http::{
@@ -1157,6 +1173,8 @@ mod tests {
             ),
             subnet_read_state_router: Router::new()
                 .route(SubnetReadStateService::route(), axum::routing::post(dummy)),
+            canister_subscribe_router: Router::new()
+                .route(CanisterSubscribeService::route(), axum::routing::get(dummy)),
             pprof_home_router: Router::new()
                 .route(PprofHomeService::route(), axum::routing::get(dummy)),
             pprof_profile_router: Router::new()
@@ -0,0 +1,276 @@
+//! Module that deals with requests to /api/v2/canister/.../subscribe
+//!
+//! A subscription is a long-lived server-sent events stream. Each event carries
+//! a certificate for the `canister/<id>/certified_data` path and is emitted
+//! whenever the certified data of the canister differs from the last one sent
+//! to the client, starting with the current value.
+
+use crate::{common::into_cbor, ReplicaHealthStatus};
+
+use axum::{
+    extract::State,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Router,
+};
+use crossbeam::atomic::AtomicCell;
+use futures::stream::{self, Stream};
+use hyper::StatusCode;
+use ic_crypto_tree_hash::{sparse_labeled_tree_from_paths, Label, Path};
+use ic_interfaces_state_manager::StateReader;
+use ic_logger::{warn, ReplicaLogger};
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    messages::{Blob, Certificate, CertificateDelegation},
+    CanisterId, Height,
+};
+use std::convert::Infallible;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// How often a subscription checks whether a new height has been certified.
+const CERTIFIED_HEIGHT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone)]
+pub(crate) struct CanisterSubscribeService {
+    log: ReplicaLogger,
+    health_status: Arc<AtomicCell<ReplicaHealthStatus>>,
+    delegation_from_nns: Arc<RwLock<Option<CertificateDelegation>>>,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    // The response future of a streaming endpoint completes as soon as the
+    // headers are sent, so a `GlobalConcurrencyLimitLayer` would release its
+    // permit long before the connection is closed. Instead, each stream holds
+    // a permit of this semaphore for as long as the client stays subscribed.
+    connection_limiter: Arc<Semaphore>,
+}
+
+pub(crate) struct CanisterSubscribeServiceBuilder {
+    log: ReplicaLogger,
+    health_status: Option<Arc<AtomicCell<ReplicaHealthStatus>>>,
+    delegation_from_nns: Arc<RwLock<Option<CertificateDelegation>>>,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    max_concurrent_connections: usize,
+}
+
+impl CanisterSubscribeService {
+    pub(crate) fn route() -> &'static str {
+        "/api/v2/canister/:effective_canister_id/subscribe"
+    }
+}
+
+impl CanisterSubscribeServiceBuilder {
+    pub(crate) fn builder(
+        log: ReplicaLogger,
+        state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        delegation_from_nns: Arc<RwLock<Option<CertificateDelegation>>>,
+        max_concurrent_connections: usize,
+    ) -> Self {
+        Self {
+            log,
+            health_status: None,
+            delegation_from_nns,
+            state_reader,
+            max_concurrent_connections,
+        }
+    }
+
+    pub(crate) fn with_health_status(
+        mut self,
+        health_status: Arc<AtomicCell<ReplicaHealthStatus>>,
+    ) -> Self {
+        self.health_status = Some(health_status);
+        self
+    }
+
+    pub(crate) fn build_router(self) -> Router {
+        let state = CanisterSubscribeService {
+            log: self.log,
+            health_status: self
+                .health_status
+                .unwrap_or_else(|| Arc::new(AtomicCell::new(ReplicaHealthStatus::Healthy))),
+            delegation_from_nns: self.delegation_from_nns,
+            state_reader: self.state_reader,
+            connection_limiter: Arc::new(Semaphore::new(self.max_concurrent_connections)),
+        };
+        Router::new().route(
+            CanisterSubscribeService::route(),
+            axum::routing::get(canister_subscribe).with_state(state),
+        )
+    }
+}
+
+pub(crate) async fn canister_subscribe(
+    axum::extract::Path(effective_canister_id): axum::extract::Path<CanisterId>,
+    State(CanisterSubscribeService {
+        log,
+        health_status,
+        delegation_from_nns,
+        state_reader,
+        connection_limiter,
+    }): State<CanisterSubscribeService>,
+) -> Response {
+    if health_status.load() != ReplicaHealthStatus::Healthy {
+        let status = StatusCode::SERVICE_UNAVAILABLE;
+        let text = format!(
+            "Replica is unhealthy: {:?}. Check the /api/v2/status for more information.",
+            health_status.load(),
+        );
+        return (status, text).into_response();
+    }
+
+    let permit = match connection_limiter.try_acquire_owned() {
+        Ok(permit) => permit,
+        Err(_) => {
+            let status = StatusCode::TOO_MANY_REQUESTS;
+            let text = "The service is overloaded.".to_string();
+            return (status, text).into_response();
+        }
+    };
+
+    let subscription = Subscription {
+        log,
+        canister_id: effective_canister_id,
+        delegation_from_nns,
+        state_reader,
+        last_height: None,
+        last_certified_data: None,
+        _permit: permit,
+    };
+    Sse::new(certified_data_events(subscription))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// The state of a single subscription. Dropping it, which happens when the
+/// client disconnects, releases the connection permit.
+struct Subscription {
+    log: ReplicaLogger,
+    canister_id: CanisterId,
+    delegation_from_nns: Arc<RwLock<Option<CertificateDelegation>>>,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    /// The last certified height that was inspected.
+    last_height: Option<Height>,
+    /// The certified data sent in the last event, `Some(None)` if the
+    /// canister did not exist at that height.
+    last_certified_data: Option<Option<Vec<u8>>>,
+    _permit: OwnedSemaphorePermit,
+}
+
+/// A certificate for the certified data of a canister at a certified height.
+struct CertifiedDataUpdate {
+    height: Height,
+    certified_data: Option<Vec<u8>>,
+    certificate: Vec<u8>,
+}
+
+enum PollResult {
+    /// The certified data is the same as in the last event.
+    Unchanged(Height),
+    Changed(CertifiedDataUpdate),
+    /// No certified state is available yet.
+    Unavailable,
+}
+
+fn certified_data_events(
+    subscription: Subscription,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    stream::unfold(subscription, |mut subscription| async move {
+        loop {
+            let certified_height = subscription.state_reader.latest_certified_height();
+            if subscription.last_height < Some(certified_height) {
+                let state_reader = subscription.state_reader.clone();
+                let delegation_from_nns = subscription.delegation_from_nns.read().unwrap().clone();
+                let canister_id = subscription.canister_id;
+                let last_certified_data = subscription.last_certified_data.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    poll_certified_data(
+                        state_reader.as_ref(),
+                        canister_id,
+                        delegation_from_nns,
+                        last_certified_data,
+                    )
+                })
+                .await;
+
+                match result {
+                    Ok(PollResult::Changed(update)) => {
+                        subscription.last_height = Some(update.height);
+                        subscription.last_certified_data = Some(update.certified_data);
+                        let event = Event::default()
+                            .event("certificate")
+                            .id(update.height.get().to_string())
+                            .data(base64::encode(update.certificate));
+                        return Some((Ok(event), subscription));
+                    }
+                    Ok(PollResult::Unchanged(height)) => {
+                        subscription.last_height = Some(height);
+                    }
+                    Ok(PollResult::Unavailable) => {}
+                    Err(err) => {
+                        warn!(
+                            subscription.log,
+                            "Closing subscription to {}: {}", subscription.canister_id, err
+                        );
+                        return None;
+                    }
+                }
+            }
+            tokio::time::sleep(CERTIFIED_HEIGHT_POLL_INTERVAL).await;
+        }
+    })
+}
+
+/// Reads the certified data of `canister_id` from the latest certified state
+/// and returns a certificate for it if it differs from `last_certified_data`.
+fn poll_certified_data(
+    state_reader: &dyn StateReader<State = ReplicatedState>,
+    canister_id: CanisterId,
+    delegation_from_nns: Option<CertificateDelegation>,
+    last_certified_data: Option<Option<Vec<u8>>>,
+) -> PollResult {
+    let certified_state_reader = match state_reader.get_certified_state_snapshot() {
+        Some(reader) => reader,
+        None => return PollResult::Unavailable,
+    };
+    let height = certified_state_reader.get_height();
+
+    let certified_data = certified_state_reader
+        .get_state()
+        .canister_state(&canister_id)
+        .map(|canister| canister.system_state.certified_data.clone());
+    if last_certified_data.as_ref() == Some(&certified_data) {
+        return PollResult::Unchanged(height);
+    }
+
+    // For a canister that does not exist the certificate proves the absence
+    // of the path.
+    let paths = vec![
+        Path::from(Label::from("time")),
+        Path::new(vec![
+            Label::from("canister"),
+            Label::from(canister_id.get_ref().as_slice()),
+            Label::from("certified_data"),
+        ]),
+    ];
+    let labeled_tree =
+        sparse_labeled_tree_from_paths(&paths).expect("the subscription paths are not too long");
+    let (tree, certification) = match certified_state_reader.read_certified_state(&labeled_tree) {
+        Some(r) => r,
+        None => return PollResult::Unavailable,
+    };
+
+    let signature = certification.signed.signature.signature.get().0;
+    let certificate = into_cbor(&Certificate {
+        tree,
+        signature: Blob(signature),
+        delegation: delegation_from_nns,
+    });
+    PollResult::Changed(CertifiedDataUpdate {
+        height,
+        certified_data,
+        certificate,
+    })
+}
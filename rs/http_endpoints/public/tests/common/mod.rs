@@ -788,4 +788,27 @@ pub mod test_agent {
                 .unwrap()
         }
     }
+
+    #[derive(Default)]
+    pub struct Subscribe {
+        effective_canister_id: PrincipalId,
+    }
+
+    impl Subscribe {
+        pub fn new(effective_canister_id: PrincipalId) -> Self {
+            Self {
+                effective_canister_id,
+            }
+        }
+
+        /// Opens a subscription. The response body is a stream of server-sent events.
+        pub async fn subscribe(self, addr: SocketAddr) -> reqwest::Response {
+            let url = format!(
+                "http://{}/api/v2/canister/{}/subscribe",
+                addr, self.effective_canister_id
+            );
+
+            reqwest::Client::new().get(url).send().await.unwrap()
+        }
+    }
 }
@@ -197,6 +197,59 @@ fn test_load_shedding_read_state() {
     });
 }
 
+/// Test that the number of open `/subscribe` streams is limited and that further
+/// subscriptions are rejected with 429 until a stream is closed.
+/// Test scenario:
+/// 1. Set `max_subscribe_concurrent_connections` to 1.
+/// 2. Open a subscription and keep it open. A second subscription is rejected.
+/// 3. Close the first subscription. A new subscription is accepted.
+#[test]
+fn test_load_shedding_subscribe() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+
+    let config = Config {
+        listen_addr: addr,
+        max_subscribe_concurrent_connections: 1,
+        ..Default::default()
+    };
+
+    let _ = HttpEndpointBuilder::new(rt.handle().clone(), config).run();
+
+    rt.block_on(async {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        let mut subscription = test_agent::Subscribe::default().subscribe(addr).await;
+        assert_eq!(StatusCode::OK, subscription.status());
+        // Wait for the first event so that the stream is known to be open.
+        assert!(subscription.chunk().await.unwrap().is_some());
+
+        let response = test_agent::Subscribe::default().subscribe(addr).await;
+        assert_eq!(
+            StatusCode::TOO_MANY_REQUESTS,
+            response.status(),
+            "Load shedder did not kick in."
+        );
+
+        drop(subscription);
+
+        // The permit is released once the server notices the closed connection,
+        // at the latest when it sends the next keep-alive.
+        let mut status = StatusCode::TOO_MANY_REQUESTS;
+        for _ in 0..200 {
+            status = test_agent::Subscribe::default()
+                .subscribe(addr)
+                .await
+                .status();
+            if status != StatusCode::TOO_MANY_REQUESTS {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        assert_eq!(StatusCode::OK, status);
+    });
+}
+
 /// Test concurrency limiter for `/_/pprof` endpoints, and that when the load shedder kicks in
 /// we return 429.
 /// Test scenario:
@@ -661,6 +661,50 @@ fn test_too_long_paths_are_rejected() {
     });
 }
 
+/// Test that a subscription immediately streams a certificate for the certified data of
+/// the canister as a server-sent event.
+#[test]
+fn test_subscribe_streams_certificate() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+    let config = Config {
+        listen_addr: addr,
+        ..Default::default()
+    };
+
+    HttpEndpointBuilder::new(rt.handle().clone(), config).run();
+
+    rt.block_on(async move {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        let mut response = test_agent::Subscribe::default().subscribe(addr).await;
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            "text/event-stream",
+            response.headers().get(CONTENT_TYPE).unwrap()
+        );
+
+        let chunk = response.chunk().await.unwrap().unwrap();
+        let event = std::str::from_utf8(&chunk).unwrap();
+        let field = |name: &str| {
+            event
+                .lines()
+                .find_map(|line| line.strip_prefix(&format!("{}:", name)))
+                .map(str::trim)
+                .unwrap_or_else(|| panic!("event {:?} has no {} field", event, name))
+        };
+
+        assert_eq!("certificate", field("event"));
+        assert_eq!(
+            default_latest_certified_height().get().to_string(),
+            field("id")
+        );
+        let certificate = base64::decode(field("data")).unwrap();
+        serde_cbor::from_slice::<Certificate>(&certificate).unwrap();
+    });
+}
+
 /// This test verifies that the http endpoint returns 503 (SERVICE_UNAVAILABLE) when the
 /// per canister certified state is unavailable. I.e. when the
 /// [`QueryExecutionService`](ic_interfaces::execution_environment::QueryExecutionService)
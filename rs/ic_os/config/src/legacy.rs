@@ -0,0 +1,343 @@
+//! Migration from the legacy configuration: the `config.ini` key-value file and the
+//! `deployment.json` file.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+
+use utils::deployment::DeploymentJson;
+
+use crate::types::*;
+use crate::{hostos_config_from_setupos_config, ConfigMap};
+
+/// The CPU mode of the GuestOS virtual machine when deployment.json does not specify one.
+pub const DEFAULT_VM_CPU: &str = "kvm";
+
+/// Builds a SetupOS configuration from the legacy configuration files and validates it.
+///
+/// This is where a node's configuration is created, so it is the only place that rejects
+/// invalid configurations.
+pub fn setupos_config_from_legacy(
+    config_map: &ConfigMap,
+    deployment: &DeploymentJson,
+    node_operator_private_key_path: Option<PathBuf>,
+    ssh_authorized_keys_path: Option<PathBuf>,
+) -> Result<SetupOSConfig> {
+    let config = config_from_legacy(
+        config_map,
+        deployment,
+        node_operator_private_key_path,
+        ssh_authorized_keys_path,
+    )?;
+    config.validate()?;
+
+    Ok(config)
+}
+
+/// Builds a HostOS configuration from the legacy configuration files.
+///
+/// Used on nodes that were installed before SetupOS wrote a HostOS configuration object. The
+/// configuration is not validated, as these nodes were set up before validation existed.
+pub fn hostos_config_from_legacy(
+    config_map: &ConfigMap,
+    deployment: &DeploymentJson,
+    node_operator_private_key_path: Option<PathBuf>,
+    ssh_authorized_keys_path: Option<PathBuf>,
+) -> Result<HostOSConfig> {
+    let setupos_config = config_from_legacy(
+        config_map,
+        deployment,
+        node_operator_private_key_path,
+        ssh_authorized_keys_path,
+    )?;
+    Ok(hostos_config_from_setupos_config(setupos_config))
+}
+
+fn config_from_legacy(
+    config_map: &ConfigMap,
+    deployment: &DeploymentJson,
+    node_operator_private_key_path: Option<PathBuf>,
+    ssh_authorized_keys_path: Option<PathBuf>,
+) -> Result<SetupOSConfig> {
+    Ok(SetupOSConfig {
+        config_version: CONFIG_VERSION,
+        network_settings: network_settings_from_config_map(config_map)?,
+        icos_settings: ICOSSettings {
+            deployment_name: deployment.deployment.name.clone(),
+            mgmt_mac: config_map.get("mgmt_mac").cloned(),
+            nns_urls: deployment.nns.url.clone(),
+            elasticsearch_hosts: deployment.logging.hosts.clone(),
+        },
+        hostos_settings: HostOSSettings {
+            vm_memory: deployment.resources.memory,
+            vm_cpu: deployment
+                .resources
+                .cpu
+                .clone()
+                .unwrap_or_else(|| DEFAULT_VM_CPU.to_string()),
+            verbose: config_map
+                .get("verbose")
+                .is_some_and(|verbose| verbose.eq_ignore_ascii_case("true")),
+            node_operator_private_key_path,
+            ssh_authorized_keys_path,
+        },
+    })
+}
+
+/// Builds the network settings from config.ini alone, for the tools that can do without
+/// deployment.json.
+pub fn network_settings_from_config_map(config_map: &ConfigMap) -> Result<NetworkSettings> {
+    let ipv6_gateway = config_map
+        .get("ipv6_gateway")
+        .context("Missing config parameter: ipv6_gateway")?;
+    let ipv6_gateway = ipv6_gateway
+        .parse::<Ipv6Addr>()
+        .with_context(|| format!("Invalid ipv6 gateway: {}", ipv6_gateway))?;
+
+    // Optional ipv6_address - for testing. Takes precedence over ipv6_prefix, which is kept
+    // alongside it to derive the addresses of other nodes, e.g., GuestOS.
+    let ipv6_config = match (
+        config_map.get("ipv6_address"),
+        config_map.get("ipv6_prefix"),
+    ) {
+        (Some(address), prefix) => {
+            let suffix = format!("/{}", IPV6_PREFIX_LENGTH);
+            let address = address.strip_suffix(&suffix).unwrap_or(address);
+            Ipv6Config::Fixed(FixedIpv6Config {
+                address: format!("{address}{suffix}"),
+                gateway: ipv6_gateway,
+                prefix: prefix.cloned(),
+            })
+        }
+        (None, Some(prefix)) => Ipv6Config::Deterministic(DeterministicIpv6Config {
+            prefix: prefix.clone(),
+            prefix_length: IPV6_PREFIX_LENGTH,
+            gateway: ipv6_gateway,
+        }),
+        (None, None) => {
+            bail!("Missing config parameter: need at least one of ipv6_prefix or ipv6_address")
+        }
+    };
+
+    let ipv4_config = match (
+        config_map.get("ipv4_address"),
+        config_map.get("ipv4_prefix_length"),
+        config_map.get("ipv4_gateway"),
+    ) {
+        (Some(address), Some(prefix_length), Some(gateway)) => Some(Ipv4Config {
+            address: address
+                .parse::<Ipv4Addr>()
+                .with_context(|| format!("Invalid ipv4 address: {}", address))?,
+            gateway: gateway
+                .parse::<Ipv4Addr>()
+                .with_context(|| format!("Invalid ipv4 gateway: {}", gateway))?,
+            prefix_length: prefix_length
+                .parse::<u8>()
+                .with_context(|| format!("Invalid ipv4 prefix length: {}", prefix_length))?,
+        }),
+        (None, None, None) => None,
+        _ => bail!(
+            "Incomplete configuration - an ipv4_address, ipv4_prefix_length, and ipv4_gateway \
+             are required. Please specify all."
+        ),
+    };
+
+    Ok(NetworkSettings {
+        ipv6_config,
+        ipv4_config,
+        domain: config_map.get("domain").cloned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_HOSTOS_SSH_AUTHORIZED_KEYS_PATH;
+    use std::collections::HashMap;
+    use utils::deployment::{Deployment, Logging, Nns, Resources};
+
+    fn deployment() -> DeploymentJson {
+        DeploymentJson {
+            deployment: Deployment {
+                name: "mainnet".to_string(),
+            },
+            logging: Logging {
+                hosts: "elasticsearch.example.com:443".to_string(),
+            },
+            nns: Nns {
+                url: vec!["https://icp-api.io".parse().unwrap()],
+            },
+            resources: Resources {
+                memory: 490,
+                cpu: None,
+            },
+        }
+    }
+
+    fn config_map(entries: &[(&str, &str)]) -> ConfigMap {
+        entries
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>()
+    }
+
+    #[test]
+    fn test_setupos_config_from_legacy() {
+        let config_map = config_map(&[
+            ("ipv6_prefix", "2a00:fb01:400:200"),
+            ("ipv6_gateway", "2a00:fb01:400:200::1"),
+            ("ipv4_address", "212.71.124.178"),
+            ("ipv4_prefix_length", "28"),
+            ("ipv4_gateway", "212.71.124.177"),
+            ("domain", "node1.example.com"),
+            ("verbose", "true"),
+        ]);
+
+        let config = setupos_config_from_legacy(&config_map, &deployment(), None, None).unwrap();
+
+        assert_eq!(
+            config.network_settings,
+            NetworkSettings {
+                ipv6_config: Ipv6Config::Deterministic(DeterministicIpv6Config {
+                    prefix: "2a00:fb01:400:200".to_string(),
+                    prefix_length: 64,
+                    gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+                }),
+                ipv4_config: Some(Ipv4Config {
+                    address: "212.71.124.178".parse().unwrap(),
+                    gateway: "212.71.124.177".parse().unwrap(),
+                    prefix_length: 28,
+                }),
+                domain: Some("node1.example.com".to_string()),
+            }
+        );
+        assert_eq!(
+            config.hostos_settings,
+            HostOSSettings {
+                vm_memory: 490,
+                vm_cpu: "kvm".to_string(),
+                verbose: true,
+                node_operator_private_key_path: None,
+                ssh_authorized_keys_path: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_hostos_config_from_legacy_uses_hostos_key_paths() {
+        let config_map = config_map(&[
+            ("ipv6_prefix", "2a00:fb01:400:200"),
+            ("ipv6_gateway", "2a00:fb01:400:200::1"),
+        ]);
+
+        let config = hostos_config_from_legacy(
+            &config_map,
+            &deployment(),
+            None,
+            Some(PathBuf::from(DEFAULT_HOSTOS_SSH_AUTHORIZED_KEYS_PATH)),
+        )
+        .unwrap();
+
+        assert_eq!(config.hostos_settings.node_operator_private_key_path, None);
+        assert_eq!(
+            config.hostos_settings.ssh_authorized_keys_path,
+            Some(PathBuf::from(DEFAULT_HOSTOS_SSH_AUTHORIZED_KEYS_PATH))
+        );
+    }
+
+    #[test]
+    fn test_setupos_config_from_legacy_prefers_ipv6_address() {
+        let config_map = config_map(&[
+            ("ipv6_prefix", "2a00:fb01:400:200"),
+            ("ipv6_address", "2a00:fb01:400:200::3"),
+            ("ipv6_gateway", "2a00:fb01:400:200::1"),
+        ]);
+
+        let config = setupos_config_from_legacy(&config_map, &deployment(), None, None).unwrap();
+
+        assert_eq!(
+            config.network_settings.ipv6_config,
+            Ipv6Config::Fixed(FixedIpv6Config {
+                address: "2a00:fb01:400:200::3/64".to_string(),
+                gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+                prefix: Some("2a00:fb01:400:200".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_config_with_ipv6_address_and_prefix_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_ini = dir.path().join("config.ini");
+        std::fs::write(
+            &config_ini,
+            "ipv6_prefix=2a00:fb01:400:200\n\
+             ipv6_address=2a00:fb01:400:200::3\n\
+             ipv6_gateway=2a00:fb01:400:200::1\n",
+        )
+        .unwrap();
+
+        let config_map = crate::config_map_from_path(&config_ini).unwrap();
+        let config = hostos_config_from_legacy(&config_map, &deployment(), None, None).unwrap();
+        let config_json = dir.path().join("config.json");
+        crate::serialize_and_write_config(&config_json, &config).unwrap();
+        let config: HostOSConfig = crate::deserialize_config(&config_json).unwrap();
+
+        assert_eq!(
+            config.network_settings.ipv6_config,
+            Ipv6Config::Fixed(FixedIpv6Config {
+                address: "2a00:fb01:400:200::3/64".to_string(),
+                gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+                prefix: Some("2a00:fb01:400:200".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_hostos_config_from_legacy_does_not_validate() {
+        let config_map = config_map(&[
+            ("ipv6_prefix", "2a00:fb01:400:200"),
+            ("ipv6_gateway", "2a00:fb01:400:200::1"),
+            ("ipv4_address", "212.71.124.178"),
+            ("ipv4_prefix_length", "28"),
+            ("ipv4_gateway", "212.71.124.177"),
+        ]);
+
+        assert!(setupos_config_from_legacy(&config_map, &deployment(), None, None).is_err());
+        assert!(hostos_config_from_legacy(&config_map, &deployment(), None, None).is_ok());
+    }
+
+    #[test]
+    fn test_setupos_config_from_legacy_rejects_misconfiguration() {
+        for (entries, expected) in [
+            (vec![("ipv6_prefix", "2a00:fb01:400:200")], "ipv6_gateway"),
+            (
+                vec![("ipv6_gateway", "2a00:fb01:400:200::1")],
+                "need at least one of ipv6_prefix or ipv6_address",
+            ),
+            (
+                vec![
+                    ("ipv6_prefix", "2a00:fb01:400:200"),
+                    ("ipv6_gateway", "2a00:fb01:400:200::1"),
+                    ("ipv4_address", "212.71.124.178"),
+                ],
+                "Incomplete configuration",
+            ),
+            (
+                vec![
+                    ("ipv6_prefix", "2a00:fb01:400:200"),
+                    ("ipv6_gateway", "2a00:fb01:400:200::1"),
+                    ("ipv4_address", "212.71.124.178"),
+                    ("ipv4_prefix_length", "28"),
+                    ("ipv4_gateway", "212.71.124.177"),
+                ],
+                "domain is required",
+            ),
+        ] {
+            let err = setupos_config_from_legacy(&config_map(&entries), &deployment(), None, None)
+                .unwrap_err();
+            assert!(format!("{err:#}").contains(expected), "{err:#}");
+        }
+    }
+}
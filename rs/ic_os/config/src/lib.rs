@@ -1,8 +1,16 @@
+pub mod legacy;
+pub mod types;
+
 use std::collections::HashMap;
-use std::fs::read_to_string;
-use std::path::Path;
+use std::fs::{read_to_string, File};
+use std::net::Ipv6Addr;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
-use anyhow::{Context, Result};
+use crate::types::*;
 
 pub type ConfigMap = HashMap<String, String>;
 
@@ -12,6 +20,19 @@ pub static DEFAULT_SETUPOS_DEPLOYMENT_JSON_PATH: &str = "/data/deployment.json";
 pub static DEFAULT_HOSTOS_CONFIG_FILE_PATH: &str = "/boot/config/config.ini";
 pub static DEFAULT_HOSTOS_DEPLOYMENT_JSON_PATH: &str = "/boot/config/deployment.json";
 
+pub static DEFAULT_SETUPOS_CONFIG_OBJECT_PATH: &str = "/var/ic/config/config.json";
+pub static DEFAULT_SETUPOS_NODE_OPERATOR_PRIVATE_KEY_PATH: &str =
+    "/config/node_operator_private_key.pem";
+pub static DEFAULT_SETUPOS_SSH_AUTHORIZED_KEYS_PATH: &str = "/config/ssh_authorized_keys";
+
+pub static DEFAULT_HOSTOS_CONFIG_OBJECT_PATH: &str = "/boot/config/config.json";
+pub static DEFAULT_HOSTOS_NODE_OPERATOR_PRIVATE_KEY_PATH: &str =
+    "/boot/config/node_operator_private_key.pem";
+pub static DEFAULT_HOSTOS_SSH_AUTHORIZED_KEYS_PATH: &str = "/boot/config/ssh_authorized_keys";
+pub static DEFAULT_HOSTOS_GUESTOS_CONFIG_OBJECT_PATH: &str = "/boot/config/config-guestos.json";
+
+pub static DEFAULT_GUESTOS_CONFIG_OBJECT_PATH: &str = "/boot/config/config.json";
+
 fn parse_config_line(line: &str) -> Option<(String, String)> {
     // Skip blank lines and comments
     if line.is_empty() || line.trim().starts_with('#') {
@@ -36,3 +57,311 @@ pub fn config_map_from_path(config_file_path: &Path) -> Result<ConfigMap> {
         .filter_map(parse_config_line)
         .collect())
 }
+
+/// Writes a configuration object as JSON.
+pub fn serialize_and_write_config<T: Serialize>(path: &Path, config: &T) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("Error creating file: {}", path.display()))?;
+    serde_json::to_writer_pretty(file, config)
+        .with_context(|| format!("Error writing config to: {}", path.display()))
+}
+
+/// Reads a configuration object written by `serialize_and_write_config`, migrating it to the
+/// current schema version if needed.
+///
+/// The object is not validated again: it was validated when SetupOS created it, and a node that
+/// is already running must not stop booting because validation got stricter.
+pub fn deserialize_config<T: DeserializeOwned + VersionedConfig>(path: &Path) -> Result<T> {
+    let file_contents =
+        read_to_string(path).with_context(|| format!("Error reading file: {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&file_contents)
+        .with_context(|| format!("Invalid json content: {}", path.display()))?;
+    let config: T = serde_json::from_value(migrate(value)?)
+        .with_context(|| format!("Invalid config: {}", path.display()))?;
+    Ok(config)
+}
+
+/// Upgrades a configuration object of an older schema version to `CONFIG_VERSION`, one
+/// version at a time.
+fn migrate(mut value: serde_json::Value) -> Result<serde_json::Value> {
+    let mut config_version = value
+        .get("config_version")
+        .and_then(serde_json::Value::as_u64)
+        .context("Missing config parameter: config_version")?;
+    if config_version > u64::from(CONFIG_VERSION) {
+        bail!(
+            "Config version {} is newer than the supported version {}",
+            config_version,
+            CONFIG_VERSION
+        );
+    }
+
+    while config_version < u64::from(CONFIG_VERSION) {
+        value = match config_version {
+            1 => migrate_v1_to_v2(value)?,
+            version => bail!("Config version {} can no longer be migrated", version),
+        };
+        config_version += 1;
+        value["config_version"] = serde_json::Value::from(config_version);
+    }
+
+    Ok(value)
+}
+
+/// Moves the key paths from `icos_settings` to `hostos_settings`. GuestOS configuration objects
+/// have no `hostos_settings`, so the key paths are dropped from them.
+fn migrate_v1_to_v2(mut value: serde_json::Value) -> Result<serde_json::Value> {
+    let icos_settings = value
+        .get_mut("icos_settings")
+        .and_then(serde_json::Value::as_object_mut)
+        .context("Missing config parameter: icos_settings")?;
+    let key_paths: serde_json::Map<String, serde_json::Value> =
+        ["node_operator_private_key_path", "ssh_authorized_keys_path"]
+            .into_iter()
+            .map(|key| {
+                let path = icos_settings.remove(key).unwrap_or(serde_json::Value::Null);
+                (key.to_string(), path)
+            })
+            .collect();
+
+    if let Some(hostos_settings) = value
+        .get_mut("hostos_settings")
+        .and_then(serde_json::Value::as_object_mut)
+    {
+        hostos_settings.extend(key_paths);
+    }
+
+    Ok(value)
+}
+
+/// The HostOS configuration that SetupOS installs.
+///
+/// SetupOS copies the keys the node provider supplied to the HostOS config partition, so the
+/// HostOS configuration refers to the copies.
+pub fn hostos_config_from_setupos_config(setupos_config: SetupOSConfig) -> HostOSConfig {
+    let SetupOSConfig {
+        config_version,
+        network_settings,
+        icos_settings,
+        hostos_settings,
+    } = setupos_config;
+    let hostos_settings = HostOSSettings {
+        node_operator_private_key_path: hostos_settings
+            .node_operator_private_key_path
+            .map(|_| PathBuf::from(DEFAULT_HOSTOS_NODE_OPERATOR_PRIVATE_KEY_PATH)),
+        ssh_authorized_keys_path: hostos_settings
+            .ssh_authorized_keys_path
+            .map(|_| PathBuf::from(DEFAULT_HOSTOS_SSH_AUTHORIZED_KEYS_PATH)),
+        ..hostos_settings
+    };
+    HostOSConfig {
+        config_version,
+        network_settings,
+        icos_settings,
+        hostos_settings,
+    }
+}
+
+/// Generates the configuration of the GuestOS virtual machine that HostOS runs.
+///
+/// GuestOS does not derive its own IPv6 address: it is given the address that HostOS derived
+/// from the GuestOS MAC address.
+pub fn generate_guestos_config(
+    hostos_config: &HostOSConfig,
+    guestos_ipv6_address: Ipv6Addr,
+) -> Result<GuestOSConfig> {
+    let (ipv6_gateway, ipv6_prefix) = match &hostos_config.network_settings.ipv6_config {
+        Ipv6Config::Deterministic(config) => (config.gateway, Some(config.prefix.clone())),
+        Ipv6Config::Fixed(config) => (config.gateway, config.prefix.clone()),
+        Ipv6Config::RouterAdvertisement => {
+            bail!("Cannot generate a GuestOS config for a HostOS using router advertisements")
+        }
+    };
+
+    let guestos_config = GuestOSConfig {
+        config_version: CONFIG_VERSION,
+        network_settings: NetworkSettings {
+            ipv6_config: Ipv6Config::Fixed(FixedIpv6Config {
+                address: format!("{}/{}", guestos_ipv6_address, IPV6_PREFIX_LENGTH),
+                gateway: ipv6_gateway,
+                prefix: ipv6_prefix,
+            }),
+            ..hostos_config.network_settings.clone()
+        },
+        icos_settings: hostos_config.icos_settings.clone(),
+    };
+
+    Ok(guestos_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hostos_config() -> HostOSConfig {
+        HostOSConfig {
+            config_version: CONFIG_VERSION,
+            network_settings: NetworkSettings {
+                ipv6_config: Ipv6Config::Deterministic(DeterministicIpv6Config {
+                    prefix: "2a00:fb01:400:200".to_string(),
+                    prefix_length: 64,
+                    gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+                }),
+                ipv4_config: None,
+                domain: None,
+            },
+            icos_settings: ICOSSettings {
+                deployment_name: "mainnet".to_string(),
+                mgmt_mac: None,
+                nns_urls: vec!["https://icp-api.io".parse().unwrap()],
+                elasticsearch_hosts: "elasticsearch.example.com:443".to_string(),
+            },
+            hostos_settings: HostOSSettings {
+                vm_memory: 490,
+                vm_cpu: "kvm".to_string(),
+                verbose: false,
+                node_operator_private_key_path: Some(PathBuf::from(
+                    DEFAULT_HOSTOS_NODE_OPERATOR_PRIVATE_KEY_PATH,
+                )),
+                ssh_authorized_keys_path: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_generate_guestos_config() {
+        let hostos_config = hostos_config();
+        let guestos_config = generate_guestos_config(
+            &hostos_config,
+            "2a00:fb01:400:200:6801:6bff:fe5e:b6e".parse().unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            guestos_config.network_settings.ipv6_config,
+            Ipv6Config::Fixed(FixedIpv6Config {
+                address: "2a00:fb01:400:200:6801:6bff:fe5e:b6e/64".to_string(),
+                gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+                prefix: Some("2a00:fb01:400:200".to_string()),
+            })
+        );
+        assert!(guestos_config.validate().is_ok());
+        assert_eq!(guestos_config.icos_settings, hostos_config.icos_settings);
+    }
+
+    #[test]
+    fn test_migrate_rejects_unknown_versions() {
+        let mut value = serde_json::to_value(hostos_config()).unwrap();
+        assert_eq!(migrate(value.clone()).unwrap(), value);
+
+        value["config_version"] = serde_json::json!(CONFIG_VERSION + 1);
+        assert!(migrate(value.clone()).is_err());
+
+        value["config_version"] = serde_json::json!(0);
+        let err = migrate(value.clone()).unwrap_err();
+        assert!(
+            err.to_string().contains("can no longer be migrated"),
+            "{err}"
+        );
+
+        value.as_object_mut().unwrap().remove("config_version");
+        let err = migrate(value).unwrap_err();
+        assert!(err.to_string().contains("config_version"), "{err}");
+    }
+
+    /// A HostOS configuration object as written by version 1.
+    fn hostos_config_v1() -> serde_json::Value {
+        serde_json::json!({
+            "config_version": 1,
+            "network_settings": {
+                "ipv6_config": {
+                    "Deterministic": {
+                        "prefix": "2a00:fb01:400:200",
+                        "prefix_length": 64,
+                        "gateway": "2a00:fb01:400:200::1"
+                    }
+                },
+                "ipv4_config": null,
+                "domain": null
+            },
+            "icos_settings": {
+                "deployment_name": "mainnet",
+                "mgmt_mac": null,
+                "nns_urls": ["https://icp-api.io/"],
+                "elasticsearch_hosts": "elasticsearch.example.com:443",
+                "node_operator_private_key_path": "/boot/config/node_operator_private_key.pem",
+                "ssh_authorized_keys_path": null
+            },
+            "hostos_settings": {
+                "vm_memory": 490,
+                "vm_cpu": "kvm",
+                "verbose": false
+            }
+        })
+    }
+
+    #[test]
+    fn test_migrate_hostos_config_from_v1() {
+        let config: HostOSConfig =
+            serde_json::from_value(migrate(hostos_config_v1()).unwrap()).unwrap();
+
+        assert_eq!(config, hostos_config());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_migrate_guestos_config_from_v1() {
+        let mut value = hostos_config_v1();
+        value.as_object_mut().unwrap().remove("hostos_settings");
+
+        let migrated = migrate(value).unwrap();
+        assert_eq!(
+            migrated["config_version"],
+            serde_json::json!(CONFIG_VERSION)
+        );
+        assert!(migrated["icos_settings"]
+            .get("node_operator_private_key_path")
+            .is_none());
+
+        let config: GuestOSConfig = serde_json::from_value(migrated).unwrap();
+        assert_eq!(config.icos_settings, hostos_config().icos_settings);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_deserialize_config_migrates_v1() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, hostos_config_v1().to_string()).unwrap();
+
+        let config: HostOSConfig = deserialize_config(&path).unwrap();
+
+        assert_eq!(config, hostos_config());
+    }
+
+    #[test]
+    fn test_hostos_config_from_setupos_config_uses_hostos_key_paths() {
+        let HostOSConfig {
+            config_version,
+            network_settings,
+            icos_settings,
+            hostos_settings,
+        } = hostos_config();
+        let setupos_config = SetupOSConfig {
+            config_version,
+            network_settings,
+            icos_settings,
+            hostos_settings: HostOSSettings {
+                node_operator_private_key_path: Some(PathBuf::from(
+                    DEFAULT_SETUPOS_NODE_OPERATOR_PRIVATE_KEY_PATH,
+                )),
+                ..hostos_settings
+            },
+        };
+
+        assert_eq!(
+            hostos_config_from_setupos_config(setupos_config),
+            hostos_config()
+        );
+    }
+}
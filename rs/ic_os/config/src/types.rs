@@ -0,0 +1,348 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
+
+use anyhow::{bail, ensure, Result};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// The version of the configuration schema written by this crate.
+///
+/// Bump this whenever a change to the types below is not backwards compatible, and teach
+/// `migrate` how to upgrade objects of the previous version.
+///
+/// Version history:
+/// 1. Initial version.
+/// 2. The key paths moved from `ICOSSettings` to `HostOSSettings`, as GuestOS has no use for
+///    them.
+pub const CONFIG_VERSION: u32 = 2;
+
+/// The prefix length of every IPv6 network that IC nodes are deployed in.
+pub const IPV6_PREFIX_LENGTH: u8 = 64;
+
+/// SetupOS configuration. Created from the files the node provider puts on the installation
+/// media, and used to install HostOS.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct SetupOSConfig {
+    pub config_version: u32,
+    pub network_settings: NetworkSettings,
+    pub icos_settings: ICOSSettings,
+    pub hostos_settings: HostOSSettings,
+}
+
+/// HostOS configuration. Written by SetupOS during installation.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct HostOSConfig {
+    pub config_version: u32,
+    pub network_settings: NetworkSettings,
+    pub icos_settings: ICOSSettings,
+    pub hostos_settings: HostOSSettings,
+}
+
+/// GuestOS configuration. Generated by HostOS from its own configuration before the GuestOS
+/// virtual machine is started.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct GuestOSConfig {
+    pub config_version: u32,
+    pub network_settings: NetworkSettings,
+    pub icos_settings: ICOSSettings,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct NetworkSettings {
+    pub ipv6_config: Ipv6Config,
+    pub ipv4_config: Option<Ipv4Config>,
+    /// Required when an IPv4 address is configured.
+    pub domain: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum Ipv6Config {
+    /// The address is derived from the prefix and the node's MAC address.
+    Deterministic(DeterministicIpv6Config),
+    /// The address is given explicitly, e.g., for GuestOS or for testnets.
+    Fixed(FixedIpv6Config),
+    /// The address is assigned through router advertisements.
+    RouterAdvertisement,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct DeterministicIpv6Config {
+    /// The network part of the address, e.g., "2a00:fb01:400:200".
+    pub prefix: String,
+    pub prefix_length: u8,
+    pub gateway: Ipv6Addr,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct FixedIpv6Config {
+    /// The address, with the prefix length, e.g., "2a00:fb01:400:200::3/64".
+    pub address: String,
+    pub gateway: Ipv6Addr,
+    /// The network part of the address, if known. HostOS derives the GuestOS address from it.
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct Ipv4Config {
+    pub address: Ipv4Addr,
+    pub gateway: Ipv4Addr,
+    pub prefix_length: u8,
+}
+
+/// Settings that all IC-OS variants share.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ICOSSettings {
+    /// The name of the deployment, e.g., "mainnet". Used to derive MAC addresses.
+    pub deployment_name: String,
+    /// The BMC's MAC address, used to derive the node's MAC addresses.
+    pub mgmt_mac: Option<String>,
+    pub nns_urls: Vec<Url>,
+    pub elasticsearch_hosts: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct HostOSSettings {
+    /// The memory of the GuestOS virtual machine, in GiB.
+    pub vm_memory: u32,
+    /// The CPU mode of the GuestOS virtual machine, e.g., "kvm" or "qemu".
+    pub vm_cpu: String,
+    /// Whether to log the GuestOS console to the HostOS terminal.
+    pub verbose: bool,
+    /// The node operator's private key, if the node provider supplied one. HostOS passes it on
+    /// to the GuestOS virtual machine.
+    pub node_operator_private_key_path: Option<PathBuf>,
+    pub ssh_authorized_keys_path: Option<PathBuf>,
+}
+
+/// Configuration objects that carry a schema version and can be validated.
+pub trait VersionedConfig {
+    fn config_version(&self) -> u32;
+    fn network_settings(&self) -> &NetworkSettings;
+    fn icos_settings(&self) -> &ICOSSettings;
+
+    /// Checks the configuration, so that a misconfigured node is rejected before it is set up
+    /// rather than failing later with a less obvious error.
+    fn validate(&self) -> Result<()> {
+        ensure!(
+            self.config_version() == CONFIG_VERSION,
+            "Unsupported config version {} (expected {})",
+            self.config_version(),
+            CONFIG_VERSION
+        );
+        self.network_settings().validate()?;
+        self.icos_settings().validate()
+    }
+}
+
+macro_rules! impl_versioned_config {
+    ($config:ty) => {
+        impl VersionedConfig for $config {
+            fn config_version(&self) -> u32 {
+                self.config_version
+            }
+            fn network_settings(&self) -> &NetworkSettings {
+                &self.network_settings
+            }
+            fn icos_settings(&self) -> &ICOSSettings {
+                &self.icos_settings
+            }
+        }
+    };
+}
+
+impl_versioned_config!(SetupOSConfig);
+impl_versioned_config!(HostOSConfig);
+impl_versioned_config!(GuestOSConfig);
+
+pub fn is_valid_ipv6_prefix(ipv6_prefix: &str) -> bool {
+    ipv6_prefix.len() <= 19 && format!("{ipv6_prefix}::").parse::<Ipv6Addr>().is_ok()
+}
+
+impl NetworkSettings {
+    pub fn validate(&self) -> Result<()> {
+        match &self.ipv6_config {
+            Ipv6Config::Deterministic(config) => {
+                ensure!(
+                    is_valid_ipv6_prefix(&config.prefix),
+                    "Invalid ipv6 prefix: {}",
+                    config.prefix
+                );
+                ensure!(
+                    config.prefix_length == IPV6_PREFIX_LENGTH,
+                    "Invalid ipv6 prefix length: {} (expected {})",
+                    config.prefix_length,
+                    IPV6_PREFIX_LENGTH
+                );
+            }
+            Ipv6Config::Fixed(config) => {
+                let Some((address, prefix_length)) = config.address.split_once('/') else {
+                    bail!(
+                        "Invalid ipv6 address: {} (expected a '/{}' suffix)",
+                        config.address,
+                        IPV6_PREFIX_LENGTH
+                    );
+                };
+                ensure!(
+                    address.parse::<Ipv6Addr>().is_ok(),
+                    "Invalid ipv6 address: {}",
+                    config.address
+                );
+                ensure!(
+                    prefix_length == IPV6_PREFIX_LENGTH.to_string(),
+                    "Invalid ipv6 prefix length: {} (expected {})",
+                    prefix_length,
+                    IPV6_PREFIX_LENGTH
+                );
+                if let Some(prefix) = &config.prefix {
+                    ensure!(
+                        is_valid_ipv6_prefix(prefix),
+                        "Invalid ipv6 prefix: {}",
+                        prefix
+                    );
+                }
+            }
+            Ipv6Config::RouterAdvertisement => {}
+        }
+
+        if let Some(ipv4_config) = &self.ipv4_config {
+            ipv4_config.validate()?;
+            let has_domain = self
+                .domain
+                .as_ref()
+                .is_some_and(|domain| !domain.trim().is_empty());
+            ensure!(
+                has_domain,
+                "Missing config parameter: domain is required when an ipv4 address is configured"
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl Ipv4Config {
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            (1..=32).contains(&self.prefix_length),
+            "Invalid ipv4 prefix length: {} (expected 1 to 32)",
+            self.prefix_length
+        );
+        let mask = u32::MAX << (32 - self.prefix_length);
+        ensure!(
+            u32::from(self.address) & mask == u32::from(self.gateway) & mask,
+            "Invalid ipv4 gateway: {} is not in the network of {}/{}",
+            self.gateway,
+            self.address,
+            self.prefix_length
+        );
+        Ok(())
+    }
+}
+
+impl ICOSSettings {
+    pub fn validate(&self) -> Result<()> {
+        ensure!(
+            !self.deployment_name.trim().is_empty(),
+            "Missing config parameter: deployment_name"
+        );
+        ensure!(
+            !self.nns_urls.is_empty(),
+            "Missing config parameter: at least one nns_url is required"
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network_settings() -> NetworkSettings {
+        NetworkSettings {
+            ipv6_config: Ipv6Config::Deterministic(DeterministicIpv6Config {
+                prefix: "2a00:fb01:400:200".to_string(),
+                prefix_length: 64,
+                gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+            }),
+            ipv4_config: None,
+            domain: None,
+        }
+    }
+
+    fn ipv4_config() -> Ipv4Config {
+        Ipv4Config {
+            address: "212.71.124.178".parse().unwrap(),
+            gateway: "212.71.124.177".parse().unwrap(),
+            prefix_length: 28,
+        }
+    }
+
+    #[test]
+    fn test_is_valid_ipv6_prefix() {
+        assert!(is_valid_ipv6_prefix("2a00:1111:1111:1111"));
+        assert!(is_valid_ipv6_prefix("2a00:111:11:11"));
+        assert!(is_valid_ipv6_prefix("2602:fb2b:100:10"));
+        assert!(!is_valid_ipv6_prefix("2a00:fb01:400:20g"));
+        assert!(!is_valid_ipv6_prefix("2a00:fb01:400:2000:1"));
+    }
+
+    #[test]
+    fn test_validate_network_settings() {
+        assert!(network_settings().validate().is_ok());
+
+        let mut settings = network_settings();
+        settings.ipv6_config = Ipv6Config::Deterministic(DeterministicIpv6Config {
+            prefix: "2a00:fb01:400:20g".to_string(),
+            prefix_length: 64,
+            gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+        });
+        assert!(settings.validate().is_err());
+
+        let mut settings = network_settings();
+        settings.ipv6_config = Ipv6Config::Fixed(FixedIpv6Config {
+            address: "2a00:fb01:400:200::3".to_string(),
+            gateway: "2a00:fb01:400:200::1".parse().unwrap(),
+            prefix: None,
+        });
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_ipv4_requires_domain_and_reachable_gateway() {
+        let mut settings = network_settings();
+        settings.ipv4_config = Some(ipv4_config());
+        let err = settings.validate().unwrap_err();
+        assert!(err.to_string().contains("domain is required"), "{err}");
+
+        settings.domain = Some("node1.example.com".to_string());
+        assert!(settings.validate().is_ok());
+
+        settings.ipv4_config = Some(Ipv4Config {
+            gateway: "212.71.124.1".parse().unwrap(),
+            ..ipv4_config()
+        });
+        let err = settings.validate().unwrap_err();
+        assert!(err.to_string().contains("not in the network"), "{err}");
+    }
+
+    #[test]
+    fn test_config_round_trips_through_json() {
+        let config = GuestOSConfig {
+            config_version: CONFIG_VERSION,
+            network_settings: network_settings(),
+            icos_settings: ICOSSettings {
+                deployment_name: "mainnet".to_string(),
+                mgmt_mac: None,
+                nns_urls: vec!["https://icp-api.io".parse().unwrap()],
+                elasticsearch_hosts: "elasticsearch.example.com:443".to_string(),
+            },
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<GuestOSConfig>(&json).unwrap(),
+            config
+        );
+        assert!(config.validate().is_ok());
+    }
+}
@@ -6,7 +6,8 @@ use std::str::FromStr;
 
 use anyhow::{bail, Context, Result};
 
-use config::config_map_from_path;
+use config::types::{GuestOSConfig, Ipv6Config};
+use config::{config_map_from_path, deserialize_config};
 use network::interfaces::{get_interface_name as get_valid_interface_name, get_interface_paths};
 use utils::get_command_stdout;
 
@@ -83,13 +84,17 @@ impl IpAddressInfo {
     }
 }
 
-/// Generate network configuration for systemd networkd based on the provided network configuration.
+/// Generate network configuration for systemd networkd based on the provided GuestOS configuration.
+///
+/// GuestOS virtual machines that were started by an older HostOS only have the legacy network
+/// configuration file, which is used if there is no GuestOS configuration object.
 pub fn generate_networkd_config(
+    guestos_config: &Path,
     network_config: &Path,
     systemd_network_dir: &Path,
     ipv4_info: Option<IpAddressInfo>,
 ) -> Result<()> {
-    eprintln!("Network config file: {}", network_config.display());
+    eprintln!("GuestOS config file: {}", guestos_config.display());
     eprintln!(
         "Systemd network directory: {}",
         systemd_network_dir.display()
@@ -98,10 +103,23 @@ pub fn generate_networkd_config(
 
     std::fs::create_dir_all(systemd_network_dir)?;
 
-    let network_config_variables: HashMap<String, String> = config_map_from_path(network_config)?;
-    eprintln!("Network parameters {:#?}", network_config_variables);
+    let network_info = if guestos_config.exists() {
+        let guestos_config: GuestOSConfig = deserialize_config(guestos_config)?;
+        eprintln!("GuestOS config: {:?}", guestos_config);
 
-    let network_info: NetworkInfo = create_network_info(&network_config_variables, ipv4_info)?;
+        create_network_info_from_config(&guestos_config, ipv4_info)?
+    } else {
+        eprintln!(
+            "{} does not exist, using network config file: {}",
+            guestos_config.display(),
+            network_config.display()
+        );
+        let network_config_variables: HashMap<String, String> =
+            config_map_from_path(network_config)?;
+        eprintln!("Network parameters {:#?}", network_config_variables);
+
+        create_network_info(&network_config_variables, ipv4_info)?
+    };
     eprintln!("{:#?}", network_info);
 
     let network_interface_name = get_interface_name()?;
@@ -171,6 +189,44 @@ fn create_network_info(
     })
 }
 
+fn create_network_info_from_config(
+    guestos_config: &GuestOSConfig,
+    ipv4_info: Option<IpAddressInfo>,
+) -> Result<NetworkInfo> {
+    let network_settings = &guestos_config.network_settings;
+    let ipv6_info = match &network_settings.ipv6_config {
+        Ipv6Config::Fixed(ipv6_config) => Some(IpAddressInfo::new_ipv6_address(
+            &ipv6_config.address,
+            &ipv6_config.gateway.to_string(),
+        )?),
+        Ipv6Config::RouterAdvertisement => {
+            eprintln!("Proceeding with network configuration using Router Advertisements.");
+            None
+        }
+        Ipv6Config::Deterministic(_) => {
+            bail!(
+                "ERROR: GuestOS cannot derive its IPv6 address - a fixed IPv6 address is required."
+            );
+        }
+    };
+
+    // An IPv4 configuration that is passed explicitly takes precedence over the configured one.
+    let ipv4_info = match (ipv4_info, &network_settings.ipv4_config) {
+        (Some(ipv4_info), _) => Some(ipv4_info),
+        (None, Some(ipv4_config)) => Some(IpAddressInfo::new_ipv4_address(
+            &ipv4_config.address.to_string(),
+            &ipv4_config.prefix_length.to_string(),
+            &ipv4_config.gateway.to_string(),
+        )?),
+        (None, None) => None,
+    };
+
+    Ok(NetworkInfo {
+        ipv6_info,
+        ipv4_info,
+    })
+}
+
 fn process_ipv6_address_and_gateway(
     ipv6_address_with_prefix: &str,
     ipv6_gateway: &str,
@@ -299,6 +355,7 @@ fn is_k8s_testnet() -> Result<bool> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use config::types::*;
 
     #[test]
     fn test_create_network_info_with_valid_ipv6_and_ipv4() {
@@ -375,6 +432,79 @@ mod tests {
         assert!(result.ipv6_info.is_none());
     }
 
+    fn guestos_config(ipv6_config: Ipv6Config, ipv4_config: Option<Ipv4Config>) -> GuestOSConfig {
+        GuestOSConfig {
+            config_version: CONFIG_VERSION,
+            network_settings: NetworkSettings {
+                ipv6_config,
+                ipv4_config,
+                domain: Some("node1.example.com".to_string()),
+            },
+            icos_settings: ICOSSettings {
+                deployment_name: "mainnet".to_string(),
+                mgmt_mac: None,
+                nns_urls: vec!["https://icp-api.io".parse().unwrap()],
+                elasticsearch_hosts: "elasticsearch.example.com:443".to_string(),
+            },
+        }
+    }
+
+    fn fixed_ipv6_config() -> Ipv6Config {
+        Ipv6Config::Fixed(FixedIpv6Config {
+            address: "2001:db8::1/64".to_string(),
+            gateway: "2001:db8::1".parse().unwrap(),
+            prefix: None,
+        })
+    }
+
+    #[test]
+    fn test_create_network_info_from_config_with_ipv6_and_ipv4() {
+        let config = guestos_config(
+            fixed_ipv6_config(),
+            Some(Ipv4Config {
+                address: "192.168.1.100".parse().unwrap(),
+                gateway: "192.168.1.97".parse().unwrap(),
+                prefix_length: 28,
+            }),
+        );
+
+        let result = create_network_info_from_config(&config, None).unwrap();
+
+        let ipv6_info = result.ipv6_info.as_ref().unwrap();
+        assert_eq!(ipv6_info.address_with_prefix, "2001:db8::1/64");
+        assert_eq!(ipv6_info.gateway, "2001:db8::1");
+
+        let ipv4_info = result.ipv4_info.as_ref().unwrap();
+        assert_eq!(ipv4_info.address_with_prefix, "192.168.1.100/28");
+        assert_eq!(ipv4_info.gateway, "192.168.1.97");
+
+        // An explicitly passed IPv4 configuration takes precedence.
+        let ipv4_info =
+            Some(IpAddressInfo::new_ipv4_address("192.168.1.2", "30", "192.168.1.1").unwrap());
+        let result = create_network_info_from_config(&config, ipv4_info).unwrap();
+        let ipv4_info = result.ipv4_info.as_ref().unwrap();
+        assert_eq!(ipv4_info.address_with_prefix, "192.168.1.2/30");
+        assert_eq!(ipv4_info.gateway, "192.168.1.1");
+    }
+
+    #[test]
+    fn test_create_network_info_from_config_without_fixed_ipv6() {
+        let config = guestos_config(Ipv6Config::RouterAdvertisement, None);
+        let result = create_network_info_from_config(&config, None).unwrap();
+        assert!(result.ipv6_info.is_none());
+        assert!(result.ipv4_info.is_none());
+
+        let config = guestos_config(
+            Ipv6Config::Deterministic(DeterministicIpv6Config {
+                prefix: "2001:db8:0:1".to_string(),
+                prefix_length: 64,
+                gateway: "2001:db8::1".parse().unwrap(),
+            }),
+            None,
+        );
+        assert!(create_network_info_from_config(&config, None).is_err());
+    }
+
     #[test]
     fn test_validate_ipv4_network_info_no_input() {
         assert!(validate_and_construct_ipv4_address_info(None, None, None)
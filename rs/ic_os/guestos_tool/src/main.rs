@@ -15,6 +15,7 @@ use generate_network_config::{
     DEFAULT_GUESTOS_NETWORK_CONFIG_PATH,
 };
 
+use config::DEFAULT_GUESTOS_CONFIG_OBJECT_PATH;
 use network::systemd::{restart_systemd_networkd, DEFAULT_SYSTEMD_NETWORK_DIR};
 
 #[derive(Subcommand)]
@@ -25,8 +26,12 @@ pub enum Commands {
         /// systemd-networkd output directory
         systemd_network_dir: String,
 
+        #[arg(long, default_value_t = DEFAULT_GUESTOS_CONFIG_OBJECT_PATH.to_string(), value_name = "FILE")]
+        /// GuestOS config.json input file
+        guestos_config: String,
+
         #[arg(long, default_value_t = DEFAULT_GUESTOS_NETWORK_CONFIG_PATH.to_string(), value_name = "FILE")]
-        /// network.conf input file
+        /// Legacy network.conf input file, used if there is no GuestOS config.json
         network_config: String,
     },
     /// Regenerate systemd network configuration files, optionally incorporating specified IPv4 configuration parameters, and then restart the systemd network.
@@ -35,8 +40,12 @@ pub enum Commands {
         /// systemd-networkd output directory
         systemd_network_dir: String,
 
+        #[arg(long, default_value_t = DEFAULT_GUESTOS_CONFIG_OBJECT_PATH.to_string(), value_name = "FILE")]
+        /// GuestOS config.json input file
+        guestos_config: String,
+
         #[arg(long, default_value_t = DEFAULT_GUESTOS_NETWORK_CONFIG_PATH.to_string(), value_name = "FILE")]
-        /// network.conf input file
+        /// Legacy network.conf input file, used if there is no GuestOS config.json
         network_config: String,
 
         #[arg(long, value_name = "IPV4_ADDRESS")]
@@ -84,14 +93,17 @@ pub fn main() -> Result<()> {
         }
         Some(Commands::GenerateNetworkConfig {
             systemd_network_dir,
+            guestos_config,
             network_config,
         }) => generate_networkd_config(
+            Path::new(&guestos_config),
             Path::new(&network_config),
             Path::new(&systemd_network_dir),
             None,
         ),
         Some(Commands::RegenerateNetworkConfig {
             systemd_network_dir,
+            guestos_config,
             network_config,
             ipv4_address,
             ipv4_prefix_length,
@@ -104,6 +116,7 @@ pub fn main() -> Result<()> {
             )?;
 
             generate_networkd_config(
+                Path::new(&guestos_config),
                 Path::new(&network_config),
                 Path::new(&systemd_network_dir),
                 ipv4_info,
@@ -1,10 +1,15 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
 
+use config::legacy::{hostos_config_from_legacy, network_settings_from_config_map};
+use config::types::HostOSConfig;
 use config::{
-    config_map_from_path, DEFAULT_HOSTOS_CONFIG_FILE_PATH, DEFAULT_HOSTOS_DEPLOYMENT_JSON_PATH,
+    config_map_from_path, deserialize_config, generate_guestos_config, serialize_and_write_config,
+    DEFAULT_HOSTOS_CONFIG_FILE_PATH, DEFAULT_HOSTOS_CONFIG_OBJECT_PATH,
+    DEFAULT_HOSTOS_DEPLOYMENT_JSON_PATH, DEFAULT_HOSTOS_GUESTOS_CONFIG_OBJECT_PATH,
+    DEFAULT_HOSTOS_NODE_OPERATOR_PRIVATE_KEY_PATH, DEFAULT_HOSTOS_SSH_AUTHORIZED_KEYS_PATH,
 };
 use network::generate_network_config;
 use network::info::NetworkInfo;
@@ -31,21 +36,99 @@ pub enum Commands {
         #[arg(short, long, default_value = "HostOS")]
         node_type: String,
     },
+    /// Generate the GuestOS configuration object from the HostOS configuration object.
+    GenerateGuestosConfig {
+        #[arg(short, long, default_value_t = DEFAULT_HOSTOS_GUESTOS_CONFIG_OBJECT_PATH.to_string(), value_name = "FILE")]
+        /// GuestOS config.json output file path
+        output: String,
+    },
 }
 
 #[derive(Parser)]
 struct HostOSArgs {
+    #[arg(long, default_value_t = DEFAULT_HOSTOS_CONFIG_OBJECT_PATH.to_string(), value_name = "FILE")]
+    /// HostOS config.json file path
+    hostos_config: String,
+
     #[arg(short, long, default_value_t = DEFAULT_HOSTOS_CONFIG_FILE_PATH.to_string(), value_name = "FILE")]
+    /// Legacy config.ini file path, used if there is no HostOS config.json
     config: String,
 
     #[arg(short, long, default_value_t = DEFAULT_HOSTOS_DEPLOYMENT_JSON_PATH.to_string(), value_name = "FILE")]
-    /// deployment.json file path
+    /// Legacy deployment.json file path, used if there is no HostOS config.json
     deployment_file: String,
 
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+/// Reads the HostOS configuration object. Nodes that were installed before SetupOS wrote one
+/// only have the legacy config.ini and deployment.json, so it is built from those instead.
+fn read_hostos_config(opts: &HostOSArgs) -> Result<HostOSConfig> {
+    let hostos_config_path = Path::new(&opts.hostos_config);
+    let hostos_config = if hostos_config_path.exists() {
+        deserialize_config(hostos_config_path)
+            .context("Please specify a valid HostOS config file with '--hostos-config'")?
+    } else {
+        eprintln!(
+            "{} does not exist, using the legacy config files",
+            hostos_config_path.display()
+        );
+        let config_map = config_map_from_path(Path::new(&opts.config))
+            .context("Please specify a valid config file with '--config'")?;
+        eprintln!("Using config: {:?}", config_map);
+
+        let deployment = read_deployment_file(Path::new(&opts.deployment_file))
+            .context("Please specify a valid deployment file with '--deployment-file'")?;
+        eprintln!("Deployment config: {:?}", deployment);
+
+        let existing_path = |path: &str| Some(PathBuf::from(path)).filter(|p| p.exists());
+        hostos_config_from_legacy(
+            &config_map,
+            &deployment,
+            existing_path(DEFAULT_HOSTOS_NODE_OPERATOR_PRIVATE_KEY_PATH),
+            existing_path(DEFAULT_HOSTOS_SSH_AUTHORIZED_KEYS_PATH),
+        )
+        .context("Invalid HostOS configuration")?
+    };
+    eprintln!("HostOS config: {:?}", hostos_config);
+
+    Ok(hostos_config)
+}
+
+/// Reads the network info and, if available, the deployment name. Unlike `read_hostos_config`,
+/// this does not require deployment.json on nodes that only have the legacy config files.
+fn read_network_info(opts: &HostOSArgs) -> Result<(NetworkInfo, Option<String>)> {
+    if Path::new(&opts.hostos_config).exists() {
+        let hostos_config = read_hostos_config(opts)?;
+        let network_info = NetworkInfo::from_config(&hostos_config)?;
+        return Ok((
+            network_info,
+            Some(hostos_config.icos_settings.deployment_name),
+        ));
+    }
+
+    let config_map = config_map_from_path(Path::new(&opts.config))
+        .context("Please specify a valid config file with '--config'")?;
+    eprintln!("Using config: {:?}", config_map);
+
+    let network_settings = network_settings_from_config_map(&config_map)?;
+    let network_info = NetworkInfo::from_network_settings(
+        &network_settings,
+        config_map.get("mgmt_mac").map(String::as_str),
+    )?;
+
+    let deployment_name = match read_deployment_file(Path::new(&opts.deployment_file)) {
+        Ok(deployment) => Some(deployment.deployment.name),
+        Err(e) => {
+            eprintln!("Error retrieving deployment file: {e}. Continuing without it");
+            None
+        }
+    };
+
+    Ok((network_info, deployment_name))
+}
+
 pub fn main() -> Result<()> {
     #[cfg(not(target_os = "linux"))]
     {
@@ -55,46 +138,27 @@ pub fn main() -> Result<()> {
 
     let opts = HostOSArgs::parse();
 
-    match opts.command {
+    match &opts.command {
         Some(Commands::GenerateNetworkConfig { output_directory }) => {
-            let config_map = config_map_from_path(Path::new(&opts.config))
-                .context("Please specify a valid config file with '--config'")?;
-            eprintln!("Using config: {:?}", config_map);
-
-            let network_info = NetworkInfo::from_config_map(&config_map)?;
+            let (network_info, deployment_name) = read_network_info(&opts)?;
             eprintln!("Network info config: {:?}", &network_info);
 
-            let deployment = read_deployment_file(Path::new(&opts.deployment_file));
-            let deployment_name: Option<&str> = match &deployment {
-                Ok(deployment) => Some(deployment.deployment.name.as_str()),
-                Err(e) => {
-                    eprintln!("Error retrieving deployment file: {e}. Continuing without it");
-                    None
-                }
-            };
-
             generate_network_config(
                 &network_info,
-                deployment_name,
+                deployment_name.as_deref(),
                 NodeType::HostOS,
                 Path::new(&output_directory),
             )
         }
         Some(Commands::GenerateIpv6Address { node_type }) => {
-            let deployment = read_deployment_file(Path::new(&opts.deployment_file))
-                .context("Please specify a valid deployment file with '--deployment-file'")?;
-            eprintln!("Deployment config: {:?}", deployment);
+            let hostos_config = read_hostos_config(&opts)?;
 
-            let config_map = config_map_from_path(Path::new(&opts.config))
-                .context("Please specify a valid config file with '--config'")?;
-            eprintln!("Using config: {:?}", config_map);
-
-            let network_info = NetworkInfo::from_config_map(&config_map)?;
+            let network_info = NetworkInfo::from_config(&hostos_config)?;
             eprintln!("Network info config: {:?}", &network_info);
 
             let node_type = node_type.parse::<NodeType>()?;
             let mac = generate_mac_address(
-                &deployment.deployment.name,
+                &hostos_config.icos_settings.deployment_name,
                 &node_type,
                 &network_info.bmc_mac,
             )?;
@@ -106,20 +170,14 @@ pub fn main() -> Result<()> {
             Ok(())
         }
         Some(Commands::GenerateMacAddress { node_type }) => {
-            let config_map = config_map_from_path(Path::new(&opts.config))
-                .context("Please specify a valid config file with '--config'")?;
-            eprintln!("Using config: {:?}", config_map);
+            let hostos_config = read_hostos_config(&opts)?;
 
-            let network_info = NetworkInfo::from_config_map(&config_map)?;
+            let network_info = NetworkInfo::from_config(&hostos_config)?;
             eprintln!("Network info config: {:?}", &network_info);
 
-            let deployment = read_deployment_file(Path::new(&opts.deployment_file))
-                .context("Please specify a valid deployment file with '--deployment-file'")?;
-            eprintln!("Deployment config: {:?}", deployment);
-
             let node_type = node_type.parse::<NodeType>()?;
             let mac = generate_mac_address(
-                &deployment.deployment.name,
+                &hostos_config.icos_settings.deployment_name,
                 &node_type,
                 &network_info.bmc_mac,
             )?;
@@ -127,6 +185,26 @@ pub fn main() -> Result<()> {
             println!("{}", mac.get());
             Ok(())
         }
+        Some(Commands::GenerateGuestosConfig { output }) => {
+            let hostos_config = read_hostos_config(&opts)?;
+
+            let network_info = NetworkInfo::from_config(&hostos_config)?;
+            let mac = generate_mac_address(
+                &hostos_config.icos_settings.deployment_name,
+                &NodeType::GuestOS,
+                &network_info.bmc_mac,
+            )?;
+            let ipv6_prefix = network_info
+                .ipv6_prefix
+                .context("ipv6_prefix required in config to generate the GuestOS config")?;
+            let guestos_ipv6_address = generate_ipv6_address(&ipv6_prefix, &mac)?;
+
+            let guestos_config = generate_guestos_config(&hostos_config, guestos_ipv6_address)
+                .context("Invalid GuestOS configuration")?;
+            eprintln!("GuestOS config: {:?}", guestos_config);
+
+            serialize_and_write_config(Path::new(&output), &guestos_config)
+        }
         None => Err(anyhow!(
             "No subcommand specified. Run with '--help' for subcommands"
         )),
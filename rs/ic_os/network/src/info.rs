@@ -3,7 +3,7 @@ use std::net::Ipv6Addr;
 use anyhow::{bail, Context, Result};
 
 use crate::mac_address::FormattedMacAddress;
-use config::ConfigMap;
+use config::types::{Ipv6Config, NetworkSettings, VersionedConfig, IPV6_PREFIX_LENGTH};
 
 #[derive(Debug)]
 pub struct NetworkInfo {
@@ -17,60 +17,54 @@ pub struct NetworkInfo {
     pub bmc_mac: Option<FormattedMacAddress>,
 }
 
-fn is_valid_prefix(ipv6_prefix: &str) -> bool {
-    ipv6_prefix.len() <= 19 && format!("{ipv6_prefix}::").parse::<Ipv6Addr>().is_ok()
-}
-
 impl NetworkInfo {
-    pub fn from_config_map(config_map: &ConfigMap) -> Result<NetworkInfo> {
-        // Per PFOPS - this will never not be 64
-        let ipv6_subnet = 64_u8;
+    /// Network info of a node, taken from its configuration object.
+    pub fn from_config(config: &impl VersionedConfig) -> Result<NetworkInfo> {
+        Self::from_network_settings(
+            config.network_settings(),
+            config.icos_settings().mgmt_mac.as_deref(),
+        )
+    }
 
-        let ipv6_prefix = match config_map.get("ipv6_prefix") {
-            Some(ipv6_prefix) => {
-                // Prefix should have a max length of 19 ("1234:6789:1234:6789")
-                // It could have fewer characters though. Parsing as an ip address with trailing '::' should work.
-                if !is_valid_prefix(ipv6_prefix) {
-                    bail!("Invalid ipv6 prefix: {}", ipv6_prefix);
-                }
-                Some(ipv6_prefix.clone())
+    /// Network info of a node, taken from its network settings and its BMC's MAC address.
+    pub fn from_network_settings(
+        network_settings: &NetworkSettings,
+        mgmt_mac: Option<&str>,
+    ) -> Result<NetworkInfo> {
+        let (ipv6_prefix, ipv6_address, ipv6_gateway) = match &network_settings.ipv6_config {
+            Ipv6Config::Deterministic(ipv6_config) => {
+                (Some(ipv6_config.prefix.clone()), None, ipv6_config.gateway)
             }
-            None => None,
-        };
-
-        // Optional ipv6_address - for testing. Takes precedence over ipv6_prefix.
-        let ipv6_address = match config_map.get("ipv6_address") {
-            Some(address) => {
-                // ipv6_address might be formatted with the trailing suffix. Remove it.
-                let ipv6_subnet = format!("/{}", ipv6_subnet);
-                let address = address.strip_suffix(&ipv6_subnet).unwrap_or(address);
+            // Fixed addresses are for testing. They take precedence over the ipv6 prefix.
+            Ipv6Config::Fixed(ipv6_config) => {
+                // The address is formatted with the trailing prefix length. Remove it.
+                let address = ipv6_config
+                    .address
+                    .split_once('/')
+                    .map_or(ipv6_config.address.as_str(), |(address, _)| address);
                 let address = address
                     .parse::<Ipv6Addr>()
                     .context(format!("Invalid ipv6 address: {}", address))?;
-                Some(address)
+                (
+                    ipv6_config.prefix.clone(),
+                    Some(address),
+                    ipv6_config.gateway,
+                )
+            }
+            Ipv6Config::RouterAdvertisement => {
+                bail!("Missing config parameter: need at least one of ipv6_prefix or ipv6_address")
             }
-            None => None,
         };
 
-        if ipv6_address.is_none() && ipv6_prefix.is_none() {
-            bail!("Missing config parameter: need at least one of ipv6_prefix or ipv6_address");
-        }
-
-        let ipv6_gateway = config_map
-            .get("ipv6_gateway")
-            .context("Missing config parameter: ipv6_gateway")?;
-        let ipv6_gateway = ipv6_gateway
-            .parse::<Ipv6Addr>()
-            .context(format!("Invalid ipv6 address: {}", ipv6_gateway))?;
-
-        let bmc_mac = match config_map.get("mgmt_mac") {
-            Some(bmc_mac) => Some(FormattedMacAddress::try_from(bmc_mac.as_str())?),
+        let bmc_mac = match mgmt_mac {
+            Some(bmc_mac) => Some(FormattedMacAddress::try_from(bmc_mac)?),
             None => None,
         };
 
         Ok(NetworkInfo {
             ipv6_prefix,
-            ipv6_subnet,
+            // Per PFOPS - this will never not be 64
+            ipv6_subnet: IPV6_PREFIX_LENGTH,
             ipv6_gateway,
             ipv6_address,
             bmc_mac,
@@ -80,83 +74,105 @@ impl NetworkInfo {
 
 #[cfg(test)]
 pub mod tests {
-    use std::collections::HashMap;
-
     use super::*;
-    #[test]
-    fn test_is_valid_prefix() {
-        assert!(is_valid_prefix("2a00:1111:1111:1111"));
-        assert!(is_valid_prefix("2a00:111:11:11"));
-        assert!(is_valid_prefix("2602:fb2b:100:10"));
+    use config::types::*;
+
+    fn hostos_config(ipv6_config: Ipv6Config, mgmt_mac: Option<&str>) -> HostOSConfig {
+        HostOSConfig {
+            config_version: CONFIG_VERSION,
+            network_settings: NetworkSettings {
+                ipv6_config,
+                ipv4_config: None,
+                domain: None,
+            },
+            icos_settings: ICOSSettings {
+                deployment_name: "mainnet".to_string(),
+                mgmt_mac: mgmt_mac.map(str::to_string),
+                nns_urls: vec!["https://icp-api.io".parse().unwrap()],
+                elasticsearch_hosts: "elasticsearch.example.com:443".to_string(),
+            },
+            hostos_settings: HostOSSettings {
+                vm_memory: 490,
+                vm_cpu: "kvm".to_string(),
+                verbose: false,
+                node_operator_private_key_path: None,
+                ssh_authorized_keys_path: None,
+            },
+        }
     }
 
     #[test]
-    fn test_from_config_map() {
-        // Example config.ini
-        let config_map = HashMap::from([
-            ("ipv6_prefix".to_string(), "2a00:fb01:400:100".to_string()),
-            (
-                "ipv6_gateway".to_string(),
-                "2a00:fb01:400:100::1".to_string(),
-            ),
-        ]);
-        assert!(NetworkInfo::from_config_map(&config_map).is_ok());
-
-        // With ipv6_address and ipv6_prefix
-        let config_map = HashMap::from([
-            ("ipv6_prefix".to_string(), "2a00:fb01:400:100".to_string()),
-            (
-                "ipv6_gateway".to_string(),
-                "2a00:fb01:400:100::1".to_string(),
-            ),
-            (
-                "ipv6_address".to_string(),
-                "2a00:fb01:400:100::3".to_string(),
-            ),
-        ]);
-        assert!(NetworkInfo::from_config_map(&config_map).is_ok());
-
-        // No subnet
-        let config_map = HashMap::from([
-            ("ipv6_prefix".to_string(), "2a00:fb01:400:100".to_string()),
-            (
-                "ipv6_gateway".to_string(),
-                "2a00:fb01:400:100::1".to_string(),
-            ),
-        ]);
-        assert!(NetworkInfo::from_config_map(&config_map).is_ok());
-
-        // Need address or prefix
-        let config_map = HashMap::from([
-            (
-                "ipv6_address".to_string(),
-                "2a00:fb01:400:100::1".to_string(),
-            ),
-            (
-                "ipv6_gateway".to_string(),
-                "2a00:fb01:400:100::1".to_string(),
-            ),
-        ]);
-        assert!(NetworkInfo::from_config_map(&config_map).is_ok());
-
-        // Need prefix or address, gateway
-        let config_map = HashMap::from([(
-            "ipv6_gateway".to_string(),
-            "2a00:fb01:400:100::1".to_string(),
-        )]);
-        assert!(NetworkInfo::from_config_map(&config_map).is_err());
-        let config_map =
-            HashMap::from([("ipv6_prefix".to_string(), "2a00:fb01:400:100".to_string())]);
-        assert!(NetworkInfo::from_config_map(&config_map).is_err());
-
-        // With ipv6_address with subnet len
-        let config_map = HashMap::from([
-            (
-                "ipv6_gateway".to_string(),
-                "2a00:fb01:400:100::1".to_string(),
-            ),
-            ("ipv6_address".to_string(), "fd00:2:1:1::11/64".to_string()),
-        ]);
-        assert!(NetworkInfo::from_config_map(&config_map).is_ok());
+    fn test_from_config() {
+        let gateway: Ipv6Addr = "2a00:fb01:400:100::1".parse().unwrap();
+
+        // Deterministic address
+        let config = hostos_config(
+            Ipv6Config::Deterministic(DeterministicIpv6Config {
+                prefix: "2a00:fb01:400:100".to_string(),
+                prefix_length: 64,
+                gateway,
+            }),
+            Some("de:ad:de:ad:de:ad"),
+        );
+        let network_info = NetworkInfo::from_config(&config).unwrap();
+        assert_eq!(
+            network_info.ipv6_prefix.as_deref(),
+            Some("2a00:fb01:400:100")
+        );
+        assert_eq!(network_info.ipv6_address, None);
+        assert_eq!(network_info.ipv6_subnet, 64);
+        assert_eq!(network_info.ipv6_gateway, gateway);
+        assert!(network_info.bmc_mac.is_some());
+
+        // Fixed address with subnet len
+        let config = hostos_config(
+            Ipv6Config::Fixed(FixedIpv6Config {
+                address: "fd00:2:1:1::11/64".to_string(),
+                gateway,
+                prefix: None,
+            }),
+            None,
+        );
+        let network_info = NetworkInfo::from_config(&config).unwrap();
+        assert_eq!(network_info.ipv6_prefix, None);
+        assert_eq!(
+            network_info.ipv6_address,
+            Some("fd00:2:1:1::11".parse().unwrap())
+        );
+        assert!(network_info.bmc_mac.is_none());
+
+        // Fixed address with prefix
+        let config = hostos_config(
+            Ipv6Config::Fixed(FixedIpv6Config {
+                address: "2a00:fb01:400:100::11/64".to_string(),
+                gateway,
+                prefix: Some("2a00:fb01:400:100".to_string()),
+            }),
+            None,
+        );
+        let network_info = NetworkInfo::from_config(&config).unwrap();
+        assert_eq!(
+            network_info.ipv6_prefix.as_deref(),
+            Some("2a00:fb01:400:100")
+        );
+        assert_eq!(
+            network_info.ipv6_address,
+            Some("2a00:fb01:400:100::11".parse().unwrap())
+        );
+
+        // Need prefix or address
+        let config = hostos_config(Ipv6Config::RouterAdvertisement, None);
+        assert!(NetworkInfo::from_config(&config).is_err());
+
+        // Invalid BMC MAC address
+        let config = hostos_config(
+            Ipv6Config::Fixed(FixedIpv6Config {
+                address: "fd00:2:1:1::11/64".to_string(),
+                gateway,
+                prefix: None,
+            }),
+            Some("not a mac address"),
+        );
+        assert!(NetworkInfo::from_config(&config).is_err());
     }
 }
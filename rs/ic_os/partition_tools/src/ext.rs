@@ -1,30 +1,36 @@
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
-use std::process::{Output, Stdio};
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
-use itertools::Itertools;
 use pcre2::bytes::Regex;
 use tempfile::{tempdir, TempDir};
 use tokio::fs;
 use tokio::fs::File;
-use tokio::io::{self, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom};
-use tokio::process::Command;
+use tokio::io::{self, AsyncReadExt, AsyncSeekExt, SeekFrom};
 
 use crate::partition;
 use crate::Partition;
 
+mod checksum;
+mod directory;
+mod filesystem;
+mod hash;
+
+use filesystem::ExtFilesystem;
+
 const STORE_NAME: &str = "backing_store";
 
 pub struct ExtPartition {
     index: Option<usize>,
     backing_dir: TempDir,
     original: PathBuf,
+    filesystem: ExtFilesystem,
 }
 
 #[async_trait]
 impl Partition for ExtPartition {
-    /// Open an ext4 partition for writing, via debugfs
+    /// Open an ext4 partition for writing, via a copy of the partition
     async fn open(image: PathBuf, index: Option<usize>) -> Result<Self> {
         let backing_dir = tempdir()?;
         let output_path = backing_dir.path().join(STORE_NAME);
@@ -32,9 +38,9 @@ impl Partition for ExtPartition {
         let mut input = File::open(&image).await?;
 
         if let Some(index) = index {
-            let mut output = File::create(output_path).await?;
-            let offset = partition::check_offset(&image, index).await?;
-            let length = partition::check_length(&image, index).await?;
+            let mut output = File::create(&output_path).await?;
+            let offset = partition::check_offset(&image, index)?;
+            let length = partition::check_length(&image, index)?;
 
             input.seek(SeekFrom::Start(offset)).await?;
             io::copy(&mut input.take(length), &mut output).await?;
@@ -44,22 +50,29 @@ impl Partition for ExtPartition {
             fs::copy(&image, &output_path).await?;
         }
 
+        let filesystem = ExtFilesystem::open(partition::open_volume(&output_path, None)?)
+            .context("failed to open ext4 filesystem")?;
+
         Ok(ExtPartition {
             index,
             backing_dir,
             original: image,
+            filesystem,
         })
     }
 
     /// Close an ext4 partition, and write back to the input disk
     async fn close(self) -> Result<()> {
+        // Every change is already flushed to the backing store.
+        drop(self.filesystem);
+
         let input_path = self.backing_dir.path().join(STORE_NAME);
         let output_path = self.original;
 
         if let Some(index) = self.index {
             let mut input = File::open(&input_path).await?;
             let mut output = File::options().write(true).open(&output_path).await?;
-            let offset = partition::check_offset(&output_path, index).await?;
+            let offset = partition::check_offset(&output_path, index)?;
 
             output.seek(SeekFrom::Start(offset)).await?;
             io::copy(&mut input, &mut output).await?;
@@ -74,70 +87,18 @@ impl Partition for ExtPartition {
 
     /// Copy a file into place
     async fn write_file(&mut self, input: &Path, output: &Path) -> Result<()> {
-        let mut cmd = Command::new("/usr/bin/faketime")
-            .args([
-                "-f",
-                "1970-1-1 0:0:0",
-                "/usr/sbin/debugfs",
-                "-w",
-                (self.backing_dir.path().join(STORE_NAME).to_str().unwrap()),
-                "-f",
-                "-",
-            ])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("failed to run debugfs")?;
+        let contents = fs::read(input).await?;
+        let mode = fs::metadata(input).await?.permissions().mode();
 
-        cmd.stdin
-            .as_mut()
-            .unwrap()
-            .write_all(Self::debugfs_input(input, output).as_bytes())
-            .await?;
-        Self::check_debugfs_result(&cmd.wait_with_output().await?)
+        self.filesystem
+            .write_file(output, &contents, (mode & 0o7777) as u16)
     }
 
     /// Read a file from a given partition
     async fn read_file(&mut self, input: &Path) -> Result<String> {
-        // run the underlying debugfs operation
-        let mut cmd = Command::new("/usr/sbin/debugfs")
-            .args([
-                (self.backing_dir.path().join(STORE_NAME).to_str().unwrap()),
-                "-f",
-                "-",
-            ])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("failed to run debugfs")?;
-
-        let mut stdin = cmd.stdin.as_mut().unwrap();
-        io::copy(
-            &mut indoc::formatdoc!(
-                r#"
-                cd {path}
-                cat {filename}
-            "#,
-                path = input.parent().unwrap().to_str().unwrap(),
-                filename = input.file_name().unwrap().to_str().unwrap(),
-            )
-            .as_bytes(),
-            &mut stdin,
-        )
-        .await?;
-
-        let out = cmd.wait_with_output().await?;
-        Self::check_debugfs_result(&out)?;
-
-        let cleaned_output = std::str::from_utf8(&out.stdout)?
-            .lines()
-            .skip(2)
-            .collect::<Vec<_>>()
-            .join("\n");
+        let contents = self.filesystem.read_file(input)?;
 
-        Ok(cleaned_output)
+        Ok(String::from_utf8(contents)?)
     }
 }
 
@@ -149,106 +110,10 @@ impl ExtPartition {
         mode: usize,
         context: Option<&str>,
     ) -> Result<()> {
-        let mut cmd = Command::new("/usr/bin/faketime")
-            .args([
-                "-f",
-                "1970-1-1 0:0:0",
-                "/usr/sbin/debugfs",
-                "-w",
-                (self.backing_dir.path().join(STORE_NAME).to_str().unwrap()),
-                "-f",
-                "-",
-            ])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("failed to run debugfs")?;
-
-        let mut stdin = cmd.stdin.as_mut().unwrap();
-
-        let path = output.parent().unwrap().to_str().unwrap();
-        let filename = output.file_name().unwrap().to_str().unwrap();
-        let context_str = if let Some(context) = context {
-            format!("ea_set {filename} security.selinux {context}\000")
-        } else {
-            "".to_string()
-        };
+        let mode = u16::try_from(mode).map_err(|_| anyhow!("invalid mode: {mode:o}"))?;
 
         // Always set root:root, and timestamp 0
-        io::copy(
-            &mut indoc::formatdoc!(
-                r#"
-                cd {path}
-                set_inode_field {filename} extra_isize 28
-                set_inode_field {filename} mode {mode}
-                set_inode_field {filename} uid 0
-                set_inode_field {filename} gid 0
-                set_inode_field {filename} atime 0
-                set_inode_field {filename} ctime 0
-                set_inode_field {filename} mtime 0
-                set_inode_field {filename} crtime 0
-                {context_str}
-            "#
-            )
-            .as_bytes(),
-            &mut stdin,
-        )
-        .await?;
-
-        let out = cmd.wait_with_output().await?;
-        if !out.status.success() {
-            return Err(anyhow!(
-                "debugfs failed: {}",
-                String::from_utf8(out.stderr)?
-            ));
-        }
-
-        Ok(())
-    }
-
-    fn check_debugfs_result(out: &Output) -> Result<()> {
-        let errors = std::str::from_utf8(&out.stderr)?
-            .lines()
-            .filter(|error| !error.starts_with("debugfs")) // version number
-            // Some errors we ignore.
-            .filter(|error| !error.contains("Ext2 directory already exists"))
-            .filter(|error| !error.contains("rm: File not found"))
-            .join("\n");
-        if !out.status.success() || !errors.is_empty() {
-            bail!("debugfs failed:\n{errors}");
-        }
-
-        Ok(())
-    }
-
-    fn debugfs_input(input: &Path, output: &Path) -> String {
-        // Commands that generate all parent dirs of input (debugfs doesn't
-        // support mkdir -p)
-        // Eg. if `output` is `/opt/ic/bin/something.txt`, `mkdir_all` will be:
-        // mkdir /opt
-        // mkdir /opt/ic
-        // mkdir /opt/ic/bin
-        let mkdir_all = output
-            .ancestors()
-            .skip(1) // skip `output` itself
-            .take_while(|path| path != &Path::new("/"))
-            .collect::<Vec<_>>()
-            .into_iter()
-            .rev()
-            .map(|path| format!("mkdir {}", path.display()))
-            .join("\n");
-        indoc::formatdoc!(
-            r#"
-            {mkdir_all}
-            cd {path}
-            rm {filename}
-            write {input} {filename}
-        "#,
-            path = output.parent().unwrap().to_str().unwrap(),
-            filename = output.file_name().unwrap().to_str().unwrap(),
-            input = &input.to_str().unwrap(),
-        )
+        self.filesystem.set_metadata(output, mode, context)
     }
 }
 
@@ -294,6 +159,10 @@ impl FileContexts {
 
 #[cfg(test)]
 mod test {
+    use std::os::unix::fs::FileExt;
+
+    use tokio::process::Command;
+
     use super::*;
 
     // Selected subset of an example SELinux file_contexts file
@@ -374,37 +243,8 @@ mod test {
         );
     }
 
-    #[test]
-    fn debugfs_input_test() {
-        assert_eq!(
-            ExtPartition::debugfs_input(
-                Path::new("path/to/input.sh"),
-                Path::new("/opt/ic/bin/output.sh")
-            ),
-            indoc::formatdoc!(
-                "mkdir /opt
-                 mkdir /opt/ic
-                 mkdir /opt/ic/bin
-                 cd /opt/ic/bin
-                 rm output.sh
-                 write path/to/input.sh output.sh
-               "
-            )
-        );
-    }
-
     async fn create_empty_partition_img(path: &Path) -> Result<()> {
-        Command::new("/usr/bin/dd")
-            .args([
-                "if=/dev/zero",
-                &format!("of={}", path.display()),
-                "bs=1K",
-                "count=256",
-            ])
-            .spawn()
-            .unwrap()
-            .wait()
-            .await?;
+        File::create(path).await?.set_len(256 * 1024).await?;
 
         Command::new("/usr/sbin/mkfs.ext4")
             .args([path.as_os_str()])
@@ -416,6 +256,20 @@ mod test {
         Ok(())
     }
 
+    async fn check_partition_img(path: &Path) {
+        let status = Command::new("/usr/sbin/e2fsck")
+            .args(["-fn".as_ref(), path.as_os_str()])
+            .status()
+            .await
+            .unwrap();
+
+        assert!(
+            status.success(),
+            "e2fsck found errors in {}",
+            path.display()
+        );
+    }
+
     #[tokio::test]
     async fn write_read_test() {
         let dir = tempdir().unwrap();
@@ -468,5 +322,92 @@ mod test {
             .expect_err("Expected reading non-existing file to fail")
             .to_string()
             .contains("File not found"));
+
+        // Fix up the metadata of the file, and write the partition back.
+        partition
+            .fixup_metadata(target_path, 0o100644, Some("system_u:object_r:etc_t:s0"))
+            .await
+            .unwrap();
+        partition.close().await.unwrap();
+        check_partition_img(&img_path).await;
+
+        let mut partition = ExtPartition::open(img_path.to_path_buf(), None)
+            .await
+            .expect("Could not open partition");
+        let read = partition
+            .read_file(target_path)
+            .await
+            .expect("Could not read file from partition");
+
+        assert_eq!(read, std::str::from_utf8(contents2).unwrap());
+    }
+
+    #[tokio::test]
+    async fn disk_image_test() {
+        let dir = tempdir().unwrap();
+        let img_path = dir.path().join("disk.img");
+        let disk = std::fs::File::options()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&img_path)
+            .unwrap();
+        crate::gpt::test::write_test_gpt(
+            &disk,
+            8192,
+            &[(2048, 2559, "config"), (2560, 8143, "root")],
+        );
+        let marker = vec![0xab; 512 * 512];
+        disk.write_all_at(&marker, 2048 * 512).unwrap();
+
+        // Format the second partition in place.
+        let status = Command::new("/usr/sbin/mkfs.ext4")
+            .args(["-q", "-F", "-E"])
+            .arg(format!("offset={}", 2560 * 512))
+            .arg(&img_path)
+            .arg(format!("{}k", (8143 - 2560 + 1) / 2))
+            .status()
+            .await
+            .unwrap();
+        assert!(status.success());
+
+        let input = dir.path().join("input.txt");
+        fs::write(&input, b"Hello World!").await.unwrap();
+        let target_path = Path::new("/etc/hostname");
+        let mut partition = ExtPartition::open(img_path.clone(), Some(2)).await.unwrap();
+        partition.write_file(&input, target_path).await.unwrap();
+        partition
+            .fixup_metadata(target_path, 0o100644, Some("system_u:object_r:etc_t:s0"))
+            .await
+            .unwrap();
+        partition.close().await.unwrap();
+
+        // The neighbouring partition and the partition table are left alone.
+        let mut contents = vec![0; marker.len()];
+        disk.read_exact_at(&mut contents, 2048 * 512).unwrap();
+        assert_eq!(contents, marker);
+        let gpt = crate::gpt::Gpt::read(&disk).unwrap();
+        assert_eq!(gpt.partition(2).unwrap().name(), "root");
+
+        // The partition can be checked and read by the e2fsprogs tools.
+        let root_path = dir.path().join("root.img");
+        let mut root = vec![0; (8143 - 2560 + 1) * 512];
+        disk.read_exact_at(&mut root, 2560 * 512).unwrap();
+        fs::write(&root_path, &root).await.unwrap();
+        check_partition_img(&root_path).await;
+        let output = Command::new("/usr/sbin/debugfs")
+            .args(["-R", "cat /etc/hostname"])
+            .arg(&root_path)
+            .output()
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"Hello World!");
+
+        let mut partition = ExtPartition::open(img_path, Some(2)).await.unwrap();
+        assert_eq!(
+            partition.read_file(target_path).await.unwrap(),
+            "Hello World!"
+        );
     }
 }
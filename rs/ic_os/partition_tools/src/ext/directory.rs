@@ -0,0 +1,389 @@
+//! The layout of ext4 directory blocks: linear entries and the nodes of the hash index.
+
+use anyhow::{ensure, Result};
+
+pub(crate) const FT_REG_FILE: u8 = 1;
+pub(crate) const FT_DIR: u8 = 2;
+
+/// The size of the checksum entry at the end of leaf blocks with `metadata_csum`.
+pub(crate) const TAIL_SIZE: usize = 12;
+const TAIL_FILE_TYPE: u8 = 0xde;
+
+const ROOT_INFO_OFFSET: usize = 0x18;
+const NODE_COUNT_OFFSET: usize = 8;
+const DX_ENTRY_SIZE: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DirEntry {
+    pub offset: usize,
+    pub inode: u32,
+    pub rec_len: usize,
+    pub file_type: u8,
+    pub name: Vec<u8>,
+}
+
+/// The space an entry with a name of `name_len` bytes needs.
+pub(crate) fn entry_size(name_len: usize) -> usize {
+    (8 + name_len + 3) & !3
+}
+
+/// All entries of a leaf block, including unused ones, within the first `usable` bytes.
+pub(crate) fn entries(block: &[u8], usable: usize) -> Result<Vec<DirEntry>> {
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset < usable {
+        ensure!(offset + 8 <= usable, "truncated directory entry");
+        let rec_len = rec_len_from_disk(read_u16(block, offset + 4), block.len());
+        let name_len = block[offset + 6] as usize;
+        ensure!(
+            rec_len >= 8
+                && rec_len % 4 == 0
+                && offset + rec_len <= usable
+                && 8 + name_len <= rec_len,
+            "corrupted directory entry at offset {offset}"
+        );
+        entries.push(DirEntry {
+            offset,
+            inode: read_u32(block, offset),
+            rec_len,
+            file_type: block[offset + 7],
+            name: block[offset + 8..offset + 8 + name_len].to_vec(),
+        });
+        offset += rec_len;
+    }
+    Ok(entries)
+}
+
+/// Add an entry to a leaf block, if there is enough space for it.
+pub(crate) fn insert(
+    block: &mut [u8],
+    usable: usize,
+    name: &[u8],
+    inode: u32,
+    file_type: u8,
+) -> Result<bool> {
+    let needed = entry_size(name.len());
+    for entry in entries(block, usable)? {
+        let used = if entry.inode == 0 {
+            0
+        } else {
+            entry_size(entry.name.len())
+        };
+        if entry.rec_len - used < needed {
+            continue;
+        }
+
+        if used != 0 {
+            write_entry(
+                block,
+                entry.offset,
+                used,
+                &entry.name,
+                entry.inode,
+                entry.file_type,
+            );
+        }
+        write_entry(
+            block,
+            entry.offset + used,
+            entry.rec_len - used,
+            name,
+            inode,
+            file_type,
+        );
+        return Ok(true);
+    }
+    Ok(false)
+}
+
+/// Remove the entry with the given name from a leaf block, returning its inode.
+pub(crate) fn remove(block: &mut [u8], usable: usize, name: &[u8]) -> Result<Option<u32>> {
+    let entries = entries(block, usable)?;
+    let Some(index) = entries
+        .iter()
+        .position(|entry| entry.inode != 0 && entry.name == name)
+    else {
+        return Ok(None);
+    };
+
+    let entry = &entries[index];
+    match index.checked_sub(1).map(|i| &entries[i]) {
+        // Merge the space into the previous entry.
+        Some(previous) => write_entry(
+            block,
+            previous.offset,
+            previous.rec_len + entry.rec_len,
+            &previous.name,
+            previous.inode,
+            previous.file_type,
+        ),
+        // The first entry of a block can't be merged, so only mark it unused.
+        None => block[entry.offset..entry.offset + 4].copy_from_slice(&0u32.to_le_bytes()),
+    }
+    Ok(Some(entry.inode))
+}
+
+/// Fill a leaf block with the given entries, given as (name, inode, file type).
+pub(crate) fn init_block(block: &mut [u8], usable: usize, entries: &[(&[u8], u32, u8)]) {
+    block[..usable].fill(0);
+    let mut offset = 0;
+    for (i, (name, inode, file_type)) in entries.iter().enumerate() {
+        let rec_len = if i == entries.len() - 1 {
+            usable - offset
+        } else {
+            entry_size(name.len())
+        };
+        write_entry(block, offset, rec_len, name, *inode, *file_type);
+        offset += rec_len;
+    }
+    if entries.is_empty() {
+        write_entry(block, 0, usable, b"", 0, 0);
+    }
+}
+
+/// Write the checksum entry at the end of a leaf block.
+pub(crate) fn set_tail(block: &mut [u8], checksum: u32) {
+    let offset = block.len() - TAIL_SIZE;
+    block[offset..].fill(0);
+    block[offset + 4..offset + 6].copy_from_slice(&(TAIL_SIZE as u16).to_le_bytes());
+    block[offset + 7] = TAIL_FILE_TYPE;
+    block[offset + 8..].copy_from_slice(&checksum.to_le_bytes());
+}
+
+/// Whether a block holds an interior node of the hash index, which starts with an unused entry
+/// that spans the whole block.
+pub(crate) fn is_dx_node(block: &[u8]) -> bool {
+    read_u32(block, 0) == 0 && rec_len_from_disk(read_u16(block, 4), block.len()) == block.len()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DxNode {
+    pub count_offset: usize,
+    pub limit: usize,
+    /// Pairs of (hash, logical block). The hash of the first entry is implicitly 0.
+    pub entries: Vec<(u32, u32)>,
+}
+
+impl DxNode {
+    /// The entry that covers names with the given hash.
+    pub(crate) fn lookup(&self, hash: u32) -> usize {
+        self.entries
+            .iter()
+            .rposition(|(entry_hash, _)| *entry_hash <= hash)
+            .unwrap_or(0)
+    }
+
+    /// The offset of the checksum after the entries, with `metadata_csum`.
+    pub(crate) fn tail_offset(&self) -> usize {
+        self.count_offset + self.limit * DX_ENTRY_SIZE
+    }
+
+    /// The length of the entries, which are covered by the checksum.
+    pub(crate) fn used_length(&self) -> usize {
+        self.count_offset + self.entries.len() * DX_ENTRY_SIZE
+    }
+
+    fn read(block: &[u8], count_offset: usize) -> Result<Self> {
+        let limit = read_u16(block, count_offset) as usize;
+        let count = read_u16(block, count_offset + 2) as usize;
+        ensure!(
+            count > 0 && count <= limit && count_offset + limit * DX_ENTRY_SIZE <= block.len(),
+            "corrupted directory index"
+        );
+        let entries = (0..count)
+            .map(|i| {
+                let offset = count_offset + i * DX_ENTRY_SIZE;
+                let hash = if i == 0 { 0 } else { read_u32(block, offset) };
+                (hash, read_u32(block, offset + 4))
+            })
+            .collect();
+        Ok(Self {
+            count_offset,
+            limit,
+            entries,
+        })
+    }
+
+    pub(crate) fn write(&self, block: &mut [u8]) {
+        let count = self.entries.len() as u16;
+        block[self.count_offset + 2..self.count_offset + 4].copy_from_slice(&count.to_le_bytes());
+        for (i, (hash, logical)) in self.entries.iter().enumerate() {
+            let offset = self.count_offset + i * DX_ENTRY_SIZE;
+            if i != 0 {
+                block[offset..offset + 4].copy_from_slice(&hash.to_le_bytes());
+            }
+            block[offset + 4..offset + 8].copy_from_slice(&logical.to_le_bytes());
+        }
+    }
+}
+
+/// The root of the hash index, stored in the first block of an indexed directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct DxRoot {
+    pub hash_version: u8,
+    pub indirect_levels: u8,
+    pub node: DxNode,
+}
+
+impl DxRoot {
+    pub(crate) fn read(block: &[u8]) -> Result<Self> {
+        let info_length = block[ROOT_INFO_OFFSET + 5] as usize;
+        ensure!(
+            read_u32(block, ROOT_INFO_OFFSET) == 0 && info_length == 8,
+            "corrupted directory index root"
+        );
+        Ok(Self {
+            hash_version: block[ROOT_INFO_OFFSET + 4],
+            indirect_levels: block[ROOT_INFO_OFFSET + 6],
+            node: DxNode::read(block, ROOT_INFO_OFFSET + info_length)?,
+        })
+    }
+}
+
+/// Read an interior node of the hash index.
+pub(crate) fn read_dx_node(block: &[u8]) -> Result<DxNode> {
+    DxNode::read(block, NODE_COUNT_OFFSET)
+}
+
+fn write_entry(
+    block: &mut [u8],
+    offset: usize,
+    rec_len: usize,
+    name: &[u8],
+    inode: u32,
+    file_type: u8,
+) {
+    let rec_len = rec_len_to_disk(rec_len, block.len());
+    block[offset..offset + 4].copy_from_slice(&inode.to_le_bytes());
+    block[offset + 4..offset + 6].copy_from_slice(&rec_len.to_le_bytes());
+    block[offset + 6] = name.len() as u8;
+    block[offset + 7] = file_type;
+    block[offset + 8..offset + 8 + name.len()].copy_from_slice(name);
+}
+
+// Blocks of 64KiB can't store their full length in the 16 bit field.
+fn rec_len_from_disk(rec_len: u16, block_size: usize) -> usize {
+    if block_size >= 65536 && (rec_len == 0 || rec_len == 65535) {
+        65536
+    } else {
+        rec_len as usize
+    }
+}
+
+fn rec_len_to_disk(rec_len: usize, block_size: usize) -> u16 {
+    if block_size >= 65536 && rec_len == 65536 {
+        65535
+    } else {
+        rec_len as u16
+    }
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names(block: &[u8], usable: usize) -> Vec<Vec<u8>> {
+        entries(block, usable)
+            .unwrap()
+            .into_iter()
+            .filter(|entry| entry.inode != 0)
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    #[test]
+    fn insert_remove_test() {
+        let mut block = vec![0; 1024];
+        let usable = block.len() - TAIL_SIZE;
+        init_block(
+            &mut block,
+            usable,
+            &[(b".", 12, FT_DIR), (b"..", 2, FT_DIR)],
+        );
+        assert_eq!(names(&block, usable), [b".".to_vec(), b"..".to_vec()]);
+
+        assert!(insert(&mut block, usable, b"a.txt", 13, FT_REG_FILE).unwrap());
+        assert!(insert(&mut block, usable, b"b.txt", 14, FT_REG_FILE).unwrap());
+        assert_eq!(
+            names(&block, usable),
+            [
+                b".".to_vec(),
+                b"..".to_vec(),
+                b"a.txt".to_vec(),
+                b"b.txt".to_vec()
+            ]
+        );
+
+        assert_eq!(remove(&mut block, usable, b"a.txt").unwrap(), Some(13));
+        assert_eq!(remove(&mut block, usable, b"a.txt").unwrap(), None);
+        assert_eq!(
+            names(&block, usable),
+            [b".".to_vec(), b"..".to_vec(), b"b.txt".to_vec()]
+        );
+
+        // The freed space is reused.
+        assert!(insert(&mut block, usable, b"c.txt", 15, FT_REG_FILE).unwrap());
+        let entries = entries(&block, usable).unwrap();
+        assert_eq!(entries[2].name, b"c.txt");
+        assert_eq!(entries[2].offset, 24);
+        assert_eq!(
+            entries.iter().map(|entry| entry.rec_len).sum::<usize>(),
+            usable
+        );
+
+        // Removing the first entry of a block keeps its space.
+        let mut block = vec![0; 1024];
+        init_block(
+            &mut block,
+            1024,
+            &[(b"x", 20, FT_REG_FILE), (b"y", 21, FT_REG_FILE)],
+        );
+        assert_eq!(remove(&mut block, 1024, b"x").unwrap(), Some(20));
+        assert_eq!(names(&block, 1024), [b"y".to_vec()]);
+        assert!(insert(&mut block, 1024, b"z", 22, FT_REG_FILE).unwrap());
+        assert_eq!(names(&block, 1024), [b"z".to_vec(), b"y".to_vec()]);
+    }
+
+    #[test]
+    fn full_block_test() {
+        let mut block = vec![0; 1024];
+        init_block(&mut block, 1024, &[]);
+        let name = [b'x'; 240];
+        for i in 0..4 {
+            let mut name = name;
+            name[0] = b'a' + i;
+            assert!(insert(&mut block, 1024, &name, 20 + u32::from(i), FT_REG_FILE).unwrap());
+        }
+        assert!(!insert(&mut block, 1024, &name, 30, FT_REG_FILE).unwrap());
+        assert!(insert(&mut block, 1024, b"short", 30, FT_REG_FILE).unwrap());
+    }
+
+    #[test]
+    fn dx_node_test() {
+        let mut block = vec![0; 1024];
+        block[4..6].copy_from_slice(&1024u16.to_le_bytes());
+        block[8..10].copy_from_slice(&127u16.to_le_bytes());
+        block[10..12].copy_from_slice(&1u16.to_le_bytes());
+        block[12..16].copy_from_slice(&1u32.to_le_bytes());
+        assert!(is_dx_node(&block));
+
+        let mut node = read_dx_node(&block).unwrap();
+        assert_eq!(node.entries, [(0, 1)]);
+        node.entries.push((0x8000_0000, 2));
+        node.write(&mut block);
+
+        let node = read_dx_node(&block).unwrap();
+        assert_eq!(node.entries, [(0, 1), (0x8000_0000, 2)]);
+        assert_eq!(node.lookup(0x1234), 0);
+        assert_eq!(node.lookup(0x8000_0000), 1);
+        assert_eq!(node.tail_offset(), 8 + 127 * 8);
+    }
+}
@@ -0,0 +1,1786 @@
+//! A small ext4 implementation, which supports reading files and inserting or replacing files in
+//! an existing filesystem.
+//!
+//! Like `debugfs`, changes bypass the journal, so the filesystem must not be mounted. Timestamps
+//! are always set to 0 and new files are owned by root, so that the same inputs always produce
+//! the same image.
+
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path};
+
+use anyhow::{bail, ensure, Context, Result};
+
+use super::checksum::{crc16, crc32c};
+use super::directory::{self, DirEntry, DxNode, DxRoot, FT_DIR, FT_REG_FILE, TAIL_SIZE};
+use super::hash::{dx_hash, DX_HASH_LEGACY_UNSIGNED, DX_HASH_TEA};
+use crate::volume::Volume;
+
+const SUPERBLOCK_OFFSET: u64 = 1024;
+const SUPERBLOCK_SIZE: usize = 1024;
+const SUPERBLOCK_CHECKSUM: usize = 0x3fc;
+const MAGIC: u16 = 0xef53;
+const ROOT_INODE: u32 = 2;
+const GOOD_OLD_INODE_SIZE: usize = 128;
+
+const COMPAT_DIR_INDEX: u32 = 0x20;
+const COMPAT_SPARSE_SUPER2: u32 = 0x200;
+
+const INCOMPAT_FILETYPE: u32 = 0x2;
+const INCOMPAT_RECOVER: u32 = 0x4;
+const INCOMPAT_EXTENTS: u32 = 0x40;
+const INCOMPAT_64BIT: u32 = 0x80;
+const INCOMPAT_FLEX_BG: u32 = 0x200;
+const INCOMPAT_CSUM_SEED: u32 = 0x2000;
+const INCOMPAT_LARGEDIR: u32 = 0x4000;
+const SUPPORTED_INCOMPAT: u32 = INCOMPAT_FILETYPE
+    | INCOMPAT_EXTENTS
+    | INCOMPAT_64BIT
+    | INCOMPAT_FLEX_BG
+    | INCOMPAT_CSUM_SEED
+    | INCOMPAT_LARGEDIR;
+
+const RO_COMPAT_SPARSE_SUPER: u32 = 0x1;
+const RO_COMPAT_LARGE_FILE: u32 = 0x2;
+const RO_COMPAT_HUGE_FILE: u32 = 0x8;
+const RO_COMPAT_GDT_CSUM: u32 = 0x10;
+const RO_COMPAT_DIR_NLINK: u32 = 0x20;
+const RO_COMPAT_EXTRA_ISIZE: u32 = 0x40;
+const RO_COMPAT_METADATA_CSUM: u32 = 0x400;
+// Quotas and bigalloc are missing on purpose, as writes would have to account for them.
+const SUPPORTED_RO_COMPAT: u32 = RO_COMPAT_SPARSE_SUPER
+    | RO_COMPAT_LARGE_FILE
+    | RO_COMPAT_HUGE_FILE
+    | RO_COMPAT_GDT_CSUM
+    | RO_COMPAT_DIR_NLINK
+    | RO_COMPAT_EXTRA_ISIZE
+    | RO_COMPAT_METADATA_CSUM;
+
+const FLAGS_UNSIGNED_HASH: u32 = 0x2;
+
+// Group descriptor fields, as the offsets of their low and high parts. The high parts only
+// exist with 64 byte descriptors.
+const BG_BLOCK_BITMAP: (usize, usize) = (0x0, 0x20);
+const BG_INODE_BITMAP: (usize, usize) = (0x4, 0x24);
+const BG_INODE_TABLE: (usize, usize) = (0x8, 0x28);
+const BG_FREE_BLOCKS: (usize, usize) = (0xc, 0x2c);
+const BG_FREE_INODES: (usize, usize) = (0xe, 0x2e);
+const BG_USED_DIRS: (usize, usize) = (0x10, 0x30);
+const BG_FLAGS: usize = 0x12;
+const BG_BLOCK_BITMAP_CSUM: (usize, usize) = (0x18, 0x38);
+const BG_INODE_BITMAP_CSUM: (usize, usize) = (0x1a, 0x3a);
+const BG_ITABLE_UNUSED: (usize, usize) = (0x1c, 0x32);
+const BG_CHECKSUM: usize = 0x1e;
+
+const BG_INODE_UNINIT: u16 = 0x1;
+const BG_BLOCK_UNINIT: u16 = 0x2;
+
+const S_IFMT: u16 = 0xf000;
+const S_IFREG: u16 = 0x8000;
+const S_IFDIR: u16 = 0x4000;
+
+const INODE_INDEX_FL: u32 = 0x1000;
+const INODE_HUGE_FILE_FL: u32 = 0x4_0000;
+const INODE_EXTENTS_FL: u32 = 0x8_0000;
+const INODE_INLINE_DATA_FL: u32 = 0x1000_0000;
+
+const EXTENT_MAGIC: u16 = 0xf30a;
+const EXTENT_HEADER_SIZE: usize = 12;
+const EXTENT_ENTRY_SIZE: usize = 12;
+const MAX_EXTENT_LEN: u32 = 32768;
+const MAX_EXTENT_DEPTH: u16 = 5;
+/// The number of extents that fit into the inode itself.
+const INODE_EXTENTS: usize = 4;
+
+const XATTR_MAGIC: u32 = 0xea02_0000;
+const XATTR_ENTRY_SIZE: usize = 16;
+const XATTR_INDEX_SECURITY: u8 = 6;
+const XATTR_BLOCK_REFCOUNT: usize = 0x4;
+const XATTR_BLOCK_CHECKSUM: usize = 0x10;
+
+/// The extra inode size set by `set_metadata`, which covers the extended timestamps.
+const METADATA_EXTRA_ISIZE: usize = 28;
+
+/// Directories with more links than this only count 1, with `dir_nlink`.
+const LINK_MAX: u16 = 65000;
+
+pub(crate) struct ExtFilesystem {
+    volume: Volume,
+    superblock: Vec<u8>,
+    groups: Vec<GroupDesc>,
+    block_size: u64,
+    /// The seed for metadata checksums, if they are enabled.
+    csum_seed: Option<u32>,
+}
+
+impl ExtFilesystem {
+    pub(crate) fn open(volume: Volume) -> Result<Self> {
+        let mut superblock = vec![0; SUPERBLOCK_SIZE];
+        volume
+            .read_at(&mut superblock, SUPERBLOCK_OFFSET)
+            .context("failed to read superblock")?;
+        ensure!(
+            read_u16(&superblock, 0x38) == MAGIC,
+            "volume has no ext filesystem"
+        );
+        ensure!(read_u32(&superblock, 0x4c) >= 1, "unsupported ext revision");
+
+        let incompat = read_u32(&superblock, 0x60);
+        ensure!(
+            incompat & INCOMPAT_RECOVER == 0,
+            "filesystem needs journal recovery"
+        );
+        ensure!(
+            incompat & !SUPPORTED_INCOMPAT == 0,
+            "unsupported incompatible features: {:#x}",
+            incompat & !SUPPORTED_INCOMPAT
+        );
+        ensure!(
+            incompat & INCOMPAT_FILETYPE != 0 && incompat & INCOMPAT_EXTENTS != 0,
+            "filesystem has no support for file types and extents"
+        );
+        let ro_compat = read_u32(&superblock, 0x64);
+        ensure!(
+            ro_compat & !SUPPORTED_RO_COMPAT == 0,
+            "unsupported read-only features: {:#x}",
+            ro_compat & !SUPPORTED_RO_COMPAT
+        );
+
+        let log_block_size = read_u32(&superblock, 0x18);
+        ensure!(log_block_size <= 6, "invalid block size");
+        let block_size = 1024 << log_block_size;
+
+        let csum_seed = if ro_compat & RO_COMPAT_METADATA_CSUM != 0 {
+            ensure!(
+                crc32c(!0, &superblock[..SUPERBLOCK_CHECKSUM])
+                    == read_u32(&superblock, SUPERBLOCK_CHECKSUM),
+                "superblock checksum mismatch"
+            );
+            Some(if incompat & INCOMPAT_CSUM_SEED != 0 {
+                read_u32(&superblock, 0x270)
+            } else {
+                crc32c(!0, &superblock[0x68..0x78])
+            })
+        } else {
+            None
+        };
+
+        let mut filesystem = Self {
+            volume,
+            superblock,
+            groups: Vec::new(),
+            block_size,
+            csum_seed,
+        };
+        ensure!(
+            filesystem.desc_size() >= 32
+                && filesystem.desc_size().is_power_of_two()
+                && filesystem.blocks_per_group() > 0
+                && filesystem.blocks_per_group() <= block_size * 8
+                && filesystem.inodes_per_group() > 0
+                && u64::from(filesystem.inodes_per_group()) <= block_size * 8
+                && filesystem.inode_size() >= GOOD_OLD_INODE_SIZE
+                && filesystem.blocks_count() > filesystem.first_data_block()
+                && filesystem.blocks_count() * block_size <= filesystem.volume.length(),
+            "invalid superblock"
+        );
+
+        let mut descriptors = vec![0; filesystem.group_count() * filesystem.desc_size()];
+        filesystem
+            .volume
+            .read_at(&mut descriptors, filesystem.gdt_offset())
+            .context("failed to read group descriptors")?;
+        filesystem.groups = descriptors
+            .chunks_exact(filesystem.desc_size())
+            .map(|raw| GroupDesc { raw: raw.to_vec() })
+            .collect();
+
+        Ok(filesystem)
+    }
+
+    /// Read the contents of the file at `path`.
+    pub(crate) fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        let ino = self
+            .resolve(path)?
+            .with_context(|| format!("File not found: {}", path.display()))?;
+        let inode = self.read_inode(ino)?;
+        ensure!(!inode.is_dir(), "{} is a directory", path.display());
+
+        self.read_data(&inode)
+    }
+
+    /// Write `contents` to a new file at `path`, replacing the file if it exists. Missing parent
+    /// directories are created.
+    pub(crate) fn write_file(
+        &mut self,
+        path: &Path,
+        contents: &[u8],
+        permissions: u16,
+    ) -> Result<()> {
+        let components = path_components(path)?;
+        let (name, parents) = components.split_last().context("path has no file name")?;
+        ensure!(
+            contents.len() < 1 << 31 || self.has_ro_compat(RO_COMPAT_LARGE_FILE),
+            "file is too large for the filesystem"
+        );
+
+        let mut dir = ROOT_INODE;
+        for parent in parents {
+            dir = match self.lookup(dir, parent)? {
+                Some(ino) => ino,
+                None => self.mkdir(dir, parent)?,
+            };
+        }
+
+        if let Some(ino) = self.lookup(dir, name)? {
+            ensure!(
+                !self.read_inode(ino)?.is_dir(),
+                "{} is a directory",
+                path.display()
+            );
+            self.unlink(dir, name)?;
+        }
+
+        let ino = self.allocate_inode(false)?;
+        let mut inode = self.new_inode(S_IFREG | (permissions & 0o7777), 1);
+
+        let block_size = self.block_size as usize;
+        let mut extents = Vec::new();
+        let mut logical = 0;
+        for (start, len) in self.allocate_blocks(contents.len().div_ceil(block_size) as u64)? {
+            let begin = logical as usize * block_size;
+            let end = (begin + len as usize * block_size).min(contents.len());
+            let mut data = contents[begin..end].to_vec();
+            data.resize(len as usize * block_size, 0);
+            self.volume.write_at(&data, start * self.block_size)?;
+
+            let mut offset = 0;
+            while offset < len {
+                let extent_len = (len - offset).min(u64::from(MAX_EXTENT_LEN));
+                extents.push(Extent {
+                    logical: logical + offset as u32,
+                    start: start + offset,
+                    len: extent_len as u32,
+                    uninit: false,
+                });
+                offset += extent_len;
+            }
+            logical += len as u32;
+        }
+        inode.set_size(contents.len() as u64);
+        self.set_extents(ino, &mut inode, &extents)?;
+        self.write_inode(ino, &mut inode)?;
+        self.add_entry(dir, name, ino, FT_REG_FILE)?;
+
+        self.flush()
+    }
+
+    /// Set the mode and the SELinux context of the file at `path`, and reset its ownership and
+    /// timestamps.
+    pub(crate) fn set_metadata(
+        &mut self,
+        path: &Path,
+        mode: u16,
+        context: Option<&str>,
+    ) -> Result<()> {
+        let ino = self
+            .resolve(path)?
+            .with_context(|| format!("File not found: {}", path.display()))?;
+        let mut inode = self.read_inode(ino)?;
+        ensure!(
+            self.inode_size() >= GOOD_OLD_INODE_SIZE + METADATA_EXTRA_ISIZE + 4,
+            "inodes are too small for extended metadata"
+        );
+
+        let mut xattrs = inode.xattrs()?;
+        if let Some(context) = context {
+            let mut value = context.as_bytes().to_vec();
+            value.push(0);
+            xattrs
+                .retain(|xattr| !(xattr.index == XATTR_INDEX_SECURITY && xattr.name == b"selinux"));
+            xattrs.push(Xattr {
+                index: XATTR_INDEX_SECURITY,
+                name: b"selinux".to_vec(),
+                value,
+            });
+        }
+        inode.set_u16(0x80, METADATA_EXTRA_ISIZE as u16);
+        inode.set_xattrs(&xattrs)?;
+
+        inode.set_u16(0x0, mode);
+        inode.set_root_owner();
+        inode.clear_times();
+        self.write_inode(ino, &mut inode)?;
+
+        self.flush()
+    }
+
+    fn resolve(&self, path: &Path) -> Result<Option<u32>> {
+        let mut ino = ROOT_INODE;
+        for component in path_components(path)? {
+            match self.lookup(ino, &component)? {
+                Some(child) => ino = child,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(ino))
+    }
+
+    /// Find the inode of the entry `name` in the directory `dir`.
+    fn lookup(&self, dir: u32, name: &[u8]) -> Result<Option<u32>> {
+        let inode = self.read_inode(dir)?;
+        ensure!(
+            inode.is_dir(),
+            "{} is not a directory",
+            String::from_utf8_lossy(name)
+        );
+
+        for (_, _, block) in self.dir_leaves(&inode)? {
+            if let Some(entry) = directory::entries(&block, self.dir_usable())?
+                .into_iter()
+                .find(|entry| entry.inode != 0 && entry.name == name)
+            {
+                return Ok(Some(entry.inode));
+            }
+        }
+        Ok(None)
+    }
+
+    /// All leaf blocks of a directory, as (logical block, physical block, contents).
+    fn dir_leaves(&self, inode: &Inode) -> Result<Vec<(usize, u64, Vec<u8>)>> {
+        let indexed = self.is_indexed(inode);
+        let mut leaves = Vec::new();
+        for (logical, block_nr) in self.dir_blocks(inode)?.into_iter().enumerate() {
+            let block = self.read_block(block_nr)?;
+            // The index blocks of an indexed directory don't hold any entries.
+            if indexed && (logical == 0 || directory::is_dx_node(&block)) {
+                continue;
+            }
+            leaves.push((logical, block_nr, block));
+        }
+        Ok(leaves)
+    }
+
+    fn mkdir(&mut self, parent: u32, name: &[u8]) -> Result<u32> {
+        let ino = self.allocate_inode(true)?;
+        let (block_nr, _) = self.allocate_blocks(1)?[0];
+
+        let mut inode = self.new_inode(S_IFDIR | 0o755, 2);
+        inode.set_size(self.block_size);
+        self.set_extents(
+            ino,
+            &mut inode,
+            &[Extent {
+                logical: 0,
+                start: block_nr,
+                len: 1,
+                uninit: false,
+            }],
+        )?;
+
+        let mut block = vec![0; self.block_size as usize];
+        directory::init_block(
+            &mut block,
+            self.dir_usable(),
+            &[(b".", ino, FT_DIR), (b"..", parent, FT_DIR)],
+        );
+        self.write_dir_leaf(ino, &inode, block_nr, &mut block)?;
+        self.write_inode(ino, &mut inode)?;
+
+        self.add_entry(parent, name, ino, FT_DIR)?;
+        let mut parent_inode = self.read_inode(parent)?;
+        let links = parent_inode.links();
+        if links != 1 {
+            let links = if links + 1 >= LINK_MAX {
+                ensure!(
+                    self.has_ro_compat(RO_COMPAT_DIR_NLINK),
+                    "too many subdirectories"
+                );
+                1
+            } else {
+                links + 1
+            };
+            parent_inode.set_u16(0x1a, links);
+        }
+        self.write_inode(parent, &mut parent_inode)?;
+
+        Ok(ino)
+    }
+
+    fn add_entry(&mut self, dir: u32, name: &[u8], ino: u32, file_type: u8) -> Result<()> {
+        let mut dir_inode = self.read_inode(dir)?;
+
+        if self.is_indexed(&dir_inode) {
+            self.add_indexed_entry(dir, &mut dir_inode, name, ino, file_type)?;
+        } else {
+            let mut added = false;
+            for block_nr in self.dir_blocks(&dir_inode)? {
+                let mut block = self.read_block(block_nr)?;
+                if directory::insert(&mut block, self.dir_usable(), name, ino, file_type)? {
+                    self.write_dir_leaf(dir, &dir_inode, block_nr, &mut block)?;
+                    added = true;
+                    break;
+                }
+            }
+            if !added {
+                let (_, block_nr) = self.append_dir_block(dir, &mut dir_inode)?;
+                let mut block = vec![0; self.block_size as usize];
+                directory::init_block(&mut block, self.dir_usable(), &[(name, ino, file_type)]);
+                self.write_dir_leaf(dir, &dir_inode, block_nr, &mut block)?;
+            }
+        }
+
+        dir_inode.clear_modification_times();
+        self.write_inode(dir, &mut dir_inode)
+    }
+
+    /// Add an entry to a directory with a hash index, splitting the leaf if it is full.
+    fn add_indexed_entry(
+        &mut self,
+        dir: u32,
+        dir_inode: &mut Inode,
+        name: &[u8],
+        ino: u32,
+        file_type: u8,
+    ) -> Result<()> {
+        let blocks = self.dir_blocks(dir_inode)?;
+        let root_nr = *blocks.first().context("indexed directory is empty")?;
+        let root_block = self.read_block(root_nr)?;
+        let root = DxRoot::read(&root_block)?;
+
+        let mut version = root.hash_version;
+        if version <= DX_HASH_TEA && self.superblock_flags() & FLAGS_UNSIGNED_HASH != 0 {
+            version += DX_HASH_LEGACY_UNSIGNED;
+        }
+        let seed = self.hash_seed();
+        let hash = dx_hash(name, version, seed)?;
+
+        // Walk down the index to the leaf for the hash.
+        let (mut node_nr, mut node_block, mut node) = (root_nr, root_block, root.node);
+        let mut level = 0;
+        let leaf_logical = loop {
+            let child = node.entries[node.lookup(hash)].1 as usize;
+            ensure!(
+                child > 0 && child < blocks.len(),
+                "directory index points outside the directory"
+            );
+            if level == root.indirect_levels {
+                break child;
+            }
+            node_nr = blocks[child];
+            node_block = self.read_block(node_nr)?;
+            node = directory::read_dx_node(&node_block)?;
+            level += 1;
+        };
+
+        let usable = self.dir_usable();
+        let leaf_nr = blocks[leaf_logical];
+        let mut leaf = self.read_block(leaf_nr)?;
+        if directory::insert(&mut leaf, usable, name, ino, file_type)? {
+            return self.write_dir_leaf(dir, dir_inode, leaf_nr, &mut leaf);
+        }
+
+        // Move the upper half of the entries, ordered by hash, into a new leaf.
+        ensure!(node.entries.len() < node.limit, "directory index is full");
+        let mut entries = directory::entries(&leaf, usable)?
+            .into_iter()
+            .filter(|entry| entry.inode != 0)
+            .map(|entry| Ok((dx_hash(&entry.name, version, seed)?, entry)))
+            .collect::<Result<Vec<(u32, DirEntry)>>>()?;
+        entries.sort_by_key(|(hash, _)| *hash);
+        ensure!(entries.len() >= 2, "directory block is full");
+        let split = entries.len() / 2;
+        let split_hash = entries[split].0;
+        // Mark that names with this hash continue in the previous leaf.
+        let continued = u32::from(split_hash == entries[split - 1].0);
+
+        let (new_logical, new_nr) = self.append_dir_block(dir, dir_inode)?;
+        let init_block = |block: &mut [u8], entries: &[(u32, DirEntry)]| {
+            let entries: Vec<(&[u8], u32, u8)> = entries
+                .iter()
+                .map(|(_, entry)| (entry.name.as_slice(), entry.inode, entry.file_type))
+                .collect();
+            directory::init_block(block, usable, &entries);
+        };
+        let mut new_leaf = vec![0; self.block_size as usize];
+        init_block(&mut leaf, &entries[..split]);
+        init_block(&mut new_leaf, &entries[split..]);
+
+        let target = if hash >= split_hash {
+            &mut new_leaf
+        } else {
+            &mut leaf
+        };
+        ensure!(
+            directory::insert(target, usable, name, ino, file_type)?,
+            "directory block is full after splitting"
+        );
+        self.write_dir_leaf(dir, dir_inode, leaf_nr, &mut leaf)?;
+        self.write_dir_leaf(dir, dir_inode, new_nr, &mut new_leaf)?;
+
+        let index = node.lookup(hash);
+        node.entries
+            .insert(index + 1, (split_hash | continued, new_logical));
+        self.write_dx_block(dir, dir_inode, node_nr, &mut node_block, &node)
+    }
+
+    /// Remove the entry `name` from a directory, and release its inode if it has no links left.
+    fn unlink(&mut self, dir: u32, name: &[u8]) -> Result<()> {
+        let mut dir_inode = self.read_inode(dir)?;
+        for (_, block_nr, mut block) in self.dir_leaves(&dir_inode)? {
+            let Some(ino) = directory::remove(&mut block, self.dir_usable(), name)? else {
+                continue;
+            };
+            self.write_dir_leaf(dir, &dir_inode, block_nr, &mut block)?;
+            dir_inode.clear_modification_times();
+            self.write_inode(dir, &mut dir_inode)?;
+
+            let mut inode = self.read_inode(ino)?;
+            match inode.links().saturating_sub(1) {
+                0 => self.release_inode(ino, &inode)?,
+                links => {
+                    inode.set_u16(0x1a, links);
+                    self.write_inode(ino, &mut inode)?;
+                }
+            }
+            return Ok(());
+        }
+
+        bail!("File not found: {}", String::from_utf8_lossy(name))
+    }
+
+    fn release_inode(&mut self, ino: u32, inode: &Inode) -> Result<()> {
+        let (extents, index_blocks) = self.read_extents(inode)?;
+        let mut runs: Vec<(u64, u64)> = extents
+            .iter()
+            .map(|extent| (extent.start, u64::from(extent.len)))
+            .collect();
+        runs.extend(index_blocks.iter().map(|block_nr| (*block_nr, 1)));
+
+        let xattr_block = inode.file_acl();
+        if xattr_block != 0 {
+            let mut block = self.read_block(xattr_block)?;
+            match read_u32(&block, XATTR_BLOCK_REFCOUNT) {
+                0 | 1 => runs.push((xattr_block, 1)),
+                refcount => {
+                    block[XATTR_BLOCK_REFCOUNT..XATTR_BLOCK_REFCOUNT + 4]
+                        .copy_from_slice(&(refcount - 1).to_le_bytes());
+                    if let Some(seed) = self.csum_seed {
+                        block[XATTR_BLOCK_CHECKSUM..XATTR_BLOCK_CHECKSUM + 4].fill(0);
+                        let checksum = crc32c(crc32c(seed, &xattr_block.to_le_bytes()), &block);
+                        block[XATTR_BLOCK_CHECKSUM..XATTR_BLOCK_CHECKSUM + 4]
+                            .copy_from_slice(&checksum.to_le_bytes());
+                    }
+                    self.write_block(xattr_block, &block)?;
+                }
+            }
+        }
+
+        self.free_blocks(&runs)?;
+        self.free_inode(ino, inode.is_dir())?;
+        // Unused inodes are all zeros, which is also a valid checksum.
+        self.volume
+            .write_at(&vec![0; self.inode_size()], self.inode_offset(ino)?)
+    }
+
+    /// Add a block to the end of a directory, returning its logical and physical number.
+    fn append_dir_block(&mut self, dir: u32, dir_inode: &mut Inode) -> Result<(u32, u64)> {
+        let (mut extents, index_blocks) = self.read_extents(dir_inode)?;
+        let logical = (dir_inode.size() / self.block_size) as u32;
+        let (block_nr, _) = self.allocate_blocks(1)?[0];
+
+        match extents.last_mut() {
+            Some(last)
+                if !last.uninit
+                    && last.logical + last.len == logical
+                    && last.start + u64::from(last.len) == block_nr
+                    && last.len < MAX_EXTENT_LEN =>
+            {
+                last.len += 1
+            }
+            _ => extents.push(Extent {
+                logical,
+                start: block_nr,
+                len: 1,
+                uninit: false,
+            }),
+        }
+
+        let runs: Vec<(u64, u64)> = index_blocks.iter().map(|block_nr| (*block_nr, 1)).collect();
+        self.free_blocks(&runs)?;
+        self.set_extents(dir, dir_inode, &extents)?;
+        dir_inode.set_size(dir_inode.size() + self.block_size);
+
+        Ok((logical, block_nr))
+    }
+
+    fn is_indexed(&self, inode: &Inode) -> bool {
+        inode.flags() & INODE_INDEX_FL != 0 && self.feature_compat() & COMPAT_DIR_INDEX != 0
+    }
+
+    /// The bytes of a leaf directory block available for entries.
+    fn dir_usable(&self) -> usize {
+        match self.csum_seed {
+            Some(_) => self.block_size as usize - TAIL_SIZE,
+            None => self.block_size as usize,
+        }
+    }
+
+    /// The physical blocks of a directory, by logical block.
+    fn dir_blocks(&self, inode: &Inode) -> Result<Vec<u64>> {
+        let (mut extents, _) = self.read_extents(inode)?;
+        extents.sort_by_key(|extent| extent.logical);
+
+        let mut blocks = Vec::new();
+        for extent in extents {
+            ensure!(
+                extent.logical as usize == blocks.len(),
+                "directories with holes are not supported"
+            );
+            blocks.extend((0..u64::from(extent.len)).map(|i| extent.start + i));
+        }
+        Ok(blocks)
+    }
+
+    fn write_dir_leaf(
+        &self,
+        dir: u32,
+        dir_inode: &Inode,
+        block_nr: u64,
+        block: &mut [u8],
+    ) -> Result<()> {
+        if let Some(seed) = self.inode_seed(dir, dir_inode) {
+            let checksum = crc32c(seed, &block[..block.len() - TAIL_SIZE]);
+            directory::set_tail(block, checksum);
+        }
+        self.write_block(block_nr, block)
+    }
+
+    fn write_dx_block(
+        &self,
+        dir: u32,
+        dir_inode: &Inode,
+        block_nr: u64,
+        block: &mut [u8],
+        node: &DxNode,
+    ) -> Result<()> {
+        node.write(block);
+        if let Some(seed) = self.inode_seed(dir, dir_inode) {
+            let tail = node.tail_offset();
+            ensure!(tail + 8 <= block.len(), "directory index has no checksum");
+            let mut checksum = crc32c(seed, &block[..node.used_length()]);
+            checksum = crc32c(checksum, &block[tail..tail + 4]);
+            checksum = crc32c(checksum, &[0; 4]);
+            block[tail + 4..tail + 8].copy_from_slice(&checksum.to_le_bytes());
+        }
+        self.write_block(block_nr, block)
+    }
+
+    /// All extents of an inode, and the blocks of its extent tree.
+    fn read_extents(&self, inode: &Inode) -> Result<(Vec<Extent>, Vec<u64>)> {
+        ensure!(
+            inode.flags() & INODE_INLINE_DATA_FL == 0,
+            "inline data is not supported"
+        );
+        let mut extents = Vec::new();
+        let mut index_blocks = Vec::new();
+        if inode.flags() & INODE_EXTENTS_FL == 0 {
+            ensure!(
+                inode.blocks() == 0,
+                "files without extents are not supported"
+            );
+            return Ok((extents, index_blocks));
+        }
+
+        self.walk_extents(
+            inode.i_block(),
+            MAX_EXTENT_DEPTH,
+            &mut extents,
+            &mut index_blocks,
+        )?;
+        Ok((extents, index_blocks))
+    }
+
+    fn walk_extents(
+        &self,
+        node: &[u8],
+        max_depth: u16,
+        extents: &mut Vec<Extent>,
+        index_blocks: &mut Vec<u64>,
+    ) -> Result<()> {
+        ensure!(read_u16(node, 0) == EXTENT_MAGIC, "invalid extent header");
+        let entries = read_u16(node, 2) as usize;
+        let depth = read_u16(node, 6);
+        ensure!(
+            depth <= max_depth && EXTENT_HEADER_SIZE + entries * EXTENT_ENTRY_SIZE <= node.len(),
+            "invalid extent header"
+        );
+
+        for i in 0..entries {
+            let entry = &node[EXTENT_HEADER_SIZE + i * EXTENT_ENTRY_SIZE..][..EXTENT_ENTRY_SIZE];
+            if depth == 0 {
+                let len = u32::from(read_u16(entry, 4));
+                let (len, uninit) = if len > MAX_EXTENT_LEN {
+                    (len - MAX_EXTENT_LEN, true)
+                } else {
+                    (len, false)
+                };
+                extents.push(Extent {
+                    logical: read_u32(entry, 0),
+                    start: (u64::from(read_u16(entry, 6)) << 32) | u64::from(read_u32(entry, 8)),
+                    len,
+                    uninit,
+                });
+            } else {
+                let child = (u64::from(read_u16(entry, 8)) << 32) | u64::from(read_u32(entry, 4));
+                index_blocks.push(child);
+                let block = self.read_block(child)?;
+                self.walk_extents(&block, depth - 1, extents, index_blocks)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Store the extents of an inode, in the inode itself or in a single level of leaf blocks,
+    /// and update its block count.
+    fn set_extents(&mut self, ino: u32, inode: &mut Inode, extents: &[Extent]) -> Result<()> {
+        let mut root = [0; 60];
+        let index_blocks = if extents.len() <= INODE_EXTENTS {
+            write_extent_node(&mut root, 0, INODE_EXTENTS, extents);
+            0
+        } else {
+            let per_block = (self.block_size as usize - EXTENT_HEADER_SIZE) / EXTENT_ENTRY_SIZE;
+            let leaves: Vec<&[Extent]> = extents.chunks(per_block).collect();
+            ensure!(leaves.len() <= INODE_EXTENTS, "file is too fragmented");
+
+            let leaf_blocks: Vec<u64> = self
+                .allocate_blocks(leaves.len() as u64)?
+                .into_iter()
+                .flat_map(|(start, len)| start..start + len)
+                .collect();
+            let seed = self.inode_seed(ino, inode);
+            for (leaf, block_nr) in leaves.iter().zip(&leaf_blocks) {
+                let mut block = vec![0; self.block_size as usize];
+                write_extent_node(&mut block, 0, per_block, leaf);
+                if let Some(seed) = seed {
+                    let tail = EXTENT_HEADER_SIZE + per_block * EXTENT_ENTRY_SIZE;
+                    let checksum = crc32c(seed, &block[..tail]);
+                    block[tail..tail + 4].copy_from_slice(&checksum.to_le_bytes());
+                }
+                self.write_block(*block_nr, &block)?;
+            }
+
+            write_extent_header(&mut root, leaves.len(), INODE_EXTENTS, 1);
+            for (i, (leaf, block_nr)) in leaves.iter().zip(&leaf_blocks).enumerate() {
+                let entry =
+                    &mut root[EXTENT_HEADER_SIZE + i * EXTENT_ENTRY_SIZE..][..EXTENT_ENTRY_SIZE];
+                entry[0..4].copy_from_slice(&leaf[0].logical.to_le_bytes());
+                entry[4..8].copy_from_slice(&(*block_nr as u32).to_le_bytes());
+                entry[8..10].copy_from_slice(&((*block_nr >> 32) as u16).to_le_bytes());
+            }
+            leaf_blocks.len() as u64
+        };
+
+        inode.raw[0x28..0x64].copy_from_slice(&root);
+        inode.set_u32(
+            0x20,
+            (inode.flags() | INODE_EXTENTS_FL) & !INODE_HUGE_FILE_FL,
+        );
+        let data_blocks: u64 = extents.iter().map(|extent| u64::from(extent.len)).sum();
+        let xattr_blocks = u64::from(inode.file_acl() != 0);
+        inode.set_blocks((data_blocks + index_blocks + xattr_blocks) * (self.block_size / 512));
+
+        Ok(())
+    }
+
+    fn read_data(&self, inode: &Inode) -> Result<Vec<u8>> {
+        let size = usize::try_from(inode.size()).context("file is too large")?;
+        let mut data = vec![0; size];
+        let (extents, _) = self.read_extents(inode)?;
+        for extent in extents.iter().filter(|extent| !extent.uninit) {
+            let begin = extent.logical as usize * self.block_size as usize;
+            if begin >= size {
+                continue;
+            }
+            let end = (begin + extent.len as usize * self.block_size as usize).min(size);
+            self.volume
+                .read_at(&mut data[begin..end], extent.start * self.block_size)?;
+        }
+        Ok(data)
+    }
+
+    fn new_inode(&self, mode: u16, links: u16) -> Inode {
+        let mut inode = Inode {
+            raw: vec![0; self.inode_size()],
+        };
+        inode.set_u16(0x0, mode);
+        inode.set_u16(0x1a, links);
+        inode.set_u32(0x20, INODE_EXTENTS_FL);
+        if self.inode_size() > GOOD_OLD_INODE_SIZE {
+            let available = self.inode_size() - GOOD_OLD_INODE_SIZE;
+            let want = read_u16(&self.superblock, 0x15e) as usize;
+            let extra_isize = if (4..=available).contains(&want) {
+                want
+            } else {
+                32.min(available)
+            };
+            inode.set_u16(0x80, extra_isize as u16);
+        }
+        inode
+    }
+
+    fn inode_offset(&self, ino: u32) -> Result<u64> {
+        ensure!(
+            ino >= 1 && ino <= read_u32(&self.superblock, 0x0),
+            "invalid inode {ino}"
+        );
+        let group = ((ino - 1) / self.inodes_per_group()) as usize;
+        let index = u64::from((ino - 1) % self.inodes_per_group());
+        Ok(self.groups[group].get32(BG_INODE_TABLE) * self.block_size
+            + index * self.inode_size() as u64)
+    }
+
+    fn read_inode(&self, ino: u32) -> Result<Inode> {
+        let mut inode = Inode {
+            raw: vec![0; self.inode_size()],
+        };
+        self.volume
+            .read_at(&mut inode.raw, self.inode_offset(ino)?)?;
+        if let Some(seed) = self.inode_seed(ino, &inode) {
+            let (checksum, has_hi) = inode.checksum(seed);
+            let stored = u32::from(inode.u16(0x7c))
+                | if has_hi {
+                    u32::from(inode.u16(0x82)) << 16
+                } else {
+                    0
+                };
+            let checksum = if has_hi { checksum } else { checksum & 0xffff };
+            ensure!(checksum == stored, "inode {ino} checksum mismatch");
+        }
+        Ok(inode)
+    }
+
+    fn write_inode(&self, ino: u32, inode: &mut Inode) -> Result<()> {
+        if let Some(seed) = self.inode_seed(ino, inode) {
+            let (checksum, has_hi) = inode.checksum(seed);
+            inode.set_u16(0x7c, checksum as u16);
+            if has_hi {
+                inode.set_u16(0x82, (checksum >> 16) as u16);
+            }
+        }
+        self.volume.write_at(&inode.raw, self.inode_offset(ino)?)
+    }
+
+    /// The checksum seed of an inode, which all its metadata checksums start from.
+    fn inode_seed(&self, ino: u32, inode: &Inode) -> Option<u32> {
+        self.csum_seed.map(|seed| {
+            let seed = crc32c(seed, &ino.to_le_bytes());
+            crc32c(seed, &inode.u32(0x64).to_le_bytes())
+        })
+    }
+
+    fn allocate_inode(&mut self, is_dir: bool) -> Result<u32> {
+        let inodes_per_group = self.inodes_per_group();
+        let first_ino = read_u32(&self.superblock, 0x54);
+        for group in 0..self.groups.len() {
+            if self.groups[group].get16(BG_FREE_INODES) == 0 {
+                continue;
+            }
+            let mut bitmap = self.read_inode_bitmap(group)?;
+            let first_index = first_ino.saturating_sub(group as u32 * inodes_per_group + 1);
+            let Some(index) =
+                (first_index..inodes_per_group).find(|index| !bit(&bitmap, u64::from(*index)))
+            else {
+                continue;
+            };
+            set_bit(&mut bitmap, u64::from(index));
+            self.write_inode_bitmap(group, &bitmap)?;
+
+            let desc = &mut self.groups[group];
+            desc.set16(BG_FREE_INODES, desc.get16(BG_FREE_INODES) - 1);
+            if is_dir {
+                desc.set16(BG_USED_DIRS, desc.get16(BG_USED_DIRS) + 1);
+            }
+            // Inodes past the used part of the inode table may contain garbage.
+            let unused = desc.get16(BG_ITABLE_UNUSED);
+            if index >= inodes_per_group - unused {
+                desc.set16(BG_ITABLE_UNUSED, inodes_per_group - index - 1);
+            }
+            let free = read_u32(&self.superblock, 0x10);
+            self.superblock[0x10..0x14].copy_from_slice(&(free - 1).to_le_bytes());
+
+            return Ok(group as u32 * inodes_per_group + index + 1);
+        }
+        bail!("no free inodes")
+    }
+
+    fn free_inode(&mut self, ino: u32, is_dir: bool) -> Result<()> {
+        let group = ((ino - 1) / self.inodes_per_group()) as usize;
+        let index = u64::from((ino - 1) % self.inodes_per_group());
+        let mut bitmap = self.read_inode_bitmap(group)?;
+        ensure!(bit(&bitmap, index), "inode {ino} is already free");
+        clear_bit(&mut bitmap, index);
+        self.write_inode_bitmap(group, &bitmap)?;
+
+        let desc = &mut self.groups[group];
+        desc.set16(BG_FREE_INODES, desc.get16(BG_FREE_INODES) + 1);
+        if is_dir {
+            desc.set16(BG_USED_DIRS, desc.get16(BG_USED_DIRS).saturating_sub(1));
+        }
+        let free = read_u32(&self.superblock, 0x10);
+        self.superblock[0x10..0x14].copy_from_slice(&(free + 1).to_le_bytes());
+        Ok(())
+    }
+
+    /// Allocate `count` blocks, returning them as runs of (first block, length).
+    fn allocate_blocks(&mut self, count: u64) -> Result<Vec<(u64, u64)>> {
+        ensure!(self.free_blocks_count() >= count, "not enough free space");
+
+        let mut runs: Vec<(u64, u64)> = Vec::new();
+        let mut remaining = count;
+        for group in 0..self.groups.len() {
+            if remaining == 0 {
+                break;
+            }
+            if self.groups[group].get16(BG_FREE_BLOCKS) == 0 {
+                continue;
+            }
+
+            let mut bitmap = self.read_block_bitmap(group)?;
+            let first = self.group_first_block(group);
+            let mut allocated = 0;
+            for index in 0..self.blocks_in_group(group) {
+                if remaining == 0 {
+                    break;
+                }
+                if bit(&bitmap, index) {
+                    continue;
+                }
+                set_bit(&mut bitmap, index);
+                allocated += 1;
+                remaining -= 1;
+                match runs.last_mut() {
+                    Some((start, len)) if *start + *len == first + index => *len += 1,
+                    _ => runs.push((first + index, 1)),
+                }
+            }
+
+            if allocated > 0 {
+                self.write_block_bitmap(group, &bitmap)?;
+                let desc = &mut self.groups[group];
+                desc.set16(
+                    BG_FREE_BLOCKS,
+                    desc.get16(BG_FREE_BLOCKS) - allocated as u32,
+                );
+                self.set_free_blocks_count(self.free_blocks_count() - allocated);
+            }
+        }
+        ensure!(remaining == 0, "not enough free space");
+
+        Ok(runs)
+    }
+
+    fn free_blocks(&mut self, runs: &[(u64, u64)]) -> Result<()> {
+        for (start, len) in runs {
+            let (mut block_nr, end) = (*start, start + len);
+            while block_nr < end {
+                ensure!(
+                    block_nr >= self.first_data_block() && end <= self.blocks_count(),
+                    "invalid block {block_nr}"
+                );
+                let group =
+                    ((block_nr - self.first_data_block()) / self.blocks_per_group()) as usize;
+                let first = self.group_first_block(group);
+                let group_end = end.min(first + self.blocks_per_group());
+
+                let mut bitmap = self.read_block_bitmap(group)?;
+                for block in block_nr..group_end {
+                    ensure!(bit(&bitmap, block - first), "block {block} is already free");
+                    clear_bit(&mut bitmap, block - first);
+                }
+                self.write_block_bitmap(group, &bitmap)?;
+
+                let freed = group_end - block_nr;
+                let desc = &mut self.groups[group];
+                desc.set16(BG_FREE_BLOCKS, desc.get16(BG_FREE_BLOCKS) + freed as u32);
+                self.set_free_blocks_count(self.free_blocks_count() + freed);
+                block_nr = group_end;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_block_bitmap(&self, group: usize) -> Result<Vec<u8>> {
+        if self.groups[group].flags() & BG_BLOCK_UNINIT != 0 {
+            return Ok(self.init_block_bitmap(group));
+        }
+        self.read_block(self.groups[group].get32(BG_BLOCK_BITMAP))
+    }
+
+    /// Compute the block bitmap of a group that has never been initialized, in which only the
+    /// filesystem metadata is in use.
+    fn init_block_bitmap(&self, group: usize) -> Vec<u8> {
+        let mut bitmap = vec![0; self.block_size as usize];
+        let first = self.group_first_block(group);
+        let count = self.blocks_in_group(group);
+        let mut mark = |start: u64, len: u64| {
+            for block in start.max(first)..(start + len).min(first + count) {
+                set_bit(&mut bitmap, block - first);
+            }
+        };
+
+        if self.has_superblock(group) {
+            let reserved_gdt_blocks = u64::from(read_u16(&self.superblock, 0xce));
+            mark(first, 1 + self.gdt_blocks() + reserved_gdt_blocks);
+        }
+        let inode_table_blocks = (u64::from(self.inodes_per_group()) * self.inode_size() as u64)
+            .div_ceil(self.block_size);
+        for desc in &self.groups {
+            mark(desc.get32(BG_BLOCK_BITMAP), 1);
+            mark(desc.get32(BG_INODE_BITMAP), 1);
+            mark(desc.get32(BG_INODE_TABLE), inode_table_blocks);
+        }
+        // Blocks past the end of the group are always in use.
+        for index in count..self.block_size * 8 {
+            set_bit(&mut bitmap, index);
+        }
+        bitmap
+    }
+
+    fn write_block_bitmap(&mut self, group: usize, bitmap: &[u8]) -> Result<()> {
+        let bytes = (self.blocks_per_group() / 8) as usize;
+        let checksum = self.csum_seed.map(|seed| crc32c(seed, &bitmap[..bytes]));
+        let desc = &mut self.groups[group];
+        desc.set_flags(desc.flags() & !BG_BLOCK_UNINIT);
+        if let Some(checksum) = checksum {
+            desc.set16(BG_BLOCK_BITMAP_CSUM, checksum);
+        }
+        self.write_block(self.groups[group].get32(BG_BLOCK_BITMAP), bitmap)
+    }
+
+    fn read_inode_bitmap(&self, group: usize) -> Result<Vec<u8>> {
+        if self.groups[group].flags() & BG_INODE_UNINIT != 0 {
+            let mut bitmap = vec![0; self.block_size as usize];
+            for index in u64::from(self.inodes_per_group())..self.block_size * 8 {
+                set_bit(&mut bitmap, index);
+            }
+            return Ok(bitmap);
+        }
+        self.read_block(self.groups[group].get32(BG_INODE_BITMAP))
+    }
+
+    fn write_inode_bitmap(&mut self, group: usize, bitmap: &[u8]) -> Result<()> {
+        let bytes = (self.inodes_per_group() / 8) as usize;
+        let checksum = self.csum_seed.map(|seed| crc32c(seed, &bitmap[..bytes]));
+        let desc = &mut self.groups[group];
+        desc.set_flags(desc.flags() & !BG_INODE_UNINIT);
+        if let Some(checksum) = checksum {
+            desc.set16(BG_INODE_BITMAP_CSUM, checksum);
+        }
+        self.write_block(self.groups[group].get32(BG_INODE_BITMAP), bitmap)
+    }
+
+    /// Write the group descriptors and the superblock. Like the kernel, only the primary copies
+    /// are updated.
+    fn flush(&mut self) -> Result<()> {
+        let mut descriptors = Vec::with_capacity(self.groups.len() * self.desc_size());
+        for group in 0..self.groups.len() {
+            if let Some(checksum) = self.group_checksum(group) {
+                self.groups[group].raw[BG_CHECKSUM..BG_CHECKSUM + 2]
+                    .copy_from_slice(&checksum.to_le_bytes());
+            }
+            descriptors.extend_from_slice(&self.groups[group].raw);
+        }
+        self.volume.write_at(&descriptors, self.gdt_offset())?;
+
+        if self.csum_seed.is_some() {
+            let checksum = crc32c(!0, &self.superblock[..SUPERBLOCK_CHECKSUM]);
+            self.superblock[SUPERBLOCK_CHECKSUM..].copy_from_slice(&checksum.to_le_bytes());
+        }
+        self.volume.write_at(&self.superblock, SUPERBLOCK_OFFSET)?;
+
+        self.volume.sync()
+    }
+
+    fn group_checksum(&self, group: usize) -> Option<u16> {
+        let raw = &self.groups[group].raw;
+        let group = (group as u32).to_le_bytes();
+        if let Some(seed) = self.csum_seed {
+            let mut checksum = crc32c(seed, &group);
+            checksum = crc32c(checksum, &raw[..BG_CHECKSUM]);
+            checksum = crc32c(checksum, &[0; 2]);
+            checksum = crc32c(checksum, &raw[BG_CHECKSUM + 2..]);
+            Some(checksum as u16)
+        } else if self.has_ro_compat(RO_COMPAT_GDT_CSUM) {
+            let mut checksum = crc16(!0, &self.superblock[0x68..0x78]);
+            checksum = crc16(checksum, &group);
+            checksum = crc16(checksum, &raw[..BG_CHECKSUM]);
+            checksum = crc16(checksum, &raw[BG_CHECKSUM + 2..]);
+            Some(checksum)
+        } else {
+            None
+        }
+    }
+
+    fn read_block(&self, block_nr: u64) -> Result<Vec<u8>> {
+        let mut block = vec![0; self.block_size as usize];
+        self.volume
+            .read_at(&mut block, block_nr * self.block_size)?;
+        Ok(block)
+    }
+
+    fn write_block(&self, block_nr: u64, block: &[u8]) -> Result<()> {
+        self.volume.write_at(block, block_nr * self.block_size)
+    }
+
+    fn feature_compat(&self) -> u32 {
+        read_u32(&self.superblock, 0x5c)
+    }
+
+    fn has_ro_compat(&self, feature: u32) -> bool {
+        read_u32(&self.superblock, 0x64) & feature != 0
+    }
+
+    fn is_64bit(&self) -> bool {
+        read_u32(&self.superblock, 0x60) & INCOMPAT_64BIT != 0
+    }
+
+    fn superblock_flags(&self) -> u32 {
+        read_u32(&self.superblock, 0x160)
+    }
+
+    fn hash_seed(&self) -> [u32; 4] {
+        [0, 1, 2, 3].map(|i| read_u32(&self.superblock, 0xec + 4 * i))
+    }
+
+    fn blocks_count(&self) -> u64 {
+        self.read_u64_split(0x4, 0x150)
+    }
+
+    fn free_blocks_count(&self) -> u64 {
+        self.read_u64_split(0xc, 0x158)
+    }
+
+    fn set_free_blocks_count(&mut self, count: u64) {
+        self.superblock[0xc..0x10].copy_from_slice(&(count as u32).to_le_bytes());
+        if self.is_64bit() {
+            self.superblock[0x158..0x15c].copy_from_slice(&((count >> 32) as u32).to_le_bytes());
+        }
+    }
+
+    fn read_u64_split(&self, lo: usize, hi: usize) -> u64 {
+        let hi = if self.is_64bit() {
+            u64::from(read_u32(&self.superblock, hi))
+        } else {
+            0
+        };
+        (hi << 32) | u64::from(read_u32(&self.superblock, lo))
+    }
+
+    fn first_data_block(&self) -> u64 {
+        u64::from(read_u32(&self.superblock, 0x14))
+    }
+
+    fn blocks_per_group(&self) -> u64 {
+        u64::from(read_u32(&self.superblock, 0x20))
+    }
+
+    fn inodes_per_group(&self) -> u32 {
+        read_u32(&self.superblock, 0x28)
+    }
+
+    fn inode_size(&self) -> usize {
+        read_u16(&self.superblock, 0x58) as usize
+    }
+
+    fn desc_size(&self) -> usize {
+        if self.is_64bit() {
+            read_u16(&self.superblock, 0xfe) as usize
+        } else {
+            32
+        }
+    }
+
+    fn group_count(&self) -> usize {
+        (self.blocks_count() - self.first_data_block()).div_ceil(self.blocks_per_group()) as usize
+    }
+
+    fn gdt_offset(&self) -> u64 {
+        (self.first_data_block() + 1) * self.block_size
+    }
+
+    fn gdt_blocks(&self) -> u64 {
+        ((self.group_count() * self.desc_size()) as u64).div_ceil(self.block_size)
+    }
+
+    fn group_first_block(&self, group: usize) -> u64 {
+        self.first_data_block() + group as u64 * self.blocks_per_group()
+    }
+
+    fn blocks_in_group(&self, group: usize) -> u64 {
+        (self.blocks_count() - self.group_first_block(group)).min(self.blocks_per_group())
+    }
+
+    /// Whether a group holds a copy of the superblock and the group descriptors.
+    fn has_superblock(&self, group: usize) -> bool {
+        if group == 0 {
+            return true;
+        }
+        if self.feature_compat() & COMPAT_SPARSE_SUPER2 != 0 {
+            let backups = [0x24c, 0x250].map(|offset| read_u32(&self.superblock, offset));
+            return backups.contains(&(group as u32));
+        }
+        if !self.has_ro_compat(RO_COMPAT_SPARSE_SUPER) {
+            return true;
+        }
+        [3, 5, 7].iter().any(|base| {
+            let mut power = 1;
+            while power < group {
+                power *= base;
+            }
+            power == group
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Extent {
+    logical: u32,
+    start: u64,
+    len: u32,
+    uninit: bool,
+}
+
+fn write_extent_header(node: &mut [u8], entries: usize, max: usize, depth: u16) {
+    node[0..2].copy_from_slice(&EXTENT_MAGIC.to_le_bytes());
+    node[2..4].copy_from_slice(&(entries as u16).to_le_bytes());
+    node[4..6].copy_from_slice(&(max as u16).to_le_bytes());
+    node[6..8].copy_from_slice(&depth.to_le_bytes());
+}
+
+fn write_extent_node(node: &mut [u8], depth: u16, max: usize, extents: &[Extent]) {
+    write_extent_header(node, extents.len(), max, depth);
+    for (i, extent) in extents.iter().enumerate() {
+        let entry = &mut node[EXTENT_HEADER_SIZE + i * EXTENT_ENTRY_SIZE..][..EXTENT_ENTRY_SIZE];
+        let len = if extent.uninit {
+            extent.len + MAX_EXTENT_LEN
+        } else {
+            extent.len
+        };
+        entry[0..4].copy_from_slice(&extent.logical.to_le_bytes());
+        entry[4..6].copy_from_slice(&(len as u16).to_le_bytes());
+        entry[6..8].copy_from_slice(&((extent.start >> 32) as u16).to_le_bytes());
+        entry[8..12].copy_from_slice(&(extent.start as u32).to_le_bytes());
+    }
+}
+
+struct GroupDesc {
+    raw: Vec<u8>,
+}
+
+impl GroupDesc {
+    fn get32(&self, (lo, hi): (usize, usize)) -> u64 {
+        let hi = if self.raw.len() >= hi + 4 {
+            u64::from(read_u32(&self.raw, hi))
+        } else {
+            0
+        };
+        (hi << 32) | u64::from(read_u32(&self.raw, lo))
+    }
+
+    fn get16(&self, (lo, hi): (usize, usize)) -> u32 {
+        let hi = if self.raw.len() >= hi + 2 {
+            u32::from(read_u16(&self.raw, hi))
+        } else {
+            0
+        };
+        (hi << 16) | u32::from(read_u16(&self.raw, lo))
+    }
+
+    fn set16(&mut self, (lo, hi): (usize, usize), value: u32) {
+        self.raw[lo..lo + 2].copy_from_slice(&(value as u16).to_le_bytes());
+        if self.raw.len() >= hi + 2 {
+            self.raw[hi..hi + 2].copy_from_slice(&((value >> 16) as u16).to_le_bytes());
+        }
+    }
+
+    fn flags(&self) -> u16 {
+        read_u16(&self.raw, BG_FLAGS)
+    }
+
+    fn set_flags(&mut self, flags: u16) {
+        self.raw[BG_FLAGS..BG_FLAGS + 2].copy_from_slice(&flags.to_le_bytes());
+    }
+}
+
+struct Xattr {
+    index: u8,
+    name: Vec<u8>,
+    value: Vec<u8>,
+}
+
+struct Inode {
+    raw: Vec<u8>,
+}
+
+impl Inode {
+    fn u16(&self, offset: usize) -> u16 {
+        read_u16(&self.raw, offset)
+    }
+
+    fn u32(&self, offset: usize) -> u32 {
+        read_u32(&self.raw, offset)
+    }
+
+    fn set_u16(&mut self, offset: usize, value: u16) {
+        self.raw[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn set_u32(&mut self, offset: usize, value: u32) {
+        self.raw[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    fn is_dir(&self) -> bool {
+        self.u16(0x0) & S_IFMT == S_IFDIR
+    }
+
+    fn size(&self) -> u64 {
+        (u64::from(self.u32(0x6c)) << 32) | u64::from(self.u32(0x4))
+    }
+
+    fn set_size(&mut self, size: u64) {
+        self.set_u32(0x4, size as u32);
+        self.set_u32(0x6c, (size >> 32) as u32);
+    }
+
+    fn links(&self) -> u16 {
+        self.u16(0x1a)
+    }
+
+    fn flags(&self) -> u32 {
+        self.u32(0x20)
+    }
+
+    fn blocks(&self) -> u64 {
+        (u64::from(self.u16(0x74)) << 32) | u64::from(self.u32(0x1c))
+    }
+
+    fn set_blocks(&mut self, blocks: u64) {
+        self.set_u32(0x1c, blocks as u32);
+        self.set_u16(0x74, (blocks >> 32) as u16);
+    }
+
+    fn file_acl(&self) -> u64 {
+        (u64::from(self.u16(0x76)) << 32) | u64::from(self.u32(0x68))
+    }
+
+    fn i_block(&self) -> &[u8] {
+        &self.raw[0x28..0x64]
+    }
+
+    fn extra_isize(&self) -> usize {
+        if self.raw.len() > GOOD_OLD_INODE_SIZE {
+            self.u16(0x80) as usize
+        } else {
+            0
+        }
+    }
+
+    /// Whether an extra field at `offset` is covered by the extra inode size.
+    fn has_extra_field(&self, offset: usize, size: usize) -> bool {
+        offset + size <= GOOD_OLD_INODE_SIZE + self.extra_isize()
+    }
+
+    fn set_root_owner(&mut self) {
+        for offset in [0x2, 0x18, 0x78, 0x7a] {
+            self.set_u16(offset, 0);
+        }
+    }
+
+    fn clear_modification_times(&mut self) {
+        self.set_u32(0xc, 0);
+        self.set_u32(0x10, 0);
+        for offset in [0x84, 0x88] {
+            if self.has_extra_field(offset, 4) {
+                self.set_u32(offset, 0);
+            }
+        }
+    }
+
+    fn clear_times(&mut self) {
+        self.clear_modification_times();
+        self.set_u32(0x8, 0);
+        for offset in [0x8c, 0x90, 0x94] {
+            if self.has_extra_field(offset, 4) {
+                self.set_u32(offset, 0);
+            }
+        }
+    }
+
+    /// The inode checksum, and whether it has room for the upper 16 bits.
+    fn checksum(&self, seed: u32) -> (u32, bool) {
+        let mut raw = self.raw.clone();
+        raw[0x7c..0x7e].fill(0);
+        let has_hi = self.has_extra_field(0x82, 2);
+        if has_hi {
+            raw[0x82..0x84].fill(0);
+        }
+        (crc32c(seed, &raw), has_hi)
+    }
+
+    /// The extended attributes stored in the inode.
+    fn xattrs(&self) -> Result<Vec<Xattr>> {
+        let start = GOOD_OLD_INODE_SIZE + self.extra_isize();
+        if self.raw.len() < start + 4 || self.u32(start) != XATTR_MAGIC {
+            return Ok(Vec::new());
+        }
+
+        let base = start + 4;
+        let mut offset = base;
+        let mut xattrs = Vec::new();
+        while offset + 4 <= self.raw.len() && self.u32(offset) != 0 {
+            ensure!(
+                offset + XATTR_ENTRY_SIZE <= self.raw.len(),
+                "corrupted extended attribute"
+            );
+            let name_len = self.raw[offset] as usize;
+            let value_offset = base + self.u16(offset + 2) as usize;
+            let value_size = self.u32(offset + 8) as usize;
+            ensure!(
+                self.u32(offset + 4) == 0,
+                "extended attributes in separate inodes are not supported"
+            );
+            ensure!(
+                offset + XATTR_ENTRY_SIZE + name_len <= self.raw.len()
+                    && value_offset + value_size <= self.raw.len(),
+                "corrupted extended attribute"
+            );
+            xattrs.push(Xattr {
+                index: self.raw[offset + 1],
+                name: self.raw[offset + XATTR_ENTRY_SIZE..][..name_len].to_vec(),
+                value: self.raw[value_offset..value_offset + value_size].to_vec(),
+            });
+            offset += pad4(XATTR_ENTRY_SIZE + name_len);
+        }
+        Ok(xattrs)
+    }
+
+    /// Replace the extended attributes stored in the inode, placing the entries after the
+    /// extra fields and the values at the end.
+    fn set_xattrs(&mut self, xattrs: &[Xattr]) -> Result<()> {
+        let start = GOOD_OLD_INODE_SIZE + self.extra_isize();
+        self.raw[start..].fill(0);
+        if xattrs.is_empty() {
+            return Ok(());
+        }
+
+        let base = start + 4;
+        self.set_u32(start, XATTR_MAGIC);
+        let mut entry_offset = base;
+        let mut value_offset = self.raw.len();
+        for xattr in xattrs {
+            let entry_size = pad4(XATTR_ENTRY_SIZE + xattr.name.len());
+            value_offset = value_offset
+                .checked_sub(pad4(xattr.value.len()))
+                .context("extended attributes don't fit into the inode")?;
+            // Leave room for the terminating entry.
+            ensure!(
+                entry_offset + entry_size + 4 <= value_offset,
+                "extended attributes don't fit into the inode"
+            );
+
+            self.raw[value_offset..value_offset + xattr.value.len()].copy_from_slice(&xattr.value);
+            self.raw[entry_offset] = xattr.name.len() as u8;
+            self.raw[entry_offset + 1] = xattr.index;
+            self.set_u16(entry_offset + 2, (value_offset - base) as u16);
+            self.set_u32(entry_offset + 8, xattr.value.len() as u32);
+            self.set_u32(entry_offset + 12, xattr_hash(&xattr.name, &xattr.value));
+            self.raw[entry_offset + XATTR_ENTRY_SIZE..][..xattr.name.len()]
+                .copy_from_slice(&xattr.name);
+            entry_offset += entry_size;
+        }
+        Ok(())
+    }
+}
+
+fn xattr_hash(name: &[u8], value: &[u8]) -> u32 {
+    let mut hash = name
+        .iter()
+        .fold(0u32, |hash, c| (hash << 5) ^ (hash >> 27) ^ u32::from(*c));
+    for word in value.chunks(4) {
+        let mut bytes = [0; 4];
+        bytes[..word.len()].copy_from_slice(word);
+        hash = (hash << 16) ^ (hash >> 16) ^ u32::from_le_bytes(bytes);
+    }
+    hash
+}
+
+fn pad4(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn bit(bitmap: &[u8], index: u64) -> bool {
+    bitmap[(index / 8) as usize] & (1 << (index % 8)) != 0
+}
+
+fn set_bit(bitmap: &mut [u8], index: u64) {
+    bitmap[(index / 8) as usize] |= 1 << (index % 8);
+}
+
+fn clear_bit(bitmap: &mut [u8], index: u64) {
+    bitmap[(index / 8) as usize] &= !(1 << (index % 8));
+}
+
+fn path_components(path: &Path) -> Result<Vec<Vec<u8>>> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::RootDir | Component::CurDir => None,
+            Component::Normal(name) if name.len() <= 255 => Some(Ok(name.as_bytes().to_vec())),
+            _ => Some(Err(anyhow::anyhow!("unsupported path: {}", path.display()))),
+        })
+        .collect()
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::process::Command;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    /// Create an image with `mkfs.ext4` and open it.
+    fn format(size: u64, options: &[&str]) -> (NamedTempFile, ExtFilesystem) {
+        let image = NamedTempFile::new().unwrap();
+        image.as_file().set_len(size).unwrap();
+        let status = Command::new("/usr/sbin/mkfs.ext4")
+            .args([
+                "-q",
+                "-F",
+                "-E",
+                "root_owner=0:0,hash_seed=01234567-89ab-cdef-0123-456789abcdef",
+            ])
+            .args(options)
+            .arg(image.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let filesystem = reopen(&image);
+        (image, filesystem)
+    }
+
+    fn reopen(image: &NamedTempFile) -> ExtFilesystem {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .open(image.path())
+            .unwrap();
+        ExtFilesystem::open(Volume::whole(file).unwrap()).unwrap()
+    }
+
+    fn check(image: &NamedTempFile) {
+        let output = Command::new("/usr/sbin/e2fsck")
+            .arg("-fn")
+            .arg(image.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+
+    fn debugfs(image: &NamedTempFile, request: &str) -> String {
+        let output = Command::new("/usr/sbin/debugfs")
+            .args(["-R", request])
+            .arg(image.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    fn contents(seed: u8, len: usize) -> Vec<u8> {
+        (0..len)
+            .map(|i| (i as u8).wrapping_mul(31) ^ seed)
+            .collect()
+    }
+
+    #[test]
+    fn write_read_test() {
+        let variants: [&[&str]; 4] = [
+            &[],
+            &["-b", "4096"],
+            &["-O", "^metadata_csum,uninit_bg"],
+            &["-O", "^64bit,^metadata_csum,^uninit_bg", "-I", "128"],
+        ];
+        for options in variants {
+            let (image, mut filesystem) = format(8 * 1024 * 1024, options);
+            let small = b"hello world\n".to_vec();
+            let large = contents(1, 300_000);
+            filesystem
+                .write_file(Path::new("/small"), &small, 0o644)
+                .unwrap();
+            filesystem
+                .write_file(Path::new("/a/b/c/large"), &large, 0o755)
+                .unwrap();
+            filesystem
+                .write_file(Path::new("/empty"), &[], 0o600)
+                .unwrap();
+            check(&image);
+
+            let filesystem = reopen(&image);
+            assert_eq!(filesystem.read_file(Path::new("/small")).unwrap(), small);
+            assert_eq!(
+                filesystem.read_file(Path::new("a/b/c/large")).unwrap(),
+                large
+            );
+            assert!(filesystem
+                .read_file(Path::new("/empty"))
+                .unwrap()
+                .is_empty());
+            assert!(filesystem.read_file(Path::new("/missing")).is_err());
+            assert!(filesystem.read_file(Path::new("/a/b")).is_err());
+
+            // The files can be read by other tools, too.
+            let dump = NamedTempFile::new().unwrap();
+            debugfs(
+                &image,
+                &format!("dump /a/b/c/large {}", dump.path().display()),
+            );
+            assert_eq!(std::fs::read(dump.path()).unwrap(), large);
+            assert!(debugfs(&image, "stat /a/b/c/large").contains("Mode:  0755"));
+        }
+    }
+
+    #[test]
+    fn replace_test() {
+        let (image, mut filesystem) = format(4 * 1024 * 1024, &[]);
+        let free_blocks = filesystem.free_blocks_count();
+
+        for i in 0..5 {
+            let data = contents(i, 100_000 + usize::from(i) * 1000);
+            filesystem
+                .write_file(Path::new("/file"), &data, 0o644)
+                .unwrap();
+            assert_eq!(filesystem.read_file(Path::new("/file")).unwrap(), data);
+        }
+        filesystem
+            .write_file(Path::new("/file"), &[], 0o644)
+            .unwrap();
+        assert_eq!(filesystem.free_blocks_count(), free_blocks);
+        check(&image);
+
+        // Directories can't be replaced.
+        filesystem
+            .write_file(Path::new("/dir/file"), &[], 0o644)
+            .unwrap();
+        assert!(filesystem
+            .write_file(Path::new("/dir"), &[], 0o644)
+            .is_err());
+        check(&image);
+    }
+
+    #[test]
+    fn fragmented_test() {
+        let (image, mut filesystem) = format(4 * 1024 * 1024, &[]);
+
+        // Leave every other block free, so a large file needs more extents than fit into the
+        // inode.
+        for i in 0..40 {
+            filesystem
+                .write_file(Path::new(&format!("/{i}")), &[1; 1024], 0o644)
+                .unwrap();
+        }
+        for i in (0..40).step_by(2) {
+            filesystem
+                .write_file(Path::new(&format!("/{i}")), &[], 0o644)
+                .unwrap();
+        }
+        let data = contents(7, 200 * 1024);
+        filesystem
+            .write_file(Path::new("/large"), &data, 0o644)
+            .unwrap();
+        let stat = debugfs(&image, "stat /large");
+        assert!(stat.contains("(ETB0)"), "{stat}");
+        check(&image);
+
+        assert_eq!(reopen(&image).read_file(Path::new("/large")).unwrap(), data);
+        filesystem
+            .write_file(Path::new("/large"), &[], 0o644)
+            .unwrap();
+        check(&image);
+    }
+
+    #[test]
+    fn indexed_directory_test() {
+        for options in [&["-b", "1024"][..], &["-b", "1024", "-O", "^metadata_csum"]] {
+            let (image, mut filesystem) = format(16 * 1024 * 1024, options);
+            for i in 0..1000 {
+                let name = format!("/dir/a_file_with_a_long_name_to_fill_blocks_{i}");
+                filesystem.write_file(Path::new(&name), &[], 0o644).unwrap();
+                if i == 100 {
+                    // Let e2fsck index the directory, so the remaining files go through the index.
+                    let output = Command::new("/usr/sbin/e2fsck")
+                        .arg("-fyD")
+                        .arg(image.path())
+                        .output()
+                        .unwrap();
+                    // Exit code 1 means errors were corrected, which includes optimizing
+                    // directories.
+                    assert!(output.status.code().unwrap() <= 1);
+                    filesystem = reopen(&image);
+                    assert!(debugfs(&image, "htree /dir").contains("Root node dump"));
+                }
+            }
+            check(&image);
+
+            let filesystem = reopen(&image);
+            for i in 0..1000 {
+                let name = format!("/dir/a_file_with_a_long_name_to_fill_blocks_{i}");
+                assert!(filesystem.read_file(Path::new(&name)).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn set_metadata_test() {
+        let (image, mut filesystem) = format(4 * 1024 * 1024, &[]);
+        filesystem
+            .write_file(Path::new("/file"), b"data", 0o644)
+            .unwrap();
+        filesystem
+            .set_metadata(
+                Path::new("/file"),
+                0o100_600,
+                Some("system_u:object_r:etc_t:s0"),
+            )
+            .unwrap();
+        // Setting the context again replaces it.
+        filesystem
+            .set_metadata(
+                Path::new("/file"),
+                0o100_640,
+                Some("system_u:object_r:bin_t:s0"),
+            )
+            .unwrap();
+        check(&image);
+
+        let stat = debugfs(&image, "stat /file");
+        assert!(stat.contains("Mode:  0640"), "{stat}");
+        assert!(stat.contains("User:     0   Group:     0"), "{stat}");
+        assert!(stat.contains("Size of extra inode fields: 28"), "{stat}");
+        assert!(debugfs(&image, "ea_list /file")
+            .contains("security.selinux (27) = \"system_u:object_r:bin_t:s0\\000\""));
+    }
+}
@@ -0,0 +1,206 @@
+//! The hash functions used to index ext4 directories (htree).
+
+use anyhow::{bail, Result};
+
+pub(crate) const DX_HASH_LEGACY: u8 = 0;
+pub(crate) const DX_HASH_HALF_MD4: u8 = 1;
+pub(crate) const DX_HASH_TEA: u8 = 2;
+pub(crate) const DX_HASH_LEGACY_UNSIGNED: u8 = 3;
+pub(crate) const DX_HASH_HALF_MD4_UNSIGNED: u8 = 4;
+pub(crate) const DX_HASH_TEA_UNSIGNED: u8 = 5;
+
+/// The major hash of a name, which orders the entries of an indexed directory.
+pub(crate) fn dx_hash(name: &[u8], version: u8, seed: [u32; 4]) -> Result<u32> {
+    let mut buf = if seed.iter().any(|word| *word != 0) {
+        seed
+    } else {
+        [0x6745_2301, 0xefcd_ab89, 0x98ba_dcfe, 0x1032_5476]
+    };
+    let signed = version < DX_HASH_LEGACY_UNSIGNED;
+
+    let hash = match version {
+        DX_HASH_LEGACY | DX_HASH_LEGACY_UNSIGNED => legacy_hash(name, signed),
+        DX_HASH_HALF_MD4 | DX_HASH_HALF_MD4_UNSIGNED => {
+            for chunk in chunks(name, 32) {
+                half_md4_transform(&mut buf, &str_to_hash_buf::<8>(chunk, signed));
+            }
+            buf[1]
+        }
+        DX_HASH_TEA | DX_HASH_TEA_UNSIGNED => {
+            for chunk in chunks(name, 16) {
+                tea_transform(&mut buf, &str_to_hash_buf::<4>(chunk, signed));
+            }
+            buf[0]
+        }
+        _ => bail!("unsupported directory hash version {version}"),
+    };
+
+    // The lowest bit is reserved to mark hash collisions across blocks, and the largest value
+    // marks the end of a directory.
+    let hash = hash & !1;
+    Ok(if hash == 0x7fff_ffff << 1 {
+        0x7fff_fffe << 1
+    } else {
+        hash
+    })
+}
+
+/// The remaining name from each chunk onwards, as the padding depends on the remaining length.
+fn chunks(name: &[u8], size: usize) -> impl Iterator<Item = &[u8]> {
+    (0..name.len()).step_by(size).map(|i| &name[i..])
+}
+
+fn char_value(c: u8, signed: bool) -> u32 {
+    if signed {
+        c as i8 as i32 as u32
+    } else {
+        u32::from(c)
+    }
+}
+
+fn legacy_hash(name: &[u8], signed: bool) -> u32 {
+    let (mut hash0, mut hash1): (u32, u32) = (0x12a3_fe2d, 0x37ab_e8f9);
+    for c in name {
+        let mut hash = hash1.wrapping_add(hash0 ^ char_value(*c, signed).wrapping_mul(7_152_373));
+        if hash & 0x8000_0000 != 0 {
+            hash = hash.wrapping_sub(0x7fff_ffff);
+        }
+        hash1 = hash0;
+        hash0 = hash;
+    }
+    hash0 << 1
+}
+
+fn str_to_hash_buf<const N: usize>(msg: &[u8], signed: bool) -> [u32; N] {
+    let len = msg.len() as u32;
+    let mut pad = len | (len << 8);
+    pad |= pad << 16;
+
+    let mut buf = [pad; N];
+    let mut val = pad;
+    let mut words = 0;
+    for (i, c) in msg.iter().take(N * 4).enumerate() {
+        val = char_value(*c, signed).wrapping_add(val << 8);
+        if i % 4 == 3 {
+            buf[words] = val;
+            val = pad;
+            words += 1;
+        }
+    }
+    if words < N {
+        buf[words] = val;
+    }
+    buf
+}
+
+fn half_md4_transform(buf: &mut [u32; 4], input: &[u32; 8]) {
+    const K2: u32 = 0x5a82_7999;
+    const K3: u32 = 0x6ed9_eba1;
+
+    let f = |x: u32, y: u32, z: u32| z ^ (x & (y ^ z));
+    let g = |x: u32, y: u32, z: u32| (x & y).wrapping_add((x ^ y) & z);
+    let h = |x: u32, y: u32, z: u32| x ^ y ^ z;
+
+    let [mut a, mut b, mut c, mut d] = *buf;
+    macro_rules! round {
+        ($f:expr, $a:ident, $b:ident, $c:ident, $d:ident, $x:expr, $s:expr) => {
+            $a = $a
+                .wrapping_add($f($b, $c, $d))
+                .wrapping_add($x)
+                .rotate_left($s);
+        };
+    }
+
+    round!(f, a, b, c, d, input[0], 3);
+    round!(f, d, a, b, c, input[1], 7);
+    round!(f, c, d, a, b, input[2], 11);
+    round!(f, b, c, d, a, input[3], 19);
+    round!(f, a, b, c, d, input[4], 3);
+    round!(f, d, a, b, c, input[5], 7);
+    round!(f, c, d, a, b, input[6], 11);
+    round!(f, b, c, d, a, input[7], 19);
+
+    round!(g, a, b, c, d, input[1].wrapping_add(K2), 3);
+    round!(g, d, a, b, c, input[3].wrapping_add(K2), 5);
+    round!(g, c, d, a, b, input[5].wrapping_add(K2), 9);
+    round!(g, b, c, d, a, input[7].wrapping_add(K2), 13);
+    round!(g, a, b, c, d, input[0].wrapping_add(K2), 3);
+    round!(g, d, a, b, c, input[2].wrapping_add(K2), 5);
+    round!(g, c, d, a, b, input[4].wrapping_add(K2), 9);
+    round!(g, b, c, d, a, input[6].wrapping_add(K2), 13);
+
+    round!(h, a, b, c, d, input[3].wrapping_add(K3), 3);
+    round!(h, d, a, b, c, input[7].wrapping_add(K3), 9);
+    round!(h, c, d, a, b, input[2].wrapping_add(K3), 11);
+    round!(h, b, c, d, a, input[6].wrapping_add(K3), 15);
+    round!(h, a, b, c, d, input[1].wrapping_add(K3), 3);
+    round!(h, d, a, b, c, input[5].wrapping_add(K3), 9);
+    round!(h, c, d, a, b, input[0].wrapping_add(K3), 11);
+    round!(h, b, c, d, a, input[4].wrapping_add(K3), 15);
+
+    buf[0] = buf[0].wrapping_add(a);
+    buf[1] = buf[1].wrapping_add(b);
+    buf[2] = buf[2].wrapping_add(c);
+    buf[3] = buf[3].wrapping_add(d);
+}
+
+fn tea_transform(buf: &mut [u32; 4], input: &[u32; 4]) {
+    const DELTA: u32 = 0x9e37_79b9;
+
+    let (mut b0, mut b1) = (buf[0], buf[1]);
+    let [a, b, c, d] = *input;
+    let mut sum: u32 = 0;
+    for _ in 0..16 {
+        sum = sum.wrapping_add(DELTA);
+        b0 = b0.wrapping_add(
+            (b1 << 4).wrapping_add(a) ^ b1.wrapping_add(sum) ^ (b1 >> 5).wrapping_add(b),
+        );
+        b1 = b1.wrapping_add(
+            (b0 << 4).wrapping_add(c) ^ b0.wrapping_add(sum) ^ (b0 >> 5).wrapping_add(d),
+        );
+    }
+    buf[0] = buf[0].wrapping_add(b0);
+    buf[1] = buf[1].wrapping_add(b1);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The seed 01234567-89ab-cdef-0123-456789abcdef, as stored in the superblock.
+    const SEED: [u32; 4] = [0x6745_2301, 0xefcd_ab89, 0x6745_2301, 0xefcd_ab89];
+
+    #[test]
+    fn dx_hash_test() {
+        // Reference values from `debugfs -R "dx_hash -h <version> -s <seed> <name>"`.
+        let long_name: &[u8] = b"hello_world_this_is_a_long_file_name_for_hashing";
+        let cases: [(u8, &[u8], u32); 12] = [
+            (DX_HASH_LEGACY, b"a", 0xe74b_53e2),
+            (DX_HASH_LEGACY, long_name, 0xa689_4302),
+            (DX_HASH_LEGACY, b"check-setupos-age.sh", 0x6444_6880),
+            (DX_HASH_LEGACY, "caf\u{e9}".as_bytes(), 0x96ca_5a2c),
+            (DX_HASH_HALF_MD4, b"a", 0x35dc_0cc4),
+            (DX_HASH_HALF_MD4, long_name, 0xe0d1_e750),
+            (DX_HASH_HALF_MD4, b"check-setupos-age.sh", 0x3059_a1d4),
+            (DX_HASH_HALF_MD4, "caf\u{e9}".as_bytes(), 0xd6b4_ad14),
+            (DX_HASH_TEA, b"a", 0x6d0e_a4c0),
+            (DX_HASH_TEA, long_name, 0x9c99_2ab8),
+            (DX_HASH_TEA, b"check-setupos-age.sh", 0xd849_0e2e),
+            (DX_HASH_TEA, "caf\u{e9}".as_bytes(), 0x1058_42ea),
+        ];
+        for (version, name, hash) in cases {
+            assert_eq!(dx_hash(name, version, SEED).unwrap(), hash);
+            // Signedness only matters for non-ASCII names.
+            if name.is_ascii() {
+                assert_eq!(dx_hash(name, version + 3, SEED).unwrap(), hash);
+            }
+        }
+
+        // Without a seed, the default one is used.
+        assert_eq!(
+            dx_hash(b"hello", DX_HASH_HALF_MD4, [0; 4]).unwrap(),
+            0x1746_da32
+        );
+        assert!(dx_hash(b"hello", 6, SEED).is_err());
+    }
+}
@@ -1,105 +1,48 @@
 use std::path::{Path, PathBuf};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::Result;
 use async_trait::async_trait;
-use tokio::process::Command;
+use tokio::fs;
 
 use crate::partition;
 use crate::Partition;
 
+mod filesystem;
+
+use filesystem::FatFilesystem;
+
 pub struct FatPartition {
-    index: Option<usize>,
-    original: PathBuf,
+    filesystem: FatFilesystem,
 }
 
 #[async_trait]
 impl Partition for FatPartition {
-    /// Open a fat3 partition for writing, via mtools. There is nothing to do
-    /// here, as mtools works in place.
+    /// Open a fat partition for writing. Changes are written in place.
     async fn open(image: PathBuf, index: Option<usize>) -> Result<Self> {
+        let volume = partition::open_volume(&image, index)?;
+
         Ok(FatPartition {
-            index,
-            original: image,
+            filesystem: FatFilesystem::open(volume)?,
         })
     }
 
-    /// Close a fat32 partition. There is nothing to do here, as mtools works
-    /// in place.
+    /// Close a fat partition. There is nothing to do here, as every change is
+    /// written in place.
     async fn close(self) -> Result<()> {
         Ok(())
     }
 
     /// Copy a file into place
     async fn write_file(&mut self, input: &Path, output: &Path) -> Result<()> {
-        let out = if let Some(index) = self.index {
-            let offset = partition::check_offset(&self.original, index).await?;
-
-            Command::new("mcopy")
-                .args([
-                    "-o",
-                    "-i",
-                    &format!("{}@@{}", self.original.display(), offset),
-                    &input.display().to_string(),
-                    &format!("::{}", output.display()),
-                ])
-                .output()
-                .await
-                .context("failed to run mcopy")?
-        } else {
-            Command::new("mcopy")
-                .args([
-                    "-o",
-                    "-i",
-                    &format!("{}", self.original.display()),
-                    &input.display().to_string(),
-                    &format!("::{}", output.display()),
-                ])
-                .output()
-                .await
-                .context("failed to run mcopy")?
-        };
+        let contents = fs::read(input).await?;
 
-        if !out.status.success() {
-            return Err(anyhow!("mcopy failed: {}", String::from_utf8(out.stderr)?));
-        }
-
-        Ok(())
+        self.filesystem.write_file(output, &contents)
     }
 
     /// Read a file from a given partition
     async fn read_file(&mut self, input: &Path) -> Result<String> {
-        let out = if let Some(index) = self.index {
-            let offset = partition::check_offset(&self.original, index).await?;
-
-            Command::new("mcopy")
-                .args([
-                    "-o",
-                    "-i",
-                    &format!("{}@@{}", self.original.display(), offset),
-                    &format!("::{}", input.display()),
-                    "-",
-                ])
-                .output()
-                .await
-                .context("failed to run mcopy")?
-        } else {
-            Command::new("mcopy")
-                .args([
-                    "-o",
-                    "-i",
-                    &format!("{}", self.original.display()),
-                    &format!("::{}", input.display()),
-                    "-",
-                ])
-                .output()
-                .await
-                .context("failed to run mcopy")?
-        };
-
-        if !out.status.success() {
-            return Err(anyhow!("mcopy failed: {}", String::from_utf8(out.stderr)?));
-        }
+        let contents = self.filesystem.read_file(input)?;
 
-        Ok(String::from_utf8(out.stdout)?)
+        Ok(String::from_utf8(contents)?)
     }
 }
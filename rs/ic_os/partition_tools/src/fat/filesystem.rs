@@ -0,0 +1,1127 @@
+//! A small FAT12/16/32 implementation, which supports reading files and inserting files into
+//! an existing filesystem.
+//!
+//! All timestamps are set to the FAT epoch, so that the same inputs always produce the same
+//! image.
+
+use std::path::{Component, Path};
+
+use anyhow::{bail, ensure, Context, Result};
+
+use crate::volume::Volume;
+
+const DIR_ENTRY_SIZE: u64 = 32;
+
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_ARCHIVE: u8 = 0x20;
+const ATTR_LONG_NAME: u8 = 0x0f;
+
+const ENTRY_END: u8 = 0x00;
+const ENTRY_DELETED: u8 = 0xe5;
+const LFN_LAST: u8 = 0x40;
+const LFN_CHARS: usize = 13;
+
+// Case flags that let short names be displayed in lower case without a long name.
+const NT_LOWER_BASE: u8 = 0x08;
+const NT_LOWER_EXT: u8 = 0x10;
+
+/// 1980-01-01, the earliest date FAT can represent.
+const FIXED_DATE: u16 = (1 << 5) | 1;
+
+const FS_INFO_FREE_COUNT: u64 = 488;
+const FS_INFO_NEXT_FREE: u64 = 492;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FatType {
+    Fat12,
+    Fat16,
+    Fat32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Directory {
+    /// The fixed size root directory of FAT12 and FAT16.
+    FixedRoot { offset: u64, entries: u64 },
+    /// A directory stored in a cluster chain.
+    Clusters(u32),
+}
+
+#[derive(Debug)]
+struct Entry {
+    long_name: Option<String>,
+    short_name: [u8; 11],
+    nt_flags: u8,
+    attr: u8,
+    first_cluster: u32,
+    size: u32,
+    /// The offset of the short entry, which follows the long name entries.
+    offset: u64,
+}
+
+impl Entry {
+    fn is_dir(&self) -> bool {
+        self.attr & ATTR_DIRECTORY != 0
+    }
+
+    fn display_short_name(&self) -> String {
+        let mut base = String::from_utf8_lossy(&self.short_name[..8])
+            .trim_end()
+            .to_string();
+        let mut ext = String::from_utf8_lossy(&self.short_name[8..])
+            .trim_end()
+            .to_string();
+        if self.short_name[0] == 0x05 {
+            base.replace_range(..1, "\u{e5}");
+        }
+        if self.nt_flags & NT_LOWER_BASE != 0 {
+            base = base.to_ascii_lowercase();
+        }
+        if self.nt_flags & NT_LOWER_EXT != 0 {
+            ext = ext.to_ascii_lowercase();
+        }
+        if ext.is_empty() {
+            base
+        } else {
+            format!("{base}.{ext}")
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.long_name
+            .as_ref()
+            .is_some_and(|long_name| long_name.eq_ignore_ascii_case(name))
+            || self.display_short_name().eq_ignore_ascii_case(name)
+    }
+}
+
+pub(crate) struct FatFilesystem {
+    volume: Volume,
+    fat_type: FatType,
+    cluster_size: u64,
+    cluster_count: u32,
+    data_offset: u64,
+    root: Directory,
+    /// The active FAT, which is written to every offset in `fat_offsets`.
+    fat: Vec<u8>,
+    fat_offsets: Vec<u64>,
+    fs_info_offset: Option<u64>,
+}
+
+impl FatFilesystem {
+    pub(crate) fn open(volume: Volume) -> Result<Self> {
+        let mut boot = [0; 512];
+        volume
+            .read_at(&mut boot, 0)
+            .context("failed to read boot sector")?;
+        ensure!(
+            boot[510..512] == [0x55, 0xaa],
+            "volume has no FAT boot sector"
+        );
+
+        let bytes_per_sector = u64::from(read_u16(&boot, 11));
+        let sectors_per_cluster = u64::from(boot[13]);
+        let reserved_sectors = u64::from(read_u16(&boot, 14));
+        let num_fats = u64::from(boot[16]);
+        let root_entries = u64::from(read_u16(&boot, 17));
+        ensure!(
+            [512, 1024, 2048, 4096].contains(&bytes_per_sector)
+                && sectors_per_cluster.is_power_of_two()
+                && reserved_sectors > 0
+                && num_fats > 0,
+            "invalid FAT boot sector"
+        );
+
+        let total_sectors = match read_u16(&boot, 19) {
+            0 => u64::from(read_u32(&boot, 32)),
+            sectors => u64::from(sectors),
+        };
+        let fat_sectors = match read_u16(&boot, 22) {
+            0 => u64::from(read_u32(&boot, 36)),
+            sectors => u64::from(sectors),
+        };
+        let root_sectors = (root_entries * DIR_ENTRY_SIZE).div_ceil(bytes_per_sector);
+        let data_sector = reserved_sectors + num_fats * fat_sectors + root_sectors;
+        ensure!(
+            total_sectors > data_sector && total_sectors * bytes_per_sector <= volume.length(),
+            "invalid FAT geometry"
+        );
+        let cluster_count = ((total_sectors - data_sector) / sectors_per_cluster) as u32;
+
+        // The FAT type is determined by the cluster count alone.
+        let fat_type = if cluster_count < 4085 {
+            FatType::Fat12
+        } else if cluster_count < 65525 {
+            FatType::Fat16
+        } else {
+            FatType::Fat32
+        };
+
+        let fat_length = fat_sectors * bytes_per_sector;
+        let fat_offset = |index: u64| (reserved_sectors + index * fat_sectors) * bytes_per_sector;
+        let (root, fat_offsets, fs_info_offset) = if fat_type == FatType::Fat32 {
+            let ext_flags = read_u16(&boot, 40);
+            let fat_offsets = if ext_flags & 0x80 != 0 {
+                // Mirroring is disabled, only the active FAT is in use.
+                vec![fat_offset(u64::from(ext_flags & 0x0f))]
+            } else {
+                (0..num_fats).map(fat_offset).collect()
+            };
+            let fs_info_sector = u64::from(read_u16(&boot, 48));
+            let fs_info_offset = (fs_info_sector != 0 && fs_info_sector != 0xffff)
+                .then_some(fs_info_sector * bytes_per_sector);
+            (
+                Directory::Clusters(read_u32(&boot, 44)),
+                fat_offsets,
+                fs_info_offset,
+            )
+        } else {
+            let root = Directory::FixedRoot {
+                offset: (reserved_sectors + num_fats * fat_sectors) * bytes_per_sector,
+                entries: root_entries,
+            };
+            (root, (0..num_fats).map(fat_offset).collect(), None)
+        };
+
+        let mut fat = vec![0; fat_length as usize];
+        volume
+            .read_at(&mut fat, fat_offsets[0])
+            .context("failed to read FAT")?;
+
+        let filesystem = Self {
+            volume,
+            fat_type,
+            cluster_size: sectors_per_cluster * bytes_per_sector,
+            cluster_count,
+            data_offset: data_sector * bytes_per_sector,
+            root,
+            fat,
+            fat_offsets,
+            fs_info_offset,
+        };
+        ensure!(
+            (filesystem.max_cluster() as u64 + 1) * filesystem.fat_entry_bits() <= fat_length * 8,
+            "FAT is too small for the volume"
+        );
+
+        Ok(filesystem)
+    }
+
+    /// Read the contents of the file at `path`.
+    pub(crate) fn read_file(&self, path: &Path) -> Result<Vec<u8>> {
+        let components = path_components(path)?;
+        let (name, parents) = components.split_last().context("path has no file name")?;
+        let dir = self.find_dir(parents)?;
+        let entry = self
+            .find_entry(dir, name)?
+            .with_context(|| format!("File not found: {}", path.display()))?;
+        ensure!(!entry.is_dir(), "{} is a directory", path.display());
+
+        let mut contents = Vec::with_capacity(entry.size as usize);
+        if entry.first_cluster != 0 {
+            for cluster in self.chain(entry.first_cluster)? {
+                let mut buf = vec![0; self.cluster_size as usize];
+                self.volume
+                    .read_at(&mut buf, self.cluster_offset(cluster))?;
+                contents.extend_from_slice(&buf);
+            }
+        }
+        ensure!(
+            contents.len() >= entry.size as usize,
+            "{} is truncated",
+            path.display()
+        );
+        contents.truncate(entry.size as usize);
+
+        Ok(contents)
+    }
+
+    /// Write `contents` to the file at `path`, replacing it if it exists. Missing parent
+    /// directories are created.
+    pub(crate) fn write_file(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+        let components = path_components(path)?;
+        let (name, parents) = components.split_last().context("path has no file name")?;
+        let size = u32::try_from(contents.len()).context("file is too large for FAT")?;
+
+        let mut dir = self.root;
+        for parent in parents {
+            dir = match self.find_entry(dir, parent)? {
+                Some(entry) if entry.is_dir() => Directory::Clusters(entry.first_cluster),
+                Some(_) => bail!("{parent} is not a directory"),
+                None => self.create_dir(dir, parent)?,
+            };
+        }
+
+        let existing = self.find_entry(dir, name)?;
+        if let Some(entry) = &existing {
+            ensure!(!entry.is_dir(), "{} is a directory", path.display());
+            if entry.first_cluster != 0 {
+                self.free_chain(entry.first_cluster)?;
+            }
+        }
+
+        let clusters = self.allocate_chain(contents.len().div_ceil(self.cluster_size as usize))?;
+        for (cluster, chunk) in clusters
+            .iter()
+            .zip(contents.chunks(self.cluster_size as usize))
+        {
+            let mut buf = chunk.to_vec();
+            buf.resize(self.cluster_size as usize, 0);
+            self.volume.write_at(&buf, self.cluster_offset(*cluster))?;
+        }
+        let first_cluster = clusters.first().copied().unwrap_or(0);
+
+        match existing {
+            Some(entry) => {
+                let mut short_entry = [0; DIR_ENTRY_SIZE as usize];
+                self.volume.read_at(&mut short_entry, entry.offset)?;
+                set_entry_cluster(&mut short_entry, first_cluster);
+                short_entry[28..32].copy_from_slice(&size.to_le_bytes());
+                self.volume.write_at(&short_entry, entry.offset)?;
+            }
+            None => self.add_entry(dir, name, ATTR_ARCHIVE, first_cluster, size)?,
+        }
+
+        self.flush()
+    }
+
+    fn find_dir(&self, components: &[String]) -> Result<Directory> {
+        let mut dir = self.root;
+        for component in components {
+            dir = match self.find_entry(dir, component)? {
+                Some(entry) if entry.is_dir() => Directory::Clusters(entry.first_cluster),
+                Some(_) => bail!("{component} is not a directory"),
+                None => bail!("File not found: {component}"),
+            };
+        }
+        Ok(dir)
+    }
+
+    fn find_entry(&self, dir: Directory, name: &str) -> Result<Option<Entry>> {
+        Ok(self
+            .read_dir(dir)?
+            .into_iter()
+            .find(|entry| entry.matches(name)))
+    }
+
+    /// The offsets of all entry slots of a directory.
+    fn dir_slots(&self, dir: Directory) -> Result<Vec<u64>> {
+        match dir {
+            Directory::FixedRoot { offset, entries } => {
+                Ok((0..entries).map(|i| offset + i * DIR_ENTRY_SIZE).collect())
+            }
+            Directory::Clusters(first_cluster) => Ok(self
+                .chain(first_cluster)?
+                .into_iter()
+                .flat_map(|cluster| {
+                    let offset = self.cluster_offset(cluster);
+                    (0..self.cluster_size / DIR_ENTRY_SIZE)
+                        .map(move |i| offset + i * DIR_ENTRY_SIZE)
+                })
+                .collect()),
+        }
+    }
+
+    fn read_dir(&self, dir: Directory) -> Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        // Long name parts by their sequence number, and the checksum of the short name they
+        // belong to.
+        let mut long_name_parts: Vec<(u8, [u16; LFN_CHARS])> = Vec::new();
+        let mut long_name_checksum = None;
+
+        for offset in self.dir_slots(dir)? {
+            let mut slot = [0; DIR_ENTRY_SIZE as usize];
+            self.volume.read_at(&mut slot, offset)?;
+
+            match (slot[0], slot[11]) {
+                (ENTRY_END, _) => break,
+                (ENTRY_DELETED, _) => {
+                    long_name_parts.clear();
+                }
+                (order, ATTR_LONG_NAME) => {
+                    if order & LFN_LAST != 0 {
+                        long_name_parts.clear();
+                        long_name_checksum = Some(slot[13]);
+                    }
+                    let mut chars = [0; LFN_CHARS];
+                    for (i, position) in LFN_CHAR_OFFSETS.iter().enumerate() {
+                        chars[i] = read_u16(&slot, *position);
+                    }
+                    long_name_parts.push((order & !LFN_LAST, chars));
+                }
+                (_, attr) if attr & ATTR_VOLUME_ID != 0 => {
+                    long_name_parts.clear();
+                }
+                (_, attr) => {
+                    let short_name: [u8; 11] = slot[..11].try_into().unwrap();
+                    let long_name = (!long_name_parts.is_empty()
+                        && long_name_checksum == Some(short_name_checksum(&short_name)))
+                    .then(|| assemble_long_name(&mut long_name_parts));
+                    long_name_parts.clear();
+
+                    if short_name[0] == b'.' {
+                        // The "." and ".." entries.
+                        continue;
+                    }
+                    entries.push(Entry {
+                        long_name,
+                        short_name,
+                        nt_flags: slot[12],
+                        attr,
+                        first_cluster: (u32::from(read_u16(&slot, 20)) << 16)
+                            | u32::from(read_u16(&slot, 26)),
+                        size: read_u32(&slot, 28),
+                        offset,
+                    });
+                }
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn create_dir(&mut self, parent: Directory, name: &str) -> Result<Directory> {
+        let cluster = self.allocate_chain(1)?[0];
+        let mut buf = vec![0; self.cluster_size as usize];
+        let parent_cluster = match parent {
+            Directory::Clusters(cluster) if Directory::Clusters(cluster) != self.root => cluster,
+            // ".." refers to the root directory with cluster 0.
+            _ => 0,
+        };
+        buf[..32].copy_from_slice(&short_entry(*b".          ", 0, ATTR_DIRECTORY, cluster, 0));
+        buf[32..64].copy_from_slice(&short_entry(
+            *b"..         ",
+            0,
+            ATTR_DIRECTORY,
+            parent_cluster,
+            0,
+        ));
+        self.volume.write_at(&buf, self.cluster_offset(cluster))?;
+        self.add_entry(parent, name, ATTR_DIRECTORY, cluster, 0)?;
+
+        Ok(Directory::Clusters(cluster))
+    }
+
+    fn add_entry(
+        &mut self,
+        dir: Directory,
+        name: &str,
+        attr: u8,
+        first_cluster: u32,
+        size: u32,
+    ) -> Result<()> {
+        let existing: Vec<[u8; 11]> = self
+            .read_dir(dir)?
+            .iter()
+            .map(|entry| entry.short_name)
+            .collect();
+        let (short_name, nt_flags, needs_long_name) = short_name_for(name, &existing)?;
+
+        let mut slots = Vec::new();
+        if needs_long_name {
+            slots.extend(long_name_entries(name, &short_name)?);
+        }
+        slots.push(short_entry(short_name, nt_flags, attr, first_cluster, size));
+
+        let offsets = self.find_free_slots(dir, slots.len())?;
+        for (slot, offset) in slots.iter().zip(offsets) {
+            self.volume.write_at(slot, offset)?;
+        }
+
+        Ok(())
+    }
+
+    /// Find `count` consecutive free slots in a directory, growing it if needed.
+    fn find_free_slots(&mut self, dir: Directory, count: usize) -> Result<Vec<u64>> {
+        loop {
+            let slots = self.dir_slots(dir)?;
+            let mut run = Vec::new();
+            let mut at_end = false;
+            for offset in &slots {
+                let mut first_byte = [0];
+                if !at_end {
+                    self.volume.read_at(&mut first_byte, *offset)?;
+                    at_end = first_byte[0] == ENTRY_END;
+                }
+                if at_end || first_byte[0] == ENTRY_DELETED {
+                    run.push(*offset);
+                    if run.len() == count {
+                        return Ok(run);
+                    }
+                } else {
+                    run.clear();
+                }
+            }
+
+            let Directory::Clusters(first_cluster) = dir else {
+                bail!("root directory is full");
+            };
+            let last_cluster = *self.chain(first_cluster)?.last().unwrap();
+            let cluster = self.allocate_chain(1)?[0];
+            self.set_fat_entry(last_cluster, cluster);
+            self.volume.write_at(
+                &vec![0; self.cluster_size as usize],
+                self.cluster_offset(cluster),
+            )?;
+        }
+    }
+
+    fn cluster_offset(&self, cluster: u32) -> u64 {
+        self.data_offset + u64::from(cluster - 2) * self.cluster_size
+    }
+
+    fn max_cluster(&self) -> u32 {
+        self.cluster_count + 1
+    }
+
+    fn fat_entry_bits(&self) -> u64 {
+        match self.fat_type {
+            FatType::Fat12 => 12,
+            FatType::Fat16 => 16,
+            FatType::Fat32 => 32,
+        }
+    }
+
+    fn end_of_chain(&self) -> u32 {
+        match self.fat_type {
+            FatType::Fat12 => 0xfff,
+            FatType::Fat16 => 0xffff,
+            FatType::Fat32 => 0x0fff_ffff,
+        }
+    }
+
+    fn fat_entry(&self, cluster: u32) -> u32 {
+        let cluster = cluster as usize;
+        match self.fat_type {
+            FatType::Fat12 => {
+                let value = u32::from(read_u16(&self.fat, cluster + cluster / 2));
+                if cluster % 2 == 1 {
+                    value >> 4
+                } else {
+                    value & 0xfff
+                }
+            }
+            FatType::Fat16 => u32::from(read_u16(&self.fat, cluster * 2)),
+            FatType::Fat32 => read_u32(&self.fat, cluster * 4) & 0x0fff_ffff,
+        }
+    }
+
+    fn set_fat_entry(&mut self, cluster: u32, value: u32) {
+        let cluster = cluster as usize;
+        match self.fat_type {
+            FatType::Fat12 => {
+                let offset = cluster + cluster / 2;
+                let old = read_u16(&self.fat, offset);
+                let value = value as u16 & 0xfff;
+                let new = if cluster % 2 == 1 {
+                    (old & 0x000f) | (value << 4)
+                } else {
+                    (old & 0xf000) | value
+                };
+                self.fat[offset..offset + 2].copy_from_slice(&new.to_le_bytes());
+            }
+            FatType::Fat16 => {
+                self.fat[cluster * 2..cluster * 2 + 2]
+                    .copy_from_slice(&(value as u16).to_le_bytes());
+            }
+            FatType::Fat32 => {
+                // The upper four bits are reserved and must be preserved.
+                let old = read_u32(&self.fat, cluster * 4);
+                let new = (old & 0xf000_0000) | (value & 0x0fff_ffff);
+                self.fat[cluster * 4..cluster * 4 + 4].copy_from_slice(&new.to_le_bytes());
+            }
+        }
+    }
+
+    fn is_valid_cluster(&self, cluster: u32) -> bool {
+        (2..=self.max_cluster()).contains(&cluster)
+    }
+
+    fn chain(&self, first_cluster: u32) -> Result<Vec<u32>> {
+        let mut chain = Vec::new();
+        let mut cluster = first_cluster;
+        loop {
+            ensure!(
+                self.is_valid_cluster(cluster),
+                "invalid cluster {cluster} in chain"
+            );
+            ensure!(
+                chain.len() < self.cluster_count as usize,
+                "cluster chain contains a loop"
+            );
+            chain.push(cluster);
+            let next = self.fat_entry(cluster);
+            // Values from the bad cluster marker upwards end the chain.
+            if next >= self.end_of_chain() - 8 {
+                return Ok(chain);
+            }
+            cluster = next;
+        }
+    }
+
+    fn allocate_chain(&mut self, count: usize) -> Result<Vec<u32>> {
+        let clusters: Vec<u32> = (2..=self.max_cluster())
+            .filter(|cluster| self.fat_entry(*cluster) == 0)
+            .take(count)
+            .collect();
+        ensure!(clusters.len() == count, "not enough free space");
+
+        for pair in clusters.windows(2) {
+            self.set_fat_entry(pair[0], pair[1]);
+        }
+        if let Some(last) = clusters.last() {
+            self.set_fat_entry(*last, self.end_of_chain());
+        }
+
+        Ok(clusters)
+    }
+
+    fn free_chain(&mut self, first_cluster: u32) -> Result<()> {
+        for cluster in self.chain(first_cluster)? {
+            self.set_fat_entry(cluster, 0);
+        }
+        Ok(())
+    }
+
+    /// Write the FAT to all of its copies and update the free cluster count.
+    fn flush(&mut self) -> Result<()> {
+        for offset in &self.fat_offsets {
+            self.volume.write_at(&self.fat, *offset)?;
+        }
+
+        if let Some(fs_info_offset) = self.fs_info_offset {
+            let free = (2..=self.max_cluster())
+                .filter(|cluster| self.fat_entry(*cluster) == 0)
+                .count() as u32;
+            self.volume
+                .write_at(&free.to_le_bytes(), fs_info_offset + FS_INFO_FREE_COUNT)?;
+            // No hint for the next free cluster.
+            self.volume.write_at(
+                &0xffff_ffffu32.to_le_bytes(),
+                fs_info_offset + FS_INFO_NEXT_FREE,
+            )?;
+        }
+
+        self.volume.sync()
+    }
+}
+
+/// The offsets of the name characters in a long name entry.
+const LFN_CHAR_OFFSETS: [usize; LFN_CHARS] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+
+fn assemble_long_name(parts: &mut [(u8, [u16; LFN_CHARS])]) -> String {
+    parts.sort_by_key(|(order, _)| *order);
+    let chars: Vec<u16> = parts
+        .iter()
+        .flat_map(|(_, chars)| chars.iter().copied())
+        .take_while(|c| *c != 0x0000 && *c != 0xffff)
+        .collect();
+    String::from_utf16_lossy(&chars)
+}
+
+fn long_name_entries(name: &str, short_name: &[u8; 11]) -> Result<Vec<[u8; 32]>> {
+    let mut chars: Vec<u16> = name.encode_utf16().collect();
+    ensure!(chars.len() <= 255, "file name is too long: {name}");
+    if chars.len() % LFN_CHARS != 0 {
+        chars.push(0x0000);
+    }
+    while chars.len() % LFN_CHARS != 0 {
+        chars.push(0xffff);
+    }
+
+    let checksum = short_name_checksum(short_name);
+    let count = chars.len() / LFN_CHARS;
+    // The entry with the last part of the name comes first.
+    Ok((1..=count)
+        .rev()
+        .map(|order| {
+            let mut slot = [0; 32];
+            slot[0] = order as u8 | if order == count { LFN_LAST } else { 0 };
+            slot[11] = ATTR_LONG_NAME;
+            slot[13] = checksum;
+            for (i, position) in LFN_CHAR_OFFSETS.iter().enumerate() {
+                slot[*position..*position + 2]
+                    .copy_from_slice(&chars[(order - 1) * LFN_CHARS + i].to_le_bytes());
+            }
+            slot
+        })
+        .collect())
+}
+
+fn short_entry(short_name: [u8; 11], nt_flags: u8, attr: u8, cluster: u32, size: u32) -> [u8; 32] {
+    let mut slot = [0; 32];
+    slot[..11].copy_from_slice(&short_name);
+    slot[11] = attr;
+    slot[12] = nt_flags;
+    // Creation, access and modification dates. All times stay at midnight.
+    slot[16..18].copy_from_slice(&FIXED_DATE.to_le_bytes());
+    slot[18..20].copy_from_slice(&FIXED_DATE.to_le_bytes());
+    slot[24..26].copy_from_slice(&FIXED_DATE.to_le_bytes());
+    set_entry_cluster(&mut slot, cluster);
+    slot[28..32].copy_from_slice(&size.to_le_bytes());
+    slot
+}
+
+fn set_entry_cluster(slot: &mut [u8; 32], cluster: u32) {
+    slot[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+    slot[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+}
+
+fn short_name_checksum(short_name: &[u8; 11]) -> u8 {
+    short_name
+        .iter()
+        .fold(0u8, |sum, c| sum.rotate_right(1).wrapping_add(*c))
+}
+
+fn is_short_name_char(c: u8) -> bool {
+    c.is_ascii_uppercase() || c.is_ascii_digit() || b"!#$%&'()-@^_`{}~".contains(&c)
+}
+
+/// Pick the short name for a new entry. Returns the name, the case flags and whether a long
+/// name entry is needed to store the name as given.
+fn short_name_for(name: &str, existing: &[[u8; 11]]) -> Result<([u8; 11], u8, bool)> {
+    ensure!(
+        !name.is_empty() && !name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|']),
+        "invalid file name: {name}"
+    );
+
+    let (base, ext) = match name.rsplit_once('.') {
+        Some((base, ext)) if !base.is_empty() => (base, ext),
+        _ => (name, ""),
+    };
+
+    // Names that fit into 8.3 with a consistent case per part don't need a long name.
+    let case_flag = |part: &str, lower_flag: u8| {
+        if part.bytes().any(|c| c.is_ascii_lowercase()) {
+            (!part.bytes().any(|c| c.is_ascii_uppercase())).then_some(lower_flag)
+        } else {
+            Some(0)
+        }
+    };
+    let fits = (1..=8).contains(&base.len())
+        && ext.len() <= 3
+        && !base.contains('.')
+        && (base.bytes().chain(ext.bytes())).all(|c| is_short_name_char(c.to_ascii_uppercase()));
+    if fits {
+        if let (Some(base_flag), Some(ext_flag)) =
+            (case_flag(base, NT_LOWER_BASE), case_flag(ext, NT_LOWER_EXT))
+        {
+            let short_name = pad_short_name(base, ext);
+            if !existing.contains(&short_name) {
+                return Ok((short_name, base_flag | ext_flag, false));
+            }
+        }
+    }
+
+    let sanitize = |part: &str| -> String {
+        part.bytes()
+            .filter(|c| *c != b' ' && *c != b'.')
+            .map(|c| {
+                let c = c.to_ascii_uppercase();
+                if is_short_name_char(c) {
+                    c as char
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    };
+    let basis = sanitize(base);
+    let basis = if basis.is_empty() {
+        "_".to_string()
+    } else {
+        basis
+    };
+    let ext: String = sanitize(ext).chars().take(3).collect();
+
+    for n in 1..1_000_000u32 {
+        let tail = format!("~{n}");
+        let base: String = basis.chars().take(8 - tail.len()).collect();
+        let short_name = pad_short_name(&format!("{base}{tail}"), &ext);
+        if !existing.contains(&short_name) {
+            return Ok((short_name, 0, true));
+        }
+    }
+    bail!("no short name available for {name}")
+}
+
+fn pad_short_name(base: &str, ext: &str) -> [u8; 11] {
+    let mut short_name = [b' '; 11];
+    for (i, c) in base.bytes().take(8).enumerate() {
+        short_name[i] = c.to_ascii_uppercase();
+    }
+    for (i, c) in ext.bytes().take(3).enumerate() {
+        short_name[8 + i] = c.to_ascii_uppercase();
+    }
+    short_name
+}
+
+fn path_components(path: &Path) -> Result<Vec<String>> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::RootDir | Component::CurDir => None,
+            Component::Normal(name) => Some(
+                name.to_str()
+                    .map(str::to_string)
+                    .context("path is not valid UTF-8"),
+            ),
+            _ => Some(Err(anyhow::anyhow!("unsupported path: {}", path.display()))),
+        })
+        .collect()
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([buf[offset], buf[offset + 1]])
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use std::fs::File;
+    use std::os::unix::fs::FileExt;
+    use std::process::Command;
+
+    use tempfile::NamedTempFile;
+
+    /// Format `file` with an empty FAT filesystem, similar to `mkfs.vfat`.
+    pub(crate) fn format(file: &File, offset: u64, sectors: u32, fat32: bool) {
+        let sectors_per_cluster: u8 = 1;
+        let reserved_sectors: u16 = if fat32 { 32 } else { 1 };
+        let root_entries: u16 = if fat32 { 0 } else { 512 };
+        let clusters = sectors; // an upper bound
+        let fat_sectors = if fat32 {
+            (clusters * 4).div_ceil(512)
+        } else if clusters < 4085 {
+            (clusters * 3 / 2 + 2).div_ceil(512)
+        } else {
+            (clusters * 2).div_ceil(512)
+        };
+
+        let mut boot = [0u8; 512];
+        boot[..3].copy_from_slice(&[0xeb, 0x3c, 0x90]);
+        boot[3..11].copy_from_slice(b"mkfs.fat");
+        boot[11..13].copy_from_slice(&512u16.to_le_bytes());
+        boot[13] = sectors_per_cluster;
+        boot[14..16].copy_from_slice(&reserved_sectors.to_le_bytes());
+        boot[16] = 2;
+        boot[17..19].copy_from_slice(&root_entries.to_le_bytes());
+        boot[21] = 0xf8;
+        boot[32..36].copy_from_slice(&sectors.to_le_bytes());
+        if fat32 {
+            boot[36..40].copy_from_slice(&fat_sectors.to_le_bytes());
+            boot[44..48].copy_from_slice(&2u32.to_le_bytes());
+            boot[48..50].copy_from_slice(&1u16.to_le_bytes());
+        } else {
+            boot[22..24].copy_from_slice(&(fat_sectors as u16).to_le_bytes());
+        }
+        boot[510] = 0x55;
+        boot[511] = 0xaa;
+        file.write_all_at(&boot, offset).unwrap();
+
+        let fat_offset = offset + u64::from(reserved_sectors) * 512;
+        let mut fat = vec![0u8; fat_sectors as usize * 512];
+        if fat32 {
+            fat[..12].copy_from_slice(&[
+                0xf8, 0xff, 0xff, 0x0f, 0xff, 0xff, 0xff, 0x0f, 0xff, 0xff, 0xff, 0x0f,
+            ]);
+            let mut fs_info = [0u8; 512];
+            fs_info[..4].copy_from_slice(&0x4161_5252u32.to_le_bytes());
+            fs_info[484..488].copy_from_slice(&0x6141_7272u32.to_le_bytes());
+            fs_info[508..512].copy_from_slice(&0xaa55_0000u32.to_le_bytes());
+            file.write_all_at(&fs_info, offset + 512).unwrap();
+        } else if clusters < 4085 {
+            fat[..3].copy_from_slice(&[0xf8, 0xff, 0xff]);
+        } else {
+            fat[..4].copy_from_slice(&[0xf8, 0xff, 0xff, 0xff]);
+        }
+        for i in 0..2 {
+            file.write_all_at(&fat, fat_offset + i * fat.len() as u64)
+                .unwrap();
+        }
+        let data_offset = fat_offset + 2 * fat.len() as u64;
+        let root_length = if fat32 {
+            512
+        } else {
+            root_entries as usize * 32
+        };
+        file.write_all_at(&vec![0; root_length], data_offset)
+            .unwrap();
+    }
+
+    fn open(file: &File, offset: u64, length: u64) -> FatFilesystem {
+        FatFilesystem::open(Volume::new(file.try_clone().unwrap(), offset, length)).unwrap()
+    }
+
+    #[test]
+    fn short_name_test() {
+        assert_eq!(
+            short_name_for("CONFIG.INI", &[]).unwrap(),
+            (*b"CONFIG  INI", 0, false)
+        );
+        assert_eq!(
+            short_name_for("config.ini", &[]).unwrap(),
+            (*b"CONFIG  INI", NT_LOWER_BASE | NT_LOWER_EXT, false)
+        );
+        assert_eq!(
+            short_name_for("admin", &[]).unwrap(),
+            (*b"ADMIN      ", NT_LOWER_BASE, false)
+        );
+        assert_eq!(
+            short_name_for("ssh_authorized_keys", &[]).unwrap(),
+            (*b"SSH_AU~1   ", 0, true)
+        );
+        assert_eq!(
+            short_name_for("ssh_authorized_keys", &[*b"SSH_AU~1   "]).unwrap(),
+            (*b"SSH_AU~2   ", 0, true)
+        );
+        assert_eq!(
+            short_name_for("Mixed.txt", &[]).unwrap(),
+            (*b"MIXED~1 TXT", 0, true)
+        );
+        assert_eq!(
+            short_name_for("node_operator_private_key.pem", &[]).unwrap(),
+            (*b"NODE_O~1PEM", 0, true)
+        );
+        assert!(short_name_for("a/b", &[]).is_err());
+    }
+
+    #[test]
+    fn long_name_round_trip_test() {
+        let short_name = *b"SSH_AU~1   ";
+        let mut parts: Vec<(u8, [u16; LFN_CHARS])> =
+            long_name_entries("ssh_authorized_keys", &short_name)
+                .unwrap()
+                .iter()
+                .map(|slot| {
+                    let mut chars = [0; LFN_CHARS];
+                    for (i, position) in LFN_CHAR_OFFSETS.iter().enumerate() {
+                        chars[i] = read_u16(slot, *position);
+                    }
+                    assert_eq!(slot[13], short_name_checksum(&short_name));
+                    (slot[0] & !LFN_LAST, chars)
+                })
+                .collect();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(assemble_long_name(&mut parts), "ssh_authorized_keys");
+    }
+
+    fn write_read(fat32: bool, sectors: u32) {
+        let file = tempfile::tempfile().unwrap();
+        let offset = 4096;
+        let length = u64::from(sectors) * 512;
+        file.set_len(offset + length).unwrap();
+        format(&file, offset, sectors, fat32);
+
+        let mut fs = open(&file, offset, length);
+        fs.write_file(Path::new("/config.ini"), b"ipv6_prefix=2a00:fb01:400:200\n")
+            .unwrap();
+        fs.write_file(
+            Path::new("/ssh_authorized_keys/admin"),
+            b"ssh-ed25519 AAAA admin\n",
+        )
+        .unwrap();
+        let large: Vec<u8> = (0..5000u32).map(|i| i as u8).collect();
+        fs.write_file(Path::new("/a/b/c/large.bin"), &large)
+            .unwrap();
+
+        // Reopen to make sure everything was written to the volume.
+        let mut fs = open(&file, offset, length);
+        assert_eq!(
+            fs.read_file(Path::new("/config.ini")).unwrap(),
+            b"ipv6_prefix=2a00:fb01:400:200\n"
+        );
+        assert_eq!(
+            fs.read_file(Path::new("ssh_authorized_keys/admin"))
+                .unwrap(),
+            b"ssh-ed25519 AAAA admin\n"
+        );
+        assert_eq!(
+            fs.read_file(Path::new("/CONFIG.INI")).unwrap(),
+            b"ipv6_prefix=2a00:fb01:400:200\n"
+        );
+        assert_eq!(fs.read_file(Path::new("/a/b/c/large.bin")).unwrap(), large);
+
+        // Overwriting a file releases its clusters.
+        let free_before = (2..=fs.max_cluster())
+            .filter(|cluster| fs.fat_entry(*cluster) == 0)
+            .count();
+        fs.write_file(Path::new("/a/b/c/large.bin"), b"small")
+            .unwrap();
+        let free_after = (2..=fs.max_cluster())
+            .filter(|cluster| fs.fat_entry(*cluster) == 0)
+            .count();
+        assert_eq!(free_after, free_before + 9);
+        assert_eq!(
+            fs.read_file(Path::new("/a/b/c/large.bin")).unwrap(),
+            b"small"
+        );
+        assert_eq!(fs.read_dir(fs.root).unwrap().len(), 3);
+
+        fs.write_file(Path::new("/empty"), b"").unwrap();
+        assert_eq!(fs.read_file(Path::new("/empty")).unwrap(), b"");
+
+        assert!(fs
+            .read_file(Path::new("/does/not/exist.txt"))
+            .unwrap_err()
+            .to_string()
+            .contains("File not found"));
+        assert!(fs.read_file(Path::new("/a")).is_err());
+
+        // Both FAT copies are identical.
+        let fat_length = fs.fat.len();
+        let mut copies = vec![vec![0; fat_length]; 2];
+        for (copy, fat_offset) in copies.iter_mut().zip(&fs.fat_offsets) {
+            file.read_exact_at(copy, offset + fat_offset).unwrap();
+        }
+        assert_eq!(copies[0], copies[1]);
+    }
+
+    #[test]
+    fn fat12_test() {
+        write_read(false, 2048);
+    }
+
+    #[test]
+    fn fat16_test() {
+        write_read(false, 32768);
+    }
+
+    #[test]
+    fn fat32_test() {
+        write_read(true, 70000);
+    }
+
+    /// Create an image with `mkfs.vfat`.
+    fn mkfs_vfat(fat_size: &str, sectors: u32) -> NamedTempFile {
+        let image = NamedTempFile::new().unwrap();
+        image.as_file().set_len(u64::from(sectors) * 512).unwrap();
+        let status = Command::new("/usr/sbin/mkfs.vfat")
+            .args(["-F", fat_size, "-i", "0", "-n", "CONFIG"])
+            .arg(image.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        image
+    }
+
+    fn fsck_vfat(image: &NamedTempFile) {
+        let output = Command::new("/usr/sbin/fsck.vfat")
+            .arg("-n")
+            .arg(image.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+    }
+
+    fn mtools(tool: &str, image: &NamedTempFile, args: &[&str]) -> Vec<u8> {
+        let output = Command::new(tool)
+            .env("MTOOLS_SKIP_CHECK", "1")
+            .arg("-i")
+            .arg(image.path())
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output.stdout
+    }
+
+    #[test]
+    fn mkfs_vfat_test() {
+        for (fat_size, sectors) in [("12", 2048), ("16", 32768), ("32", 131072)] {
+            let image = mkfs_vfat(fat_size, sectors);
+            let length = u64::from(sectors) * 512;
+
+            // Files written by mtools can be read and replaced.
+            let keys = NamedTempFile::new().unwrap();
+            std::fs::write(keys.path(), b"ssh-ed25519 AAAA admin\n").unwrap();
+            mtools("/usr/bin/mmd", &image, &["::/ssh_authorized_keys"]);
+            mtools(
+                "/usr/bin/mcopy",
+                &image,
+                &[
+                    keys.path().to_str().unwrap(),
+                    "::/ssh_authorized_keys/admin",
+                ],
+            );
+            let mut fs = open(image.as_file(), 0, length);
+            assert_eq!(fs.fat_entry_bits().to_string(), fat_size);
+            assert_eq!(
+                fs.read_file(Path::new("/ssh_authorized_keys/admin"))
+                    .unwrap(),
+                b"ssh-ed25519 AAAA admin\n"
+            );
+
+            let large: Vec<u8> = (0..5000u32).map(|i| (i * 7) as u8).collect();
+            fs.write_file(
+                Path::new("/ssh_authorized_keys/admin"),
+                b"ssh-ed25519 BBBB admin\n",
+            )
+            .unwrap();
+            fs.write_file(Path::new("/config.ini"), b"ipv6_prefix=2a00:fb01:400:200\n")
+                .unwrap();
+            fs.write_file(Path::new("/a/b/c/large.bin"), &large)
+                .unwrap();
+            fs.write_file(Path::new("/a/b/c/large.bin"), &large[..1000])
+                .unwrap();
+            fs.write_file(Path::new("/node_operator_private_key.pem"), b"")
+                .unwrap();
+            fsck_vfat(&image);
+
+            // The files can be read by other tools, too.
+            assert_eq!(
+                mtools("/usr/bin/mtype", &image, &["::/ssh_authorized_keys/admin"]),
+                b"ssh-ed25519 BBBB admin\n"
+            );
+            assert_eq!(
+                mtools("/usr/bin/mtype", &image, &["::/config.ini"]),
+                b"ipv6_prefix=2a00:fb01:400:200\n"
+            );
+            assert_eq!(
+                mtools("/usr/bin/mtype", &image, &["::/a/b/c/large.bin"]),
+                &large[..1000]
+            );
+            assert!(mtools(
+                "/usr/bin/mtype",
+                &image,
+                &["::/node_operator_private_key.pem"]
+            )
+            .is_empty());
+        }
+    }
+
+    #[test]
+    fn directory_growth_test() {
+        let file = tempfile::tempfile().unwrap();
+        file.set_len(2048 * 512).unwrap();
+        format(&file, 0, 2048, false);
+
+        // Every file needs a long name entry, so 20 of them exceed a single cluster.
+        let mut fs = open(&file, 0, 2048 * 512);
+        for i in 0..20 {
+            fs.write_file(
+                Path::new(&format!("/dir/file_with_a_long_name_{i}")),
+                i.to_string().as_bytes(),
+            )
+            .unwrap();
+        }
+        for i in 0..20 {
+            assert_eq!(
+                fs.read_file(Path::new(&format!("/dir/file_with_a_long_name_{i}")))
+                    .unwrap(),
+                i.to_string().as_bytes()
+            );
+        }
+    }
+}
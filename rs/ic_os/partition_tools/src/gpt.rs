@@ -0,0 +1,362 @@
+//! Reading and writing GUID partition tables.
+
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+
+use anyhow::{bail, ensure, Context, Result};
+
+/// Images are always built with 512 byte logical sectors.
+pub const SECTOR_SIZE: u64 = 512;
+
+const SIGNATURE: &[u8; 8] = b"EFI PART";
+const HEADER_SIZE: usize = 92;
+
+/// A partition table entry. Only the fields that locate a partition are decoded, everything
+/// else is written back as it was read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GptEntry {
+    raw: Vec<u8>,
+}
+
+impl GptEntry {
+    pub fn is_used(&self) -> bool {
+        self.raw[..16].iter().any(|byte| *byte != 0)
+    }
+
+    pub fn first_lba(&self) -> u64 {
+        read_u64(&self.raw, 32)
+    }
+
+    pub fn last_lba(&self) -> u64 {
+        read_u64(&self.raw, 40)
+    }
+
+    pub fn set_lbas(&mut self, first_lba: u64, last_lba: u64) {
+        self.raw[32..40].copy_from_slice(&first_lba.to_le_bytes());
+        self.raw[40..48].copy_from_slice(&last_lba.to_le_bytes());
+    }
+
+    /// The partition name, stored as UTF-16.
+    pub fn name(&self) -> String {
+        let name: Vec<u16> = self.raw[56..128]
+            .chunks_exact(2)
+            .map(|chunk| u16::from_le_bytes([chunk[0], chunk[1]]))
+            .take_while(|c| *c != 0)
+            .collect();
+        String::from_utf16_lossy(&name)
+    }
+
+    /// The byte offset of the partition in the disk image.
+    pub fn offset(&self) -> Result<u64> {
+        self.first_lba()
+            .checked_mul(SECTOR_SIZE)
+            .with_context(|| format!("Invalid first LBA of partition '{}'", self.name()))
+    }
+
+    /// The byte length of the partition.
+    pub fn length(&self) -> Result<u64> {
+        self.last_lba()
+            .checked_add(1)
+            .and_then(|end| end.checked_sub(self.first_lba()))
+            .and_then(|sectors| sectors.checked_mul(SECTOR_SIZE))
+            .with_context(|| {
+                format!(
+                    "Invalid LBAs of partition '{}': {}..={}",
+                    self.name(),
+                    self.first_lba(),
+                    self.last_lba()
+                )
+            })
+    }
+}
+
+/// The partition table of a disk image, as read from its primary header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gpt {
+    header: Vec<u8>,
+    entries: Vec<GptEntry>,
+}
+
+impl Gpt {
+    /// Read and verify the primary partition table.
+    pub fn read(file: &File) -> Result<Self> {
+        let mut header = vec![0; HEADER_SIZE];
+        file.read_exact_at(&mut header, SECTOR_SIZE)
+            .context("failed to read GPT header")?;
+        ensure!(&header[..8] == SIGNATURE, "disk image has no GPT");
+        ensure!(
+            read_u32(&header, 12) as usize == HEADER_SIZE,
+            "unsupported GPT header size {}",
+            read_u32(&header, 12)
+        );
+        let expected = read_u32(&header, 16);
+        ensure!(
+            header_crc(&header) == expected,
+            "GPT header checksum mismatch"
+        );
+
+        let entry_size = read_u32(&header, 84) as usize;
+        ensure!(entry_size >= 128, "unsupported GPT entry size {entry_size}");
+        let mut entries = vec![0; read_u32(&header, 80) as usize * entry_size];
+        file.read_exact_at(&mut entries, read_u64(&header, 72) * SECTOR_SIZE)
+            .context("failed to read GPT entries")?;
+        ensure!(
+            crc32fast::hash(&entries) == read_u32(&header, 88),
+            "GPT entries checksum mismatch"
+        );
+
+        Ok(Self {
+            header,
+            entries: entries
+                .chunks_exact(entry_size)
+                .map(|raw| GptEntry { raw: raw.to_vec() })
+                .collect(),
+        })
+    }
+
+    /// Look up a partition by its 1-based index, as numbered by `fdisk`.
+    pub fn partition(&self, index: usize) -> Result<&GptEntry> {
+        match index.checked_sub(1).and_then(|i| self.entries.get(i)) {
+            Some(entry) if entry.is_used() => Ok(entry),
+            _ => bail!("Partition index '{index}' not found in image"),
+        }
+    }
+
+    pub fn partition_mut(&mut self, index: usize) -> Result<&mut GptEntry> {
+        match index.checked_sub(1).and_then(|i| self.entries.get_mut(i)) {
+            Some(entry) if entry.is_used() => Ok(entry),
+            _ => bail!("Partition index '{index}' not found in image"),
+        }
+    }
+
+    pub fn first_usable_lba(&self) -> u64 {
+        read_u64(&self.header, 40)
+    }
+
+    pub fn last_usable_lba(&self) -> u64 {
+        read_u64(&self.header, 48)
+    }
+
+    /// Write the primary and the backup partition table, recomputing their checksums.
+    pub fn write(&self, file: &File) -> Result<()> {
+        for entry in self.entries.iter().filter(|entry| entry.is_used()) {
+            ensure!(
+                entry.first_lba() >= self.first_usable_lba()
+                    && entry.first_lba() <= entry.last_lba()
+                    && entry.last_lba() <= self.last_usable_lba(),
+                "partition '{}' is outside of the usable area",
+                entry.name()
+            );
+        }
+
+        let entries: Vec<u8> = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.raw.iter().copied())
+            .collect();
+        let entries_crc = crc32fast::hash(&entries);
+
+        let primary_lba = read_u64(&self.header, 24);
+        let backup_lba = read_u64(&self.header, 32);
+        let primary_entries_lba = read_u64(&self.header, 72);
+        // The backup entries directly precede the backup header.
+        let backup_entries_lba = self.last_usable_lba() + 1;
+
+        let mut primary = self.header.clone();
+        primary[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+        let mut backup = primary.clone();
+        backup[24..32].copy_from_slice(&backup_lba.to_le_bytes());
+        backup[32..40].copy_from_slice(&primary_lba.to_le_bytes());
+        backup[72..80].copy_from_slice(&backup_entries_lba.to_le_bytes());
+
+        for (header, header_lba, entries_lba) in [
+            (&mut primary, primary_lba, primary_entries_lba),
+            (&mut backup, backup_lba, backup_entries_lba),
+        ] {
+            let crc = header_crc(header);
+            header[16..20].copy_from_slice(&crc.to_le_bytes());
+            file.write_all_at(&entries, entries_lba * SECTOR_SIZE)
+                .context("failed to write GPT entries")?;
+            file.write_all_at(header, header_lba * SECTOR_SIZE)
+                .context("failed to write GPT header")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn header_crc(header: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&header[..16]);
+    hasher.update(&[0; 4]);
+    hasher.update(&header[20..HEADER_SIZE]);
+    hasher.finalize()
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use std::path::Path;
+    use std::process::Command;
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    /// Lay out a GPT with the given partitions, given as (first LBA, last LBA, name), on a disk
+    /// of `sectors` sectors.
+    pub(crate) fn write_test_gpt(file: &File, sectors: u64, partitions: &[(u64, u64, &str)]) {
+        file.set_len(sectors * SECTOR_SIZE).unwrap();
+
+        let mut entries = vec![GptEntry { raw: vec![0; 128] }; 128];
+        for (entry, (first_lba, last_lba, name)) in entries.iter_mut().zip(partitions) {
+            // Linux filesystem data
+            entry.raw[..16].copy_from_slice(&[
+                0xaf, 0x3d, 0xc6, 0x0f, 0x83, 0x84, 0x72, 0x47, 0x8e, 0x79, 0x3d, 0x69, 0xd8, 0x47,
+                0x7d, 0xe4,
+            ]);
+            entry.raw[16] = 1;
+            entry.set_lbas(*first_lba, *last_lba);
+            for (i, c) in name.encode_utf16().enumerate() {
+                entry.raw[56 + 2 * i..58 + 2 * i].copy_from_slice(&c.to_le_bytes());
+            }
+        }
+
+        let mut header = vec![0; HEADER_SIZE];
+        header[..8].copy_from_slice(SIGNATURE);
+        header[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+        header[12..16].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        header[24..32].copy_from_slice(&1u64.to_le_bytes());
+        header[32..40].copy_from_slice(&(sectors - 1).to_le_bytes());
+        header[40..48].copy_from_slice(&34u64.to_le_bytes());
+        header[48..56].copy_from_slice(&(sectors - 34).to_le_bytes());
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&128u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+
+        Gpt { header, entries }.write(file).unwrap();
+    }
+
+    #[test]
+    fn read_write_test() {
+        let file = tempfile::tempfile().unwrap();
+        write_test_gpt(&file, 4096, &[(2048, 2559, "config"), (2560, 4000, "data")]);
+
+        let mut gpt = Gpt::read(&file).unwrap();
+        let config = gpt.partition(1).unwrap();
+        assert_eq!(config.name(), "config");
+        assert_eq!(config.offset().unwrap(), 2048 * 512);
+        assert_eq!(config.length().unwrap(), 512 * 512);
+        assert!(gpt.partition(3).is_err());
+        assert!(gpt.partition(0).is_err());
+
+        gpt.partition_mut(2).unwrap().set_lbas(2560, 4062);
+        gpt.write(&file).unwrap();
+        let gpt = Gpt::read(&file).unwrap();
+        assert_eq!(gpt.partition(2).unwrap().length().unwrap(), 1503 * 512);
+
+        // The backup header at the last sector must be consistent with the primary one.
+        let mut backup = vec![0; HEADER_SIZE];
+        file.read_exact_at(&mut backup, 4095 * SECTOR_SIZE).unwrap();
+        assert_eq!(&backup[..8], SIGNATURE);
+        assert_eq!(read_u64(&backup, 24), 4095);
+        assert_eq!(read_u64(&backup, 32), 1);
+        assert_eq!(read_u32(&backup, 16), header_crc(&backup));
+        assert_eq!(read_u32(&backup, 88), read_u32(&gpt.header, 88));
+
+        // Corrupt entries are reported rather than overflowing.
+        let mut entry = gpt.partition(2).unwrap().clone();
+        entry.set_lbas(2560, 2558);
+        assert!(entry.length().is_err());
+        entry.set_lbas(u64::MAX, u64::MAX);
+        assert!(entry.offset().is_err());
+        assert!(entry.length().is_err());
+
+        // Partitions may not be moved out of the usable area.
+        let mut gpt = gpt;
+        gpt.partition_mut(2).unwrap().set_lbas(2560, 4095);
+        assert!(gpt.write(&file).is_err());
+    }
+
+    fn sgdisk(image: &Path, args: &[&str]) -> String {
+        let output = Command::new("/usr/sbin/sgdisk")
+            .args(args)
+            .arg(image)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+        assert!(output.status.success(), "{stdout}");
+        stdout
+    }
+
+    #[test]
+    fn sgdisk_test() {
+        let image = NamedTempFile::new().unwrap();
+        image.as_file().set_len(16384 * SECTOR_SIZE).unwrap();
+        sgdisk(
+            image.path(),
+            &[
+                "-n",
+                "1:2048:4095",
+                "-c",
+                "1:config",
+                "-n",
+                "2:4096:8191",
+                "-c",
+                "2:data",
+            ],
+        );
+        let info = sgdisk(image.path(), &["-i", "2"]);
+
+        let mut gpt = Gpt::read(image.as_file()).unwrap();
+        let config = gpt.partition(1).unwrap();
+        assert_eq!(config.name(), "config");
+        assert_eq!(config.offset().unwrap(), 2048 * SECTOR_SIZE);
+        assert_eq!(config.length().unwrap(), 2048 * SECTOR_SIZE);
+        assert_eq!(gpt.partition(2).unwrap().name(), "data");
+        assert!(gpt.partition(3).is_err());
+
+        // Grow the last partition to the end of the disk, as done when resizing images.
+        let last_usable_lba = gpt.last_usable_lba();
+        gpt.partition_mut(2)
+            .unwrap()
+            .set_lbas(4096, last_usable_lba);
+        gpt.write(image.as_file()).unwrap();
+
+        let verify = sgdisk(image.path(), &["-v"]);
+        assert!(verify.contains("No problems found"), "{verify}");
+        let resized = sgdisk(image.path(), &["-i", "2"]);
+        assert!(
+            resized.contains(&format!("Last sector: {last_usable_lba} ")),
+            "{resized}"
+        );
+        // Everything but the location is unchanged.
+        let unique_guid = |info: &str| {
+            info.lines()
+                .find(|line| line.starts_with("Partition unique GUID"))
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(unique_guid(&resized), unique_guid(&info));
+        assert!(resized.contains("Partition name: 'data'"), "{resized}");
+    }
+
+    #[test]
+    fn corrupted_header_test() {
+        let file = tempfile::tempfile().unwrap();
+        write_test_gpt(&file, 4096, &[(2048, 4000, "data")]);
+        file.write_all_at(&[0xff], SECTOR_SIZE + 40).unwrap();
+
+        assert!(Gpt::read(&file)
+            .unwrap_err()
+            .to_string()
+            .contains("checksum mismatch"));
+    }
+}
@@ -1,5 +1,7 @@
 pub mod ext;
 pub mod fat;
+pub mod gpt;
 mod partition;
+mod volume;
 
 pub use partition::Partition;
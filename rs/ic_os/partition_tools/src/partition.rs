@@ -1,8 +1,11 @@
+use std::fs::File;
 use std::path::{Path, PathBuf};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use async_trait::async_trait;
-use tokio::process::Command;
+
+use crate::gpt::Gpt;
+use crate::volume::Volume;
 
 #[async_trait]
 pub trait Partition: Sized {
@@ -19,84 +22,34 @@ pub trait Partition: Sized {
     async fn read_file(&mut self, input: &Path) -> Result<String>;
 }
 
-/// Use fdisk to check the byte offset of a given partition
-pub async fn check_offset(disk_image: &Path, index: usize) -> Result<u64> {
-    /*
-    Example output:
-    Disk /srv/images/setupos.node-908.img: 3.78 GiB, 4039114752 bytes, 7888896 sectors
-    Units: sectors of 1 * 512 = 512 bytes
-    Sector size (logical/physical): 512 bytes / 512 bytes
-    I/O size (minimum/optimal): 512 bytes / 512 bytes
-    Disklabel type: gpt
-    Disk identifier: 2B110BB7-CDEC-7D41-B97E-893EDCBE5428
-
-    Device                              Start
-    /srv/images/setupos.node-908.img1    2048
-    /srv/images/setupos.node-908.img2  206848
-    /srv/images/setupos.node-908.img3  411648
-    /srv/images/setupos.node-908.img4  514048
-    /srv/images/setupos.node-908.img5 4098048
-    /srv/images/setupos.node-908.img6 4302848
-    */
-
-    let prefix = disk_image.to_str().expect("Path is not valid UTF-8");
-
-    let out = Command::new("fdisk")
-        .args([
-            "-l",
-            &disk_image.display().to_string(),
-            "-o",
-            "device,start",
-        ])
-        .output()
-        .await
-        .context("Failed to run fdisk")?;
-
-    if !out.status.success() {
-        return Err(anyhow!("fdisk failed: {}", String::from_utf8(out.stderr)?));
-    }
-
-    let line = std::str::from_utf8(&out.stdout)?
-        .lines()
-        .find(|v| v.starts_with(&format!("{}{}", prefix, index)))
-        .expect("Partition index '{index}' not found in image");
-    let offset = line
-        .split_ascii_whitespace()
-        .nth(1)
-        .expect("Unable to parse fdisk output");
+/// Read the partition table to find the byte offset of a given partition
+pub fn check_offset(disk_image: &Path, index: usize) -> Result<u64> {
+    let gpt = Gpt::read(&File::open(disk_image)?)?;
 
-    // Assume 512 byte sectors
-    Ok(offset.parse::<u64>()? * 512)
+    gpt.partition(index)?.offset()
 }
 
-/// Use fdisk to check the byte size of a given partition
-pub async fn check_length(disk_image: &Path, index: usize) -> Result<u64> {
-    let prefix = disk_image.to_str().expect("Path is not valid UTF-8");
+/// Read the partition table to find the byte size of a given partition
+pub fn check_length(disk_image: &Path, index: usize) -> Result<u64> {
+    let gpt = Gpt::read(&File::open(disk_image)?)?;
 
-    let out = Command::new("fdisk")
-        .args([
-            "-l",
-            &disk_image.display().to_string(),
-            "-o",
-            "device,sectors",
-        ])
-        .output()
-        .await
-        .context("Failed to run fdisk")?;
+    gpt.partition(index)?.length()
+}
 
-    if !out.status.success() {
-        return Err(anyhow!("fdisk failed: {}", String::from_utf8(out.stderr)?));
+/// Open a partition of a disk image, or the whole image, for reading and writing in place
+pub(crate) fn open_volume(image: &Path, index: Option<usize>) -> Result<Volume> {
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .open(image)
+        .with_context(|| format!("failed to open {}", image.display()))?;
+
+    match index {
+        Some(index) => {
+            let gpt = Gpt::read(&file)?;
+            let entry = gpt.partition(index)?;
+            Ok(Volume::new(file, entry.offset()?, entry.length()?))
+        }
+        None => Volume::whole(file),
     }
-
-    let line = std::str::from_utf8(&out.stdout)?
-        .lines()
-        .find(|v| v.starts_with(&format!("{}{}", prefix, index)))
-        .expect("Partition index '{index}' not found in image");
-    let offset = line
-        .split_ascii_whitespace()
-        .nth(1)
-        .expect("Unable to parse fdisk output");
-
-    // Assume 512 byte sectors
-    Ok(offset.parse::<u64>()? * 512)
 }
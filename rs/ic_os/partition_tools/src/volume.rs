@@ -0,0 +1,61 @@
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+
+use anyhow::{ensure, Context, Result};
+
+/// A byte range of an image file that holds a single filesystem.
+pub(crate) struct Volume {
+    file: File,
+    offset: u64,
+    length: u64,
+}
+
+impl Volume {
+    pub(crate) fn new(file: File, offset: u64, length: u64) -> Self {
+        Self {
+            file,
+            offset,
+            length,
+        }
+    }
+
+    /// Use the whole file as the volume.
+    pub(crate) fn whole(file: File) -> Result<Self> {
+        let length = file.metadata()?.len();
+        Ok(Self::new(file, 0, length))
+    }
+
+    pub(crate) fn length(&self) -> u64 {
+        self.length
+    }
+
+    pub(crate) fn read_at(&self, buf: &mut [u8], position: u64) -> Result<()> {
+        self.check_bounds(buf.len(), position)?;
+        self.file
+            .read_exact_at(buf, self.offset + position)
+            .with_context(|| format!("failed to read {} bytes at {}", buf.len(), position))
+    }
+
+    pub(crate) fn write_at(&self, buf: &[u8], position: u64) -> Result<()> {
+        self.check_bounds(buf.len(), position)?;
+        self.file
+            .write_all_at(buf, self.offset + position)
+            .with_context(|| format!("failed to write {} bytes at {}", buf.len(), position))
+    }
+
+    pub(crate) fn sync(&self) -> Result<()> {
+        self.file.sync_data().context("failed to sync volume")
+    }
+
+    fn check_bounds(&self, len: usize, position: u64) -> Result<()> {
+        ensure!(
+            position
+                .checked_add(len as u64)
+                .is_some_and(|end| end <= self.length),
+            "access of {} bytes at {} is beyond the end of the volume",
+            len,
+            position
+        );
+        Ok(())
+    }
+}
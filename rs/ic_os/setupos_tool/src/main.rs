@@ -1,10 +1,15 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
 use clap::{Parser, Subcommand};
 
+use config::legacy::{network_settings_from_config_map, setupos_config_from_legacy};
+use config::types::SetupOSConfig;
 use config::{
-    config_map_from_path, DEFAULT_SETUPOS_CONFIG_FILE_PATH, DEFAULT_SETUPOS_DEPLOYMENT_JSON_PATH,
+    config_map_from_path, hostos_config_from_setupos_config, serialize_and_write_config,
+    DEFAULT_SETUPOS_CONFIG_FILE_PATH, DEFAULT_SETUPOS_CONFIG_OBJECT_PATH,
+    DEFAULT_SETUPOS_DEPLOYMENT_JSON_PATH, DEFAULT_SETUPOS_NODE_OPERATOR_PRIVATE_KEY_PATH,
+    DEFAULT_SETUPOS_SSH_AUTHORIZED_KEYS_PATH,
 };
 use network::generate_network_config;
 use network::info::NetworkInfo;
@@ -31,6 +36,19 @@ pub enum Commands {
         #[arg(short, long, default_value = "SetupOS")]
         node_type: String,
     },
+    /// Create the typed configuration object from config.ini and deployment.json, rejecting
+    /// invalid configurations.
+    CreateSetuposConfig {
+        #[arg(short, long, default_value_t = DEFAULT_SETUPOS_CONFIG_OBJECT_PATH.to_string(), value_name = "FILE")]
+        /// config.json output file path
+        output: String,
+    },
+    /// Create the HostOS configuration object that is installed on the HostOS config partition.
+    CreateHostosConfig {
+        #[arg(short, long, value_name = "FILE")]
+        /// HostOS config.json output file path
+        output: String,
+    },
 }
 
 #[derive(Parser)]
@@ -46,6 +64,54 @@ struct SetupOSArgs {
     command: Option<Commands>,
 }
 
+/// Reads config.ini and deployment.json, and builds the SetupOS configuration object from them,
+/// rejecting invalid configurations.
+fn read_setupos_config(opts: &SetupOSArgs) -> Result<SetupOSConfig> {
+    let config_map = config_map_from_path(Path::new(&opts.config))
+        .context("Please specify a valid config file with '--config'")?;
+    eprintln!("Using config: {:?}", config_map);
+
+    let deployment = read_deployment_file(Path::new(&opts.deployment_file))
+        .context("Please specify a valid deployment file with '--deployment-file'")?;
+    eprintln!("Deployment config: {:?}", deployment);
+
+    let existing_path = |path: &str| Some(PathBuf::from(path)).filter(|p| p.exists());
+    let setupos_config = setupos_config_from_legacy(
+        &config_map,
+        &deployment,
+        existing_path(DEFAULT_SETUPOS_NODE_OPERATOR_PRIVATE_KEY_PATH),
+        existing_path(DEFAULT_SETUPOS_SSH_AUTHORIZED_KEYS_PATH),
+    )
+    .context("Invalid SetupOS configuration")?;
+    eprintln!("SetupOS config: {:?}", setupos_config);
+
+    Ok(setupos_config)
+}
+
+/// Reads the network info from config.ini, without validating the rest of the configuration.
+fn read_network_info(opts: &SetupOSArgs) -> Result<NetworkInfo> {
+    let config_map = config_map_from_path(Path::new(&opts.config))
+        .context("Please specify a valid config file with '--config'")?;
+    eprintln!("Using config: {:?}", config_map);
+
+    let network_settings = network_settings_from_config_map(&config_map)?;
+    let network_info = NetworkInfo::from_network_settings(
+        &network_settings,
+        config_map.get("mgmt_mac").map(String::as_str),
+    )?;
+    eprintln!("Network info config: {:?}", &network_info);
+
+    Ok(network_info)
+}
+
+fn read_deployment_name(opts: &SetupOSArgs) -> Result<String> {
+    let deployment = read_deployment_file(Path::new(&opts.deployment_file))
+        .context("Please specify a valid deployment file with '--deployment-file'")?;
+    eprintln!("Deployment config: {:?}", deployment);
+
+    Ok(deployment.deployment.name)
+}
+
 pub fn main() -> Result<()> {
     #[cfg(not(target_os = "linux"))]
     {
@@ -54,49 +120,31 @@ pub fn main() -> Result<()> {
     }
     let opts = SetupOSArgs::parse();
 
-    match opts.command {
+    match &opts.command {
         Some(Commands::GenerateNetworkConfig { output_directory }) => {
-            let config_map = config_map_from_path(Path::new(&opts.config))
-                .context("Please specify a valid config file with '--config'")?;
-            eprintln!("Using config: {:?}", config_map);
-
-            let network_info = NetworkInfo::from_config_map(&config_map)?;
-            eprintln!("Network info config: {:?}", &network_info);
+            let network_info = read_network_info(&opts)?;
 
-            let deployment = read_deployment_file(Path::new(&opts.deployment_file));
-            let deployment_name: Option<&str> = match &deployment {
-                Ok(deployment) => Some(deployment.deployment.name.as_str()),
+            let deployment_name = match read_deployment_name(&opts) {
+                Ok(deployment_name) => Some(deployment_name),
                 Err(e) => {
-                    eprintln!("Error retrieving deployment file: {e}. Continuing without it");
+                    eprintln!("Error retrieving deployment file: {e:#}. Continuing without it");
                     None
                 }
             };
 
             generate_network_config(
                 &network_info,
-                deployment_name,
+                deployment_name.as_deref(),
                 NodeType::SetupOS,
                 Path::new(&output_directory),
             )
         }
         Some(Commands::GenerateIpv6Address { node_type }) => {
-            let deployment = read_deployment_file(Path::new(&opts.deployment_file))
-                .context("Please specify a valid deployment file with '--deployment-file'")?;
-            eprintln!("Deployment config: {:?}", deployment);
-
-            let config_map = config_map_from_path(Path::new(&opts.config))
-                .context("Please specify a valid config file with '--config'")?;
-            eprintln!("Using config: {:?}", config_map);
-
-            let network_info = NetworkInfo::from_config_map(&config_map)?;
-            eprintln!("Network info config: {:?}", &network_info);
+            let network_info = read_network_info(&opts)?;
+            let deployment_name = read_deployment_name(&opts)?;
 
             let node_type = node_type.parse::<NodeType>()?;
-            let mac = generate_mac_address(
-                &deployment.deployment.name,
-                &node_type,
-                &network_info.bmc_mac,
-            )?;
+            let mac = generate_mac_address(&deployment_name, &node_type, &network_info.bmc_mac)?;
             let ipv6_prefix = network_info
                 .ipv6_prefix
                 .context("ipv6_prefix required in config to generate ipv6 address")?;
@@ -105,27 +153,24 @@ pub fn main() -> Result<()> {
             Ok(())
         }
         Some(Commands::GenerateMacAddress { node_type }) => {
-            let config_map = config_map_from_path(Path::new(&opts.config))
-                .context("Please specify a valid config file with '--config'")?;
-            eprintln!("Using config: {:?}", config_map);
-
-            let network_info = NetworkInfo::from_config_map(&config_map)?;
-            eprintln!("Network info config: {:?}", &network_info);
-
-            let deployment = read_deployment_file(Path::new(&opts.deployment_file))
-                .context("Please specify a valid deployment file with '--deployment-file'")?;
-            eprintln!("Deployment config: {:?}", deployment);
+            let network_info = read_network_info(&opts)?;
+            let deployment_name = read_deployment_name(&opts)?;
 
             let node_type = node_type.parse::<NodeType>()?;
-            let mac = generate_mac_address(
-                &deployment.deployment.name,
-                &node_type,
-                &network_info.bmc_mac,
-            )?;
+            let mac = generate_mac_address(&deployment_name, &node_type, &network_info.bmc_mac)?;
             let mac = FormattedMacAddress::from(&mac);
             println!("{}", mac.get());
             Ok(())
         }
+        Some(Commands::CreateSetuposConfig { output }) => {
+            let setupos_config = read_setupos_config(&opts)?;
+            serialize_and_write_config(Path::new(&output), &setupos_config)
+        }
+        Some(Commands::CreateHostosConfig { output }) => {
+            let hostos_config = hostos_config_from_setupos_config(read_setupos_config(&opts)?);
+            eprintln!("HostOS config: {:?}", hostos_config);
+            serialize_and_write_config(Path::new(&output), &hostos_config)
+        }
         None => Err(anyhow!(
             "No subcommand specified. Run with '--help' for subcommands"
         )),
@@ -2,10 +2,14 @@
 
 use clap::{Args, Parser};
 use vsock_lib::protocol::{Command, NotifyData, Payload, UpgradeData};
-use vsock_lib::send_command;
+use vsock_lib::{run_guest_server, send_command};
 fn main() -> Result<(), String> {
     let cli = Cli::parse();
 
+    if cli.serve_health {
+        return run_guest_server().map_err(|e| e.to_string());
+    }
+
     let port = cli.port;
     let command = get_command(cli)?;
     let payload = send_command(command, port)?;
@@ -14,6 +18,7 @@ fn main() -> Result<(), String> {
     match payload {
         Payload::HostOSVsockVersion(version) => println!("{}", version),
         Payload::HostOSVersion(version) => println!("{}", version),
+        Payload::GuestOSHealth(health) => println!("{}", health),
         Payload::NoPayload => (),
     }
 
@@ -39,6 +44,10 @@ struct Cli {
     #[clap(long)]
     get_hostos_version: bool,
 
+    /// Serve GuestOS health queries from the hostOS
+    #[clap(long)]
+    serve_health: bool,
+
     /// Set a custom port
     #[clap(long, default_value = "19090")]
     port: u32,
@@ -1,6 +1,38 @@
 #![cfg(target_os = "linux")]
 
-use vsock_lib::run_server;
-fn main() -> std::io::Result<()> {
-    run_server()
+use std::path::PathBuf;
+
+use clap::Parser;
+use vsock_lib::{query_guestos_health, run_server, write_metrics};
+
+fn main() -> Result<(), String> {
+    let cli = Cli::parse();
+
+    if !cli.query_guestos_health {
+        return run_server().map_err(|e| e.to_string());
+    }
+
+    let health = query_guestos_health();
+    if let Some(metrics_file) = cli.metrics {
+        write_metrics(&health, &metrics_file).map_err(|e| e.to_string())?;
+    }
+
+    println!("{}", health?);
+
+    Ok(())
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    version = "1.0.0",
+    about = "The HostOS vsock server, and a CLI for querying the GuestOS"
+)]
+struct Cli {
+    /// Query the GuestOS health instead of running the server
+    #[arg(long)]
+    query_guestos_health: bool,
+
+    /// Write the GuestOS health as Prometheus metrics to the given file
+    #[arg(long, value_name = "FILE", requires = "query_guestos_health")]
+    metrics: Option<PathBuf>,
 }
@@ -0,0 +1,148 @@
+use crate::protocol::{DiskUsage, GuestOSHealth};
+use ic_replica_metrics::parse_consensus_height;
+use regex::Regex;
+use std::process::Command;
+use std::time::Duration;
+
+const REPLICA_SERVICE: &str = "ic-replica";
+// The replica config generated at boot, which contains the address of the metrics endpoint.
+const REPLICA_CONFIG_FILE_PATH: &str = "/run/ic-node/config/ic.json5";
+const MONITORED_MOUNT_POINTS: [&str; 2] = ["/var/lib/ic/data", "/var/lib/ic/crypto"];
+
+/// Collect the GuestOS health. Failures are logged and leave the corresponding value empty.
+pub fn collect_guestos_health() -> GuestOSHealth {
+    let replica_status = get_replica_status().unwrap_or_else(|err| {
+        println!("Error getting replica status: {}", err);
+        "unknown".to_string()
+    });
+
+    let metrics = get_replica_metrics().unwrap_or_else(|err| {
+        println!("Error getting replica metrics: {}", err);
+        String::new()
+    });
+
+    let disk_usage = get_disk_usage(&MONITORED_MOUNT_POINTS).unwrap_or_else(|err| {
+        println!("Error getting disk usage: {}", err);
+        Vec::new()
+    });
+
+    GuestOSHealth {
+        replica_status,
+        finalized_height: parse_consensus_height(&metrics, "finalization"),
+        last_cup_height: parse_consensus_height(&metrics, "catch_up_package"),
+        disk_usage,
+    }
+}
+
+fn get_replica_status() -> Result<String, String> {
+    // `systemctl is-active` exits with a non-zero status for inactive units, but still reports
+    // the state on stdout.
+    let output = Command::new("systemctl")
+        .args(["is-active", REPLICA_SERVICE])
+        .output()
+        .map_err(|err| format!("Unable to run systemctl: {}", err))?;
+
+    String::from_utf8(output.stdout)
+        .map(|status| status.trim().to_string())
+        .map_err(|err| format!("Unable to read systemctl output: {}", err))
+}
+
+fn get_replica_metrics() -> Result<String, String> {
+    let replica_config = std::fs::read_to_string(REPLICA_CONFIG_FILE_PATH)
+        .map_err(|err| format!("Unable to read replica config: {}", err))?;
+    let metrics_address = parse_metrics_address(&replica_config)
+        .ok_or_else(|| "Replica config does not contain a metrics address".to_string())?;
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(3))
+        .build()
+        .map_err(|err| format!("Could not build metrics client: {}", err))?;
+
+    client
+        .get(format!("http://{}/metrics", metrics_address))
+        .send()
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(|err| format!("Could not fetch replica metrics: {}", err))
+}
+
+fn parse_metrics_address(replica_config: &str) -> Option<String> {
+    let regex = Regex::new(r#"exporter:\s*\{\s*http:\s*"([^"]+)""#).ok()?;
+    regex
+        .captures(replica_config)
+        .map(|captures| captures[1].to_string())
+}
+
+fn get_disk_usage(mount_points: &[&str]) -> Result<Vec<DiskUsage>, String> {
+    let output = Command::new("df")
+        .args(["--block-size=1", "--output=target,used,size"])
+        .args(mount_points)
+        .output()
+        .map_err(|err| format!("Unable to run df: {}", err))?;
+
+    let output = String::from_utf8(output.stdout)
+        .map_err(|err| format!("Unable to read df output: {}", err))?;
+
+    Ok(parse_disk_usage(&output))
+}
+
+fn parse_disk_usage(df_output: &str) -> Vec<DiskUsage> {
+    df_output
+        .lines()
+        // Skip the header line.
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.next()?.to_string();
+            let used_bytes = fields.next()?.parse().ok()?;
+            let total_bytes = fields.next()?.parse().ok()?;
+            Some(DiskUsage {
+                mount_point,
+                used_bytes,
+                total_bytes,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_metrics_address() {
+        let replica_config = r#"
+    metrics: {
+        exporter: { http: "[2a00:fb01:400:200:6801:6bff:fe5e:b6e]:9090", },
+    },"#;
+        assert_eq!(
+            parse_metrics_address(replica_config),
+            Some("[2a00:fb01:400:200:6801:6bff:fe5e:b6e]:9090".to_string())
+        );
+        assert_eq!(parse_metrics_address("metrics: {}"), None);
+    }
+
+    #[test]
+    fn test_parse_disk_usage() {
+        let df_output = "\
+Mounted on                 Used         1B-blocks
+/var/lib/ic/data   536870912000 2147483648000
+/var/lib/ic/crypto     12288000     100000000
+";
+        assert_eq!(
+            parse_disk_usage(df_output),
+            vec![
+                DiskUsage {
+                    mount_point: "/var/lib/ic/data".to_string(),
+                    used_bytes: 536870912000,
+                    total_bytes: 2147483648000,
+                },
+                DiskUsage {
+                    mount_point: "/var/lib/ic/crypto".to_string(),
+                    used_bytes: 12288000,
+                    total_bytes: 100000000,
+                },
+            ]
+        );
+    }
+}
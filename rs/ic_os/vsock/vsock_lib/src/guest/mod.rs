@@ -1,4 +1,6 @@
 mod client;
+mod health;
+pub(crate) mod server;
 use crate::protocol::{Command, Request, Response};
 
 /// Send a command to the host vsock server
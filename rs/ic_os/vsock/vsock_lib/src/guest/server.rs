@@ -0,0 +1,134 @@
+use crate::guest::health::collect_guestos_health;
+use crate::protocol::{parse_guest_command, GuestCommand, Payload, Response};
+use std::io::{Error, ErrorKind, Read, Result, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use vsock::{VsockAddr, VsockListener, VsockStream, VMADDR_CID_ANY, VMADDR_CID_HOST};
+
+pub(crate) const DEFAULT_GUEST_PORT: u32 = 19091;
+// The HostOS queries the health periodically, so a few connections at a time are plenty.
+const MAX_CONCURRENT_CONNECTIONS: usize = 4;
+
+/// Runs the guest vsock server, which answers health queries from the HostOS.
+pub fn run_guest_server() -> Result<()> {
+    let addr = VsockAddr::new(VMADDR_CID_ANY, DEFAULT_GUEST_PORT);
+    let vsock_listener = VsockListener::bind(&addr)?;
+
+    println!("Listening for vsock connection from the host.\n");
+
+    let active_connections = Arc::new(AtomicUsize::new(0));
+    for stream in vsock_listener.incoming() {
+        let mut stream: VsockStream = stream?;
+        stream.set_write_timeout(Some(std::time::Duration::from_secs(5)))?;
+        stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+
+        let Some(connection) = ConnectionSlot::acquire(&active_connections) else {
+            let error = "Too many concurrent connections, try again later";
+            println!("Rejecting vsock connection: {}", error);
+            // Best effort: the connection is dropped either way.
+            let _ = send_response(&mut stream, &Err(error.to_string()));
+            continue;
+        };
+
+        std::thread::spawn(move || -> Result<()> {
+            let _connection = connection;
+            process_connection(&mut stream)
+        });
+    }
+
+    Ok(())
+}
+
+/// One of the `MAX_CONCURRENT_CONNECTIONS` connections that are processed at a time, released
+/// when dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn acquire(active_connections: &Arc<AtomicUsize>) -> Option<Self> {
+        active_connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| {
+                (active < MAX_CONCURRENT_CONNECTIONS).then_some(active + 1)
+            })
+            .ok()
+            .map(|_| Self(Arc::clone(active_connections)))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn process_connection(stream: &mut VsockStream) -> Result<()> {
+    // Only the HostOS may query the GuestOS.
+    if let Err(err) = verify_sender_is_host(stream) {
+        send_response(stream, &Err(err.to_string()))?;
+        return Err(err);
+    }
+
+    let command = match get_command(stream) {
+        Ok(command) => command,
+        Err(err) => {
+            send_response(stream, &Err(err.to_string()))?;
+            return Err(err);
+        }
+    };
+    println!("Received vsock command: {}", command);
+
+    let response: Response = match command {
+        GuestCommand::GetGuestOSHealth => Ok(Payload::GuestOSHealth(collect_guestos_health())),
+    };
+
+    send_response(stream, &response)
+}
+
+fn get_command(stream: &mut VsockStream) -> Result<GuestCommand> {
+    let mut buffer = [0; 4096];
+    let bytes_read = stream.read(&mut buffer)?;
+    let json_command = std::str::from_utf8(&buffer[..bytes_read])
+        .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+
+    parse_guest_command(json_command).map_err(|e| Error::new(ErrorKind::InvalidInput, e))
+}
+
+fn verify_sender_is_host(stream: &mut VsockStream) -> Result<()> {
+    let peer_address = stream.peer_addr().map_err(|err| {
+        let error = format!("Error: could not verify the sender cid. {}", err);
+        Error::new(ErrorKind::InvalidData, error)
+    })?;
+
+    if peer_address.cid() == VMADDR_CID_HOST {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::PermissionDenied,
+            "Only the host may send commands to the guest",
+        ))
+    }
+}
+
+fn send_response(stream: &mut VsockStream, response: &Response) -> Result<()> {
+    let json_response = serde_json::to_string(&response)?;
+    stream.write_all(json_response.as_bytes())?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_slots_are_bounded_and_released() {
+        let active_connections = Arc::new(AtomicUsize::new(0));
+        let slots: Vec<_> = (0..MAX_CONCURRENT_CONNECTIONS)
+            .map(|_| ConnectionSlot::acquire(&active_connections).unwrap())
+            .collect();
+        assert!(ConnectionSlot::acquire(&active_connections).is_none());
+
+        drop(slots);
+        assert_eq!(active_connections.load(Ordering::SeqCst), 0);
+        assert!(ConnectionSlot::acquire(&active_connections).is_some());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::guest::server::DEFAULT_GUEST_PORT;
+use crate::protocol::{parse_response, GuestCommand, GuestOSHealth, Payload};
+use regex::Regex;
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::path::Path;
+use vsock::VsockStream;
+
+// the hard-coded domain name defined in the xml file for starting guestOS in virsh
+const DOMAIN_NAME: &str = "guestos";
+
+/// Query the GuestOS health over the vsock.
+pub fn query_guestos_health() -> Result<GuestOSHealth, String> {
+    let guest_cid = get_guest_cid()?;
+
+    let mut stream = VsockStream::connect_with_cid_port(guest_cid, DEFAULT_GUEST_PORT)
+        .map_err(|err| format!("Could not connect to the guest: {}", err))?;
+    stream
+        .set_write_timeout(Some(std::time::Duration::from_secs(10)))
+        .map_err(|err| err.to_string())?;
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_secs(10)))
+        .map_err(|err| err.to_string())?;
+
+    let json_command =
+        serde_json::to_string(&GuestCommand::GetGuestOSHealth).map_err(|err| err.to_string())?;
+    stream
+        .write_all(json_command.as_bytes())
+        .map_err(|err| format!("Could not send command to the guest: {}", err))?;
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|err| format!("Could not read response from the guest: {}", err))?;
+
+    match parse_response(&response)? {
+        Payload::GuestOSHealth(health) => Ok(health),
+        payload => Err(format!("Unexpected response from the guest: {}", payload)),
+    }
+}
+
+// The guest CID is assigned by libvirt when the virtual machine starts.
+fn get_guest_cid() -> Result<u32, String> {
+    let output = std::process::Command::new("virsh")
+        .args(["dumpxml", DOMAIN_NAME])
+        .output()
+        .map_err(|err| format!("Unable to run virsh: {}", err))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Unable to get the {} domain: {}",
+            DOMAIN_NAME,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+
+    parse_guest_cid(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| format!("The {} domain has no vsock cid", DOMAIN_NAME))
+}
+
+fn parse_guest_cid(domain_xml: &str) -> Option<u32> {
+    let regex = Regex::new(r#"<cid [^>]*address=['"](\d+)['"]"#).ok()?;
+    regex.captures(domain_xml)?[1].parse().ok()
+}
+
+/// Render the result of a health query as metrics in the Prometheus text format.
+pub fn to_p8s_metrics_string(health: &Result<GuestOSHealth, String>) -> String {
+    let mut metrics = String::new();
+    let mut write_metric = |name: &str, help: &str, samples: Vec<(String, u64)>| {
+        let _ = writeln!(metrics, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+        for (labels, value) in samples {
+            let _ = writeln!(metrics, "{}{} {}", name, labels, value);
+        }
+    };
+
+    write_metric(
+        "hostos_guestos_health_query_success",
+        "Whether the last GuestOS health query over the vsock succeeded",
+        vec![(String::new(), health.is_ok() as u64)],
+    );
+    let Ok(health) = health else {
+        return metrics;
+    };
+
+    write_metric(
+        "hostos_guestos_replica_active",
+        "Whether the ic-replica service in the GuestOS is active",
+        vec![(String::new(), (health.replica_status == "active") as u64)],
+    );
+    if let Some(height) = health.finalized_height {
+        write_metric(
+            "hostos_guestos_finalized_height",
+            "The highest finalized height reported by the GuestOS replica",
+            vec![(String::new(), height)],
+        );
+    }
+    if let Some(height) = health.last_cup_height {
+        write_metric(
+            "hostos_guestos_last_cup_height",
+            "The height of the latest CUP reported by the GuestOS replica",
+            vec![(String::new(), height)],
+        );
+    }
+    let mount_point_label = |mount_point: &str| format!("{{mount_point=\"{}\"}}", mount_point);
+    write_metric(
+        "hostos_guestos_disk_used_bytes",
+        "The used space of GuestOS file systems, in bytes",
+        health
+            .disk_usage
+            .iter()
+            .map(|usage| (mount_point_label(&usage.mount_point), usage.used_bytes))
+            .collect(),
+    );
+    write_metric(
+        "hostos_guestos_disk_total_bytes",
+        "The size of GuestOS file systems, in bytes",
+        health
+            .disk_usage
+            .iter()
+            .map(|usage| (mount_point_label(&usage.mount_point), usage.total_bytes))
+            .collect(),
+    );
+
+    metrics
+}
+
+/// Write the metrics through a temporary file, so that node_exporter never reads a partial file.
+pub fn write_metrics(health: &Result<GuestOSHealth, String>, path: &Path) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, to_p8s_metrics_string(health))?;
+    std::fs::rename(tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::DiskUsage;
+
+    #[test]
+    fn test_parse_guest_cid() {
+        let domain_xml = r#"
+    <vsock model='virtio'>
+      <cid auto='yes' address='3'/>
+      <alias name='vsock0'/>
+    </vsock>"#;
+        assert_eq!(parse_guest_cid(domain_xml), Some(3));
+        assert_eq!(parse_guest_cid("<cid auto='yes'/>"), None);
+    }
+
+    #[test]
+    fn test_to_p8s_metrics_string() {
+        let health = GuestOSHealth {
+            replica_status: "active".to_string(),
+            finalized_height: Some(1200),
+            last_cup_height: None,
+            disk_usage: vec![DiskUsage {
+                mount_point: "/var/lib/ic/data".to_string(),
+                used_bytes: 1024,
+                total_bytes: 4096,
+            }],
+        };
+        let metrics = to_p8s_metrics_string(&Ok(health));
+        assert!(metrics.contains("hostos_guestos_health_query_success 1\n"));
+        assert!(metrics.contains("hostos_guestos_replica_active 1\n"));
+        assert!(metrics.contains("hostos_guestos_finalized_height 1200\n"));
+        assert!(!metrics.contains("hostos_guestos_last_cup_height"));
+        assert!(metrics
+            .contains("hostos_guestos_disk_used_bytes{mount_point=\"/var/lib/ic/data\"} 1024\n"));
+
+        let metrics = to_p8s_metrics_string(&Err("unreachable".to_string()));
+        assert!(metrics.contains("hostos_guestos_health_query_success 0\n"));
+        assert!(!metrics.contains("hostos_guestos_replica_active"));
+    }
+}
@@ -1,4 +1,5 @@
 mod agent;
 mod command_utilities;
+pub(crate) mod guestos_health;
 mod hsm;
 pub(crate) mod server;
@@ -2,8 +2,10 @@
 
 mod guest;
 pub use guest::send_command;
+pub use guest::server::run_guest_server;
 
 mod host;
+pub use host::guestos_health::{query_guestos_health, write_metrics};
 pub use host::server::run_server;
 
 pub mod protocol;
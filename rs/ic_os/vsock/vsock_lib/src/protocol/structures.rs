@@ -6,6 +6,7 @@ pub type Response = Result<Payload, String>;
 pub enum Payload {
     HostOSVsockVersion(HostOSVsockVersion),
     HostOSVersion(String),
+    GuestOSHealth(GuestOSHealth),
     NoPayload,
 }
 
@@ -14,6 +15,7 @@ impl fmt::Display for Payload {
         match self {
             Payload::HostOSVsockVersion(version) => write!(f, "HostOSVsockVersion({})", version),
             Payload::HostOSVersion(version) => write!(f, "HostOSVersion({})", version),
+            Payload::GuestOSHealth(health) => write!(f, "GuestOSHealth({})", health),
             Payload::NoPayload => write!(f, "NoPayload"),
         }
     }
@@ -98,3 +100,65 @@ pub struct NotifyData {
     pub count: u32,
     pub message: String,
 }
+
+/// All commands that can be sent to the Guest server. Only the HostOS may send them.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub enum GuestCommand {
+    GetGuestOSHealth,
+}
+
+impl fmt::Display for GuestCommand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GuestCommand::GetGuestOSHealth => write!(f, "Command: Get GuestOS Health"),
+        }
+    }
+}
+
+/// A snapshot of the GuestOS health. Values that could not be collected are left empty, so that
+/// a partially broken GuestOS still reports everything else.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Default)]
+pub struct GuestOSHealth {
+    /// The state of the ic-replica service as reported by systemd, e.g., "active" or "failed".
+    pub replica_status: String,
+    pub finalized_height: Option<u64>,
+    pub last_cup_height: Option<u64>,
+    pub disk_usage: Vec<DiskUsage>,
+}
+
+impl fmt::Display for GuestOSHealth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let display_height = |height: Option<u64>| match height {
+            Some(height) => height.to_string(),
+            None => "unknown".to_string(),
+        };
+        write!(
+            f,
+            "Replica status: {}\nFinalized height: {}\nLast CUP height: {}",
+            self.replica_status,
+            display_height(self.finalized_height),
+            display_height(self.last_cup_height)
+        )?;
+        for disk_usage in &self.disk_usage {
+            write!(f, "\nDisk usage: {}", disk_usage)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub mount_point: String,
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl fmt::Display for DiskUsage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} of {} bytes used",
+            self.mount_point, self.used_bytes, self.total_bytes
+        )
+    }
+}
@@ -14,6 +14,12 @@ pub fn parse_request(json_str: &str) -> Result<Request, String> {
         .map_err(|error| format!("Unable to parse guest request: {}: {}", json_str, error))
 }
 
+/// Parse a command sent by the host in a json string to a `GuestCommand`.
+pub fn parse_guest_command(json_str: &str) -> Result<GuestCommand, String> {
+    serde_json::from_str::<GuestCommand>(json_str)
+        .map_err(|error| format!("Unable to parse host command: {}: {}", json_str, error))
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -214,4 +220,27 @@ pub mod tests {
         let response = parse_response(json_str);
         assert!(response.is_err());
     }
+
+    #[test]
+    fn test_guestos_health_serialization() {
+        assert_eq!(
+            Ok(GuestCommand::GetGuestOSHealth),
+            parse_guest_command(r#""GetGuestOSHealth""#)
+        );
+        assert!(parse_guest_command(r#""attach-hsm""#).is_err());
+
+        let health = GuestOSHealth {
+            replica_status: "active".to_string(),
+            finalized_height: Some(1200),
+            last_cup_height: None,
+            disk_usage: vec![DiskUsage {
+                mount_point: "/var/lib/ic/data".to_string(),
+                used_bytes: 1024,
+                total_bytes: 4096,
+            }],
+        };
+        let response: Response = Ok(Payload::GuestOSHealth(health));
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(response, parse_response(&json));
+    }
 }
@@ -20,6 +20,11 @@ pub enum InvalidQueryStatsPayloadReason {
     },
     /// Stats for a [`CanisterId`] have been send twice
     DuplicateCanisterId(CanisterId),
+    /// Stats for a [`CanisterId`] contain more methods than allowed
+    TooManyMethods {
+        canister_id: CanisterId,
+        num_methods: usize,
+    },
 }
 
 #[derive(Debug)]
@@ -4,12 +4,17 @@ use dfn_core::{api::PrincipalId, call, CanisterId};
 use dfn_protobuf::protobuf;
 use ic_crypto_sha2::Sha256;
 use ic_ledger_core::block::BlockIndex;
+use ic_nervous_system_runtime::{DfnRuntime, Runtime};
 use icp_ledger::{
     tokens_from_proto, AccountBalanceArgs, AccountIdentifier, Memo, SendArgs,
     Subaccount as IcpSubaccount, Tokens, TotalSupplyArgs,
 };
-use icrc_ledger_types::icrc1::account::{Account, Subaccount};
+use icrc_ledger_types::icrc1::{
+    account::{Account, Subaccount},
+    transfer::{Memo as IcrcMemo, NumTokens, TransferArg, TransferError},
+};
 use mockall::automock;
+use num_traits::ToPrimitive;
 
 pub struct IcpLedgerCanister {
     id: CanisterId,
@@ -38,6 +43,23 @@ pub trait ICRC1Ledger: Send + Sync {
         memo: u64,
     ) -> Result<BlockIndex, NervousSystemError>;
 
+    /// Transfers funds like `transfer_funds`, but sets the transfer's `created_at_time`, so
+    /// that the ledger deduplicates retries of the same transfer. If the transfer was already
+    /// recorded, returns the block height of the original transfer.
+    async fn transfer_funds_deduplicated(
+        &self,
+        _amount_e8s: u64,
+        _fee_e8s: u64,
+        _from_subaccount: Option<Subaccount>,
+        _to: Account,
+        _memo: u64,
+        _created_at_time_nanos: u64,
+    ) -> Result<BlockIndex, NervousSystemError> {
+        Err(NervousSystemError::new_with_message(
+            "This ledger does not support deduplicated transfers.",
+        ))
+    }
+
     /// Gets the total supply of tokens from the sum of all accounts except for the
     /// minting canister's.
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError>;
@@ -105,6 +127,51 @@ impl ICRC1Ledger for IcpLedgerCanister {
         .await
     }
 
+    async fn transfer_funds_deduplicated(
+        &self,
+        amount_e8s: u64,
+        fee_e8s: u64,
+        from_subaccount: Option<Subaccount>,
+        to: Account,
+        memo: u64,
+        created_at_time_nanos: u64,
+    ) -> Result<BlockIndex, NervousSystemError> {
+        // Unlike send_pb, icrc1_transfer reports duplicates with the index of the original
+        // transfer.
+        let args = TransferArg {
+            from_subaccount,
+            to,
+            fee: Some(NumTokens::from(fee_e8s)),
+            created_at_time: Some(created_at_time_nanos),
+            memo: Some(IcrcMemo::from(memo)),
+            amount: NumTokens::from(amount_e8s),
+        };
+        let result: Result<(Result<NumTokens, TransferError>,), (i32, String)> =
+            DfnRuntime::call_with_cleanup(self.id, "icrc1_transfer", (args,)).await;
+        let block_index = match result {
+            Ok((Ok(block_index),)) => block_index,
+            Ok((Err(TransferError::Duplicate { duplicate_of }),)) => duplicate_of,
+            Ok((Err(err),)) => {
+                return Err(NervousSystemError::new_with_message(format!(
+                    "'icrc1_transfer' of the ledger canister failed. Error: {:?}",
+                    err
+                )))
+            }
+            Err((code, msg)) => {
+                return Err(NervousSystemError::new_with_message(format!(
+                    "Error calling method 'icrc1_transfer' of the ledger canister. Code: {:?}. Message: {}",
+                    code, msg
+                )))
+            }
+        };
+        block_index.0.to_u64().ok_or_else(|| {
+            NervousSystemError::new_with_message(format!(
+                "Block index {} does not fit into u64.",
+                block_index
+            ))
+        })
+    }
+
     async fn total_supply(&self) -> Result<Tokens, NervousSystemError> {
         <IcpLedgerCanister as IcpLedger>::total_supply(self).await
     }
@@ -110,6 +110,7 @@ impl From<LogVisibilityV2> for LogVisibility {
 ///     log_visibility : opt log_visibility;
 ///     wasm_memory_limit: opt nat;
 ///     wasm_memory_threshold: opt nat;
+///     low_cycles_threshold_multiplier: opt nat;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
 pub struct CanisterSettingsArgs {
@@ -121,6 +122,7 @@ pub struct CanisterSettingsArgs {
     pub log_visibility: Option<LogVisibility>,
     pub wasm_memory_limit: Option<candid::Nat>,
     pub wasm_memory_threshold: Option<candid::Nat>,
+    pub low_cycles_threshold_multiplier: Option<candid::Nat>,
 }
 
 impl From<CanisterSettingsArgs> for Ic00CanisterSettingsArgs {
@@ -134,6 +136,7 @@ impl From<CanisterSettingsArgs> for Ic00CanisterSettingsArgs {
             log_visibility: settings.log_visibility.map(LogVisibilityV2::from),
             wasm_memory_limit: settings.wasm_memory_limit,
             wasm_memory_threshold: settings.wasm_memory_threshold,
+            low_cycles_threshold_multiplier: settings.low_cycles_threshold_multiplier,
         }
     }
 }
@@ -149,6 +152,7 @@ impl From<Ic00CanisterSettingsArgs> for CanisterSettingsArgs {
             log_visibility: settings.log_visibility.map(LogVisibility::from),
             wasm_memory_limit: settings.wasm_memory_limit,
             wasm_memory_threshold: settings.wasm_memory_threshold,
+            low_cycles_threshold_multiplier: settings.low_cycles_threshold_multiplier,
         }
     }
 }
@@ -3191,6 +3191,33 @@ pub struct MonthlyNodeProviderRewards {
     /// The list of node_provieders at the time when the rewards were calculated.
     #[prost(message, repeated, tag = "7")]
     pub node_providers: ::prost::alloc::vec::Vec<NodeProvider>,
+    /// The rewards of each node, as computed by the Registry. Only set if the rewards were reduced
+    /// according to the block failure rates of the nodes.
+    #[prost(message, repeated, tag = "8")]
+    pub rewards_per_node: ::prost::alloc::vec::Vec<NodeRewardsBreakdown>,
+}
+/// The performance-adjusted rewards of a single node, in 10,000ths of an XDR.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NodeRewardsBreakdown {
+    #[prost(message, optional, tag = "1")]
+    pub node_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(message, optional, tag = "2")]
+    pub node_operator_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    #[prost(message, optional, tag = "3")]
+    pub node_provider_id: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// The node's share of the rewards of its Node Operator.
+    #[prost(uint64, tag = "4")]
+    pub base_xdr_permyriad: u64,
+    /// Not set if the node was not a block maker in the reward period.
+    #[prost(uint64, optional, tag = "5")]
+    pub failure_rate_permyriad: ::core::option::Option<u64>,
+    /// By how much the rewards were reduced, in 10,000ths.
+    #[prost(uint64, tag = "6")]
+    pub reduction_permyriad: u64,
+    #[prost(uint64, tag = "7")]
+    pub xdr_permyriad: u64,
 }
 /// TODO(NNS1-1589): Until the Jira ticket gets solved, changes here need to be
 /// manually propagated to (sns) swap.proto.
@@ -10,6 +10,7 @@ use crate::{
     heap_governance_data::{
         reassemble_governance_proto, split_governance_proto, HeapGovernanceData, XdrConversionRate,
     },
+    is_performance_based_node_provider_rewards_enabled,
     migrations::maybe_run_migrations,
     neuron::{DissolveStateAndAge, Neuron, NeuronBuilder},
     neuron_data_validation::{NeuronDataValidationSummary, NeuronDataValidator},
@@ -56,9 +57,9 @@ use crate::{
         NeuronState, NeuronsFundAuditInfo, NeuronsFundData,
         NeuronsFundEconomics as NeuronsFundNetworkEconomicsPb,
         NeuronsFundParticipation as NeuronsFundParticipationPb,
-        NeuronsFundSnapshot as NeuronsFundSnapshotPb, NnsFunction, NodeProvider, Proposal,
-        ProposalData, ProposalInfo, ProposalRewardStatus, ProposalStatus, RestoreAgingSummary,
-        RewardEvent, RewardNodeProvider, RewardNodeProviders,
+        NeuronsFundSnapshot as NeuronsFundSnapshotPb, NnsFunction, NodeProvider,
+        NodeRewardsBreakdown, Proposal, ProposalData, ProposalInfo, ProposalRewardStatus,
+        ProposalStatus, RestoreAgingSummary, RewardEvent, RewardNodeProvider, RewardNodeProviders,
         SettleNeuronsFundParticipationRequest, SettleNeuronsFundParticipationResponse,
         StopOrStartCanister, Tally, Topic, UpdateCanisterSettings, UpdateNodeProvider, Visibility,
         Vote, WaitForQuietState, XdrConversionRate as XdrConversionRatePb,
@@ -94,6 +95,7 @@ use ic_nns_constants::{
 };
 use ic_nns_governance_api::subnet_rental::SubnetRentalRequest;
 use ic_protobuf::registry::dc::v1::AddOrRemoveDataCentersProposalPayload;
+use ic_registry_canister_api::{GetNodeRewardsBreakdownRequest, GetNodeRewardsBreakdownResponse};
 use ic_sns_init::pb::v1::SnsInitPayload;
 use ic_sns_swap::pb::v1::{self as sns_swap_pb, Lifecycle, NeuronsFundParticipationConstraints};
 use ic_sns_wasm::pb::v1::{
@@ -107,7 +109,16 @@ use itertools::Itertools;
 use maplit::hashmap;
 use mockall::automock;
 use registry_canister::{
-    mutations::do_add_node_operator::AddNodeOperatorPayload, pb::v1::NodeProvidersMonthlyXdrRewards,
+    dry_run::{DryRunRequest, DryRunResponse},
+    mutations::{
+        do_add_node_operator::AddNodeOperatorPayload,
+        do_add_nodes_to_subnet::AddNodesToSubnetPayload,
+        do_change_subnet_membership::ChangeSubnetMembershipPayload,
+        do_create_subnet::CreateSubnetPayload,
+        do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
+        do_update_subnet::UpdateSubnetPayload,
+    },
+    pb::v1::NodeProvidersMonthlyXdrRewards,
 };
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
@@ -5085,6 +5096,54 @@ impl Governance {
         Ok(())
     }
 
+    /// Dry-runs proposals that change subnets against the latest registry
+    /// state, so that proposals the registry would reject on execution, e.g.
+    /// because they violate registry invariants, are already rejected when
+    /// they are submitted.
+    async fn validate_proposal_against_registry(
+        &mut self,
+        proposal: &Proposal,
+    ) -> Result<(), GovernanceError> {
+        let Some(Action::ExecuteNnsFunction(execute_nns_function)) = &proposal.action else {
+            return Ok(());
+        };
+        let Some(request) = registry_dry_run_request(execute_nns_function)? else {
+            return Ok(());
+        };
+
+        let response = self
+            .env
+            .call_canister_method(REGISTRY_CANISTER_ID, "dry_run", Encode!(&request).unwrap())
+            .await
+            .map_err(|(code, msg)| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Error calling dry_run on the registry: code: {:?}, message: {}",
+                        code, msg
+                    ),
+                )
+            })?;
+        let response = Decode!(&response, DryRunResponse).map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Cannot decode return type from dry_run. Error: {}", err),
+            )
+        })?;
+
+        if !response.is_ok() {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                format!(
+                    "The proposal would be rejected by the registry. Mutation type errors: {:?}, \
+                     invariant violations: {:?}",
+                    response.mutation_type_errors, response.invariant_violations
+                ),
+            ));
+        }
+        Ok(())
+    }
+
     fn validate_icp_xdr_conversion_rate_payload(
         payload: &[u8],
         minimum_icp_xdr_rate: u64,
@@ -5238,22 +5297,18 @@ impl Governance {
             .collect()
     }
 
-    pub fn make_proposal(
-        &mut self,
+    /// Checks that `caller` may submit `proposal` on behalf of the neuron
+    /// `proposer_id`, i.e., that it is the neuron's controller or a
+    /// registered hot key, and that the neuron's minted stake covers the
+    /// proposal submission fee. Returns the neuron's dissolve delay.
+    fn check_proposer_can_submit(
+        &self,
         proposer_id: &NeuronId,
         caller: &PrincipalId,
         proposal: &Proposal,
-    ) -> Result<ProposalId, GovernanceError> {
-        let topic = proposal.topic();
+    ) -> Result<u64, GovernanceError> {
         let now_seconds = self.env.now();
 
-        // Validate proposal
-        let action = self.validate_proposal(proposal)?;
-
-        // Before actually modifying anything, we first make sure that
-        // the neuron is allowed to make this proposal and create the
-        // electoral roll.
-        //
         // Find the proposing neuron.
         let (
             is_proposer_authorized_to_vote,
@@ -5278,8 +5333,6 @@ impl Governance {
 
         let proposal_submission_fee = self.proposal_submission_fee(proposal)?;
 
-        let reject_cost_e8s = self.reject_cost_e8s(proposal)?;
-
         // If the current stake of this neuron is less than the cost
         // of having a proposal rejected, the neuron cannot make the proposal -
         // because the proposal may be rejected.
@@ -5293,6 +5346,29 @@ impl Governance {
             ));
         }
 
+        Ok(proposer_dissolve_delay_seconds)
+    }
+
+    pub fn make_proposal(
+        &mut self,
+        proposer_id: &NeuronId,
+        caller: &PrincipalId,
+        proposal: &Proposal,
+    ) -> Result<ProposalId, GovernanceError> {
+        let topic = proposal.topic();
+        let now_seconds = self.env.now();
+
+        // Validate proposal
+        let action = self.validate_proposal(proposal)?;
+
+        // Before actually modifying anything, we first make sure that
+        // the neuron is allowed to make this proposal and create the
+        // electoral roll.
+        let proposer_dissolve_delay_seconds =
+            self.check_proposer_can_submit(proposer_id, caller, proposal)?;
+
+        let reject_cost_e8s = self.reject_cost_e8s(proposal)?;
+
         let min_dissolve_delay_seconds_to_vote = if let Action::ManageNeuron(_) = action {
             0
         } else {
@@ -6310,6 +6386,12 @@ impl Governance {
                 .follow(&id, caller, f)
                 .map(|_| ManageNeuronResponse::follow_response()),
             Some(Command::MakeProposal(p)) => {
+                // Only dry-run proposals against the registry once the proposer
+                // is known to be allowed to submit them, so that arbitrary callers
+                // cannot make governance call the registry. `make_proposal` checks
+                // everything again, as the neuron may change during the call.
+                self.check_proposer_can_submit(&id, caller, p)?;
+                self.validate_proposal_against_registry(p).await?;
                 self.make_proposal(&id, caller, p).map(|proposal_id| {
                     ManageNeuronResponse::make_proposal_response(
                         proposal_id,
@@ -7636,14 +7718,22 @@ impl Governance {
     /// Registry, then fetches the average XDR to ICP conversion rate for
     /// the last 30 days, then applies this conversion rate to convert each
     /// node provider's XDR rewards to ICP.
+    ///
+    /// If performance based rewards are enabled, the XDR rewards are reduced
+    /// according to the block failure rates of the nodes since the last
+    /// monthly node provider rewards.
     pub async fn get_monthly_node_provider_rewards(
         &mut self,
     ) -> Result<MonthlyNodeProviderRewards, GovernanceError> {
         let mut rewards = vec![];
 
         // Maps node providers to their rewards in XDR
-        let xdr_permyriad_rewards: NodeProvidersMonthlyXdrRewards =
-            self.get_node_providers_monthly_xdr_rewards().await?;
+        let (xdr_permyriad_rewards, rewards_per_node): (NodeProvidersMonthlyXdrRewards, _) =
+            if is_performance_based_node_provider_rewards_enabled() {
+                self.get_node_rewards_breakdown().await?
+            } else {
+                (self.get_node_providers_monthly_xdr_rewards().await?, vec![])
+            };
 
         // The average (last 30 days) conversion rate from 10,000ths of an XDR to 1 ICP
         let icp_xdr_conversion_rate = self.get_average_icp_xdr_conversion_rate().await?.data;
@@ -7690,6 +7780,7 @@ impl Governance {
             maximum_node_provider_rewards_e8s: Some(maximum_node_provider_rewards_e8s),
             registry_version: Some(registry_version),
             node_providers: self.heap_data.node_providers.clone(),
+            rewards_per_node,
         })
     }
 
@@ -7727,6 +7818,93 @@ impl Governance {
             .map_err(|msg| GovernanceError::new_with_message(ErrorType::External, msg))
     }
 
+    /// A helper for the Registry's get_node_rewards_breakdown method
+    ///
+    /// The reward period starts with the most recent monthly node provider
+    /// rewards. The Registry uses the day before that as the baseline of the
+    /// node metrics.
+    ///
+    /// Returns the rewards per node provider along with the rewards of each
+    /// node, which are kept in the monthly node provider rewards so that node
+    /// providers can see why their rewards were reduced.
+    async fn get_node_rewards_breakdown(
+        &mut self,
+    ) -> Result<(NodeProvidersMonthlyXdrRewards, Vec<NodeRewardsBreakdown>), GovernanceError> {
+        let reward_period_start_seconds =
+            match &self.heap_data.most_recent_monthly_node_provider_rewards {
+                Some(recent_rewards) => recent_rewards.timestamp,
+                None => self
+                    .env
+                    .now()
+                    .saturating_sub(NODE_PROVIDER_REWARD_PERIOD_SECONDS),
+            };
+        let request = GetNodeRewardsBreakdownRequest {
+            from_timestamp_nanos: reward_period_start_seconds
+                .saturating_sub(ONE_DAY_SECONDS)
+                .saturating_mul(1_000_000_000),
+        };
+
+        let registry_response: Vec<u8> = self
+            .env
+            .call_canister_method(
+                REGISTRY_CANISTER_ID,
+                "get_node_rewards_breakdown",
+                Encode!(&request).unwrap(),
+            )
+            .await
+            .map_err(|(code, msg)| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!(
+                        "Error calling 'get_node_rewards_breakdown': code: {:?}, message: {}",
+                        code, msg
+                    ),
+                )
+            })?;
+
+        let breakdown = Decode!(
+            [decoder_config()];
+            &registry_response,
+            Result<GetNodeRewardsBreakdownResponse, String>
+        )
+        .map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!(
+                    "Cannot decode return type from 'get_node_rewards_breakdown'. Error: {}",
+                    err,
+                ),
+            )
+        })?
+        .map_err(|msg| GovernanceError::new_with_message(ErrorType::External, msg))?;
+
+        let rewards_per_node = breakdown
+            .rewards_per_node
+            .into_iter()
+            .map(|node| NodeRewardsBreakdown {
+                node_id: Some(node.node_id),
+                node_operator_id: Some(node.node_operator_id),
+                node_provider_id: Some(node.node_provider_id),
+                base_xdr_permyriad: node.base_xdr_permyriad,
+                failure_rate_permyriad: node.failure_rate_permyriad,
+                reduction_permyriad: node.reduction_permyriad,
+                xdr_permyriad: node.xdr_permyriad,
+            })
+            .collect();
+        let rewards_per_node_provider = NodeProvidersMonthlyXdrRewards {
+            rewards: breakdown
+                .rewards_per_node_provider
+                .into_iter()
+                .map(|(node_provider_id, xdr_permyriad)| {
+                    (node_provider_id.to_string(), xdr_permyriad)
+                })
+                .collect(),
+            registry_version: Some(breakdown.registry_version),
+        };
+
+        Ok((rewards_per_node_provider, rewards_per_node))
+    }
+
     /// A helper for the CMC's get_average_icp_xdr_conversion_rate method
     async fn get_average_icp_xdr_conversion_rate(
         &mut self,
@@ -8007,6 +8185,43 @@ async fn call_deploy_new_sns(
     }
 }
 
+/// Returns the registry dry run request of an `ExecuteNnsFunction` proposal
+/// that changes subnets, or `None` if the NNS function is not dry-run.
+fn registry_dry_run_request(
+    execute_nns_function: &ExecuteNnsFunction,
+) -> Result<Option<DryRunRequest>, GovernanceError> {
+    let payload = &execute_nns_function.payload;
+    let invalid_payload = |err: candid::Error| {
+        GovernanceError::new_with_message(
+            ErrorType::InvalidProposal,
+            format!("The payload could not be decoded: {}", err),
+        )
+    };
+
+    let request = match NnsFunction::try_from(execute_nns_function.nns_function) {
+        Ok(NnsFunction::CreateSubnet) => DryRunRequest::CreateSubnet(
+            Decode!([decoder_config()]; payload, CreateSubnetPayload).map_err(invalid_payload)?,
+        ),
+        Ok(NnsFunction::UpdateConfigOfSubnet) => DryRunRequest::UpdateSubnet(
+            Decode!([decoder_config()]; payload, UpdateSubnetPayload).map_err(invalid_payload)?,
+        ),
+        Ok(NnsFunction::AddNodeToSubnet) => DryRunRequest::AddNodesToSubnet(
+            Decode!([decoder_config()]; payload, AddNodesToSubnetPayload)
+                .map_err(invalid_payload)?,
+        ),
+        Ok(NnsFunction::RemoveNodesFromSubnet) => DryRunRequest::RemoveNodesFromSubnet(
+            Decode!([decoder_config()]; payload, RemoveNodesFromSubnetPayload)
+                .map_err(invalid_payload)?,
+        ),
+        Ok(NnsFunction::ChangeSubnetMembership) => DryRunRequest::ChangeSubnetMembership(
+            Decode!([decoder_config()]; payload, ChangeSubnetMembershipPayload)
+                .map_err(invalid_payload)?,
+        ),
+        _ => return Ok(None),
+    };
+    Ok(Some(request))
+}
+
 /// Validates the user submitted proposal fields.
 pub fn validate_user_submitted_proposal_fields(proposal: &Proposal) -> Result<(), String> {
     validate_proposal_title(&proposal.title)?;
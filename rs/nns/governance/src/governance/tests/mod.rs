@@ -6,9 +6,11 @@ use crate::{
         neuron::DissolveState,
         Neuron as NeuronProto,
     },
-    test_utils::{MockEnvironment, StubCMC, StubIcpLedger},
+    test_utils::{
+        ExpectedCallCanisterMethodCallArguments, MockEnvironment, StubCMC, StubIcpLedger,
+    },
 };
-use ic_base_types::PrincipalId;
+use ic_base_types::{NodeId, PrincipalId};
 use ic_nervous_system_common::{assert_is_err, assert_is_ok, E8};
 #[cfg(feature = "test")]
 use ic_nervous_system_proto::pb::v1::GlobalTimeOfDay;
@@ -1397,6 +1399,180 @@ fn can_spawn_neurons_only_true_when_not_spawning_and_neurons_ready_to_spawn() {
     assert!(governance.can_spawn_neurons());
 }
 
+#[tokio::test]
+async fn test_proposals_violating_registry_invariants_are_rejected() {
+    let payload = RemoveNodesFromSubnetPayload {
+        node_ids: vec![NodeId::from(PrincipalId::new_node_test_id(1))],
+    };
+    let dry_run_request = DryRunRequest::RemoveNodesFromSubnet(payload.clone());
+    let dry_run_response = DryRunResponse {
+        invariant_violations: vec!["some invariant is violated".to_string()],
+        ..Default::default()
+    };
+    let mut governance = Governance::new(
+        GovernanceProto {
+            economics: Some(NetworkEconomics::with_default_values()),
+            ..Default::default()
+        },
+        Box::new(MockEnvironment::new(
+            vec![(
+                ExpectedCallCanisterMethodCallArguments::new(
+                    REGISTRY_CANISTER_ID,
+                    "dry_run",
+                    Encode!(&dry_run_request).unwrap(),
+                ),
+                Ok(Encode!(&dry_run_response).unwrap()),
+            )],
+            100,
+        )),
+        Box::new(StubIcpLedger {}),
+        Box::new(StubCMC {}),
+    );
+    let proposal = Proposal {
+        title: Some("Remove nodes from subnet".to_string()),
+        action: Some(Action::ExecuteNnsFunction(ExecuteNnsFunction {
+            nns_function: NnsFunction::RemoveNodesFromSubnet as i32,
+            payload: Encode!(&payload).unwrap(),
+        })),
+        ..Default::default()
+    };
+
+    let error = governance
+        .validate_proposal_against_registry(&proposal)
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.error_type, ErrorType::InvalidProposal as i32);
+    assert!(
+        error.error_message.contains("some invariant is violated"),
+        "{}",
+        error.error_message
+    );
+}
+
+#[tokio::test]
+async fn test_proposals_not_changing_subnets_are_not_dry_run() {
+    let mut governance = Governance::new(
+        GovernanceProto {
+            economics: Some(NetworkEconomics::with_default_values()),
+            ..Default::default()
+        },
+        Box::new(MockEnvironment::new(vec![], 100)),
+        Box::new(StubIcpLedger {}),
+        Box::new(StubCMC {}),
+    );
+    let proposal = Proposal {
+        title: Some("Motion".to_string()),
+        action: Some(Action::Motion(Motion {
+            motion_text: "motion".to_string(),
+        })),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        governance
+            .validate_proposal_against_registry(&proposal)
+            .await,
+        Ok(())
+    );
+}
+
+#[tokio::test]
+async fn test_registry_errors_during_dry_run_are_external() {
+    let payload = RemoveNodesFromSubnetPayload {
+        node_ids: vec![NodeId::from(PrincipalId::new_node_test_id(1))],
+    };
+    let mut governance = Governance::new(
+        GovernanceProto {
+            economics: Some(NetworkEconomics::with_default_values()),
+            ..Default::default()
+        },
+        Box::new(MockEnvironment::new(
+            vec![(
+                ExpectedCallCanisterMethodCallArguments::new(
+                    REGISTRY_CANISTER_ID,
+                    "dry_run",
+                    Encode!(&DryRunRequest::RemoveNodesFromSubnet(payload.clone())).unwrap(),
+                ),
+                Err((None, "registry is unavailable".to_string())),
+            )],
+            100,
+        )),
+        Box::new(StubIcpLedger {}),
+        Box::new(StubCMC {}),
+    );
+    let proposal = Proposal {
+        title: Some("Remove nodes from subnet".to_string()),
+        action: Some(Action::ExecuteNnsFunction(ExecuteNnsFunction {
+            nns_function: NnsFunction::RemoveNodesFromSubnet as i32,
+            payload: Encode!(&payload).unwrap(),
+        })),
+        ..Default::default()
+    };
+
+    let error = governance
+        .validate_proposal_against_registry(&proposal)
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.error_type, ErrorType::External as i32);
+}
+
+#[tokio::test]
+async fn test_proposals_of_unauthorized_callers_are_not_dry_run() {
+    // There are no expected canister calls, so a dry run would panic.
+    let mut governance = Governance::new(
+        GovernanceProto {
+            economics: Some(NetworkEconomics::with_default_values()),
+            ..Default::default()
+        },
+        Box::new(MockEnvironment::new(vec![], 100)),
+        Box::new(StubIcpLedger {}),
+        Box::new(StubCMC {}),
+    );
+    governance
+        .neuron_store
+        .add_neuron(
+            NeuronBuilder::new(
+                NeuronId { id: 1 },
+                Subaccount::try_from(vec![0u8; 32].as_slice()).unwrap(),
+                PrincipalId::new_user_test_id(1),
+                DissolveStateAndAge::NotDissolving {
+                    dissolve_delay_seconds: MIN_DISSOLVE_DELAY_FOR_VOTE_ELIGIBILITY_SECONDS,
+                    aging_since_timestamp_seconds: 1,
+                },
+                100 * E8,
+            )
+            .build(),
+        )
+        .unwrap();
+    let payload = RemoveNodesFromSubnetPayload {
+        node_ids: vec![NodeId::from(PrincipalId::new_node_test_id(1))],
+    };
+    let proposal = Proposal {
+        title: Some("Remove nodes from subnet".to_string()),
+        action: Some(Action::ExecuteNnsFunction(ExecuteNnsFunction {
+            nns_function: NnsFunction::RemoveNodesFromSubnet as i32,
+            payload: Encode!(&payload).unwrap(),
+        })),
+        ..Default::default()
+    };
+
+    let error = governance
+        .manage_neuron_internal(
+            &PrincipalId::new_user_test_id(2),
+            &ManageNeuron {
+                id: Some(NeuronId { id: 1 }),
+                command: Some(Command::MakeProposal(Box::new(proposal))),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.error_type, ErrorType::NotAuthorized as i32);
+}
+
 #[test]
 fn test_validate_execute_nns_function() {
     let governance = Governance::new(
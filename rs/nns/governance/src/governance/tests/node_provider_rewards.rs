@@ -15,6 +15,7 @@ fn test_node_provider_rewards_read_from_correct_sources() {
         maximum_node_provider_rewards_e8s: None,
         registry_version: None,
         node_providers: vec![],
+        rewards_per_node: vec![],
     };
 
     let rewards_2 = MonthlyNodeProviderRewards {
@@ -25,6 +26,7 @@ fn test_node_provider_rewards_read_from_correct_sources() {
         maximum_node_provider_rewards_e8s: None,
         registry_version: None,
         node_providers: vec![],
+        rewards_per_node: vec![],
     };
 
     let mut governance = Governance::new(
@@ -64,6 +66,7 @@ fn test_list_node_provider_rewards_api() {
         maximum_node_provider_rewards_e8s: None,
         registry_version: None,
         node_providers: vec![],
+        rewards_per_node: vec![],
     };
 
     let rewards_2 = MonthlyNodeProviderRewards {
@@ -74,6 +77,7 @@ fn test_list_node_provider_rewards_api() {
         maximum_node_provider_rewards_e8s: None,
         registry_version: None,
         node_providers: vec![],
+        rewards_per_node: vec![],
     };
 
     let mut governance = Governance::new(
@@ -116,6 +120,7 @@ fn test_list_node_provider_rewards_api_with_paging_and_filters() {
             maximum_node_provider_rewards_e8s: None,
             registry_version: None,
             node_providers: vec![],
+            rewards_per_node: vec![],
         };
         governance.update_most_recent_monthly_node_provider_rewards(rewards.clone());
         rewards_minted.push(rewards);
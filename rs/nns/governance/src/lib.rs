@@ -188,6 +188,8 @@ thread_local! {
     static IS_PRIVATE_NEURON_ENFORCEMENT_ENABLED: Cell<bool> = const { Cell::new(cfg!(feature = "test")) };
 
     static ARE_SET_VISIBILITY_PROPOSALS_ENABLED: Cell<bool> = const { Cell::new(true) };
+
+    static IS_PERFORMANCE_BASED_NODE_PROVIDER_REWARDS_ENABLED: Cell<bool> = const { Cell::new(false) };
 }
 
 pub fn is_private_neuron_enforcement_enabled() -> bool {
@@ -218,6 +220,22 @@ pub fn temporarily_disable_set_visibility_proposals() -> Temporary {
     Temporary::new(&ARE_SET_VISIBILITY_PROPOSALS_ENABLED, false)
 }
 
+/// Whether the monthly node provider rewards are reduced according to the block
+/// failure rates of the nodes (see the Registry's get_node_rewards_breakdown).
+pub fn is_performance_based_node_provider_rewards_enabled() -> bool {
+    IS_PERFORMANCE_BASED_NODE_PROVIDER_REWARDS_ENABLED.with(|ok| ok.get())
+}
+
+/// Only integration tests should use this.
+pub fn temporarily_enable_performance_based_node_provider_rewards() -> Temporary {
+    Temporary::new(&IS_PERFORMANCE_BASED_NODE_PROVIDER_REWARDS_ENABLED, true)
+}
+
+/// Only integration tests should use this.
+pub fn temporarily_disable_performance_based_node_provider_rewards() -> Temporary {
+    Temporary::new(&IS_PERFORMANCE_BASED_NODE_PROVIDER_REWARDS_ENABLED, false)
+}
+
 pub fn decoder_config() -> DecoderConfig {
     let mut config = DecoderConfig::new();
     config.set_skipping_quota(DEFAULT_SKIPPING_QUOTA);
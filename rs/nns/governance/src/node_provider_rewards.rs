@@ -96,6 +96,7 @@ mod test {
             maximum_node_provider_rewards_e8s: None,
             registry_version: None,
             node_providers: vec![],
+            rewards_per_node: vec![],
         };
 
         let rewards_2 = MonthlyNodeProviderRewards {
@@ -106,6 +107,7 @@ mod test {
             maximum_node_provider_rewards_e8s: None,
             registry_version: None,
             node_providers: vec![],
+            rewards_per_node: vec![],
         };
 
         // Assert empty on start
@@ -3866,6 +3866,11 @@ impl From<pb::MonthlyNodeProviderRewards> for pb_api::MonthlyNodeProviderRewards
             maximum_node_provider_rewards_e8s: item.maximum_node_provider_rewards_e8s,
             registry_version: item.registry_version,
             node_providers: item.node_providers.into_iter().map(|x| x.into()).collect(),
+            rewards_per_node: item
+                .rewards_per_node
+                .into_iter()
+                .map(|x| x.into())
+                .collect(),
         }
     }
 }
@@ -3879,6 +3884,38 @@ impl From<pb_api::MonthlyNodeProviderRewards> for pb::MonthlyNodeProviderRewards
             maximum_node_provider_rewards_e8s: item.maximum_node_provider_rewards_e8s,
             registry_version: item.registry_version,
             node_providers: item.node_providers.into_iter().map(|x| x.into()).collect(),
+            rewards_per_node: item
+                .rewards_per_node
+                .into_iter()
+                .map(|x| x.into())
+                .collect(),
+        }
+    }
+}
+
+impl From<pb::NodeRewardsBreakdown> for pb_api::NodeRewardsBreakdown {
+    fn from(item: pb::NodeRewardsBreakdown) -> Self {
+        Self {
+            node_id: item.node_id,
+            node_operator_id: item.node_operator_id,
+            node_provider_id: item.node_provider_id,
+            base_xdr_permyriad: item.base_xdr_permyriad,
+            failure_rate_permyriad: item.failure_rate_permyriad,
+            reduction_permyriad: item.reduction_permyriad,
+            xdr_permyriad: item.xdr_permyriad,
+        }
+    }
+}
+impl From<pb_api::NodeRewardsBreakdown> for pb::NodeRewardsBreakdown {
+    fn from(item: pb_api::NodeRewardsBreakdown) -> Self {
+        Self {
+            node_id: item.node_id,
+            node_operator_id: item.node_operator_id,
+            node_provider_id: item.node_provider_id,
+            base_xdr_permyriad: item.base_xdr_permyriad,
+            failure_rate_permyriad: item.failure_rate_permyriad,
+            reduction_permyriad: item.reduction_permyriad,
+            xdr_permyriad: item.xdr_permyriad,
         }
     }
 }
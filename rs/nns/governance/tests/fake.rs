@@ -21,6 +21,9 @@ use ic_nns_governance::{
         NetworkEconomics, Neuron, NnsFunction, Proposal, Vote,
     },
 };
+use ic_registry_canister_api::{
+    GetNodeRewardsBreakdownRequest, GetNodeRewardsBreakdownResponse, NodeRewardsBreakdown,
+};
 use ic_sns_root::{GetSnsCanistersSummaryRequest, GetSnsCanistersSummaryResponse};
 use ic_sns_swap::pb::v1 as sns_swap_pb;
 use ic_sns_wasm::pb::v1::{DeployedSns, ListDeployedSnsesRequest, ListDeployedSnsesResponse};
@@ -39,6 +42,9 @@ use std::{
 
 const DEFAULT_TEST_START_TIMESTAMP_SECONDS: u64 = 999_111_000_u64;
 pub const NODE_PROVIDER_REWARD: u64 = 10_000;
+/// The reward of the node provider once it is reduced according to the block
+/// failure rates of its nodes.
+pub const PERFORMANCE_BASED_NODE_PROVIDER_REWARD: u64 = 7_000;
 
 lazy_static! {
     pub(crate) static ref SNS_ROOT_CANISTER_ID: PrincipalId = PrincipalId::new_user_test_id(213599);
@@ -461,6 +467,31 @@ impl Environment for FakeDriver {
             .unwrap());
         }
 
+        if method_name == "get_node_rewards_breakdown" {
+            assert_eq!(PrincipalId::from(target), REGISTRY_CANISTER_ID.get());
+            Decode!(&request, GetNodeRewardsBreakdownRequest).unwrap();
+
+            return Ok(Encode!(&Ok::<GetNodeRewardsBreakdownResponse, String>(
+                GetNodeRewardsBreakdownResponse {
+                    rewards_per_node_provider: vec![(
+                        PrincipalId::new_user_test_id(1),
+                        PERFORMANCE_BASED_NODE_PROVIDER_REWARD
+                    )],
+                    rewards_per_node: vec![NodeRewardsBreakdown {
+                        node_id: PrincipalId::new_node_test_id(1),
+                        node_operator_id: PrincipalId::new_user_test_id(2),
+                        node_provider_id: PrincipalId::new_user_test_id(1),
+                        base_xdr_permyriad: 2 * PERFORMANCE_BASED_NODE_PROVIDER_REWARD,
+                        failure_rate_permyriad: Some(10_000),
+                        reduction_permyriad: 5_000,
+                        xdr_permyriad: PERFORMANCE_BASED_NODE_PROVIDER_REWARD,
+                    }],
+                    registry_version: 5,
+                }
+            ))
+            .unwrap());
+        }
+
         if method_name == "get_average_icp_xdr_conversion_rate" {
             assert_eq!(PrincipalId::from(target), CYCLES_MINTING_CANISTER_ID.get());
 
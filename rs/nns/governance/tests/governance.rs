@@ -3,7 +3,8 @@
 //! complex/weird configurations of neurons and proposals against which several
 //! tests are run.
 use crate::fake::{
-    DAPP_CANISTER_ID, DEVELOPER_PRINCIPAL_ID, NODE_PROVIDER_REWARD, SNS_GOVERNANCE_CANISTER_ID,
+    DAPP_CANISTER_ID, DEVELOPER_PRINCIPAL_ID, NODE_PROVIDER_REWARD,
+    PERFORMANCE_BASED_NODE_PROVIDER_REWARD, SNS_GOVERNANCE_CANISTER_ID,
     SNS_LEDGER_ARCHIVE_CANISTER_ID, SNS_LEDGER_CANISTER_ID, SNS_LEDGER_INDEX_CANISTER_ID,
     SNS_ROOT_CANISTER_ID, TARGET_SWAP_CANISTER_ID,
 };
@@ -83,8 +84,8 @@ use ic_nns_governance::{
         KnownNeuronData, ListNeurons, ListProposalInfo, ListProposalInfoResponse, ManageNeuron,
         ManageNeuronResponse, MonthlyNodeProviderRewards, Motion, NetworkEconomics, Neuron,
         NeuronChange, NeuronState, NeuronType, NeuronsFundData, NeuronsFundParticipation,
-        NeuronsFundSnapshot, NnsFunction, NodeProvider, Proposal, ProposalChange, ProposalData,
-        ProposalDataChange,
+        NeuronsFundSnapshot, NnsFunction, NodeProvider, NodeRewardsBreakdown, Proposal,
+        ProposalChange, ProposalData, ProposalDataChange,
         ProposalRewardStatus::{self, AcceptVotes, ReadyToSettle},
         ProposalStatus::{self, Rejected},
         RewardEvent, RewardNodeProvider, RewardNodeProviders,
@@ -94,6 +95,7 @@ use ic_nns_governance::{
     },
     proposals::create_service_nervous_system::ExecutedCreateServiceNervousSystemProposal,
     temporarily_disable_private_neuron_enforcement, temporarily_disable_set_visibility_proposals,
+    temporarily_enable_performance_based_node_provider_rewards,
     temporarily_enable_private_neuron_enforcement, temporarily_enable_set_visibility_proposals,
 };
 use ic_nns_governance_init::GovernanceCanisterInitPayloadBuilder;
@@ -1484,6 +1486,65 @@ async fn test_minimum_icp_xdr_conversion_rate_limits_monthly_node_provider_rewar
     assert_eq!(actual_node_provider_reward, expected_node_provider_reward);
 }
 
+#[tokio::test]
+async fn test_monthly_node_provider_rewards_are_performance_based_if_enabled() {
+    let _restore_on_drop = temporarily_enable_performance_based_node_provider_rewards();
+    let driver = fake::FakeDriver::default();
+    let mut gov = Governance::new(
+        fixture_for_following(),
+        driver.get_fake_env(),
+        driver.get_fake_ledger(),
+        driver.get_fake_cmc(),
+    );
+    let minimum_icp_xdr_rate = 100;
+    gov.heap_data
+        .economics
+        .as_mut()
+        .unwrap()
+        .minimum_icp_xdr_rate = minimum_icp_xdr_rate;
+
+    let node_provider_pid = PrincipalId::new_user_test_id(1);
+    let node_provider = NodeProvider {
+        id: Some(node_provider_pid),
+        reward_account: None,
+    };
+
+    gov.heap_data.node_providers = vec![node_provider.clone()];
+
+    let monthly_node_provider_rewards = gov.get_monthly_node_provider_rewards().await.unwrap();
+    let actual_node_provider_reward = monthly_node_provider_rewards
+        .rewards
+        .iter()
+        .find(|reward| reward.node_provider.as_ref().unwrap().id == Some(node_provider_pid))
+        .unwrap()
+        .clone();
+
+    // The rewards come from the Registry's get_node_rewards_breakdown rather
+    // than from get_node_providers_monthly_xdr_rewards.
+    let expected_node_provider_reward = get_node_provider_reward(
+        &node_provider,
+        PERFORMANCE_BASED_NODE_PROVIDER_REWARD,
+        minimum_icp_xdr_rate * NetworkEconomics::ICP_XDR_RATE_TO_BASIS_POINT_MULTIPLIER,
+    )
+    .unwrap();
+    assert_eq!(actual_node_provider_reward, expected_node_provider_reward);
+    assert_eq!(monthly_node_provider_rewards.registry_version, Some(5));
+
+    // The rewards of each node are kept, so that node providers can look them up.
+    assert_eq!(
+        monthly_node_provider_rewards.rewards_per_node,
+        vec![NodeRewardsBreakdown {
+            node_id: Some(PrincipalId::new_node_test_id(1)),
+            node_operator_id: Some(PrincipalId::new_user_test_id(2)),
+            node_provider_id: Some(node_provider_pid),
+            base_xdr_permyriad: 2 * PERFORMANCE_BASED_NODE_PROVIDER_REWARD,
+            failure_rate_permyriad: Some(10_000),
+            reduction_permyriad: 5_000,
+            xdr_permyriad: PERFORMANCE_BASED_NODE_PROVIDER_REWARD,
+        }]
+    );
+}
+
 #[tokio::test]
 async fn test_mint_monthly_node_provider_rewards() {
     // Step 1: prepare the canister state and the Governance minting account.
@@ -1509,6 +1570,7 @@ async fn test_mint_monthly_node_provider_rewards() {
                 maximum_node_provider_rewards_e8s: None,
                 registry_version: None,
                 node_providers: vec![],
+                rewards_per_node: vec![],
             }),
             ..Default::default()
         },
@@ -1536,6 +1598,7 @@ async fn test_mint_monthly_node_provider_rewards() {
         maximum_node_provider_rewards_e8s,
         registry_version,
         node_providers,
+        rewards_per_node,
     } = most_recent_monthly_node_provider_rewards;
     let reward = rewards[0].clone();
     assert_eq!(reward.node_provider.unwrap(), node_provider);
@@ -1551,6 +1614,8 @@ async fn test_mint_monthly_node_provider_rewards() {
     // It happens to be 5, we just want to ensure it is set.
     assert_eq!(registry_version, Some(5));
     assert_eq!(node_providers.len(), 1);
+    // Performance based rewards are disabled, so there is no breakdown per node.
+    assert!(rewards_per_node.is_empty());
 }
 
 #[tokio::test]
@@ -23,7 +23,8 @@ const REBOOT_TIME_FILENAME: &str = "reboot_time.txt";
 /// Defines the image upgrader trait and default implementation. It receives a generic version identifier `V`
 /// and a return value `R` stemming from a periodically called `check_for_upgrade` function.
 /// The lifecycle of an image can be described by:
-/// 1. Confirming the boot of the current image using the `manageboot.sh` script. Cf. `confirm_boot()`
+/// 1. Confirming the boot of the current image using the `manageboot.sh` script. Cf. `confirm_boot()`.
+///    As long as the boot is not confirmed, it can be rolled back, Cf. `rollback_boot()`
 /// 2. Optionally collecting metrics of the reboot time from disk.
 /// 3. Checking for new versions and executing upgrades in a loop, Cf. `upgrade_loop()`
 ///
@@ -127,6 +128,31 @@ pub trait ImageUpgrader<V: Clone + Debug + PartialEq + Eq + Send + Sync, R: Send
         }
     }
 
+    /// Calls a corresponding script to reboot into the previous image without
+    /// confirming the current one. This is only possible as long as the boot of
+    /// the current image was not confirmed. `system_type` is the system whose
+    /// boot is rolled back, as expected by `manageboot.sh`, i.e. `guestos` or
+    /// `hostos`.
+    async fn rollback_boot(&self, system_type: &str) -> UpgradeResult<()> {
+        info!(self.log(), "Attempting to roll back to the previous image");
+        let script = self.binary_dir().join("manageboot.sh");
+        let mut cmd = Command::new(script.into_os_string());
+        let out = cmd
+            .arg(system_type)
+            .arg("rollback")
+            .output()
+            .await
+            .map_err(|e| UpgradeError::file_command_error(e, &cmd))?;
+
+        if !out.status.success() {
+            warn!(self.log(), "rollback has failed: {:?}", out.status);
+            Err(UpgradeError::GenericError("rollback failed".to_string()))
+        } else {
+            info!(self.log(), "Rebooting {:?}", out);
+            exit(42);
+        }
+    }
+
     /// Return a value that would differentiate the nodes (but not necessarily unique) in order
     /// to allow them to download the new release package from different URLs.
     fn get_load_balance_number(&self) -> usize;
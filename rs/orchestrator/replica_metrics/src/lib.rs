@@ -0,0 +1,61 @@
+//! Parsing of the metrics that the replica exports, for the components that
+//! watch it from outside, e.g., the orchestrator and the GuestOS vsock agent.
+
+/// The metric holding the heights of the artifacts in the consensus pool.
+pub const CONSENSUS_HEIGHT_METRIC: &str = "artifact_pool_consensus_height_stat";
+
+/// Parse the highest validated height of the given consensus artifact type,
+/// e.g., "finalization" or "catch_up_package", from metrics in the Prometheus
+/// text format.
+pub fn parse_consensus_height(metrics: &str, artifact_type: &str) -> Option<u64> {
+    let type_label = format!("type=\"{}\"", artifact_type);
+    let expected_labels = [
+        "pool_type=\"validated\"",
+        "stat=\"max\"",
+        type_label.as_str(),
+    ];
+
+    metrics
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let labels = line
+                .strip_prefix(CONSENSUS_HEIGHT_METRIC)?
+                .strip_prefix('{')?;
+            let (labels, value) = labels.split_once('}')?;
+            let labels: Vec<&str> = labels.split(',').map(str::trim).collect();
+            if !expected_labels.iter().all(|label| labels.contains(label)) {
+                return None;
+            }
+            let value = value.trim().parse::<f64>().ok()?;
+            Some(value as u64)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_consensus_height() {
+        let metrics = "\
+# HELP artifact_pool_consensus_height_stat The height of objects in a consensus pool
+# TYPE artifact_pool_consensus_height_stat gauge
+artifact_pool_consensus_height_stat{pool_type=\"unvalidated\",stat=\"max\",type=\"finalization\"} 1300
+artifact_pool_consensus_height_stat{pool_type=\"validated\",stat=\"min\",type=\"finalization\"} 1000
+artifact_pool_consensus_height_stat{pool_type=\"validated\",stat=\"max\",type=\"notarization\"} 1201
+artifact_pool_consensus_height_stat{pool_type=\"validated\",stat=\"max\",type=\"finalization\"} 1200
+artifact_pool_consensus_height_stat{pool_type=\"validated\",stat=\"max\",type=\"catch_up_package\"} 1100
+";
+        assert_eq!(parse_consensus_height(metrics, "finalization"), Some(1200));
+        assert_eq!(
+            parse_consensus_height(metrics, "catch_up_package"),
+            Some(1100)
+        );
+        assert_eq!(parse_consensus_height(metrics, "random_beacon"), None);
+        assert_eq!(
+            parse_consensus_height("replica_info 1\n", "finalization"),
+            None
+        );
+    }
+}
@@ -59,6 +59,15 @@ pub struct OrchestratorArgs {
     /// If not provided, the relevant data are not persisted to the disk.
     #[clap(long, parse(from_os_str))]
     pub(crate) orchestrator_data_directory: PathBuf,
+
+    /// The minimum number of seconds a replica started after an upgrade has to
+    /// prove healthy (running, finalizing blocks and producing a CUP) before
+    /// the boot is confirmed. The window spans a few CUP intervals of the
+    /// subnet, so it is longer on subnets with a long DKG interval. If the
+    /// replica doesn't prove healthy, the node reverts to the previous boot
+    /// partition.
+    #[clap(long, default_value = "600")]
+    pub(crate) min_upgrade_health_window_secs: u64,
 }
 
 impl OrchestratorArgs {
@@ -0,0 +1,661 @@
+//! Health gate for the first boot of a GuestOS after an upgrade.
+//!
+//! Before rebooting into a new replica version, the orchestrator persists a
+//! [`PendingUpgrade`] record in its data directory. When it starts again on the
+//! new version, it doesn't confirm the boot right away. Instead, it observes
+//! the replica for a configurable window and only confirms the boot once
+//!
+//! 1. the replica process is running and doesn't keep crashing,
+//! 2. the finalized height reported by the replica advances, and
+//! 3. a CUP higher than the one at boot time was produced.
+//!
+//! While the subnet is halted or being recovered, none of this can be expected,
+//! so the window is suspended until the subnet resumes.
+//!
+//! The window spans a few CUP intervals of the subnet, see [`health_window`].
+//!
+//! If the window expires before all of these hold, the orchestrator marks the
+//! record as failed and reboots without confirming, which makes the bootloader
+//! fall back to the previous boot partition. The orchestrator of the previous
+//! version then finds the failed record, reports the rollback once and doesn't
+//! retry the upgrade to the same version, until the subnet proves that the
+//! version works or `ROLLED_BACK_UPGRADE_RETRY_DELAY` passed. Then the record
+//! is deleted.
+
+use crate::error::{OrchestratorError, OrchestratorResult};
+use ic_replica_metrics::parse_consensus_height;
+use ic_sys::fs::write_atomically;
+use ic_types::{Height, ReplicaVersion};
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+const PENDING_UPGRADE_FILENAME: &str = "pending_upgrade.cbor";
+
+/// The number of times the replica may exit during the health window before
+/// the upgrade is considered failed.
+pub(crate) const MAX_REPLICA_RESTARTS: usize = 3;
+
+/// How long an upgrade that was rolled back is not retried, unless the subnet
+/// confirms the version earlier.
+pub(crate) const ROLLED_BACK_UPGRADE_RETRY_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The number of CUP intervals a replica has to prove healthy after an upgrade.
+/// The replica may have to catch up to the latest CUP first, and the gate then
+/// waits for the next one.
+const HEALTH_WINDOW_CUP_INTERVALS: u32 = 4;
+
+/// The block time assumed for subnets whose initial notary delay is shorter.
+const MIN_BLOCK_TIME: Duration = Duration::from_secs(1);
+
+/// An upgrade whose boot was not confirmed yet, or that was rolled back if
+/// `failure` is set.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct PendingUpgrade {
+    pub from: ReplicaVersion,
+    pub to: ReplicaVersion,
+    pub failure: Option<String>,
+    /// When the orchestrator of the previous version found the upgrade rolled
+    /// back and reported it. The retry delay counts from then, across restarts.
+    #[serde(default)]
+    pub rolled_back_at: Option<SystemTime>,
+}
+
+impl PendingUpgrade {
+    pub(crate) fn new(from: ReplicaVersion, to: ReplicaVersion) -> Self {
+        Self {
+            from,
+            to,
+            failure: None,
+            rolled_back_at: None,
+        }
+    }
+
+    pub(crate) fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(PENDING_UPGRADE_FILENAME)
+    }
+
+    /// Read the record from the given data directory, if one exists.
+    pub(crate) fn load(data_dir: &Path) -> OrchestratorResult<Option<Self>> {
+        let path = Self::path(data_dir);
+        if !path
+            .try_exists()
+            .map_err(OrchestratorError::boot_health_error)?
+        {
+            return Ok(None);
+        }
+        let file = std::fs::File::open(path).map_err(OrchestratorError::boot_health_error)?;
+        serde_cbor::from_reader(file)
+            .map(Some)
+            .map_err(OrchestratorError::boot_health_error)
+    }
+
+    /// Write the record to the given data directory. The record is written to
+    /// a temporary file first, so that a crash never leaves a partial record.
+    pub(crate) fn persist(&self, data_dir: &Path) -> OrchestratorResult<()> {
+        write_atomically(Self::path(data_dir), |writer| {
+            serde_cbor::to_writer(writer, self).map_err(std::io::Error::other)
+        })
+        .map_err(OrchestratorError::boot_health_error)
+    }
+
+    pub(crate) fn remove(data_dir: &Path) -> OrchestratorResult<()> {
+        let path = Self::path(data_dir);
+        if path
+            .try_exists()
+            .map_err(OrchestratorError::boot_health_error)?
+        {
+            std::fs::remove_file(path).map_err(OrchestratorError::boot_health_error)?;
+        }
+        Ok(())
+    }
+}
+
+/// An upgrade of this node that was rolled back, and that is not retried until
+/// the subnet confirms the version or the retry delay passed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RolledBackUpgrade {
+    upgrade: PendingUpgrade,
+    since: SystemTime,
+    /// The height of the first CUP requiring the version of the upgrade.
+    first_cup_height: Option<Height>,
+}
+
+impl RolledBackUpgrade {
+    pub(crate) fn new(upgrade: PendingUpgrade, since: SystemTime) -> Self {
+        Self {
+            upgrade,
+            since,
+            first_cup_height: None,
+        }
+    }
+
+    pub(crate) fn upgrade(&self) -> &PendingUpgrade {
+        &self.upgrade
+    }
+
+    /// Whether the upgrade to the given version must not be retried at `now`.
+    pub(crate) fn blocks(&self, version: &ReplicaVersion, now: SystemTime) -> bool {
+        &self.upgrade.to == version && !self.has_expired(now)
+    }
+
+    /// Whether the retry delay passed at `now`.
+    pub(crate) fn has_expired(&self, now: SystemTime) -> bool {
+        // If the clock went backwards, the delay counts from `now`.
+        now.duration_since(self.since).unwrap_or_default() >= ROLLED_BACK_UPGRADE_RETRY_DELAY
+    }
+
+    /// Record the latest CUP of the subnet, which requires the given version,
+    /// and return whether it confirms the version of the upgrade. That is the
+    /// case once a signed CUP above the first CUP requiring that version
+    /// exists, as it was produced by the subnet running the version.
+    pub(crate) fn is_confirmed_by_cup(
+        &mut self,
+        cup_version: &ReplicaVersion,
+        cup_height: Height,
+        cup_is_signed: bool,
+    ) -> bool {
+        if cup_version != &self.upgrade.to {
+            return false;
+        }
+        match self.first_cup_height {
+            Some(first_cup_height) => cup_is_signed && cup_height > first_cup_height,
+            None => {
+                self.first_cup_height = Some(cup_height);
+                false
+            }
+        }
+    }
+}
+
+/// The state of the current boot, as shown on the dashboard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum BootHealth {
+    /// The boot was confirmed, or didn't follow an upgrade.
+    Confirmed,
+    /// The boot follows an upgrade and the replica is being observed.
+    Checking {
+        upgrade: PendingUpgrade,
+        elapsed: Duration,
+        window: Duration,
+        unmet: Vec<String>,
+    },
+    /// The upgrade failed the health gate and the node is reverting to the
+    /// previous boot partition.
+    RollingBack(PendingUpgrade),
+    /// The node was reverted to the previous boot partition after a failed
+    /// upgrade.
+    RolledBack(PendingUpgrade),
+}
+
+impl fmt::Display for BootHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BootHealth::Confirmed => write!(f, "confirmed"),
+            BootHealth::Checking {
+                upgrade,
+                elapsed,
+                window,
+                unmet,
+            } => write!(
+                f,
+                "checking upgrade {} -> {} ({}s of {}s): waiting for {}",
+                upgrade.from,
+                upgrade.to,
+                elapsed.as_secs(),
+                window.as_secs(),
+                unmet.join(", ")
+            ),
+            BootHealth::RollingBack(upgrade) => write!(
+                f,
+                "rolling back upgrade {} -> {}: {}",
+                upgrade.from,
+                upgrade.to,
+                upgrade.failure.as_deref().unwrap_or("unknown failure")
+            ),
+            BootHealth::RolledBack(upgrade) => write!(
+                f,
+                "rolled back upgrade {} -> {}: {}",
+                upgrade.from,
+                upgrade.to,
+                upgrade
+                    .failure
+                    .as_deref()
+                    .unwrap_or("the new version was never confirmed")
+            ),
+        }
+    }
+}
+
+/// What the orchestrator observed about the replica during one check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct HealthSample {
+    pub replica_running: bool,
+    /// Whether the subnet is halted or being recovered from an unsigned CUP,
+    /// in which case it can't make progress.
+    pub subnet_halted: bool,
+    pub finalized_height: Option<Height>,
+    pub cup_height: Option<Height>,
+}
+
+/// The outcome of observing a sample.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum GateDecision {
+    /// Not all conditions hold yet, but the window didn't expire.
+    Pending(Vec<String>),
+    Healthy,
+    Failed(String),
+}
+
+/// Decides whether a freshly upgraded replica is healthy, based on the samples
+/// taken by the orchestrator while the boot is not confirmed.
+pub(crate) struct BootHealthGate {
+    upgrade: PendingUpgrade,
+    started: Instant,
+    window: Duration,
+    /// Whether the finalized height can be observed, i.e. whether the replica
+    /// exports its metrics over HTTP.
+    require_finalization: bool,
+    initial_finalized_height: Option<Height>,
+    initial_cup_height: Option<Height>,
+    replica_seen_running: bool,
+    replica_restarts: usize,
+    /// Set while the subnet is halted.
+    suspended_since: Option<Instant>,
+    /// How long the gate was suspended before `suspended_since`.
+    suspended: Duration,
+}
+
+impl BootHealthGate {
+    pub(crate) fn new(
+        upgrade: PendingUpgrade,
+        started: Instant,
+        window: Duration,
+        require_finalization: bool,
+    ) -> Self {
+        Self {
+            upgrade,
+            started,
+            window,
+            require_finalization,
+            initial_finalized_height: None,
+            initial_cup_height: None,
+            replica_seen_running: false,
+            replica_restarts: 0,
+            suspended_since: None,
+            suspended: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn upgrade(&self) -> &PendingUpgrade {
+        &self.upgrade
+    }
+
+    pub(crate) fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Set the window, once the subnet of the replica is known.
+    pub(crate) fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+
+    /// Record the given sample and decide whether the replica is healthy, is
+    /// still to be observed, or failed the gate.
+    pub(crate) fn observe(&mut self, now: Instant, sample: HealthSample) -> GateDecision {
+        if sample.subnet_halted {
+            self.suspended_since.get_or_insert(now);
+            // The progress and the restarts of the replica are observed anew
+            // once the subnet resumes.
+            self.initial_finalized_height = None;
+            self.initial_cup_height = None;
+            self.replica_seen_running = false;
+            return GateDecision::Pending(vec!["the subnet to resume".to_string()]);
+        }
+        if let Some(since) = self.suspended_since.take() {
+            self.suspended += now.saturating_duration_since(since);
+        }
+
+        if sample.replica_running {
+            self.replica_seen_running = true;
+        } else if self.replica_seen_running {
+            // The replica was running at the previous check and has exited since.
+            self.replica_seen_running = false;
+            self.replica_restarts += 1;
+        }
+        if self.replica_restarts > MAX_REPLICA_RESTARTS {
+            return GateDecision::Failed(format!(
+                "the replica exited {} times",
+                self.replica_restarts
+            ));
+        }
+
+        if self.initial_finalized_height.is_none() {
+            self.initial_finalized_height = sample.finalized_height;
+        }
+        if self.initial_cup_height.is_none() {
+            self.initial_cup_height = sample.cup_height;
+        }
+
+        let mut unmet = Vec::new();
+        if !sample.replica_running {
+            unmet.push("the replica to run".to_string());
+        }
+        if self.require_finalization
+            && !advanced(self.initial_finalized_height, sample.finalized_height)
+        {
+            unmet.push(format!(
+                "the finalized height to advance past {}",
+                display_height(self.initial_finalized_height)
+            ));
+        }
+        if !advanced(self.initial_cup_height, sample.cup_height) {
+            unmet.push(format!(
+                "a CUP above height {}",
+                display_height(self.initial_cup_height)
+            ));
+        }
+
+        if unmet.is_empty() {
+            GateDecision::Healthy
+        } else if self.elapsed(now) >= self.window {
+            GateDecision::Failed(format!(
+                "the replica was not healthy after {}s, still waiting for {}",
+                self.window.as_secs(),
+                unmet.join(", ")
+            ))
+        } else {
+            GateDecision::Pending(unmet)
+        }
+    }
+
+    /// The time the replica was observed for, excluding the time the subnet
+    /// was halted.
+    pub(crate) fn elapsed(&self, now: Instant) -> Duration {
+        let suspended = self.suspended
+            + self
+                .suspended_since
+                .map_or(Duration::ZERO, |since| now.saturating_duration_since(since));
+        now.saturating_duration_since(self.started)
+            .saturating_sub(suspended)
+    }
+}
+
+/// Return how long a replica has to prove healthy after an upgrade, given the
+/// DKG interval length and the initial notary delay of its subnet. That is
+/// `HEALTH_WINDOW_CUP_INTERVALS` CUP intervals, but at least `min_window`.
+pub(crate) fn health_window(
+    dkg_interval_length: u64,
+    initial_notary_delay: Duration,
+    min_window: Duration,
+) -> Duration {
+    let blocks_per_cup = u32::try_from(dkg_interval_length.saturating_add(1)).unwrap_or(u32::MAX);
+    initial_notary_delay
+        .max(MIN_BLOCK_TIME)
+        .saturating_mul(blocks_per_cup)
+        .saturating_mul(HEALTH_WINDOW_CUP_INTERVALS)
+        .max(min_window)
+}
+
+fn advanced(initial: Option<Height>, current: Option<Height>) -> bool {
+    matches!((initial, current), (Some(initial), Some(current)) if current > initial)
+}
+
+fn display_height(height: Option<Height>) -> String {
+    height.map_or_else(|| "None".to_string(), |h| h.to_string())
+}
+
+/// Fetch the metrics exported by the replica at the given address and return
+/// its highest validated finalization height.
+pub(crate) async fn fetch_finalized_height(addr: SocketAddr) -> OrchestratorResult<Height> {
+    let body = reqwest::Client::new()
+        .get(format!("http://{}/metrics", addr))
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(OrchestratorError::boot_health_error)?
+        .text()
+        .await
+        .map_err(OrchestratorError::boot_health_error)?;
+    parse_consensus_height(&body, "finalization")
+        .map(Height::from)
+        .ok_or_else(|| {
+            OrchestratorError::boot_health_error(format!(
+                "The replica metrics at {} don't contain the finalization height",
+                addr
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const WINDOW: Duration = Duration::from_secs(600);
+
+    fn upgrade() -> PendingUpgrade {
+        PendingUpgrade::new(
+            ReplicaVersion::try_from("old").unwrap(),
+            ReplicaVersion::try_from("new").unwrap(),
+        )
+    }
+
+    fn sample(running: bool, finalized: u64, cup: u64) -> HealthSample {
+        HealthSample {
+            replica_running: running,
+            subnet_halted: false,
+            finalized_height: Some(Height::from(finalized)),
+            cup_height: Some(Height::from(cup)),
+        }
+    }
+
+    #[test]
+    fn test_pending_upgrade_roundtrip() {
+        let dir = tempdir().unwrap();
+        assert_eq!(PendingUpgrade::load(dir.path()).unwrap(), None);
+
+        let mut pending = upgrade();
+        pending.persist(dir.path()).unwrap();
+        assert_eq!(
+            PendingUpgrade::load(dir.path()).unwrap(),
+            Some(pending.clone())
+        );
+
+        pending.failure = Some("failed".to_string());
+        pending.rolled_back_at = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+        pending.persist(dir.path()).unwrap();
+        assert_eq!(PendingUpgrade::load(dir.path()).unwrap(), Some(pending));
+
+        PendingUpgrade::remove(dir.path()).unwrap();
+        assert_eq!(PendingUpgrade::load(dir.path()).unwrap(), None);
+        // Removing a missing record is fine.
+        PendingUpgrade::remove(dir.path()).unwrap();
+    }
+
+    #[test]
+    fn test_gate_is_healthy_once_heights_advance() {
+        let start = Instant::now();
+        let mut gate = BootHealthGate::new(upgrade(), start, WINDOW, true);
+
+        assert!(matches!(
+            gate.observe(start, sample(false, 100, 99)),
+            GateDecision::Pending(_)
+        ));
+        assert!(matches!(
+            gate.observe(start + Duration::from_secs(10), sample(true, 150, 99)),
+            GateDecision::Pending(unmet) if unmet == vec!["a CUP above height 99".to_string()]
+        ));
+        assert_eq!(
+            gate.observe(start + Duration::from_secs(20), sample(true, 200, 199)),
+            GateDecision::Healthy
+        );
+    }
+
+    #[test]
+    fn test_gate_fails_when_finalization_stalls() {
+        let start = Instant::now();
+        let mut gate = BootHealthGate::new(upgrade(), start, WINDOW, true);
+
+        assert!(matches!(
+            gate.observe(start, sample(true, 100, 99)),
+            GateDecision::Pending(_)
+        ));
+        assert!(matches!(
+            gate.observe(start + WINDOW, sample(true, 100, 199)),
+            GateDecision::Failed(reason) if reason.contains("the finalized height to advance past 100")
+        ));
+    }
+
+    #[test]
+    fn test_gate_ignores_finalization_if_not_observable() {
+        let start = Instant::now();
+        let mut gate = BootHealthGate::new(upgrade(), start, WINDOW, false);
+        let sample = |cup| HealthSample {
+            replica_running: true,
+            subnet_halted: false,
+            finalized_height: None,
+            cup_height: Some(Height::from(cup)),
+        };
+
+        assert!(matches!(
+            gate.observe(start, sample(99)),
+            GateDecision::Pending(_)
+        ));
+        assert_eq!(
+            gate.observe(start + Duration::from_secs(10), sample(199)),
+            GateDecision::Healthy
+        );
+    }
+
+    #[test]
+    fn test_gate_fails_early_when_replica_keeps_crashing() {
+        let start = Instant::now();
+        let mut gate = BootHealthGate::new(upgrade(), start, WINDOW, true);
+
+        for _ in 0..MAX_REPLICA_RESTARTS {
+            gate.observe(start, sample(true, 100, 99));
+            assert!(matches!(
+                gate.observe(start, sample(false, 100, 99)),
+                GateDecision::Pending(_)
+            ));
+        }
+        gate.observe(start, sample(true, 100, 99));
+        assert!(matches!(
+            gate.observe(start, sample(false, 100, 99)),
+            GateDecision::Failed(reason) if reason.contains("exited 4 times")
+        ));
+    }
+
+    #[test]
+    fn test_gate_is_suspended_while_subnet_is_halted() {
+        let start = Instant::now();
+        let mut gate = BootHealthGate::new(upgrade(), start, WINDOW, true);
+        let halted = HealthSample {
+            subnet_halted: true,
+            ..sample(false, 100, 99)
+        };
+
+        assert!(matches!(
+            gate.observe(start, sample(true, 100, 99)),
+            GateDecision::Pending(_)
+        ));
+        // The stopped replica doesn't fail the gate while the subnet is
+        // halted, and the window doesn't advance.
+        for _ in 0..=MAX_REPLICA_RESTARTS {
+            assert_eq!(
+                gate.observe(start + WINDOW / 2, halted),
+                GateDecision::Pending(vec!["the subnet to resume".to_string()])
+            );
+        }
+        // The window continues once the subnet resumes, e.g. from a recovery
+        // CUP at a higher height.
+        let resumed = start + WINDOW / 2 + 2 * WINDOW;
+        assert!(matches!(
+            gate.observe(resumed, sample(true, 300, 299)),
+            GateDecision::Pending(_)
+        ));
+        assert_eq!(gate.elapsed(resumed), WINDOW / 2);
+        assert_eq!(
+            gate.observe(resumed, sample(true, 400, 399)),
+            GateDecision::Healthy
+        );
+    }
+
+    #[test]
+    fn test_pending_upgrade_without_rollback_time_can_be_loaded() {
+        #[derive(Serialize)]
+        struct RecordWithoutRollbackTime {
+            from: ReplicaVersion,
+            to: ReplicaVersion,
+            failure: Option<String>,
+        }
+        let dir = tempdir().unwrap();
+        let file = std::fs::File::create(PendingUpgrade::path(dir.path())).unwrap();
+        serde_cbor::to_writer(
+            file,
+            &RecordWithoutRollbackTime {
+                from: ReplicaVersion::try_from("old").unwrap(),
+                to: ReplicaVersion::try_from("new").unwrap(),
+                failure: None,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(PendingUpgrade::load(dir.path()).unwrap(), Some(upgrade()));
+    }
+
+    #[test]
+    fn test_rolled_back_upgrade_expires() {
+        let start = SystemTime::now();
+        let rolled_back = RolledBackUpgrade::new(upgrade(), start);
+        let new = ReplicaVersion::try_from("new").unwrap();
+        let other = ReplicaVersion::try_from("other").unwrap();
+
+        assert!(rolled_back.blocks(&new, start));
+        assert!(!rolled_back.blocks(&other, start));
+        assert!(!rolled_back.has_expired(start - Duration::from_secs(60)));
+        assert!(!rolled_back.blocks(&new, start + ROLLED_BACK_UPGRADE_RETRY_DELAY));
+        assert!(rolled_back.has_expired(start + ROLLED_BACK_UPGRADE_RETRY_DELAY));
+    }
+
+    #[test]
+    fn test_health_window_spans_cup_intervals() {
+        let min_window = Duration::from_secs(600);
+
+        // 500 blocks of 1s per CUP interval.
+        assert_eq!(
+            health_window(499, Duration::from_millis(300), min_window),
+            Duration::from_secs(4 * 500)
+        );
+        // 100 blocks of 2s per CUP interval.
+        assert_eq!(
+            health_window(99, Duration::from_secs(2), min_window),
+            Duration::from_secs(4 * 200)
+        );
+        assert_eq!(
+            health_window(9, Duration::from_millis(300), min_window),
+            min_window
+        );
+        assert!(health_window(u64::MAX, Duration::from_secs(1), min_window) > min_window);
+    }
+
+    #[test]
+    fn test_rolled_back_upgrade_is_confirmed_by_later_signed_cup() {
+        let mut rolled_back = RolledBackUpgrade::new(upgrade(), SystemTime::now());
+        let new = ReplicaVersion::try_from("new").unwrap();
+        let old = ReplicaVersion::try_from("old").unwrap();
+
+        assert!(!rolled_back.is_confirmed_by_cup(&old, Height::from(50), true));
+        // The CUP that triggered the upgrade.
+        assert!(!rolled_back.is_confirmed_by_cup(&new, Height::from(100), true));
+        assert!(!rolled_back.is_confirmed_by_cup(&new, Height::from(100), true));
+        assert!(!rolled_back.is_confirmed_by_cup(&new, Height::from(200), false));
+        assert!(rolled_back.is_confirmed_by_cup(&new, Height::from(200), true));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
-    catch_up_package_provider::CatchUpPackageProvider, process_manager::ProcessManager,
-    registry_helper::RegistryHelper, ssh_access_manager::SshAccessParameters,
-    upgrade::ReplicaProcess,
+    boot_health::BootHealth, catch_up_package_provider::CatchUpPackageProvider,
+    process_manager::ProcessManager, registry_helper::RegistryHelper,
+    ssh_access_manager::SshAccessParameters, upgrade::ReplicaProcess,
 };
 use async_trait::async_trait;
 pub use ic_dashboard::Dashboard;
@@ -30,6 +30,7 @@ pub(crate) struct OrchestratorDashboard {
     replica_version: ReplicaVersion,
     hostos_version: Option<HostosVersion>,
     cup_provider: Arc<CatchUpPackageProvider>,
+    boot_health: Arc<RwLock<BootHealth>>,
     logger: ReplicaLogger,
 }
 
@@ -49,6 +50,7 @@ impl Dashboard for OrchestratorDashboard {
              replica version: {}\n\
              host os version: {}\n\
              scheduled upgrade: {}\n\
+             boot health: {}\n\
              {}\n\
              firewall config registry version: {}\n\
              ipv4 config registry version: {}\n\
@@ -67,6 +69,7 @@ impl Dashboard for OrchestratorDashboard {
                 .map(|v| v.to_string())
                 .unwrap_or_else(|| "None".to_string()),
             self.get_scheduled_upgrade().await,
+            *self.boot_health.read().await,
             self.get_local_cup_info(),
             *self.last_applied_firewall_version.read().await,
             *self.last_applied_ipv4_config_version.read().await,
@@ -94,6 +97,7 @@ impl OrchestratorDashboard {
         replica_version: ReplicaVersion,
         hostos_version: Option<HostosVersion>,
         cup_provider: Arc<CatchUpPackageProvider>,
+        boot_health: Arc<RwLock<BootHealth>>,
         logger: ReplicaLogger,
     ) -> Self {
         Self {
@@ -107,6 +111,7 @@ impl OrchestratorDashboard {
             replica_version,
             hostos_version,
             cup_provider,
+            boot_health,
             logger,
         }
     }
@@ -65,6 +65,9 @@ pub(crate) enum OrchestratorError {
     /// Generic error while monitoring key changes
     ThresholdKeyMonitoringError(String),
 
+    /// Generic error while checking the health of a freshly upgraded replica
+    BootHealthError(String),
+
     /// Network configuration error
     NetworkConfigurationError(String),
 
@@ -89,6 +92,10 @@ impl OrchestratorError {
         OrchestratorError::ThresholdKeyMonitoringError(msg.to_string())
     }
 
+    pub(crate) fn boot_health_error(msg: impl ToString) -> Self {
+        OrchestratorError::BootHealthError(msg.to_string())
+    }
+
     pub(crate) fn deserialize_cup_error(height: Option<Height>, msg: impl ToString) -> Self {
         OrchestratorError::DeserializeCupError(height, msg.to_string())
     }
@@ -132,6 +139,13 @@ impl fmt::Display for OrchestratorError {
                     msg
                 )
             }
+            OrchestratorError::BootHealthError(msg) => {
+                write!(
+                    f,
+                    "Failed to check the health of the upgraded replica: {}",
+                    msg
+                )
+            }
             OrchestratorError::SubnetMissingError(subnet_id, registry_version) => write!(
                 f,
                 "Subnet ID {:?} does not exist in the Registry at registry version {:?}",
@@ -26,6 +26,10 @@
 //! 5. If the version is different from what we are currently running, apply
 //!    upgrade and restart replica with that CUP.
 //!
+//! After rebooting into a new version, the boot is only confirmed once the
+//! new replica proved to be healthy. Otherwise, the node reverts to the
+//! previous boot partition, see [`boot_health`].
+//!
 //! # Registry
 //!
 //! The orchestrator also fetches configuration updates from the
@@ -33,6 +37,7 @@
 //! system to read.
 
 pub mod args;
+mod boot_health;
 mod boundary_node;
 mod catch_up_package_provider;
 mod dashboard;
@@ -15,6 +15,8 @@ pub struct OrchestratorMetrics {
     pub master_public_key_changed_errors: IntCounterVec,
     pub failed_consecutive_upgrade_checks: IntCounter,
     pub critical_error_cup_deserialization_failed: IntCounter,
+    pub boot_health_status: IntGaugeVec,
+    pub upgrade_rolled_back: IntGaugeVec,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, Eq, IntoStaticStr, PartialOrd, Ord, PartialEq)]
@@ -27,6 +29,14 @@ pub enum KeyRotationStatus {
     Error,
 }
 
+#[derive(Copy, Clone, Debug, EnumIter, Eq, IntoStaticStr, PartialOrd, Ord, PartialEq)]
+pub enum BootHealthStatus {
+    Confirmed,
+    Checking,
+    RollingBack,
+    RolledBack,
+}
+
 impl KeyRotationStatus {
     fn is_transient(self) -> bool {
         matches!(
@@ -82,6 +92,16 @@ impl OrchestratorMetrics {
                 "orchestrator_cup_deserialization_failed_total",
                 "Number of times the deserialization of the locally persisted CUP failed",
             ),
+            boot_health_status: metrics_registry.int_gauge_vec(
+                "orchestrator_boot_health_status",
+                "The health status of the current boot after an upgrade.",
+                &["status"],
+            ),
+            upgrade_rolled_back: metrics_registry.int_gauge_vec(
+                "orchestrator_upgrade_rolled_back",
+                "Set to 1 if the upgrade between the given versions failed the health check and was rolled back",
+                &["from_version", "to_version"],
+            ),
         }
     }
 
@@ -104,4 +124,13 @@ impl OrchestratorMetrics {
             .with_label_values(&[KeyRotationStatus::Error.into()])
             .set(1);
     }
+
+    /// Set the current boot health status to the given status and clear all other states.
+    pub fn observe_boot_health_status(&self, status: BootHealthStatus) {
+        BootHealthStatus::iter().for_each(|s| {
+            self.boot_health_status
+                .with_label_values(&[s.into()])
+                .set((s == status) as i64);
+        });
+    }
 }
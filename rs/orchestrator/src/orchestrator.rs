@@ -227,23 +227,28 @@ impl Orchestrator {
             registration.register_node().await;
         }
 
-        let upgrade = Some(
-            Upgrade::new(
-                Arc::clone(&registry),
-                Arc::clone(&metrics),
-                Arc::clone(&replica_process),
-                Arc::clone(&cup_provider),
-                replica_version.clone(),
-                args.replica_config_file.clone(),
-                node_id,
-                ic_binary_directory.clone(),
-                registry_replicator,
-                args.replica_binary_dir.clone(),
-                logger.clone(),
-                args.orchestrator_data_directory.clone(),
-            )
-            .await,
-        );
+        let replica_metrics_addr = match config.metrics.exporter {
+            Exporter::Http(addr) => Some(addr),
+            _ => None,
+        };
+        let upgrade = Upgrade::new(
+            Arc::clone(&registry),
+            Arc::clone(&metrics),
+            Arc::clone(&replica_process),
+            Arc::clone(&cup_provider),
+            replica_version.clone(),
+            args.replica_config_file.clone(),
+            node_id,
+            ic_binary_directory.clone(),
+            registry_replicator,
+            args.replica_binary_dir.clone(),
+            logger.clone(),
+            args.orchestrator_data_directory.clone(),
+            replica_metrics_addr,
+            Duration::from_secs(args.min_upgrade_health_window_secs),
+        )
+        .await;
+        let boot_health = upgrade.get_boot_health();
 
         let hostos_version = UtilityCommand::request_hostos_version()
             .await
@@ -317,6 +322,7 @@ impl Orchestrator {
             replica_version,
             hostos_version.ok(),
             cup_provider,
+            boot_health,
             logger.clone(),
         ));
 
@@ -326,7 +332,7 @@ impl Orchestrator {
             logger,
             _async_log_guard,
             _metrics_runtime,
-            upgrade,
+            upgrade: Some(upgrade),
             hostos_upgrade,
             boundary_node_manager: Some(boundary_node),
             firewall: Some(firewall),
@@ -1,7 +1,11 @@
 use crate::{
+    boot_health::{
+        fetch_finalized_height, health_window, BootHealth, BootHealthGate, GateDecision,
+        HealthSample, PendingUpgrade, RolledBackUpgrade,
+    },
     catch_up_package_provider::CatchUpPackageProvider,
     error::{OrchestratorError, OrchestratorResult},
-    metrics::OrchestratorMetrics,
+    metrics::{BootHealthStatus, OrchestratorMetrics},
     process_manager::{Process, ProcessManager},
     registry_helper::RegistryHelper,
 };
@@ -29,9 +33,12 @@ use ic_types::{
 };
 use std::{
     collections::BTreeMap,
+    net::SocketAddr,
     path::PathBuf,
     sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
 };
+use tokio::sync::RwLock;
 
 const KEY_CHANGES_FILENAME: &str = "key_changed_metric.cbor";
 
@@ -78,6 +85,18 @@ pub(crate) struct Upgrade {
     /// The replica version that is prepared by 'prepare_upgrade' to upgrade to.
     pub prepared_upgrade_version: Option<ReplicaVersion>,
     pub orchestrator_data_directory: PathBuf,
+    /// The address the replica exports its metrics on, used to observe the
+    /// finalized height after an upgrade.
+    replica_metrics_addr: Option<SocketAddr>,
+    /// How long a replica started after an upgrade has to become healthy at
+    /// least. The window is longer on subnets with long CUP intervals.
+    min_health_window: Duration,
+    /// Set while the boot following an upgrade is not confirmed yet.
+    boot_health_gate: Option<BootHealthGate>,
+    /// Set if an upgrade of this node was rolled back, in which case the
+    /// upgrade to the same version is not retried for a while.
+    rolled_back_upgrade: Option<RolledBackUpgrade>,
+    boot_health: Arc<RwLock<BootHealth>>,
 }
 
 impl Upgrade {
@@ -95,8 +114,10 @@ impl Upgrade {
         release_content_dir: PathBuf,
         logger: ReplicaLogger,
         orchestrator_data_directory: PathBuf,
+        replica_metrics_addr: Option<SocketAddr>,
+        min_health_window: Duration,
     ) -> Self {
-        let value = Self {
+        let mut value = Self {
             registry,
             metrics,
             replica_process,
@@ -110,6 +131,11 @@ impl Upgrade {
             logger: logger.clone(),
             prepared_upgrade_version: None,
             orchestrator_data_directory,
+            replica_metrics_addr,
+            min_health_window,
+            boot_health_gate: None,
+            rolled_back_upgrade: None,
+            boot_health: Arc::new(RwLock::new(BootHealth::Confirmed)),
         };
         if let Err(e) = value.report_reboot_time() {
             warn!(logger, "Cannot report the reboot time: {}", e);
@@ -123,10 +149,291 @@ impl Upgrade {
                 "Cannot report master public key changed metric: {}", e
             );
         }
-        value.confirm_boot().await;
+        value.confirm_boot_unless_upgraded().await;
         value
     }
 
+    pub(crate) fn get_boot_health(&self) -> Arc<RwLock<BootHealth>> {
+        Arc::clone(&self.boot_health)
+    }
+
+    /// Confirm the boot, unless it follows an upgrade to the current version.
+    /// In that case, the boot is only confirmed once the replica proved to be
+    /// healthy, see `check_boot_health()`.
+    async fn confirm_boot_unless_upgraded(&mut self) {
+        let pending_upgrade = PendingUpgrade::load(&self.orchestrator_data_directory)
+            .unwrap_or_else(|e| {
+                warn!(self.logger, "Cannot read the pending upgrade: {}", e);
+                None
+            });
+        match pending_upgrade {
+            Some(upgrade) if upgrade.to == self.replica_version => {
+                info!(
+                    self.logger,
+                    "Booted after the upgrade {} -> {}, checking the replica health before \
+                    confirming the boot",
+                    upgrade.from,
+                    upgrade.to,
+                );
+                self.metrics
+                    .observe_boot_health_status(BootHealthStatus::Checking);
+                *self.boot_health.write().await = BootHealth::Checking {
+                    upgrade: upgrade.clone(),
+                    elapsed: Duration::ZERO,
+                    window: self.min_health_window,
+                    unmet: vec!["the first health check".to_string()],
+                };
+                self.boot_health_gate = Some(BootHealthGate::new(
+                    upgrade,
+                    Instant::now(),
+                    self.min_health_window,
+                    self.replica_metrics_addr.is_some(),
+                ));
+                return;
+            }
+            // We are not running the version we upgraded to, so the upgrade was
+            // rolled back, either by us or by the bootloader.
+            Some(mut upgrade) => {
+                let since = match upgrade.rolled_back_at {
+                    // The rollback was reported before this restart.
+                    Some(since) => since,
+                    None => {
+                        warn!(
+                            self.logger,
+                            "The upgrade {} -> {} was rolled back: {}",
+                            upgrade.from,
+                            upgrade.to,
+                            upgrade
+                                .failure
+                                .as_deref()
+                                .unwrap_or("the boot was never confirmed")
+                        );
+                        let now = SystemTime::now();
+                        upgrade.rolled_back_at = Some(now);
+                        if let Err(e) = upgrade.persist(&self.orchestrator_data_directory) {
+                            warn!(self.logger, "Cannot persist the rolled back upgrade: {}", e);
+                        }
+                        now
+                    }
+                };
+                self.metrics
+                    .upgrade_rolled_back
+                    .with_label_values(&[upgrade.from.as_ref(), upgrade.to.as_ref()])
+                    .set(1);
+                self.metrics
+                    .observe_boot_health_status(BootHealthStatus::RolledBack);
+                *self.boot_health.write().await = BootHealth::RolledBack(upgrade.clone());
+                self.rolled_back_upgrade = Some(RolledBackUpgrade::new(upgrade, since));
+            }
+            None => self
+                .metrics
+                .observe_boot_health_status(BootHealthStatus::Confirmed),
+        }
+        self.confirm_boot().await;
+    }
+
+    /// Sample the health of the replica if the boot following an upgrade is
+    /// not confirmed yet, and either confirm the boot or roll back the upgrade
+    /// once the health gate decides.
+    async fn check_boot_health(
+        &mut self,
+        replica_running: bool,
+        subnet_halted: bool,
+        cup_height: Height,
+        window: Option<Duration>,
+    ) -> OrchestratorResult<()> {
+        if self.boot_health_gate.is_none() {
+            return Ok(());
+        }
+        let finalized_height = match self.replica_metrics_addr {
+            Some(addr) if replica_running => fetch_finalized_height(addr)
+                .await
+                .inspect_err(|e| warn!(self.logger, "{}", e))
+                .ok(),
+            _ => None,
+        };
+        let sample = HealthSample {
+            replica_running,
+            subnet_halted,
+            finalized_height,
+            cup_height: Some(cup_height),
+        };
+
+        let now = Instant::now();
+        let Some(gate) = self.boot_health_gate.as_mut() else {
+            return Ok(());
+        };
+        if let Some(window) = window {
+            gate.set_window(window);
+        }
+        match gate.observe(now, sample) {
+            GateDecision::Pending(unmet) => {
+                *self.boot_health.write().await = BootHealth::Checking {
+                    upgrade: gate.upgrade().clone(),
+                    elapsed: gate.elapsed(now),
+                    window: gate.window(),
+                    unmet,
+                };
+                Ok(())
+            }
+            GateDecision::Healthy => {
+                info!(self.logger, "The upgraded replica is healthy");
+                self.confirm_upgraded_boot().await;
+                Ok(())
+            }
+            GateDecision::Failed(reason) => self.roll_back_upgrade(reason).await,
+        }
+    }
+
+    /// Confirm the boot following an upgrade and stop checking the replica
+    /// health.
+    async fn confirm_upgraded_boot(&mut self) {
+        self.boot_health_gate = None;
+        self.confirm_boot().await;
+        if let Err(e) = PendingUpgrade::remove(&self.orchestrator_data_directory) {
+            warn!(self.logger, "Cannot remove the pending upgrade: {}", e);
+        }
+        self.metrics
+            .observe_boot_health_status(BootHealthStatus::Confirmed);
+        *self.boot_health.write().await = BootHealth::Confirmed;
+    }
+
+    /// Record the failure of the current upgrade and reboot into the previous
+    /// boot partition. Only returns if the rollback failed, in which case it is
+    /// retried during the next check.
+    async fn roll_back_upgrade(&mut self, reason: String) -> OrchestratorResult<()> {
+        let Some(gate) = self.boot_health_gate.as_ref() else {
+            return Ok(());
+        };
+        let mut upgrade = gate.upgrade().clone();
+        error!(
+            self.logger,
+            "The upgrade {} -> {} failed the health check: {}. Rolling back to the previous \
+            boot partition",
+            upgrade.from,
+            upgrade.to,
+            reason
+        );
+        upgrade.failure = Some(reason);
+        upgrade.persist(&self.orchestrator_data_directory)?;
+        self.metrics
+            .observe_boot_health_status(BootHealthStatus::RollingBack);
+        *self.boot_health.write().await = BootHealth::RollingBack(upgrade);
+        if let Err(e) = self.stop_replica() {
+            warn!(self.logger, "{}", e);
+        }
+        self.rollback_boot("guestos")
+            .await
+            .map_err(OrchestratorError::from)
+    }
+
+    /// Execute the upgrade to the given version, after persisting it so that
+    /// the orchestrator of the new version checks the replica health before
+    /// confirming the boot.
+    async fn execute_upgrade_with_health_check(
+        &mut self,
+        version: &ReplicaVersion,
+    ) -> OrchestratorResult<()> {
+        self.ensure_not_rolled_back(version)?;
+        if self.boot_health_gate.is_some() {
+            // The bootloader only accepts a new upgrade once the current boot
+            // is confirmed. Reaching the CUP of the next upgrade means that the
+            // current version made progress.
+            info!(
+                self.logger,
+                "Upgrading to {} before the health check completed, confirming the boot", version
+            );
+            self.confirm_upgraded_boot().await;
+        }
+        PendingUpgrade::new(self.replica_version.clone(), version.clone())
+            .persist(&self.orchestrator_data_directory)?;
+        let result = self.execute_upgrade(version).await;
+        // Executing the upgrade only returns if it failed.
+        if let Err(e) = PendingUpgrade::remove(&self.orchestrator_data_directory) {
+            warn!(self.logger, "Cannot remove the pending upgrade: {}", e);
+        }
+        result.map_err(OrchestratorError::from)
+    }
+
+    /// Return an error if the upgrade to the given version was rolled back
+    /// recently. Such an upgrade is only retried once the subnet confirmed the
+    /// version, or after `ROLLED_BACK_UPGRADE_RETRY_DELAY`.
+    fn ensure_not_rolled_back(&self, version: &ReplicaVersion) -> OrchestratorResult<()> {
+        match &self.rolled_back_upgrade {
+            Some(rolled_back) if rolled_back.blocks(version, SystemTime::now()) => {
+                let upgrade = rolled_back.upgrade();
+                Err(OrchestratorError::UpgradeError(format!(
+                    "The upgrade {} -> {} was rolled back, not retrying it yet",
+                    upgrade.from, upgrade.to
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Allow retrying a rolled back upgrade once the latest CUP, which
+    /// requires the given version, shows that the subnet makes progress on the
+    /// version of the upgrade.
+    async fn clear_rolled_back_upgrade_if_confirmed(
+        &mut self,
+        cup_version: &ReplicaVersion,
+        cup_height: Height,
+        cup_is_signed: bool,
+    ) {
+        let Some(rolled_back) = self.rolled_back_upgrade.as_mut() else {
+            return;
+        };
+        if !rolled_back.is_confirmed_by_cup(cup_version, cup_height, cup_is_signed) {
+            return;
+        }
+        let upgrade = rolled_back.upgrade();
+        info!(
+            self.logger,
+            "The subnet runs version {} since the upgrade {} -> {} was rolled back, retrying it",
+            upgrade.to,
+            upgrade.from,
+            upgrade.to
+        );
+        self.clear_rolled_back_upgrade().await;
+    }
+
+    /// Allow retrying a rolled back upgrade once `ROLLED_BACK_UPGRADE_RETRY_DELAY`
+    /// passed.
+    async fn clear_rolled_back_upgrade_if_expired(&mut self) {
+        let Some(rolled_back) = self.rolled_back_upgrade.as_ref() else {
+            return;
+        };
+        if !rolled_back.has_expired(SystemTime::now()) {
+            return;
+        }
+        let upgrade = rolled_back.upgrade();
+        info!(
+            self.logger,
+            "The upgrade {} -> {} was rolled back a while ago, allowing to retry it",
+            upgrade.from,
+            upgrade.to
+        );
+        self.clear_rolled_back_upgrade().await;
+    }
+
+    /// Forget the rolled back upgrade and delete its record.
+    async fn clear_rolled_back_upgrade(&mut self) {
+        let Some(rolled_back) = self.rolled_back_upgrade.take() else {
+            return;
+        };
+        let upgrade = rolled_back.upgrade();
+        if let Err(e) = PendingUpgrade::remove(&self.orchestrator_data_directory) {
+            warn!(self.logger, "Cannot remove the rolled back upgrade: {}", e);
+        }
+        self.metrics
+            .upgrade_rolled_back
+            .with_label_values(&[upgrade.from.as_ref(), upgrade.to.as_ref()])
+            .set(0);
+        self.metrics
+            .observe_boot_health_status(BootHealthStatus::Confirmed);
+        *self.boot_health.write().await = BootHealth::Confirmed;
+    }
+
     fn report_reboot_time(&self) -> OrchestratorResult<()> {
         let elapsed_time = self.get_time_since_last_reboot_trigger()?;
         self.metrics
@@ -138,6 +445,7 @@ impl Upgrade {
     /// Checks for a new release package, and if found, upgrades to this release
     /// package
     pub(crate) async fn check(&mut self) -> OrchestratorResult<Option<SubnetId>> {
+        self.clear_rolled_back_upgrade_if_expired().await;
         let latest_registry_version = self.registry.get_latest_version();
         // Determine the subnet_id using the local CUP.
         let (subnet_id, local_cup_proto, local_cup) = {
@@ -210,6 +518,13 @@ impl Upgrade {
                     }
                     // If no subnet is assigned to the node id, we're unassigned.
                     _ => {
+                        if self.boot_health_gate.is_some() {
+                            info!(
+                                self.logger,
+                                "No replica runs on unassigned nodes, confirming the boot"
+                            );
+                            self.confirm_upgraded_boot().await;
+                        }
                         self.check_for_upgrade_as_unassigned().await?;
                         return Ok(None);
                     }
@@ -279,6 +594,12 @@ impl Upgrade {
         let new_replica_version = self
             .registry
             .get_replica_version(subnet_id, cup_registry_version)?;
+        self.clear_rolled_back_upgrade_if_confirmed(
+            &new_replica_version,
+            latest_cup.height(),
+            latest_cup.is_signed(),
+        )
+        .await;
         if new_replica_version != self.replica_version {
             info!(
                 self.logger,
@@ -290,7 +611,8 @@ impl Upgrade {
             // Only downloads the new image if it doesn't already exists locally, i.e. it
             // was previously downloaded by `prepare_upgrade_if_scheduled()`, see
             // below.
-            self.execute_upgrade(&new_replica_version).await?;
+            self.execute_upgrade_with_health_check(&new_replica_version)
+                .await?;
             return Ok(Some(subnet_id));
         }
 
@@ -300,8 +622,35 @@ impl Upgrade {
         self.stop_replica_if_new_recovery_cup(&latest_cup, old_cup_height);
 
         // This will start a new replica process if none is running.
+        let replica_running = self.replica_process.lock().unwrap().is_running();
         self.ensure_replica_is_running(&self.replica_version, subnet_id)?;
 
+        // If we just upgraded, this will confirm the boot once the replica is
+        // healthy, or roll back the upgrade. The check is suspended while the
+        // subnet is halted or recovered from an unsigned CUP.
+        let subnet_record = self
+            .registry
+            .get_subnet_record(subnet_id, latest_registry_version)
+            .ok();
+        let subnet_halted = !latest_cup.is_signed()
+            || subnet_record
+                .as_ref()
+                .is_some_and(|record| record.is_halted || record.halt_at_cup_height);
+        let health_window = subnet_record.map(|record| {
+            health_window(
+                record.dkg_interval_length,
+                Duration::from_millis(record.initial_notary_delay_millis),
+                self.min_health_window,
+            )
+        });
+        self.check_boot_health(
+            replica_running,
+            subnet_halted,
+            latest_cup.height(),
+            health_window,
+        )
+        .await?;
+
         // This will trigger an image download if one is already scheduled but we did
         // not arrive at the corresponding CUP yet.
         self.prepare_upgrade_if_scheduled(subnet_id).await?;
@@ -379,9 +728,15 @@ impl Upgrade {
         &mut self,
         subnet_id: SubnetId,
     ) -> OrchestratorResult<()> {
+        // The bootloader doesn't accept a new image before the current boot
+        // is confirmed.
+        if self.boot_health_gate.is_some() {
+            return Ok(());
+        }
         let (expected_replica_version, registry_version) =
             self.registry.get_expected_replica_version(subnet_id)?;
         if expected_replica_version != self.replica_version {
+            self.ensure_not_rolled_back(&expected_replica_version)?;
             info!(
                 self.logger,
                 "Replica version upgrade detected at registry version {}: {} -> {}",
@@ -417,9 +772,8 @@ impl Upgrade {
             self.replica_version,
             replica_version
         );
-        self.execute_upgrade(&replica_version)
+        self.execute_upgrade_with_health_check(&replica_version)
             .await
-            .map_err(OrchestratorError::from)
     }
 
     /// Stop the current replica process.
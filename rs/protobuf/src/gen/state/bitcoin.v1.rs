@@ -184,6 +184,9 @@ pub enum Network {
     Testnet = 1,
     Mainnet = 2,
     Regtest = 3,
+    DogecoinMainnet = 4,
+    DogecoinTestnet = 5,
+    DogecoinRegtest = 6,
 }
 impl Network {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -196,6 +199,9 @@ impl Network {
             Network::Testnet => "NETWORK_TESTNET",
             Network::Mainnet => "NETWORK_MAINNET",
             Network::Regtest => "NETWORK_REGTEST",
+            Network::DogecoinMainnet => "NETWORK_DOGECOIN_MAINNET",
+            Network::DogecoinTestnet => "NETWORK_DOGECOIN_TESTNET",
+            Network::DogecoinRegtest => "NETWORK_DOGECOIN_REGTEST",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -205,6 +211,9 @@ impl Network {
             "NETWORK_TESTNET" => Some(Self::Testnet),
             "NETWORK_MAINNET" => Some(Self::Mainnet),
             "NETWORK_REGTEST" => Some(Self::Regtest),
+            "NETWORK_DOGECOIN_MAINNET" => Some(Self::DogecoinMainnet),
+            "NETWORK_DOGECOIN_TESTNET" => Some(Self::DogecoinTestnet),
+            "NETWORK_DOGECOIN_REGTEST" => Some(Self::DogecoinRegtest),
             _ => None,
         }
     }
@@ -169,6 +169,7 @@ pub mod wasm_method {
         CanisterHeartbeat = 6,
         CanisterGlobalTimer = 8,
         CanisterOnLowWasmMemory = 9,
+        CanisterOnLowCycles = 10,
     }
     impl SystemMethod {
         /// String value of the enum field names used in the ProtoBuf definition.
@@ -188,6 +189,7 @@ pub mod wasm_method {
                 SystemMethod::CanisterOnLowWasmMemory => {
                     "SYSTEM_METHOD_CANISTER_ON_LOW_WASM_MEMORY"
                 }
+                SystemMethod::CanisterOnLowCycles => "SYSTEM_METHOD_CANISTER_ON_LOW_CYCLES",
             }
         }
         /// Creates an enum from field names used in the ProtoBuf definition.
@@ -202,6 +204,7 @@ pub mod wasm_method {
                 "SYSTEM_METHOD_CANISTER_HEARTBEAT" => Some(Self::CanisterHeartbeat),
                 "SYSTEM_METHOD_CANISTER_GLOBAL_TIMER" => Some(Self::CanisterGlobalTimer),
                 "SYSTEM_METHOD_CANISTER_ON_LOW_WASM_MEMORY" => Some(Self::CanisterOnLowWasmMemory),
+                "SYSTEM_METHOD_CANISTER_ON_LOW_CYCLES" => Some(Self::CanisterOnLowCycles),
                 _ => None,
             }
         }
@@ -380,6 +383,7 @@ pub mod execution_task {
         Heartbeat = 1,
         Timer = 2,
         OnLowWasmMemory = 3,
+        OnLowCycles = 4,
     }
     impl CanisterTask {
         /// String value of the enum field names used in the ProtoBuf definition.
@@ -392,6 +396,7 @@ pub mod execution_task {
                 CanisterTask::Heartbeat => "CANISTER_TASK_HEARTBEAT",
                 CanisterTask::Timer => "CANISTER_TASK_TIMER",
                 CanisterTask::OnLowWasmMemory => "CANISTER_TASK_ON_LOW_WASM_MEMORY",
+                CanisterTask::OnLowCycles => "CANISTER_TASK_ON_LOW_CYCLES",
             }
         }
         /// Creates an enum from field names used in the ProtoBuf definition.
@@ -401,6 +406,7 @@ pub mod execution_task {
                 "CANISTER_TASK_HEARTBEAT" => Some(Self::Heartbeat),
                 "CANISTER_TASK_TIMER" => Some(Self::Timer),
                 "CANISTER_TASK_ON_LOW_WASM_MEMORY" => Some(Self::OnLowWasmMemory),
+                "CANISTER_TASK_ON_LOW_CYCLES" => Some(Self::OnLowCycles),
                 _ => None,
             }
         }
@@ -537,6 +543,14 @@ pub struct TotalQueryStats {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MethodTotalQueryStats {
+    #[prost(string, tag = "1")]
+    pub method_name: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub stats: ::core::option::Option<TotalQueryStats>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct WasmChunkData {
     #[prost(bytes = "vec", tag = "1")]
     pub hash: ::prost::alloc::vec::Vec<u8>,
@@ -698,6 +712,16 @@ pub struct CanisterStateBits {
     pub long_execution_mode: i32,
     #[prost(uint64, optional, tag = "50")]
     pub wasm_memory_threshold: ::core::option::Option<u64>,
+    /// Multiple of the freezing threshold below which the `canister_on_low_cycles`
+    /// hook is activated.
+    #[prost(uint64, tag = "53")]
+    pub low_cycles_threshold_multiplier: u64,
+    #[prost(enumeration = "OnLowCyclesHookStatus", tag = "54")]
+    pub on_low_cycles_hook_status: i32,
+    /// Statistics on query execution for entire lifetime of canister, broken down
+    /// by query method.
+    #[prost(message, repeated, tag = "55")]
+    pub total_method_query_stats: ::prost::alloc::vec::Vec<MethodTotalQueryStats>,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -870,3 +894,39 @@ impl LongExecutionMode {
         }
     }
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum OnLowCyclesHookStatus {
+    Unspecified = 0,
+    ConditionNotSatisfied = 1,
+    Ready = 2,
+    Executed = 3,
+}
+impl OnLowCyclesHookStatus {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            OnLowCyclesHookStatus::Unspecified => "ON_LOW_CYCLES_HOOK_STATUS_UNSPECIFIED",
+            OnLowCyclesHookStatus::ConditionNotSatisfied => {
+                "ON_LOW_CYCLES_HOOK_STATUS_CONDITION_NOT_SATISFIED"
+            }
+            OnLowCyclesHookStatus::Ready => "ON_LOW_CYCLES_HOOK_STATUS_READY",
+            OnLowCyclesHookStatus::Executed => "ON_LOW_CYCLES_HOOK_STATUS_EXECUTED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ON_LOW_CYCLES_HOOK_STATUS_UNSPECIFIED" => Some(Self::Unspecified),
+            "ON_LOW_CYCLES_HOOK_STATUS_CONDITION_NOT_SATISFIED" => {
+                Some(Self::ConditionNotSatisfied)
+            }
+            "ON_LOW_CYCLES_HOOK_STATUS_READY" => Some(Self::Ready),
+            "ON_LOW_CYCLES_HOOK_STATUS_EXECUTED" => Some(Self::Executed),
+            _ => None,
+        }
+    }
+}
@@ -1243,6 +1243,22 @@ pub struct CanisterQueryStats {
     pub ingress_payload_size: u64,
     #[prost(uint64, tag = "5")]
     pub egress_payload_size: u64,
+    #[prost(message, repeated, tag = "6")]
+    pub method_stats: ::prost::alloc::vec::Vec<MethodQueryStats>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MethodQueryStats {
+    #[prost(string, tag = "1")]
+    pub method_name: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub num_calls: u32,
+    #[prost(uint64, tag = "3")]
+    pub num_instructions: u64,
+    #[prost(uint64, tag = "4")]
+    pub ingress_payload_size: u64,
+    #[prost(uint64, tag = "5")]
+    pub egress_payload_size: u64,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
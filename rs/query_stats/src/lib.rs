@@ -49,7 +49,7 @@ pub fn init_query_stats(
 /// the payload builder interface.
 pub struct QueryStatsCollector {
     log: ReplicaLogger,
-    pub current_query_stats: Mutex<BTreeMap<CanisterId, QueryStats>>, // Needs to be pub for testing
+    pub current_query_stats: Mutex<BTreeMap<CanisterId, CanisterQueryStats>>, // Needs to be pub for testing
     current_epoch: RwLock<Option<QueryStatsEpoch>>,
     sender: Sender<LocalQueryStats>,
     query_stats_epoch_length: u64,
@@ -88,10 +88,7 @@ impl QueryStatsCollector {
         // Epoch changed, send stats from previous epoch to block maker
         match self.sender.try_send(LocalQueryStats {
             epoch: previous_epoch,
-            stats: previous_stats
-                .into_iter()
-                .map(|(canister_id, stats)| CanisterQueryStats { canister_id, stats })
-                .collect(),
+            stats: previous_stats.into_values().collect(),
         }) {
             Ok(()) => (),
             Err(TrySendError::Full(_)) => {
@@ -116,7 +113,27 @@ impl QueryStatsCollector {
             .set(new_epoch.get() as i64);
     }
 
-    pub fn register_query_statistics(&self, canister_id: CanisterId, stats: &QueryStats) {
+    /// Registers the stats of a single execution of the query method `method_name`.
+    ///
+    /// If `method_name` is `None`, the stats only count towards the stats of the whole canister.
+    pub fn register_query_statistics(
+        &self,
+        canister_id: CanisterId,
+        method_name: Option<&str>,
+        stats: &QueryStats,
+    ) {
+        let mut canister_stats = CanisterQueryStats::new(canister_id);
+        match method_name {
+            Some(method_name) => canister_stats.saturating_accumulate_method(method_name, stats),
+            None => canister_stats.stats.saturating_accumulate(stats),
+        }
+        self.register_canister_query_statistics(&canister_stats);
+    }
+
+    /// Registers previously collected stats of a canister, including their breakdown per method.
+    pub fn register_canister_query_statistics(&self, canister_stats: &CanisterQueryStats) {
+        let canister_id = canister_stats.canister_id;
+        let stats = &canister_stats.stats;
         let current_epoch = *self.current_epoch.read().unwrap();
         if current_epoch.is_none() {
             info!(
@@ -131,8 +148,8 @@ impl QueryStatsCollector {
         let mut state = self.current_query_stats.lock().unwrap();
         state
             .entry(canister_id)
-            .or_default()
-            .saturating_accumulate(stats);
+            .or_insert_with(|| CanisterQueryStats::new(canister_id))
+            .saturating_accumulate(canister_stats);
 
         self.metrics.query_stats_collector.add(stats);
         self.metrics
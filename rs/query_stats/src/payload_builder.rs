@@ -13,7 +13,10 @@ use ic_logger::{error, warn, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
 use ic_replicated_state::ReplicatedState;
 use ic_types::{
-    batch::{LocalQueryStats, QueryStats, QueryStatsPayload, ValidationContext},
+    batch::{
+        LocalQueryStats, QueryStats, QueryStatsPayload, ValidationContext,
+        MAX_QUERY_STATS_METHODS_PER_CANISTER,
+    },
     epoch_from_height, CanisterId, Height, NodeId, NumBytes, QueryStatsEpoch,
 };
 use std::{
@@ -270,6 +273,20 @@ impl QueryStatsPayloadBuilderImpl {
             }
         }
 
+        // Check that the number of methods per canister is bounded
+        if let Some(stat) = payload
+            .stats
+            .iter()
+            .find(|stat| stat.method_stats.len() > MAX_QUERY_STATS_METHODS_PER_CANISTER)
+        {
+            return Err(invalid_artifact(
+                InvalidQueryStatsPayloadReason::TooManyMethods {
+                    canister_id: stat.canister_id,
+                    num_methods: stat.method_stats.len(),
+                },
+            ));
+        }
+
         // Get the previous ids, that have been already reported by this node in the epoch
         // NOTE: This also checks that the epoch that is being reported has not been aggregated yet
         let previous_ids = self.get_previous_ids(
@@ -427,7 +444,7 @@ mod tests {
         RegistryVersion,
     };
     use ic_types_test_utils::ids::{canister_test_id, node_test_id};
-    use std::{ops::Range, time::Duration};
+    use std::{collections::BTreeMap, ops::Range, time::Duration};
 
     const MAX_PAYLOAD_SIZE: NumBytes = NumBytes::new(1024 * 1024);
 
@@ -598,6 +615,7 @@ mod tests {
             stats: vec![CanisterQueryStats {
                 canister_id: canister_test_id(0),
                 stats: QueryStats::default(),
+                method_stats: BTreeMap::new(),
             }],
         }
         .serialize_with_limit(MAX_PAYLOAD_SIZE);
@@ -639,6 +657,7 @@ mod tests {
             stats: vec![CanisterQueryStats {
                 canister_id: canister_test_id(0),
                 stats: QueryStats::default(),
+                method_stats: BTreeMap::new(),
             }],
         }
         .serialize_with_limit(MAX_PAYLOAD_SIZE);
@@ -679,6 +698,7 @@ mod tests {
             stats: vec![CanisterQueryStats {
                 canister_id: canister_test_id(0),
                 stats: QueryStats::default(),
+                method_stats: BTreeMap::new(),
             }],
         }
         .serialize_with_limit(MAX_PAYLOAD_SIZE);
@@ -763,6 +783,49 @@ mod tests {
         }
     }
 
+    /// Test that a payload with too many methods for a canister won't validate
+    #[test]
+    fn too_many_methods_test() {
+        let test_stats = test_epoch_stats(0, 0);
+        let state = test_state(RawQueryStats::default());
+        let payload_builder = setup_payload_builder_impl(state, test_stats);
+        let validation_context = test_validation_context();
+        let proposal_context = test_proposal_context(&validation_context);
+
+        let num_methods = MAX_QUERY_STATS_METHODS_PER_CANISTER + 1;
+        let payload = QueryStatsPayload {
+            epoch: QueryStatsEpoch::new(0),
+            proposer: node_test_id(1),
+            stats: vec![CanisterQueryStats {
+                canister_id: canister_test_id(0),
+                stats: QueryStats::default(),
+                method_stats: (0..num_methods)
+                    .map(|idx| (format!("method_{}", idx), QueryStats::default()))
+                    .collect(),
+            }],
+        }
+        .serialize_with_limit(MAX_PAYLOAD_SIZE);
+
+        let validation_result =
+            payload_builder.validate_payload_impl(Height::new(1), &proposal_context, &payload, &[]);
+
+        match validation_result {
+            Err(ValidationError::InvalidArtifact(
+                InvalidPayloadReason::InvalidQueryStatsPayload(
+                    InvalidQueryStatsPayloadReason::TooManyMethods {
+                        canister_id,
+                        num_methods: reported,
+                    },
+                ),
+            )) if canister_id == canister_test_id(0) && reported == num_methods => (),
+            Err(err) => panic!(
+                "QueryStatsPayload had too many methods, yet instead got error {:?}",
+                err
+            ),
+            Ok(_) => panic!("QueryStatsPayload had too many methods, yet got validated"),
+        }
+    }
+
     fn test_validation_context() -> ValidationContext {
         ValidationContext {
             registry_version: RegistryVersion::new(0),
@@ -825,14 +888,18 @@ mod tests {
         LocalQueryStats {
             epoch: QueryStatsEpoch::new(epoch),
             stats: (0..num_stats)
-                .map(|id| CanisterQueryStats {
-                    canister_id: canister_test_id(id),
-                    stats: QueryStats {
-                        num_calls: 1,
-                        num_instructions: 1000,
-                        ingress_payload_size: 1000,
-                        egress_payload_size: 1000,
-                    },
+                .map(|id| {
+                    let mut stats = CanisterQueryStats::new(canister_test_id(id));
+                    stats.saturating_accumulate_method(
+                        "query",
+                        &QueryStats {
+                            num_calls: 1,
+                            num_instructions: 1000,
+                            ingress_payload_size: 1000,
+                            egress_payload_size: 1000,
+                        },
+                    );
+                    stats
                 })
                 .collect(),
         }
@@ -850,7 +917,7 @@ mod tests {
                 query_stats.epoch,
                 query_stats.stats[range]
                     .iter()
-                    .map(|stat| (stat.canister_id, stat.stats.clone()))
+                    .map(|stat| (stat.canister_id, stat.clone()))
                     .collect(),
             )]
             .into_iter()
@@ -11,10 +11,10 @@
 //!     module, until it can be aggregated into canisters.
 //!
 //! The state in [`RawQueryStats`] is a map of
-//! [`NodeId`] -> [`QueryStatsEpoch`] -> [`CanisterId`] -> [`QueryStats`],
+//! [`NodeId`] -> [`QueryStatsEpoch`] -> [`CanisterId`] -> [`CanisterQueryStats`],
 //! as well as a counter `highest_aggregated_epoch` which indicates up until which epoch the state has already
 //! been aggregated.
-//! Within this module, we call the [`CanisterId`] -> [`QueryStats`] part the record.
+//! Within this module, we call the [`CanisterId`] -> [`CanisterQueryStats`] part the record.
 //! The record with the highest [`QueryStatsEpoch`] is called the current record, all other records
 //! are called fully submitted records.
 //!
@@ -39,6 +39,8 @@
 //!
 //! If this is the case, we calculate the median of each of the statistics value for each [`CanisterId`]
 //! and add it to the canister's statistic.
+//! The same is done for the statistics of each query method of the canister, where a node that did not
+//! report a method counts as having reported empty statistics for it.
 //! Now we increase `highest_aggregated_epoch` by `1`.
 //!
 //! # Inclusion of partial records into aggregation
@@ -71,13 +73,16 @@ use crate::metrics::{QueryStatsAggregatorMetrics, CRITICAL_ERROR_AGGREGATION_FAI
 use ic_logger::{error, info, ReplicaLogger};
 use ic_replicated_state::ReplicatedState;
 use ic_types::{
-    batch::{QueryStats, QueryStatsPayload, RawQueryStats},
+    batch::{
+        CanisterQueryStats, QueryStats, QueryStatsPayload, RawQueryStats, TotalQueryStats,
+        MAX_QUERY_STATS_METHODS_PER_CANISTER,
+    },
     consensus::get_faults_tolerated,
     CanisterId, NodeId, QueryStatsEpoch,
 };
 use std::{
     cmp::Ordering,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     ops::{Add, Div},
 };
 
@@ -124,27 +129,87 @@ fn aggregate_query_stats(stats: Vec<&QueryStats>) -> QueryStats {
     }
 }
 
+/// Aggregate the query stats reported for a canister, including the stats of each of its methods.
+///
+/// `stats` needs to contain one entry per node that has a complete record for the epoch.
+/// Methods that have not been reported by a node are considered empty for that node.
+/// Methods whose aggregated stats are empty are omitted.
+fn aggregate_canister_query_stats(
+    canister_id: CanisterId,
+    stats: Vec<&CanisterQueryStats>,
+) -> CanisterQueryStats {
+    let empty_stats = QueryStats::default();
+    let method_names: BTreeSet<&String> = stats
+        .iter()
+        .flat_map(|stats| stats.method_stats.keys())
+        .collect();
+
+    let method_stats = method_names
+        .into_iter()
+        .map(|method_name| {
+            let method_stats = stats
+                .iter()
+                .map(|stats| stats.method_stats.get(method_name).unwrap_or(&empty_stats))
+                .collect();
+            (method_name.clone(), aggregate_query_stats(method_stats))
+        })
+        .filter(|(_, aggregated_stats)| *aggregated_stats != empty_stats)
+        .collect();
+
+    CanisterQueryStats {
+        canister_id,
+        stats: aggregate_query_stats(stats.iter().map(|stats| &stats.stats).collect()),
+        method_stats,
+    }
+}
+
 /// Aggregate given query stats and into each canister's state.
 fn apply_query_stats_to_canister(
-    aggregated_stats: &QueryStats,
-    canister_id: CanisterId,
+    aggregated_stats: &CanisterQueryStats,
     num_nodes: usize,
     state: &mut ReplicatedState,
     logger: &ReplicaLogger,
 ) {
+    let canister_id = aggregated_stats.canister_id;
     // Note that the use of the number of nodes in the subnet like this does not handle the case that
     // the number of machines in the subnet might have changed throughout an epoch.
     // Given that subnet topology changes are an infrequent event, we tolerate this occasional inaccuracy here.
     let num_nodes = num_nodes as u128;
     if let Some(canister_state) = state.canister_state_mut(&canister_id) {
-        let canister_query_stats = &mut canister_state.scheduler_state.total_query_stats;
-        canister_query_stats.num_calls += aggregated_stats.num_calls as u128 * num_nodes;
-        canister_query_stats.num_instructions +=
-            aggregated_stats.num_instructions as u128 * num_nodes;
-        canister_query_stats.ingress_payload_size +=
-            aggregated_stats.ingress_payload_size as u128 * num_nodes;
-        canister_query_stats.egress_payload_size +=
-            aggregated_stats.egress_payload_size as u128 * num_nodes;
+        let scheduler_state = &mut canister_state.scheduler_state;
+        scheduler_state
+            .total_query_stats
+            .add_scaled(&aggregated_stats.stats, num_nodes);
+
+        let total_method_query_stats = &mut scheduler_state.total_method_query_stats;
+        for (method_name, stats) in &aggregated_stats.method_stats {
+            if let Some(total_stats) = total_method_query_stats.get_mut(method_name) {
+                total_stats.add_scaled(stats, num_nodes);
+                continue;
+            }
+
+            let mut new_total_stats = TotalQueryStats::default();
+            new_total_stats.add_scaled(stats, num_nodes);
+
+            // If the maximum number of methods is tracked already, the new method replaces the
+            // least used one, but only if it has been used more.
+            if total_method_query_stats.len() >= MAX_QUERY_STATS_METHODS_PER_CANISTER {
+                let usage = |stats: &TotalQueryStats| (stats.num_calls, stats.num_instructions);
+                match total_method_query_stats
+                    .iter()
+                    .min_by_key(|(_, total_stats)| usage(total_stats))
+                    .map(|(method_name, total_stats)| (method_name.clone(), usage(total_stats)))
+                {
+                    Some((least_used_method, least_usage))
+                        if least_usage < usage(&new_total_stats) =>
+                    {
+                        total_method_query_stats.remove(&least_used_method);
+                    }
+                    _ => continue,
+                }
+            }
+            total_method_query_stats.insert(method_name.clone(), new_total_stats);
+        }
     } else {
         info!(
             logger,
@@ -206,7 +271,7 @@ fn process_payload(
         query_stats_received.saturating_accumulate(&message.stats);
 
         // Insert the record into the state machine
-        let previous_record = stats.insert(message.canister_id, message.stats.clone());
+        let previous_record = stats.insert(message.canister_id, message.clone());
 
         // If there was a previous record, we have received a set of statistics twice, which is likely a bug
         if previous_record.is_some() {
@@ -336,17 +401,17 @@ fn try_aggregate_one_epoch(
     let mut empty_stats_counter: usize = 0;
     let mut total_stats_counter: usize = 0;
 
-    let empty_stats = QueryStats::default();
     let mut query_stats_to_be_applied = vec![];
     for (canister_id, mut stats) in records {
+        let empty_stats = CanisterQueryStats::new(canister_id);
         let num_empty_stats = num_nodes_with_stats.saturating_sub(stats.len());
         stats.append(&mut vec![&empty_stats; num_empty_stats]);
 
         empty_stats_counter += num_empty_stats;
         total_stats_counter += stats.len();
 
-        let aggregated_stats = aggregate_query_stats(stats);
-        query_stats_to_be_applied.push((canister_id, aggregated_stats));
+        let aggregated_stats = aggregate_canister_query_stats(canister_id, stats);
+        query_stats_to_be_applied.push(aggregated_stats);
     }
 
     metrics
@@ -357,16 +422,10 @@ fn try_aggregate_one_epoch(
         .add(total_stats_counter as i64);
 
     let mut delivered_query_stats = QueryStats::default();
-    for (canister_id, aggregated_stats) in query_stats_to_be_applied {
-        delivered_query_stats.saturating_accumulate(&aggregated_stats);
-
-        apply_query_stats_to_canister(
-            &aggregated_stats,
-            canister_id,
-            num_nodes,
-            replicated_state,
-            logger,
-        );
+    for aggregated_stats in query_stats_to_be_applied {
+        delivered_query_stats.saturating_accumulate(&aggregated_stats.stats);
+
+        apply_query_stats_to_canister(&aggregated_stats, num_nodes, replicated_state, logger);
     }
 
     metrics.query_stats_delivered.add(&delivered_query_stats);
@@ -442,7 +501,7 @@ pub(crate) fn get_stats_for_node_id_and_epoch<'a>(
     state: &'a RawQueryStats,
     node_id: &NodeId,
     epoch: &QueryStatsEpoch,
-) -> Option<&'a BTreeMap<CanisterId, QueryStats>> {
+) -> Option<&'a BTreeMap<CanisterId, CanisterQueryStats>> {
     state
         .stats
         .get(node_id)
@@ -454,10 +513,7 @@ mod tests {
     use super::*;
     use ic_logger::replica_logger::no_op_logger;
     use ic_test_utilities_state::{CanisterStateBuilder, ReplicatedStateBuilder};
-    use ic_types::{
-        batch::{CanisterQueryStats, TotalQueryStats},
-        NodeId, QueryStatsEpoch,
-    };
+    use ic_types::{NodeId, QueryStatsEpoch};
     use ic_types_test_utils::ids::{canister_test_id, node_test_id};
 
     #[test]
@@ -514,6 +570,80 @@ mod tests {
         assert_eq!(stats, stats2)
     }
 
+    #[test]
+    fn method_aggregation_test() {
+        let state = test_message_processing(4, 1);
+        let stats = get_canister_query_stats(&state, &canister_test_id(1))
+            .expect("Expected the stats to be aggregated already");
+        let method_stats = get_canister_method_query_stats(&state, &canister_test_id(1));
+
+        assert_eq!(method_stats.len(), 1);
+        assert_eq!(method_stats.get("query"), Some(&stats));
+    }
+
+    #[test]
+    fn methods_reported_by_few_nodes_are_not_aggregated() {
+        let mut state = test_state();
+
+        for id in 1..=3 {
+            let mut stats = test_payload(node_test_id(id), 0, 100);
+            if id == 1 {
+                stats.stats[0].method_stats.insert(
+                    "rarely_reported".to_string(),
+                    QueryStats {
+                        num_calls: 1,
+                        num_instructions: 1000,
+                        ..Default::default()
+                    },
+                );
+            }
+            deliver_stats(stats, &mut state);
+        }
+        for id in 1..=3 {
+            deliver_stats(test_payload(node_test_id(id), 1, 0), &mut state);
+        }
+
+        let method_stats = get_canister_method_query_stats(&state, &canister_test_id(1));
+        assert_eq!(method_stats.len(), 1);
+        assert_eq!(method_stats.get("query").unwrap().num_instructions, 400);
+    }
+
+    #[test]
+    fn applied_method_stats_evict_least_used_method() {
+        let mut state = test_state();
+        let canister_stats = |method_name: &str, num_calls| {
+            let mut stats = CanisterQueryStats::new(canister_test_id(1));
+            stats.saturating_accumulate_method(
+                method_name,
+                &QueryStats {
+                    num_calls,
+                    num_instructions: 1000,
+                    ..Default::default()
+                },
+            );
+            stats
+        };
+        for idx in 0..MAX_QUERY_STATS_METHODS_PER_CANISTER {
+            let num_calls = if idx == 0 { 1 } else { 2 };
+            let stats = canister_stats(&format!("method_{}", idx), num_calls);
+            apply_query_stats_to_canister(&stats, 4, &mut state, &no_op_logger());
+        }
+
+        // Used less than every tracked method.
+        let stats = canister_stats("rare", 1);
+        apply_query_stats_to_canister(&stats, 4, &mut state, &no_op_logger());
+        let method_stats = get_canister_method_query_stats(&state, &canister_test_id(1));
+        assert!(!method_stats.contains_key("rare"));
+
+        // Used more than the least used method.
+        let stats = canister_stats("popular", 2);
+        apply_query_stats_to_canister(&stats, 4, &mut state, &no_op_logger());
+        let method_stats = get_canister_method_query_stats(&state, &canister_test_id(1));
+        assert_eq!(method_stats.len(), MAX_QUERY_STATS_METHODS_PER_CANISTER);
+        assert!(!method_stats.contains_key("method_0"));
+        assert_eq!(method_stats["popular"].num_calls, 8);
+    }
+
     fn test_message_processing(num_epoch0_msgs: usize, next_epoch: u64) -> ReplicatedState {
         let mut state = test_state();
 
@@ -561,17 +691,19 @@ mod tests {
     }
 
     fn test_payload(proposer: NodeId, epoch: u64, insts: u64) -> QueryStatsPayload {
+        let stats = QueryStats {
+            num_calls: 0,
+            num_instructions: insts,
+            ingress_payload_size: 0,
+            egress_payload_size: 0,
+        };
         QueryStatsPayload {
             epoch: QueryStatsEpoch::from(epoch),
             proposer,
             stats: vec![CanisterQueryStats {
                 canister_id: canister_test_id(1),
-                stats: QueryStats {
-                    num_calls: 0,
-                    num_instructions: insts,
-                    ingress_payload_size: 0,
-                    egress_payload_size: 0,
-                },
+                stats: stats.clone(),
+                method_stats: BTreeMap::from([("query".to_string(), stats)]),
             }],
         }
     }
@@ -584,4 +716,19 @@ mod tests {
             .canister_state(canister_id)
             .map(|canister_state| canister_state.scheduler_state.total_query_stats.clone())
     }
+
+    fn get_canister_method_query_stats(
+        state: &ReplicatedState,
+        canister_id: &CanisterId,
+    ) -> BTreeMap<String, TotalQueryStats> {
+        state
+            .canister_state(canister_id)
+            .map(|canister_state| {
+                canister_state
+                    .scheduler_state
+                    .total_method_query_stats
+                    .clone()
+            })
+            .unwrap_or_default()
+    }
 }
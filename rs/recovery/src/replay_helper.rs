@@ -31,6 +31,9 @@ pub async fn replay(
         replay_until_height,
         subcmd,
         data_root: Some(data_root),
+        trace_out: None,
+        query_console: false,
+        did_file: Vec::new(),
     };
     // Since replay output needs to be persisted anyway in case the recovery process
     // is restarted, we avoid declaring a return value and moving out of the
@@ -27,17 +27,25 @@ fn impl_proposal_metadata(ast: &syn::DeriveInput) -> TokenStream {
             }
 
             fn proposer_and_sender(&self, sender: Sender) -> (NeuronId, Sender) {
-                let use_test_neuron = self.test_neuron_proposer || (self.dry_run && matches!(sender, Sender::Anonymous));
+                let use_test_neuron = self.test_neuron_proposer || (self.is_dry_run() && matches!(sender, Sender::Anonymous));
                 get_proposer_and_sender(self.proposer.clone(), sender, use_test_neuron)
             }
 
             fn is_dry_run(&self) -> bool {
-                self.dry_run
+                self.dry_run || self.output_payload.is_some()
             }
 
             fn is_json(&self) -> bool {
                 self.json
             }
+
+            fn output_payload(&self) -> Option<&std::path::Path> {
+                self.output_payload.as_deref()
+            }
+
+            fn local_registry_store(&self) -> Option<&std::path::Path> {
+                self.local_registry_store.as_deref()
+            }
         }
     };
     gen.into()
@@ -94,6 +102,17 @@ pub fn derive_common_proposal_fields(_: TokenStream, item: TokenStream) -> Token
                             /// If set, JSON output will be printed for --dry-run
                             #[clap(long)]
                             pub json: bool,
+
+                            /// If set, the Candid encoding of the proposal action is written
+                            /// to this file and its SHA-256 hash is printed. Implies --dry-run.
+                            #[clap(long)]
+                            pub output_payload: Option<std::path::PathBuf>,
+
+                            /// If set, the proposal payload is validated against the registry
+                            /// local store in this directory (see update-registry-local-store)
+                            /// before the proposal is written or submitted.
+                            #[clap(long)]
+                            pub local_registry_store: Option<std::path::PathBuf>,
                     });
                     stream.extend(gen);
                     stream.extend(group.stream());
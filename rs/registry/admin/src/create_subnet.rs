@@ -326,6 +326,8 @@ mod tests {
             subnet_type: SubnetType::Application,
             test_neuron_proposer: false,
             dry_run: false,
+            output_payload: None,
+            local_registry_store: None,
             json: true,
             start_as_nns: false,
             is_halted: false,
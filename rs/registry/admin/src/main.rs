@@ -21,7 +21,7 @@ use ic_crypto_utils_threshold_sig_der::{
 };
 use ic_http_utils::file_downloader::{check_file_hash, FileDownloader};
 use ic_interfaces_registry::{RegistryClient, RegistryDataProvider};
-use ic_management_canister_types::CanisterInstallMode;
+use ic_management_canister_types::{CanisterInstallMode, NodeMetrics, NodeMetricsHistoryResponse};
 use ic_nervous_system_clients::{
     canister_id_record::CanisterIdRecord, canister_status::CanisterStatusResult,
 };
@@ -78,7 +78,7 @@ use ic_protobuf::registry::{
     firewall::v1::{FirewallConfig, FirewallRule, FirewallRuleSet},
     node::v1::NodeRecord,
     node_operator::v1::{NodeOperatorRecord, RemoveNodeOperatorsPayload},
-    node_rewards::v2::{NodeRewardRate, UpdateNodeRewardsTableProposalPayload},
+    node_rewards::v2::{NodeRewardRate, NodeRewardsTable, UpdateNodeRewardsTableProposalPayload},
     provisional_whitelist::v1::ProvisionalWhitelist as ProvisionalWhitelistProto,
     replica_version::v1::{BlessedReplicaVersions, ReplicaVersionRecord},
     routing_table::v1::{CanisterMigrations, RoutingTable},
@@ -99,14 +99,17 @@ use ic_registry_keys::{
     make_node_operator_record_key, make_node_record_key, make_provisional_whitelist_record_key,
     make_replica_version_key, make_routing_table_record_key, make_subnet_list_record_key,
     make_subnet_record_key, make_unassigned_nodes_config_record_key, FirewallRulesScope,
-    API_BOUNDARY_NODE_RECORD_KEY_PREFIX, NODE_OPERATOR_RECORD_KEY_PREFIX, NODE_REWARDS_TABLE_KEY,
-    ROOT_SUBNET_ID_KEY,
+    API_BOUNDARY_NODE_RECORD_KEY_PREFIX, DATA_CENTER_KEY_PREFIX, NODE_OPERATOR_RECORD_KEY_PREFIX,
+    NODE_RECORD_KEY_PREFIX, NODE_REWARDS_TABLE_KEY, ROOT_SUBNET_ID_KEY,
 };
 use ic_registry_local_store::{
     Changelog, ChangelogEntry, KeyMutation, LocalStoreImpl, LocalStoreWriter,
 };
 use ic_registry_nns_data_provider::registry::RegistryCanister;
 use ic_registry_nns_data_provider_wrappers::{CertifiedNnsDataProvider, NnsDataProvider};
+use ic_registry_node_provider_rewards::{
+    calculate_rewards_v1, node_failure_stats_from_metrics_history, NodeFailureStats,
+};
 use ic_registry_routing_table::{
     CanisterIdRange, CanisterMigrations as OtherCanisterMigrations,
     RoutingTable as OtherRoutingTable,
@@ -124,6 +127,7 @@ use ic_types::{
 use indexmap::IndexMap;
 use itertools::izip;
 use maplit::hashmap;
+use proposal_payload::{execute_nns_function_action, process_proposal_action};
 use prost::Message;
 use recover_subnet::ProposeToUpdateRecoveryCupCmd;
 use registry_canister::mutations::{
@@ -179,6 +183,7 @@ extern crate chrono;
 
 mod create_subnet;
 mod helpers;
+mod proposal_payload;
 mod recover_subnet;
 mod types;
 mod update_subnet;
@@ -378,6 +383,9 @@ enum SubCommand {
     GetNodeRewardsTable,
     /// Submit a proposal to update the node rewards table
     ProposeToUpdateNodeRewardsTable(ProposeToUpdateNodeRewardsTableCmd),
+    /// Simulate the performance-based monthly rewards of each node and Node
+    /// Provider for the given node metrics
+    SimulateNodeProviderRewards(SimulateNodeProviderRewardsCmd),
     /// Submit a proposal to update the unassigned nodes. This subcommand is obsolete; please use
     /// `ProposeToDeployGuestosToAllUnassignedNodes` or `ProposeToUpdateSshReadonlyAccessForAllUnassignedNodes` instead.
     ProposeToUpdateUnassignedNodesConfig(ProposeToUpdateUnassignedNodesConfigCmd),
@@ -2175,6 +2183,19 @@ impl ProposalPayload<UpdateNodeRewardsTableProposalPayload> for ProposeToUpdateN
     }
 }
 
+/// Sub-command to simulate the performance-based node provider rewards.
+#[derive(Parser)]
+struct SimulateNodeProviderRewardsCmd {
+    /// A JSON file with the `node_metrics_history` of each subnet, as returned
+    /// by the management canister. The oldest entry of each subnet serves as
+    /// the baseline of the simulated period.
+    ///
+    /// Example:
+    /// '{ "<subnet id>": [{ "timestamp_nanos": 0, "node_metrics": [{ "node_id": "<node id>", "num_blocks_proposed_total": 10, "num_block_failures_total": 1 }] }] }'
+    #[clap(long)]
+    pub node_metrics_history: PathBuf,
+}
+
 /// Sub-command to fetch a `NodeOperatorRecord` from the registry.
 #[derive(Parser)]
 struct GetNodeOperatorCmd {
@@ -2879,6 +2900,8 @@ impl TryFrom<ProposeToCreateServiceNervousSystemCmd> for CreateServiceNervousSys
             summary: _,
             summary_file: _,
             dry_run: _,
+            output_payload: _,
+            local_registry_store: _,
             json: _,
         } = cmd;
 
@@ -3113,6 +3136,9 @@ async fn propose_to_create_service_nervous_system(
         action,
     };
     print_proposal(&proposal, &cmd);
+    if let Some(action) = &proposal.action {
+        process_proposal_action(action, &cmd);
+    }
 
     if is_dry_run {
         return;
@@ -4490,6 +4516,9 @@ async fn main() {
                     .expect("Failed to serialize the rewards table to JSON")
             );
         }
+        SubCommand::SimulateNodeProviderRewards(cmd) => {
+            simulate_node_provider_rewards(reachable_nns_urls, cmd).await;
+        }
         SubCommand::ProposeToUpdateNodeRewardsTable(cmd) => {
             let (proposer, sender) = cmd.proposer_and_sender(sender);
             propose_external_proposal_from_command(
@@ -4686,6 +4715,13 @@ async fn main() {
             // Custom rendering to make it easier to debug your command
             if cmd.is_dry_run() {
                 let payload = cmd.payload(&agent).await;
+                process_proposal_action(
+                    &execute_nns_function_action(
+                        NnsFunction::InsertSnsWasmUpgradePathEntries,
+                        &payload,
+                    ),
+                    &cmd,
+                );
                 print_insert_sns_wasm_upgrade_path_entries_payload(payload);
                 return;
             }
@@ -5079,6 +5115,7 @@ async fn propose_external_proposal_from_command<
     ));
 
     print_proposal(&payload, &cmd);
+    process_proposal_action(&execute_nns_function_action(nns_function, &payload), &cmd);
 
     if cmd.is_dry_run() {
         return;
@@ -5122,6 +5159,7 @@ where
     let action = cmd.action().await;
 
     print_proposal(&Action::from(action.clone()), &cmd);
+    process_proposal_action(&action, &cmd);
 
     if cmd.is_dry_run() {
         return;
@@ -5629,6 +5667,10 @@ async fn propose_to_add_or_remove_node_provider(
     };
     let payload = AddOrRemoveNodeProvider { change };
     print_proposal(&payload, &cmd);
+    process_proposal_action(
+        &ProposalActionRequest::AddOrRemoveNodeProvider(payload.clone()),
+        &cmd,
+    );
 
     if cmd.is_dry_run() {
         return;
@@ -5670,6 +5712,165 @@ fn get_root_subnet_pub_key(
         .ok_or_else(|| "Root subnet public key is not found".to_string())
 }
 
+/// Calculate the performance-based node provider rewards for the latest
+/// registry version at the given `nns_urls`, with the block failure stats
+/// taken from the node metrics history in `cmd`, and print them as JSON.
+async fn simulate_node_provider_rewards(nns_urls: Vec<Url>, cmd: SimulateNodeProviderRewardsCmd) {
+    #[derive(Deserialize)]
+    struct NodeMetricsJson {
+        node_id: String,
+        num_blocks_proposed_total: u64,
+        num_block_failures_total: u64,
+    }
+
+    #[derive(Deserialize)]
+    struct NodeMetricsHistoryJson {
+        timestamp_nanos: u64,
+        node_metrics: Vec<NodeMetricsJson>,
+    }
+
+    #[derive(Serialize)]
+    struct NodeRewardsJson {
+        node_operator_id: String,
+        node_provider_id: String,
+        base_xdr_permyriad: u64,
+        failure_rate_permyriad: Option<u64>,
+        reduction_permyriad: u64,
+        xdr_permyriad: u64,
+    }
+
+    #[derive(Serialize)]
+    struct SimulatedRewardsJson {
+        registry_version: u64,
+        rewards_per_node_provider: BTreeMap<String, u64>,
+        rewards_per_node: BTreeMap<String, NodeRewardsJson>,
+    }
+
+    let parse_principal = |id: &str| {
+        PrincipalId::from_str(id).unwrap_or_else(|e| panic!("Invalid principal '{}': {}", id, e))
+    };
+
+    let history: BTreeMap<String, Vec<NodeMetricsHistoryJson>> = serde_json::from_str(
+        &read_to_string(&cmd.node_metrics_history)
+            .expect("Could not read the node metrics history file"),
+    )
+    .unwrap_or_else(|e| panic!("Unable to parse the node metrics history: {}", e));
+
+    let mut node_failure_stats = BTreeMap::<PrincipalId, NodeFailureStats>::new();
+    for subnet_history in history.into_values() {
+        let subnet_history = subnet_history
+            .into_iter()
+            .map(|entry| NodeMetricsHistoryResponse {
+                timestamp_nanos: entry.timestamp_nanos,
+                node_metrics: entry
+                    .node_metrics
+                    .into_iter()
+                    .map(|metrics| NodeMetrics {
+                        node_id: parse_principal(&metrics.node_id),
+                        num_blocks_proposed_total: metrics.num_blocks_proposed_total,
+                        num_block_failures_total: metrics.num_block_failures_total,
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        for (node_id, stats) in node_failure_stats_from_metrics_history(&subnet_history) {
+            *node_failure_stats.entry(node_id).or_default() += stats;
+        }
+    }
+
+    let registry_client = RegistryClientImpl::new(
+        Arc::new(NnsDataProvider::new(
+            tokio::runtime::Handle::current(),
+            nns_urls,
+        )),
+        None,
+    );
+    // maximum number of retries, let the user ctrl+c if necessary
+    registry_client
+        .try_polling_latest_version(usize::MAX)
+        .unwrap();
+    let version = registry_client.get_latest_version();
+
+    let get_records = |prefix: &str| {
+        registry_client
+            .get_key_family(prefix, version)
+            .unwrap()
+            .into_iter()
+            .map(|key| {
+                let bytes = registry_client
+                    .get_value(&key, version)
+                    .unwrap()
+                    .unwrap_or_default();
+                (key.strip_prefix(prefix).unwrap().to_string(), bytes)
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let rewards_table = registry_client
+        .get_value(NODE_REWARDS_TABLE_KEY, version)
+        .unwrap()
+        .map(|bytes| NodeRewardsTable::decode(bytes.as_slice()).unwrap())
+        .expect("Node Rewards Table was not found in the Registry");
+    let node_operators = get_records(NODE_OPERATOR_RECORD_KEY_PREFIX)
+        .into_iter()
+        .map(|(id, bytes)| (id, NodeOperatorRecord::decode(bytes.as_slice()).unwrap()))
+        .collect::<Vec<_>>();
+    let data_centers = get_records(DATA_CENTER_KEY_PREFIX)
+        .into_iter()
+        .map(|(id, bytes)| (id, DataCenterRecord::decode(bytes.as_slice()).unwrap()))
+        .collect::<BTreeMap<_, _>>();
+    let nodes = get_records(NODE_RECORD_KEY_PREFIX)
+        .into_iter()
+        .map(|(id, bytes)| {
+            let node = NodeRecord::decode(bytes.as_slice()).unwrap();
+            (
+                parse_principal(&id),
+                PrincipalId::try_from(&node.node_operator_id).unwrap(),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let rewards = calculate_rewards_v1(
+        &rewards_table,
+        &node_operators,
+        &data_centers,
+        &nodes,
+        &node_failure_stats,
+    )
+    .unwrap_or_else(|e| panic!("Failed to calculate the rewards: {}", e));
+
+    let simulated_rewards = SimulatedRewardsJson {
+        registry_version: version.get(),
+        rewards_per_node_provider: rewards
+            .rewards_per_node_provider
+            .into_iter()
+            .map(|(id, xdr_permyriad)| (id.to_string(), xdr_permyriad))
+            .collect(),
+        rewards_per_node: rewards
+            .rewards_per_node
+            .into_iter()
+            .map(|(id, node_rewards)| {
+                (
+                    id.to_string(),
+                    NodeRewardsJson {
+                        node_operator_id: node_rewards.node_operator_id.to_string(),
+                        node_provider_id: node_rewards.node_provider_id.to_string(),
+                        base_xdr_permyriad: node_rewards.base_xdr_permyriad,
+                        failure_rate_permyriad: node_rewards.failure_rate_permyriad,
+                        reduction_permyriad: node_rewards.reduction_permyriad,
+                        xdr_permyriad: node_rewards.xdr_permyriad,
+                    },
+                )
+            })
+            .collect(),
+    };
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&simulated_rewards)
+            .expect("Failed to serialize the simulated rewards to JSON")
+    );
+}
+
 /// Fetch registry records from the given `nns_urls`, and update the local
 /// registry store with the new records.
 async fn update_registry_local_store(nns_urls: Vec<Url>, cmd: UpdateRegistryLocalStoreCmd) {
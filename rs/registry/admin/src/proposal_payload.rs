@@ -0,0 +1,284 @@
+//! Offline handling of proposals: writing the exact action that would be
+//! submitted to a file, and validating registry proposals against a local
+//! registry store before anything is sent to the NNS.
+
+use crate::types::ProposalMetadata;
+use candid::{CandidType, Decode, Encode};
+use ic_crypto_sha2::Sha256;
+use ic_interfaces_registry::ZERO_REGISTRY_VERSION;
+use ic_nns_governance_api::pb::v1::{ExecuteNnsFunction, NnsFunction, ProposalActionRequest};
+use ic_registry_local_store::{LocalStoreImpl, LocalStoreReader};
+use ic_registry_transport::{
+    delete,
+    pb::v1::{RegistryAtomicMutateRequest, RegistryMutation},
+    upsert,
+};
+use prost::Message;
+use registry_canister::{
+    dry_run::{DryRunRequest, DryRunResponse},
+    mutations::{
+        do_add_nodes_to_subnet::AddNodesToSubnetPayload,
+        do_change_subnet_membership::ChangeSubnetMembershipPayload,
+        do_create_subnet::CreateSubnetPayload,
+        do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
+        do_update_subnet::UpdateSubnetPayload,
+    },
+    pb::v1::{registry_stable_storage, ChangelogEntry, RegistryStableStorage},
+    registry::Registry,
+};
+use std::path::Path;
+
+/// Handles the `--local-registry-store` and `--output-payload` options of a
+/// proposal command, after its payload has been printed.
+///
+/// Exits the process if local validation fails, so that a proposal that would
+/// be rejected is neither written nor submitted.
+pub(crate) fn process_proposal_action<Command: ProposalMetadata>(
+    action: &ProposalActionRequest,
+    cmd: &Command,
+) {
+    if let Some(local_store) = cmd.local_registry_store() {
+        validate_proposal_action_locally(action, local_store);
+    }
+    if let Some(path) = cmd.output_payload() {
+        output_proposal_action(action, path);
+    }
+}
+
+/// Returns the action of an `ExecuteNnsFunction` proposal, encoded the same
+/// way as by `create_external_update_proposal_candid`.
+pub(crate) fn execute_nns_function_action<T: CandidType>(
+    nns_function: NnsFunction,
+    payload: &T,
+) -> ProposalActionRequest {
+    ProposalActionRequest::ExecuteNnsFunction(ExecuteNnsFunction {
+        nns_function: nns_function as i32,
+        payload: Encode!(payload).expect("Error encoding proposal payload"),
+    })
+}
+
+/// Writes the Candid encoding of `action`, i.e. the action of the
+/// `MakeProposalRequest` submitted to governance, to `path` and prints its
+/// SHA-256 hash.
+///
+/// The hash is printed to stderr so that the output of `--json` stays valid.
+fn output_proposal_action(action: &ProposalActionRequest, path: &Path) {
+    let bytes = Encode!(action).expect("Failed to encode the proposal action.");
+    std::fs::write(path, &bytes).unwrap_or_else(|e| {
+        panic!(
+            "Failed to write the proposal action to {}: {}",
+            path.display(),
+            e
+        )
+    });
+    eprintln!(
+        "Wrote {} bytes of proposal action to {}",
+        bytes.len(),
+        path.display()
+    );
+    eprintln!("Action SHA-256: {}", hex::encode(Sha256::hash(&bytes)));
+}
+
+/// Maps the registry operations that support dry runs to the corresponding
+/// request, as governance does before submitting such a proposal.
+///
+/// Returns `Ok(None)` for actions that cannot be validated locally, and an
+/// error if the payload of a supported operation cannot be decoded.
+fn dry_run_request(action: &ProposalActionRequest) -> Result<Option<DryRunRequest>, String> {
+    let ProposalActionRequest::ExecuteNnsFunction(execute_nns_function) = action else {
+        return Ok(None);
+    };
+    let payload = &execute_nns_function.payload;
+    let decode_error = |e: candid::Error| format!("Failed to decode the proposal payload: {}", e);
+
+    let request = match NnsFunction::try_from(execute_nns_function.nns_function) {
+        Ok(NnsFunction::UpdateConfigOfSubnet) => DryRunRequest::UpdateSubnet(
+            Decode!(payload, UpdateSubnetPayload).map_err(decode_error)?,
+        ),
+        Ok(NnsFunction::AddNodeToSubnet) => DryRunRequest::AddNodesToSubnet(
+            Decode!(payload, AddNodesToSubnetPayload).map_err(decode_error)?,
+        ),
+        Ok(NnsFunction::RemoveNodesFromSubnet) => DryRunRequest::RemoveNodesFromSubnet(
+            Decode!(payload, RemoveNodesFromSubnetPayload).map_err(decode_error)?,
+        ),
+        Ok(NnsFunction::ChangeSubnetMembership) => DryRunRequest::ChangeSubnetMembership(
+            Decode!(payload, ChangeSubnetMembershipPayload).map_err(decode_error)?,
+        ),
+        Ok(NnsFunction::CreateSubnet) => DryRunRequest::CreateSubnet(
+            Decode!(payload, CreateSubnetPayload).map_err(decode_error)?,
+        ),
+        _ => return Ok(None),
+    };
+    Ok(Some(request))
+}
+
+/// Loads the full changelog of the local store at `path` into a registry, as
+/// the registry canister would after an upgrade.
+fn registry_from_local_store(path: &Path) -> Registry {
+    let changelog = LocalStoreImpl::new(path)
+        .get_changelog_since_version(ZERO_REGISTRY_VERSION)
+        .unwrap_or_else(|e| {
+            panic!(
+                "Failed to read the local registry store at {}: {}",
+                path.display(),
+                e
+            )
+        });
+
+    let changelog = changelog
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let mutations: Vec<RegistryMutation> = entry
+                .into_iter()
+                .map(|km| match km.value {
+                    Some(value) => upsert(km.key, value),
+                    None => delete(km.key),
+                })
+                .collect();
+            ChangelogEntry {
+                version: i as u64 + 1,
+                encoded_mutation: RegistryAtomicMutateRequest {
+                    mutations,
+                    preconditions: vec![],
+                }
+                .encode_to_vec(),
+            }
+        })
+        .collect();
+
+    let mut registry = Registry::new();
+    registry.from_serializable_form(RegistryStableStorage {
+        version: registry_stable_storage::Version::Version1 as i32,
+        deltas: vec![],
+        changelog,
+    });
+    registry
+}
+
+fn print_dry_run_response(response: &DryRunResponse) {
+    for error in &response.mutation_type_errors {
+        eprintln!("Mutation type error: {}", error);
+    }
+    for violation in &response.invariant_violations {
+        eprintln!("Invariant violation: {}", violation);
+    }
+    eprintln!(
+        "The proposal would change {} registry key(s):",
+        response.key_diff.len()
+    );
+    for diff in &response.key_diff {
+        eprintln!("  {}", String::from_utf8_lossy(&diff.key));
+    }
+}
+
+/// Validates `action` against the registry state in the local store at
+/// `local_store`, using the same code as the registry canister.
+fn validate_proposal_action_locally(action: &ProposalActionRequest, local_store: &Path) {
+    let request = match dry_run_request(action) {
+        Ok(Some(request)) => request,
+        Ok(None) => {
+            eprintln!("No local validation is available for this proposal type.");
+            return;
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let registry = registry_from_local_store(local_store);
+    eprintln!(
+        "Validating the proposal against the local registry store at version {}",
+        registry.latest_version()
+    );
+    let response = registry.dry_run(request);
+    print_dry_run_response(&response);
+
+    if !response.is_ok() {
+        eprintln!("The proposal failed local validation.");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_nns_governance_api::pb::v1::AddOrRemoveNodeProvider;
+    use ic_registry_local_store::{KeyMutation, LocalStoreWriter};
+    use ic_types::{NodeId, PrincipalId, RegistryVersion};
+
+    fn remove_nodes_payload() -> RemoveNodesFromSubnetPayload {
+        RemoveNodesFromSubnetPayload {
+            node_ids: vec![NodeId::from(PrincipalId::new_node_test_id(1))],
+        }
+    }
+
+    #[test]
+    fn test_dry_run_request_decodes_registry_payloads() {
+        let payload = remove_nodes_payload();
+        let action = execute_nns_function_action(NnsFunction::RemoveNodesFromSubnet, &payload);
+
+        assert_eq!(
+            dry_run_request(&action),
+            Ok(Some(DryRunRequest::RemoveNodesFromSubnet(payload)))
+        );
+    }
+
+    #[test]
+    fn test_dry_run_request_ignores_other_actions() {
+        let action = execute_nns_function_action(NnsFunction::NnsCanisterUpgrade, &());
+        assert_eq!(dry_run_request(&action), Ok(None));
+
+        let action = ProposalActionRequest::AddOrRemoveNodeProvider(AddOrRemoveNodeProvider {
+            change: None,
+        });
+        assert_eq!(dry_run_request(&action), Ok(None));
+    }
+
+    #[test]
+    fn test_dry_run_request_rejects_mismatched_payload() {
+        let action =
+            execute_nns_function_action(NnsFunction::UpdateConfigOfSubnet, &"not a payload");
+
+        assert!(dry_run_request(&action).is_err());
+    }
+
+    #[test]
+    fn test_output_proposal_action_writes_the_encoded_action() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("action.bin");
+        let action = execute_nns_function_action(
+            NnsFunction::RemoveNodesFromSubnet,
+            &remove_nodes_payload(),
+        );
+
+        output_proposal_action(&action, &path);
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes, Encode!(&action).unwrap());
+        assert_eq!(Decode!(&bytes, ProposalActionRequest).unwrap(), action);
+    }
+
+    #[test]
+    fn test_registry_from_local_store_replays_the_changelog() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalStoreImpl::new(dir.path());
+        let mutation = |value: Option<&[u8]>| KeyMutation {
+            key: "key".to_string(),
+            value: value.map(|v| v.to_vec()),
+        };
+        store
+            .store(RegistryVersion::from(1), vec![mutation(Some(b"one"))])
+            .unwrap();
+        store
+            .store(RegistryVersion::from(2), vec![mutation(Some(b"two"))])
+            .unwrap();
+
+        let registry = registry_from_local_store(dir.path());
+
+        assert_eq!(registry.latest_version(), 2);
+        assert_eq!(registry.get(b"key", 1).unwrap().value, b"one".to_vec());
+        assert_eq!(registry.get(b"key", 2).unwrap().value, b"two".to_vec());
+    }
+}
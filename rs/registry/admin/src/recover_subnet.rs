@@ -257,6 +257,8 @@ mod tests {
             subnet: SubnetDescriptor::Id(subnet_id.get()),
             test_neuron_proposer: false,
             dry_run: false,
+            output_payload: None,
+            local_registry_store: None,
             json: true,
             height,
             time_ns,
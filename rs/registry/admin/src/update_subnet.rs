@@ -398,6 +398,8 @@ mod tests {
             subnet: SubnetDescriptor::Id(subnet_id.get()),
             test_neuron_proposer: false,
             dry_run: true,
+            output_payload: None,
+            local_registry_store: None,
             json: true,
             proposer: None,
             proposal_url: None,
@@ -1,5 +1,5 @@
 use candid::{CandidType, Deserialize};
-use ic_base_types::NodeId;
+use ic_base_types::{NodeId, PrincipalId};
 use serde::Serialize;
 use std::collections::HashSet;
 use std::fmt;
@@ -201,6 +201,36 @@ pub struct UpdateNodeIPv4ConfigDirectlyPayload {
     pub ipv4_config: Option<IPv4Config>,
 }
 
+/// A request for the performance-adjusted rewards of the current registry
+/// state. The block statistics of the nodes are taken from the
+/// `node_metrics_history` of every subnet, starting at
+/// `from_timestamp_nanos`. The first entry of each history serves as the
+/// baseline, so to cover a reward period starting on day `d`, this should be a
+/// timestamp on day `d - 1`.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetNodeRewardsBreakdownRequest {
+    pub from_timestamp_nanos: u64,
+}
+
+/// The performance-adjusted rewards of a single node, in 10,000ths of an XDR.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NodeRewardsBreakdown {
+    pub node_id: PrincipalId,
+    pub node_operator_id: PrincipalId,
+    pub node_provider_id: PrincipalId,
+    pub base_xdr_permyriad: u64,
+    pub failure_rate_permyriad: Option<u64>,
+    pub reduction_permyriad: u64,
+    pub xdr_permyriad: u64,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetNodeRewardsBreakdownResponse {
+    pub rewards_per_node_provider: Vec<(PrincipalId, u64)>,
+    pub rewards_per_node: Vec<NodeRewardsBreakdown>,
+    pub registry_version: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -13,7 +13,8 @@ use ic_protobuf::registry::{
     node_rewards::v2::UpdateNodeRewardsTableProposalPayload,
 };
 use ic_registry_canister_api::{
-    AddNodePayload, UpdateNodeDirectlyPayload, UpdateNodeIPv4ConfigDirectlyPayload,
+    AddNodePayload, GetNodeRewardsBreakdownRequest, GetNodeRewardsBreakdownResponse,
+    UpdateNodeDirectlyPayload, UpdateNodeIPv4ConfigDirectlyPayload,
 };
 use ic_registry_transport::{
     deserialize_atomic_mutate_request, deserialize_get_changes_since_request,
@@ -31,6 +32,8 @@ use prost::Message;
 use registry_canister::{
     certification::{current_version_tree, hash_tree_to_proto},
     common::LOG_PREFIX,
+    dry_run::{DryRunRequest, DryRunResponse},
+    get_node_providers_monthly_xdr_rewards::get_node_failure_stats_from_ic00,
     init::RegistryCanisterInitPayload,
     mutations::{
         complete_canister_migration::CompleteCanisterMigrationPayload,
@@ -882,6 +885,33 @@ fn get_node_providers_monthly_xdr_rewards_() -> Result<NodeProvidersMonthlyXdrRe
     registry().get_node_providers_monthly_xdr_rewards()
 }
 
+#[export_name = "canister_update get_node_rewards_breakdown"]
+fn get_node_rewards_breakdown() {
+    check_caller_is_governance_and_log("get_node_rewards_breakdown");
+    over_async(
+        candid_one,
+        |request: GetNodeRewardsBreakdownRequest| async move {
+            get_node_rewards_breakdown_(request).await
+        },
+    )
+}
+
+#[candid_method(update, rename = "get_node_rewards_breakdown")]
+async fn get_node_rewards_breakdown_(
+    request: GetNodeRewardsBreakdownRequest,
+) -> Result<GetNodeRewardsBreakdownResponse, String> {
+    let subnet_ids = registry()
+        .get_subnet_list_record()
+        .subnets
+        .iter()
+        .map(|subnet_id| PrincipalId::try_from(subnet_id.as_slice()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("The subnet list contains an invalid subnet ID: {}", err))?;
+    let node_failure_stats =
+        get_node_failure_stats_from_ic00(subnet_ids, request.from_timestamp_nanos).await?;
+    registry().get_node_rewards_breakdown(&node_failure_stats)
+}
+
 #[export_name = "canister_query get_node_operators_and_dcs_of_node_provider"]
 fn get_node_operators_and_dcs_of_node_provider() {
     over(
@@ -915,6 +945,18 @@ fn get_subnet_for_canister_(arg: GetSubnetForCanisterRequest) -> Result<SubnetFo
         .map_err(|e| e.to_string())
 }
 
+#[export_name = "canister_query dry_run"]
+fn dry_run() {
+    over(candid_one, dry_run_)
+}
+
+/// Reports the invariant violations and key changes that a request would
+/// cause, without applying it.
+#[candid_method(query, rename = "dry_run")]
+fn dry_run_(request: DryRunRequest) -> DryRunResponse {
+    registry().dry_run(request)
+}
+
 #[export_name = "canister_update add_node"]
 fn add_node() {
     // This method can be called by anyone
@@ -0,0 +1,329 @@
+//! Dry-running registry requests.
+//!
+//! A dry run computes the mutations of a request against the latest registry
+//! state and reports the checks that would fail as well as the keys that
+//! would change, without modifying the registry. It lets `ic-admin` and NNS
+//! governance catch invariant violations before a proposal is submitted or
+//! executed.
+//!
+//! Note that some invariant checks and payload validations assert rather
+//! than return an error. Such failures abort the dry run with the same
+//! message as the actual request would, i.e. the query is rejected.
+
+use crate::{
+    mutations::{
+        do_add_nodes_to_subnet::AddNodesToSubnetPayload,
+        do_change_subnet_membership::ChangeSubnetMembershipPayload,
+        do_create_subnet::CreateSubnetPayload,
+        do_remove_nodes_from_subnet::RemoveNodesFromSubnetPayload,
+        do_update_subnet::UpdateSubnetPayload,
+    },
+    registry::Registry,
+};
+
+use candid::{CandidType, Deserialize};
+use ic_registry_transport::pb::v1::{registry_mutation::Type, RegistryMutation};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A request to be evaluated without being applied.
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum DryRunRequest {
+    /// A raw list of mutations, as passed to `atomic_mutate`.
+    Mutations(Vec<RegistryMutation>),
+    UpdateSubnet(UpdateSubnetPayload),
+    AddNodesToSubnet(AddNodesToSubnetPayload),
+    RemoveNodesFromSubnet(RemoveNodesFromSubnetPayload),
+    ChangeSubnetMembership(ChangeSubnetMembershipPayload),
+    /// Creating a subnet requires calls to the management canister, which
+    /// cannot be made from a query. The key diff covers the subnet record,
+    /// the subnet list and the routing table, but not the NI-DKG transcripts
+    /// and the threshold signing public key. Unless `subnet_id_override` is
+    /// set, the anonymous principal stands in for the id of the new subnet.
+    CreateSubnet(CreateSubnetPayload),
+}
+
+/// The outcome of a dry run.
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DryRunResponse {
+    /// Mutations whose type does not match the current state of their key,
+    /// e.g. an insert of an existing key.
+    pub mutation_type_errors: Vec<String>,
+    /// Global state invariants that would not hold after the request.
+    pub invariant_violations: Vec<String>,
+    /// The keys that would change, sorted by key.
+    pub key_diff: Vec<KeyDiff>,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KeyDiff {
+    pub key: Vec<u8>,
+    pub change: KeyChange,
+}
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum KeyChange {
+    Added {
+        value: Vec<u8>,
+    },
+    Modified {
+        old_value: Vec<u8>,
+        new_value: Vec<u8>,
+    },
+    Removed {
+        old_value: Vec<u8>,
+    },
+}
+
+impl DryRunResponse {
+    /// Returns whether the request would be applied by the registry.
+    pub fn is_ok(&self) -> bool {
+        self.mutation_type_errors.is_empty() && self.invariant_violations.is_empty()
+    }
+}
+
+impl Registry {
+    /// Evaluates `request` against the latest version of the registry without
+    /// applying it.
+    pub fn dry_run(&self, request: DryRunRequest) -> DryRunResponse {
+        let mutations = match request {
+            DryRunRequest::Mutations(mutations) => mutations,
+            DryRunRequest::UpdateSubnet(payload) => self.mutations_to_update_subnet(payload),
+            DryRunRequest::AddNodesToSubnet(payload) => {
+                self.mutations_to_add_nodes_to_subnet(&payload)
+            }
+            DryRunRequest::RemoveNodesFromSubnet(payload) => {
+                self.mutations_to_remove_nodes_from_subnet(&payload)
+            }
+            DryRunRequest::ChangeSubnetMembership(payload) => {
+                self.mutations_to_change_subnet_membership(&payload)
+            }
+            DryRunRequest::CreateSubnet(payload) => {
+                self.mutations_to_create_subnet_without_dkg(payload)
+            }
+        };
+
+        // Mutations with type errors are rejected before the invariants are
+        // checked, so neither invariant violations nor key changes are
+        // reported for them.
+        let mutation_type_errors: Vec<_> = self
+            .verify_mutation_type(&mutations)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        if !mutation_type_errors.is_empty() {
+            return DryRunResponse {
+                mutation_type_errors,
+                ..Default::default()
+            };
+        }
+
+        DryRunResponse {
+            mutation_type_errors,
+            invariant_violations: self
+                .global_state_invariant_violations(&mutations)
+                .into_iter()
+                .map(|e| e.msg)
+                .collect(),
+            key_diff: self.key_diff(&mutations),
+        }
+    }
+
+    /// Returns the changes that `mutations` make to the latest values of the
+    /// keys they touch. A later mutation of the same key takes precedence,
+    /// as when the mutations are applied.
+    fn key_diff(&self, mutations: &[RegistryMutation]) -> Vec<KeyDiff> {
+        let new_values: BTreeMap<&[u8], Option<&[u8]>> = mutations
+            .iter()
+            .map(|mutation| {
+                let new_value = (mutation.mutation_type != Type::Delete as i32)
+                    .then_some(mutation.value.as_slice());
+                (mutation.key.as_slice(), new_value)
+            })
+            .collect();
+
+        let latest_version = self.latest_version();
+        new_values
+            .into_iter()
+            .filter_map(|(key, new_value)| {
+                let old_value = self
+                    .get(key, latest_version)
+                    .map(|value| value.value.as_slice());
+                let change = match (old_value, new_value) {
+                    (None, Some(value)) => KeyChange::Added {
+                        value: value.to_vec(),
+                    },
+                    (Some(old_value), Some(new_value)) if old_value != new_value => {
+                        KeyChange::Modified {
+                            old_value: old_value.to_vec(),
+                            new_value: new_value.to_vec(),
+                        }
+                    }
+                    (Some(old_value), None) => KeyChange::Removed {
+                        old_value: old_value.to_vec(),
+                    },
+                    _ => return None,
+                };
+                Some(KeyDiff {
+                    key: key.to_vec(),
+                    change,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::test_helpers::{invariant_compliant_registry, prepare_registry_with_nodes};
+    use ic_base_types::{PrincipalId, SubnetId};
+    use ic_nns_common::registry::MAX_NUM_SSH_KEYS;
+    use ic_protobuf::registry::unassigned_nodes_config::v1::UnassignedNodesConfigRecord;
+    use ic_registry_keys::{
+        make_routing_table_record_key, make_subnet_list_record_key, make_subnet_record_key,
+        make_unassigned_nodes_config_record_key,
+    };
+    use ic_registry_transport::{delete, insert, update, upsert};
+    use ic_types::ReplicaVersion;
+    use prost::Message;
+
+    #[test]
+    fn dry_run_reports_key_diff_without_applying_mutations() {
+        let registry = invariant_compliant_registry(0);
+        let before = registry.clone();
+
+        let response = registry.dry_run(DryRunRequest::Mutations(vec![
+            insert("dry_run_key_1", "value"),
+            upsert("dry_run_key_2", "value"),
+        ]));
+
+        assert_eq!(
+            response,
+            DryRunResponse {
+                mutation_type_errors: vec![],
+                invariant_violations: vec![],
+                key_diff: vec![
+                    KeyDiff {
+                        key: b"dry_run_key_1".to_vec(),
+                        change: KeyChange::Added {
+                            value: b"value".to_vec()
+                        },
+                    },
+                    KeyDiff {
+                        key: b"dry_run_key_2".to_vec(),
+                        change: KeyChange::Added {
+                            value: b"value".to_vec()
+                        },
+                    },
+                ],
+            }
+        );
+        assert_eq!(registry, before);
+    }
+
+    #[test]
+    fn dry_run_reports_modified_and_removed_keys() {
+        let mut registry = invariant_compliant_registry(0);
+        registry.maybe_apply_mutation_internal(vec![
+            insert("dry_run_key_1", "old"),
+            insert("dry_run_key_2", "old"),
+        ]);
+
+        let response = registry.dry_run(DryRunRequest::Mutations(vec![
+            update("dry_run_key_1", "new"),
+            delete("dry_run_key_2"),
+        ]));
+
+        assert_eq!(
+            response.key_diff,
+            vec![
+                KeyDiff {
+                    key: b"dry_run_key_1".to_vec(),
+                    change: KeyChange::Modified {
+                        old_value: b"old".to_vec(),
+                        new_value: b"new".to_vec(),
+                    },
+                },
+                KeyDiff {
+                    key: b"dry_run_key_2".to_vec(),
+                    change: KeyChange::Removed {
+                        old_value: b"old".to_vec()
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn dry_run_reports_mutation_type_errors() {
+        let registry = invariant_compliant_registry(0);
+
+        let response = registry.dry_run(DryRunRequest::Mutations(vec![update(
+            "dry_run_missing_key",
+            "value",
+        )]));
+
+        assert_eq!(response.mutation_type_errors.len(), 1);
+        assert!(response.invariant_violations.is_empty());
+        assert!(response.key_diff.is_empty());
+    }
+
+    #[test]
+    fn dry_run_reports_invariant_violations() {
+        let registry = invariant_compliant_registry(0);
+        let key = make_unassigned_nodes_config_record_key();
+        let value = UnassignedNodesConfigRecord {
+            ssh_readonly_access: vec!["key".to_string(); MAX_NUM_SSH_KEYS + 1],
+            replica_version: ReplicaVersion::default().into(),
+        }
+        .encode_to_vec();
+
+        let response = registry.dry_run(DryRunRequest::Mutations(vec![upsert(
+            key.as_bytes(),
+            value,
+        )]));
+
+        assert!(response.mutation_type_errors.is_empty());
+        assert_eq!(response.invariant_violations.len(), 1);
+        assert!(response.invariant_violations[0].contains("SSH key access list that is too long"));
+        assert_eq!(response.key_diff.len(), 1);
+        assert_eq!(response.key_diff[0].key, key.into_bytes());
+    }
+
+    #[test]
+    fn dry_run_reports_key_diff_of_create_subnet() {
+        let mut registry = invariant_compliant_registry(0);
+        let (mutate_request, node_ids_and_dkg_pks) = prepare_registry_with_nodes(1, 1);
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+        let before = registry.clone();
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1000));
+
+        let response = registry.dry_run(DryRunRequest::CreateSubnet(CreateSubnetPayload {
+            node_ids: node_ids_and_dkg_pks.into_keys().collect(),
+            subnet_id_override: Some(subnet_id.get()),
+            replica_version_id: ReplicaVersion::default().into(),
+            ..Default::default()
+        }));
+
+        assert!(response.is_ok(), "{:?}", response);
+        let changed_keys: Vec<_> = response
+            .key_diff
+            .iter()
+            .map(|diff| String::from_utf8(diff.key.clone()).unwrap())
+            .collect();
+        assert_eq!(
+            changed_keys,
+            vec![
+                make_routing_table_record_key(),
+                make_subnet_list_record_key(),
+                make_subnet_record_key(subnet_id),
+            ]
+        );
+        assert!(matches!(
+            response.key_diff[2].change,
+            KeyChange::Added { .. }
+        ));
+        assert_eq!(registry, before);
+    }
+}
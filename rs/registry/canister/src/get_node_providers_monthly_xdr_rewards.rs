@@ -3,18 +3,28 @@ use crate::{
     pb::v1::NodeProvidersMonthlyXdrRewards,
     registry::Registry,
 };
+use candid::{Decode, Encode};
+use dfn_core::api::{call, CanisterId};
 #[cfg(target_arch = "wasm32")]
 use dfn_core::println;
+use ic_base_types::PrincipalId;
+use ic_management_canister_types::{NodeMetricsHistoryArgs, NodeMetricsHistoryResponse};
 use ic_protobuf::registry::{
-    dc::v1::DataCenterRecord, node_operator::v1::NodeOperatorRecord,
+    dc::v1::DataCenterRecord, node::v1::NodeRecord, node_operator::v1::NodeOperatorRecord,
     node_rewards::v2::NodeRewardsTable,
 };
+use ic_registry_canister_api::{GetNodeRewardsBreakdownResponse, NodeRewardsBreakdown};
 use ic_registry_keys::{
-    DATA_CENTER_KEY_PREFIX, NODE_OPERATOR_RECORD_KEY_PREFIX, NODE_REWARDS_TABLE_KEY,
+    DATA_CENTER_KEY_PREFIX, NODE_OPERATOR_RECORD_KEY_PREFIX, NODE_RECORD_KEY_PREFIX,
+    NODE_REWARDS_TABLE_KEY,
 };
-use ic_registry_node_provider_rewards::calculate_rewards_v0;
+use ic_registry_node_provider_rewards::{
+    calculate_rewards_v0, calculate_rewards_v1, node_failure_stats_from_metrics_history,
+    NodeFailureStats,
+};
+use on_wire::bytes;
 use prost::Message;
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, str::FromStr};
 
 impl Registry {
     /// Return a map from Node Provider IDs to the amount (in 10,000ths of an
@@ -25,6 +35,89 @@ impl Registry {
     ) -> Result<NodeProvidersMonthlyXdrRewards, String> {
         let mut rewards = NodeProvidersMonthlyXdrRewards::default();
 
+        let (rewards_table, node_operators, data_centers) = self.get_rewards_inputs()?;
+
+        let reward_values = calculate_rewards_v0(&rewards_table, &node_operators, &data_centers)?;
+
+        rewards.rewards = reward_values
+            .rewards_per_node_provider
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect();
+
+        rewards.registry_version = Some(self.latest_version());
+
+        Ok(rewards)
+    }
+
+    /// Return the monthly rewards of each Node Provider and each node, reduced
+    /// according to the block failure rates in `node_failure_stats`, as
+    /// returned by `get_node_failure_stats_from_ic00`.
+    pub fn get_node_rewards_breakdown(
+        &self,
+        node_failure_stats: &BTreeMap<PrincipalId, NodeFailureStats>,
+    ) -> Result<GetNodeRewardsBreakdownResponse, String> {
+        let (rewards_table, node_operators, data_centers) = self.get_rewards_inputs()?;
+
+        let nodes = get_key_family_iter::<NodeRecord>(self, NODE_RECORD_KEY_PREFIX)
+            .map(|(node_id, node)| {
+                let node_id = PrincipalId::from_str(&node_id).map_err(|e| {
+                    format!(
+                        "Node key '{}' cannot be parsed as a PrincipalId: {}",
+                        node_id, e
+                    )
+                })?;
+                let node_operator_id =
+                    PrincipalId::try_from(&node.node_operator_id).map_err(|e| {
+                        format!(
+                            "Node '{}' has a node_operator_id that cannot be parsed as a \
+                             PrincipalId: {}",
+                            node_id, e
+                        )
+                    })?;
+                Ok((node_id, node_operator_id))
+            })
+            .collect::<Result<BTreeMap<_, _>, String>>()?;
+
+        let rewards = calculate_rewards_v1(
+            &rewards_table,
+            &node_operators,
+            &data_centers,
+            &nodes,
+            node_failure_stats,
+        )?;
+
+        Ok(GetNodeRewardsBreakdownResponse {
+            rewards_per_node_provider: rewards.rewards_per_node_provider.into_iter().collect(),
+            rewards_per_node: rewards
+                .rewards_per_node
+                .into_iter()
+                .map(|(node_id, node_rewards)| NodeRewardsBreakdown {
+                    node_id,
+                    node_operator_id: node_rewards.node_operator_id,
+                    node_provider_id: node_rewards.node_provider_id,
+                    base_xdr_permyriad: node_rewards.base_xdr_permyriad,
+                    failure_rate_permyriad: node_rewards.failure_rate_permyriad,
+                    reduction_permyriad: node_rewards.reduction_permyriad,
+                    xdr_permyriad: node_rewards.xdr_permyriad,
+                })
+                .collect(),
+            registry_version: self.latest_version(),
+        })
+    }
+
+    /// Returns the registry records the rewards are calculated from.
+    #[allow(clippy::type_complexity)]
+    fn get_rewards_inputs(
+        &self,
+    ) -> Result<
+        (
+            NodeRewardsTable,
+            Vec<(String, NodeOperatorRecord)>,
+            BTreeMap<String, DataCenterRecord>,
+        ),
+        String,
+    > {
         let rewards_table_bytes = self
             .get(NODE_REWARDS_TABLE_KEY.as_bytes(), self.latest_version())
             .ok_or_else(|| "Node Rewards Table was not found in the Registry".to_string())?
@@ -39,18 +132,57 @@ impl Registry {
         let data_centers = get_key_family_iter::<DataCenterRecord>(self, DATA_CENTER_KEY_PREFIX)
             .collect::<BTreeMap<String, DataCenterRecord>>();
 
-        let reward_values = calculate_rewards_v0(&rewards_table, &node_operators, &data_centers)?;
-
-        rewards.rewards = reward_values
-            .rewards_per_node_provider
-            .into_iter()
-            .map(|(k, v)| (k.to_string(), v))
-            .collect();
+        Ok((rewards_table, node_operators, data_centers))
+    }
+}
 
-        rewards.registry_version = Some(self.latest_version());
+/// Fetches the `node_metrics_history` of the given subnets from ic_00 and
+/// returns the block statistics of every node since `from_timestamp_nanos`.
+///
+/// The management canister is the only trusted source of these statistics, so
+/// they are never taken from the caller of the registry.
+pub async fn get_node_failure_stats_from_ic00(
+    subnet_ids: Vec<PrincipalId>,
+    from_timestamp_nanos: u64,
+) -> Result<BTreeMap<PrincipalId, NodeFailureStats>, String> {
+    let mut node_failure_stats = BTreeMap::<PrincipalId, NodeFailureStats>::new();
+
+    for subnet_id in subnet_ids {
+        let request = NodeMetricsHistoryArgs {
+            subnet_id,
+            start_at_timestamp_nanos: from_timestamp_nanos,
+        };
 
-        Ok(rewards)
+        let response_bytes = call(
+            CanisterId::ic_00(),
+            "node_metrics_history",
+            bytes,
+            Encode!(&request).unwrap(),
+        )
+        .await
+        .map_err(|(code, msg)| {
+            format!(
+                "Error calling 'node_metrics_history' for subnet {}: code: {:?}, message: {}",
+                subnet_id, code, msg
+            )
+        })?;
+
+        let node_metrics_history = Decode!(&response_bytes, Vec<NodeMetricsHistoryResponse>)
+            .map_err(|err| {
+                format!(
+                    "Cannot decode the node_metrics_history of subnet {}: {}",
+                    subnet_id, err
+                )
+            })?;
+
+        // A node that moved between subnets in the period has statistics in
+        // the history of each of them.
+        for (node_id, stats) in node_failure_stats_from_metrics_history(&node_metrics_history) {
+            *node_failure_stats.entry(node_id).or_default() += stats;
+        }
     }
+
+    Ok(node_failure_stats)
 }
 
 #[cfg(test)]
@@ -7,7 +7,7 @@ use candid::{CandidType, Deserialize};
 use dfn_core::println;
 use ic_base_types::{NodeId, PrincipalId, SubnetId};
 use ic_registry_keys::make_subnet_record_key;
-use ic_registry_transport::upsert;
+use ic_registry_transport::{pb::v1::RegistryMutation, upsert};
 use prost::Message;
 use serde::Serialize;
 
@@ -22,8 +22,25 @@ impl Registry {
             LOG_PREFIX, payload
         );
 
+        let mutations = self.mutations_to_add_nodes_to_subnet(&payload);
+
+        // Check invariants before applying mutations
+        self.maybe_apply_mutation_internal(mutations);
+
+        println!(
+            "{}do_add_nodes_to_subnet finished: {:?}",
+            LOG_PREFIX, payload
+        );
+    }
+
+    /// Validates `payload` and returns the mutations that add the nodes to
+    /// the subnet.
+    pub(crate) fn mutations_to_add_nodes_to_subnet(
+        &self,
+        payload: &AddNodesToSubnetPayload,
+    ) -> Vec<RegistryMutation> {
         // Validate payload
-        self.validate_add_nodes_to_subnet_payload(&payload);
+        self.validate_add_nodes_to_subnet_payload(payload);
 
         let mut nodes_to_add = payload.node_ids.clone();
         let subnet_id = SubnetId::from(payload.subnet_id);
@@ -38,18 +55,10 @@ impl Registry {
         nodes_to_add.append(&mut existing_nodes);
 
         self.replace_subnet_record_membership(subnet_id, &mut subnet_record, nodes_to_add);
-        let mutations = vec![upsert(
+        vec![upsert(
             make_subnet_record_key(subnet_id),
             subnet_record.encode_to_vec(),
-        )];
-
-        // Check invariants before applying mutations
-        self.maybe_apply_mutation_internal(mutations);
-
-        println!(
-            "{}do_add_nodes_to_subnet finished: {:?}",
-            LOG_PREFIX, payload
-        );
+        )]
     }
 
     /// Ensure all nodes for new subnet are not already assigned as ApiBoundaryNode
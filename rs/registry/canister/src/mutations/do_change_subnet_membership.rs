@@ -7,7 +7,7 @@ use candid::{CandidType, Deserialize};
 use dfn_core::println;
 use ic_base_types::{NodeId, PrincipalId, SubnetId};
 use ic_registry_keys::make_subnet_record_key;
-use ic_registry_transport::upsert;
+use ic_registry_transport::{pb::v1::RegistryMutation, upsert};
 use prost::Message;
 use serde::Serialize;
 
@@ -22,6 +22,23 @@ impl Registry {
             LOG_PREFIX, payload
         );
 
+        let mutations = self.mutations_to_change_subnet_membership(&payload);
+
+        // Check the invariants and apply the mutations if invariants are satisfied
+        self.maybe_apply_mutation_internal(mutations);
+
+        println!(
+            "{}do_change_subnet_membership finished: {:?}",
+            LOG_PREFIX, payload
+        );
+    }
+
+    /// Validates `payload` and returns the mutations that change the
+    /// membership of the subnet.
+    pub(crate) fn mutations_to_change_subnet_membership(
+        &self,
+        payload: &ChangeSubnetMembershipPayload,
+    ) -> Vec<RegistryMutation> {
         let nodes_to_add = payload.node_ids_add.clone();
         let subnet_id = SubnetId::from(payload.subnet_id);
         let mut subnet_record = self.get_subnet_or_panic(subnet_id);
@@ -59,18 +76,10 @@ impl Registry {
             &mut subnet_record,
             subnet_membership_after_change,
         );
-        let mutations = vec![upsert(
+        vec![upsert(
             make_subnet_record_key(subnet_id),
             subnet_record.encode_to_vec(),
-        )];
-
-        // Check the invariants and apply the mutations if invariants are satisfied
-        self.maybe_apply_mutation_internal(mutations);
-
-        println!(
-            "{}do_change_subnet_membership finished: {:?}",
-            LOG_PREFIX, payload
-        );
+        )]
     }
 }
 
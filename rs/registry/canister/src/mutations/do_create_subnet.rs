@@ -86,25 +86,7 @@ impl Registry {
 
         // TODO[NNS1-3022]: Stop reading `payload.ecdsa_config` and mutating `payload`.
 
-        // Legacy ECDSA data is used only if there is nothing in `payload.chain_key_config`.
-        // Even if legacy ECDSA data is used, it is converted to `InitialChainKeyConfig` here.
-        let initial_chain_key_config_from_legacy_source =
-            payload.ecdsa_config.clone().map(|ecdsa_initial_config| {
-                InitialChainKeyConfigInternal::try_from(ecdsa_initial_config)
-                    .expect("Invalid EcdsaInitialConfig")
-            });
-
-        let initial_chain_key_config_from_new_source =
-            payload
-                .chain_key_config
-                .clone()
-                .map(|initial_chain_key_config| {
-                    InitialChainKeyConfigInternal::try_from(initial_chain_key_config)
-                        .expect("Invalid InitialChainKeyConfig")
-                });
-
-        let initial_chain_key_config = initial_chain_key_config_from_new_source
-            .or(initial_chain_key_config_from_legacy_source);
+        let initial_chain_key_config = initial_chain_key_config(&payload);
 
         let receiver_nodes = payload.node_ids.clone();
         let chain_key_initializations = self
@@ -144,11 +126,49 @@ impl Registry {
             value: response.subnet_threshold_public_key.encode_to_vec(),
         };
 
-        let subnet_record = SubnetRecord::from(payload);
-
         // 4. Update registry with the new subnet data
         // The subnet data is the new subnet record plus the update to the global
         // subnet list.
+        let mut mutations = self.mutations_to_add_subnet(payload, subnet_id);
+        mutations.extend([new_subnet_dkg, new_subnet_threshold_signing_pubkey]);
+
+        // Check invariants before applying mutations
+        self.maybe_apply_mutation_internal(mutations);
+    }
+
+    /// Returns the mutations of creating a subnet from `payload`, except for
+    /// the NI-DKG transcripts and the threshold signing public key, which are
+    /// computed by the management canister when the subnet is created. For
+    /// the same reason, the subnet id is only known if `subnet_id_override`
+    /// is set; otherwise the anonymous principal stands in for it.
+    pub(crate) fn mutations_to_create_subnet_without_dkg(
+        &self,
+        payload: CreateSubnetPayload,
+    ) -> Vec<RegistryMutation> {
+        self.validate_create_subnet_payload(&payload);
+
+        let subnet_id = payload
+            .subnet_id_override
+            .map(SubnetId::new)
+            .unwrap_or_else(|| SubnetId::new(PrincipalId::new_anonymous()));
+        let payload = CreateSubnetPayload {
+            ecdsa_config: None,
+            chain_key_config: initial_chain_key_config(&payload).map(InitialChainKeyConfig::from),
+            ..payload
+        };
+        self.mutations_to_add_subnet(payload, subnet_id)
+    }
+
+    /// Returns the mutations that insert the subnet record of `subnet_id` and
+    /// add the subnet to the subnet list and the routing table. `payload`
+    /// must not set the legacy `ecdsa_config`.
+    fn mutations_to_add_subnet(
+        &self,
+        payload: CreateSubnetPayload,
+        subnet_id: SubnetId,
+    ) -> Vec<RegistryMutation> {
+        let subnet_record = SubnetRecord::from(payload);
+
         let mut subnet_list_record = self.get_subnet_list_record();
         if subnet_list_record
             .subnets
@@ -177,16 +197,7 @@ impl Registry {
         let routing_table_mutation =
             self.add_subnet_to_routing_table(self.latest_version(), subnet_id);
 
-        let mutations = vec![
-            subnet_list_mutation,
-            new_subnet,
-            new_subnet_dkg,
-            new_subnet_threshold_signing_pubkey,
-            routing_table_mutation,
-        ];
-
-        // Check invariants before applying mutations
-        self.maybe_apply_mutation_internal(mutations);
+        vec![subnet_list_mutation, new_subnet, routing_table_mutation]
     }
 
     /// Validates runtime payload values that aren't checked by invariants.
@@ -285,6 +296,30 @@ impl Registry {
     }
 }
 
+/// Returns the initial chain key config of `payload`. The legacy
+/// `ecdsa_config` is used only if `chain_key_config` is not set, and is
+/// converted to a chain key config.
+fn initial_chain_key_config(
+    payload: &CreateSubnetPayload,
+) -> Option<InitialChainKeyConfigInternal> {
+    let initial_chain_key_config_from_legacy_source =
+        payload.ecdsa_config.clone().map(|ecdsa_initial_config| {
+            InitialChainKeyConfigInternal::try_from(ecdsa_initial_config)
+                .expect("Invalid EcdsaInitialConfig")
+        });
+
+    let initial_chain_key_config_from_new_source =
+        payload
+            .chain_key_config
+            .clone()
+            .map(|initial_chain_key_config| {
+                InitialChainKeyConfigInternal::try_from(initial_chain_key_config)
+                    .expect("Invalid InitialChainKeyConfig")
+            });
+
+    initial_chain_key_config_from_new_source.or(initial_chain_key_config_from_legacy_source)
+}
+
 /// The payload of a proposal to create a new subnet.
 ///
 /// See /rs/protobuf/def/registry/subnet/v1/subnet.proto
@@ -6,7 +6,7 @@ use dfn_core::println;
 use ic_base_types::NodeId;
 use ic_nns_common::registry::get_subnet_ids_from_subnet_list;
 use ic_registry_keys::make_subnet_record_key;
-use ic_registry_transport::update;
+use ic_registry_transport::{pb::v1::RegistryMutation, update};
 use prost::Message;
 use serde::Serialize;
 
@@ -18,7 +18,23 @@ impl Registry {
             LOG_PREFIX, payload
         );
 
-        let mutations = get_subnet_ids_from_subnet_list(self.get_subnet_list_record())
+        let mutations = self.mutations_to_remove_nodes_from_subnet(&payload);
+
+        // Check invariants before applying mutations
+        self.maybe_apply_mutation_internal(mutations);
+
+        println!(
+            "{}do_remove_nodes_from_subnet finished: {:?}",
+            LOG_PREFIX, payload
+        );
+    }
+
+    /// Returns the mutations that remove the nodes from their subnets.
+    pub(crate) fn mutations_to_remove_nodes_from_subnet(
+        &self,
+        payload: &RemoveNodesFromSubnetPayload,
+    ) -> Vec<RegistryMutation> {
+        get_subnet_ids_from_subnet_list(self.get_subnet_list_record())
             .into_iter()
             .map(|subnet_id| (subnet_id, self.get_subnet_or_panic(subnet_id)))
             .filter_map(|(subnet_id, mut subnet)| {
@@ -40,15 +56,7 @@ impl Registry {
                     None
                 }
             })
-            .collect();
-
-        // Check invariants before applying mutations
-        self.maybe_apply_mutation_internal(mutations);
-
-        println!(
-            "{}do_remove_nodes_from_subnet finished: {:?}",
-            LOG_PREFIX, payload
-        );
+            .collect()
     }
 }
 
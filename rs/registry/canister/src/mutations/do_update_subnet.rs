@@ -25,6 +25,17 @@ impl Registry {
     pub fn do_update_subnet(&mut self, payload: UpdateSubnetPayload) {
         println!("{}do_update_subnet: {:?}", LOG_PREFIX, payload);
 
+        let mutations = self.mutations_to_update_subnet(payload);
+
+        // Check invariants before applying mutations
+        self.maybe_apply_mutation_internal(mutations);
+    }
+
+    /// Validates `payload` and returns the mutations that update the subnet.
+    pub(crate) fn mutations_to_update_subnet(
+        &self,
+        payload: UpdateSubnetPayload,
+    ) -> Vec<RegistryMutation> {
         self.validate_update_payload_chain_key_config(&payload);
         self.validate_update_sev_feature(&payload);
 
@@ -81,8 +92,7 @@ impl Registry {
             );
         }
 
-        // Check invariants before applying mutations
-        self.maybe_apply_mutation_internal(mutations);
+        mutations
     }
 
     /// Validates that the chain key IDs are globally unique across all subnets.
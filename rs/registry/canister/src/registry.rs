@@ -248,7 +248,7 @@ impl Registry {
 
     /// Verifies the implicit precondition corresponding to the mutation_type
     /// field.
-    fn verify_mutation_type(&self, mutations: &[RegistryMutation]) -> Vec<Error> {
+    pub(crate) fn verify_mutation_type(&self, mutations: &[RegistryMutation]) -> Vec<Error> {
         mutations
             .iter()
             .map(|m| {
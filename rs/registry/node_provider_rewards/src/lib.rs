@@ -6,6 +6,13 @@ use ic_protobuf::registry::{
 };
 use std::collections::{BTreeMap, HashMap};
 
+mod performance;
+
+pub use performance::{
+    node_failure_stats_from_metrics_history, rewards_reduction_permyriad, NodeFailureStats,
+    MAX_FAILURE_RATE_PERMYRIAD, MAX_REWARDS_REDUCTION_PERMYRIAD, MIN_FAILURE_RATE_PERMYRIAD,
+};
+
 pub struct RewardsPerNodeProvider {
     pub rewards_per_node_provider: BTreeMap<PrincipalId, u64>,
 }
@@ -15,6 +22,124 @@ pub fn calculate_rewards_v0(
     node_operators: &[(String, NodeOperatorRecord)],
     data_centers: &BTreeMap<String, DataCenterRecord>,
 ) -> Result<RewardsPerNodeProvider, String> {
+    let mut rewards = BTreeMap::new();
+    for node_operator_rewards in
+        calculate_rewards_per_node_operator(rewards_table, node_operators, data_centers)?.values()
+    {
+        *rewards
+            .entry(node_operator_rewards.node_provider_id)
+            .or_default() += node_operator_rewards.xdr_permyriad;
+    }
+
+    Ok(RewardsPerNodeProvider {
+        rewards_per_node_provider: rewards,
+    })
+}
+
+/// The rewards of a single node, as computed by [`calculate_rewards_v1`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeRewards {
+    pub node_operator_id: PrincipalId,
+    pub node_provider_id: PrincipalId,
+    /// The node's share of the rewards of its Node Operator.
+    pub base_xdr_permyriad: u64,
+    /// `None` if the node was not a block maker in the reward period.
+    pub failure_rate_permyriad: Option<u64>,
+    pub reduction_permyriad: u64,
+    pub xdr_permyriad: u64,
+}
+
+pub struct RewardsPerNode {
+    pub rewards_per_node_provider: BTreeMap<PrincipalId, u64>,
+    pub rewards_per_node: BTreeMap<PrincipalId, NodeRewards>,
+}
+
+/// Calculates the rewards like [`calculate_rewards_v0`], and then reduces the
+/// rewards of each node according to its block failure rate (see
+/// [`rewards_reduction_permyriad`]).
+///
+/// The rewards of a Node Operator are split evenly among its rewardable nodes,
+/// where `nodes` maps the ID of each node in the registry to the ID of its Node
+/// Operator. If a Node Operator has more nodes than rewardable nodes, the
+/// nodes with the lowest IDs are considered to be the rewardable ones. Nodes
+/// without entry in `node_failure_stats` receive their full rewards.
+pub fn calculate_rewards_v1(
+    rewards_table: &NodeRewardsTable,
+    node_operators: &[(String, NodeOperatorRecord)],
+    data_centers: &BTreeMap<String, DataCenterRecord>,
+    nodes: &BTreeMap<PrincipalId, PrincipalId>,
+    node_failure_stats: &BTreeMap<PrincipalId, NodeFailureStats>,
+) -> Result<RewardsPerNode, String> {
+    let rewards_per_node_operator =
+        calculate_rewards_per_node_operator(rewards_table, node_operators, data_centers)?;
+
+    let mut nodes_per_node_operator = BTreeMap::<PrincipalId, Vec<PrincipalId>>::new();
+    for (node_id, node_operator_id) in nodes.iter() {
+        nodes_per_node_operator
+            .entry(*node_operator_id)
+            .or_default()
+            .push(*node_id);
+    }
+
+    let mut rewards_per_node_provider = BTreeMap::new();
+    let mut rewards_per_node = BTreeMap::new();
+    for (node_operator_id, node_operator_rewards) in rewards_per_node_operator.iter() {
+        let node_provider_id = node_operator_rewards.node_provider_id;
+        let mut np_reward = node_operator_rewards.xdr_permyriad;
+
+        let node_ids = nodes_per_node_operator
+            .get(node_operator_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for (i, node_id) in node_ids.iter().enumerate() {
+            let base_reward = if (i as u64) < node_operator_rewards.num_rewardable_nodes {
+                node_operator_rewards.xdr_permyriad / node_operator_rewards.num_rewardable_nodes
+            } else {
+                0
+            };
+            let failure_rate = node_failure_stats
+                .get(node_id)
+                .and_then(NodeFailureStats::failure_rate_permyriad);
+            let reduction = failure_rate.map_or(0, rewards_reduction_permyriad);
+            let reduction_amount = base_reward * reduction / 10_000;
+
+            np_reward -= reduction_amount;
+            rewards_per_node.insert(
+                *node_id,
+                NodeRewards {
+                    node_operator_id: *node_operator_id,
+                    node_provider_id,
+                    base_xdr_permyriad: base_reward,
+                    failure_rate_permyriad: failure_rate,
+                    reduction_permyriad: reduction,
+                    xdr_permyriad: base_reward - reduction_amount,
+                },
+            );
+        }
+
+        *rewards_per_node_provider
+            .entry(node_provider_id)
+            .or_default() += np_reward;
+    }
+
+    Ok(RewardsPerNode {
+        rewards_per_node_provider,
+        rewards_per_node,
+    })
+}
+
+/// The rewards of a Node Operator, before any performance-based reduction.
+struct NodeOperatorRewards {
+    node_provider_id: PrincipalId,
+    xdr_permyriad: u64,
+    num_rewardable_nodes: u64,
+}
+
+fn calculate_rewards_per_node_operator(
+    rewards_table: &NodeRewardsTable,
+    node_operators: &[(String, NodeOperatorRecord)],
+    data_centers: &BTreeMap<String, DataCenterRecord>,
+) -> Result<BTreeMap<PrincipalId, NodeOperatorRewards>, String> {
     // The reward coefficients for the NP, at the moment used only for type3 nodes, as a measure for stimulating decentralization.
     // It is kept outside of the reward calculation loop in order to reduce node rewards for NPs with multiple DCs.
     // We want to have as many independent NPs as possible for the given reward budget.
@@ -49,22 +174,22 @@ pub fn calculate_rewards_v0(
         })?;
         let region = &dc.region;
 
-        let np_rewards = rewards.entry(node_provider_id).or_default();
+        let no_rewards = rewards
+            .entry(node_operator_id)
+            .or_insert(NodeOperatorRewards {
+                node_provider_id,
+                xdr_permyriad: 0,
+                num_rewardable_nodes: 0,
+            });
         for (node_type, node_count) in node_operator.rewardable_nodes.iter() {
             let rate = match rewards_table.get_rate(region, node_type) {
                 Some(rate) => rate,
-                None => {
-                    println!(
-                                "The Node Rewards Table does not have an entry for \
-                             node type '{}' within region '{}' or parent region, defaulting to 1 xdr per month per node, for \
-                             NodeProvider '{}' on Node Operator '{}'",
-                                node_type, region, node_provider_id, node_operator_id
-                            );
-                    NodeRewardRate {
-                        xdr_permyriad_per_node_per_month: 1,
-                        reward_coefficient_percent: Some(100),
-                    }
-                }
+                // Node types without an entry in the Node Rewards Table, within the region or a
+                // parent region, default to 1 XDR permyriad per month per node.
+                None => NodeRewardRate {
+                    xdr_permyriad_per_node_per_month: 1,
+                    reward_coefficient_percent: Some(100),
+                },
             };
 
             let dc_reward = match &node_type {
@@ -117,17 +242,8 @@ pub fn calculate_rewards_v0(
                         rate.reward_coefficient_percent.unwrap_or(80) as f64 / 100.0;
 
                     let mut dc_reward = 0;
-                    for i in 0..*node_count {
+                    for _ in 0..*node_count {
                         let node_reward = (reward_base * np_coeff) as u64;
-                        println!(
-                            "NodeProvider {} {}/{} {} node in {} DC: reward {}",
-                            node_provider_id,
-                            i + 1,
-                            node_count,
-                            node_type.as_str(),
-                            node_operator.dc_id,
-                            node_reward,
-                        );
                         dc_reward += node_reward;
                         np_coeff *= dc_reward_coefficient_percent;
                     }
@@ -137,19 +253,113 @@ pub fn calculate_rewards_v0(
                 _ => *node_count as u64 * rate.xdr_permyriad_per_node_per_month,
             };
 
-            println!(
-                "NodeProvider {} reward for all {} {} nodes in {} DC: reward {}",
-                node_provider_id,
-                node_count,
-                node_type.as_str(),
-                node_operator.dc_id,
-                dc_reward,
-            );
-            *np_rewards += dc_reward;
+            no_rewards.xdr_permyriad += dc_reward;
+            no_rewards.num_rewardable_nodes += *node_count as u64;
         }
     }
 
-    Ok(RewardsPerNodeProvider {
-        rewards_per_node_provider: rewards,
-    })
+    Ok(rewards)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_protobuf::registry::node_rewards::v2::NodeRewardRates;
+
+    #[test]
+    fn rewards_v1_are_reduced_for_failing_nodes() {
+        let node_provider_id = PrincipalId::new_user_test_id(1);
+        let node_operator_id = PrincipalId::new_user_test_id(2);
+        let rewards_table = NodeRewardsTable {
+            table: BTreeMap::from([(
+                "Europe".to_string(),
+                NodeRewardRates {
+                    rates: BTreeMap::from([(
+                        "type1".to_string(),
+                        NodeRewardRate {
+                            xdr_permyriad_per_node_per_month: 1_000,
+                            reward_coefficient_percent: None,
+                        },
+                    )]),
+                },
+            )]),
+        };
+        let node_operators = vec![(
+            "node_operator".to_string(),
+            NodeOperatorRecord {
+                node_operator_principal_id: node_operator_id.to_vec(),
+                node_allowance: 0,
+                node_provider_principal_id: node_provider_id.to_vec(),
+                dc_id: "dc1".to_string(),
+                rewardable_nodes: BTreeMap::from([("type1".to_string(), 2)]),
+                ipv6: None,
+            },
+        )];
+        let data_centers = BTreeMap::from([(
+            "dc1".to_string(),
+            DataCenterRecord {
+                id: "dc1".to_string(),
+                region: "Europe,CH,Zurich".to_string(),
+                owner: "owner".to_string(),
+                gps: None,
+            },
+        )]);
+        let healthy_node = PrincipalId::new_node_test_id(1);
+        let failing_node = PrincipalId::new_node_test_id(2);
+        let unrewarded_node = PrincipalId::new_node_test_id(3);
+        let nodes = BTreeMap::from([
+            (healthy_node, node_operator_id),
+            (failing_node, node_operator_id),
+            (unrewarded_node, node_operator_id),
+        ]);
+        let node_failure_stats = BTreeMap::from([
+            (
+                healthy_node,
+                NodeFailureStats {
+                    num_blocks_proposed: 100,
+                    num_blocks_failed: 0,
+                },
+            ),
+            (
+                failing_node,
+                NodeFailureStats {
+                    num_blocks_proposed: 65,
+                    num_blocks_failed: 35,
+                },
+            ),
+        ]);
+
+        let rewards = calculate_rewards_v1(
+            &rewards_table,
+            &node_operators,
+            &data_centers,
+            &nodes,
+            &node_failure_stats,
+        )
+        .unwrap();
+
+        assert_eq!(
+            rewards.rewards_per_node_provider,
+            BTreeMap::from([(node_provider_id, 1_600)])
+        );
+        assert_eq!(rewards.rewards_per_node[&healthy_node].xdr_permyriad, 1_000);
+        assert_eq!(
+            rewards.rewards_per_node[&failing_node],
+            NodeRewards {
+                node_operator_id,
+                node_provider_id,
+                base_xdr_permyriad: 1_000,
+                failure_rate_permyriad: Some(3_500),
+                reduction_permyriad: 4_000,
+                xdr_permyriad: 600,
+            }
+        );
+        assert_eq!(rewards.rewards_per_node[&unrewarded_node].xdr_permyriad, 0);
+        assert_eq!(
+            calculate_rewards_v0(&rewards_table, &node_operators, &data_centers)
+                .unwrap()
+                .rewards_per_node_provider,
+            BTreeMap::from([(node_provider_id, 2_000)])
+        );
+    }
 }
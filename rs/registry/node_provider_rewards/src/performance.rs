@@ -0,0 +1,209 @@
+use ic_base_types::PrincipalId;
+use ic_management_canister_types::NodeMetricsHistoryResponse;
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+
+/// Nodes with a block failure rate up to this threshold receive their full
+/// rewards.
+pub const MIN_FAILURE_RATE_PERMYRIAD: u64 = 1_000;
+
+/// Nodes with a block failure rate at or above this threshold receive the
+/// maximum reduction of their rewards.
+pub const MAX_FAILURE_RATE_PERMYRIAD: u64 = 6_000;
+
+/// The maximum reduction of the rewards of a node, reached at
+/// `MAX_FAILURE_RATE_PERMYRIAD`.
+pub const MAX_REWARDS_REDUCTION_PERMYRIAD: u64 = 8_000;
+
+const PERMYRIAD: u64 = 10_000;
+
+/// The number of blocks a node proposed and failed to propose (i.e. it was
+/// the block maker but the block was not finalized) over a reward period.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NodeFailureStats {
+    pub num_blocks_proposed: u64,
+    pub num_blocks_failed: u64,
+}
+
+impl NodeFailureStats {
+    /// Returns the share of failed blocks in permyriad, or `None` if the node
+    /// was not a block maker in the period, e.g. because it is unassigned.
+    pub fn failure_rate_permyriad(&self) -> Option<u64> {
+        let total = self.num_blocks_proposed + self.num_blocks_failed;
+        if total == 0 {
+            return None;
+        }
+        Some(self.num_blocks_failed * PERMYRIAD / total)
+    }
+}
+
+impl AddAssign for NodeFailureStats {
+    fn add_assign(&mut self, other: Self) {
+        self.num_blocks_proposed += other.num_blocks_proposed;
+        self.num_blocks_failed += other.num_blocks_failed;
+    }
+}
+
+/// Returns the reduction (in permyriad) of the rewards of a node with the
+/// given block failure rate.
+///
+/// There is no reduction up to `MIN_FAILURE_RATE_PERMYRIAD`, and the
+/// reduction grows linearly from there to `MAX_REWARDS_REDUCTION_PERMYRIAD`
+/// at `MAX_FAILURE_RATE_PERMYRIAD`. Only integer arithmetic is used so that
+/// every party computing the rewards arrives at the same result.
+pub fn rewards_reduction_permyriad(failure_rate_permyriad: u64) -> u64 {
+    if failure_rate_permyriad <= MIN_FAILURE_RATE_PERMYRIAD {
+        0
+    } else if failure_rate_permyriad >= MAX_FAILURE_RATE_PERMYRIAD {
+        MAX_REWARDS_REDUCTION_PERMYRIAD
+    } else {
+        MAX_REWARDS_REDUCTION_PERMYRIAD * (failure_rate_permyriad - MIN_FAILURE_RATE_PERMYRIAD)
+            / (MAX_FAILURE_RATE_PERMYRIAD - MIN_FAILURE_RATE_PERMYRIAD)
+    }
+}
+
+/// Computes the block statistics of each node over the period covered by the
+/// `node_metrics_history` of a single subnet.
+///
+/// The metrics in the history are totals since a node joined the subnet. The
+/// oldest entry serves as the baseline, i.e. to cover a period starting on day
+/// `d`, the history should start on day `d - 1`. A node that (re)joins the
+/// subnet within the period is counted from zero.
+pub fn node_failure_stats_from_metrics_history(
+    node_metrics_history: &[NodeMetricsHistoryResponse],
+) -> BTreeMap<PrincipalId, NodeFailureStats> {
+    let mut history: Vec<&NodeMetricsHistoryResponse> = node_metrics_history.iter().collect();
+    history.sort_by_key(|entry| entry.timestamp_nanos);
+
+    let mut stats = BTreeMap::<PrincipalId, NodeFailureStats>::new();
+    let mut history = history.into_iter();
+    let mut last_totals: BTreeMap<PrincipalId, NodeFailureStats> = match history.next() {
+        Some(baseline) => baseline
+            .node_metrics
+            .iter()
+            .map(|metrics| {
+                (
+                    metrics.node_id,
+                    NodeFailureStats {
+                        num_blocks_proposed: metrics.num_blocks_proposed_total,
+                        num_blocks_failed: metrics.num_block_failures_total,
+                    },
+                )
+            })
+            .collect(),
+        None => return stats,
+    };
+
+    for entry in history {
+        let mut totals = BTreeMap::new();
+        for metrics in entry.node_metrics.iter() {
+            let current = NodeFailureStats {
+                num_blocks_proposed: metrics.num_blocks_proposed_total,
+                num_blocks_failed: metrics.num_block_failures_total,
+            };
+            let previous = last_totals
+                .get(&metrics.node_id)
+                .copied()
+                .unwrap_or_default();
+            // The totals are reset if the node left the subnet and rejoined it.
+            let delta = if current.num_blocks_proposed >= previous.num_blocks_proposed
+                && current.num_blocks_failed >= previous.num_blocks_failed
+            {
+                NodeFailureStats {
+                    num_blocks_proposed: current.num_blocks_proposed - previous.num_blocks_proposed,
+                    num_blocks_failed: current.num_blocks_failed - previous.num_blocks_failed,
+                }
+            } else {
+                current
+            };
+            *stats.entry(metrics.node_id).or_default() += delta;
+            totals.insert(metrics.node_id, current);
+        }
+        last_totals = totals;
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_management_canister_types::NodeMetrics;
+
+    fn node_metrics(node_id: u64, proposed: u64, failed: u64) -> NodeMetrics {
+        NodeMetrics {
+            node_id: PrincipalId::new_node_test_id(node_id),
+            num_blocks_proposed_total: proposed,
+            num_block_failures_total: failed,
+        }
+    }
+
+    #[test]
+    fn rewards_reduction_is_linear_between_thresholds() {
+        assert_eq!(rewards_reduction_permyriad(0), 0);
+        assert_eq!(rewards_reduction_permyriad(MIN_FAILURE_RATE_PERMYRIAD), 0);
+        assert_eq!(rewards_reduction_permyriad(3_500), 4_000);
+        assert_eq!(
+            rewards_reduction_permyriad(MAX_FAILURE_RATE_PERMYRIAD),
+            MAX_REWARDS_REDUCTION_PERMYRIAD
+        );
+        assert_eq!(
+            rewards_reduction_permyriad(PERMYRIAD),
+            MAX_REWARDS_REDUCTION_PERMYRIAD
+        );
+    }
+
+    #[test]
+    fn failure_rate_is_none_without_blocks() {
+        assert_eq!(NodeFailureStats::default().failure_rate_permyriad(), None);
+        assert_eq!(
+            NodeFailureStats {
+                num_blocks_proposed: 3,
+                num_blocks_failed: 1,
+            }
+            .failure_rate_permyriad(),
+            Some(2_500)
+        );
+    }
+
+    #[test]
+    fn failure_stats_are_computed_relative_to_the_oldest_entry() {
+        let history = vec![
+            NodeMetricsHistoryResponse {
+                timestamp_nanos: 2,
+                node_metrics: vec![node_metrics(1, 150, 20), node_metrics(2, 5, 0)],
+            },
+            NodeMetricsHistoryResponse {
+                timestamp_nanos: 0,
+                node_metrics: vec![node_metrics(1, 100, 10)],
+            },
+            NodeMetricsHistoryResponse {
+                timestamp_nanos: 1,
+                node_metrics: vec![node_metrics(1, 120, 15), node_metrics(2, 10, 1)],
+            },
+        ];
+
+        let stats = node_failure_stats_from_metrics_history(&history);
+
+        assert_eq!(
+            stats,
+            BTreeMap::from([
+                (
+                    PrincipalId::new_node_test_id(1),
+                    NodeFailureStats {
+                        num_blocks_proposed: 50,
+                        num_blocks_failed: 10,
+                    }
+                ),
+                // Node 2 joined at timestamp 1 and its totals were reset at 2.
+                (
+                    PrincipalId::new_node_test_id(2),
+                    NodeFailureStats {
+                        num_blocks_proposed: 15,
+                        num_blocks_failed: 1,
+                    }
+                ),
+            ])
+        );
+    }
+}
@@ -15,6 +15,29 @@ impl std::str::FromStr for ClapSubnetId {
     }
 }
 
+/// The Candid interface of a canister, given as `<canister_id>=<path>`.
+#[derive(Clone, Debug)]
+pub struct DidFile {
+    pub canister_id: CanisterId,
+    pub path: PathBuf,
+}
+
+impl std::str::FromStr for DidFile {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (canister_id, path) = s
+            .split_once('=')
+            .ok_or_else(|| format!("Expected <canister_id>=<path>, got {:?}", s))?;
+        let canister_id = canister_id
+            .parse::<CanisterId>()
+            .map_err(|e| format!("Unable to parse canister_id {:?}", e))?;
+        Ok(DidFile {
+            canister_id,
+            path: PathBuf::from(path),
+        })
+    }
+}
+
 #[derive(Parser)]
 #[clap(version = "1.0")]
 pub struct ReplayToolArgs {
@@ -39,6 +62,21 @@ pub struct ReplayToolArgs {
     #[clap(long)]
     /// The replay will stop at this height and make a checkpoint.
     pub replay_until_height: Option<u64>,
+
+    /// Write every executed message and the state hash after each batch to
+    /// this file, in the JSON lines format.
+    #[clap(long)]
+    pub trace_out: Option<PathBuf>,
+
+    /// After the replay, start an interactive console for query calls and
+    /// read-only inspection of canisters in the replayed state.
+    #[clap(long)]
+    pub query_console: bool,
+
+    /// Candid interface used by the query console to encode the arguments and
+    /// decode the replies of a canister, as `<canister_id>=<path to .did>`.
+    #[clap(long)]
+    pub did_file: Vec<DidFile>,
 }
 
 #[derive(Clone, Parser)]
@@ -0,0 +1,388 @@
+//! An interactive console for inspecting canisters in the replayed state.
+//!
+//! The console keeps the state manager and the execution environment of the
+//! replay alive, so that query calls are executed against the state at the
+//! height the replay stopped at. Nothing is executed in replicated mode and no
+//! state is modified.
+
+use crate::{cmd::DidFile, player::Player};
+use candid::{
+    types::{Type, TypeEnv},
+    IDLArgs,
+};
+use candid_parser::{parse_idl_args, utils::CandidSource};
+use ic_replicated_state::{
+    canister_state::WASM_PAGE_SIZE_IN_BYTES, page_map::Buffer, ExecutionState,
+};
+use ic_types::{ingress::WasmResult, time::current_time, CanisterId, Height};
+use std::{
+    collections::BTreeMap,
+    io::{stdin, stdout, BufRead, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+const HELP: &str = "\
+Commands:
+  query <canister_id> <method> [<candid args>]   Execute a query call
+  status <canister_id>                           Show the status of a canister
+  stable <canister_id> <offset> <length> [<file>]
+                                                 Dump a range of stable memory,
+                                                 as hex or to a file
+  help                                           Show this help
+  exit                                           Leave the console";
+
+// The expiry of the query calls, relative to the current time.
+const INGRESS_EXPIRY: Duration = Duration::from_secs(4 * 60);
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Query {
+        canister_id: CanisterId,
+        method: String,
+        args: String,
+    },
+    Status {
+        canister_id: CanisterId,
+    },
+    StableMemory {
+        canister_id: CanisterId,
+        offset: usize,
+        length: usize,
+        output: Option<PathBuf>,
+    },
+    Help,
+    Exit,
+}
+
+/// The Candid interface of a canister: its type environment and the type of
+/// its service.
+struct Interface {
+    env: TypeEnv,
+    service: Type,
+}
+
+/// Runs the console on stdin until `exit` or the end of the input.
+pub fn run(player: &Player, did_files: &[DidFile], height: Height) -> Result<(), String> {
+    let interfaces = load_interfaces(did_files)?;
+    println!(
+        "Query console at height {}. Type `help` for a list of commands.",
+        height
+    );
+
+    let mut lines = stdin().lock().lines();
+    loop {
+        print!("> ");
+        let _ = stdout().flush();
+        let line = match lines.next() {
+            Some(line) => line.map_err(|err| format!("Couldn't read user input: {}", err))?,
+            None => return Ok(()),
+        };
+        let result = match parse_command(&line) {
+            Ok(None) => Ok(()),
+            Ok(Some(Command::Exit)) => return Ok(()),
+            Ok(Some(command)) => execute(player, &interfaces, command),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = result {
+            println!("Error: {}", err);
+        }
+    }
+}
+
+fn load_interfaces(did_files: &[DidFile]) -> Result<BTreeMap<CanisterId, Interface>, String> {
+    let mut interfaces = BTreeMap::new();
+    for did_file in did_files {
+        let (env, service) = CandidSource::File(&did_file.path)
+            .load()
+            .map_err(|err| format!("Couldn't load {:?}: {}", did_file.path, err))?;
+        let service = service.ok_or_else(|| format!("{:?} defines no service", did_file.path))?;
+        interfaces.insert(did_file.canister_id, Interface { env, service });
+    }
+    Ok(interfaces)
+}
+
+fn parse_command(line: &str) -> Result<Option<Command>, String> {
+    let line = line.trim();
+    let (name, rest) = match line.split_once(char::is_whitespace) {
+        Some((name, rest)) => (name, rest.trim_start()),
+        None => (line, ""),
+    };
+    let mut words = rest.split_whitespace();
+    let mut canister_id = || -> Result<CanisterId, String> {
+        words
+            .next()
+            .ok_or_else(|| "Missing canister id".to_string())?
+            .parse::<CanisterId>()
+            .map_err(|err| format!("Invalid canister id: {:?}", err))
+    };
+    let command = match name {
+        "" => return Ok(None),
+        "query" => {
+            let canister_id = canister_id()?;
+            let mut args = rest.splitn(3, char::is_whitespace).skip(1);
+            let method = args
+                .next()
+                .filter(|method| !method.is_empty())
+                .ok_or_else(|| "Missing method name".to_string())?;
+            Command::Query {
+                canister_id,
+                method: method.to_string(),
+                args: args.next().unwrap_or_default().trim().to_string(),
+            }
+        }
+        "status" => Command::Status {
+            canister_id: canister_id()?,
+        },
+        "stable" => {
+            let canister_id = canister_id()?;
+            let mut number = |name: &str| -> Result<usize, String> {
+                words
+                    .next()
+                    .ok_or_else(|| format!("Missing {}", name))?
+                    .parse::<usize>()
+                    .map_err(|err| format!("Invalid {}: {}", name, err))
+            };
+            let offset = number("offset")?;
+            let length = number("length")?;
+            Command::StableMemory {
+                canister_id,
+                offset,
+                length,
+                output: words.next().map(PathBuf::from),
+            }
+        }
+        "help" => Command::Help,
+        "exit" | "quit" => Command::Exit,
+        _ => return Err(format!("Unknown command {:?}. Type `help` for help.", name)),
+    };
+    Ok(Some(command))
+}
+
+fn execute(
+    player: &Player,
+    interfaces: &BTreeMap<CanisterId, Interface>,
+    command: Command,
+) -> Result<(), String> {
+    match command {
+        Command::Query {
+            canister_id,
+            method,
+            args,
+        } => {
+            let interface = interfaces.get(&canister_id);
+            let payload = encode_args(interface, &method, &args)?;
+            match player.query(
+                canister_id,
+                &method,
+                payload,
+                current_time() + INGRESS_EXPIRY,
+            )? {
+                WasmResult::Reply(bytes) => {
+                    println!("{}", decode_reply(interface, &method, &bytes))
+                }
+                WasmResult::Reject(message) => println!("Rejected: {}", message),
+            }
+        }
+        Command::Status { canister_id } => print_status(player, canister_id)?,
+        Command::StableMemory {
+            canister_id,
+            offset,
+            length,
+            output,
+        } => {
+            let bytes = read_stable_memory(player, canister_id, offset, length)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, &bytes)
+                        .map_err(|err| format!("Couldn't write {:?}: {}", path, err))?;
+                    println!("Wrote {} bytes to {:?}", bytes.len(), path);
+                }
+                None => println!("{}", hex::encode(bytes)),
+            }
+        }
+        Command::Help => println!("{}", HELP),
+        Command::Exit => {}
+    }
+    Ok(())
+}
+
+/// Encodes the Candid text `args`, using the argument types of `method` if
+/// the interface of the canister is known.
+fn encode_args(interface: Option<&Interface>, method: &str, args: &str) -> Result<Vec<u8>, String> {
+    let args = if args.is_empty() { "()" } else { args };
+    let args = parse_idl_args(args).map_err(|err| format!("Invalid Candid arguments: {}", err))?;
+    match interface {
+        Some(interface) => {
+            let function = interface
+                .env
+                .get_method(&interface.service, method)
+                .map_err(|err| format!("{}", err))?;
+            args.to_bytes_with_types(&interface.env, &function.args)
+        }
+        None => args.to_bytes(),
+    }
+    .map_err(|err| format!("Couldn't encode the arguments: {}", err))
+}
+
+/// Decodes a reply to Candid text, using the return types of `method` if the
+/// interface of the canister is known. Replies that aren't valid Candid are
+/// returned as hex.
+fn decode_reply(interface: Option<&Interface>, method: &str, bytes: &[u8]) -> String {
+    let decoded = match interface {
+        Some(interface) => interface
+            .env
+            .get_method(&interface.service, method)
+            .and_then(|function| {
+                IDLArgs::from_bytes_with_types(bytes, &interface.env, &function.rets)
+            }),
+        None => IDLArgs::from_bytes(bytes),
+    };
+    match decoded {
+        Ok(args) => args.to_string(),
+        Err(_) => format!("0x{}", hex::encode(bytes)),
+    }
+}
+
+fn print_status(player: &Player, canister_id: CanisterId) -> Result<(), String> {
+    let state = player.get_latest_state();
+    let canister = state
+        .canister_state(&canister_id)
+        .ok_or_else(|| format!("Canister {} not found", canister_id))?;
+    let controllers: Vec<_> = canister
+        .controllers()
+        .iter()
+        .map(|controller| controller.to_string())
+        .collect();
+    let module_hash = canister
+        .execution_state
+        .as_ref()
+        .map(|execution_state| hex::encode(execution_state.wasm_binary.binary.module_hash()));
+    println!("Status: {}", canister.status());
+    println!("Controllers: {}", controllers.join(", "));
+    println!(
+        "Module hash: {}",
+        module_hash.unwrap_or_else(|| "none".to_string())
+    );
+    println!("Cycles balance: {}", canister.system_state.balance());
+    println!(
+        "Reserved cycles: {}",
+        canister.system_state.reserved_balance()
+    );
+    println!(
+        "Freezing threshold: {} seconds",
+        canister.system_state.freeze_threshold
+    );
+    println!("Compute allocation: {}", canister.compute_allocation());
+    println!("Memory allocation: {}", canister.memory_allocation());
+    println!("Memory usage: {} bytes", canister.memory_usage().get());
+    println!(
+        "Stable memory size: {} bytes",
+        stable_memory_size(canister.execution_state.as_ref())
+    );
+    Ok(())
+}
+
+fn stable_memory_size(execution_state: Option<&ExecutionState>) -> usize {
+    execution_state.map_or(0, |execution_state| {
+        execution_state.stable_memory.size.get() * WASM_PAGE_SIZE_IN_BYTES
+    })
+}
+
+fn read_stable_memory(
+    player: &Player,
+    canister_id: CanisterId,
+    offset: usize,
+    length: usize,
+) -> Result<Vec<u8>, String> {
+    let state = player.get_latest_state();
+    let canister = state
+        .canister_state(&canister_id)
+        .ok_or_else(|| format!("Canister {} not found", canister_id))?;
+    let execution_state = canister
+        .execution_state
+        .as_ref()
+        .ok_or_else(|| format!("Canister {} is empty", canister_id))?;
+    let size = stable_memory_size(Some(execution_state));
+    if offset.checked_add(length).map_or(true, |end| end > size) {
+        return Err(format!(
+            "The range {}..{} exceeds the stable memory size of {} bytes",
+            offset,
+            offset.saturating_add(length),
+            size
+        ));
+    }
+    let mut bytes = vec![0; length];
+    Buffer::new(execution_state.stable_memory.page_map.clone()).read(&mut bytes, offset);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_types::ids::canister_test_id;
+
+    const DID: &str = r#"service : {
+        get : (text) -> (opt nat64) query;
+    }"#;
+
+    fn interface() -> Interface {
+        let (env, service) = CandidSource::Text(DID).load().unwrap();
+        Interface {
+            env,
+            service: service.unwrap(),
+        }
+    }
+
+    #[test]
+    fn parses_commands() {
+        let canister_id = canister_test_id(1);
+        assert_eq!(parse_command("  "), Ok(None));
+        assert_eq!(
+            parse_command(&format!("query {} get (\"a b\")", canister_id)),
+            Ok(Some(Command::Query {
+                canister_id,
+                method: "get".to_string(),
+                args: "(\"a b\")".to_string(),
+            }))
+        );
+        assert_eq!(
+            parse_command(&format!("query {} get", canister_id)),
+            Ok(Some(Command::Query {
+                canister_id,
+                method: "get".to_string(),
+                args: String::new(),
+            }))
+        );
+        assert_eq!(
+            parse_command(&format!("status {}", canister_id)),
+            Ok(Some(Command::Status { canister_id }))
+        );
+        assert_eq!(
+            parse_command(&format!("stable {} 16 32 /tmp/dump", canister_id)),
+            Ok(Some(Command::StableMemory {
+                canister_id,
+                offset: 16,
+                length: 32,
+                output: Some(PathBuf::from("/tmp/dump")),
+            }))
+        );
+        assert_eq!(parse_command("exit"), Ok(Some(Command::Exit)));
+        assert!(parse_command(&format!("query {}", canister_id)).is_err());
+        assert!(parse_command("stable abc 1 2").is_err());
+        assert!(parse_command("update").is_err());
+    }
+
+    #[test]
+    fn encodes_and_decodes_with_interface() {
+        let interface = interface();
+        let bytes = encode_args(Some(&interface), "get", "(\"key\")").unwrap();
+        assert_eq!(bytes, candid::Encode!(&"key").unwrap());
+        assert!(encode_args(Some(&interface), "get", "(1)").is_err());
+        assert!(encode_args(Some(&interface), "put", "(\"key\")").is_err());
+
+        let reply = candid::Encode!(&Some(42_u64)).unwrap();
+        assert!(decode_reply(Some(&interface), "get", &reply).contains("42"));
+        assert_eq!(decode_reply(None, "get", &[1, 2]), "0x0102");
+    }
+}
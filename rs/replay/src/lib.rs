@@ -15,9 +15,10 @@
 //! Use `ic-replay --help` to find out more.
 
 use crate::{
-    cmd::{ReplayToolArgs, SubCommand},
+    cmd::{DidFile, ReplayToolArgs, SubCommand},
     ingress::*,
     player::{Player, ReplayResult},
+    trace::TraceWriter,
 };
 use ic_canister_client::{Agent, Sender};
 use ic_config::{Config, ConfigSource};
@@ -25,14 +26,16 @@ use ic_nns_constants::GOVERNANCE_CANISTER_ID;
 use ic_protobuf::{registry::subnet::v1::InitialNiDkgTranscriptRecord, types::v1 as pb};
 use ic_types::ReplicaVersion;
 use prost::Message;
-use std::{cell::RefCell, convert::TryFrom, rc::Rc};
+use std::{cell::RefCell, convert::TryFrom, rc::Rc, sync::Arc};
 
 mod backup;
 pub mod cmd;
+mod console;
 pub mod ingress;
 mod mocks;
 pub mod player;
 mod registry_helper;
+pub mod trace;
 mod validator;
 
 /// Replays the past blocks and creates a checkpoint of the latest state.
@@ -54,6 +57,9 @@ mod validator;
 ///     canister_caller_id: None,
 ///     replay_until_height: None,
 ///     data_root: None,
+///     trace_out: None,
+///     query_console: false,
+///     did_file: Vec::new(),
 ///     subcmd: Some(SubCommand::RestoreFromBackup(RestoreFromBackupCmd {
 ///         registry_local_store_path: PathBuf::from("/path/to/ic_registry_local_store"),
 ///         backup_spool_path: PathBuf::from("/path/to/spool"),
@@ -98,6 +104,13 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
             })
             .0;
 
+        let trace = args.trace_out.as_ref().map(|path| {
+            Arc::new(TraceWriter::create(path).unwrap_or_else(|err| {
+                println!("{}", err);
+                std::process::exit(1);
+            }))
+        });
+
         let target_height = args.replay_until_height;
         if let Some(h) = target_height {
             let question = format!("The checkpoint created at height {} ", h)
@@ -119,9 +132,13 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                 &cmd.registry_local_store_path,
                 subnet_id,
                 cmd.start_height,
+                trace,
             )
             .with_replay_target_height(target_height);
             *res_clone.borrow_mut() = player.restore(cmd.start_height + 1);
+            if args.query_console {
+                run_query_console(&player, &args.did_file, &res_clone.borrow());
+            }
             return;
         }
 
@@ -134,7 +151,7 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                 );
                 }
                 (_, target_height) => {
-                    Player::new(cfg, subnet_id).with_replay_target_height(target_height)
+                    Player::new(cfg, subnet_id, trace).with_replay_target_height(target_height)
                 }
             };
 
@@ -204,6 +221,9 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                     }
                 }
                 err => err,
+            };
+            if args.query_console {
+                run_query_console(&player, &args.did_file, &res_clone.borrow());
             }
         }
     });
@@ -211,6 +231,18 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
     ret
 }
 
+// Starts the query console against the replayed state, unless the replay failed.
+fn run_query_console(player: &Player, did_files: &[DidFile], result: &ReplayResult) {
+    match result {
+        Ok(state_params) => {
+            if let Err(err) = console::run(player, did_files, state_params.height) {
+                println!("Query console failed: {}", err);
+            }
+        }
+        Err(err) => println!("Not starting the query console: {:?}", err),
+    }
+}
+
 /// Prints a question to the user and returns `true`
 /// if the user replied with a yes.
 pub fn consent_given(question: &str) -> bool {
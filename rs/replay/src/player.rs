@@ -2,6 +2,7 @@ use crate::{
     backup,
     backup::{cup_file_name, rename_file},
     ingress::IngressWithPrinter,
+    trace::TraceWriter,
     validator::{InvalidArtifact, ReplayValidator},
 };
 use ic_artifact_pool::{
@@ -19,7 +20,7 @@ use ic_crypto_test_utils_ni_dkg::{
     dummy_initial_dkg_transcript_with_master_key, sign_message, SecretKeyBytes,
 };
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{ExecutionServices, ExecutionSetup, ExecutionTracer};
 use ic_interfaces::{
     certification::CertificationPool,
     execution_environment::{IngressHistoryReader, QueryExecutionError, QueryExecutionService},
@@ -51,6 +52,7 @@ use ic_registry_transport::{
     deserialize_get_value_response, serialize_get_changes_since_request,
     serialize_get_value_request,
 };
+use ic_replicated_state::ReplicatedState;
 use ic_state_manager::StateManagerImpl;
 use ic_types::{
     batch::{Batch, BatchMessages, BlockmakerMetrics},
@@ -67,8 +69,8 @@ use ic_types::{
     messages::{CertificateDelegation, Query, QuerySource},
     signature::ThresholdSignature,
     time::current_time,
-    CryptoHashOfPartialState, CryptoHashOfState, Height, NodeId, PrincipalId, Randomness,
-    RegistryVersion, ReplicaVersion, SubnetId, Time, UserId,
+    CanisterId, CryptoHashOfPartialState, CryptoHashOfState, Height, NodeId, PrincipalId,
+    Randomness, RegistryVersion, ReplicaVersion, SubnetId, Time, UserId,
 };
 use rand::{rngs::StdRng, SeedableRng};
 use serde::{Deserialize, Serialize};
@@ -136,12 +138,15 @@ pub struct Player {
     // The target height until which the state will be replayed.
     // None means finalized height.
     replay_target_height: Option<u64>,
+    // The trace of the executions, if requested.
+    trace: Option<Arc<TraceWriter>>,
     runtime: Runtime,
 }
 
 impl Player {
     /// Create and return a `Player` from a replica configuration object for
-    /// restoring states from backups.
+    /// restoring states from backups. If `trace` is set, the executions are
+    /// written to it.
     pub fn new_for_backup(
         mut cfg: Config,
         replica_version: ReplicaVersion,
@@ -149,6 +154,7 @@ impl Player {
         registry_local_store_path: &Path,
         subnet_id: SubnetId,
         start_height: u64,
+        trace: Option<Arc<TraceWriter>>,
     ) -> Self {
         let (log, _async_log_guard) = new_replica_logger_from_config(&cfg.logger);
 
@@ -207,14 +213,15 @@ impl Player {
             replica_version,
             log,
             _async_log_guard,
+            trace,
         );
         player.tmp_dir = Some(tmp_dir);
         player
     }
 
     /// Create and return a `Player` from a replica configuration object for
-    /// subnet recovery.
-    pub fn new(cfg: Config, subnet_id: SubnetId) -> Self {
+    /// subnet recovery. If `trace` is set, the executions are written to it.
+    pub fn new(cfg: Config, subnet_id: SubnetId, trace: Option<Arc<TraceWriter>>) -> Self {
         let (log, _async_log_guard) = new_replica_logger_from_config(&cfg.logger);
         let metrics_registry = MetricsRegistry::new();
         let registry = setup_registry(cfg.clone(), Some(&metrics_registry));
@@ -253,6 +260,7 @@ impl Player {
             replica_version,
             log,
             _async_log_guard,
+            trace,
         )
     }
 
@@ -266,6 +274,7 @@ impl Player {
         replica_version: ReplicaVersion,
         log: ReplicaLogger,
         _async_log_guard: AsyncGuard,
+        trace: Option<Arc<TraceWriter>>,
     ) -> Self {
         println!("Setting default replica version {}", replica_version);
         if ReplicaVersion::set_default_version(replica_version.clone()).is_err() {
@@ -307,17 +316,20 @@ impl Player {
             MaliciousFlags::default(),
         ));
         let (completed_execution_messages_tx, _) = tokio::sync::mpsc::channel(1);
-        let execution_service = ExecutionServices::setup_execution(
-            log.clone(),
-            &metrics_registry,
-            subnet_id,
-            subnet_type,
-            subnet_config.scheduler_config,
-            cfg.hypervisor.clone(),
-            Arc::clone(&cycles_account_manager),
-            Arc::clone(&state_manager) as Arc<_>,
-            state_manager.get_fd_factory(),
-            completed_execution_messages_tx,
+        let execution_service = ExecutionServices::setup_execution_with_tracer(
+            ExecutionSetup {
+                logger: log.clone(),
+                metrics_registry: &metrics_registry,
+                own_subnet_id: subnet_id,
+                own_subnet_type: subnet_type,
+                scheduler_config: subnet_config.scheduler_config,
+                config: cfg.hypervisor.clone(),
+                cycles_account_manager: Arc::clone(&cycles_account_manager),
+                state_reader: Arc::clone(&state_manager) as Arc<_>,
+                fd_factory: state_manager.get_fd_factory(),
+                completed_execution_messages_tx,
+            },
+            trace.clone().map(|trace| trace as Arc<dyn ExecutionTracer>),
         );
         let message_routing = Arc::new(MessageRoutingImpl::new(
             state_manager.clone(),
@@ -385,6 +397,7 @@ impl Player {
             _async_log_guard,
             tmp_dir: None,
             replay_target_height: None,
+            trace,
             runtime,
         }
     }
@@ -631,6 +644,9 @@ impl Player {
             height,
             self.state_manager.latest_state_height()
         );
+        if let Some(trace) = &self.trace {
+            trace.record_state_hashes(self.state_manager.list_state_hashes_to_certify());
+        }
     }
 
     /// Return latest height and state hash according to state manager (latest checkpoint or CUP
@@ -954,6 +970,43 @@ impl Player {
         }
     }
 
+    /// Execute a query call from the anonymous user against the latest state.
+    pub fn query(
+        &self,
+        receiver: CanisterId,
+        method_name: &str,
+        method_payload: Vec<u8>,
+        ingress_expiry: Time,
+    ) -> Result<WasmResult, String> {
+        let query = Query {
+            source: QuerySource::User {
+                user_id: UserId::from(PrincipalId::new_anonymous()),
+                ingress_expiry: ingress_expiry.as_nanos_since_unix_epoch(),
+                nonce: None,
+            },
+            receiver,
+            method_name: method_name.to_string(),
+            method_payload,
+        };
+        self.certify_state_with_dummy_certification();
+        match self
+            .runtime
+            .block_on(self.query_handler.clone().oneshot((query, None)))
+            .unwrap()
+        {
+            Ok((Ok(wasm_result), _)) => Ok(wasm_result),
+            Ok((Err(err), _)) => Err(format!("Query failed: {}", err)),
+            Err(QueryExecutionError::CertifiedStateUnavailable) => {
+                panic!("Certified state unavailable for query call.")
+            }
+        }
+    }
+
+    /// Return the latest replicated state.
+    pub fn get_latest_state(&self) -> Arc<ReplicatedState> {
+        self.state_manager.get_latest_state().take()
+    }
+
     /// Return the highest CatchUpPackage
     pub fn get_highest_catch_up_package(&self) -> CatchUpPackage {
         PoolReader::new(self.consensus_pool.as_ref().unwrap()).get_highest_catch_up_package()
@@ -0,0 +1,215 @@
+//! Writes a trace of the replayed executions to a file in the JSON lines
+//! format, as requested with `--trace-out`.
+//!
+//! Every executed ingress message, inter-canister request or response,
+//! heartbeat and timer results in one `message` line, and every replayed batch
+//! in one `batch` line carrying the hash of the resulting state. Messages are
+//! written as they are executed, so a `batch` line may follow the `message`
+//! lines of several batches; the `height` field relates them to each other.
+
+use ic_execution_environment::{
+    ExecutionTraceEntry, ExecutionTraceKind, ExecutionTraceResult, ExecutionTracer,
+};
+use ic_types::{CryptoHashOfPartialState, ExecutionRound, Height};
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TraceLine {
+    Message {
+        height: u64,
+        canister_id: String,
+        kind: &'static str,
+        method: Option<String>,
+        caller: Option<String>,
+        instructions_used: u64,
+        cycles_charged: u128,
+        result: Option<&'static str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        reject_code: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error_code: Option<String>,
+    },
+    Batch {
+        height: u64,
+        state_hash: String,
+    },
+}
+
+struct TraceFile {
+    writer: BufWriter<File>,
+    // The height of the last state whose hash was written.
+    last_state_height: Option<Height>,
+}
+
+/// An execution tracer writing the trace of a replay to a file.
+pub struct TraceWriter {
+    file: Mutex<TraceFile>,
+}
+
+impl TraceWriter {
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path)
+            .map_err(|err| format!("Couldn't create the trace file {:?}: {}", path, err))?;
+        Ok(Self {
+            file: Mutex::new(TraceFile {
+                writer: BufWriter::new(file),
+                last_state_height: None,
+            }),
+        })
+    }
+
+    /// Writes the hashes of all states above the last traced one and flushes
+    /// the trace to the file.
+    pub fn record_state_hashes(&self, state_hashes: Vec<(Height, CryptoHashOfPartialState)>) {
+        let mut file = self.file.lock().unwrap();
+        for (height, hash) in state_hashes {
+            if file.last_state_height.map_or(false, |last| height <= last) {
+                continue;
+            }
+            write_line(
+                &mut file.writer,
+                &TraceLine::Batch {
+                    height: height.get(),
+                    state_hash: hex::encode(hash.get().0),
+                },
+            );
+            file.last_state_height = Some(height);
+        }
+        file.writer
+            .flush()
+            .unwrap_or_else(|err| panic!("Couldn't flush the trace: {}", err));
+    }
+}
+
+impl ExecutionTracer for TraceWriter {
+    fn record(&self, round: ExecutionRound, entry: ExecutionTraceEntry) {
+        let (result, reject_code, error_code) = match entry.result {
+            ExecutionTraceResult::Reply => (Some("reply"), None, None),
+            ExecutionTraceResult::Reject(code) => (Some("reject"), Some(code as u64), None),
+            ExecutionTraceResult::Error(code) => (Some("error"), None, Some(code.to_string())),
+            ExecutionTraceResult::None => (None, None, None),
+        };
+        let line = TraceLine::Message {
+            height: round.get(),
+            canister_id: entry.canister_id.to_string(),
+            kind: kind_name(entry.kind),
+            method: entry.method,
+            caller: entry.caller.map(|caller| caller.to_string()),
+            instructions_used: entry.instructions_used.get(),
+            cycles_charged: entry.cycles_charged.get(),
+            result,
+            reject_code,
+            error_code,
+        };
+        write_line(&mut self.file.lock().unwrap().writer, &line);
+    }
+}
+
+fn kind_name(kind: ExecutionTraceKind) -> &'static str {
+    match kind {
+        ExecutionTraceKind::Ingress => "ingress",
+        ExecutionTraceKind::Request => "request",
+        ExecutionTraceKind::Response => "response",
+        ExecutionTraceKind::Heartbeat => "heartbeat",
+        ExecutionTraceKind::GlobalTimer => "global_timer",
+        ExecutionTraceKind::OnLowWasmMemory => "on_low_wasm_memory",
+        ExecutionTraceKind::OnLowCycles => "on_low_cycles",
+    }
+}
+
+fn write_line(writer: &mut BufWriter<File>, line: &TraceLine) {
+    serde_json::to_writer(&mut *writer, line)
+        .unwrap_or_else(|err| panic!("Couldn't write to the trace: {}", err));
+    writeln!(writer).unwrap_or_else(|err| panic!("Couldn't write to the trace: {}", err));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_error_types::{ErrorCode, RejectCode};
+    use ic_test_utilities_types::ids::{canister_test_id, user_test_id};
+    use ic_types::{
+        crypto::{CryptoHash, CryptoHashOf},
+        Cycles, NumInstructions,
+    };
+
+    fn entry(result: ExecutionTraceResult) -> ExecutionTraceEntry {
+        ExecutionTraceEntry {
+            canister_id: canister_test_id(1),
+            kind: ExecutionTraceKind::Ingress,
+            method: Some("update".to_string()),
+            caller: Some(user_test_id(2).get()),
+            instructions_used: NumInstructions::from(1_000),
+            cycles_charged: Cycles::new(500),
+            result,
+        }
+    }
+
+    fn hash(byte: u8) -> CryptoHashOfPartialState {
+        CryptoHashOf::from(CryptoHash(vec![byte; 2]))
+    }
+
+    #[test]
+    fn writes_trace_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trace.jsonl");
+        let writer = TraceWriter::create(&path).unwrap();
+
+        writer.record(ExecutionRound::from(5), entry(ExecutionTraceResult::Reply));
+        writer.record(
+            ExecutionRound::from(6),
+            entry(ExecutionTraceResult::Reject(RejectCode::CanisterReject)),
+        );
+        writer.record(
+            ExecutionRound::from(6),
+            entry(ExecutionTraceResult::Error(ErrorCode::CanisterTrapped)),
+        );
+        writer.record_state_hashes(vec![
+            (Height::from(5), hash(0xab)),
+            (Height::from(6), hash(0xcd)),
+        ]);
+        // Hashes of already traced states are not written again.
+        writer.record_state_hashes(vec![
+            (Height::from(6), hash(0xcd)),
+            (Height::from(7), hash(0xef)),
+        ]);
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "type": "message",
+                "height": 5,
+                "canister_id": canister_test_id(1).to_string(),
+                "kind": "ingress",
+                "method": "update",
+                "caller": user_test_id(2).get().to_string(),
+                "instructions_used": 1000,
+                "cycles_charged": 500,
+                "result": "reply",
+            })
+        );
+        assert_eq!(lines[1]["result"], "reject");
+        assert_eq!(lines[1]["reject_code"], 4);
+        assert_eq!(lines[2]["result"], "error");
+        assert_eq!(lines[2]["error_code"], "IC0502");
+        assert_eq!(
+            lines[3],
+            serde_json::json!({"type": "batch", "height": 5, "state_hash": "abab"})
+        );
+        assert_eq!(lines[4]["height"], 6);
+        assert_eq!(lines[5]["height"], 7);
+    }
+}
@@ -7,7 +7,7 @@ use ic_error_types::{ErrorCode, RejectCode};
 use ic_management_canister_types::{
     self as ic00, CanisterChange, CanisterIdRecord, CanisterInstallMode,
     CanisterSettingsArgsBuilder, CanisterStatusResultV2, CanisterStatusType, EmptyBlob,
-    InstallCodeArgs, Method, Payload, UpdateSettingsArgs, IC_00,
+    InstallCodeArgs, MemoryMetrics, Method, Payload, UpdateSettingsArgs, IC_00,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_replica_tests as utils;
@@ -708,6 +708,17 @@ fn can_get_canister_information() {
                 canister_a.get(),
                 vec![canister_a.get()],
                 NumBytes::from((2 * size_of::<CanisterChange>() + 2 * size_of::<PrincipalId>()) as u64),
+                MemoryMetrics::new(
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from((2 * size_of::<CanisterChange>() + 2 * size_of::<PrincipalId>()) as u64),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                    NumBytes::from(0),
+                ),
                 num_cycles.get(),
                 ComputeAllocation::default().as_percent(),
                 None,
@@ -720,6 +731,7 @@ fn can_get_canister_information() {
                 0u128,
                 0u128,
                 0u128,
+                vec![],
                 Some(DEFAULT_WASM_MEMORY_LIMIT.get()),
             )
         );
@@ -766,6 +778,7 @@ fn can_get_canister_information() {
                     // We don't assert a specific memory size since the universal canister's
                     // size changes between updates.
                     NumBytes::from(0),
+                    MemoryMetrics::default(),
                     num_cycles.get(),
                     ComputeAllocation::default().as_percent(),
                     None,
@@ -778,6 +791,7 @@ fn can_get_canister_information() {
                     0u128,
                     0u128,
                     0u128,
+                    vec![],
                     Some(DEFAULT_WASM_MEMORY_LIMIT.get())
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
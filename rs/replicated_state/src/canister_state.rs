@@ -24,7 +24,7 @@ use ic_validate_eq::ValidateEq;
 use ic_validate_eq_derive::ValidateEq;
 use phantom_newtype::AmountOf;
 pub use queues::{CanisterQueues, DEFAULT_QUEUE_CAPACITY};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::From;
 use std::sync::Arc;
 use std::time::Duration;
@@ -83,6 +83,12 @@ pub struct SchedulerState {
     /// At the end of an "epoch", each node deterministically aggregates all those partial
     /// query statistics received from consensus blocks and mutates these values.
     pub total_query_stats: TotalQueryStats,
+
+    /// Query statistics broken down by query method.
+    ///
+    /// Aggregated the same way as `total_query_stats`, but tracked for at most
+    /// `MAX_QUERY_STATS_METHODS_PER_CANISTER` methods.
+    pub total_method_query_stats: BTreeMap<String, TotalQueryStats>,
 }
 
 impl Default for SchedulerState {
@@ -97,6 +103,7 @@ impl Default for SchedulerState {
             install_code_debit: 0.into(),
             time_of_last_allocation_charge: UNIX_EPOCH,
             total_query_stats: TotalQueryStats::default(),
+            total_method_query_stats: BTreeMap::new(),
         }
     }
 }
@@ -223,6 +230,7 @@ impl CanisterState {
             (Some(ExecutionTask::Heartbeat), _) => NextExecution::StartNew,
             (Some(ExecutionTask::GlobalTimer), _) => NextExecution::StartNew,
             (Some(ExecutionTask::OnLowWasmMemory), _) => NextExecution::StartNew,
+            (Some(ExecutionTask::OnLowCycles), _) => NextExecution::StartNew,
             (Some(ExecutionTask::AbortedExecution { .. }), _)
             | (Some(ExecutionTask::PausedExecution { .. }), _) => NextExecution::ContinueLong,
             (Some(ExecutionTask::AbortedInstallCode { .. }), _)
@@ -243,6 +251,7 @@ impl CanisterState {
             | Some(ExecutionTask::Heartbeat)
             | Some(ExecutionTask::GlobalTimer)
             | Some(ExecutionTask::OnLowWasmMemory)
+            | Some(ExecutionTask::OnLowCycles)
             | Some(ExecutionTask::PausedExecution { .. })
             | Some(ExecutionTask::PausedInstallCode(..))
             | Some(ExecutionTask::AbortedInstallCode { .. }) => false,
@@ -257,6 +266,7 @@ impl CanisterState {
             | Some(ExecutionTask::Heartbeat)
             | Some(ExecutionTask::GlobalTimer)
             | Some(ExecutionTask::OnLowWasmMemory)
+            | Some(ExecutionTask::OnLowCycles)
             | Some(ExecutionTask::PausedInstallCode(..))
             | Some(ExecutionTask::AbortedExecution { .. })
             | Some(ExecutionTask::AbortedInstallCode { .. }) => false,
@@ -271,6 +281,7 @@ impl CanisterState {
             | Some(ExecutionTask::Heartbeat)
             | Some(ExecutionTask::GlobalTimer)
             | Some(ExecutionTask::OnLowWasmMemory)
+            | Some(ExecutionTask::OnLowCycles)
             | Some(ExecutionTask::PausedExecution { .. })
             | Some(ExecutionTask::AbortedExecution { .. })
             | Some(ExecutionTask::AbortedInstallCode { .. }) => false,
@@ -285,6 +296,7 @@ impl CanisterState {
             | Some(ExecutionTask::Heartbeat)
             | Some(ExecutionTask::GlobalTimer)
             | Some(ExecutionTask::OnLowWasmMemory)
+            | Some(ExecutionTask::OnLowCycles)
             | Some(ExecutionTask::PausedExecution { .. })
             | Some(ExecutionTask::PausedInstallCode(..))
             | Some(ExecutionTask::AbortedExecution { .. }) => false,
@@ -385,7 +397,7 @@ impl CanisterState {
         self.execution_memory_usage()
             + self.canister_history_memory_usage()
             + self.wasm_chunk_store_memory_usage()
-            + self.system_state.snapshots_memory_usage
+            + self.snapshots_memory_usage()
     }
 
     /// Returns the amount of execution memory (heap, stable, globals, Wasm)
@@ -396,6 +408,34 @@ impl CanisterState {
             .map_or(NumBytes::from(0), |es| es.memory_usage())
     }
 
+    /// Returns the amount of memory used by the Wasm heap in bytes.
+    pub fn wasm_memory_usage(&self) -> NumBytes {
+        self.execution_state
+            .as_ref()
+            .map_or(NumBytes::from(0), |es| es.wasm_memory_usage())
+    }
+
+    /// Returns the amount of memory used by stable memory in bytes.
+    pub fn stable_memory_usage(&self) -> NumBytes {
+        self.execution_state
+            .as_ref()
+            .map_or(NumBytes::from(0), |es| es.stable_memory_usage())
+    }
+
+    /// Returns the amount of memory used by exported globals in bytes.
+    pub fn globals_memory_usage(&self) -> NumBytes {
+        self.execution_state
+            .as_ref()
+            .map_or(NumBytes::from(0), |es| es.globals_memory_usage())
+    }
+
+    /// Returns the amount of memory used by the Wasm binary in bytes.
+    pub fn wasm_binary_memory_usage(&self) -> NumBytes {
+        self.execution_state
+            .as_ref()
+            .map_or(NumBytes::from(0), |es| es.wasm_binary_memory_usage())
+    }
+
     /// Returns the amount memory used by or reserved for guaranteed response
     /// canister messages, in bytes.
     pub fn message_memory_usage(&self) -> NumBytes {
@@ -416,10 +456,16 @@ impl CanisterState {
     }
 
     /// Returns the memory usage of the wasm chunk store in bytes.
-    pub(super) fn wasm_chunk_store_memory_usage(&self) -> NumBytes {
+    pub fn wasm_chunk_store_memory_usage(&self) -> NumBytes {
         self.system_state.wasm_chunk_store.memory_usage()
     }
 
+    /// Returns the memory used by the snapshots that belong to this canister,
+    /// as last computed from `CanisterSnapshots::compute_memory_usage_by_canister`.
+    pub fn snapshots_memory_usage(&self) -> NumBytes {
+        self.system_state.snapshots_memory_usage
+    }
+
     /// Returns the snapshot size estimation in bytes based on the current canister's state.
     ///
     /// It represents the memory usage of a snapshot that would be created at the time of the call
@@ -491,6 +537,12 @@ impl CanisterState {
         self.exports_method(&WasmMethod::System(SystemMethod::CanisterOnLowWasmMemory))
     }
 
+    /// Returns true if the canister exports the `canister_on_low_cycles`
+    /// system method.
+    pub fn exports_on_low_cycles(&self) -> bool {
+        self.exports_method(&WasmMethod::System(SystemMethod::CanisterOnLowCycles))
+    }
+
     /// Returns true if the canister exports the given Wasm method.
     pub fn exports_method(&self, method: &WasmMethod) -> bool {
         match &self.execution_state {
@@ -558,6 +610,7 @@ impl CanisterState {
             ExecutionTask::Heartbeat
             | ExecutionTask::GlobalTimer
             | ExecutionTask::OnLowWasmMemory
+            | ExecutionTask::OnLowCycles
             | ExecutionTask::PausedExecution { .. }
             | ExecutionTask::PausedInstallCode(_)
             | ExecutionTask::AbortedExecution { .. } => true,
@@ -162,6 +162,9 @@ pub struct ExportedFunctions {
 
     /// Cached info about exporting a on low Wasm memory to skip expensive BTreeSet lookup.
     exports_on_low_wasm_memory: bool,
+
+    /// Cached info about exporting a on low cycles to skip expensive BTreeSet lookup.
+    exports_on_low_cycles: bool,
 }
 
 impl ExportedFunctions {
@@ -172,11 +175,14 @@ impl ExportedFunctions {
             exported_functions.contains(&WasmMethod::System(SystemMethod::CanisterGlobalTimer));
         let exports_on_low_wasm_memory =
             exported_functions.contains(&WasmMethod::System(SystemMethod::CanisterOnLowWasmMemory));
+        let exports_on_low_cycles =
+            exported_functions.contains(&WasmMethod::System(SystemMethod::CanisterOnLowCycles));
         Self {
             exported_functions: Arc::new(exported_functions),
             exports_heartbeat,
             exports_global_timer,
             exports_on_low_wasm_memory,
+            exports_on_low_cycles,
         }
     }
 
@@ -188,6 +194,7 @@ impl ExportedFunctions {
             WasmMethod::System(SystemMethod::CanisterOnLowWasmMemory) => {
                 self.exports_on_low_wasm_memory
             }
+            WasmMethod::System(SystemMethod::CanisterOnLowCycles) => self.exports_on_low_cycles,
             // Expensive lookup.
             _ => self.exported_functions.contains(method),
         }
@@ -562,16 +569,34 @@ impl ExecutionState {
 
     /// Returns the memory currently used by the `ExecutionState`.
     pub fn memory_usage(&self) -> NumBytes {
-        // We use 8 bytes per global.
-        let globals_size_bytes = 8 * self.exported_globals.len() as u64;
-        let wasm_binary_size_bytes = self.wasm_binary.binary.len() as u64;
+        self.wasm_memory_usage()
+            + self.stable_memory_usage()
+            + self.globals_memory_usage()
+            + self.wasm_binary_memory_usage()
+            + self.metadata.memory_usage()
+    }
+
+    /// Returns the memory currently used by the Wasm heap.
+    pub fn wasm_memory_usage(&self) -> NumBytes {
         num_bytes_try_from(self.wasm_memory.size)
             .expect("could not convert from wasm memory number of pages to bytes")
-            + num_bytes_try_from(self.stable_memory.size)
-                .expect("could not convert from stable memory number of pages to bytes")
-            + NumBytes::from(globals_size_bytes)
-            + NumBytes::from(wasm_binary_size_bytes)
-            + self.metadata.memory_usage()
+    }
+
+    /// Returns the memory currently used by stable memory.
+    pub fn stable_memory_usage(&self) -> NumBytes {
+        num_bytes_try_from(self.stable_memory.size)
+            .expect("could not convert from stable memory number of pages to bytes")
+    }
+
+    /// Returns the memory currently used by exported globals.
+    pub fn globals_memory_usage(&self) -> NumBytes {
+        // We use 8 bytes per global.
+        NumBytes::from(8 * self.exported_globals.len() as u64)
+    }
+
+    /// Returns the memory currently used by the Wasm binary.
+    pub fn wasm_binary_memory_usage(&self) -> NumBytes {
+        NumBytes::from(self.wasm_binary.binary.len() as u64)
     }
 
     /// Returns the number of global variables in the Wasm module.
@@ -138,6 +138,60 @@ impl TryFrom<pb::CyclesUseCase> for CyclesUseCase {
     }
 }
 
+/// Tracks the `canister_on_low_cycles` hook across rounds so that it runs at
+/// most once each time the cycles balance drops below the low cycles threshold.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, EnumIter)]
+pub enum OnLowCyclesHookStatus {
+    /// The cycles balance is above the low cycles threshold.
+    #[default]
+    ConditionNotSatisfied = 1,
+    /// The cycles balance dropped below the threshold and the hook has not
+    /// been executed yet.
+    Ready = 2,
+    /// The hook was executed since the balance dropped below the threshold.
+    Executed = 3,
+}
+
+impl OnLowCyclesHookStatus {
+    /// Updates the status given whether the cycles balance is currently below
+    /// the low cycles threshold.
+    pub fn update(&mut self, is_below_threshold: bool) {
+        *self = match (*self, is_below_threshold) {
+            (_, false) => Self::ConditionNotSatisfied,
+            (Self::ConditionNotSatisfied, true) => Self::Ready,
+            (status @ (Self::Ready | Self::Executed), true) => status,
+        };
+    }
+
+    /// Returns true if the hook should be scheduled for execution.
+    pub fn is_ready(&self) -> bool {
+        *self == Self::Ready
+    }
+}
+
+impl From<OnLowCyclesHookStatus> for pb::OnLowCyclesHookStatus {
+    fn from(item: OnLowCyclesHookStatus) -> Self {
+        match item {
+            OnLowCyclesHookStatus::ConditionNotSatisfied => {
+                pb::OnLowCyclesHookStatus::ConditionNotSatisfied
+            }
+            OnLowCyclesHookStatus::Ready => pb::OnLowCyclesHookStatus::Ready,
+            OnLowCyclesHookStatus::Executed => pb::OnLowCyclesHookStatus::Executed,
+        }
+    }
+}
+
+impl From<pb::OnLowCyclesHookStatus> for OnLowCyclesHookStatus {
+    fn from(item: pb::OnLowCyclesHookStatus) -> Self {
+        match item {
+            pb::OnLowCyclesHookStatus::Unspecified
+            | pb::OnLowCyclesHookStatus::ConditionNotSatisfied => Self::ConditionNotSatisfied,
+            pb::OnLowCyclesHookStatus::Ready => Self::Ready,
+            pb::OnLowCyclesHookStatus::Executed => Self::Executed,
+        }
+    }
+}
+
 enum ConsumingCycles {
     Yes,
     No,
@@ -282,6 +336,11 @@ pub struct SystemState {
     pub memory_allocation: MemoryAllocation,
     /// Threshold used for activation of canister_on_low_wasm_memory hook.
     pub wasm_memory_threshold: NumBytes,
+    /// Multiple of the freezing threshold (in cycles) below which the
+    /// canister_on_low_cycles hook is activated. Zero disables the hook.
+    pub low_cycles_threshold_multiplier: u64,
+    /// Whether the canister_on_low_cycles hook is due or already executed.
+    pub on_low_cycles_hook_status: OnLowCyclesHookStatus,
     pub freeze_threshold: NumSeconds,
     /// The status of the canister: Running, Stopping, or Stopped.
     /// Different statuses allow for different behaviors on the SystemState.
@@ -474,6 +533,10 @@ pub enum ExecutionTask {
     /// The task exists only within an execution round, it never gets serialized.
     OnLowWasmMemory,
 
+    /// On low cycles hook.
+    /// The task exists only within an execution round, it never gets serialized.
+    OnLowCycles,
+
     /// A paused execution task exists only within an epoch (between
     /// checkpoints). It is never serialized, and it turns into `AbortedExecution`
     /// before the checkpoint or when there are too many long-running executions.
@@ -520,6 +583,7 @@ impl From<&ExecutionTask> for pb::ExecutionTask {
             ExecutionTask::Heartbeat
             | ExecutionTask::GlobalTimer
             | ExecutionTask::OnLowWasmMemory
+            | ExecutionTask::OnLowCycles
             | ExecutionTask::PausedExecution { .. }
             | ExecutionTask::PausedInstallCode(_) => {
                 panic!("Attempt to serialize ephemeral task: {:?}.", item);
@@ -728,6 +792,8 @@ impl SystemState {
             reserved_balance_limit: None,
             memory_allocation: MemoryAllocation::BestEffort,
             wasm_memory_threshold: NumBytes::new(0),
+            low_cycles_threshold_multiplier: 0,
+            on_low_cycles_hook_status: OnLowCyclesHookStatus::default(),
             freeze_threshold,
             status,
             certified_data: Default::default(),
@@ -752,6 +818,8 @@ impl SystemState {
         queues: CanisterQueues,
         memory_allocation: MemoryAllocation,
         wasm_memory_threshold: NumBytes,
+        low_cycles_threshold_multiplier: u64,
+        on_low_cycles_hook_status: OnLowCyclesHookStatus,
         freeze_threshold: NumSeconds,
         status: CanisterStatus,
         certified_data: Vec<u8>,
@@ -779,6 +847,8 @@ impl SystemState {
             queues,
             memory_allocation,
             wasm_memory_threshold,
+            low_cycles_threshold_multiplier,
+            on_low_cycles_hook_status,
             freeze_threshold,
             status,
             certified_data,
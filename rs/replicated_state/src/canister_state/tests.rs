@@ -6,7 +6,7 @@ use crate::canister_state::execution_state::CustomSection;
 use crate::canister_state::execution_state::CustomSectionType;
 use crate::canister_state::execution_state::WasmMetadata;
 use crate::canister_state::system_state::{
-    CallContextManager, CanisterHistory, CanisterStatus, CyclesUseCase,
+    CallContextManager, CanisterHistory, CanisterStatus, CyclesUseCase, OnLowCyclesHookStatus,
     MAX_CANISTER_HISTORY_CHANGES,
 };
 use crate::metadata_state::subnet_call_context_manager::InstallCodeCallId;
@@ -650,6 +650,44 @@ fn long_execution_mode_round_trip() {
     );
 }
 
+#[test]
+fn on_low_cycles_hook_status_round_trip() {
+    use ic_protobuf::state::canister_state_bits::v1 as pb;
+
+    for initial in OnLowCyclesHookStatus::iter() {
+        let encoded = pb::OnLowCyclesHookStatus::from(initial);
+        let round_trip = OnLowCyclesHookStatus::from(encoded);
+
+        assert_eq!(initial, round_trip);
+    }
+
+    // Backward compatibility check.
+    assert_eq!(
+        OnLowCyclesHookStatus::from(pb::OnLowCyclesHookStatus::Unspecified),
+        OnLowCyclesHookStatus::ConditionNotSatisfied
+    );
+}
+
+#[test]
+fn on_low_cycles_hook_status_update() {
+    let mut status = OnLowCyclesHookStatus::default();
+    status.update(false);
+    assert_eq!(status, OnLowCyclesHookStatus::ConditionNotSatisfied);
+
+    status.update(true);
+    assert!(status.is_ready());
+
+    status = OnLowCyclesHookStatus::Executed;
+    status.update(true);
+    assert_eq!(status, OnLowCyclesHookStatus::Executed);
+
+    // Topping up the canister re-arms the hook.
+    status.update(false);
+    assert_eq!(status, OnLowCyclesHookStatus::ConditionNotSatisfied);
+    status.update(true);
+    assert!(status.is_ready());
+}
+
 #[test]
 fn long_execution_mode_decoding() {
     use ic_protobuf::state::canister_state_bits::v1 as pb;
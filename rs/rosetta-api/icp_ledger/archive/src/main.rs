@@ -11,7 +11,12 @@ use icp_ledger::{
     Block, BlockRange, BlockRes, CandidBlock, GetBlocksArgs, GetBlocksError, GetBlocksResult,
     GetEncodedBlocksResult, IterBlocksArgs,
 };
+use icrc_ledger_types::icrc::generic_value::Hash as Icrc3Hash;
+use icrc_ledger_types::icrc3::blocks::{
+    BlockWithId, GetBlocksRequest, GetBlocksResult as Icrc3GetBlocksResult,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::sync::RwLock;
 
 lazy_static::lazy_static! {
@@ -29,6 +34,11 @@ struct ArchiveNodeState {
     pub ledger_canister_id: ic_base_types::CanisterId,
     #[serde(skip)]
     pub last_upgrade_timestamp: u64,
+    /// ICRC-3 hashes pushed by the Ledger: the hash of the block preceding the
+    /// first block of this node and the hashes of the checkpoint blocks, see
+    /// [icp_ledger::ICRC3_HASH_CHECKPOINT_INTERVAL].
+    #[serde(default)]
+    pub icrc3_hashes: BTreeMap<BlockIndex, Icrc3Hash>,
 }
 
 const DEFAULT_MAX_MEMORY_SIZE: usize = 1024 * 1024 * 1024;
@@ -46,6 +56,7 @@ impl ArchiveNodeState {
             total_block_size: 0,
             ledger_canister_id: archive_main_canister_id,
             last_upgrade_timestamp: 0,
+            icrc3_hashes: BTreeMap::new(),
         }
     }
 }
@@ -76,6 +87,17 @@ fn append_blocks(mut blocks: Vec<EncodedBlock>) {
     ));
 }
 
+// Store the ICRC-3 hashes pushed by the Ledger
+fn icrc3_add_hashes(hashes: Vec<(BlockIndex, Icrc3Hash)>) {
+    let mut archive_state = ARCHIVE_STATE.write().unwrap();
+    assert_eq!(
+        dfn_core::api::caller(),
+        archive_state.ledger_canister_id.get(),
+        "Only Ledger canister is allowed to add ICRC-3 hashes to an Archive Node"
+    );
+    archive_state.icrc3_hashes.extend(hashes);
+}
+
 // Return the number of bytes the canister can still accommodate
 fn remaining_capacity() -> usize {
     let archive_state = ARCHIVE_STATE.read().unwrap();
@@ -153,6 +175,11 @@ fn append_blocks_() {
     dfn_core::over(dfn_candid::candid_one, append_blocks);
 }
 
+#[export_name = "canister_update icrc3_add_hashes"]
+fn icrc3_add_hashes_() {
+    dfn_core::over(dfn_candid::candid_one, icrc3_add_hashes);
+}
+
 /// Get multiple blocks by *offset into the container* (not BlockIndex) and
 /// length. Note that this simply iterates the blocks available in the this
 /// particular archive node without taking into account the ledger or the
@@ -317,6 +344,86 @@ fn get_encoded_blocks_blocks_() {
     dfn_core::over(candid_one, get_encoded_blocks);
 }
 
+fn decode_block(archive_state: &ArchiveNodeState, index: BlockIndex) -> Block {
+    let encoded_block =
+        archive_state.blocks[(index - archive_state.block_height_offset) as usize].clone();
+    Block::decode(encoded_block).expect("failed to decode a block")
+}
+
+/// Returns the ICRC-3 hash of the block at `index`, computed from the closest
+/// hash pushed by the Ledger. `index` is either a block of this node or the
+/// block preceding its first block. Traps if the Ledger hasn't pushed the
+/// hashes of this node yet.
+fn icrc3_hash(archive_state: &ArchiveNodeState, index: BlockIndex) -> Icrc3Hash {
+    let offset = archive_state.block_height_offset;
+    let (first_index_to_hash, mut hash) =
+        match archive_state.icrc3_hashes.range(..=index).next_back() {
+            Some((known_index, known_hash)) if known_index + 1 >= offset => {
+                (known_index + 1, Some(*known_hash))
+            }
+            _ if offset == 0 => (0, None),
+            _ => ic_cdk::trap(
+                "the ICRC-3 hashes of the blocks of this archive are not available yet",
+            ),
+        };
+    for index in first_index_to_hash..=index {
+        hash = Some(decode_block(archive_state, index).icrc3_hash(hash));
+    }
+    hash.expect("bug: no ICRC-3 hash computed")
+}
+
+/// Get the blocks of the given ranges stored in this node as ICRC-3 generic
+/// values. Blocks outside the range stored in this node are skipped.
+#[candid_method(query, rename = "icrc3_get_blocks")]
+fn icrc3_get_blocks(reqs: Vec<GetBlocksRequest>) -> Icrc3GetBlocksResult {
+    let archive_state = ARCHIVE_STATE.read().unwrap();
+    let block_range = range_utils::make_range(
+        archive_state.block_height_offset,
+        archive_state.blocks.len(),
+    );
+    let max_blocks = icp_ledger::max_blocks_per_request(&caller());
+
+    let mut blocks = vec![];
+    for req in reqs {
+        let (start, length) = req
+            .as_start_and_length()
+            .unwrap_or_else(|msg| ic_cdk::trap(&msg));
+        let max_length = max_blocks.saturating_sub(blocks.len());
+        if max_length == 0 {
+            break;
+        }
+        let requested_range =
+            range_utils::make_range(start, length.min(max_length as u64) as usize);
+        let Ok(effective_range) = range_utils::intersect(&block_range, &requested_range) else {
+            continue;
+        };
+        let mut parent_hash = effective_range
+            .start
+            .checked_sub(1)
+            .map(|parent_index| icrc3_hash(&archive_state, parent_index));
+        for id in effective_range {
+            let block = decode_block(&archive_state, id).to_icrc3_value(parent_hash);
+            parent_hash = Some(block.clone().hash());
+            blocks.push(BlockWithId {
+                id: candid::Nat::from(id),
+                block,
+            });
+        }
+    }
+    Icrc3GetBlocksResult {
+        // We return the local log length because the archive
+        // knows only about its local blocks.
+        log_length: candid::Nat::from(archive_state.blocks.len()),
+        blocks,
+        archived_blocks: vec![],
+    }
+}
+
+#[export_name = "canister_query icrc3_get_blocks"]
+fn icrc3_get_blocks_() {
+    dfn_core::over(candid_one, icrc3_get_blocks);
+}
+
 #[export_name = "canister_query __get_candid_interface_tmp_hack"]
 fn get_canidid_interface() {
     dfn_core::over(candid_one, |()| -> &'static str {
@@ -121,7 +121,10 @@ fn install_ledger(
         .token_symbol_and_name(TOKEN_SYMBOL, TOKEN_NAME)
         .archive_options(archive_options)
         .initial_values(initial_balances)
-        .feature_flags(FeatureFlags { icrc2: true })
+        .feature_flags(FeatureFlags {
+            icrc2: true,
+            icrc3: None,
+        })
         .build()
         .unwrap();
     env.install_canister(ledger_wasm(), Encode!(&args).unwrap(), None)
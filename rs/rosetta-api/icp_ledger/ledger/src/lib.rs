@@ -1,5 +1,6 @@
 use dfn_core::api::{now, trap_with};
 use ic_base_types::{CanisterId, PrincipalId};
+use ic_crypto_tree_hash::{Label, MixedHashTree};
 use ic_ledger_canister_core::archive::ArchiveCanisterWasm;
 use ic_ledger_canister_core::blockchain::Blockchain;
 use ic_ledger_canister_core::ledger::{
@@ -12,9 +13,11 @@ use ic_ledger_core::{
 use ic_ledger_core::{block::BlockIndex, tokens::Tokens};
 use ic_ledger_hash_of::HashOf;
 use icp_ledger::{
-    AccountIdentifier, Block, FeatureFlags, LedgerAllowances, LedgerBalances, Memo, Operation,
-    PaymentError, Transaction, TransferError, TransferFee, UpgradeArgs, DEFAULT_TRANSFER_FEE,
+    is_icrc3_hash_checkpoint, AccountIdentifier, Block, FeatureFlags, LedgerAllowances,
+    LedgerBalances, Memo, Operation, PaymentError, Transaction, TransferError, TransferFee,
+    UpgradeArgs, DEFAULT_TRANSFER_FEE,
 };
+use icrc_ledger_types::icrc::generic_value::Hash as Icrc3Hash;
 use icrc_ledger_types::icrc1::account::Account;
 use intmap::IntMap;
 use lazy_static::lazy_static;
@@ -106,6 +109,58 @@ pub struct Ledger {
 
     #[serde(default)]
     pub feature_flags: FeatureFlags,
+
+    #[serde(default)]
+    pub icrc3_hashes: Icrc3Hashes,
+}
+
+/// The ICRC-3 hashes of the blocks.
+///
+/// The ICRC-3 hash of a block covers the ICRC-3 hash of its parent, so these
+/// hashes form a chain of their own next to the one of the hashes of the
+/// encoded blocks. The ledger computes them from the first block on: new
+/// blocks are hashed as they are added, blocks archived before the ledger
+/// started hashing them are fetched back from the archive.
+///
+/// The ledger keeps the hashes until it has pushed the ones needed by the
+/// archive nodes to them, see [Ledger::icrc3_hashes_to_push].
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Icrc3Hashes {
+    /// The number of blocks hashed so far, starting from the first block.
+    len: u64,
+    /// The hashes of the last blocks, by block index. Contains at least the
+    /// hash of the last block hashed.
+    hashes: BTreeMap<BlockIndex, Icrc3Hash>,
+    /// The number of blocks whose hashes were pushed to the archive nodes.
+    num_pushed: u64,
+}
+
+impl Icrc3Hashes {
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn num_pushed(&self) -> u64 {
+        self.num_pushed
+    }
+
+    pub fn get(&self, index: BlockIndex) -> Option<Icrc3Hash> {
+        self.hashes.get(&index).copied()
+    }
+
+    /// Hashes the block following the last block hashed.
+    pub fn push(&mut self, block: &Block) {
+        let parent_hash = self.len.checked_sub(1).map(|parent_index| {
+            self.get(parent_index)
+                .expect("bug: the hash of the last block hashed is missing")
+        });
+        self.hashes.insert(self.len, block.icrc3_hash(parent_hash));
+        self.len += 1;
+    }
 }
 
 impl LedgerContext for Ledger {
@@ -224,6 +279,7 @@ impl Default for Ledger {
             token_symbol: unknown_token(),
             token_name: unknown_token(),
             feature_flags: FeatureFlags::default(),
+            icrc3_hashes: Icrc3Hashes::default(),
         }
     }
 }
@@ -431,6 +487,162 @@ impl Ledger {
         }
     }
 
+    /// Hashes the local blocks not hashed yet, if ICRC-3 is enabled and all the
+    /// previous blocks are hashed. The blocks archived before the ledger
+    /// started hashing them are hashed by [Ledger::add_archived_icrc3_hashes].
+    pub fn update_icrc3_hashes(&mut self) {
+        if !self.feature_flags.icrc3_enabled()
+            || self.icrc3_hashes.len() < self.blockchain.num_archived_blocks()
+        {
+            return;
+        }
+        let new_blocks = self.icrc3_hashes.len()..self.blockchain.chain_length();
+        for encoded_block in self.blockchain.block_slice(new_blocks) {
+            let block =
+                Block::decode(encoded_block.clone()).expect("bug: failed to decode encoded block");
+            self.icrc3_hashes.push(&block);
+        }
+    }
+
+    /// Hashes the given archived blocks, which must follow the last block
+    /// hashed. Returns an error if they don't.
+    pub fn add_archived_icrc3_hashes(
+        &mut self,
+        start: BlockIndex,
+        blocks: &[Block],
+    ) -> Result<(), String> {
+        if start != self.icrc3_hashes.len() {
+            return Err(format!(
+                "expected archived blocks starting at {}, got blocks starting at {}",
+                self.icrc3_hashes.len(),
+                start
+            ));
+        }
+        if start + blocks.len() as u64 > self.blockchain.num_archived_blocks() {
+            return Err(format!(
+                "blocks {}..{} are not archived",
+                start,
+                start + blocks.len() as u64
+            ));
+        }
+        for block in blocks {
+            self.icrc3_hashes.push(block);
+        }
+        Ok(())
+    }
+
+    /// Returns the ICRC-3 hash of the block at the given index, if it is known.
+    pub fn icrc3_hash(&self, index: BlockIndex) -> Option<Icrc3Hash> {
+        if index < self.icrc3_hashes.len() {
+            self.icrc3_hashes.get(index)
+        } else {
+            None
+        }
+    }
+
+    /// Whether some archived blocks still have to be hashed or some hashes
+    /// still have to be pushed to the archive nodes.
+    pub fn icrc3_hashes_update_pending(&self) -> bool {
+        let num_archived_blocks = self.blockchain.num_archived_blocks();
+        let hashes = &self.icrc3_hashes;
+        hashes.len() < num_archived_blocks
+            || hashes.num_pushed() < hashes.len().min(num_archived_blocks)
+    }
+
+    /// Returns the ICRC-3 hashes the archive nodes need to compute the ICRC-3
+    /// hashes of their blocks on their own, by archive node, together with the
+    /// number of blocks they cover.
+    ///
+    /// Each node gets the hash of the block preceding its first block and the
+    /// hashes of the checkpoint blocks it stores, see
+    /// [icp_ledger::ICRC3_HASH_CHECKPOINT_INTERVAL].
+    pub fn icrc3_hashes_to_push(&self) -> (u64, Vec<(CanisterId, Vec<(BlockIndex, Icrc3Hash)>)>) {
+        let num_pushed = self.icrc3_hashes.num_pushed();
+        let push_up_to = self
+            .icrc3_hashes
+            .len()
+            .min(self.blockchain.num_archived_blocks());
+        let archive = self.blockchain.archive.read().unwrap();
+        let hashes_by_node = archive
+            .iter()
+            .flat_map(|archive| archive.index())
+            .filter_map(|((start, end), canister_id)| {
+                let parent = start
+                    .checked_sub(1)
+                    .filter(|parent| start >= num_pushed && *parent < push_up_to);
+                let checkpoints = (start.max(num_pushed)..(end + 1).min(push_up_to))
+                    .filter(|index| is_icrc3_hash_checkpoint(*index));
+                let hashes: Vec<_> = parent
+                    .into_iter()
+                    .chain(checkpoints)
+                    .filter_map(|index| Some((index, self.icrc3_hashes.get(index)?)))
+                    .collect();
+                (!hashes.is_empty()).then_some((canister_id, hashes))
+            })
+            .collect();
+        (push_up_to.max(num_pushed), hashes_by_node)
+    }
+
+    /// Records that the hashes of the first `num_pushed` blocks were pushed to
+    /// the archive nodes and drops the ones the ledger doesn't need anymore.
+    pub fn set_icrc3_hashes_pushed(&mut self, num_pushed: u64) {
+        let hashes = &mut self.icrc3_hashes;
+        hashes.num_pushed = hashes.num_pushed.max(num_pushed);
+        // The hash of the last archived block is needed to hash the local blocks.
+        let keep_from = hashes
+            .num_pushed
+            .min(self.blockchain.num_archived_blocks())
+            .min(hashes.len)
+            .saturating_sub(1);
+        hashes.hashes = hashes.hashes.split_off(&keep_from);
+    }
+
+    /// The hash tree certified by the ledger if ICRC-3 is enabled and all the
+    /// blocks are hashed, containing the index and the ICRC-3 hash of the last
+    /// block, laid out as by the ICRC-1 ledger.
+    ///
+    /// The tree also contains the hash of the last encoded block under
+    /// `icp_tip_hash`, which the ledger certified directly before ICRC-3, so
+    /// that clients can keep verifying the tip without the ICRC-3 hash chain.
+    pub fn construct_hash_tree(&self) -> Option<MixedHashTree> {
+        if !self.feature_flags.icrc3_enabled() {
+            return None;
+        }
+        let last_block_index = self.blockchain.chain_length().checked_sub(1)?;
+        let tip_hash = self.icrc3_hash(last_block_index)?;
+        let icp_tip_hash = self.blockchain.last_hash?;
+        Some(MixedHashTree::Fork(Box::new((
+            MixedHashTree::Labeled(
+                Label::from("icp_tip_hash"),
+                Box::new(MixedHashTree::Leaf(icp_tip_hash.as_slice().to_vec())),
+            ),
+            MixedHashTree::Fork(Box::new((
+                MixedHashTree::Labeled(
+                    Label::from("last_block_index"),
+                    Box::new(MixedHashTree::Leaf(last_block_index.to_be_bytes().to_vec())),
+                ),
+                MixedHashTree::Labeled(
+                    Label::from("tip_hash"),
+                    Box::new(MixedHashTree::Leaf(tip_hash.to_vec())),
+                ),
+            ))),
+        ))))
+    }
+
+    /// The data certified by the ledger: the digest of the ICRC-3 hash tree if
+    /// the ledger has one, see [Ledger::construct_hash_tree], or the hash of
+    /// the last encoded block otherwise.
+    pub fn certified_data(&self) -> [u8; 32] {
+        match self.construct_hash_tree() {
+            Some(hash_tree) => hash_tree.digest().0,
+            None => self
+                .blockchain
+                .last_hash
+                .map(|h| h.into_bytes())
+                .unwrap_or([0u8; 32]),
+        }
+    }
+
     pub fn upgrade(&mut self, args: UpgradeArgs) {
         if let Some(icrc1_minting_account) = args.icrc1_minting_account {
             if Some(AccountIdentifier::from(icrc1_minting_account)) != self.minting_account_id {
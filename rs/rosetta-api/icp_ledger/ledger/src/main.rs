@@ -29,10 +29,10 @@ use icp_ledger::{
     max_blocks_per_request, protobuf, tokens_into_proto, AccountBalanceArgs, AccountIdBlob,
     AccountIdentifier, ArchiveInfo, ArchivedBlocksRange, ArchivedEncodedBlocksRange, Archives,
     BinaryAccountBalanceArgs, Block, BlockArg, BlockRes, CandidBlock, Decimals, FeatureFlags,
-    GetBlocksArgs, InitArgs, IterBlocksArgs, LedgerCanisterPayload, Memo, Name, Operation,
-    PaymentError, QueryBlocksResponse, QueryEncodedBlocksResponse, SendArgs, Subaccount, Symbol,
-    TipOfChainRes, TotalSupplyArgs, Transaction, TransferArgs, TransferError, TransferFee,
-    TransferFeeArgs, MEMO_SIZE_BYTES,
+    GetBlocksArgs, GetEncodedBlocksResult, InitArgs, IterBlocksArgs, LedgerCanisterPayload, Memo,
+    Name, Operation, PaymentError, QueryBlocksResponse, QueryEncodedBlocksResponse, SendArgs,
+    Subaccount, Symbol, TipOfChainRes, TotalSupplyArgs, Transaction, TransferArgs, TransferError,
+    TransferFee, TransferFeeArgs, MEMO_SIZE_BYTES,
 };
 use icrc_ledger_types::icrc1::transfer::TransferError as Icrc1TransferError;
 use icrc_ledger_types::icrc2::allowance::{Allowance, AllowanceArgs};
@@ -40,7 +40,11 @@ use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::{
     icrc::generic_metadata_value::MetadataValue as Value,
     icrc21::lib::build_icrc21_consent_info_for_icrc1_and_icrc2_endpoints,
-    icrc3::archive::QueryArchiveFn,
+    icrc3::archive::{GetArchivesArgs, GetArchivesResult, ICRC3ArchiveInfo, QueryArchiveFn},
+    icrc3::blocks::{
+        ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResult, ICRC3DataCertificate,
+        SupportedBlockType,
+    },
 };
 use icrc_ledger_types::{
     icrc1::account::Account, icrc2::transfer_from::TransferFromArgs,
@@ -54,10 +58,10 @@ use ledger_canister::{Ledger, LEDGER, MAX_MESSAGE_SIZE_BYTES};
 use num_traits::cast::ToPrimitive;
 #[allow(unused_imports)]
 use on_wire::IntoWire;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::io::{Read, Write};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     sync::{Arc, RwLock},
     time::Duration,
 };
@@ -139,15 +143,7 @@ fn init(
             ));
         }
     }
-    set_certified_data(
-        &LEDGER
-            .read()
-            .unwrap()
-            .blockchain
-            .last_hash
-            .map(|h| h.into_bytes())
-            .unwrap_or([0u8; 32]),
-    );
+    certify_tip(&mut LEDGER.write().unwrap());
 
     if let Some(archive_options) = archive_options {
         LEDGER.write().unwrap().blockchain.archive =
@@ -162,7 +158,7 @@ fn add_payment(
     created_at_time: Option<TimeStamp>,
 ) -> (BlockIndex, ic_ledger_hash_of::HashOf<EncodedBlock>) {
     let (height, hash) = ledger_canister::add_payment(memo, operation, created_at_time);
-    set_certified_data(&hash.into_bytes());
+    certify_tip(&mut LEDGER.write().unwrap());
     (height, hash)
 }
 
@@ -237,16 +233,16 @@ async fn send(
             fee,
         }
     };
-    let (height, hash) = match LEDGER
-        .write()
-        .unwrap()
-        .add_payment(memo, transfer, created_at_time)
-    {
-        Ok((height, hash)) => (height, hash),
-        Err(PaymentError::TransferError(transfer_error)) => return Err(transfer_error),
-        Err(PaymentError::Reject(msg)) => panic!("{}", msg),
+    let height = {
+        let mut ledger = LEDGER.write().unwrap();
+        let height = match ledger.add_payment(memo, transfer, created_at_time) {
+            Ok((height, _hash)) => height,
+            Err(PaymentError::TransferError(transfer_error)) => return Err(transfer_error),
+            Err(PaymentError::Reject(msg)) => panic!("{}", msg),
+        };
+        certify_tip(&mut ledger);
+        height
     };
-    set_certified_data(&hash.into_bytes());
 
     // Don't put anything that could ever trap after this call or people using this
     // endpoint. If something did panic the payment would appear to fail, but would
@@ -347,9 +343,9 @@ async fn icrc1_send(
             icrc1_memo: memo.map(|x| x.0),
             created_at_time,
         };
-        let (block_index, hash) = apply_transaction(&mut *ledger, tx, now, effective_fee)?;
+        let (block_index, _hash) = apply_transaction(&mut *ledger, tx, now, effective_fee)?;
 
-        set_certified_data(&hash.into_bytes());
+        certify_tip(&mut ledger);
 
         block_index
     };
@@ -359,7 +355,141 @@ async fn icrc1_send(
     Ok(block_index)
 }
 
+/// Hashes the new blocks and updates the certified data accordingly.
+fn certify_tip(ledger: &mut Ledger) {
+    ledger.update_icrc3_hashes();
+    set_certified_data(&ledger.certified_data());
+}
+
+/// The maximum number of archived blocks fetched back from the archive and
+/// hashed at once, see [update_archived_icrc3_hashes].
+const MAX_ARCHIVED_BLOCKS_TO_HASH: u64 = 1_000;
+
+/// A scope guard for the ICRC-3 hashes update.
+/// It sets the in-progress flag when constructed and clears it when dropped.
+struct Icrc3HashesUpdateGuard;
+
+impl Icrc3HashesUpdateGuard {
+    /// Returns `None` if an update is already in progress.
+    fn new() -> Option<Self> {
+        if ICRC3_HASHES_UPDATE_IN_PROGRESS.with(|in_progress| in_progress.replace(true)) {
+            return None;
+        }
+        Some(Self)
+    }
+}
+
+impl Drop for Icrc3HashesUpdateGuard {
+    fn drop(&mut self) {
+        ICRC3_HASHES_UPDATE_IN_PROGRESS.with(|in_progress| in_progress.set(false));
+    }
+}
+
+/// Hashes the next archived blocks that were archived before the ledger
+/// started computing their ICRC-3 hashes, if any, and pushes the hashes the
+/// archive nodes need to them. Does one step per call and never runs
+/// concurrently with itself.
+///
+/// This runs from the heartbeat and must not trap: errors are logged and the
+/// step is retried on the next heartbeat.
+async fn update_archived_icrc3_hashes() {
+    {
+        let ledger = LEDGER.read().unwrap();
+        if !ledger.feature_flags.icrc3_enabled() || !ledger.icrc3_hashes_update_pending() {
+            return;
+        }
+    }
+    let _guard = match Icrc3HashesUpdateGuard::new() {
+        Some(guard) => guard,
+        None => return,
+    };
+    if let Err(err) = hash_archived_blocks().await {
+        print(format!("[ledger] failed to hash archived blocks: {}", err));
+    }
+    if let Err(err) = push_icrc3_hashes_to_archive().await {
+        print(format!(
+            "[ledger] failed to push ICRC-3 hashes to the archive: {}",
+            err
+        ));
+    }
+}
+
+#[export_name = "canister_heartbeat"]
+fn canister_heartbeat() {
+    // canister_heartbeat must be synchronous, so we cannot .await the future
+    dfn_core::api::futures::spawn(update_archived_icrc3_hashes());
+}
+
+async fn hash_archived_blocks() -> Result<(), String> {
+    use dfn_core::api::call_with_cleanup;
+
+    let (start, location) = {
+        let ledger = LEDGER.read().unwrap();
+        let start = ledger.icrc3_hashes.len();
+        let num_archived_blocks = ledger.blockchain.num_archived_blocks();
+        if start >= num_archived_blocks {
+            return Ok(());
+        }
+        let length = (num_archived_blocks - start).min(MAX_ARCHIVED_BLOCKS_TO_HASH);
+        let location = block_locations(&*ledger, start, length as usize)
+            .archived_blocks
+            .into_iter()
+            .next();
+        (start, location)
+    };
+    let (canister_id, slice) = match location {
+        Some((canister_id, slice)) if slice.start == start => (canister_id, slice),
+        _ => return Err(format!("block {} is not in the archive", start)),
+    };
+    let result: GetEncodedBlocksResult = call_with_cleanup(
+        canister_id,
+        "get_encoded_blocks",
+        candid_one,
+        GetBlocksArgs {
+            start,
+            length: range_utils::range_len(&slice) as usize,
+        },
+    )
+    .await
+    .map_err(|(code, msg)| {
+        format!(
+            "failed to fetch blocks from archive {}: {:?} {}",
+            canister_id, code, msg
+        )
+    })?;
+    let encoded_blocks =
+        result.map_err(|err| format!("archive {} returned an error: {:?}", canister_id, err))?;
+    let blocks = encoded_blocks
+        .into_iter()
+        .map(Block::decode)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut ledger = LEDGER.write().unwrap();
+    ledger.add_archived_icrc3_hashes(start, &blocks)?;
+    certify_tip(&mut ledger);
+    Ok(())
+}
+
+async fn push_icrc3_hashes_to_archive() -> Result<(), String> {
+    use dfn_core::api::call_with_cleanup;
+
+    let (num_pushed, hashes_by_node) = LEDGER.read().unwrap().icrc3_hashes_to_push();
+    for (canister_id, hashes) in hashes_by_node {
+        let () = call_with_cleanup(canister_id, "icrc3_add_hashes", candid_one, hashes)
+            .await
+            .map_err(|(code, msg)| {
+                format!(
+                    "failed to push hashes to archive {}: {:?} {}",
+                    canister_id, code, msg
+                )
+            })?;
+    }
+    LEDGER.write().unwrap().set_icrc3_hashes_pushed(num_pushed);
+    Ok(())
+}
+
 thread_local! {
+    static ICRC3_HASHES_UPDATE_IN_PROGRESS: Cell<bool> = const { Cell::new(false) };
     static NOTIFY_METHOD_CALLS: RefCell<u64> = const { RefCell::new(0) };
     static PRE_UPGRADE_INSTRUCTIONS_CONSUMED: RefCell<u64> = const { RefCell::new(0) };
     static POST_UPGRADE_INSTRUCTIONS_CONSUMED: RefCell<u64> = const { RefCell::new(0) };
@@ -601,6 +731,12 @@ fn icrc1_supported_standards() -> Vec<StandardRecord> {
             url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-2".to_string(),
         });
     }
+    if LEDGER.read().unwrap().feature_flags.icrc3_enabled() {
+        standards.push(StandardRecord {
+            name: "ICRC-3".to_string(),
+            url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3".to_string(),
+        });
+    }
     standards.push(
         StandardRecord {
             name: "ICRC-21".to_string(),
@@ -761,13 +897,7 @@ fn post_upgrade(args: Option<LedgerCanisterPayload>) {
         }
     }
     }
-    set_certified_data(
-        &ledger
-            .blockchain
-            .last_hash
-            .map(|h| h.into_bytes())
-            .unwrap_or([0u8; 32]),
-    );
+    certify_tip(&mut ledger);
     let mut pre_upgrade_instructions_counter_bytes = [0u8; 8];
     let pre_upgrade_instructions_consumed =
         match stable_reader.read_exact(&mut pre_upgrade_instructions_counter_bytes) {
@@ -1518,7 +1648,7 @@ async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
             memo: Memo(0),
             icrc1_memo: arg.memo.map(|x| x.0),
         };
-        let (block_index, hash) = apply_transaction(&mut *ledger, tx, now, expected_fee)
+        let (block_index, _hash) = apply_transaction(&mut *ledger, tx, now, expected_fee)
             .map_err(convert_transfer_error)
             .map_err(|err| {
                 let err: ApproveError = match ApproveError::try_from(err) {
@@ -1528,7 +1658,7 @@ async fn icrc2_approve(arg: ApproveArgs) -> Result<Nat, ApproveError> {
                 err
             })?;
 
-        set_certified_data(&hash.into_bytes());
+        certify_tip(&mut ledger);
 
         block_index
     };
@@ -1605,6 +1735,134 @@ fn icrc10_supported_standards_candid() {
     over(candid_one, |()| icrc10_supported_standards())
 }
 
+#[candid_method(query, rename = "icrc3_get_blocks")]
+fn icrc3_get_blocks(args: Vec<GetBlocksRequest>) -> GetBlocksResult {
+    let ledger = LEDGER.read().unwrap();
+    if !ledger.feature_flags.icrc3_enabled() {
+        trap_with("ICRC-3 is not enabled on this ledger");
+    }
+    if ledger.icrc3_hashes.len() < ledger.blockchain.chain_length() {
+        trap_with("the ICRC-3 hashes of the blocks are not computed yet");
+    }
+    let max_blocks = max_blocks_per_request(&caller());
+
+    let mut blocks = vec![];
+    let mut archived_blocks_by_callback = BTreeMap::new();
+    for arg in args {
+        let (start, length) = arg
+            .as_start_and_length()
+            .unwrap_or_else(|msg| trap_with(&msg));
+        let max_length = max_blocks.saturating_sub(blocks.len());
+        if max_length == 0 {
+            break;
+        }
+        let length = length.min(max_length as u64) as usize;
+        let locations = block_locations(&*ledger, start, length);
+
+        let local_blocks = locations.local_blocks;
+        for (id, encoded_block) in local_blocks
+            .clone()
+            .zip(ledger.blockchain.block_slice(local_blocks))
+        {
+            let block =
+                Block::decode(encoded_block.clone()).expect("bug: failed to decode encoded block");
+            let parent_hash = id.checked_sub(1).map(|parent_index| {
+                ledger
+                    .icrc3_hash(parent_index)
+                    .expect("bug: missing the ICRC-3 hash of a local block's parent")
+            });
+            blocks.push(BlockWithId {
+                id: Nat::from(id),
+                block: block.to_icrc3_value(parent_hash),
+            });
+        }
+        for (canister_id, slice) in locations.archived_blocks {
+            let callback = QueryArchiveFn::<Vec<GetBlocksRequest>, GetBlocksResult>::new(
+                Principal::from(canister_id),
+                "icrc3_get_blocks".to_string(),
+            );
+            archived_blocks_by_callback
+                .entry(callback)
+                .or_insert(vec![])
+                .push(GetBlocksRequest {
+                    start: Nat::from(slice.start),
+                    length: Nat::from(range_utils::range_len(&slice)),
+                });
+        }
+    }
+
+    GetBlocksResult {
+        log_length: Nat::from(ledger.blockchain.chain_length()),
+        blocks,
+        archived_blocks: archived_blocks_by_callback
+            .into_iter()
+            .map(|(callback, args)| ArchivedBlocks { args, callback })
+            .collect(),
+    }
+}
+
+#[export_name = "canister_query icrc3_get_blocks"]
+fn icrc3_get_blocks_candid() {
+    over(candid_one, icrc3_get_blocks)
+}
+
+#[candid_method(query, rename = "icrc3_get_archives")]
+fn icrc3_get_archives(args: GetArchivesArgs) -> GetArchivesResult {
+    let ledger = LEDGER.read().unwrap();
+    let archive = ledger.blockchain.archive.read().unwrap();
+    archive
+        .iter()
+        .flat_map(|archive| archive.index())
+        .filter_map(|((start, end), canister_id)| {
+            let canister_id = Principal::from(canister_id);
+            if matches!(args.from, Some(from) if canister_id <= from) {
+                return None;
+            }
+            Some(ICRC3ArchiveInfo {
+                canister_id,
+                start: Nat::from(start),
+                end: Nat::from(end),
+            })
+        })
+        .collect()
+}
+
+#[export_name = "canister_query icrc3_get_archives"]
+fn icrc3_get_archives_candid() {
+    over(candid_one, icrc3_get_archives)
+}
+
+#[candid_method(query, rename = "icrc3_get_tip_certificate")]
+fn icrc3_get_tip_certificate() -> Option<ICRC3DataCertificate> {
+    let certificate = serde_bytes::ByteBuf::from(data_certificate()?);
+    let hash_tree = LEDGER.read().unwrap().construct_hash_tree()?;
+    let mut tree_buf = vec![];
+    ciborium::ser::into_writer(&hash_tree, &mut tree_buf).unwrap();
+    Some(ICRC3DataCertificate {
+        certificate,
+        hash_tree: serde_bytes::ByteBuf::from(tree_buf),
+    })
+}
+
+#[export_name = "canister_query icrc3_get_tip_certificate"]
+fn icrc3_get_tip_certificate_candid() {
+    over(candid_one, |()| icrc3_get_tip_certificate())
+}
+
+/// ICP blocks don't have a `btype`. They are typed by the `op` of their
+/// transaction and encode accounts and memos differently than the ICRC-1 and
+/// ICRC-2 block types, see [Block::to_icrc3_value], so none of these types is
+/// advertised.
+#[candid_method(query, rename = "icrc3_supported_block_types")]
+fn icrc3_supported_block_types() -> Vec<SupportedBlockType> {
+    vec![]
+}
+
+#[export_name = "canister_query icrc3_supported_block_types"]
+fn icrc3_supported_block_types_candid() {
+    over(candid_one, |()| icrc3_supported_block_types())
+}
+
 candid::export_service!();
 
 #[export_name = "canister_query __get_candid_interface_tmp_hack"]
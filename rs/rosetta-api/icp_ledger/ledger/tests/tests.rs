@@ -1,8 +1,10 @@
 use candid::Principal;
-use candid::{Decode, Encode, Nat};
+use candid::{CandidType, Decode, Encode, Nat};
 use dfn_candid::CandidOne;
 use dfn_protobuf::ProtoBuf;
 use ic_base_types::CanisterId;
+use ic_certification::verify_certified_data;
+use ic_crypto_tree_hash::{LookupStatus, MixedHashTree};
 use ic_icrc1_ledger_sm_tests::{
     balance_of, default_approve_args, default_transfer_from_args, expect_icrc2_disabled,
     get_allowance, send_approval, send_transfer_from, supported_standards, transfer, MINTER,
@@ -10,23 +12,30 @@ use ic_icrc1_ledger_sm_tests::{
 use ic_ledger_core::{block::BlockType, Tokens};
 use ic_state_machine_tests::{ErrorCode, PrincipalId, StateMachine, UserError};
 use icp_ledger::{
-    AccountIdBlob, AccountIdentifier, ArchiveOptions, ArchivedBlocksRange, Block, CandidBlock,
-    CandidOperation, CandidTransaction, FeatureFlags, GetBlocksArgs, GetBlocksRes, GetBlocksResult,
-    GetEncodedBlocksResult, InitArgs, IterBlocksArgs, IterBlocksRes, LedgerCanisterInitPayload,
-    LedgerCanisterPayload, LedgerCanisterUpgradePayload, Operation, QueryBlocksResponse,
-    QueryEncodedBlocksResponse, TimeStamp, UpgradeArgs, DEFAULT_TRANSFER_FEE,
-    MAX_BLOCKS_PER_INGRESS_REPLICATED_QUERY_REQUEST, MAX_BLOCKS_PER_REQUEST,
+    AccountIdBlob, AccountIdentifier, ArchiveOptions, ArchivedBlocksRange,
+    ArchivedEncodedBlocksRange, Block, CandidBlock, CandidOperation, CandidTransaction,
+    FeatureFlags, GetBlocksArgs, GetBlocksRes, GetBlocksResult, GetEncodedBlocksResult, InitArgs,
+    IterBlocksArgs, IterBlocksRes, LedgerCanisterInitPayload, LedgerCanisterPayload,
+    LedgerCanisterUpgradePayload, Operation, QueryBlocksResponse, QueryEncodedBlocksResponse,
+    TimeStamp, UpgradeArgs, DEFAULT_TRANSFER_FEE, MAX_BLOCKS_PER_INGRESS_REPLICATED_QUERY_REQUEST,
+    MAX_BLOCKS_PER_REQUEST,
 };
+use icrc_ledger_types::icrc::generic_value::ICRC3Value;
 use icrc_ledger_types::icrc1::{
     account::Account,
     transfer::{Memo, TransferArg, TransferError},
 };
 use icrc_ledger_types::icrc2::allowance::AllowanceArgs;
 use icrc_ledger_types::icrc2::approve::ApproveArgs;
+use icrc_ledger_types::icrc3::archive::{GetArchivesArgs, GetArchivesResult, QueryArchiveFn};
+use icrc_ledger_types::icrc3::blocks::{
+    ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResult as Icrc3GetBlocksResult,
+    ICRC3DataCertificate, SupportedBlockType,
+};
 use num_traits::cast::ToPrimitive;
 use on_wire::{FromWire, IntoWire};
 use serde_bytes::ByteBuf;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::{Duration, SystemTime};
 
 fn system_time_to_nanos(t: SystemTime) -> u64 {
@@ -54,7 +63,10 @@ fn encode_init_args(args: ic_icrc1_ledger_sm_tests::InitArgs) -> LedgerCanisterI
         .archive_options(args.archive_options)
         .transfer_fee(Tokens::try_from(args.transfer_fee).unwrap())
         .token_symbol_and_name(&args.token_symbol, &args.token_name)
-        .feature_flags(FeatureFlags { icrc2: true })
+        .feature_flags(FeatureFlags {
+            icrc2: true,
+            icrc3: None,
+        })
         .maximum_number_of_accounts(args.maximum_number_of_accounts)
         .accounts_overflow_trim_quantity(args.accounts_overflow_trim_quantity)
         .build()
@@ -1096,7 +1108,10 @@ fn test_feature_flags() {
         .initial_values(initial_balances)
         .transfer_fee(Tokens::from_e8s(10_000))
         .token_symbol_and_name("ICP", "Internet Computer")
-        .feature_flags(FeatureFlags { icrc2: false })
+        .feature_flags(FeatureFlags {
+            icrc2: false,
+            icrc3: None,
+        })
         .build()
         .unwrap();
     let canister_id = env
@@ -1128,7 +1143,10 @@ fn test_feature_flags() {
         ledger_wasm.clone(),
         Encode!(&LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
             icrc1_minting_account: None,
-            feature_flags: Some(FeatureFlags { icrc2: false }),
+            feature_flags: Some(FeatureFlags {
+                icrc2: false,
+                icrc3: None,
+            }),
         })))
         .unwrap(),
     )
@@ -1148,7 +1166,10 @@ fn test_feature_flags() {
         ledger_wasm,
         Encode!(&LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
             icrc1_minting_account: None,
-            feature_flags: Some(FeatureFlags { icrc2: true }),
+            feature_flags: Some(FeatureFlags {
+                icrc2: true,
+                icrc3: None,
+            }),
         })))
         .unwrap(),
     )
@@ -1277,7 +1298,10 @@ fn test_query_archived_blocks() {
             cycles_for_archive_creation: None,
             max_transactions_per_response: None,
         })
-        .feature_flags(FeatureFlags { icrc2: true })
+        .feature_flags(FeatureFlags {
+            icrc2: true,
+            icrc3: None,
+        })
         .build()
         .unwrap();
     let ledger = env
@@ -1402,6 +1426,334 @@ fn test_icrc21_standard() {
     ic_icrc1_ledger_sm_tests::test_icrc21_standard(ledger_wasm(), encode_init_args);
 }
 
+fn icrc3_get_blocks(
+    env: &StateMachine,
+    ledger: CanisterId,
+    start: u64,
+    length: u64,
+) -> Icrc3GetBlocksResult {
+    Decode!(
+        &env.query(
+            ledger,
+            "icrc3_get_blocks",
+            Encode!(&vec![GetBlocksRequest {
+                start: Nat::from(start),
+                length: Nat::from(length),
+            }])
+            .unwrap()
+        )
+        .expect("failed to query icrc3_get_blocks")
+        .bytes(),
+        Icrc3GetBlocksResult
+    )
+    .expect("failed to decode icrc3_get_blocks response")
+}
+
+fn icrc3_get_tip_certificate(
+    env: &StateMachine,
+    ledger: CanisterId,
+) -> Option<ICRC3DataCertificate> {
+    Decode!(
+        &env.query(ledger, "icrc3_get_tip_certificate", Encode!().unwrap())
+            .expect("failed to query icrc3_get_tip_certificate")
+            .bytes(),
+        Option<ICRC3DataCertificate>
+    )
+    .expect("failed to decode icrc3_get_tip_certificate response")
+}
+
+// Runs the callback `f` returned by a Ledger against the state machine
+// `env` with argument `arg`.
+fn run_archive_fn<I, O>(env: &StateMachine, f: QueryArchiveFn<I, O>, arg: I) -> O
+where
+    I: CandidType,
+    O: CandidType + for<'a> candid::Deserialize<'a>,
+{
+    let res = env
+        .query(
+            CanisterId::unchecked_from_principal(PrincipalId(f.canister_id)),
+            f.method,
+            Encode!(&arg).unwrap(),
+        )
+        .expect("failed to query the archive")
+        .bytes();
+    Decode!(&res, O).expect("failed to decode the archive response")
+}
+
+// Returns all the blocks of the ledger, including the archived ones.
+fn get_all_blocks(env: &StateMachine, ledger: CanisterId) -> Vec<Block> {
+    let res = query_encoded_blocks(env, Principal::from_slice(&[1]), ledger, 0, u32::MAX.into());
+    let mut encoded_blocks = vec![];
+    for ArchivedEncodedBlocksRange {
+        start,
+        length,
+        callback,
+    } in res.archived_blocks
+    {
+        let args = GetBlocksArgs {
+            start,
+            length: length as usize,
+        };
+        encoded_blocks.extend(run_archive_fn(env, callback, args).unwrap());
+    }
+    encoded_blocks.extend(res.blocks);
+    assert_eq!(encoded_blocks.len() as u64, res.chain_length);
+    encoded_blocks
+        .into_iter()
+        .map(|encoded_block| Block::decode(encoded_block).unwrap())
+        .collect()
+}
+
+// Returns all the blocks of the ledger as ICRC-3 values, including the archived
+// ones.
+fn get_all_icrc3_blocks(env: &StateMachine, ledger: CanisterId) -> Vec<ICRC3Value> {
+    let res = icrc3_get_blocks(env, ledger, 0, u32::MAX.into());
+    let mut blocks = BTreeMap::new();
+    for ArchivedBlocks { args, callback } in res.archived_blocks {
+        for BlockWithId { id, block } in run_archive_fn(env, callback, args).blocks {
+            blocks.insert(id.0.to_u64().unwrap(), block);
+        }
+    }
+    for BlockWithId { id, block } in res.blocks {
+        blocks.insert(id.0.to_u64().unwrap(), block);
+    }
+    assert_eq!(
+        blocks.keys().copied().collect::<Vec<_>>(),
+        (0..res.log_length.0.to_u64().unwrap()).collect::<Vec<_>>()
+    );
+    blocks.into_values().collect()
+}
+
+// Checks that the ICRC-3 blocks of the ledger match its blocks and that their
+// phash is the ICRC-3 hash of their parent. Returns the ICRC-3 hash of the last
+// block.
+fn assert_icrc3_blocks_match_blocks(env: &StateMachine, ledger: CanisterId) -> [u8; 32] {
+    let blocks = get_all_blocks(env, ledger);
+    let icrc3_blocks = get_all_icrc3_blocks(env, ledger);
+    assert_eq!(blocks.len(), icrc3_blocks.len());
+    let mut parent_hash = None;
+    for (index, (block, icrc3_block)) in blocks.iter().zip(icrc3_blocks).enumerate() {
+        assert_eq!(
+            icrc3_block,
+            block.to_icrc3_value(parent_hash),
+            "unexpected ICRC-3 value of block {}",
+            index
+        );
+        parent_hash = Some(icrc3_block.hash());
+    }
+    parent_hash.expect("the ledger has no blocks")
+}
+
+// Checks that the tip certificate certifies a hash tree with the given index
+// and ICRC-3 hash of the last block, and the hash of the last encoded block.
+fn assert_tip_certificate(
+    env: &StateMachine,
+    ledger: CanisterId,
+    last_block_index: u64,
+    tip_hash: [u8; 32],
+) {
+    let ICRC3DataCertificate {
+        certificate,
+        hash_tree,
+    } = icrc3_get_tip_certificate(env, ledger).expect("the ledger returned no tip certificate");
+    let hash_tree: MixedHashTree = ciborium::de::from_reader(hash_tree.as_slice()).unwrap();
+    assert_eq!(
+        hash_tree.lookup(&["last_block_index"]),
+        LookupStatus::Found(&MixedHashTree::Leaf(
+            last_block_index.to_be_bytes().to_vec()
+        ))
+    );
+    assert_eq!(
+        hash_tree.lookup(&["tip_hash"]),
+        LookupStatus::Found(&MixedHashTree::Leaf(tip_hash.to_vec()))
+    );
+    let last_block = get_all_blocks(env, ledger)
+        .pop()
+        .expect("the ledger has no blocks");
+    assert_eq!(
+        hash_tree.lookup(&["icp_tip_hash"]),
+        LookupStatus::Found(&MixedHashTree::Leaf(
+            Block::block_hash(&last_block.encode()).as_slice().to_vec()
+        ))
+    );
+    verify_certified_data(
+        &certificate,
+        &ledger,
+        &env.root_key(),
+        &hash_tree.digest().0,
+    )
+    .expect("the certificate doesn't certify the hash tree");
+}
+
+fn icrc3_archive_options() -> ArchiveOptions {
+    ArchiveOptions {
+        trigger_threshold: 2,
+        num_blocks_to_archive: 2,
+        // About 10 blocks, to force creation of more than one archive
+        node_max_memory_size_bytes: Some(1024 + 512),
+        max_message_size_bytes: None,
+        controller_id: PrincipalId::new_anonymous(),
+        more_controller_ids: None,
+        cycles_for_archive_creation: None,
+        max_transactions_per_response: None,
+    }
+}
+
+#[test]
+fn test_icrc3_blocks_and_tip_certificate() {
+    let env = StateMachine::new();
+    let payload = LedgerCanisterInitPayload::builder()
+        .minting_account(MINTER.into())
+        .icrc1_minting_account(MINTER)
+        .transfer_fee(Tokens::from_e8s(10_000))
+        .token_symbol_and_name("ICP", "Internet Computer")
+        .archive_options(icrc3_archive_options())
+        .feature_flags(FeatureFlags {
+            icrc2: true,
+            icrc3: Some(true),
+        })
+        .build()
+        .unwrap();
+    let ledger = env
+        .install_canister(ledger_wasm(), Encode!(&payload).unwrap(), None)
+        .expect("Unable to install the Ledger canister with the new init");
+
+    assert!(supported_standards(&env, ledger)
+        .iter()
+        .any(|standard| standard.name == "ICRC-3"));
+    assert!(icrc3_get_tip_certificate(&env, ledger).is_none());
+
+    let user1 = Principal::from_slice(&[1]);
+    let user2 = Principal::from_slice(&[2]);
+    for i in 0..15 {
+        transfer(&env, ledger, MINTER, user1, 1_000_000 + i).unwrap();
+        transfer(&env, ledger, user1, user2, 100_000 + i).unwrap();
+    }
+    let last_block_index = transfer(&env, ledger, user1, MINTER, 1_000_000).unwrap();
+    // The hashes the archive nodes need are pushed to them from the heartbeat.
+    for _ in 0..5 {
+        env.tick();
+    }
+
+    // The blocks are spread over the ledger and several archive nodes.
+    let archives = Decode!(
+        &env.query(
+            ledger,
+            "icrc3_get_archives",
+            Encode!(&GetArchivesArgs { from: None }).unwrap()
+        )
+        .unwrap()
+        .bytes(),
+        GetArchivesResult
+    )
+    .unwrap();
+    assert!(
+        archives.len() > 1,
+        "expected several archives: {:?}",
+        archives
+    );
+    let local_blocks = icrc3_get_blocks(&env, ledger, 0, u32::MAX.into()).blocks;
+    assert!(!local_blocks.is_empty());
+
+    let tip_hash = assert_icrc3_blocks_match_blocks(&env, ledger);
+    assert_tip_certificate(&env, ledger, last_block_index, tip_hash);
+
+    // The legacy certificate is replaced by the one of the hash tree.
+    let certificate = query_encoded_blocks(&env, user1, ledger, 0, 0)
+        .certificate
+        .unwrap();
+    let last_block = get_all_blocks(&env, ledger).pop().unwrap();
+    assert!(verify_certified_data(
+        &certificate,
+        &ledger,
+        &env.root_key(),
+        &Block::block_hash(&last_block.encode()).into_bytes(),
+    )
+    .is_err());
+
+    // ICP blocks don't follow the schemas of the ICRC-1 and ICRC-2 block types.
+    let block_types = Decode!(
+        &env.query(ledger, "icrc3_supported_block_types", Encode!().unwrap())
+            .unwrap()
+            .bytes(),
+        Vec<SupportedBlockType>
+    )
+    .unwrap();
+    assert!(block_types.is_empty(), "{:?}", block_types);
+}
+
+#[test]
+fn test_icrc3_enabled_on_upgrade() {
+    let env = StateMachine::new();
+    let payload = LedgerCanisterInitPayload::builder()
+        .minting_account(MINTER.into())
+        .icrc1_minting_account(MINTER)
+        .transfer_fee(Tokens::from_e8s(10_000))
+        .token_symbol_and_name("ICP", "Internet Computer")
+        .archive_options(icrc3_archive_options())
+        .feature_flags(FeatureFlags {
+            icrc2: true,
+            icrc3: None,
+        })
+        .build()
+        .unwrap();
+    let ledger = env
+        .install_canister(ledger_wasm(), Encode!(&payload).unwrap(), None)
+        .expect("Unable to install the Ledger canister with the new init");
+
+    let user1 = Principal::from_slice(&[1]);
+    let user2 = Principal::from_slice(&[2]);
+    for i in 0..15 {
+        transfer(&env, ledger, MINTER, user1, 1_000_000 + i).unwrap();
+        transfer(&env, ledger, user1, user2, 100_000 + i).unwrap();
+    }
+
+    // Without ICRC-3, the ledger certifies the hash of the last block.
+    assert!(!supported_standards(&env, ledger)
+        .iter()
+        .any(|standard| standard.name == "ICRC-3"));
+    assert!(icrc3_get_tip_certificate(&env, ledger).is_none());
+    let certificate = query_encoded_blocks(&env, user1, ledger, 0, 0)
+        .certificate
+        .unwrap();
+    let last_block = get_all_blocks(&env, ledger).pop().unwrap();
+    verify_certified_data(
+        &certificate,
+        &ledger,
+        &env.root_key(),
+        &Block::block_hash(&last_block.encode()).into_bytes(),
+    )
+    .expect("the certificate doesn't certify the hash of the last block");
+
+    env.upgrade_canister(
+        ledger,
+        ledger_wasm(),
+        Encode!(&LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
+            icrc1_minting_account: None,
+            feature_flags: Some(FeatureFlags {
+                icrc2: true,
+                icrc3: Some(true),
+            }),
+        })))
+        .unwrap(),
+    )
+    .unwrap();
+
+    // The archived blocks are hashed from the heartbeat, one archive node at a
+    // time. Until then, the ledger keeps the legacy certificate.
+    assert!(icrc3_get_tip_certificate(&env, ledger).is_none());
+    let last_block_index = transfer(&env, ledger, user2, user1, 1_000).unwrap();
+    for _ in 0..10 {
+        if icrc3_get_tip_certificate(&env, ledger).is_some() {
+            break;
+        }
+        env.tick();
+    }
+
+    let tip_hash = assert_icrc3_blocks_match_blocks(&env, ledger);
+    assert_tip_certificate(&env, ledger, last_block_index, tip_hash);
+}
+
 mod metrics {
     use crate::{encode_init_args, encode_upgrade_args, ledger_wasm};
     use ic_icrc1_ledger_sm_tests::metrics::LedgerSuiteType;
@@ -12,6 +12,7 @@ use ic_ledger_core::{
 };
 use ic_ledger_hash_of::HashOf;
 use ic_ledger_hash_of::HASH_LENGTH;
+use icrc_ledger_types::icrc::generic_value::{Hash as Icrc3Hash, ICRC3Value};
 use icrc_ledger_types::icrc1::account::Account;
 use on_wire::{FromWire, IntoWire};
 use serde::{Deserialize, Serialize};
@@ -1038,6 +1039,131 @@ impl TryFrom<CandidBlock> for Block {
     }
 }
 
+/// Blocks whose ICRC-3 hash is kept by the ledger and its archives, so that the
+/// ICRC-3 hash of any block can be computed from a kept hash and at most
+/// `ICRC3_HASH_CHECKPOINT_INTERVAL - 1` blocks.
+pub const ICRC3_HASH_CHECKPOINT_INTERVAL: u64 = 1_000;
+
+pub fn is_icrc3_hash_checkpoint(index: BlockIndex) -> bool {
+    (index + 1) % ICRC3_HASH_CHECKPOINT_INTERVAL == 0
+}
+
+/// The ICRC-3 representation of blocks.
+///
+/// The schema follows the one of ICRC-1 ledger blocks, with the following
+/// differences: accounts (`from`, `to` and `spender`) are 32-byte account
+/// identifiers (including the checksum) rather than `[owner, subaccount]`
+/// arrays, the legacy memo is a `Nat` in `memo` and the ICRC-1 memo, if any, a
+/// `Blob` in `icrc1_memo`.
+///
+/// As required by ICRC-3, the `phash` is the representation-independent hash
+/// of the ICRC-3 value of the parent block. The ICRC-3 hashes therefore form a
+/// chain of their own, next to the chain of the hashes of the encoded blocks.
+impl Block {
+    /// Returns the ICRC-3 value of this block, given the ICRC-3 hash of its
+    /// parent, which must be `None` only for the first block.
+    pub fn to_icrc3_value(&self, parent_icrc3_hash: Option<Icrc3Hash>) -> ICRC3Value {
+        fn account(account: &AccountIdentifier) -> ICRC3Value {
+            ICRC3Value::Blob(ByteBuf::from(account.to_vec()))
+        }
+        fn tokens(tokens: &Tokens) -> ICRC3Value {
+            ICRC3Value::Nat(candid::Nat::from(tokens.get_e8s()))
+        }
+        fn timestamp_value(timestamp: &TimeStamp) -> ICRC3Value {
+            ICRC3Value::Nat(candid::Nat::from(timestamp.as_nanos_since_unix_epoch()))
+        }
+
+        let transaction = &self.transaction;
+        let mut tx = BTreeMap::new();
+        let op = match &transaction.operation {
+            Operation::Burn {
+                from,
+                amount,
+                spender,
+            } => {
+                tx.insert("from".to_string(), account(from));
+                tx.insert("amt".to_string(), tokens(amount));
+                if let Some(spender) = spender {
+                    tx.insert("spender".to_string(), account(spender));
+                }
+                "burn"
+            }
+            Operation::Mint { to, amount } => {
+                tx.insert("to".to_string(), account(to));
+                tx.insert("amt".to_string(), tokens(amount));
+                "mint"
+            }
+            Operation::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+                spender,
+            } => {
+                tx.insert("from".to_string(), account(from));
+                tx.insert("to".to_string(), account(to));
+                tx.insert("amt".to_string(), tokens(amount));
+                tx.insert("fee".to_string(), tokens(fee));
+                if let Some(spender) = spender {
+                    tx.insert("spender".to_string(), account(spender));
+                }
+                "xfer"
+            }
+            Operation::Approve {
+                from,
+                spender,
+                allowance,
+                expected_allowance,
+                expires_at,
+                fee,
+            } => {
+                tx.insert("from".to_string(), account(from));
+                tx.insert("spender".to_string(), account(spender));
+                tx.insert("amt".to_string(), tokens(allowance));
+                tx.insert("fee".to_string(), tokens(fee));
+                if let Some(expected_allowance) = expected_allowance {
+                    tx.insert("expected_allowance".to_string(), tokens(expected_allowance));
+                }
+                if let Some(expires_at) = expires_at {
+                    tx.insert("expires_at".to_string(), timestamp_value(expires_at));
+                }
+                "approve"
+            }
+        };
+        tx.insert("op".to_string(), ICRC3Value::Text(op.to_string()));
+        tx.insert(
+            "memo".to_string(),
+            ICRC3Value::Nat(candid::Nat::from(transaction.memo.0)),
+        );
+        if let Some(icrc1_memo) = &transaction.icrc1_memo {
+            tx.insert(
+                "icrc1_memo".to_string(),
+                ICRC3Value::Blob(icrc1_memo.clone()),
+            );
+        }
+        if let Some(created_at_time) = &transaction.created_at_time {
+            tx.insert("ts".to_string(), timestamp_value(created_at_time));
+        }
+
+        let mut block = BTreeMap::new();
+        if let Some(parent_icrc3_hash) = parent_icrc3_hash {
+            block.insert(
+                "phash".to_string(),
+                ICRC3Value::Blob(ByteBuf::from(parent_icrc3_hash.to_vec())),
+            );
+        }
+        block.insert("ts".to_string(), timestamp_value(&self.timestamp));
+        block.insert("tx".to_string(), ICRC3Value::Map(tx));
+        ICRC3Value::Map(block)
+    }
+
+    /// Returns the representation-independent hash of the ICRC-3 value of this
+    /// block, given the ICRC-3 hash of its parent.
+    pub fn icrc3_hash(&self, parent_icrc3_hash: Option<Icrc3Hash>) -> Icrc3Hash {
+        self.to_icrc3_value(parent_icrc3_hash).hash()
+    }
+}
+
 /// Argument taken by the transfer fee endpoint
 ///
 /// The reason it is a struct is so that it can be extended -- e.g., to be able
@@ -1214,11 +1340,23 @@ pub type QueryArchiveEncodedBlocksFn =
 #[derive(CandidType, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct FeatureFlags {
     pub icrc2: bool,
+    /// Whether the ledger certifies the ICRC-3 hash tree instead of the hash
+    /// of the tip of the chain. Changing the certified data breaks clients that
+    /// verify the legacy certificate, hence the flag is opt-in.
+    #[serde(default)]
+    pub icrc3: Option<bool>,
 }
 
 impl FeatureFlags {
     const fn const_default() -> Self {
-        Self { icrc2: true }
+        Self {
+            icrc2: true,
+            icrc3: None,
+        }
+    }
+
+    pub fn icrc3_enabled(&self) -> bool {
+        self.icrc3.unwrap_or(false)
     }
 }
 
@@ -1392,6 +1530,54 @@ mod test {
         )
     }
 
+    #[test]
+    fn test_icrc3_value() {
+        let from = AccountIdentifier::from_str(
+            "e7a879ea563d273c46dd28c1584eaa132fad6f3e316615b3eb657d067f3519b5",
+        )
+        .unwrap();
+        let to = AccountIdentifier::from_str(
+            "207ec07185bedd0f2176ec2760057b8b7bc619a94d60e70fbc91af322a9f7e93",
+        )
+        .unwrap();
+        let block = Block {
+            parent_hash: Some(HashOf::new([1u8; 32])),
+            transaction: Transaction {
+                operation: Operation::Transfer {
+                    from,
+                    to,
+                    amount: Tokens::from_e8s(100),
+                    fee: Tokens::from_e8s(10),
+                    spender: None,
+                },
+                memo: Memo(42),
+                created_at_time: Some(TimeStamp::from_nanos_since_unix_epoch(1)),
+                icrc1_memo: None,
+            },
+            timestamp: TimeStamp::from_nanos_since_unix_epoch(2),
+        };
+
+        let nat = |n: u64| ICRC3Value::Nat(candid::Nat::from(n));
+        let blob = |bytes: Vec<u8>| ICRC3Value::Blob(ByteBuf::from(bytes));
+        let expected_tx = BTreeMap::from([
+            ("op".to_string(), ICRC3Value::Text("xfer".to_string())),
+            ("from".to_string(), blob(from.to_vec())),
+            ("to".to_string(), blob(to.to_vec())),
+            ("amt".to_string(), nat(100)),
+            ("fee".to_string(), nat(10)),
+            ("memo".to_string(), nat(42)),
+            ("ts".to_string(), nat(1)),
+        ]);
+        let expected = ICRC3Value::Map(BTreeMap::from([
+            ("phash".to_string(), blob(vec![2u8; 32])),
+            ("ts".to_string(), nat(2)),
+            ("tx".to_string(), ICRC3Value::Map(expected_tx)),
+        ]));
+        // The phash is the ICRC-3 hash of the parent, not its native hash.
+        assert_eq!(block.to_icrc3_value(Some([2u8; 32])), expected);
+        assert_eq!(block.icrc3_hash(Some([2u8; 32])), expected.hash());
+    }
+
     #[test]
     fn test_encode_decode() {
         proptest!(|(block in arb_block())| {
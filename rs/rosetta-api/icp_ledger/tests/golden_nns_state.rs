@@ -79,7 +79,10 @@ fn upgrade_ledger(state_machine: &StateMachine, wasm_bytes: Vec<u8>) {
     let ledger_upgrade_args: LedgerCanisterPayload =
         LedgerCanisterPayload::Upgrade(Some(UpgradeArgs {
             icrc1_minting_account: None,
-            feature_flags: Some(FeatureFlags { icrc2: true }),
+            feature_flags: Some(FeatureFlags {
+                icrc2: true,
+                icrc3: None,
+            }),
         }));
 
     state_machine
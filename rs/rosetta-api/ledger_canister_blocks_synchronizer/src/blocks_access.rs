@@ -4,6 +4,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use ic_ledger_core::block::{BlockIndex, EncodedBlock};
 use icp_ledger::TipOfChainRes;
+use icrc_ledger_types::icrc3::blocks::ICRC3DataCertificate;
 
 use crate::canister_access::CanisterAccess;
 
@@ -12,6 +13,7 @@ use crate::canister_access::CanisterAccess;
 pub trait BlocksAccess {
     async fn query_raw_block(&self, height: BlockIndex) -> Result<Option<EncodedBlock>, String>;
     async fn query_tip(&self) -> Result<TipOfChainRes, String>;
+    async fn query_tip_certificate(&self) -> Result<Option<ICRC3DataCertificate>, String>;
     async fn multi_query_blocks(
         self: Arc<Self>,
         range: Range<BlockIndex>,
@@ -28,6 +30,10 @@ impl BlocksAccess for CanisterAccess {
         self.query_tip().await
     }
 
+    async fn query_tip_certificate(&self) -> Result<Option<ICRC3DataCertificate>, String> {
+        self.query_tip_certificate().await
+    }
+
     async fn multi_query_blocks(
         self: Arc<Self>,
         range: Range<BlockIndex>,
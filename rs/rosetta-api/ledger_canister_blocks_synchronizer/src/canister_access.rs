@@ -1,3 +1,4 @@
+use candid::{Decode, Encode};
 use dfn_protobuf::{ProtoBuf, ToProto};
 use ic_agent::agent::http_transport::reqwest_transport::ReqwestTransport;
 use ic_agent::identity::AnonymousIdentity;
@@ -6,6 +7,7 @@ use ic_ledger_core::block::EncodedBlock;
 use ic_types::CanisterId;
 use icp_ledger::protobuf::{ArchiveIndexEntry, ArchiveIndexResponse, TipOfChainRequest};
 use icp_ledger::{BlockArg, BlockIndex, BlockRes, GetBlocksArgs, GetBlocksRes, TipOfChainRes};
+use icrc_ledger_types::icrc3::blocks::ICRC3DataCertificate;
 use on_wire::{FromWire, IntoWire};
 use std::collections::VecDeque;
 use std::convert::TryFrom;
@@ -112,6 +114,19 @@ impl CanisterAccess {
             .map_err(|e| format!("In tip: {}", e))
     }
 
+    pub async fn query_tip_certificate(&self) -> Result<Option<ICRC3DataCertificate>, String> {
+        let arg = Encode!().map_err(|e| format!("In tip certificate: {}", e))?;
+        let bytes = self
+            .agent
+            .query(&self.canister_id.get().0, "icrc3_get_tip_certificate")
+            .with_arg(arg)
+            .call()
+            .await
+            .map_err(|e| format!("In tip certificate: {}", e))?;
+        Decode!(&bytes, Option<ICRC3DataCertificate>)
+            .map_err(|e| format!("In tip certificate: {}", e))
+    }
+
     pub async fn query_raw_block(
         &self,
         height: BlockIndex,
@@ -1,7 +1,10 @@
+use crate::blocks_access::BlocksAccess;
 use ic_certification::verify_certified_data;
-use ic_ledger_core::block::EncodedBlock;
+use ic_crypto_tree_hash::{LookupStatus, MixedHashTree};
+use ic_ledger_core::block::{BlockIndex, EncodedBlock};
 use ic_ledger_hash_of::HashOf;
 use ic_types::{crypto::threshold_sig::ThresholdSigPublicKey, CanisterId};
+use icrc_ledger_types::icrc3::blocks::ICRC3DataCertificate;
 
 pub struct VerificationInfo {
     pub root_key: ThresholdSigPublicKey,
@@ -23,3 +26,68 @@ pub(crate) fn verify_block_hash(
     .map(|_| ()) // we don't need the result so we discard it
     .map_err(|e| format!("Certification error: {:?}", e))
 }
+
+/// Verifies that `hash` is the certified hash of the block at `tip_index`.
+///
+/// Ledgers with ICRC-3 enabled certify the digest of their ICRC-3 hash tree
+/// instead of the hash itself. That tree contains the hash under
+/// `icp_tip_hash` and is fetched with the tip certificate if `cert` doesn't
+/// certify the hash directly.
+pub(crate) async fn verify_tip_hash<B: BlocksAccess + ?Sized>(
+    blocks_access: &B,
+    cert: &icp_ledger::Certification,
+    hash: HashOf<EncodedBlock>,
+    tip_index: BlockIndex,
+    info: &VerificationInfo,
+) -> Result<(), String> {
+    let Err(err) = verify_block_hash(cert, hash, info) else {
+        return Ok(());
+    };
+    match blocks_access.query_tip_certificate().await {
+        Ok(Some(tip_certificate)) => {
+            verify_tip_certificate(&tip_certificate, hash, tip_index, info)
+        }
+        Ok(None) => Err(err),
+        Err(e) => Err(format!(
+            "{}, and the tip certificate is unavailable: {}",
+            err, e
+        )),
+    }
+}
+
+/// Verifies that the ICRC-3 tip certificate certifies `hash` as the hash of
+/// the block at `tip_index`.
+fn verify_tip_certificate(
+    tip_certificate: &ICRC3DataCertificate,
+    hash: HashOf<EncodedBlock>,
+    tip_index: BlockIndex,
+    info: &VerificationInfo,
+) -> Result<(), String> {
+    let hash_tree: MixedHashTree = ciborium::de::from_reader(tip_certificate.hash_tree.as_slice())
+        .map_err(|e| format!("Invalid tip hash tree: {}", e))?;
+    verify_certified_data(
+        &tip_certificate.certificate,
+        &info.canister_id,
+        &info.root_key,
+        &hash_tree.digest().0,
+    )
+    .map_err(|e| format!("Certification error: {:?}", e))?;
+
+    let leaf = |label: &str| match hash_tree.lookup(&[label]) {
+        LookupStatus::Found(MixedHashTree::Leaf(value)) => Ok(value.clone()),
+        _ => Err(format!("The tip hash tree has no {}", label)),
+    };
+    if leaf("last_block_index")? != tip_index.to_be_bytes() {
+        return Err(format!(
+            "The tip certificate doesn't certify block {}",
+            tip_index
+        ));
+    }
+    if leaf("icp_tip_hash")? != hash.as_slice() {
+        return Err(format!(
+            "The certified hash of block {} doesn't match {}",
+            tip_index, hash
+        ));
+    }
+    Ok(())
+}
@@ -14,7 +14,7 @@ use tracing::{debug, error, info, trace, warn};
 use crate::blocks::BlockStoreError;
 use crate::blocks::{Blocks, HashedBlock};
 use crate::blocks_access::BlocksAccess;
-use crate::certification::{verify_block_hash, VerificationInfo};
+use crate::certification::{verify_tip_hash, VerificationInfo};
 use crate::errors::Error;
 
 // If pruning is enabled, instead of pruning after each new block
@@ -199,11 +199,14 @@ impl<B: BlocksAccess> LedgerBlocksSynchronizer<B> {
             .await
             .map_err(Error::InternalError)?
             .expect("Blockchain in the ledger canister is empty");
-        verify_block_hash(
+        verify_tip_hash(
+            canister_access,
             &certification,
             Block::block_hash(&tip_block),
+            tip_index,
             verification_info,
         )
+        .await
         .map_err(Error::InternalError)?;
         Ok(())
     }
@@ -235,7 +238,7 @@ impl<B: BlocksAccess> LedgerBlocksSynchronizer<B> {
                 block.timestamp,
             )
             .hash;
-            verify_block_hash(&certification, hash, info)?;
+            verify_tip_hash(canister.as_ref(), &certification, hash, tip_index, info).await?;
         }
         Ok(BlockWithIndex {
             block,
@@ -434,6 +437,7 @@ mod test {
     use icp_ledger::{
         AccountIdentifier, Block, BlockIndex, Memo, TipOfChainRes, DEFAULT_TRANSFER_FEE,
     };
+    use icrc_ledger_types::icrc3::blocks::ICRC3DataCertificate;
 
     use crate::blocks_access::BlocksAccess;
     use crate::ledger_blocks_sync::LedgerBlocksSynchronizer;
@@ -478,6 +482,10 @@ mod test {
             }
         }
 
+        async fn query_tip_certificate(&self) -> Result<Option<ICRC3DataCertificate>, String> {
+            Ok(None)
+        }
+
         async fn multi_query_blocks(
             self: Arc<Self>,
             range: Range<BlockIndex>,
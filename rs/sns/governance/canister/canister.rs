@@ -47,9 +47,11 @@ use ic_sns_governance::{
         GetModeResponse, GetNeuron, GetNeuronResponse, GetProposal, GetProposalResponse,
         GetRunningSnsVersionRequest, GetRunningSnsVersionResponse,
         GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
-        Governance as GovernanceProto, ListNervousSystemFunctionsResponse, ListNeurons,
-        ListNeuronsResponse, ListProposals, ListProposalsResponse, ManageNeuron,
-        ManageNeuronResponse, NervousSystemParameters, RewardEvent, SetMode, SetModeResponse,
+        Governance as GovernanceProto, ListKnownNeuronsRequest, ListKnownNeuronsResponse,
+        ListNervousSystemFunctionsResponse, ListNeurons, ListNeuronsResponse, ListProposals,
+        ListProposalsResponse, ListTreasuryDisbursementSchedulesRequest,
+        ListTreasuryDisbursementSchedulesResponse, ManageNeuron, ManageNeuronResponse,
+        NervousSystemParameters, RewardEvent, SetMode, SetModeResponse,
     },
     types::{Environment, HeapGrowthPotential},
 };
@@ -484,6 +486,34 @@ fn list_nervous_system_functions_() -> ListNervousSystemFunctionsResponse {
     governance().list_nervous_system_functions()
 }
 
+/// Returns the known neurons, ordered by name.
+#[export_name = "canister_query list_known_neurons"]
+fn list_known_neurons() {
+    log!(INFO, "list_known_neurons");
+    over(candid_one, list_known_neurons_)
+}
+
+/// Internal method for calling list_known_neurons.
+#[candid_method(query, rename = "list_known_neurons")]
+fn list_known_neurons_(_request: ListKnownNeuronsRequest) -> ListKnownNeuronsResponse {
+    governance().list_known_neurons()
+}
+
+/// Returns the treasury disbursement schedules that are still paying out.
+#[export_name = "canister_query list_treasury_disbursement_schedules"]
+fn list_treasury_disbursement_schedules() {
+    log!(INFO, "list_treasury_disbursement_schedules");
+    over(candid_one, list_treasury_disbursement_schedules_)
+}
+
+/// Internal method for calling list_treasury_disbursement_schedules.
+#[candid_method(query, rename = "list_treasury_disbursement_schedules")]
+fn list_treasury_disbursement_schedules_(
+    _request: ListTreasuryDisbursementSchedulesRequest,
+) -> ListTreasuryDisbursementSchedulesResponse {
+    governance().list_treasury_disbursement_schedules()
+}
+
 /// Returns the latest reward event.
 #[export_name = "canister_query get_latest_reward_event"]
 fn get_latest_reward_event() {
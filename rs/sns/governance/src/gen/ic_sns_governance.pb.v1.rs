@@ -146,6 +146,11 @@ pub struct Neuron {
     /// entry\[i\].timestamp_of_disbursement_seconds <= entry\[i+1\].timestamp_of_disbursement_seconds
     #[prost(message, repeated, tag = "18")]
     pub disburse_maturity_in_progress: ::prost::alloc::vec::Vec<DisburseMaturityInProgress>,
+    /// The name and description of the neuron, if it was registered as a known
+    /// neuron by a RegisterKnownNeuron proposal. Known neurons help voters find
+    /// reputable followees.
+    #[prost(message, optional, tag = "19")]
+    pub known_neuron_data: ::core::option::Option<KnownNeuronData>,
     /// The neuron's dissolve state, specifying whether the neuron is dissolving,
     /// non-dissolving, or dissolved.
     ///
@@ -408,6 +413,127 @@ pub mod transfer_sns_treasury_funds {
         }
     }
 }
+/// A proposal to pay out SNS treasury funds (ICP or SNS tokens) to an account
+/// in equal installments, e.g., to pay a contributor every month for a year.
+///
+/// The first payout is made `cliff_seconds` after the proposal is executed, and
+/// each following payout `period_seconds` after the previous one was due. Every
+/// payout is subject to the same 7-day upper bound as TransferSnsTreasuryFunds
+/// proposals, based on the valuation of the treasury at the time of the payout.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CreateTreasuryDisbursementSchedule {
+    /// The treasury from which to make the payouts.
+    #[prost(enumeration = "transfer_sns_treasury_funds::TransferFrom", tag = "1")]
+    pub from_treasury: i32,
+    /// The amount of each payout, in e8s.
+    #[prost(uint64, optional, tag = "2")]
+    pub amount_per_period_e8s: ::core::option::Option<u64>,
+    /// The number of payouts.
+    #[prost(uint64, optional, tag = "3")]
+    pub number_of_periods: ::core::option::Option<u64>,
+    /// The time between two consecutive payouts.
+    #[prost(uint64, optional, tag = "4")]
+    pub period_seconds: ::core::option::Option<u64>,
+    /// The time between the execution of the proposal and the first payout.
+    #[prost(uint64, optional, tag = "5")]
+    pub cliff_seconds: ::core::option::Option<u64>,
+    /// The principal to make the payouts to.
+    #[prost(message, optional, tag = "6")]
+    pub to_principal: ::core::option::Option<::ic_base_types::PrincipalId>,
+    /// An (optional) Subaccount of the principal to make the payouts to.
+    #[prost(message, optional, tag = "7")]
+    pub to_subaccount: ::core::option::Option<Subaccount>,
+}
+/// A proposal to cancel a treasury disbursement schedule. The payouts that
+/// have already been made are not affected.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CancelTreasuryDisbursementSchedule {
+    /// The ID of the schedule, i.e., the ID of the proposal that created it.
+    #[prost(uint64, optional, tag = "1")]
+    pub schedule_id: ::core::option::Option<u64>,
+}
+/// A payout made as part of a treasury disbursement schedule.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TreasuryPayout {
+    #[prost(uint64, optional, tag = "1")]
+    pub timestamp_seconds: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub amount_e8s: ::core::option::Option<u64>,
+    /// The index of the ledger block of the transfer.
+    #[prost(uint64, optional, tag = "3")]
+    pub block_height: ::core::option::Option<u64>,
+    /// The treasury from which the payout was made.
+    #[prost(enumeration = "transfer_sns_treasury_funds::TransferFrom", tag = "4")]
+    pub from_treasury: i32,
+}
+/// An active treasury disbursement schedule, created by a
+/// CreateTreasuryDisbursementSchedule proposal. A schedule is removed once
+/// its last payout has been made, or when it is cancelled.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TreasuryDisbursementSchedule {
+    /// The ID of the proposal that created the schedule.
+    #[prost(uint64, optional, tag = "1")]
+    pub id: ::core::option::Option<u64>,
+    /// The parameters of the schedule, as proposed.
+    #[prost(message, optional, tag = "2")]
+    pub parameters: ::core::option::Option<CreateTreasuryDisbursementSchedule>,
+    /// When the first payout is due.
+    #[prost(uint64, optional, tag = "3")]
+    pub first_payout_timestamp_seconds: ::core::option::Option<u64>,
+    /// The payouts made so far, oldest first.
+    #[prost(message, repeated, tag = "4")]
+    pub payouts: ::prost::alloc::vec::Vec<TreasuryPayout>,
+    /// Why the most recent payout attempt failed, if it did. Failed payouts
+    /// are retried after an hour.
+    #[prost(string, optional, tag = "6")]
+    pub last_failure_reason: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(uint64, optional, tag = "7")]
+    pub last_failure_timestamp_seconds: ::core::option::Option<u64>,
+    /// The created_at_time of the transfer of the next payout, set when it is
+    /// first attempted. Retries reuse it, so that the ledger deduplicates them
+    /// if an earlier attempt went through.
+    #[prost(uint64, optional, tag = "8")]
+    pub next_payout_created_at_time_nanos: ::core::option::Option<u64>,
+}
+/// The name and (optionally) the description of a known neuron.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KnownNeuronData {
+    /// The name of the neuron, which is unique among known neurons.
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub description: ::core::option::Option<::prost::alloc::string::String>,
+}
+/// A proposal to register a neuron as a known neuron, i.e., to give it a name
+/// (and optionally a description). Registering an already known neuron again
+/// replaces its data.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KnownNeuron {
+    #[prost(message, optional, tag = "1")]
+    pub id: ::core::option::Option<NeuronId>,
+    #[prost(message, optional, tag = "2")]
+    pub known_neuron_data: ::core::option::Option<KnownNeuronData>,
+}
+/// A proposal to remove the name and description of a known neuron.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeregisterKnownNeuron {
+    #[prost(message, optional, tag = "1")]
+    pub id: ::core::option::Option<NeuronId>,
+}
 /// A proposal function that changes the ledger's parameters.
 /// Fields with None values will remain unchanged.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -516,6 +642,98 @@ pub struct ManageDappCanisterSettings {
     #[prost(uint64, optional, tag = "7")]
     pub wasm_memory_limit: ::core::option::Option<u64>,
 }
+/// A proposal to launch a follow-on sale round in the SNS's swap canister.
+///
+/// The SNS tokens offered are either minted or taken from the SNS token
+/// treasury, and are transferred to the swap canister before the round is
+/// opened. The round starts `start_delay_seconds` after the proposal is
+/// executed and lasts `duration_seconds`. Participants receive their SNS
+/// tokens as neuron baskets, exactly as in the initial decentralization swap.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LaunchFollowOnSwap {
+    /// Where the SNS tokens offered come from.
+    #[prost(
+        enumeration = "launch_follow_on_swap::TokenSource",
+        optional,
+        tag = "1"
+    )]
+    pub token_source: ::core::option::Option<i32>,
+    /// The number of SNS tokens offered, in e8s.
+    #[prost(uint64, optional, tag = "2")]
+    pub sns_token_e8s: ::core::option::Option<u64>,
+    /// The same as the corresponding fields of the swap's `Init`.
+    #[prost(uint32, optional, tag = "3")]
+    pub min_participants: ::core::option::Option<u32>,
+    #[prost(uint64, optional, tag = "4")]
+    pub min_direct_participation_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub max_direct_participation_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "6")]
+    pub min_participant_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "7")]
+    pub max_participant_icp_e8s: ::core::option::Option<u64>,
+    /// The time between the execution of the proposal and the start of the round.
+    #[prost(uint64, optional, tag = "8")]
+    pub start_delay_seconds: ::core::option::Option<u64>,
+    /// The duration of the round, between 1 and 14 days.
+    #[prost(uint64, optional, tag = "9")]
+    pub duration_seconds: ::core::option::Option<u64>,
+    /// The number of neurons each participant receives, and the dissolve delay
+    /// interval between them.
+    #[prost(uint64, optional, tag = "10")]
+    pub neuron_basket_count: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "11")]
+    pub neuron_basket_dissolve_delay_interval_seconds: ::core::option::Option<u64>,
+}
+/// Nested message and enum types in `LaunchFollowOnSwap`.
+pub mod launch_follow_on_swap {
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        Copy,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash,
+        PartialOrd,
+        Ord,
+        ::prost::Enumeration,
+    )]
+    #[repr(i32)]
+    pub enum TokenSource {
+        Unspecified = 0,
+        /// The SNS tokens offered are newly minted.
+        Mint = 1,
+        /// The SNS tokens offered are taken from the SNS token treasury.
+        Treasury = 2,
+    }
+    impl TokenSource {
+        /// String value of the enum field names used in the ProtoBuf definition.
+        ///
+        /// The values are not transformed in any way and thus are considered stable
+        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                TokenSource::Unspecified => "TOKEN_SOURCE_UNSPECIFIED",
+                TokenSource::Mint => "TOKEN_SOURCE_MINT",
+                TokenSource::Treasury => "TOKEN_SOURCE_TREASURY",
+            }
+        }
+        /// Creates an enum from field names used in the ProtoBuf definition.
+        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+            match value {
+                "TOKEN_SOURCE_UNSPECIFIED" => Some(Self::Unspecified),
+                "TOKEN_SOURCE_MINT" => Some(Self::Mint),
+                "TOKEN_SOURCE_TREASURY" => Some(Self::Treasury),
+                _ => None,
+            }
+        }
+    }
+}
 /// A proposal is the immutable input of a proposal submission.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[compare_default]
@@ -545,7 +763,7 @@ pub struct Proposal {
     /// of this mapping.
     #[prost(
         oneof = "proposal::Action",
-        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18"
+        tags = "4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23"
     )]
     pub action: ::core::option::Option<proposal::Action>,
 }
@@ -651,6 +869,31 @@ pub mod proposal {
         /// Id = 14.
         #[prost(message, tag = "18")]
         ManageDappCanisterSettings(super::ManageDappCanisterSettings),
+        /// Launch a follow-on sale round in the SNS's swap canister.
+        ///
+        /// Id = 15.
+        #[prost(message, tag = "19")]
+        LaunchFollowOnSwap(super::LaunchFollowOnSwap),
+        /// Pay out SNS treasury funds to an account in installments.
+        ///
+        /// Id = 16.
+        #[prost(message, tag = "20")]
+        CreateTreasuryDisbursementSchedule(super::CreateTreasuryDisbursementSchedule),
+        /// Cancel the remaining payouts of a treasury disbursement schedule.
+        ///
+        /// Id = 17.
+        #[prost(message, tag = "21")]
+        CancelTreasuryDisbursementSchedule(super::CancelTreasuryDisbursementSchedule),
+        /// Register a neuron as a known neuron.
+        ///
+        /// Id = 18.
+        #[prost(message, tag = "22")]
+        RegisterKnownNeuron(super::KnownNeuron),
+        /// Deregister a known neuron.
+        ///
+        /// Id = 19.
+        #[prost(message, tag = "23")]
+        DeregisterKnownNeuron(super::DeregisterKnownNeuron),
     }
 }
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
@@ -1491,6 +1734,16 @@ pub struct Governance {
     pub is_finalizing_disburse_maturity: ::core::option::Option<bool>,
     #[prost(message, optional, tag = "26")]
     pub maturity_modulation: ::core::option::Option<governance::MaturityModulation>,
+    /// The active treasury disbursement schedules, keyed by their IDs.
+    #[prost(btree_map = "uint64, message", tag = "27")]
+    pub treasury_disbursement_schedules:
+        ::prost::alloc::collections::BTreeMap<u64, TreasuryDisbursementSchedule>,
+    /// The scheduled treasury payouts of the past 7 days, oldest first. Unlike
+    /// the payouts of a schedule, these are kept when the schedule is completed or
+    /// cancelled, so that they keep counting towards the 7-day upper bound on
+    /// treasury transfers.
+    #[prost(message, repeated, tag = "28")]
+    pub recent_treasury_payouts: ::prost::alloc::vec::Vec<TreasuryPayout>,
 }
 /// Nested message and enum types in `Governance`.
 pub mod governance {
@@ -1763,6 +2016,34 @@ pub mod governance {
         }
     }
 }
+/// Request message for 'list_treasury_disbursement_schedules'.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTreasuryDisbursementSchedulesRequest {}
+/// Response message for 'list_treasury_disbursement_schedules'.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListTreasuryDisbursementSchedulesResponse {
+    /// The active schedules, ordered by ID.
+    #[prost(message, repeated, tag = "1")]
+    pub schedules: ::prost::alloc::vec::Vec<TreasuryDisbursementSchedule>,
+}
+/// Request message for 'list_known_neurons'.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListKnownNeuronsRequest {}
+/// Response message for 'list_known_neurons'.
+#[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListKnownNeuronsResponse {
+    /// The known neurons, ordered by name.
+    #[prost(message, repeated, tag = "1")]
+    pub known_neurons: ::prost::alloc::vec::Vec<KnownNeuron>,
+}
 /// Request message for 'get_metadata'.
 #[derive(candid::CandidType, candid::Deserialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -19,6 +19,12 @@ use crate::{
             RegisterDappCanistersRequest, RegisterDappCanistersResponse, SetDappControllersRequest,
             SetDappControllersResponse,
         },
+        sns_swap_types::{
+            open_follow_on_round_response, refund_follow_on_round_tokens_response,
+            NeuronBasketConstructionParameters, OpenFollowOnRoundRequest,
+            OpenFollowOnRoundResponse, RefundFollowOnRoundTokensRequest,
+            RefundFollowOnRoundTokensResponse,
+        },
         v1::{
             claim_swap_neurons_response::SwapNeuron,
             get_neuron_response, get_proposal_response,
@@ -28,6 +34,7 @@ use crate::{
                 MaturityModulation, NeuronInFlightCommand, SnsMetadata, UpgradeInProgress, Version,
             },
             governance_error::ErrorType,
+            launch_follow_on_swap::TokenSource,
             manage_neuron::{
                 self,
                 claim_or_refresh::{By, MemoAndController},
@@ -41,34 +48,41 @@ use crate::{
             proposal::Action,
             proposal_data::ActionAuxiliary as ActionAuxiliaryPb,
             transfer_sns_treasury_funds::TransferFrom,
-            Account as AccountProto, Ballot, ClaimSwapNeuronsError, ClaimSwapNeuronsRequest,
-            ClaimSwapNeuronsResponse, ClaimedSwapNeuronStatus, DefaultFollowees,
-            DeregisterDappCanisters, DisburseMaturityInProgress, Empty,
+            Account as AccountProto, Ballot, CancelTreasuryDisbursementSchedule,
+            ClaimSwapNeuronsError, ClaimSwapNeuronsRequest, ClaimSwapNeuronsResponse,
+            ClaimedSwapNeuronStatus, CreateTreasuryDisbursementSchedule, DefaultFollowees,
+            DeregisterDappCanisters, DeregisterKnownNeuron, DisburseMaturityInProgress, Empty,
             ExecuteGenericNervousSystemFunction, FailStuckUpgradeInProgressRequest,
             FailStuckUpgradeInProgressResponse, GetMaturityModulationRequest,
             GetMaturityModulationResponse, GetMetadataRequest, GetMetadataResponse, GetMode,
             GetModeResponse, GetNeuron, GetNeuronResponse, GetProposal, GetProposalResponse,
             GetSnsInitializationParametersRequest, GetSnsInitializationParametersResponse,
-            Governance as GovernanceProto, GovernanceError, ListNervousSystemFunctionsResponse,
-            ListNeurons, ListNeuronsResponse, ListProposals, ListProposalsResponse,
-            ManageDappCanisterSettings, ManageLedgerParameters, ManageNeuron, ManageNeuronResponse,
-            ManageSnsMetadata, MintSnsTokens, NervousSystemFunction, NervousSystemParameters,
-            Neuron, NeuronId, NeuronPermission, NeuronPermissionList, NeuronPermissionType,
-            Proposal, ProposalData, ProposalDecisionStatus, ProposalId, ProposalRewardStatus,
-            RegisterDappCanisters, RewardEvent, Tally, TransferSnsTreasuryFunds,
-            UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote, WaitForQuietState,
+            Governance as GovernanceProto, GovernanceError, KnownNeuron, LaunchFollowOnSwap,
+            ListKnownNeuronsResponse, ListNervousSystemFunctionsResponse, ListNeurons,
+            ListNeuronsResponse, ListProposals, ListProposalsResponse,
+            ListTreasuryDisbursementSchedulesResponse, ManageDappCanisterSettings,
+            ManageLedgerParameters, ManageNeuron, ManageNeuronResponse, ManageSnsMetadata,
+            MintSnsTokens, NervousSystemFunction, NervousSystemParameters, Neuron, NeuronId,
+            NeuronPermission, NeuronPermissionList, NeuronPermissionType, Proposal, ProposalData,
+            ProposalDecisionStatus, ProposalId, ProposalRewardStatus, RegisterDappCanisters,
+            RewardEvent, Tally, TransferSnsTreasuryFunds, TreasuryDisbursementSchedule,
+            TreasuryPayout, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Vote,
+            WaitForQuietState,
         },
     },
     proposal::{
         get_action_auxiliary,
+        launch_follow_on_swap_amount_is_small_enough_at_execution_time_or_err,
+        scheduled_treasury_payout_is_small_enough_or_err,
         transfer_sns_treasury_funds_amount_is_small_enough_at_execution_time_or_err,
-        validate_and_render_proposal, ValidGenericNervousSystemFunction, MAX_LIST_PROPOSAL_RESULTS,
-        MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS,
+        validate_and_render_proposal, ActionAuxiliary, ValidGenericNervousSystemFunction,
+        MAX_LIST_PROPOSAL_RESULTS, MAX_NUMBER_OF_PROPOSALS_WITH_BALLOTS,
     },
     sns_upgrade::{
         get_all_sns_canisters, get_running_version, get_upgrade_params, get_wasm, SnsCanisterType,
         UpgradeSnsParams,
     },
+    treasury::prune_recent_treasury_payouts,
     types::{
         function_id_to_proposal_criticality, is_registered_function_id, Environment,
         HeapGrowthPotential, LedgerUpdateLock,
@@ -379,6 +393,20 @@ impl GovernanceProto {
         index
     }
 
+    /// Builds an index that maps the names of known neurons to their Neuron IDs.
+    pub fn build_known_neuron_name_to_id_index(
+        neurons: &BTreeMap<String, Neuron>,
+    ) -> BTreeMap<String, NeuronId> {
+        neurons
+            .values()
+            .filter_map(|neuron| {
+                let name = neuron.known_neuron_data.as_ref()?.name.clone();
+                let neuron_id = neuron.id.as_ref().expect("Neuron must have a NeuronId");
+                Some((name, neuron_id.clone()))
+            })
+            .collect()
+    }
+
     pub fn root_canister_id_or_panic(&self) -> CanisterId {
         CanisterId::unchecked_from_principal(self.root_canister_id.expect("No root_canister_id."))
     }
@@ -679,6 +707,12 @@ pub struct Governance {
     /// is saved and restored.
     pub principal_to_neuron_ids_index: BTreeMap<PrincipalId, HashSet<NeuronId>>,
 
+    /// Maps the names of known neurons to their Neuron IDs.
+    ///
+    /// This is a cached index and will be removed and recreated when the state
+    /// is saved and restored.
+    pub known_neuron_name_to_id_index: BTreeMap<String, NeuronId>,
+
     /// The timestamp, in seconds since the unix epoch, of the "closest"
     /// open proposal's deadline tracked by the governance (i.e., the deadline that will be
     /// reached first).
@@ -745,6 +779,7 @@ impl Governance {
             cmc,
             function_followee_index: BTreeMap::new(),
             principal_to_neuron_ids_index: BTreeMap::new(),
+            known_neuron_name_to_id_index: BTreeMap::new(),
             closest_proposal_deadline_timestamp_seconds: 0,
             latest_gc_timestamp_seconds: 0,
             latest_gc_num_proposals: 0,
@@ -805,6 +840,8 @@ impl Governance {
         self.principal_to_neuron_ids_index = self
             .proto
             .build_principal_to_neuron_ids_index(&self.proto.neurons);
+        self.known_neuron_name_to_id_index =
+            GovernanceProto::build_known_neuron_name_to_id_index(&self.proto.neurons);
     }
 
     /// Computes the NeuronId or returns a GovernanceError if a neuron with this ID already exists.
@@ -912,7 +949,8 @@ impl Governance {
     }
 
     /// Adds a neuron to the list of neurons and updates the indices
-    /// `principal_to_neuron_ids_index` and `function_followee_index`.
+    /// `principal_to_neuron_ids_index`, `function_followee_index` and
+    /// `known_neuron_name_to_id_index`.
     ///
     /// Preconditions:
     /// - the heap can still grow
@@ -952,13 +990,19 @@ impl Governance {
             &neuron,
         );
 
+        if let Some(known_neuron_data) = &neuron.known_neuron_data {
+            self.known_neuron_name_to_id_index
+                .insert(known_neuron_data.name.clone(), neuron_id.clone());
+        }
+
         self.proto.neurons.insert(neuron_id.to_string(), neuron);
 
         Ok(())
     }
 
     /// Removes a neuron from the list of neurons and updates the indices
-    /// `principal_to_neuron_ids_index` and `function_followee_index`.
+    /// `principal_to_neuron_ids_index`, `function_followee_index` and
+    /// `known_neuron_name_to_id_index`.
     ///
     /// Preconditions:
     /// - the given `neuron_id` exists in `self.proto.neurons`
@@ -987,6 +1031,11 @@ impl Governance {
             &neuron,
         );
 
+        if let Some(known_neuron_data) = &neuron.known_neuron_data {
+            self.known_neuron_name_to_id_index
+                .remove(&known_neuron_data.name);
+        }
+
         self.proto.neurons.remove(&neuron_id.to_string());
 
         Ok(())
@@ -1073,6 +1122,22 @@ impl Governance {
         }
     }
 
+    /// Returns the known neurons, i.e., the neurons that were given a name by a
+    /// RegisterKnownNeuron proposal, ordered by name.
+    pub fn list_known_neurons(&self) -> ListKnownNeuronsResponse {
+        let known_neurons = self
+            .known_neuron_name_to_id_index
+            .values()
+            .filter_map(|neuron_id| self.proto.neurons.get(&neuron_id.to_string()))
+            .map(|neuron| KnownNeuron {
+                id: neuron.id.clone(),
+                known_neuron_data: neuron.known_neuron_data.clone(),
+            })
+            .collect();
+
+        ListKnownNeuronsResponse { known_neurons }
+    }
+
     /// Disburse the stake of a neuron.
     ///
     /// This causes the stake of a neuron to be disbursed to the provided
@@ -1322,6 +1387,7 @@ impl Governance {
             auto_stake_maturity: parent_neuron.auto_stake_maturity,
             vesting_period_seconds: None,
             disburse_maturity_in_progress: vec![],
+            known_neuron_data: None,
         };
 
         // Add the child neuron's id to the set of neurons with ongoing operations.
@@ -1828,6 +1894,20 @@ impl Governance {
         }
     }
 
+    /// Returns the treasury disbursement schedules that are still paying out.
+    pub fn list_treasury_disbursement_schedules(
+        &self,
+    ) -> ListTreasuryDisbursementSchedulesResponse {
+        ListTreasuryDisbursementSchedulesResponse {
+            schedules: self
+                .proto
+                .treasury_disbursement_schedules
+                .values()
+                .cloned()
+                .collect(),
+        }
+    }
+
     /// Returns a list of all existing nervous system functions
     pub fn list_nervous_system_functions(&self) -> ListNervousSystemFunctionsResponse {
         let functions = Action::native_functions()
@@ -2073,6 +2153,28 @@ impl Governance {
                 self.perform_manage_dapp_canister_settings(manage_dapp_canister_settings)
                     .await
             }
+            Action::LaunchFollowOnSwap(launch_follow_on_swap) => {
+                let action_auxiliary =
+                    get_action_auxiliary(&self.proto.proposals, ProposalId { id: proposal_id });
+                self.perform_launch_follow_on_swap(
+                    proposal_id,
+                    action_auxiliary,
+                    launch_follow_on_swap,
+                )
+                .await
+            }
+            Action::CreateTreasuryDisbursementSchedule(create) => {
+                self.perform_create_treasury_disbursement_schedule(proposal_id, create)
+            }
+            Action::CancelTreasuryDisbursementSchedule(cancel) => {
+                self.perform_cancel_treasury_disbursement_schedule(cancel)
+            }
+            Action::RegisterKnownNeuron(known_neuron) => {
+                self.perform_register_known_neuron(known_neuron)
+            }
+            Action::DeregisterKnownNeuron(deregister_known_neuron) => {
+                self.perform_deregister_known_neuron(deregister_known_neuron)
+            }
             // This should not be possible, because Proposal validation is performed when
             // a proposal is first made.
             Action::Unspecified(_) => Err(GovernanceError::new_with_message(
@@ -2591,9 +2693,23 @@ impl Governance {
             transfer,
             valuation?,
             self.proto.proposals.values(),
+            &self.proto.recent_treasury_payouts,
             self.env.now(),
         )?;
 
+        self.transfer_from_treasury(transfer, None)
+            .await
+            .map(|_| ())
+    }
+
+    /// Transfers funds from the treasury as described by `transfer`, without checking any limits.
+    /// If `created_at_time_nanos` is set, the ledger deduplicates retries of the transfer.
+    /// Returns the block height of the transfer.
+    async fn transfer_from_treasury(
+        &self,
+        transfer: &TransferSnsTreasuryFunds,
+        created_at_time_nanos: Option<u64>,
+    ) -> Result<u64, GovernanceError> {
         let to = Account {
             owner: transfer
                 .to_principal
@@ -2604,53 +2720,198 @@ impl Governance {
                     .expect("Couldn't transform transfer.subaccount to Subaccount")
             }),
         };
-        match transfer.from_treasury() {
-            TransferFrom::IcpTreasury => self
-                .nns_ledger
-                .transfer_funds(
-                    transfer.amount_e8s,
-                    NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
-                    None,
-                    to,
-                    transfer.memo.unwrap_or(0),
-                )
-                .await
-                .map(|_| ())
-                .map_err(|e| {
-                    GovernanceError::new_with_message(
-                        ErrorType::External,
-                        format!("Error making ICP treasury transfer: {}", e),
-                    )
-                }),
+        let (ledger, fee_e8s, from_subaccount, treasury_name) = match transfer.from_treasury() {
+            TransferFrom::IcpTreasury => (
+                &self.nns_ledger,
+                NNS_DEFAULT_TRANSFER_FEE.get_e8s(),
+                None,
+                "ICP",
+            ),
             TransferFrom::SnsTokenTreasury => {
-                let transaction_fee_e8s = self.transaction_fee_e8s_or_panic();
                 // See ic_sns_init::distributions::FractionalDeveloperVotingPower.insert_treasury_accounts
                 let treasury_subaccount = compute_distribution_subaccount_bytes(
                     self.env.canister_id().get(),
                     TREASURY_SUBACCOUNT_NONCE,
                 );
-                self.ledger
-                    .transfer_funds(
+                (
+                    &self.ledger,
+                    self.transaction_fee_e8s_or_panic(),
+                    Some(treasury_subaccount),
+                    "SNS Token",
+                )
+            }
+            TransferFrom::Unspecified => {
+                return Err(GovernanceError::new_with_message(
+                    ErrorType::PreconditionFailed,
+                    "Invalid 'from_treasury' in transfer.",
+                ))
+            }
+        };
+        let memo = transfer.memo.unwrap_or(0);
+        let result = match created_at_time_nanos {
+            Some(created_at_time_nanos) => {
+                ledger
+                    .transfer_funds_deduplicated(
                         transfer.amount_e8s,
-                        transaction_fee_e8s,
-                        Some(treasury_subaccount),
+                        fee_e8s,
+                        from_subaccount,
                         to,
-                        transfer.memo.unwrap_or(0),
+                        memo,
+                        created_at_time_nanos,
                     )
                     .await
-                    .map(|_| ())
-                    .map_err(|e| {
-                        GovernanceError::new_with_message(
-                            ErrorType::External,
-                            format!("Error making SNS Token treasury transfer: {}", e),
-                        )
-                    })
             }
-            TransferFrom::Unspecified => Err(GovernanceError::new_with_message(
-                ErrorType::PreconditionFailed,
-                "Invalid 'from_treasury' in transfer.",
-            )),
+            None => {
+                ledger
+                    .transfer_funds(transfer.amount_e8s, fee_e8s, from_subaccount, to, memo)
+                    .await
+            }
+        };
+        result.map_err(|e| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Error making {} treasury transfer: {}", treasury_name, e),
+            )
+        })
+    }
+
+    /// Starts paying out a treasury disbursement schedule. The schedule's ID is the ID of the
+    /// proposal that created it. Payouts are made by `maybe_make_scheduled_treasury_payout`.
+    fn perform_create_treasury_disbursement_schedule(
+        &mut self,
+        proposal_id: u64,
+        create: CreateTreasuryDisbursementSchedule,
+    ) -> Result<(), GovernanceError> {
+        let schedule = TreasuryDisbursementSchedule::new(proposal_id, create, self.env.now());
+        log!(
+            INFO,
+            "{}Created treasury disbursement schedule {} with first payout at {:?}.",
+            log_prefix(),
+            proposal_id,
+            schedule.next_payout_timestamp_seconds(),
+        );
+        self.proto
+            .treasury_disbursement_schedules
+            .insert(proposal_id, schedule);
+        Ok(())
+    }
+
+    /// Stops a treasury disbursement schedule. Payouts that were already made are not affected.
+    fn perform_cancel_treasury_disbursement_schedule(
+        &mut self,
+        cancel: CancelTreasuryDisbursementSchedule,
+    ) -> Result<(), GovernanceError> {
+        let schedule_id = cancel.schedule_id.ok_or_else(|| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                "Expected CancelTreasuryDisbursementSchedule to have a schedule_id",
+            )
+        })?;
+        let schedule = self
+            .proto
+            .treasury_disbursement_schedules
+            .remove(&schedule_id)
+            .ok_or_else(|| {
+                GovernanceError::new_with_message(
+                    ErrorType::NotFound,
+                    format!(
+                        "There is no treasury disbursement schedule with ID {}. Maybe it has \
+                         already been completed or cancelled?",
+                        schedule_id
+                    ),
+                )
+            })?;
+        log!(
+            INFO,
+            "{}Cancelled treasury disbursement schedule {} after {} payouts.",
+            log_prefix(),
+            schedule_id,
+            schedule.payouts.len(),
+        );
+        Ok(())
+    }
+
+    /// Gives a neuron a name (and optionally a description), replacing its previous known
+    /// neuron data, if any.
+    ///
+    /// The name must not already belong to another known neuron. This was checked when the
+    /// proposal was made, but another RegisterKnownNeuron proposal might have been executed
+    /// since then.
+    fn perform_register_known_neuron(
+        &mut self,
+        known_neuron: KnownNeuron,
+    ) -> Result<(), GovernanceError> {
+        let KnownNeuron {
+            id,
+            known_neuron_data,
+        } = known_neuron;
+        let neuron_id = id.ok_or_else(|| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                "Expected RegisterKnownNeuron to have a neuron id",
+            )
+        })?;
+        let known_neuron_data = known_neuron_data.ok_or_else(|| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                "Expected RegisterKnownNeuron to have known_neuron_data",
+            )
+        })?;
+
+        if let Some(owner_id) = self
+            .known_neuron_name_to_id_index
+            .get(&known_neuron_data.name)
+        {
+            if *owner_id != neuron_id {
+                return Err(GovernanceError::new_with_message(
+                    ErrorType::PreconditionFailed,
+                    format!(
+                        "The name {:?} already belongs to known neuron {}",
+                        known_neuron_data.name, owner_id
+                    ),
+                ));
+            }
         }
+
+        let neuron = self.get_neuron_mut(&neuron_id)?;
+        let name = known_neuron_data.name.clone();
+        let old_known_neuron_data = neuron.known_neuron_data.replace(known_neuron_data);
+
+        if let Some(old_known_neuron_data) = old_known_neuron_data {
+            self.known_neuron_name_to_id_index
+                .remove(&old_known_neuron_data.name);
+        }
+        self.known_neuron_name_to_id_index.insert(name, neuron_id);
+
+        Ok(())
+    }
+
+    /// Removes the name and description of a known neuron.
+    fn perform_deregister_known_neuron(
+        &mut self,
+        deregister_known_neuron: DeregisterKnownNeuron,
+    ) -> Result<(), GovernanceError> {
+        let neuron_id = deregister_known_neuron.id.ok_or_else(|| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                "Expected DeregisterKnownNeuron to have a neuron id",
+            )
+        })?;
+
+        let known_neuron_data = self
+            .get_neuron_mut(&neuron_id)?
+            .known_neuron_data
+            .take()
+            .ok_or_else(|| {
+                GovernanceError::new_with_message(
+                    ErrorType::PreconditionFailed,
+                    format!("Neuron {} is not a known neuron", neuron_id),
+                )
+            })?;
+        self.known_neuron_name_to_id_index
+            .remove(&known_neuron_data.name);
+
+        Ok(())
     }
 
     async fn perform_mint_sns_tokens(
@@ -2856,6 +3117,260 @@ impl Governance {
             )
     }
 
+    /// Transfers the SNS tokens offered by a follow-on swap round to the swap canister, and
+    /// asks the swap canister to open the round.
+    ///
+    /// The tokens are subject to the same 7-day upper bound as the equivalent
+    /// TransferSnsTreasuryFunds or MintSnsTokens proposal, which is checked again right before
+    /// they are transferred. If opening the round fails after the tokens were transferred, the
+    /// swap canister is asked to return them to where they came from.
+    async fn perform_launch_follow_on_swap(
+        &mut self,
+        proposal_id: u64,
+        action_auxiliary: Result<ActionAuxiliary, GovernanceError>,
+        launch_follow_on_swap: LaunchFollowOnSwap,
+    ) -> Result<(), GovernanceError> {
+        let swap_canister_id = self.proto.swap_canister_id_or_panic();
+        let sns_token_e8s = launch_follow_on_swap.sns_token_e8s.ok_or_else(|| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                "Expected LaunchFollowOnSwap to have an sns_token_e8s",
+            )
+        })?;
+
+        launch_follow_on_swap_amount_is_small_enough_at_execution_time_or_err(
+            &launch_follow_on_swap,
+            swap_canister_id,
+            action_auxiliary?,
+            self.proto.proposals.values(),
+            &self.proto.recent_treasury_payouts,
+            self.env.now(),
+        )?;
+
+        let to = Account {
+            owner: swap_canister_id.get().0,
+            subaccount: None,
+        };
+        let memo = proposal_id;
+
+        // How the swap canister returns the tokens if the round cannot be opened: minted tokens
+        // are burned, i.e., returned to the minting account, and treasury tokens are returned to
+        // the treasury.
+        let refund = match launch_follow_on_swap.token_source() {
+            TokenSource::Mint => {
+                self.ledger
+                    .transfer_funds(sns_token_e8s, 0, None, to, memo)
+                    .await?;
+                RefundFollowOnRoundTokensRequest {
+                    sns_proposal_id: Some(proposal_id),
+                    amount_e8s: Some(sns_token_e8s),
+                    fee_e8s: Some(0),
+                    to_subaccount: None,
+                }
+            }
+            TokenSource::Treasury => {
+                let transaction_fee_e8s = self.transaction_fee_e8s_or_panic();
+                let treasury_subaccount = compute_distribution_subaccount_bytes(
+                    self.env.canister_id().get(),
+                    TREASURY_SUBACCOUNT_NONCE,
+                );
+                self.ledger
+                    .transfer_funds(
+                        sns_token_e8s,
+                        transaction_fee_e8s,
+                        Some(treasury_subaccount),
+                        to,
+                        memo,
+                    )
+                    .await?;
+                RefundFollowOnRoundTokensRequest {
+                    sns_proposal_id: Some(proposal_id),
+                    amount_e8s: Some(sns_token_e8s.saturating_sub(transaction_fee_e8s)),
+                    fee_e8s: Some(transaction_fee_e8s),
+                    to_subaccount: Some(treasury_subaccount.to_vec()),
+                }
+            }
+            TokenSource::Unspecified => {
+                return Err(GovernanceError::new_with_message(
+                    ErrorType::InvalidProposal,
+                    "Expected LaunchFollowOnSwap to have a token_source",
+                ));
+            }
+        };
+
+        let Err(err) = self
+            .open_follow_on_round(swap_canister_id, proposal_id, &launch_follow_on_swap)
+            .await
+        else {
+            return Ok(());
+        };
+
+        let error_message = match self
+            .refund_follow_on_round_tokens(swap_canister_id, refund)
+            .await
+        {
+            Ok(block_height) => format!(
+                "{} The SNS tokens sent to the swap canister were returned (block {}).",
+                err.error_message, block_height,
+            ),
+            Err(refund_err) => {
+                log!(
+                    ERROR,
+                    "{}Unable to return the {} SNS e8s of proposal {} from the swap canister: {}",
+                    log_prefix(),
+                    sns_token_e8s,
+                    proposal_id,
+                    refund_err,
+                );
+                format!(
+                    "{} Returning the SNS tokens sent to the swap canister failed as well, so \
+                     they stay with the swap canister: {}",
+                    err.error_message, refund_err.error_message,
+                )
+            }
+        };
+        Err(GovernanceError {
+            error_message,
+            ..err
+        })
+    }
+
+    /// Asks the swap canister to open the follow-on round described by `launch_follow_on_swap`.
+    async fn open_follow_on_round(
+        &self,
+        swap_canister_id: CanisterId,
+        proposal_id: u64,
+        launch_follow_on_swap: &LaunchFollowOnSwap,
+    ) -> Result<(), GovernanceError> {
+        let swap_start_timestamp_seconds = self.env.now().saturating_add(
+            launch_follow_on_swap
+                .start_delay_seconds
+                .unwrap_or_default(),
+        );
+        let swap_due_timestamp_seconds = swap_start_timestamp_seconds
+            .saturating_add(launch_follow_on_swap.duration_seconds.unwrap_or_default());
+        let request = OpenFollowOnRoundRequest {
+            sns_proposal_id: Some(proposal_id),
+            sns_token_e8s: launch_follow_on_swap.sns_token_e8s,
+            min_participants: launch_follow_on_swap.min_participants,
+            min_direct_participation_icp_e8s: launch_follow_on_swap
+                .min_direct_participation_icp_e8s,
+            max_direct_participation_icp_e8s: launch_follow_on_swap
+                .max_direct_participation_icp_e8s,
+            min_participant_icp_e8s: launch_follow_on_swap.min_participant_icp_e8s,
+            max_participant_icp_e8s: launch_follow_on_swap.max_participant_icp_e8s,
+            swap_start_timestamp_seconds: Some(swap_start_timestamp_seconds),
+            swap_due_timestamp_seconds: Some(swap_due_timestamp_seconds),
+            neuron_basket_construction_parameters: Some(NeuronBasketConstructionParameters {
+                count: launch_follow_on_swap
+                    .neuron_basket_count
+                    .unwrap_or_default(),
+                dissolve_delay_interval_seconds: launch_follow_on_swap
+                    .neuron_basket_dissolve_delay_interval_seconds
+                    .unwrap_or_default(),
+            }),
+        };
+        let payload = candid::Encode!(&request).map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::InvalidProposal,
+                format!("Could not encode OpenFollowOnRoundRequest: {err:?}"),
+            )
+        })?;
+        let reply = self
+            .env
+            .call_canister(swap_canister_id, "open_follow_on_round", payload)
+            .await
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Canister method call failed: {err:?}"),
+                )
+            })?;
+
+        use open_follow_on_round_response::Result as OpenResult;
+        match candid::Decode!(&reply, OpenFollowOnRoundResponse) {
+            Ok(OpenFollowOnRoundResponse {
+                result: Some(OpenResult::Ok(ok)),
+            }) => {
+                log!(
+                    INFO,
+                    "Proposal {} opened follow-on swap round {:?}.",
+                    proposal_id,
+                    ok.round
+                );
+                Ok(())
+            }
+            Ok(OpenFollowOnRoundResponse {
+                result: Some(OpenResult::Err(err)),
+            }) => Err(GovernanceError::new_with_message(
+                ErrorType::External,
+                format!(
+                    "The swap canister refused to open the follow-on round: {}",
+                    err.description.unwrap_or_default()
+                ),
+            )),
+            Ok(OpenFollowOnRoundResponse { result: None }) => {
+                Err(GovernanceError::new_with_message(
+                    ErrorType::External,
+                    "The swap canister replied to open_follow_on_round without a result.",
+                ))
+            }
+            Err(error) => Err(GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Could not decode OpenFollowOnRoundResponse: {error}"),
+            )),
+        }
+    }
+
+    /// Asks the swap canister to return the SNS tokens of a follow-on round that could not be
+    /// opened. Returns the block height of the transfer.
+    async fn refund_follow_on_round_tokens(
+        &self,
+        swap_canister_id: CanisterId,
+        request: RefundFollowOnRoundTokensRequest,
+    ) -> Result<u64, GovernanceError> {
+        let payload = candid::Encode!(&request).map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::UnreachableCode,
+                format!("Could not encode RefundFollowOnRoundTokensRequest: {err:?}"),
+            )
+        })?;
+        let reply = self
+            .env
+            .call_canister(swap_canister_id, "refund_follow_on_round_tokens", payload)
+            .await
+            .map_err(|err| {
+                GovernanceError::new_with_message(
+                    ErrorType::External,
+                    format!("Canister method call failed: {err:?}"),
+                )
+            })?;
+
+        use refund_follow_on_round_tokens_response::Result as RefundResult;
+        match candid::Decode!(&reply, RefundFollowOnRoundTokensResponse) {
+            Ok(RefundFollowOnRoundTokensResponse {
+                result:
+                    Some(RefundResult::Ok(refund_follow_on_round_tokens_response::Ok {
+                        block_height: Some(block_height),
+                    })),
+            }) => Ok(block_height),
+            Ok(RefundFollowOnRoundTokensResponse {
+                result: Some(RefundResult::Err(err)),
+            }) => Err(GovernanceError::new_with_message(
+                ErrorType::External,
+                err.description.unwrap_or_default(),
+            )),
+            Ok(response) => Err(GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Unexpected RefundFollowOnRoundTokensResponse: {response:?}"),
+            )),
+            Err(error) => Err(GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Could not decode RefundFollowOnRoundTokensResponse: {error}"),
+            )),
+        }
+    }
+
     // Returns an option with the NervousSystemParameters
     fn nervous_system_parameters(&self) -> Option<&NervousSystemParameters> {
         self.proto.parameters.as_ref()
@@ -3776,6 +4291,7 @@ impl Governance {
             auto_stake_maturity: None,
             vesting_period_seconds: None,
             disburse_maturity_in_progress: vec![],
+            known_neuron_data: None,
         };
 
         // This also verifies that there are not too many neurons already.
@@ -3952,6 +4468,7 @@ impl Governance {
                 auto_stake_maturity: neuron_recipe.construct_auto_staking_maturity(),
                 vesting_period_seconds: None,
                 disburse_maturity_in_progress: vec![],
+                known_neuron_data: None,
             };
 
             // Add the neuron to the various data structures and indexes to support neurons. This
@@ -4513,6 +5030,116 @@ impl Governance {
         }
     }
 
+    /// Makes at most one payout of a treasury disbursement schedule that is due, and removes
+    /// schedules whose payouts are complete.
+    ///
+    /// Only one payout is in flight at any time. A payout that fails (e.g., because it would
+    /// cause the 7 day upper bound on treasury transfers to be exceeded) is retried later.
+    async fn maybe_make_scheduled_treasury_payout(&mut self) {
+        // Only make one payout at a time. Unlike a flag in self.proto, this does not survive
+        // upgrades, and it is released when the payout returns, regardless of how.
+        thread_local! {
+            static IN_PROGRESS_SCHEDULE_ID: RefCell<Option<u64>> = const { RefCell::new(None) };
+        }
+
+        let now_seconds = self.env.now();
+        prune_recent_treasury_payouts(&mut self.proto.recent_treasury_payouts, now_seconds);
+
+        let Some((schedule_id, transfer)) = self
+            .proto
+            .treasury_disbursement_schedules
+            .iter()
+            .find_map(|(id, schedule)| {
+                if !schedule.is_payout_due(now_seconds) {
+                    return None;
+                }
+                schedule.payout_transfer().map(|transfer| (*id, transfer))
+            })
+        else {
+            return;
+        };
+        let Ok(_release_on_drop) = acquire(&IN_PROGRESS_SCHEDULE_ID, schedule_id) else {
+            return;
+        };
+
+        let result = match scheduled_treasury_payout_is_small_enough_or_err(
+            &*self.env,
+            &self.proto,
+            &transfer,
+        )
+        .await
+        {
+            Ok(()) => {
+                // The schedule might have been cancelled while the upper bound was checked.
+                let Some(schedule) = self
+                    .proto
+                    .treasury_disbursement_schedules
+                    .get_mut(&schedule_id)
+                else {
+                    return;
+                };
+                let created_at_time_nanos = schedule.payout_created_at_time_nanos(self.env.now());
+                self.transfer_from_treasury(&transfer, Some(created_at_time_nanos))
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+
+        let now_seconds = self.env.now();
+        let result = result.map(|block_height| TreasuryPayout {
+            timestamp_seconds: Some(now_seconds),
+            amount_e8s: Some(transfer.amount_e8s),
+            block_height: Some(block_height),
+            from_treasury: transfer.from_treasury,
+        });
+        // Funds that left the treasury count towards the 7 day upper bound, even if the
+        // schedule is cancelled while the payout is in flight.
+        if let Ok(payout) = &result {
+            self.proto.recent_treasury_payouts.push(payout.clone());
+        }
+
+        // The schedule might have been cancelled while the payout was in flight.
+        let Some(schedule) = self
+            .proto
+            .treasury_disbursement_schedules
+            .get_mut(&schedule_id)
+        else {
+            log!(
+                INFO,
+                "{}Treasury disbursement schedule {} was cancelled during a payout: {:?}",
+                log_prefix(),
+                schedule_id,
+                result,
+            );
+            return;
+        };
+        match result {
+            Ok(payout) => schedule.record_payout(payout),
+            Err(err) => {
+                log!(
+                    ERROR,
+                    "{}Payout of treasury disbursement schedule {} failed: {}",
+                    log_prefix(),
+                    schedule_id,
+                    err,
+                );
+                schedule.record_failure(err.error_message, now_seconds);
+            }
+        }
+
+        if schedule.next_payout_timestamp_seconds().is_none() {
+            log!(
+                INFO,
+                "{}Treasury disbursement schedule {} is complete.",
+                log_prefix(),
+                schedule_id,
+            );
+            self.proto
+                .treasury_disbursement_schedules
+                .remove(&schedule_id);
+        }
+    }
+
     /// Garbage collect obsolete data from the governance canister.
     ///
     /// Current implementation only garbage collects proposals - not neurons.
@@ -4629,6 +5256,8 @@ impl Governance {
 
         self.maybe_move_staked_maturity();
 
+        self.maybe_make_scheduled_treasury_payout().await;
+
         self.maybe_gc();
     }
 
@@ -5341,6 +5970,8 @@ impl Governance {
     /// - the followees are not changed (it's easy to update followees
     ///   via `manage_neuron` and doing it here would require updating
     ///   `function_followee_index`)
+    /// - the known neuron data is not changed (doing it here would require
+    ///   updating `known_neuron_name_to_id_index`)
     #[cfg(feature = "test")]
     pub fn update_neuron(&mut self, neuron: Neuron) -> Result<(), GovernanceError> {
         let neuron_id = &neuron.id.as_ref().expect("Neuron must have a NeuronId");
@@ -5375,6 +6006,14 @@ impl Governance {
             ));
         }
 
+        // Must NOT clobber known neuron data.
+        if old_neuron.known_neuron_data != neuron.known_neuron_data {
+            return Err(GovernanceError::new_with_message(
+                ErrorType::PreconditionFailed,
+                "Cannot update neuron's known neuron data via update_neuron.".to_string(),
+            ));
+        }
+
         // Now that neuron has been validated, update old_neuron.
         *old_neuron = neuron;
 
@@ -5574,8 +6213,8 @@ mod tests {
             governance::SnsMetadata,
             manage_neuron_response,
             nervous_system_function::{FunctionType, GenericNervousSystemFunction},
-            neuron, Account as AccountProto, Motion, NeuronPermissionType, ProposalData,
-            ProposalId, Tally, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion,
+            neuron, Account as AccountProto, KnownNeuronData, Motion, NeuronPermissionType,
+            ProposalData, ProposalId, Tally, UpgradeSnsControlledCanister, UpgradeSnsToNextVersion,
             VotingRewardsParameters, WaitForQuietState,
         },
         reward,
@@ -8606,6 +9245,80 @@ mod tests {
         NeuronId::from(compute_neuron_staking_subaccount_bytes(controller, 0))
     }
 
+    #[test]
+    fn test_register_and_deregister_known_neurons() {
+        // Step 1: Prepare the world.
+        let neuron_id_1 = test_neuron_id(*TEST_NEURON_1_OWNER_PRINCIPAL);
+        let neuron_id_2 = test_neuron_id(*TEST_NEURON_2_OWNER_PRINCIPAL);
+        let mut governance_proto = basic_governance_proto();
+        for neuron_id in [&neuron_id_1, &neuron_id_2] {
+            governance_proto.neurons.insert(
+                neuron_id.to_string(),
+                Neuron {
+                    id: Some(neuron_id.clone()),
+                    ..Default::default()
+                },
+            );
+        }
+        let mut governance = default_governance_with_proto(governance_proto);
+        let known_neuron = |neuron_id: &NeuronId, name: &str| KnownNeuron {
+            id: Some(neuron_id.clone()),
+            known_neuron_data: Some(KnownNeuronData {
+                name: name.to_string(),
+                description: None,
+            }),
+        };
+
+        // Step 2: Register both neurons, then try to reuse a name.
+        assert_is_ok!(governance.perform_register_known_neuron(known_neuron(&neuron_id_1, "Zed")));
+        assert_is_ok!(governance.perform_register_known_neuron(known_neuron(&neuron_id_2, "Amy")));
+        let err = governance
+            .perform_register_known_neuron(known_neuron(&neuron_id_2, "Zed"))
+            .unwrap_err();
+        assert_eq!(err.error_type, ErrorType::PreconditionFailed as i32);
+
+        // Step 3: Known neurons are listed by name, and their data is part of the neurons.
+        assert_eq!(
+            governance.list_known_neurons().known_neurons,
+            vec![
+                known_neuron(&neuron_id_2, "Amy"),
+                known_neuron(&neuron_id_1, "Zed"),
+            ],
+        );
+        let listed_neurons = governance
+            .list_neurons(&ListNeurons {
+                of_principal: None,
+                limit: 10,
+                start_page_at: None,
+            })
+            .neurons;
+        assert!(listed_neurons
+            .iter()
+            .all(|neuron| neuron.known_neuron_data.is_some()));
+
+        // Step 4: Renaming a neuron frees its old name; deregistering frees its name.
+        assert_is_ok!(governance.perform_register_known_neuron(known_neuron(&neuron_id_2, "Bob")));
+        assert_is_ok!(
+            governance.perform_deregister_known_neuron(DeregisterKnownNeuron {
+                id: Some(neuron_id_1.clone()),
+            })
+        );
+        assert_eq!(
+            governance.list_known_neurons().known_neurons,
+            vec![known_neuron(&neuron_id_2, "Bob")],
+        );
+        assert_eq!(
+            governance.known_neuron_name_to_id_index,
+            btreemap! { "Bob".to_string() => neuron_id_2 },
+        );
+        let err = governance
+            .perform_deregister_known_neuron(DeregisterKnownNeuron {
+                id: Some(neuron_id_1),
+            })
+            .unwrap_err();
+        assert_eq!(err.error_type, ErrorType::PreconditionFailed as i32);
+    }
+
     #[test]
     fn test_stake_maturity_succeeds() {
         // Step 1: Prepare the world and parameters.
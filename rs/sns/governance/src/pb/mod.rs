@@ -2,5 +2,8 @@
 #[path = "../sns_root_types.rs"]
 pub mod sns_root_types;
 #[allow(clippy::all)]
+#[path = "../sns_swap_types.rs"]
+pub mod sns_swap_types;
+#[allow(clippy::all)]
 #[path = "../gen/ic_sns_governance.pb.v1.rs"]
 pub mod v1;
@@ -9,6 +9,7 @@ use crate::{
     pb::v1::{
         governance::{SnsMetadata, Version},
         governance_error::ErrorType,
+        launch_follow_on_swap::TokenSource,
         nervous_system_function::{FunctionType, GenericNervousSystemFunction},
         proposal,
         proposal::Action,
@@ -17,14 +18,17 @@ use crate::{
             TransferSnsTreasuryFundsActionAuxiliary,
         },
         transfer_sns_treasury_funds::TransferFrom,
-        DeregisterDappCanisters, ExecuteGenericNervousSystemFunction, Governance, GovernanceError,
-        LogVisibility, ManageDappCanisterSettings, ManageLedgerParameters, ManageSnsMetadata,
-        MintSnsTokens, Motion, NervousSystemFunction, NervousSystemParameters, Proposal,
-        ProposalData, ProposalDecisionStatus, ProposalId, ProposalRewardStatus,
-        RegisterDappCanisters, Tally, TransferSnsTreasuryFunds, UpgradeSnsControlledCanister,
-        UpgradeSnsToNextVersion, Valuation as ValuationPb, Vote,
+        CancelTreasuryDisbursementSchedule, CreateTreasuryDisbursementSchedule,
+        DeregisterDappCanisters, DeregisterKnownNeuron, ExecuteGenericNervousSystemFunction,
+        Governance, GovernanceError, KnownNeuron, LaunchFollowOnSwap, LogVisibility,
+        ManageDappCanisterSettings, ManageLedgerParameters, ManageSnsMetadata, MintSnsTokens,
+        Motion, NervousSystemFunction, NervousSystemParameters, Neuron, Proposal, ProposalData,
+        ProposalDecisionStatus, ProposalId, ProposalRewardStatus, RegisterDappCanisters, Tally,
+        TransferSnsTreasuryFunds, TreasuryDisbursementSchedule, TreasuryPayout,
+        UpgradeSnsControlledCanister, UpgradeSnsToNextVersion, Valuation as ValuationPb, Vote,
     },
     sns_upgrade::{get_upgrade_params, UpgradeSnsParams},
+    treasury::recent_treasury_payouts_e8s,
     types::Environment,
     validate_chars_count, validate_len, validate_required_field,
 };
@@ -35,7 +39,7 @@ use ic_canister_log::log;
 use ic_crypto_sha2::Sha256;
 use ic_nervous_system_common::{
     denominations_to_tokens, i2d, ledger::compute_distribution_subaccount_bytes, ledger_validation,
-    DEFAULT_TRANSFER_FEE, E8, ONE_DAY_SECONDS,
+    DEFAULT_TRANSFER_FEE, E8, ONE_DAY_SECONDS, ONE_YEAR_SECONDS,
 };
 use ic_nervous_system_proto::pb::v1::Percentage;
 use ic_protobuf::types::v1::CanisterInstallMode;
@@ -97,6 +101,30 @@ pub const EXECUTED_MINT_SNS_TOKENS_PROPOSAL_RETENTION_DURATION_SECONDS: u64 = 7
 /// and a few constant-size fields (e.g., compute and memory allocation).
 pub const MAX_INSTALL_CODE_WASM_AND_ARG_SIZE: usize = 2_000_000; // 2MB
 
+/// The bounds on the duration of a follow-on swap round. These match the
+/// bounds the swap canister enforces.
+pub const MIN_FOLLOW_ON_SWAP_DURATION_SECONDS: u64 = ONE_DAY_SECONDS;
+pub const MAX_FOLLOW_ON_SWAP_DURATION_SECONDS: u64 = 14 * ONE_DAY_SECONDS;
+
+/// The bounds on the parameters of a treasury disbursement schedule.
+pub const MAX_TREASURY_DISBURSEMENT_PERIODS: u64 = 120;
+pub const MIN_TREASURY_DISBURSEMENT_PERIOD_SECONDS: u64 = ONE_DAY_SECONDS;
+pub const MAX_TREASURY_DISBURSEMENT_PERIOD_SECONDS: u64 = ONE_YEAR_SECONDS;
+pub const MAX_TREASURY_DISBURSEMENT_CLIFF_SECONDS: u64 = 4 * ONE_YEAR_SECONDS;
+
+/// The longest a follow-on swap round may be scheduled after its proposal is executed.
+pub const MAX_FOLLOW_ON_SWAP_START_DELAY_SECONDS: u64 = 90 * ONE_DAY_SECONDS;
+
+/// The bounds on the number of SNS neurons each participant of a follow-on swap
+/// round receives. These match the bounds of the initial decentralization swap.
+pub const MIN_FOLLOW_ON_SWAP_NEURON_BASKET_COUNT: u64 = 2;
+pub const MAX_FOLLOW_ON_SWAP_NEURON_BASKET_COUNT: u64 = 10;
+
+/// The maximum number of bytes in the name of a known neuron.
+pub const KNOWN_NEURON_NAME_MAX_LEN: usize = 200;
+/// The maximum number of bytes in the description of a known neuron.
+pub const KNOWN_NEURON_DESCRIPTION_MAX_LEN: usize = 3000;
+
 impl Proposal {
     /// Returns whether a proposal is allowed to be submitted when
     /// the heap growth potential is low.
@@ -444,6 +472,43 @@ pub(crate) async fn validate_and_render_action(
         proposal::Action::ManageDappCanisterSettings(manage_dapp_canister_settings) => {
             validate_and_render_manage_dapp_canister_settings(manage_dapp_canister_settings)
         }
+        proposal::Action::LaunchFollowOnSwap(launch_follow_on_swap) => {
+            return validate_and_render_launch_follow_on_swap(
+                launch_follow_on_swap,
+                current_parameters,
+                env,
+                swap_canister_id,
+                sns_ledger_canister_id,
+                proposals,
+            )
+            .await;
+        }
+        proposal::Action::CreateTreasuryDisbursementSchedule(create) => {
+            return validate_and_render_create_treasury_disbursement_schedule(
+                create,
+                sns_transfer_fee_e8s,
+                env,
+                swap_canister_id,
+                sns_ledger_canister_id,
+                proposals,
+            )
+            .await;
+        }
+        proposal::Action::CancelTreasuryDisbursementSchedule(cancel) => {
+            validate_and_render_cancel_treasury_disbursement_schedule(
+                cancel,
+                &governance_proto.treasury_disbursement_schedules,
+            )
+        }
+        proposal::Action::RegisterKnownNeuron(known_neuron) => {
+            validate_and_render_register_known_neuron(known_neuron, &governance_proto.neurons)
+        }
+        proposal::Action::DeregisterKnownNeuron(deregister_known_neuron) => {
+            validate_and_render_deregister_known_neuron(
+                deregister_known_neuron,
+                &governance_proto.neurons,
+            )
+        }
     }
     .map(|rendering| (rendering, ActionAuxiliary::None))
 }
@@ -1670,6 +1735,463 @@ fn validate_and_render_manage_dapp_canister_settings(
     }
 }
 
+/// Validates and renders a proposal with action CreateTreasuryDisbursementSchedule.
+///
+/// Each payout is validated like a TransferSnsTreasuryFunds proposal of the same amount. In
+/// particular, a single payout must currently fit within the 7-day upper bound on treasury
+/// transfers. The bound is checked again, against a fresh valuation, when each payout is made.
+async fn validate_and_render_create_treasury_disbursement_schedule(
+    create: &CreateTreasuryDisbursementSchedule,
+    sns_transfer_fee_e8s: u64,
+    env: &dyn Environment,
+    swap_canister_id: CanisterId,
+    sns_ledger_canister_id: CanisterId,
+    proposals: impl Iterator<Item = &ProposalData>,
+) -> Result<(String, ActionAuxiliary), String> {
+    let mut defects = vec![];
+
+    if create.amount_per_period_e8s.is_none() {
+        defects.push("Must specify an amount_per_period_e8s.".to_string());
+    }
+    let number_of_periods = create.number_of_periods();
+    if !(1..=MAX_TREASURY_DISBURSEMENT_PERIODS).contains(&number_of_periods) {
+        defects.push(format!(
+            "number_of_periods ({number_of_periods}) must be between 1 and \
+             {MAX_TREASURY_DISBURSEMENT_PERIODS}."
+        ));
+    }
+    let period_seconds = create.period_seconds();
+    if !(MIN_TREASURY_DISBURSEMENT_PERIOD_SECONDS..=MAX_TREASURY_DISBURSEMENT_PERIOD_SECONDS)
+        .contains(&period_seconds)
+    {
+        defects.push(format!(
+            "period_seconds ({period_seconds}) must be between \
+             {MIN_TREASURY_DISBURSEMENT_PERIOD_SECONDS} and \
+             {MAX_TREASURY_DISBURSEMENT_PERIOD_SECONDS}."
+        ));
+    }
+    let cliff_seconds = create.cliff_seconds();
+    if cliff_seconds > MAX_TREASURY_DISBURSEMENT_CLIFF_SECONDS {
+        defects.push(format!(
+            "cliff_seconds ({cliff_seconds}) must be at most \
+             {MAX_TREASURY_DISBURSEMENT_CLIFF_SECONDS}."
+        ));
+    }
+    if !defects.is_empty() {
+        return Err(format!(
+            "CreateTreasuryDisbursementSchedule proposal was invalid for the following \
+             reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    // Every payout is a transfer like this one, except for its memo, which identifies the payout.
+    let amount_per_period_e8s = create.amount_per_period_e8s();
+    let payout = TransferSnsTreasuryFunds {
+        from_treasury: create.from_treasury,
+        amount_e8s: amount_per_period_e8s,
+        memo: None,
+        to_principal: create.to_principal,
+        to_subaccount: create.to_subaccount.clone(),
+    };
+    let (payout_rendering, _) = validate_and_render_transfer_sns_treasury_funds(
+        &payout,
+        sns_transfer_fee_e8s,
+        env,
+        swap_canister_id,
+        sns_ledger_canister_id,
+        proposals,
+    )
+    .await
+    .map_err(|err| {
+        format!("The payouts of the CreateTreasuryDisbursementSchedule proposal are invalid: {err}")
+    })?;
+
+    // Reuse the rendering of a single transfer, without its title and its placeholder memo.
+    let payout_rendering = payout_rendering
+        .lines()
+        .skip(1)
+        .filter(|line| !line.starts_with("## Memo:"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let display_total_amount_tokens = i2d(amount_per_period_e8s) * i2d(number_of_periods) / i2d(E8);
+    Ok((
+        format!(
+            r"# Proposal to create a treasury disbursement schedule:
+## Number of payouts: {number_of_periods}
+## Time between payouts: {period_seconds} seconds
+## First payout: {cliff_seconds} seconds after execution
+## Total amount: {display_total_amount_tokens:.8}
+# Each payout:
+{payout_rendering}"
+        ),
+        ActionAuxiliary::None,
+    ))
+}
+
+/// Validates and renders a proposal with action CancelTreasuryDisbursementSchedule.
+fn validate_and_render_cancel_treasury_disbursement_schedule(
+    cancel: &CancelTreasuryDisbursementSchedule,
+    schedules: &BTreeMap<u64, TreasuryDisbursementSchedule>,
+) -> Result<String, String> {
+    let schedule_id = cancel.schedule_id.ok_or_else(|| {
+        "CancelTreasuryDisbursementSchedule must specify a schedule_id.".to_string()
+    })?;
+    let schedule = schedules.get(&schedule_id).ok_or_else(|| {
+        format!("There is no active treasury disbursement schedule with ID {schedule_id}.")
+    })?;
+
+    let number_of_periods = schedule
+        .parameters
+        .as_ref()
+        .map(|parameters| parameters.number_of_periods())
+        .unwrap_or_default();
+    let payout_count = schedule.payouts.len() as u64;
+    Ok(format!(
+        r"# Proposal to cancel treasury disbursement schedule {schedule_id}:
+## Payouts made: {payout_count}
+## Payouts cancelled: {cancelled_count}",
+        cancelled_count = number_of_periods.saturating_sub(payout_count),
+    ))
+}
+
+/// Validates and renders a proposal with action RegisterKnownNeuron.
+fn validate_and_render_register_known_neuron(
+    known_neuron: &KnownNeuron,
+    neurons: &BTreeMap<String, Neuron>,
+) -> Result<String, String> {
+    let neuron_id = validate_required_field("id", &known_neuron.id)?;
+    let known_neuron_data =
+        validate_required_field("known_neuron_data", &known_neuron.known_neuron_data)?;
+
+    let mut defects = vec![];
+    if !neurons.contains_key(&neuron_id.to_string()) {
+        defects.push(format!("There is no neuron with ID {neuron_id}."));
+    }
+    if let Err(err) = validate_len(
+        "known_neuron_data.name",
+        &known_neuron_data.name,
+        1,
+        KNOWN_NEURON_NAME_MAX_LEN,
+    ) {
+        defects.push(err);
+    }
+    if let Some(description) = &known_neuron_data.description {
+        if let Err(err) = validate_len(
+            "known_neuron_data.description",
+            description,
+            0,
+            KNOWN_NEURON_DESCRIPTION_MAX_LEN,
+        ) {
+            defects.push(err);
+        }
+    }
+    let name_is_taken = neurons.values().any(|neuron| {
+        neuron.id.as_ref() != Some(neuron_id)
+            && neuron
+                .known_neuron_data
+                .as_ref()
+                .map_or(false, |data| data.name == known_neuron_data.name)
+    });
+    if name_is_taken {
+        defects.push(format!(
+            "The name {:?} already belongs to another known neuron.",
+            known_neuron_data.name
+        ));
+    }
+    if !defects.is_empty() {
+        return Err(format!(
+            "RegisterKnownNeuron proposal was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    Ok(format!(
+        r"# Proposal to register a known neuron:
+## Neuron ID: {neuron_id}
+## Name: {name}
+## Description: {description}",
+        name = known_neuron_data.name,
+        description = known_neuron_data.description.as_deref().unwrap_or("(none)"),
+    ))
+}
+
+/// Validates and renders a proposal with action DeregisterKnownNeuron.
+fn validate_and_render_deregister_known_neuron(
+    deregister_known_neuron: &DeregisterKnownNeuron,
+    neurons: &BTreeMap<String, Neuron>,
+) -> Result<String, String> {
+    let neuron_id = validate_required_field("id", &deregister_known_neuron.id)?;
+    let neuron = neurons
+        .get(&neuron_id.to_string())
+        .ok_or_else(|| format!("There is no neuron with ID {neuron_id}."))?;
+    let known_neuron_data = neuron
+        .known_neuron_data
+        .as_ref()
+        .ok_or_else(|| format!("The neuron with ID {neuron_id} is not a known neuron."))?;
+
+    Ok(format!(
+        r"# Proposal to deregister a known neuron:
+## Neuron ID: {neuron_id}
+## Name: {name}",
+        name = known_neuron_data.name,
+    ))
+}
+
+/// Validates and renders a proposal with action LaunchFollowOnSwap.
+///
+/// Only the consistency of the proposal itself is checked here. The swap canister
+/// performs the remaining checks (e.g., that the previous round is finalized, and
+/// that participants can afford the minimum neuron stake) when the round is opened.
+///
+/// The SNS tokens offered are subject to the same 7-day upper bound as the equivalent
+/// TransferSnsTreasuryFunds or MintSnsTokens proposal (see LaunchFollowOnSwap::funding). Hence,
+/// returns ActionAuxiliary::TransferSnsTreasuryFunds or ActionAuxiliary::MintSnsTokens.
+async fn validate_and_render_launch_follow_on_swap(
+    launch_follow_on_swap: &LaunchFollowOnSwap,
+    current_parameters: &NervousSystemParameters,
+    env: &dyn Environment,
+    swap_canister_id: CanisterId,
+    sns_ledger_canister_id: CanisterId,
+    proposals: impl Iterator<Item = &ProposalData>,
+) -> Result<
+    (
+        String, // Rendering.
+        ActionAuxiliary,
+    ),
+    String,
+> {
+    let mut defects = vec![];
+
+    // Validate amount. (This requires calling CMC and the swap canister; hence, await.)
+    let action_auxiliary = match launch_follow_on_swap.funding(swap_canister_id) {
+        Ok(LaunchFollowOnSwapFunding::Treasury(transfer)) => {
+            treasury_valuation_if_proposal_amount_is_small_enough_or_err(
+                env,
+                sns_ledger_canister_id,
+                swap_canister_id,
+                proposals,
+                &transfer,
+            )
+            .await
+            .map(ActionAuxiliary::TransferSnsTreasuryFunds)
+        }
+        Ok(LaunchFollowOnSwapFunding::Mint(mint)) => {
+            treasury_valuation_if_proposal_amount_is_small_enough_or_err(
+                env,
+                sns_ledger_canister_id,
+                swap_canister_id,
+                proposals,
+                &mint,
+            )
+            .await
+            .map(ActionAuxiliary::MintSnsTokens)
+        }
+        // Reported by locally_validate_and_render_launch_follow_on_swap.
+        Err(_) => Ok(ActionAuxiliary::None),
+    };
+    let action_auxiliary = match action_auxiliary {
+        Ok(ok) => ok,
+        Err(err) => {
+            defects.push(err);
+            ActionAuxiliary::None
+        }
+    };
+
+    locally_validate_and_render_launch_follow_on_swap(
+        launch_follow_on_swap,
+        current_parameters,
+        defects,
+    )
+    .map(|rendering| (rendering, action_auxiliary))
+}
+
+/// Performs all the validation on a LaunchFollowOnSwap that does not require fetching information
+/// from other canisters.
+fn locally_validate_and_render_launch_follow_on_swap(
+    launch_follow_on_swap: &LaunchFollowOnSwap,
+    current_parameters: &NervousSystemParameters,
+    mut defects: Vec<String>,
+) -> Result<String, String> {
+    let LaunchFollowOnSwap {
+        token_source: _,
+        sns_token_e8s,
+        min_participants,
+        min_direct_participation_icp_e8s,
+        max_direct_participation_icp_e8s,
+        min_participant_icp_e8s,
+        max_participant_icp_e8s,
+        start_delay_seconds,
+        duration_seconds,
+        neuron_basket_count,
+        neuron_basket_dissolve_delay_interval_seconds,
+    } = launch_follow_on_swap;
+
+    let token_source = launch_follow_on_swap.token_source();
+    if token_source == TokenSource::Unspecified {
+        defects
+            .push("token_source must be TOKEN_SOURCE_MINT or TOKEN_SOURCE_TREASURY.".to_string());
+    }
+
+    let sns_token_e8s = sns_token_e8s.unwrap_or_default();
+    if sns_token_e8s == 0 {
+        defects.push("sns_token_e8s must be specified and positive.".to_string());
+    }
+
+    let min_participants = min_participants.unwrap_or_default();
+    if min_participants == 0 {
+        defects.push("min_participants must be specified and positive.".to_string());
+    }
+
+    let mut check_range = |field_name: &str, min: &Option<u64>, max: &Option<u64>| match (min, max)
+    {
+        (Some(min), Some(max)) if min <= max => (*min, *max),
+        (Some(min), Some(max)) => {
+            defects.push(format!(
+                "min_{field_name} ({min}) must not exceed max_{field_name} ({max})."
+            ));
+            (*min, *max)
+        }
+        _ => {
+            defects.push(format!(
+                "min_{field_name} and max_{field_name} must both be specified."
+            ));
+            (0, 0)
+        }
+    };
+    let (min_direct_participation_icp_e8s, max_direct_participation_icp_e8s) = check_range(
+        "direct_participation_icp_e8s",
+        min_direct_participation_icp_e8s,
+        max_direct_participation_icp_e8s,
+    );
+    let (min_participant_icp_e8s, max_participant_icp_e8s) = check_range(
+        "participant_icp_e8s",
+        min_participant_icp_e8s,
+        max_participant_icp_e8s,
+    );
+    if max_participant_icp_e8s > max_direct_participation_icp_e8s {
+        defects.push(format!(
+            "max_participant_icp_e8s ({max_participant_icp_e8s}) must not exceed \
+             max_direct_participation_icp_e8s ({max_direct_participation_icp_e8s})."
+        ));
+    }
+
+    let start_delay_seconds = start_delay_seconds.unwrap_or_default();
+    if start_delay_seconds > MAX_FOLLOW_ON_SWAP_START_DELAY_SECONDS {
+        defects.push(format!(
+            "start_delay_seconds ({start_delay_seconds}) must be at most \
+             {MAX_FOLLOW_ON_SWAP_START_DELAY_SECONDS}."
+        ));
+    }
+
+    let duration_seconds = duration_seconds.unwrap_or_default();
+    if !(MIN_FOLLOW_ON_SWAP_DURATION_SECONDS..=MAX_FOLLOW_ON_SWAP_DURATION_SECONDS)
+        .contains(&duration_seconds)
+    {
+        defects.push(format!(
+            "duration_seconds ({duration_seconds}) must be between \
+             {MIN_FOLLOW_ON_SWAP_DURATION_SECONDS} and {MAX_FOLLOW_ON_SWAP_DURATION_SECONDS}."
+        ));
+    }
+
+    let neuron_basket_count = neuron_basket_count.unwrap_or_default();
+    if !(MIN_FOLLOW_ON_SWAP_NEURON_BASKET_COUNT..=MAX_FOLLOW_ON_SWAP_NEURON_BASKET_COUNT)
+        .contains(&neuron_basket_count)
+    {
+        defects.push(format!(
+            "neuron_basket_count ({neuron_basket_count}) must be between \
+             {MIN_FOLLOW_ON_SWAP_NEURON_BASKET_COUNT} and {MAX_FOLLOW_ON_SWAP_NEURON_BASKET_COUNT}."
+        ));
+    }
+    let neuron_basket_dissolve_delay_interval_seconds =
+        neuron_basket_dissolve_delay_interval_seconds.unwrap_or_default();
+    if neuron_basket_dissolve_delay_interval_seconds == 0 {
+        defects.push(
+            "neuron_basket_dissolve_delay_interval_seconds must be specified and positive."
+                .to_string(),
+        );
+    }
+    let max_dissolve_delay_seconds = current_parameters
+        .max_dissolve_delay_seconds
+        .unwrap_or_default();
+    let max_neuron_basket_dissolve_delay_seconds = neuron_basket_count
+        .saturating_sub(1)
+        .saturating_mul(neuron_basket_dissolve_delay_interval_seconds);
+    if max_neuron_basket_dissolve_delay_seconds > max_dissolve_delay_seconds {
+        defects.push(format!(
+            "The neuron basket would contain neurons with a dissolve delay of \
+             {max_neuron_basket_dissolve_delay_seconds} seconds, but the SNS's \
+             max_dissolve_delay_seconds is {max_dissolve_delay_seconds}."
+        ));
+    }
+
+    if !defects.is_empty() {
+        return Err(format!(
+            "LaunchFollowOnSwap proposal was invalid for the following reason(s):\n{}",
+            defects.join("\n"),
+        ));
+    }
+
+    let token_source = match token_source {
+        TokenSource::Mint => "Newly minted SNS tokens",
+        TokenSource::Treasury => "The SNS token treasury",
+        TokenSource::Unspecified => unreachable!("Rejected above."),
+    };
+    Ok(format!(
+        r"# Proposal to launch a follow-on swap:
+## SNS tokens offered: {display_sns_tokens:.8} SNS Tokens ({sns_token_e8s} e8s)
+## Source of the SNS tokens: {token_source}
+## Start: {start_delay_seconds} seconds after execution
+## Duration: {duration_seconds} seconds
+## Minimum number of participants: {min_participants}
+## Direct participation: between {min_direct_participation_icp_e8s} and {max_direct_participation_icp_e8s} ICP e8s
+## Participation per participant: between {min_participant_icp_e8s} and {max_participant_icp_e8s} ICP e8s
+## Neuron basket: {neuron_basket_count} neurons, {neuron_basket_dissolve_delay_interval_seconds} seconds of dissolve delay apart",
+        display_sns_tokens = i2d(sns_token_e8s) / i2d(E8),
+    ))
+}
+
+/// Where the SNS tokens offered by a follow-on swap round come from, expressed as the proposal
+/// action that would move the same tokens to the swap canister.
+pub(crate) enum LaunchFollowOnSwapFunding {
+    Treasury(TransferSnsTreasuryFunds),
+    Mint(MintSnsTokens),
+}
+
+impl LaunchFollowOnSwap {
+    /// Err only if self is invalid.
+    pub(crate) fn funding(
+        &self,
+        swap_canister_id: CanisterId,
+    ) -> Result<LaunchFollowOnSwapFunding, String> {
+        let amount_e8s = self
+            .sns_token_e8s
+            .ok_or_else(|| "The `sns_token_e8s` field is not populated.".to_string())?;
+        let to_principal = Some(swap_canister_id.get());
+
+        match self.token_source() {
+            TokenSource::Treasury => Ok(LaunchFollowOnSwapFunding::Treasury(
+                TransferSnsTreasuryFunds {
+                    from_treasury: TransferFrom::SnsTokenTreasury as i32,
+                    amount_e8s,
+                    memo: None,
+                    to_principal,
+                    to_subaccount: None,
+                },
+            )),
+            TokenSource::Mint => Ok(LaunchFollowOnSwapFunding::Mint(MintSnsTokens {
+                amount_e8s: Some(amount_e8s),
+                memo: None,
+                to_principal,
+                to_subaccount: None,
+            })),
+            TokenSource::Unspecified => {
+                Err("The `token_source` field holds the Unspecified value.".to_string())
+            }
+        }
+    }
+}
+
 impl ProposalData {
     /// Returns the proposal's decision status. See [ProposalDecisionStatus] in the SNS's
     /// proto for more information.
@@ -2096,7 +2618,8 @@ impl ProposalData {
 
         // At this point, we can let go of most proposals. The only special case is
         // TransferSnsTreasuryFunds and MintSnsTokens (the common thread between these is that these
-        // affect the value of the treasury). We want to hang onto those for at least 7 days after
+        // affect the value of the treasury), as well as LaunchFollowOnSwap, which transfers or
+        // mints the SNS tokens it offers. We want to hang onto those for at least 7 days after
         // they have been successfully executed. This is because they are still needed for the
         // purposes of limiting amounts.
         let Some(proposal) = &self.proposal else {
@@ -2110,6 +2633,17 @@ impl ProposalData {
             Some(Action::MintSnsTokens(_)) => {
                 EXECUTED_MINT_SNS_TOKENS_PROPOSAL_RETENTION_DURATION_SECONDS
             }
+            Some(Action::LaunchFollowOnSwap(launch_follow_on_swap)) => {
+                match launch_follow_on_swap.token_source() {
+                    TokenSource::Treasury => {
+                        EXECUTED_TRANSFER_SNS_TREASURY_FUNDS_PROPOSAL_RETENTION_DURATION_SECONDS
+                    }
+                    TokenSource::Mint => {
+                        EXECUTED_MINT_SNS_TOKENS_PROPOSAL_RETENTION_DURATION_SECONDS
+                    }
+                    TokenSource::Unspecified => return true,
+                }
+            }
             _ => return true,
         };
 
@@ -2226,8 +2760,10 @@ pub(crate) fn transfer_sns_treasury_funds_amount_is_small_enough_at_execution_ti
     transfer: &TransferSnsTreasuryFunds,
     valuation: Valuation,
     proposals: impl Iterator<Item = &'a ProposalData>,
+    recent_treasury_payouts: &[TreasuryPayout],
     now_timestamp_seconds: u64,
 ) -> Result<(), GovernanceError> {
+    let min_timestamp_seconds = now_timestamp_seconds - 7 * ONE_DAY_SECONDS;
     let allowance_tokens = transfer_sns_treasury_funds_7_day_total_upper_bound_tokens(valuation)
         .map_err(|err| {
             // This should not be possible, because valuation was already used the same way during
@@ -2244,15 +2780,21 @@ pub(crate) fn transfer_sns_treasury_funds_amount_is_small_enough_at_execution_ti
 
     // The total calculated here _could_ be different from what was calculated at proposal
     // submission/creation time. A difference would result from the execution of (another)
-    // TransferSnsTreasuryFunds proposal between now and then.
-    let spent_tokens = total_treasury_transfer_amount_tokens(
+    // TransferSnsTreasuryFunds proposal (or scheduled treasury payout) between now and then.
+    let proposals_tokens = total_treasury_transfer_amount_tokens(
         proposals,
         transfer.from_treasury(),
-        now_timestamp_seconds - 7 * ONE_DAY_SECONDS,
+        min_timestamp_seconds,
     )
     .map_err(|message| {
         GovernanceError::new_with_message(ErrorType::InconsistentInternalData, message)
     })?;
+    let scheduled_payouts_e8s = recent_treasury_payouts_e8s(
+        recent_treasury_payouts,
+        transfer.from_treasury,
+        min_timestamp_seconds,
+    );
+    let spent_tokens = proposals_tokens + i2d(scheduled_payouts_e8s) / i2d(E8);
 
     let remainder_tokens = allowance_tokens - spent_tokens;
     let transfer_amount_tokens = denominations_to_tokens(transfer.amount_e8s, E8)
@@ -2285,6 +2827,163 @@ pub(crate) fn transfer_sns_treasury_funds_amount_is_small_enough_at_execution_ti
     Ok(())
 }
 
+/// Analogous to transfer_sns_treasury_funds_amount_is_small_enough_at_execution_time_or_err.
+fn mint_sns_tokens_amount_is_small_enough_at_execution_time_or_err<'a>(
+    mint: &MintSnsTokens,
+    valuation: Valuation,
+    proposals: impl Iterator<Item = &'a ProposalData>,
+    now_timestamp_seconds: u64,
+) -> Result<(), GovernanceError> {
+    let inconsistent_internal_data =
+        |message| GovernanceError::new_with_message(ErrorType::InconsistentInternalData, message);
+
+    let allowance_tokens = MintSnsTokens::recent_amount_total_upper_bound_tokens(&valuation)
+        .map_err(inconsistent_internal_data)?;
+    let spent_tokens = mint
+        .recent_amount_total_tokens(proposals, now_timestamp_seconds)
+        .map_err(inconsistent_internal_data)?;
+    let mint_amount_tokens = mint
+        .proposal_amount_tokens()
+        .map_err(inconsistent_internal_data)?;
+
+    if mint_amount_tokens > allowance_tokens - spent_tokens {
+        return Err(GovernanceError::new_with_message(
+            ErrorType::PreconditionFailed,
+            format!(
+                "Executing this proposal is not allowed at this time, because doing \
+                 so would cause the 7 day upper bound of {} tokens to be exceeded. \
+                 Maybe, try again later? The total amount minted in the past \
+                 7 days stands at {} tokens, and the amount in this proposal is {} \
+                 tokens. The upper bound is based on treasury valuation factors at \
+                 the time of proposal submission: {:?}",
+                allowance_tokens, spent_tokens, mint_amount_tokens, valuation,
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks the SNS tokens offered by a follow-on swap round against the 7-day upper bound that
+/// was checked when the proposal was submitted, like the equivalent TransferSnsTreasuryFunds or
+/// MintSnsTokens proposal would be.
+pub(crate) fn launch_follow_on_swap_amount_is_small_enough_at_execution_time_or_err<'a>(
+    launch_follow_on_swap: &LaunchFollowOnSwap,
+    swap_canister_id: CanisterId,
+    action_auxiliary: ActionAuxiliary,
+    proposals: impl Iterator<Item = &'a ProposalData>,
+    recent_treasury_payouts: &[TreasuryPayout],
+    now_timestamp_seconds: u64,
+) -> Result<(), GovernanceError> {
+    let funding = launch_follow_on_swap
+        .funding(swap_canister_id)
+        .map_err(|err| GovernanceError::new_with_message(ErrorType::InvalidProposal, err))?;
+
+    match (funding, action_auxiliary) {
+        (
+            LaunchFollowOnSwapFunding::Treasury(transfer),
+            ActionAuxiliary::TransferSnsTreasuryFunds(valuation),
+        ) => transfer_sns_treasury_funds_amount_is_small_enough_at_execution_time_or_err(
+            &transfer,
+            valuation,
+            proposals,
+            recent_treasury_payouts,
+            now_timestamp_seconds,
+        ),
+        (LaunchFollowOnSwapFunding::Mint(mint), ActionAuxiliary::MintSnsTokens(valuation)) => {
+            mint_sns_tokens_amount_is_small_enough_at_execution_time_or_err(
+                &mint,
+                valuation,
+                proposals,
+                now_timestamp_seconds,
+            )
+        }
+        (_, wrong) => Err(GovernanceError::new_with_message(
+            ErrorType::InconsistentInternalData,
+            format!(
+                "Missing supporting information. Specifically, \
+                 no treasury valuation factors matching the token source: {:#?}",
+                wrong,
+            ),
+        )),
+    }
+}
+
+/// Checks that a scheduled treasury payout is within the same 7-day upper bound as
+/// TransferSnsTreasuryFunds proposals. The bound is based on a fresh valuation of the treasury,
+/// and the amount already spent comprises both the executed TransferSnsTreasuryFunds proposals
+/// and the scheduled payouts of the past 7 days.
+pub(crate) async fn scheduled_treasury_payout_is_small_enough_or_err(
+    env: &dyn Environment,
+    governance_proto: &Governance,
+    payout: &TransferSnsTreasuryFunds,
+) -> Result<(), GovernanceError> {
+    let now_timestamp_seconds = env.now();
+    let min_timestamp_seconds = now_timestamp_seconds.saturating_sub(7 * ONE_DAY_SECONDS);
+
+    let token = payout
+        .token()
+        .map_err(|err| GovernanceError::new_with_message(ErrorType::InvalidProposal, err))?;
+    let treasury_account = token
+        .treasury_account(env.canister_id())
+        .map_err(|err| GovernanceError::new_with_message(ErrorType::InvalidProposal, err))?;
+    let valuation = token
+        .assess_balance(
+            governance_proto.ledger_canister_id_or_panic(),
+            governance_proto.swap_canister_id_or_panic(),
+            treasury_account,
+        )
+        .await
+        .map_err(|valuation_error| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!("Unable to value the treasury: {:?}", valuation_error),
+            )
+        })?;
+    let allowance_tokens = transfer_sns_treasury_funds_7_day_total_upper_bound_tokens(valuation)
+        .map_err(|err| {
+            GovernanceError::new_with_message(
+                ErrorType::External,
+                format!(
+                    "Unable to determine the upper bound on treasury transfers: {:?}\n\
+                     valuation: {:?}",
+                    err, valuation,
+                ),
+            )
+        })?;
+
+    let proposals_tokens = total_treasury_transfer_amount_tokens(
+        governance_proto.proposals.values(),
+        payout.from_treasury(),
+        min_timestamp_seconds,
+    )
+    .map_err(|message| {
+        GovernanceError::new_with_message(ErrorType::InconsistentInternalData, message)
+    })?;
+    let scheduled_payouts_e8s = recent_treasury_payouts_e8s(
+        &governance_proto.recent_treasury_payouts,
+        payout.from_treasury,
+        min_timestamp_seconds,
+    );
+    let spent_tokens = proposals_tokens + i2d(scheduled_payouts_e8s) / i2d(E8);
+    let payout_tokens = i2d(payout.amount_e8s) / i2d(E8);
+
+    if payout_tokens + spent_tokens > allowance_tokens {
+        return Err(GovernanceError::new_with_message(
+            ErrorType::PreconditionFailed,
+            format!(
+                "Making this payout is not allowed at this time, because doing so would cause \
+                 the 7 day upper bound of {} tokens to be exceeded. The total amount \
+                 transferred in the past 7 days stands at {} tokens, and the payout is {} \
+                 tokens. The payout will be retried later.",
+                allowance_tokens, spent_tokens, payout_tokens,
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
 /// Returns the total amount (in e8s) that was transfered from the treasury via
 /// TransferSnsTreasuryFunds proposals, or None if there was an overflow.
 ///
@@ -2306,6 +3005,14 @@ fn total_treasury_transfer_amount_tokens<'a>(
     let filter_proposal_action_amount_e8s = |action: &Action| {
         let transfer = match action {
             Action::TransferSnsTreasuryFunds(ok) => ok,
+            // Follow-on swaps funded by the treasury transfer SNS tokens to the swap canister.
+            Action::LaunchFollowOnSwap(launch_follow_on_swap)
+                if launch_follow_on_swap.token_source() == TokenSource::Treasury =>
+            {
+                return (filter_from_treasury == TransferFrom::SnsTokenTreasury)
+                    .then_some(launch_follow_on_swap.sns_token_e8s)
+                    .flatten();
+            }
             // Skip other types of proposals.
             _ => return None,
         };
@@ -2339,6 +3046,12 @@ fn total_minting_amount_tokens<'a>(
     let filter_proposal_action_amount_e8s = |action: &Action| {
         let mint = match action {
             Action::MintSnsTokens(ok) => ok,
+            // Follow-on swaps funded by minting mint SNS tokens to the swap canister.
+            Action::LaunchFollowOnSwap(launch_follow_on_swap)
+                if launch_follow_on_swap.token_source() == TokenSource::Mint =>
+            {
+                return launch_follow_on_swap.sns_token_e8s;
+            }
             // Skip other types of proposals.
             _ => return None,
         };
@@ -2428,8 +3141,8 @@ mod tests {
     use crate::{
         pb::v1::{
             governance::{self, Version},
-            Ballot, Empty, Governance as GovernanceProto, NeuronId, Proposal, ProposalId,
-            Subaccount, WaitForQuietState,
+            Ballot, Empty, Governance as GovernanceProto, KnownNeuronData, NeuronId, Proposal,
+            ProposalId, Subaccount, WaitForQuietState,
         },
         sns_upgrade::{
             CanisterSummary, GetNextSnsVersionRequest, GetNextSnsVersionResponse,
@@ -2489,6 +3202,8 @@ mod tests {
             pending_version: None,
             is_finalizing_disburse_maturity: None,
             maturity_modulation: None,
+            treasury_disbursement_schedules: Default::default(),
+            recent_treasury_payouts: vec![],
         }
     }
 
@@ -3871,6 +4586,237 @@ Version {
         );
     }
 
+    fn valid_launch_follow_on_swap() -> LaunchFollowOnSwap {
+        LaunchFollowOnSwap {
+            token_source: Some(TokenSource::Treasury as i32),
+            sns_token_e8s: Some(1_000_000 * E8),
+            min_participants: Some(5),
+            min_direct_participation_icp_e8s: Some(100 * E8),
+            max_direct_participation_icp_e8s: Some(10_000 * E8),
+            min_participant_icp_e8s: Some(E8),
+            max_participant_icp_e8s: Some(1_000 * E8),
+            start_delay_seconds: Some(ONE_DAY_SECONDS),
+            duration_seconds: Some(7 * ONE_DAY_SECONDS),
+            neuron_basket_count: Some(3),
+            neuron_basket_dissolve_delay_interval_seconds: Some(30 * ONE_DAY_SECONDS),
+        }
+    }
+
+    #[test]
+    fn validate_and_render_launch_follow_on_swap_renders_for_valid_inputs() {
+        assert_eq!(
+            locally_validate_and_render_launch_follow_on_swap(
+                &valid_launch_follow_on_swap(),
+                &NervousSystemParameters::with_default_values(),
+                vec![],
+            )
+            .unwrap(),
+            r"# Proposal to launch a follow-on swap:
+## SNS tokens offered: 1000000.00000000 SNS Tokens (100000000000000 e8s)
+## Source of the SNS tokens: The SNS token treasury
+## Start: 86400 seconds after execution
+## Duration: 604800 seconds
+## Minimum number of participants: 5
+## Direct participation: between 10000000000 and 1000000000000 ICP e8s
+## Participation per participant: between 100000000 and 100000000000 ICP e8s
+## Neuron basket: 3 neurons, 2592000 seconds of dissolve delay apart"
+        );
+    }
+
+    #[test]
+    fn validate_and_render_launch_follow_on_swap_reports_all_defects() {
+        let launch_follow_on_swap = LaunchFollowOnSwap {
+            token_source: None,
+            max_participant_icp_e8s: Some(20_000 * E8),
+            duration_seconds: Some(15 * ONE_DAY_SECONDS),
+            neuron_basket_count: Some(1),
+            ..valid_launch_follow_on_swap()
+        };
+
+        let err = locally_validate_and_render_launch_follow_on_swap(
+            &launch_follow_on_swap,
+            &NervousSystemParameters::with_default_values(),
+            vec![],
+        )
+        .unwrap_err();
+
+        for expected in [
+            "token_source must be",
+            "max_participant_icp_e8s (2000000000000) must not exceed",
+            "duration_seconds (1296000) must be between",
+            "neuron_basket_count (1) must be between",
+        ] {
+            assert!(err.contains(expected), "{expected:?} not in {err:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn validate_and_render_create_treasury_disbursement_schedule_reports_all_defects() {
+        let create = CreateTreasuryDisbursementSchedule {
+            from_treasury: TransferFrom::SnsTokenTreasury as i32,
+            amount_per_period_e8s: None,
+            number_of_periods: Some(MAX_TREASURY_DISBURSEMENT_PERIODS + 1),
+            period_seconds: Some(60),
+            cliff_seconds: Some(5 * ONE_YEAR_SECONDS),
+            to_principal: Some(*TEST_USER1_PRINCIPAL),
+            to_subaccount: None,
+        };
+
+        let err = validate_and_render_create_treasury_disbursement_schedule(
+            &create,
+            DEFAULT_TRANSFER_FEE.get_e8s(),
+            &**FAKE_ENV,
+            *SNS_SWAP_CANISTER_ID,
+            *SNS_LEDGER_CANISTER_ID,
+            std::iter::empty(),
+        )
+        .await
+        .unwrap_err();
+
+        for expected in [
+            "Must specify an amount_per_period_e8s",
+            "number_of_periods (121) must be between",
+            "period_seconds (60) must be between",
+            "cliff_seconds (157788000) must be at most",
+        ] {
+            assert!(err.contains(expected), "{expected:?} not in {err:?}");
+        }
+    }
+
+    #[test]
+    fn validate_and_render_cancel_treasury_disbursement_schedule_requires_active_schedule() {
+        let create = CreateTreasuryDisbursementSchedule {
+            from_treasury: TransferFrom::IcpTreasury as i32,
+            amount_per_period_e8s: Some(10 * E8),
+            number_of_periods: Some(12),
+            period_seconds: Some(30 * ONE_DAY_SECONDS),
+            cliff_seconds: Some(0),
+            to_principal: Some(*TEST_USER1_PRINCIPAL),
+            to_subaccount: None,
+        };
+        let mut schedule = TreasuryDisbursementSchedule::new(42, create, 1_000);
+        schedule.payouts.push(Default::default());
+        let schedules = btreemap! { 42 => schedule };
+
+        assert_eq!(
+            validate_and_render_cancel_treasury_disbursement_schedule(
+                &CancelTreasuryDisbursementSchedule {
+                    schedule_id: Some(42)
+                },
+                &schedules,
+            )
+            .unwrap(),
+            r"# Proposal to cancel treasury disbursement schedule 42:
+## Payouts made: 1
+## Payouts cancelled: 11"
+        );
+
+        let err = validate_and_render_cancel_treasury_disbursement_schedule(
+            &CancelTreasuryDisbursementSchedule {
+                schedule_id: Some(43),
+            },
+            &schedules,
+        )
+        .unwrap_err();
+        assert!(err.contains("no active treasury disbursement schedule with ID 43"));
+    }
+
+    #[test]
+    fn validate_and_render_register_known_neuron_reports_defects() {
+        let neuron_id_1 = NeuronId::new_test_neuron_id(1);
+        let neuron_id_2 = NeuronId::new_test_neuron_id(2);
+        let neurons = btreemap! {
+            neuron_id_1.to_string() => Neuron {
+                id: Some(neuron_id_1.clone()),
+                known_neuron_data: Some(KnownNeuronData {
+                    name: "Alice".to_string(),
+                    description: None,
+                }),
+                ..Default::default()
+            },
+            neuron_id_2.to_string() => Neuron {
+                id: Some(neuron_id_2.clone()),
+                ..Default::default()
+            },
+        };
+        let register = |neuron_id: &NeuronId, name: &str| KnownNeuron {
+            id: Some(neuron_id.clone()),
+            known_neuron_data: Some(KnownNeuronData {
+                name: name.to_string(),
+                description: Some("Votes on every proposal.".to_string()),
+            }),
+        };
+
+        assert_eq!(
+            validate_and_render_register_known_neuron(&register(&neuron_id_2, "Bob"), &neurons)
+                .unwrap(),
+            format!(
+                r"# Proposal to register a known neuron:
+## Neuron ID: {neuron_id_2}
+## Name: Bob
+## Description: Votes on every proposal."
+            ),
+        );
+        // A known neuron may be registered again under its own name.
+        assert_is_ok(validate_and_render_register_known_neuron(
+            &register(&neuron_id_1, "Alice"),
+            &neurons,
+        ));
+
+        let err =
+            validate_and_render_register_known_neuron(&register(&neuron_id_2, "Alice"), &neurons)
+                .unwrap_err();
+        assert!(
+            err.contains("already belongs to another known neuron"),
+            "{err}"
+        );
+        let err = validate_and_render_register_known_neuron(
+            &register(&neuron_id_2, &"x".repeat(KNOWN_NEURON_NAME_MAX_LEN + 1)),
+            &neurons,
+        )
+        .unwrap_err();
+        assert!(err.contains("too long"), "{err}");
+        let err = validate_and_render_register_known_neuron(
+            &register(&NeuronId::new_test_neuron_id(3), "Carol"),
+            &neurons,
+        )
+        .unwrap_err();
+        assert!(err.contains("There is no neuron with ID"), "{err}");
+    }
+
+    #[test]
+    fn validate_and_render_deregister_known_neuron_requires_known_neuron() {
+        let neuron_id = NeuronId::new_test_neuron_id(1);
+        let mut neurons = btreemap! {
+            neuron_id.to_string() => Neuron {
+                id: Some(neuron_id.clone()),
+                ..Default::default()
+            },
+        };
+        let deregister = DeregisterKnownNeuron {
+            id: Some(neuron_id.clone()),
+        };
+
+        let err = validate_and_render_deregister_known_neuron(&deregister, &neurons).unwrap_err();
+        assert!(err.contains("is not a known neuron"), "{err}");
+
+        neurons
+            .get_mut(&neuron_id.to_string())
+            .unwrap()
+            .known_neuron_data = Some(KnownNeuronData {
+            name: "Alice".to_string(),
+            description: None,
+        });
+        assert_eq!(
+            validate_and_render_deregister_known_neuron(&deregister, &neurons).unwrap(),
+            format!(
+                r"# Proposal to deregister a known neuron:
+## Neuron ID: {neuron_id}
+## Name: Alice"
+            ),
+        );
+    }
+
     #[test]
     fn validate_and_render_register_dapp_canisters_lists_canisters() {
         let canister_ids = (0..10_u8)
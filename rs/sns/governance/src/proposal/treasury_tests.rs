@@ -286,3 +286,67 @@ fn test_total_treasury_transfer_amount_tokens() {
         Ok((Decimal::from(u64::MAX) + Decimal::from(1)) / Decimal::from(E8)),
     );
 }
+
+#[test]
+fn test_total_amount_tokens_include_follow_on_swaps() {
+    let executed_timestamp_seconds = 123_456_789;
+
+    let new_proposal = |token_source: TokenSource, sns_token_e8s: u64| -> ProposalData {
+        ProposalData {
+            proposal: Some(Proposal {
+                action: Some(Action::LaunchFollowOnSwap(LaunchFollowOnSwap {
+                    token_source: Some(token_source as i32),
+                    sns_token_e8s: Some(sns_token_e8s),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }),
+            executed_timestamp_seconds,
+            ..Default::default()
+        }
+    };
+    let proposals = vec![
+        new_proposal(TokenSource::Treasury, 1),
+        new_proposal(TokenSource::Mint, 20),
+        // Skip because the token source is not known.
+        new_proposal(TokenSource::Unspecified, 300),
+    ];
+
+    // Follow-on swaps funded by the treasury only count towards SNS token transfers...
+    assert_eq!(
+        total_treasury_transfer_amount_tokens(
+            proposals.iter(),
+            TransferFrom::SnsTokenTreasury,
+            executed_timestamp_seconds,
+        ),
+        Ok(Decimal::from(1) / Decimal::from(E8)),
+    );
+    assert_eq!(
+        total_treasury_transfer_amount_tokens(
+            proposals.iter(),
+            TransferFrom::IcpTreasury,
+            executed_timestamp_seconds,
+        ),
+        Ok(Decimal::from(0)),
+    );
+    // ... whereas those funded by minting count towards minting.
+    assert_eq!(
+        total_minting_amount_tokens(proposals.iter(), executed_timestamp_seconds),
+        Ok(Decimal::from(20) / Decimal::from(E8)),
+    );
+
+    // Like the proposals they are equivalent to, they are retained for 7 days.
+    let settled = |proposal: ProposalData| ProposalData {
+        decided_timestamp_seconds: executed_timestamp_seconds,
+        reward_event_end_timestamp_seconds: Some(executed_timestamp_seconds),
+        is_eligible_for_rewards: true,
+        ..proposal
+    };
+    let proposals: Vec<_> = proposals.into_iter().map(settled).collect();
+    assert!(!proposals[0].can_be_purged(executed_timestamp_seconds));
+    assert!(!proposals[1].can_be_purged(executed_timestamp_seconds));
+    assert!(proposals[2].can_be_purged(executed_timestamp_seconds));
+    for proposal in &proposals {
+        assert!(proposal.can_be_purged(executed_timestamp_seconds + 7 * ONE_DAY_SECONDS + 1));
+    }
+}
@@ -0,0 +1,181 @@
+// NOTE: This file's types are all from the SNS swap canister, which depends on this crate, so
+// they cannot be included directly.
+// TODO(NNS1-1589): Remove all these types after dependency cycle is fixed.
+
+/// Mirrors `ic_sns_swap::pb::v1::NeuronBasketConstructionParameters`.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct NeuronBasketConstructionParameters {
+    #[prost(uint64, tag = "1")]
+    pub count: u64,
+    #[prost(uint64, tag = "2")]
+    pub dissolve_delay_interval_seconds: u64,
+}
+/// Mirrors `ic_sns_swap::pb::v1::OpenFollowOnRoundRequest`.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct OpenFollowOnRoundRequest {
+    #[prost(uint64, optional, tag = "1")]
+    pub sns_proposal_id: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub sns_token_e8s: ::core::option::Option<u64>,
+    #[prost(uint32, optional, tag = "3")]
+    pub min_participants: ::core::option::Option<u32>,
+    #[prost(uint64, optional, tag = "4")]
+    pub min_direct_participation_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub max_direct_participation_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "6")]
+    pub min_participant_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "7")]
+    pub max_participant_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "8")]
+    pub swap_start_timestamp_seconds: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "9")]
+    pub swap_due_timestamp_seconds: ::core::option::Option<u64>,
+    #[prost(message, optional, tag = "10")]
+    pub neuron_basket_construction_parameters:
+        ::core::option::Option<NeuronBasketConstructionParameters>,
+}
+/// Mirrors `ic_sns_swap::pb::v1::OpenFollowOnRoundResponse`.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct OpenFollowOnRoundResponse {
+    #[prost(oneof = "open_follow_on_round_response::Result", tags = "1, 2")]
+    pub result: ::core::option::Option<open_follow_on_round_response::Result>,
+}
+/// Nested message and enum types in `OpenFollowOnRoundResponse`.
+pub mod open_follow_on_round_response {
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        PartialEq,
+        ::prost::Message,
+    )]
+    pub struct Ok {
+        #[prost(uint64, optional, tag = "1")]
+        pub round: ::core::option::Option<u64>,
+    }
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        PartialEq,
+        ::prost::Message,
+    )]
+    pub struct Err {
+        #[prost(string, optional, tag = "1")]
+        pub description: ::core::option::Option<::prost::alloc::string::String>,
+    }
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        PartialEq,
+        ::prost::Oneof,
+    )]
+    pub enum Result {
+        #[prost(message, tag = "1")]
+        Ok(Ok),
+        #[prost(message, tag = "2")]
+        Err(Err),
+    }
+}
+/// Mirrors `ic_sns_swap::pb::v1::RefundFollowOnRoundTokensRequest`.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct RefundFollowOnRoundTokensRequest {
+    #[prost(uint64, optional, tag = "1")]
+    pub sns_proposal_id: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub amount_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub fee_e8s: ::core::option::Option<u64>,
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub to_subaccount: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+}
+/// Mirrors `ic_sns_swap::pb::v1::RefundFollowOnRoundTokensResponse`.
+#[derive(
+    candid::CandidType,
+    candid::Deserialize,
+    comparable::Comparable,
+    Clone,
+    PartialEq,
+    ::prost::Message,
+)]
+pub struct RefundFollowOnRoundTokensResponse {
+    #[prost(
+        oneof = "refund_follow_on_round_tokens_response::Result",
+        tags = "1, 2"
+    )]
+    pub result: ::core::option::Option<refund_follow_on_round_tokens_response::Result>,
+}
+/// Nested message and enum types in `RefundFollowOnRoundTokensResponse`.
+pub mod refund_follow_on_round_tokens_response {
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        PartialEq,
+        ::prost::Message,
+    )]
+    pub struct Ok {
+        #[prost(uint64, optional, tag = "1")]
+        pub block_height: ::core::option::Option<u64>,
+    }
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        PartialEq,
+        ::prost::Message,
+    )]
+    pub struct Err {
+        #[prost(string, optional, tag = "1")]
+        pub description: ::core::option::Option<::prost::alloc::string::String>,
+    }
+    #[derive(
+        candid::CandidType,
+        candid::Deserialize,
+        comparable::Comparable,
+        Clone,
+        PartialEq,
+        ::prost::Oneof,
+    )]
+    pub enum Result {
+        #[prost(message, tag = "1")]
+        Ok(Ok),
+        #[prost(message, tag = "2")]
+        Err(Err),
+    }
+}
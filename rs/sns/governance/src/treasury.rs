@@ -1,10 +1,12 @@
 use crate::pb::v1::{
     valuation::{Token as TokenPb, ValuationFactors as ValuationFactorsPb},
-    Account as AccountPb, Valuation as ValuationPb,
+    Account as AccountPb, CreateTreasuryDisbursementSchedule, TransferSnsTreasuryFunds,
+    TreasuryDisbursementSchedule, TreasuryPayout, Valuation as ValuationPb,
 };
 use candid::Principal;
 use ic_base_types::PrincipalId;
-use ic_nervous_system_common::E8;
+use ic_crypto_sha2::Sha256;
+use ic_nervous_system_common::{E8, NANO_SECONDS_PER_SECOND, ONE_DAY_SECONDS};
 use ic_nervous_system_proto::pb::v1::{Decimal as DecimalPb, Tokens};
 use ic_sns_governance_token_valuation::{Token, Valuation, ValuationFactors};
 use icrc_ledger_types::icrc1::account::Account;
@@ -277,3 +279,163 @@ impl<'a> Default for &'a ValuationFactorsPb {
         &DEFAULT
     }
 }
+
+/// How long to wait before retrying a scheduled treasury payout that failed, e.g., because it
+/// would have exceeded the 7-day upper bound on treasury transfers.
+pub(crate) const TREASURY_PAYOUT_RETRY_INTERVAL_SECONDS: u64 = 60 * 60;
+
+/// How long the retries of a scheduled treasury payout reuse the created_at_time of its first
+/// attempt. This stays below the ledgers' 24-hour deduplication window, beyond which they would
+/// reject the transfer as too old.
+pub(crate) const TREASURY_PAYOUT_DEDUPLICATION_SECONDS: u64 =
+    ONE_DAY_SECONDS - TREASURY_PAYOUT_RETRY_INTERVAL_SECONDS;
+
+impl TreasuryDisbursementSchedule {
+    /// Creates the schedule of a CreateTreasuryDisbursementSchedule proposal executed at
+    /// `now_seconds`.
+    pub(crate) fn new(
+        proposal_id: u64,
+        parameters: CreateTreasuryDisbursementSchedule,
+        now_seconds: u64,
+    ) -> Self {
+        let first_payout_timestamp_seconds = now_seconds.saturating_add(parameters.cliff_seconds());
+        Self {
+            id: Some(proposal_id),
+            parameters: Some(parameters),
+            first_payout_timestamp_seconds: Some(first_payout_timestamp_seconds),
+            payouts: vec![],
+            last_failure_reason: None,
+            last_failure_timestamp_seconds: None,
+            next_payout_created_at_time_nanos: None,
+        }
+    }
+
+    /// When the next payout is due, or None if all payouts have been made.
+    pub(crate) fn next_payout_timestamp_seconds(&self) -> Option<u64> {
+        let parameters = self.parameters.as_ref()?;
+        let payout_count = self.payouts.len() as u64;
+        if payout_count >= parameters.number_of_periods() {
+            return None;
+        }
+        Some(
+            self.first_payout_timestamp_seconds()
+                .saturating_add(payout_count.saturating_mul(parameters.period_seconds())),
+        )
+    }
+
+    /// Whether a payout should be attempted at `now_seconds`. Payouts that fell behind (e.g.,
+    /// because the 7-day upper bound was reached) are caught up one at a time.
+    pub(crate) fn is_payout_due(&self, now_seconds: u64) -> bool {
+        let Some(next_payout_timestamp_seconds) = self.next_payout_timestamp_seconds() else {
+            return false;
+        };
+        if now_seconds < next_payout_timestamp_seconds {
+            return false;
+        }
+        match self.last_failure_timestamp_seconds {
+            Some(last_failure_timestamp_seconds) => {
+                now_seconds
+                    >= last_failure_timestamp_seconds
+                        .saturating_add(TREASURY_PAYOUT_RETRY_INTERVAL_SECONDS)
+            }
+            None => true,
+        }
+    }
+
+    /// The transfer that makes the next payout of this schedule. Its memo identifies the payout,
+    /// see `treasury_payout_memo`.
+    pub(crate) fn payout_transfer(&self) -> Option<TransferSnsTreasuryFunds> {
+        let CreateTreasuryDisbursementSchedule {
+            from_treasury,
+            amount_per_period_e8s,
+            number_of_periods: _,
+            period_seconds: _,
+            cliff_seconds: _,
+            to_principal,
+            to_subaccount,
+        } = self.parameters.clone()?;
+        Some(TransferSnsTreasuryFunds {
+            from_treasury,
+            amount_e8s: amount_per_period_e8s?,
+            memo: Some(treasury_payout_memo(self.id?, self.payouts.len() as u64)),
+            to_principal,
+            to_subaccount,
+        })
+    }
+
+    /// The created_at_time of the transfer of the next payout. The first attempt sets it, and
+    /// the retries reuse it for up to `TREASURY_PAYOUT_DEDUPLICATION_SECONDS`, so that the ledger
+    /// recognizes them as duplicates of an earlier attempt that went through.
+    pub(crate) fn payout_created_at_time_nanos(&mut self, now_seconds: u64) -> u64 {
+        let now_nanos = now_seconds.saturating_mul(NANO_SECONDS_PER_SECOND);
+        let deduplication_nanos =
+            TREASURY_PAYOUT_DEDUPLICATION_SECONDS.saturating_mul(NANO_SECONDS_PER_SECOND);
+        match self.next_payout_created_at_time_nanos {
+            Some(created_at_time_nanos)
+                if now_nanos < created_at_time_nanos.saturating_add(deduplication_nanos) =>
+            {
+                created_at_time_nanos
+            }
+            _ => {
+                self.next_payout_created_at_time_nanos = Some(now_nanos);
+                now_nanos
+            }
+        }
+    }
+
+    /// Records a successful payout.
+    pub(crate) fn record_payout(&mut self, payout: TreasuryPayout) {
+        self.payouts.push(payout);
+        self.last_failure_reason = None;
+        self.last_failure_timestamp_seconds = None;
+        self.next_payout_created_at_time_nanos = None;
+    }
+
+    /// Records a failed payout attempt, which is retried later.
+    pub(crate) fn record_failure(&mut self, reason: String, now_seconds: u64) {
+        self.last_failure_reason = Some(reason);
+        self.last_failure_timestamp_seconds = Some(now_seconds);
+    }
+}
+
+/// The memo of payout `payout_index` of the treasury disbursement schedule `schedule_id`. Each
+/// payout gets a distinct memo, so that the ledger only deduplicates the retries of one payout.
+pub(crate) fn treasury_payout_memo(schedule_id: u64, payout_index: u64) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.write(b"treasury-payout");
+    hasher.write(&schedule_id.to_be_bytes());
+    hasher.write(&payout_index.to_be_bytes());
+    let hash = hasher.finish();
+    u64::from_be_bytes(hash[..8].try_into().expect("hash is 32 bytes long"))
+}
+
+/// How long scheduled treasury payouts are kept in `Governance.recent_treasury_payouts`, i.e., the
+/// window of the upper bound on treasury transfers.
+pub(crate) const RECENT_TREASURY_PAYOUTS_WINDOW_SECONDS: u64 = 7 * ONE_DAY_SECONDS;
+
+/// The total amount of the `payouts` made from `from_treasury` at or after
+/// `min_timestamp_seconds`.
+pub(crate) fn recent_treasury_payouts_e8s(
+    payouts: &[TreasuryPayout],
+    from_treasury: i32,
+    min_timestamp_seconds: u64,
+) -> u64 {
+    payouts
+        .iter()
+        .filter(|payout| {
+            payout.from_treasury == from_treasury
+                && payout.timestamp_seconds() >= min_timestamp_seconds
+        })
+        .fold(0_u64, |total, payout| {
+            total.saturating_add(payout.amount_e8s())
+        })
+}
+
+/// Drops the `payouts` that fell out of the window of the upper bound on treasury transfers.
+pub(crate) fn prune_recent_treasury_payouts(payouts: &mut Vec<TreasuryPayout>, now_seconds: u64) {
+    let min_timestamp_seconds = now_seconds.saturating_sub(RECENT_TREASURY_PAYOUTS_WINDOW_SECONDS);
+    payouts.retain(|payout| payout.timestamp_seconds() >= min_timestamp_seconds);
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,168 @@
+use super::*;
+use crate::pb::v1::transfer_sns_treasury_funds::TransferFrom;
+use ic_nervous_system_common::ONE_MONTH_SECONDS;
+
+const NOW_SECONDS: u64 = 1_700_000_000;
+
+fn monthly_schedule() -> TreasuryDisbursementSchedule {
+    TreasuryDisbursementSchedule::new(
+        42,
+        CreateTreasuryDisbursementSchedule {
+            from_treasury: TransferFrom::SnsTokenTreasury as i32,
+            amount_per_period_e8s: Some(100 * E8),
+            number_of_periods: Some(3),
+            period_seconds: Some(ONE_MONTH_SECONDS),
+            cliff_seconds: Some(ONE_MONTH_SECONDS),
+            to_principal: Some(PrincipalId::new_user_test_id(1)),
+            to_subaccount: None,
+        },
+        NOW_SECONDS,
+    )
+}
+
+fn payout(timestamp_seconds: u64) -> TreasuryPayout {
+    TreasuryPayout {
+        timestamp_seconds: Some(timestamp_seconds),
+        amount_e8s: Some(100 * E8),
+        block_height: Some(1),
+        from_treasury: TransferFrom::SnsTokenTreasury as i32,
+    }
+}
+
+#[test]
+fn test_treasury_disbursement_schedule_payouts_are_due_after_the_cliff_and_each_period() {
+    let mut schedule = monthly_schedule();
+    let first_payout_timestamp_seconds = NOW_SECONDS + ONE_MONTH_SECONDS;
+
+    assert_eq!(
+        schedule.next_payout_timestamp_seconds(),
+        Some(first_payout_timestamp_seconds)
+    );
+    assert!(!schedule.is_payout_due(first_payout_timestamp_seconds - 1));
+    assert!(schedule.is_payout_due(first_payout_timestamp_seconds));
+
+    schedule.record_payout(payout(first_payout_timestamp_seconds));
+    assert_eq!(
+        schedule.next_payout_timestamp_seconds(),
+        Some(first_payout_timestamp_seconds + ONE_MONTH_SECONDS)
+    );
+    assert!(!schedule.is_payout_due(first_payout_timestamp_seconds + 1));
+
+    schedule.record_payout(payout(first_payout_timestamp_seconds + ONE_MONTH_SECONDS));
+    schedule.record_payout(payout(
+        first_payout_timestamp_seconds + 2 * ONE_MONTH_SECONDS,
+    ));
+    assert_eq!(schedule.next_payout_timestamp_seconds(), None);
+    assert!(!schedule.is_payout_due(u64::MAX));
+}
+
+#[test]
+fn test_treasury_disbursement_schedule_throttles_retries() {
+    let mut schedule = monthly_schedule();
+    let due_timestamp_seconds = NOW_SECONDS + ONE_MONTH_SECONDS;
+
+    schedule.record_failure("Amount is too large.".to_string(), due_timestamp_seconds);
+    assert!(!schedule.is_payout_due(due_timestamp_seconds + 1));
+    assert!(schedule.is_payout_due(due_timestamp_seconds + TREASURY_PAYOUT_RETRY_INTERVAL_SECONDS));
+
+    // A successful payout clears the failure.
+    schedule.record_payout(payout(
+        due_timestamp_seconds + TREASURY_PAYOUT_RETRY_INTERVAL_SECONDS,
+    ));
+    assert_eq!(schedule.last_failure_reason, None);
+    assert_eq!(schedule.last_failure_timestamp_seconds, None);
+}
+
+#[test]
+fn test_treasury_disbursement_schedule_payout_transfer() {
+    let schedule = monthly_schedule();
+    assert_eq!(
+        schedule.payout_transfer(),
+        Some(TransferSnsTreasuryFunds {
+            from_treasury: TransferFrom::SnsTokenTreasury as i32,
+            amount_e8s: 100 * E8,
+            memo: Some(treasury_payout_memo(42, 0)),
+            to_principal: Some(PrincipalId::new_user_test_id(1)),
+            to_subaccount: None,
+        })
+    );
+}
+
+#[test]
+fn test_treasury_disbursement_schedule_payout_retries_are_deduplicated() {
+    let mut schedule = monthly_schedule();
+    let due_timestamp_seconds = NOW_SECONDS + ONE_MONTH_SECONDS;
+    let due_timestamp_nanos = due_timestamp_seconds * NANO_SECONDS_PER_SECOND;
+
+    let transfer = schedule.payout_transfer().unwrap();
+    assert_eq!(
+        schedule.payout_created_at_time_nanos(due_timestamp_seconds),
+        due_timestamp_nanos
+    );
+
+    // The outcome of the first attempt is unknown, so it is retried as the same transfer.
+    schedule.record_failure("Timeout.".to_string(), due_timestamp_seconds);
+    let retry_timestamp_seconds = due_timestamp_seconds + TREASURY_PAYOUT_RETRY_INTERVAL_SECONDS;
+    assert_eq!(schedule.payout_transfer().unwrap(), transfer);
+    assert_eq!(
+        schedule.payout_created_at_time_nanos(retry_timestamp_seconds),
+        due_timestamp_nanos
+    );
+
+    // Close to the end of the ledger's deduplication window, retries start over.
+    let late_retry_timestamp_seconds =
+        due_timestamp_seconds + TREASURY_PAYOUT_DEDUPLICATION_SECONDS;
+    assert_eq!(
+        schedule.payout_created_at_time_nanos(late_retry_timestamp_seconds),
+        late_retry_timestamp_seconds * NANO_SECONDS_PER_SECOND
+    );
+
+    // The next payout is a different transfer.
+    schedule.record_payout(payout(late_retry_timestamp_seconds));
+    assert_eq!(schedule.next_payout_created_at_time_nanos, None);
+    let next_transfer = schedule.payout_transfer().unwrap();
+    assert_eq!(next_transfer.memo, Some(treasury_payout_memo(42, 1)));
+    assert_ne!(next_transfer.memo, transfer.memo);
+}
+
+#[test]
+fn test_treasury_payout_memos_are_distinct() {
+    let memos = [
+        treasury_payout_memo(42, 0),
+        treasury_payout_memo(42, 1),
+        treasury_payout_memo(43, 0),
+        treasury_payout_memo(0, 42),
+    ];
+    for (i, memo) in memos.iter().enumerate() {
+        assert!(!memos[i + 1..].contains(memo), "{:?}", memos);
+    }
+}
+
+#[test]
+fn test_recent_treasury_payouts_are_summed_and_pruned() {
+    let mut payouts = vec![payout(NOW_SECONDS), payout(NOW_SECONDS + 10)];
+
+    let sns = TransferFrom::SnsTokenTreasury as i32;
+    let icp = TransferFrom::IcpTreasury as i32;
+    assert_eq!(
+        recent_treasury_payouts_e8s(&payouts, sns, NOW_SECONDS),
+        200 * E8
+    );
+    assert_eq!(
+        recent_treasury_payouts_e8s(&payouts, sns, NOW_SECONDS + 1),
+        100 * E8
+    );
+    assert_eq!(recent_treasury_payouts_e8s(&payouts, icp, NOW_SECONDS), 0);
+
+    prune_recent_treasury_payouts(
+        &mut payouts,
+        NOW_SECONDS + RECENT_TREASURY_PAYOUTS_WINDOW_SECONDS,
+    );
+    assert_eq!(payouts, vec![payout(NOW_SECONDS), payout(NOW_SECONDS + 10)]);
+
+    prune_recent_treasury_payouts(
+        &mut payouts,
+        NOW_SECONDS + RECENT_TREASURY_PAYOUTS_WINDOW_SECONDS + 1,
+    );
+    assert_eq!(payouts, vec![payout(NOW_SECONDS + 10)]);
+}
@@ -118,6 +118,21 @@ pub mod native_action_ids {
 
     /// ManageDappCanisterSettings Action.
     pub const MANAGE_DAPP_CANISTER_SETTINGS: u64 = 14;
+
+    /// LaunchFollowOnSwap Action.
+    pub const LAUNCH_FOLLOW_ON_SWAP: u64 = 15;
+
+    /// CreateTreasuryDisbursementSchedule Action.
+    pub const CREATE_TREASURY_DISBURSEMENT_SCHEDULE: u64 = 16;
+
+    /// CancelTreasuryDisbursementSchedule Action.
+    pub const CANCEL_TREASURY_DISBURSEMENT_SCHEDULE: u64 = 17;
+
+    /// RegisterKnownNeuron Action.
+    pub const REGISTER_KNOWN_NEURON: u64 = 18;
+
+    /// DeregisterKnownNeuron Action.
+    pub const DEREGISTER_KNOWN_NEURON: u64 = 19;
 }
 
 impl governance::Mode {
@@ -1168,6 +1183,64 @@ impl NervousSystemFunction {
             function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
         }
     }
+
+    fn launch_follow_on_swap() -> NervousSystemFunction {
+        NervousSystemFunction {
+            id: native_action_ids::LAUNCH_FOLLOW_ON_SWAP,
+            name: "Launch follow-on swap".to_string(),
+            description: Some(
+                "Proposal to sell additional SNS tokens in a follow-on swap round.".to_string(),
+            ),
+            function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+        }
+    }
+
+    fn create_treasury_disbursement_schedule() -> NervousSystemFunction {
+        NervousSystemFunction {
+            id: native_action_ids::CREATE_TREASURY_DISBURSEMENT_SCHEDULE,
+            name: "Create treasury disbursement schedule".to_string(),
+            description: Some(
+                "Proposal to pay out SNS treasury funds to an account in installments.".to_string(),
+            ),
+            function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+        }
+    }
+
+    fn cancel_treasury_disbursement_schedule() -> NervousSystemFunction {
+        NervousSystemFunction {
+            id: native_action_ids::CANCEL_TREASURY_DISBURSEMENT_SCHEDULE,
+            name: "Cancel treasury disbursement schedule".to_string(),
+            description: Some(
+                "Proposal to cancel the remaining payouts of a treasury disbursement schedule."
+                    .to_string(),
+            ),
+            function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+        }
+    }
+
+    fn register_known_neuron() -> NervousSystemFunction {
+        NervousSystemFunction {
+            id: native_action_ids::REGISTER_KNOWN_NEURON,
+            name: "Register known neuron".to_string(),
+            description: Some(
+                "Proposal to give a neuron a name and a description, so that voters can find it \
+                 as a followee."
+                    .to_string(),
+            ),
+            function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+        }
+    }
+
+    fn deregister_known_neuron() -> NervousSystemFunction {
+        NervousSystemFunction {
+            id: native_action_ids::DEREGISTER_KNOWN_NEURON,
+            name: "Deregister known neuron".to_string(),
+            description: Some(
+                "Proposal to remove the name and description of a known neuron.".to_string(),
+            ),
+            function_type: Some(FunctionType::NativeNervousSystemFunction(Empty {})),
+        }
+    }
 }
 
 impl From<Action> for NervousSystemFunction {
@@ -1208,6 +1281,15 @@ impl From<Action> for NervousSystemFunction {
             Action::ManageDappCanisterSettings(_) => {
                 NervousSystemFunction::manage_dapp_canister_settings()
             }
+            Action::LaunchFollowOnSwap(_) => NervousSystemFunction::launch_follow_on_swap(),
+            Action::CreateTreasuryDisbursementSchedule(_) => {
+                NervousSystemFunction::create_treasury_disbursement_schedule()
+            }
+            Action::CancelTreasuryDisbursementSchedule(_) => {
+                NervousSystemFunction::cancel_treasury_disbursement_schedule()
+            }
+            Action::RegisterKnownNeuron(_) => NervousSystemFunction::register_known_neuron(),
+            Action::DeregisterKnownNeuron(_) => NervousSystemFunction::deregister_known_neuron(),
         }
     }
 }
@@ -1647,9 +1729,11 @@ impl Action {
     fn proposal_criticality(&self) -> ProposalCriticality {
         use Action::*;
         match self {
-            DeregisterDappCanisters(_) | TransferSnsTreasuryFunds(_) | MintSnsTokens(_) => {
-                ProposalCriticality::Critical
-            }
+            DeregisterDappCanisters(_)
+            | TransferSnsTreasuryFunds(_)
+            | MintSnsTokens(_)
+            | LaunchFollowOnSwap(_)
+            | CreateTreasuryDisbursementSchedule(_) => ProposalCriticality::Critical,
 
             Unspecified(_)
             | ManageNervousSystemParameters(_)
@@ -1662,7 +1746,10 @@ impl Action {
             | ManageSnsMetadata(_)
             | ManageLedgerParameters(_)
             | RegisterDappCanisters(_)
-            | ManageDappCanisterSettings(_) => ProposalCriticality::Normal,
+            | ManageDappCanisterSettings(_)
+            | CancelTreasuryDisbursementSchedule(_)
+            | RegisterKnownNeuron(_)
+            | DeregisterKnownNeuron(_) => ProposalCriticality::Normal,
         }
     }
 }
@@ -1845,6 +1932,15 @@ impl From<&Action> for u64 {
             Action::ManageDappCanisterSettings(_) => {
                 native_action_ids::MANAGE_DAPP_CANISTER_SETTINGS
             }
+            Action::LaunchFollowOnSwap(_) => native_action_ids::LAUNCH_FOLLOW_ON_SWAP,
+            Action::CreateTreasuryDisbursementSchedule(_) => {
+                native_action_ids::CREATE_TREASURY_DISBURSEMENT_SCHEDULE
+            }
+            Action::CancelTreasuryDisbursementSchedule(_) => {
+                native_action_ids::CANCEL_TREASURY_DISBURSEMENT_SCHEDULE
+            }
+            Action::RegisterKnownNeuron(_) => native_action_ids::REGISTER_KNOWN_NEURON,
+            Action::DeregisterKnownNeuron(_) => native_action_ids::DEREGISTER_KNOWN_NEURON,
         }
     }
 }
@@ -15,6 +15,7 @@ use ic_nervous_system_clients::{
 use ic_nervous_system_common::{serve_logs, serve_logs_v2, serve_metrics};
 use ic_nervous_system_runtime::DfnRuntime;
 use ic_sns_swap::{
+    environment::CanisterEnvironment,
     logs::{ERROR, INFO},
     memory::UPGRADES_MEMORY,
     pb::v1::{
@@ -28,7 +29,9 @@ use ic_sns_swap::{
         ListCommunityFundParticipantsResponse, ListDirectParticipantsRequest,
         ListDirectParticipantsResponse, ListSnsNeuronRecipesRequest, ListSnsNeuronRecipesResponse,
         NewSaleTicketRequest, NewSaleTicketResponse, NotifyPaymentFailureRequest,
-        NotifyPaymentFailureResponse, RefreshBuyerTokensRequest, RefreshBuyerTokensResponse, Swap,
+        NotifyPaymentFailureResponse, OpenFollowOnRoundRequest, OpenFollowOnRoundResponse,
+        RefreshBuyerTokensRequest, RefreshBuyerTokensResponse, RefundFollowOnRoundTokensRequest,
+        RefundFollowOnRoundTokensResponse, Swap,
     },
 };
 use ic_stable_structures::{writer::Writer, Memory};
@@ -182,6 +185,48 @@ async fn error_refund_icp_(request: ErrorRefundIcpRequest) -> ErrorRefundIcpResp
     swap().error_refund_icp(id(), &request, &icp_ledger).await
 }
 
+/// See Swap.open_follow_on_round.
+#[export_name = "canister_update open_follow_on_round"]
+fn open_follow_on_round() {
+    over_async(candid_one, open_follow_on_round_)
+}
+
+/// See Swap.open_follow_on_round.
+#[candid_method(update, rename = "open_follow_on_round")]
+async fn open_follow_on_round_(request: OpenFollowOnRoundRequest) -> OpenFollowOnRoundResponse {
+    log!(INFO, "open_follow_on_round");
+    let clients = swap()
+        .init_or_panic()
+        .environment()
+        .expect("unable to create canister clients");
+
+    swap_mut()
+        .open_follow_on_round(caller(), request, now_seconds(), id(), clients.sns_ledger())
+        .await
+}
+
+/// See Swap.refund_follow_on_round_tokens.
+#[export_name = "canister_update refund_follow_on_round_tokens"]
+fn refund_follow_on_round_tokens() {
+    over_async(candid_one, refund_follow_on_round_tokens_)
+}
+
+/// See Swap.refund_follow_on_round_tokens.
+#[candid_method(update, rename = "refund_follow_on_round_tokens")]
+async fn refund_follow_on_round_tokens_(
+    request: RefundFollowOnRoundTokensRequest,
+) -> RefundFollowOnRoundTokensResponse {
+    log!(INFO, "refund_follow_on_round_tokens");
+    let clients = swap()
+        .init_or_panic()
+        .environment()
+        .expect("unable to create canister clients");
+
+    swap()
+        .refund_follow_on_round_tokens(caller(), request, id(), clients.sns_ledger())
+        .await
+}
+
 #[export_name = "canister_update get_canister_status"]
 fn get_canister_status() {
     over_async(candid_one, get_canister_status_)
@@ -229,9 +229,64 @@ pub struct Swap {
     /// Amount of contributions from the Neurons' Fund committed to this SNS so far.
     #[prost(uint64, optional, tag = "20")]
     pub neurons_fund_participation_icp_e8s: ::core::option::Option<u64>,
+    /// The sale round that the swap is currently running. The initial
+    /// decentralization swap is round 0; every call to `open_follow_on_round`
+    /// increments this value. Unset is equivalent to 0.
+    #[prost(uint64, optional, tag = "22")]
+    pub current_round: ::core::option::Option<u64>,
+    /// Summaries of the sale rounds that have been completed (and whose state
+    /// has been cleared) before the current round, in increasing round order.
+    #[prost(message, repeated, tag = "23")]
+    pub previous_rounds: ::prost::alloc::vec::Vec<SaleRound>,
+    /// The ID of the SNS proposal that opened the current follow-on round. Unset
+    /// for the initial decentralization swap.
+    #[prost(uint64, optional, tag = "24")]
+    pub follow_on_sns_proposal_id: ::core::option::Option<u64>,
+}
+/// A summary of a completed sale round. Recorded when the swap state of that
+/// round is cleared to make room for a follow-on round.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SaleRound {
+    /// The number of the round. The initial decentralization swap is round 0.
+    #[prost(uint64, optional, tag = "1")]
+    pub round: ::core::option::Option<u64>,
+    /// The terminal lifecycle the round ended in (COMMITTED or ABORTED).
+    #[prost(enumeration = "Lifecycle", tag = "2")]
+    pub lifecycle: i32,
+    /// When the round was opened for participation.
+    #[prost(uint64, optional, tag = "3")]
+    pub open_timestamp_seconds: ::core::option::Option<u64>,
+    /// When the round was committed or aborted.
+    #[prost(uint64, optional, tag = "4")]
+    pub termination_timestamp_seconds: ::core::option::Option<u64>,
+    /// The number of SNS tokens offered in the round.
+    #[prost(uint64, optional, tag = "5")]
+    pub sns_token_e8s: ::core::option::Option<u64>,
+    /// The total ICP contributed by direct participants.
+    #[prost(uint64, optional, tag = "6")]
+    pub direct_participation_icp_e8s: ::core::option::Option<u64>,
+    /// The total ICP contributed by the Neurons' Fund.
+    #[prost(uint64, optional, tag = "7")]
+    pub neurons_fund_participation_icp_e8s: ::core::option::Option<u64>,
+    /// The number of direct participants.
+    #[prost(uint64, optional, tag = "8")]
+    pub direct_participant_count: ::core::option::Option<u64>,
+    /// The number of SNS neurons created for the participants of the round.
+    #[prost(uint64, optional, tag = "9")]
+    pub sns_neuron_count: ::core::option::Option<u64>,
+    /// The ID of the NNS proposal associated with the round, if any.
+    #[prost(uint64, optional, tag = "10")]
+    pub nns_proposal_id: ::core::option::Option<u64>,
+    /// The ID of the SNS proposal that opened the round. Unset for round 0.
+    #[prost(uint64, optional, tag = "11")]
+    pub sns_proposal_id: ::core::option::Option<u64>,
 }
 /// The initialisation data of the canister. Always specified on
-/// canister creation, and cannot be modified afterwards.
+/// canister creation, and cannot be modified afterwards, except that the
+/// sale parameters of the current round are replaced when a follow-on round
+/// is opened (see `OpenFollowOnRoundRequest`).
 ///
 /// If the initialization parameters are incorrect, the swap will
 /// immediately be aborted.
@@ -1672,6 +1727,132 @@ pub mod error_refund_icp_response {
         Err(Err),
     }
 }
+/// Request to open a follow-on sale round, issued by SNS governance when a
+/// `LaunchFollowOnSwap` proposal is executed. The previous round must be
+/// finalized, and the SNS tokens to be offered must already be held by the
+/// swap canister.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenFollowOnRoundRequest {
+    /// The ID of the SNS proposal launching this round.
+    #[prost(uint64, optional, tag = "1")]
+    pub sns_proposal_id: ::core::option::Option<u64>,
+    /// The number of SNS tokens offered in this round.
+    #[prost(uint64, optional, tag = "2")]
+    pub sns_token_e8s: ::core::option::Option<u64>,
+    /// Same meaning as the corresponding fields of `Init`, for this round.
+    #[prost(uint32, optional, tag = "3")]
+    pub min_participants: ::core::option::Option<u32>,
+    #[prost(uint64, optional, tag = "4")]
+    pub min_direct_participation_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub max_direct_participation_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "6")]
+    pub min_participant_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "7")]
+    pub max_participant_icp_e8s: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "8")]
+    pub swap_start_timestamp_seconds: ::core::option::Option<u64>,
+    #[prost(uint64, optional, tag = "9")]
+    pub swap_due_timestamp_seconds: ::core::option::Option<u64>,
+    #[prost(message, optional, tag = "10")]
+    pub neuron_basket_construction_parameters:
+        ::core::option::Option<NeuronBasketConstructionParameters>,
+}
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct OpenFollowOnRoundResponse {
+    #[prost(oneof = "open_follow_on_round_response::Result", tags = "1, 2")]
+    pub result: ::core::option::Option<open_follow_on_round_response::Result>,
+}
+/// Nested message and enum types in `OpenFollowOnRoundResponse`.
+pub mod open_follow_on_round_response {
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Ok {
+        /// The number of the round that was opened.
+        #[prost(uint64, optional, tag = "1")]
+        pub round: ::core::option::Option<u64>,
+    }
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Err {
+        #[prost(string, optional, tag = "1")]
+        pub description: ::core::option::Option<::prost::alloc::string::String>,
+    }
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Result {
+        #[prost(message, tag = "1")]
+        Ok(Ok),
+        #[prost(message, tag = "2")]
+        Err(Err),
+    }
+}
+/// Request to return SNS tokens that SNS governance sent for a follow-on round
+/// that could not be opened. Only the tokens not needed by the current round can
+/// be returned.
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefundFollowOnRoundTokensRequest {
+    /// The ID of the SNS proposal whose tokens are returned. Used as the memo of
+    /// the transfer.
+    #[prost(uint64, optional, tag = "1")]
+    pub sns_proposal_id: ::core::option::Option<u64>,
+    /// The amount to transfer, excluding the fee.
+    #[prost(uint64, optional, tag = "2")]
+    pub amount_e8s: ::core::option::Option<u64>,
+    /// The fee of the transfer. Zero when the tokens are burned, i.e., returned
+    /// to the minting account.
+    #[prost(uint64, optional, tag = "3")]
+    pub fee_e8s: ::core::option::Option<u64>,
+    /// The subaccount of SNS governance to which the tokens are returned.
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub to_subaccount: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+}
+#[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RefundFollowOnRoundTokensResponse {
+    #[prost(
+        oneof = "refund_follow_on_round_tokens_response::Result",
+        tags = "1, 2"
+    )]
+    pub result: ::core::option::Option<refund_follow_on_round_tokens_response::Result>,
+}
+/// Nested message and enum types in `RefundFollowOnRoundTokensResponse`.
+pub mod refund_follow_on_round_tokens_response {
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Ok {
+        /// The index of the ledger block of the transfer.
+        #[prost(uint64, optional, tag = "1")]
+        pub block_height: ::core::option::Option<u64>,
+    }
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Err {
+        #[prost(string, optional, tag = "1")]
+        pub description: ::core::option::Option<::prost::alloc::string::String>,
+    }
+    #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Result {
+        #[prost(message, tag = "1")]
+        Ok(Ok),
+        #[prost(message, tag = "2")]
+        Err(Err),
+    }
+}
 /// Request struct for the method `get_lifecycle`
 #[derive(candid::CandidType, candid::Deserialize, serde::Serialize, comparable::Comparable)]
 #[allow(clippy::derive_partial_eq_without_eq)]
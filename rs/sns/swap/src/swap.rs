@@ -20,9 +20,11 @@ use crate::{
         ListCommunityFundParticipantsResponse, ListDirectParticipantsRequest,
         ListDirectParticipantsResponse, ListSnsNeuronRecipesRequest, ListSnsNeuronRecipesResponse,
         NeuronBasketConstructionParameters, NeuronId as SwapNeuronId, NewSaleTicketRequest,
-        NewSaleTicketResponse, NotifyPaymentFailureResponse, Participant,
-        RefreshBuyerTokensResponse, SetDappControllersCallResult, SetDappControllersRequest,
-        SetDappControllersResponse, SetModeCallResult, SettleNeuronsFundParticipationRequest,
+        NewSaleTicketResponse, NotifyPaymentFailureResponse, OpenFollowOnRoundRequest,
+        OpenFollowOnRoundResponse, Participant, RefreshBuyerTokensResponse,
+        RefundFollowOnRoundTokensRequest, RefundFollowOnRoundTokensResponse, SaleRound,
+        SetDappControllersCallResult, SetDappControllersRequest, SetDappControllersResponse,
+        SetModeCallResult, SettleNeuronsFundParticipationRequest,
         SettleNeuronsFundParticipationResponse, SettleNeuronsFundParticipationResult,
         SnsNeuronRecipe, Swap, SweepResult, Ticket, TransferableAmount,
     },
@@ -90,6 +92,14 @@ const DEFAULT_LIST_SNS_NEURON_RECIPES_LIMIT: u32 = 10_000;
 pub const NEURON_BASKET_MEMO_RANGE_START: u64 = 1_000_000;
 pub const SALE_NEURON_MEMO_RANGE_END: u64 = 10_000_000;
 
+/// Each sale round picks its neuron basket memos from its own slice of the range above, so that
+/// a participant of several rounds does not end up with colliding neuron IDs.
+pub const SALE_ROUND_MEMO_RANGE_SIZE: u64 = 1_000_000;
+
+/// The maximum number of sale rounds, including the initial decentralization swap.
+pub const MAX_SALE_ROUNDS: u64 =
+    (SALE_NEURON_MEMO_RANGE_END - NEURON_BASKET_MEMO_RANGE_START) / SALE_ROUND_MEMO_RANGE_SIZE;
+
 /// The principal with all bytes set to zero. The main property
 /// of this principal is that for any principal p, the following condition holds:
 /// (p != FIRST_PRINCIPAL_BYTES) ==> FIRST_PRINCIPAL_BYTES.as_slice() < p.as_slice()
@@ -422,6 +432,9 @@ impl Swap {
             auto_finalize_swap_response: None,
             direct_participation_icp_e8s: None,
             neurons_fund_participation_icp_e8s: None,
+            current_round: Some(0),
+            previous_rounds: vec![],
+            follow_on_sns_proposal_id: None,
         };
         if init.validate_swap_init_for_one_proposal_flow().is_ok() {
             // Automatically fill out the fields that the (legacy) open request
@@ -665,6 +678,26 @@ impl Swap {
         self.lifecycle().is_terminal()
     }
 
+    /// The sale round that the Swap is currently running (0 for the initial
+    /// decentralization swap).
+    pub fn current_round(&self) -> u64 {
+        self.current_round.unwrap_or(0)
+    }
+
+    /// Whether the Swap is running the initial decentralization swap, as opposed to a
+    /// follow-on round opened by SNS governance.
+    pub fn is_initial_round(&self) -> bool {
+        self.current_round() == 0
+    }
+
+    /// The first memo of the neuron basket memo range reserved for the current round.
+    fn neuron_basket_memo_range_start(&self) -> u64 {
+        NEURON_BASKET_MEMO_RANGE_START.saturating_add(
+            self.current_round()
+                .saturating_mul(SALE_ROUND_MEMO_RANGE_SIZE),
+        )
+    }
+
     //
     // --- state transition functions ------------------------------------------
     //
@@ -825,6 +858,8 @@ impl Swap {
         // is correct at the end.
         let mut total_sns_tokens_sold_e8s: u64 = 0;
 
+        let neuron_basket_memo_range_start = self.neuron_basket_memo_range_start();
+
         // =====================================================================
         // ===            This is where the actual swap happens              ===
         // =====================================================================
@@ -851,7 +886,7 @@ impl Swap {
                 &buyer_principal,
                 amount_sns_e8s,
                 neuron_basket_construction_parameters,
-                NEURON_BASKET_MEMO_RANGE_START,
+                neuron_basket_memo_range_start,
             ) {
                 Ok(direct_participant_sns_neuron_recipes) => {
                     self.neuron_recipes
@@ -880,7 +915,7 @@ impl Swap {
         // investors in the swap use the NNS Governance principal_id, there can be
         // neuron id collisions, so there must be a global memo used for all baskets
         // for all NF investors.
-        let mut global_neurons_fund_memo: u64 = NEURON_BASKET_MEMO_RANGE_START;
+        let mut global_neurons_fund_memo: u64 = neuron_basket_memo_range_start;
         for neurons_fund_participant in self.cf_participants.iter_mut() {
             let controller = neurons_fund_participant.try_get_controller();
 
@@ -1332,11 +1367,361 @@ impl Swap {
         }
     }
 
+    /// Opens a follow-on sale round on behalf of SNS governance.
+    ///
+    /// The previous round must be over and fully finalized: in a committed round every
+    /// neuron has been claimed, and in either case every participant's ICP has been
+    /// swept. The previous round is then summarized in `previous_rounds`, its
+    /// per-participant state is cleared, the sale parameters in `init` are replaced
+    /// by those of the request, and the Swap goes back to `Lifecycle::Adopted`, from
+    /// where it opens, commits or aborts, and finalizes just like the initial swap.
+    ///
+    /// The SNS tokens offered must already be held by the Swap canister; SNS
+    /// governance mints or transfers them before calling this method.
+    pub async fn open_follow_on_round(
+        &mut self,
+        caller: PrincipalId,
+        request: OpenFollowOnRoundRequest,
+        now_seconds: u64,
+        this_canister: CanisterId,
+        sns_ledger: &dyn ICRC1Ledger,
+    ) -> OpenFollowOnRoundResponse {
+        let new_init = match self.validate_open_follow_on_round(caller, &request, now_seconds) {
+            Ok(new_init) => new_init,
+            Err(description) => return OpenFollowOnRoundResponse::new_error(description),
+        };
+        let sns_token_e8s = new_init.sns_token_e8s.unwrap_or_default();
+
+        let balance = sns_ledger
+            .account_balance(Account {
+                owner: this_canister.get().0,
+                subaccount: None,
+            })
+            .await;
+        match balance {
+            Ok(balance) if balance.get_e8s() >= sns_token_e8s => (),
+            Ok(balance) => {
+                return OpenFollowOnRoundResponse::new_error(format!(
+                    "The Swap canister holds {} SNS e8s, but the round offers {}.",
+                    balance.get_e8s(),
+                    sns_token_e8s
+                ))
+            }
+            Err(err) => {
+                return OpenFollowOnRoundResponse::new_error(format!(
+                    "Unable to determine the SNS token balance of the Swap canister: {}",
+                    err
+                ))
+            }
+        }
+
+        // The state may have changed while awaiting the ledger.
+        let new_init = match self.validate_open_follow_on_round(caller, &request, now_seconds) {
+            Ok(new_init) => new_init,
+            Err(description) => return OpenFollowOnRoundResponse::new_error(description),
+        };
+
+        let round = self.current_round() + 1;
+        let sale_round = self.summarize_current_round();
+        log!(
+            INFO,
+            "Opening follow-on sale round {} (SNS proposal {:?}). Previous round: {:?}",
+            round,
+            request.sns_proposal_id,
+            sale_round
+        );
+        self.previous_rounds.push(sale_round);
+
+        // Clear the per-round state, including the stable memory indexes.
+        self.buyers.clear();
+        self.cf_participants.clear();
+        self.neuron_recipes.clear();
+        memory::BUYERS_LIST_INDEX.with(|buyers_list_index| {
+            let buyers_list_index = buyers_list_index.borrow_mut();
+            while buyers_list_index.pop().is_some() {}
+        });
+        memory::OPEN_TICKETS_MEMORY.with(|tickets| {
+            let mut tickets = tickets.borrow_mut();
+            let principals: Vec<_> = tickets.iter().map(|(principal, _)| principal).collect();
+            for principal in principals {
+                tickets.remove(&principal);
+            }
+        });
+        self.direct_participation_icp_e8s = None;
+        self.neurons_fund_participation_icp_e8s = None;
+        self.decentralization_swap_termination_timestamp_seconds = None;
+        self.already_tried_to_auto_finalize = Some(false);
+        self.auto_finalize_swap_response = None;
+        self.purge_old_tickets_next_principal = Some(FIRST_PRINCIPAL_BYTES.to_vec());
+
+        // Install the parameters of the new round.
+        self.params = Params::try_from(&new_init).ok();
+        self.decentralization_sale_open_timestamp_seconds = new_init.swap_start_timestamp_seconds;
+        self.init = Some(new_init);
+        self.current_round = Some(round);
+        self.follow_on_sns_proposal_id = request.sns_proposal_id;
+        self.set_lifecycle(Lifecycle::Adopted);
+
+        OpenFollowOnRoundResponse::new_ok(round)
+    }
+
+    /// Returns SNS tokens to SNS governance, which sent them for a follow-on round that
+    /// could not be opened.
+    ///
+    /// Only tokens that the current round does not need can be returned: until the current
+    /// round is settled (see `check_current_round_is_settled`), the tokens it offers stay
+    /// with the Swap canister.
+    pub async fn refund_follow_on_round_tokens(
+        &self,
+        caller: PrincipalId,
+        request: RefundFollowOnRoundTokensRequest,
+        this_canister: CanisterId,
+        sns_ledger: &dyn ICRC1Ledger,
+    ) -> RefundFollowOnRoundTokensResponse {
+        let sns_governance = match self.init_and_validate() {
+            Ok(init) => init.sns_governance_or_panic().get(),
+            Err(description) => return RefundFollowOnRoundTokensResponse::new_error(description),
+        };
+        if caller != sns_governance {
+            return RefundFollowOnRoundTokensResponse::new_error(format!(
+                "Only SNS governance may request a refund, but the caller is {}.",
+                caller
+            ));
+        }
+
+        let RefundFollowOnRoundTokensRequest {
+            sns_proposal_id,
+            amount_e8s,
+            fee_e8s,
+            to_subaccount,
+        } = request;
+        let Some(amount_e8s) = amount_e8s else {
+            return RefundFollowOnRoundTokensResponse::new_error("amount_e8s is required.");
+        };
+        let fee_e8s = fee_e8s.unwrap_or_default();
+        let to_subaccount = match to_subaccount.map(Subaccount::try_from).transpose() {
+            Ok(to_subaccount) => to_subaccount,
+            Err(_) => {
+                return RefundFollowOnRoundTokensResponse::new_error(
+                    "to_subaccount must be 32 bytes long.",
+                )
+            }
+        };
+
+        let reserved_e8s = if self.check_current_round_is_settled().is_ok() {
+            0
+        } else {
+            self.init
+                .as_ref()
+                .and_then(|init| init.sns_token_e8s)
+                .unwrap_or_default()
+        };
+        let required_e8s = reserved_e8s
+            .saturating_add(amount_e8s)
+            .saturating_add(fee_e8s);
+        let balance = sns_ledger
+            .account_balance(Account {
+                owner: this_canister.get().0,
+                subaccount: None,
+            })
+            .await;
+        match balance {
+            Ok(balance) if balance.get_e8s() >= required_e8s => (),
+            Ok(balance) => {
+                return RefundFollowOnRoundTokensResponse::new_error(format!(
+                    "The Swap canister holds {} SNS e8s, of which {} are needed by the \
+                     current round, so {} (including the fee) cannot be returned.",
+                    balance.get_e8s(),
+                    reserved_e8s,
+                    amount_e8s.saturating_add(fee_e8s)
+                ))
+            }
+            Err(err) => {
+                return RefundFollowOnRoundTokensResponse::new_error(format!(
+                    "Unable to determine the SNS token balance of the Swap canister: {}",
+                    err
+                ))
+            }
+        }
+
+        let to = Account {
+            owner: sns_governance.0,
+            subaccount: to_subaccount,
+        };
+        let memo = sns_proposal_id.unwrap_or_default();
+        match sns_ledger
+            .transfer_funds(amount_e8s, fee_e8s, None, to, memo)
+            .await
+        {
+            Ok(block_height) => {
+                log!(
+                    INFO,
+                    "Returned {} SNS e8s of SNS proposal {:?} to {} at height {}",
+                    amount_e8s,
+                    sns_proposal_id,
+                    to,
+                    block_height
+                );
+                RefundFollowOnRoundTokensResponse::new_ok(block_height)
+            }
+            Err(err) => RefundFollowOnRoundTokensResponse::new_error(format!(
+                "Transferring {} SNS e8s to {} failed: {}",
+                amount_e8s, to, err
+            )),
+        }
+    }
+
+    /// Checks that a follow-on round may be opened, and returns the `Init` describing it.
+    fn validate_open_follow_on_round(
+        &self,
+        caller: PrincipalId,
+        request: &OpenFollowOnRoundRequest,
+        now_seconds: u64,
+    ) -> Result<Init, String> {
+        let init = self.init_and_validate()?;
+        if caller != init.sns_governance_or_panic().get() {
+            return Err(format!(
+                "Only SNS governance may open a follow-on round, but the caller is {}.",
+                caller
+            ));
+        }
+
+        self.check_current_round_is_settled()?;
+
+        let round = self.current_round() + 1;
+        if round >= MAX_SALE_ROUNDS {
+            return Err(format!(
+                "The Swap supports at most {} sale rounds, all of which have been used.",
+                MAX_SALE_ROUNDS
+            ));
+        }
+
+        let swap_start_timestamp_seconds = request
+            .swap_start_timestamp_seconds
+            .ok_or("swap_start_timestamp_seconds is required.")?;
+        if swap_start_timestamp_seconds < now_seconds {
+            return Err(format!(
+                "swap_start_timestamp_seconds ({}) must not be in the past (now: {}).",
+                swap_start_timestamp_seconds, now_seconds
+            ));
+        }
+
+        let new_init = Init {
+            sns_token_e8s: request.sns_token_e8s,
+            min_participants: request.min_participants,
+            min_direct_participation_icp_e8s: request.min_direct_participation_icp_e8s,
+            max_direct_participation_icp_e8s: request.max_direct_participation_icp_e8s,
+            min_participant_icp_e8s: request.min_participant_icp_e8s,
+            max_participant_icp_e8s: request.max_participant_icp_e8s,
+            swap_start_timestamp_seconds: Some(swap_start_timestamp_seconds),
+            swap_due_timestamp_seconds: request.swap_due_timestamp_seconds,
+            neuron_basket_construction_parameters: request
+                .neuron_basket_construction_parameters
+                .clone(),
+            neurons_fund_participation: Some(false),
+            ..init.clone()
+        };
+        new_init.validate()?;
+
+        let params = Params::try_from(&new_init)?;
+        params.validate(&new_init)?;
+        let params = Params {
+            sale_delay_seconds: Some(swap_start_timestamp_seconds - now_seconds),
+            ..params
+        };
+        params.is_valid_if_initiated_at(now_seconds)?;
+
+        Ok(new_init)
+    }
+
+    /// Returns Ok(()) if the current round is over and nothing is left to be done for it,
+    /// i.e., its state can be cleared without losing track of anybody's funds.
+    fn check_current_round_is_settled(&self) -> Result<(), String> {
+        let lifecycle = self.lifecycle();
+        if !lifecycle.is_terminal() {
+            return Err(format!(
+                "The current round must be committed or aborted, but its lifecycle is {:?}.",
+                lifecycle
+            ));
+        }
+        if self.is_initial_round() && lifecycle == Lifecycle::Aborted {
+            return Err(
+                "The initial decentralization swap was aborted, so the SNS was never launched."
+                    .to_string(),
+            );
+        }
+        if self.is_finalize_swap_locked() {
+            return Err("The current round is being finalized.".to_string());
+        }
+
+        let unswept_buyer_count = self
+            .buyers
+            .values()
+            .filter(|buyer_state| {
+                buyer_state
+                    .icp
+                    .as_ref()
+                    .map_or(true, |icp| icp.transfer_success_timestamp_seconds == 0)
+            })
+            .count();
+        if unswept_buyer_count > 0 {
+            return Err(format!(
+                "The ICP of {} participant(s) of the current round has not been swept yet. \
+                 Please finalize the swap.",
+                unswept_buyer_count
+            ));
+        }
+
+        if lifecycle == Lifecycle::Committed {
+            let buyer_without_recipes_count = self
+                .buyers
+                .values()
+                .filter(|buyer_state| buyer_state.has_created_neuron_recipes != Some(true))
+                .count();
+            let unclaimed_neuron_count = self
+                .neuron_recipes
+                .iter()
+                .filter(|recipe| recipe.claimed_status != Some(ClaimedStatus::Success as i32))
+                .count();
+            if buyer_without_recipes_count > 0 || unclaimed_neuron_count > 0 {
+                return Err(format!(
+                    "The neurons of the current round have not all been created yet \
+                     ({} participant(s) without neuron recipes, {} unclaimed neuron(s)). \
+                     Please finalize the swap.",
+                    buyer_without_recipes_count, unclaimed_neuron_count
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// A summary of the current round, to be recorded before its state is cleared.
+    fn summarize_current_round(&self) -> SaleRound {
+        let init = self.init.as_ref();
+        SaleRound {
+            round: Some(self.current_round()),
+            lifecycle: self.lifecycle,
+            open_timestamp_seconds: self.decentralization_sale_open_timestamp_seconds,
+            termination_timestamp_seconds: self.decentralization_swap_termination_timestamp_seconds,
+            sns_token_e8s: init.and_then(|init| init.sns_token_e8s),
+            direct_participation_icp_e8s: Some(self.current_direct_participation_e8s()),
+            neurons_fund_participation_icp_e8s: Some(self.current_neurons_fund_participation_e8s()),
+            direct_participant_count: Some(self.buyers.len() as u64),
+            sns_neuron_count: Some(self.neuron_recipes.len() as u64),
+            nns_proposal_id: init.and_then(|init| init.nns_proposal_id),
+            sns_proposal_id: self.follow_on_sns_proposal_id,
+        }
+    }
+
     /// Determines if the conditions have been met in order to
     /// restore the dapp canisters to the fallback controller ids.
     /// The lifecycle MUST be set to Aborted via the commit method.
+    ///
+    /// Only an aborted initial decentralization swap hands the dapp back; an
+    /// aborted follow-on round leaves the already launched SNS untouched.
     pub fn should_restore_dapp_control(&self) -> bool {
-        self.lifecycle() == Lifecycle::Aborted
+        self.lifecycle() == Lifecycle::Aborted && self.is_initial_round()
     }
 
     /// Calls SNS Root's set_dapp_controllers with the Swap canister's configured
@@ -1563,6 +1948,12 @@ impl Swap {
             return finalize_swap_response;
         }
 
+        // An aborted follow-on round only needs to refund its participants,
+        // which happened above.
+        if self.lifecycle() == Lifecycle::Aborted {
+            return finalize_swap_response;
+        }
+
         // Create the SnsNeuronRecipes based on the contribution of direct and NF participants
         finalize_swap_response
             .set_create_sns_neuron_recipes_result(self.create_sns_neuron_recipes());
@@ -1592,8 +1983,10 @@ impl Swap {
         );
 
         // The following step is non-critical, so we'll do it after we set
-        // governance to normal mode, but only if there were no errors.
-        if !finalize_swap_response.has_error_message() {
+        // governance to normal mode, but only if there were no errors. Follow-on
+        // rounds run long after the SNS took control of its dapps, which may since
+        // have been registered or deregistered by proposal, so they skip it.
+        if !finalize_swap_response.has_error_message() && self.is_initial_round() {
             finalize_swap_response.set_set_dapp_controllers_result(
                 self.take_sole_control_of_dapp_controllers_for_finalize(environment.sns_root_mut())
                     .await,
@@ -2323,6 +2716,18 @@ impl Swap {
             );
         }
 
+        // Follow-on rounds only involve the Neurons' Fund if this was requested
+        // explicitly, in which case there is an NNS proposal to settle against.
+        if !self.is_initial_round()
+            && self
+                .init
+                .as_ref()
+                .and_then(|init| init.neurons_fund_participation)
+                != Some(true)
+        {
+            return SettleNeuronsFundParticipationResult::new_ok(0, 0);
+        }
+
         let init = match self.init_and_validate() {
             Ok(init) => init,
             Err(error_message) => {
@@ -3753,6 +4158,8 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
             purge_old_tickets_next_principal,
             already_tried_to_auto_finalize,
             auto_finalize_swap_response,
+            current_round,
+            follow_on_sns_proposal_id,
 
             // These are (potentially large) collections. To avoid an
             // overwhelmingly large log message, we need summarize and/or
@@ -3762,6 +4169,7 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
             neuron_recipes,
             direct_participation_icp_e8s,
             neurons_fund_participation_icp_e8s,
+            previous_rounds,
         } = self.swap;
 
         formatter
@@ -3796,6 +4204,8 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
                 already_tried_to_auto_finalize,
             )
             .field("auto_finalize_swap_response", auto_finalize_swap_response)
+            .field("current_round", current_round)
+            .field("follow_on_sns_proposal_id", follow_on_sns_proposal_id)
             // Summarize and/or decimate (potentially large) collection fields.
             //
             // TODO: Include some samples? E.g. the first, and last element, and
@@ -3811,6 +4221,10 @@ impl<'a> fmt::Debug for SwapDigest<'a> {
                 "neurons_fund_participation_icp_e8s",
                 neurons_fund_participation_icp_e8s,
             )
+            .field(
+                "previous_rounds",
+                &format!("<len={}>", previous_rounds.len()),
+            )
             .finish()
     }
 }
@@ -3,14 +3,17 @@ use crate::{
     environment::{CanisterClients, CanisterEnvironment},
     logs::{ERROR, INFO},
     pb::v1::{
-        error_refund_icp_response, set_dapp_controllers_call_result, set_mode_call_result,
+        error_refund_icp_response, open_follow_on_round_response,
+        refund_follow_on_round_tokens_response, set_dapp_controllers_call_result,
+        set_mode_call_result,
         set_mode_call_result::SetModeResult,
         settle_neurons_fund_participation_result,
         sns_neuron_recipe::{ClaimedStatus, Investor},
         BuyerState, CfInvestment, CfNeuron, CfParticipant, DirectInvestment,
         ErrorRefundIcpResponse, FinalizeSwapResponse, Init, Lifecycle, NeuronId as SwapNeuronId,
-        Params, SetDappControllersCallResult, SetModeCallResult,
-        SettleNeuronsFundParticipationResult, SnsNeuronRecipe, SweepResult, TransferableAmount,
+        OpenFollowOnRoundResponse, Params, RefundFollowOnRoundTokensResponse,
+        SetDappControllersCallResult, SetModeCallResult, SettleNeuronsFundParticipationResult,
+        SnsNeuronRecipe, SweepResult, TransferableAmount,
     },
     swap::is_valid_principal,
 };
@@ -43,6 +46,56 @@ pub fn validate_canister_id(p: &str) -> Result<(), String> {
     Ok(())
 }
 
+impl OpenFollowOnRoundResponse {
+    pub(crate) fn new_ok(round: u64) -> Self {
+        use open_follow_on_round_response::{Ok, Result};
+
+        Self {
+            result: Some(Result::Ok(Ok { round: Some(round) })),
+        }
+    }
+
+    pub(crate) fn new_error(description: impl ToString) -> Self {
+        use open_follow_on_round_response::{Err, Result};
+
+        let description = description.to_string();
+        log!(ERROR, "Unable to open a follow-on round: {}", description);
+        Self {
+            result: Some(Result::Err(Err {
+                description: Some(description),
+            })),
+        }
+    }
+}
+
+impl RefundFollowOnRoundTokensResponse {
+    pub(crate) fn new_ok(block_height: u64) -> Self {
+        use refund_follow_on_round_tokens_response::{Ok, Result};
+
+        Self {
+            result: Some(Result::Ok(Ok {
+                block_height: Some(block_height),
+            })),
+        }
+    }
+
+    pub(crate) fn new_error(description: impl ToString) -> Self {
+        use refund_follow_on_round_tokens_response::{Err, Result};
+
+        let description = description.to_string();
+        log!(
+            ERROR,
+            "Unable to refund the tokens of a follow-on round: {}",
+            description
+        );
+        Self {
+            result: Some(Result::Err(Err {
+                description: Some(description),
+            })),
+        }
+    }
+}
+
 impl ErrorRefundIcpResponse {
     pub(crate) fn new_ok(block_height: u64) -> Self {
         use error_refund_icp_response::{Ok, Result};
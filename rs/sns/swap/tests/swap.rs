@@ -210,6 +210,9 @@ fn create_generic_committed_swap() -> Swap {
         auto_finalize_swap_response: None,
         direct_participation_icp_e8s: Some(50 * E8),
         neurons_fund_participation_icp_e8s: None,
+        current_round: None,
+        previous_rounds: vec![],
+        follow_on_sns_proposal_id: None,
     }
 }
 
@@ -5952,3 +5955,309 @@ async fn test_finalize_swap_abort_sets_amount_transferred_and_fees_correctly() {
         },
     );
 }
+
+fn open_follow_on_round_request(now_seconds: u64) -> OpenFollowOnRoundRequest {
+    let swap_start_timestamp_seconds = now_seconds + ONE_DAY_SECONDS;
+    OpenFollowOnRoundRequest {
+        sns_proposal_id: Some(42),
+        sns_token_e8s: Some(1_000_000 * E8),
+        min_participants: Some(3),
+        min_direct_participation_icp_e8s: Some(1),
+        max_direct_participation_icp_e8s: Some(1_000_000 * E8),
+        min_participant_icp_e8s: Some(100 * E8),
+        max_participant_icp_e8s: Some(100_000 * E8),
+        swap_start_timestamp_seconds: Some(swap_start_timestamp_seconds),
+        swap_due_timestamp_seconds: Some(swap_start_timestamp_seconds + 7 * ONE_DAY_SECONDS),
+        neuron_basket_construction_parameters: Some(NeuronBasketConstructionParameters {
+            count: 3,
+            dissolve_delay_interval_seconds: 7890000, // 3 months
+        }),
+    }
+}
+
+/// Returns a committed swap whose finalization has completed.
+fn create_generic_finalized_swap() -> Swap {
+    let mut swap = create_generic_committed_swap();
+    for buyer_state in swap.buyers.values_mut() {
+        let icp = buyer_state.icp.as_mut().unwrap();
+        icp.transfer_start_timestamp_seconds = END_TIMESTAMP_SECONDS + 5;
+        icp.transfer_success_timestamp_seconds = END_TIMESTAMP_SECONDS + 10;
+        buyer_state.has_created_neuron_recipes = Some(true);
+    }
+    swap.neuron_recipes = create_generic_sns_neuron_recipes(1)
+        .into_iter()
+        .map(|recipe| SnsNeuronRecipe {
+            claimed_status: Some(ClaimedStatus::Success as i32),
+            ..recipe
+        })
+        .collect();
+    swap
+}
+
+#[tokio::test]
+async fn test_open_follow_on_round_requires_sns_governance_caller() {
+    let mut swap = create_generic_finalized_swap();
+    let now_seconds = END_TIMESTAMP_SECONDS + ONE_DAY_SECONDS;
+
+    let response = swap
+        .open_follow_on_round(
+            *TEST_USER1_PRINCIPAL,
+            open_follow_on_round_request(now_seconds),
+            now_seconds,
+            SWAP_CANISTER_ID,
+            &mock_stub(vec![]),
+        )
+        .await;
+
+    assert_matches!(
+        response.result,
+        Some(open_follow_on_round_response::Result::Err(_))
+    );
+    assert_eq!(swap.lifecycle(), Committed);
+    assert!(swap.previous_rounds.is_empty());
+}
+
+#[tokio::test]
+async fn test_open_follow_on_round_requires_finalized_round() {
+    // The participant's ICP has not been swept yet.
+    let mut swap = create_generic_committed_swap();
+    let now_seconds = END_TIMESTAMP_SECONDS + ONE_DAY_SECONDS;
+
+    let response = swap
+        .open_follow_on_round(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            open_follow_on_round_request(now_seconds),
+            now_seconds,
+            SWAP_CANISTER_ID,
+            &mock_stub(vec![]),
+        )
+        .await;
+
+    let Some(open_follow_on_round_response::Result::Err(err)) = response.result else {
+        panic!("Expected an error, got {:?}", response);
+    };
+    assert!(
+        err.description.unwrap().contains("has not been swept yet"),
+        "{:?}",
+        swap
+    );
+    assert_eq!(swap.lifecycle(), Committed);
+    assert_eq!(swap.buyers.len(), 1);
+}
+
+#[tokio::test]
+async fn test_open_follow_on_round_requires_sns_tokens() {
+    let mut swap = create_generic_finalized_swap();
+    let now_seconds = END_TIMESTAMP_SECONDS + ONE_DAY_SECONDS;
+    let ledger = mock_stub(vec![LedgerExpect::AccountBalance(
+        Account {
+            owner: SWAP_CANISTER_ID.into(),
+            subaccount: None,
+        },
+        Ok(Tokens::from_e8s(1_000_000 * E8 - 1)),
+    )]);
+
+    let response = swap
+        .open_follow_on_round(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            open_follow_on_round_request(now_seconds),
+            now_seconds,
+            SWAP_CANISTER_ID,
+            &ledger,
+        )
+        .await;
+
+    assert_matches!(
+        response.result,
+        Some(open_follow_on_round_response::Result::Err(_))
+    );
+    assert_eq!(swap.lifecycle(), Committed);
+}
+
+#[tokio::test]
+async fn test_open_follow_on_round_archives_previous_round() {
+    let mut swap = create_generic_finalized_swap();
+    let now_seconds = END_TIMESTAMP_SECONDS + ONE_DAY_SECONDS;
+    let request = open_follow_on_round_request(now_seconds);
+    let ledger = mock_stub(vec![LedgerExpect::AccountBalance(
+        Account {
+            owner: SWAP_CANISTER_ID.into(),
+            subaccount: None,
+        },
+        Ok(Tokens::from_e8s(1_000_000 * E8)),
+    )]);
+
+    let response = swap
+        .open_follow_on_round(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            request.clone(),
+            now_seconds,
+            SWAP_CANISTER_ID,
+            &ledger,
+        )
+        .await;
+
+    assert_eq!(
+        response.result,
+        Some(open_follow_on_round_response::Result::Ok(
+            open_follow_on_round_response::Ok { round: Some(1) }
+        ))
+    );
+
+    // The initial round is recorded ...
+    assert_eq!(
+        swap.previous_rounds,
+        vec![SaleRound {
+            round: Some(0),
+            lifecycle: Committed as i32,
+            open_timestamp_seconds: None,
+            termination_timestamp_seconds: None,
+            sns_token_e8s: Some(1000),
+            direct_participation_icp_e8s: Some(50 * E8),
+            neurons_fund_participation_icp_e8s: Some(0),
+            direct_participant_count: Some(1),
+            sns_neuron_count: Some(1),
+            nns_proposal_id: Some(102),
+            sns_proposal_id: None,
+        }]
+    );
+
+    // ... and its per-participant state is cleared.
+    assert!(swap.buyers.is_empty());
+    assert!(swap.cf_participants.is_empty());
+    assert!(swap.neuron_recipes.is_empty());
+    assert_eq!(get_snapshot_of_buyers_index_list(), vec![]);
+    assert_eq!(swap.current_direct_participation_e8s(), 0);
+
+    // The new round is configured from the request.
+    assert_eq!(swap.lifecycle(), Lifecycle::Adopted);
+    assert_eq!(swap.current_round, Some(1));
+    assert_eq!(swap.follow_on_sns_proposal_id, Some(42));
+    assert_eq!(
+        swap.decentralization_sale_open_timestamp_seconds,
+        request.swap_start_timestamp_seconds
+    );
+    let init = swap.init.as_ref().unwrap();
+    assert_eq!(init.sns_token_e8s, request.sns_token_e8s);
+    assert_eq!(
+        init.swap_due_timestamp_seconds,
+        request.swap_due_timestamp_seconds
+    );
+    assert_eq!(init.nns_proposal_id, Some(102));
+    assert_eq!(swap.params.as_ref().unwrap().sns_token_e8s, 1_000_000 * E8);
+
+    // Another round cannot be opened before this one is over.
+    let response = swap
+        .open_follow_on_round(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            request,
+            now_seconds,
+            SWAP_CANISTER_ID,
+            &mock_stub(vec![]),
+        )
+        .await;
+    assert_matches!(
+        response.result,
+        Some(open_follow_on_round_response::Result::Err(_))
+    );
+}
+
+#[tokio::test]
+async fn test_refund_follow_on_round_tokens() {
+    let swap = create_generic_finalized_swap();
+    let to_subaccount = [7; 32];
+    let request = RefundFollowOnRoundTokensRequest {
+        sns_proposal_id: Some(42),
+        amount_e8s: Some(1_000 * E8 - 10_000),
+        fee_e8s: Some(10_000),
+        to_subaccount: Some(to_subaccount.to_vec()),
+    };
+    let swap_account = Account {
+        owner: SWAP_CANISTER_ID.into(),
+        subaccount: None,
+    };
+
+    // Only SNS governance may ask for a refund.
+    let response = swap
+        .refund_follow_on_round_tokens(
+            *TEST_USER1_PRINCIPAL,
+            request.clone(),
+            SWAP_CANISTER_ID,
+            &mock_stub(vec![]),
+        )
+        .await;
+    assert_matches!(
+        response.result,
+        Some(refund_follow_on_round_tokens_response::Result::Err(_))
+    );
+
+    // The current round is settled, so all of the Swap canister's tokens can be returned.
+    let ledger = mock_stub(vec![
+        LedgerExpect::TransferFunds(
+            1_000 * E8 - 10_000,
+            10_000,
+            None,
+            Account {
+                owner: SNS_GOVERNANCE_CANISTER_ID.into(),
+                subaccount: Some(to_subaccount),
+            },
+            42,
+            Ok(1234),
+        ),
+        LedgerExpect::AccountBalance(swap_account, Ok(Tokens::from_e8s(1_000 * E8))),
+    ]);
+    let response = swap
+        .refund_follow_on_round_tokens(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            request,
+            SWAP_CANISTER_ID,
+            &ledger,
+        )
+        .await;
+    assert_eq!(
+        response.result,
+        Some(refund_follow_on_round_tokens_response::Result::Ok(
+            refund_follow_on_round_tokens_response::Ok {
+                block_height: Some(1234)
+            }
+        ))
+    );
+}
+
+#[tokio::test]
+async fn test_refund_follow_on_round_tokens_keeps_the_tokens_of_the_current_round() {
+    // The participant's ICP has not been swept yet, so the round still needs its tokens.
+    let swap = create_generic_committed_swap();
+    let sns_token_e8s = swap.init_or_panic().sns_token_e8s.unwrap();
+    let ledger = mock_stub(vec![LedgerExpect::AccountBalance(
+        Account {
+            owner: SWAP_CANISTER_ID.into(),
+            subaccount: None,
+        },
+        Ok(Tokens::from_e8s(sns_token_e8s + 100 * E8)),
+    )]);
+
+    let response = swap
+        .refund_follow_on_round_tokens(
+            SNS_GOVERNANCE_CANISTER_ID.get(),
+            RefundFollowOnRoundTokensRequest {
+                sns_proposal_id: Some(42),
+                amount_e8s: Some(100 * E8),
+                fee_e8s: Some(10_000),
+                to_subaccount: None,
+            },
+            SWAP_CANISTER_ID,
+            &ledger,
+        )
+        .await;
+
+    let Some(refund_follow_on_round_tokens_response::Result::Err(err)) = response.result else {
+        panic!("Expected an error, got {:?}", response);
+    };
+    let description = err.description.unwrap();
+    assert!(
+        description.contains("needed by the current round"),
+        "{}",
+        description
+    );
+}
@@ -17,7 +17,10 @@ use ic_protobuf::{
 use ic_replicated_state::{
     canister_state::{
         execution_state::{NextScheduledMethod, WasmMetadata},
-        system_state::{wasm_chunk_store::WasmChunkStoreMetadata, CanisterHistory, CyclesUseCase},
+        system_state::{
+            wasm_chunk_store::WasmChunkStoreMetadata, CanisterHistory, CyclesUseCase,
+            OnLowCyclesHookStatus,
+        },
     },
     page_map::{Shard, StorageLayout},
     CallContextManager, CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
@@ -147,6 +150,8 @@ pub struct CanisterStateBits {
     pub execution_state_bits: Option<ExecutionStateBits>,
     pub memory_allocation: MemoryAllocation,
     pub wasm_memory_threshold: NumBytes,
+    pub low_cycles_threshold_multiplier: u64,
+    pub on_low_cycles_hook_status: OnLowCyclesHookStatus,
     pub freeze_threshold: NumSeconds,
     pub cycles_balance: Cycles,
     pub cycles_debit: Cycles,
@@ -170,6 +175,7 @@ pub struct CanisterStateBits {
     pub canister_history: CanisterHistory,
     pub wasm_chunk_store_metadata: WasmChunkStoreMetadata,
     pub total_query_stats: TotalQueryStats,
+    pub total_method_query_stats: BTreeMap<String, TotalQueryStats>,
     pub log_visibility: LogVisibilityV2,
     pub canister_log: CanisterLog,
     pub wasm_memory_limit: Option<NumBytes>,
@@ -2236,6 +2242,11 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             execution_state_bits: item.execution_state_bits.as_ref().map(|v| v.into()),
             memory_allocation: item.memory_allocation.bytes().get(),
             wasm_memory_threshold: Some(item.wasm_memory_threshold.get()),
+            low_cycles_threshold_multiplier: item.low_cycles_threshold_multiplier,
+            on_low_cycles_hook_status: pb_canister_state_bits::OnLowCyclesHookStatus::from(
+                item.on_low_cycles_hook_status,
+            )
+            .into(),
             freeze_threshold: item.freeze_threshold.get(),
             cycles_balance: Some(item.cycles_balance.into()),
             cycles_debit: Some(item.cycles_debit.into()),
@@ -2268,6 +2279,16 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             canister_history: Some((&item.canister_history).into()),
             wasm_chunk_store_metadata: Some((&item.wasm_chunk_store_metadata).into()),
             total_query_stats: Some((&item.total_query_stats).into()),
+            total_method_query_stats: item
+                .total_method_query_stats
+                .iter()
+                .map(
+                    |(method_name, stats)| pb_canister_state_bits::MethodTotalQueryStats {
+                        method_name: method_name.clone(),
+                        stats: Some(stats.into()),
+                    },
+                )
+                .collect(),
             log_visibility_v2: pb_canister_state_bits::LogVisibilityV2::from(&item.log_visibility)
                 .into(),
             canister_log_records: item
@@ -2374,6 +2395,12 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                     err: format!("{:?}", e),
                 })?,
             wasm_memory_threshold: NumBytes::new(value.wasm_memory_threshold.unwrap_or(0)),
+            low_cycles_threshold_multiplier: value.low_cycles_threshold_multiplier,
+            on_low_cycles_hook_status: pb_canister_state_bits::OnLowCyclesHookStatus::try_from(
+                value.on_low_cycles_hook_status,
+            )
+            .unwrap_or_default()
+            .into(),
             freeze_threshold: NumSeconds::from(value.freeze_threshold),
             cycles_balance,
             cycles_debit,
@@ -2416,6 +2443,15 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                 "CanisterStateBits::total_query_stats",
             )
             .unwrap_or_default(),
+            total_method_query_stats: value
+                .total_method_query_stats
+                .into_iter()
+                .map(|entry| {
+                    let stats: TotalQueryStats =
+                        try_from_option_field(entry.stats, "MethodTotalQueryStats::stats")?;
+                    Ok((entry.method_name, stats))
+                })
+                .collect::<Result<_, ProxyDecodeError>>()?,
             log_visibility: try_from_option_field(
                 value.log_visibility_v2,
                 "CanisterStateBits::log_visibility_v2",
@@ -4,8 +4,10 @@ use ic_management_canister_types::{
     CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, IC_00,
 };
 use ic_replicated_state::{
-    canister_state::system_state::CanisterHistory,
-    metadata_state::subnet_call_context_manager::InstallCodeCallId, page_map::Shard, NumWasmPages,
+    canister_state::system_state::{CanisterHistory, OnLowCyclesHookStatus},
+    metadata_state::subnet_call_context_manager::InstallCodeCallId,
+    page_map::Shard,
+    NumWasmPages,
 };
 use ic_test_utilities_logger::with_test_replica_logger;
 use ic_test_utilities_tmpdir::tmpdir;
@@ -30,6 +32,8 @@ fn default_canister_state_bits() -> CanisterStateBits {
         execution_state_bits: None,
         memory_allocation: MemoryAllocation::default(),
         wasm_memory_threshold: NumBytes::new(0),
+        low_cycles_threshold_multiplier: 0,
+        on_low_cycles_hook_status: OnLowCyclesHookStatus::default(),
         freeze_threshold: NumSeconds::from(0),
         cycles_balance: Cycles::zero(),
         cycles_debit: Cycles::zero(),
@@ -53,6 +57,7 @@ fn default_canister_state_bits() -> CanisterStateBits {
         canister_history: CanisterHistory::default(),
         wasm_chunk_store_metadata: WasmChunkStoreMetadata::default(),
         total_query_stats: TotalQueryStats::default(),
+        total_method_query_stats: BTreeMap::new(),
         log_visibility: Default::default(),
         canister_log: Default::default(),
         wasm_memory_limit: None,
@@ -118,6 +123,51 @@ fn test_encode_decode_non_empty_controllers() {
     assert_eq!(canister_state_bits.controllers, expected_controllers);
 }
 
+#[test]
+fn test_encode_decode_method_query_stats() {
+    let query_stats = TotalQueryStats {
+        num_calls: 1,
+        num_instructions: 2,
+        ingress_payload_size: 3,
+        egress_payload_size: 4,
+    };
+    let total_method_query_stats = BTreeMap::from([
+        ("get".to_string(), query_stats.clone()),
+        ("list".to_string(), TotalQueryStats::default()),
+    ]);
+    let canister_state_bits = CanisterStateBits {
+        total_query_stats: query_stats,
+        total_method_query_stats: total_method_query_stats.clone(),
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(
+        canister_state_bits.total_method_query_stats,
+        total_method_query_stats
+    );
+}
+
+#[test]
+fn test_encode_decode_low_cycles_hook_settings() {
+    let canister_state_bits = CanisterStateBits {
+        low_cycles_threshold_multiplier: 3,
+        on_low_cycles_hook_status: OnLowCyclesHookStatus::Executed,
+        ..default_canister_state_bits()
+    };
+
+    let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+    let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+
+    assert_eq!(canister_state_bits.low_cycles_threshold_multiplier, 3);
+    assert_eq!(
+        canister_state_bits.on_low_cycles_hook_status,
+        OnLowCyclesHookStatus::Executed
+    );
+}
+
 #[test]
 fn test_encode_decode_empty_history() {
     let canister_history = CanisterHistory::default();
@@ -468,6 +468,8 @@ pub fn load_canister_state(
         queues,
         canister_state_bits.memory_allocation,
         canister_state_bits.wasm_memory_threshold,
+        canister_state_bits.low_cycles_threshold_multiplier,
+        canister_state_bits.on_low_cycles_hook_status,
         canister_state_bits.freeze_threshold,
         canister_state_bits.status,
         canister_state_bits.certified_data,
@@ -505,6 +507,7 @@ pub fn load_canister_state(
                 canister_state_bits.time_of_last_allocation_charge_nanos,
             ),
             total_query_stats: canister_state_bits.total_query_stats,
+            total_method_query_stats: canister_state_bits.total_method_query_stats,
         },
     };
 
@@ -986,6 +986,10 @@ fn serialize_canister_to_tip(
             accumulated_priority: canister_state.scheduler_state.accumulated_priority,
             memory_allocation: canister_state.system_state.memory_allocation,
             wasm_memory_threshold: canister_state.system_state.wasm_memory_threshold,
+            low_cycles_threshold_multiplier: canister_state
+                .system_state
+                .low_cycles_threshold_multiplier,
+            on_low_cycles_hook_status: canister_state.system_state.on_low_cycles_hook_status,
             freeze_threshold: canister_state.system_state.freeze_threshold,
             cycles_balance: canister_state.system_state.balance(),
             cycles_debit: canister_state.system_state.ingress_induction_cycles_debit(),
@@ -1042,6 +1046,10 @@ fn serialize_canister_to_tip(
                 .metadata()
                 .clone(),
             total_query_stats: canister_state.scheduler_state.total_query_stats.clone(),
+            total_method_query_stats: canister_state
+                .scheduler_state
+                .total_method_query_stats
+                .clone(),
             log_visibility: canister_state.system_state.log_visibility.clone(),
             canister_log: canister_state.system_state.canister_log.clone(),
             wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
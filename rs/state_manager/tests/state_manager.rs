@@ -737,12 +737,16 @@ fn query_stats_are_persisted() {
         let (_curr_height, mut state) = state_manager.take_tip();
 
         let epoch = QueryStatsEpoch::from(42);
-        let test_query_stats: QueryStats = QueryStats {
-            num_calls: 1337,
-            num_instructions: 100000,
-            ingress_payload_size: 100001,
-            egress_payload_size: 100002,
-        };
+        let mut test_query_stats = CanisterQueryStats::new(canister_id);
+        test_query_stats.saturating_accumulate_method(
+            "query",
+            &QueryStats {
+                num_calls: 1337,
+                num_instructions: 100000,
+                ingress_payload_size: 100001,
+                egress_payload_size: 100002,
+            },
+        );
 
         let mut inner = BTreeMap::new();
         inner.insert(canister_id, test_query_stats.clone());
@@ -7139,6 +7143,7 @@ fn query_stats_are_collected() {
                     ingress_payload_size: 3,
                     egress_payload_size: 4,
                 },
+                method_stats: BTreeMap::new(),
             });
 
             // This canister does not exist in the replicated state.
@@ -7152,6 +7157,7 @@ fn query_stats_are_collected() {
                     ingress_payload_size: 3,
                     egress_payload_size: 4,
                 },
+                method_stats: BTreeMap::new(),
             });
 
             if i < NUM_MALICIOUS {
@@ -7164,6 +7170,7 @@ fn query_stats_are_collected() {
                         ingress_payload_size: 3,
                         egress_payload_size: 4,
                     },
+                    method_stats: BTreeMap::new(),
                 });
             } else {
                 // Simulate malicious nodes not sending (under-reporting) stats for a canister that does execute queries
@@ -7175,6 +7182,7 @@ fn query_stats_are_collected() {
                         ingress_payload_size: 3,
                         egress_payload_size: 4,
                     },
+                    method_stats: BTreeMap::new(),
                 });
             }
         }
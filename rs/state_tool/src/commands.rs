@@ -1,4 +1,6 @@
 //! Command implementations.
+pub mod canister_archive;
+pub mod canister_diff;
 pub mod cdiff;
 pub mod chash;
 pub mod convert_ids;
@@ -0,0 +1,471 @@
+//! Exports a single canister from a checkpoint as a self-describing archive
+//! and imports such an archive into another checkpoint.
+//!
+//! The archive is a tar file containing a `canister.json` metadata file
+//! followed by the files of the canister directory of the checkpoint (module,
+//! heap, stable memory, wasm chunk store, queues and system metadata such as
+//! controllers and certified data). Canister snapshots are not exported.
+
+use ic_crypto_sha2::Sha256;
+use ic_protobuf::state::canister_state_bits::v1 as pb_canister;
+use ic_state_layout::{
+    CheckpointLayout, ProtoFileWith, ReadOnly, CANISTER_FILE, CANISTER_STATES_DIR, WASM_FILE,
+};
+use ic_types::{CanisterId, Cycles, Height, PrincipalId};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+/// The version of the archive format produced by `export-canister`.
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// The name of the metadata file, always the first entry of the archive.
+const METADATA_FILE: &str = "canister.json";
+
+/// The directory of the archive holding the canister files.
+const FILES_DIR: &str = "canister";
+
+/// Describes an exported canister and the files of the archive.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanisterArchiveMetadata {
+    pub format_version: u32,
+    pub canister_id: String,
+    /// The height of the checkpoint the canister was exported from, if the
+    /// checkpoint directory follows the state layout naming scheme.
+    pub source_height: Option<u64>,
+    pub controllers: Vec<String>,
+    pub cycles_balance: u128,
+    pub canister_version: u64,
+    /// Hex-encoded certified data.
+    pub certified_data: String,
+    /// Hex-encoded SHA-256 of the Wasm module, if the canister has one.
+    pub module_hash: Option<String>,
+    pub files: Vec<ArchivedFile>,
+}
+
+/// A file of the canister directory stored in the archive.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedFile {
+    pub name: String,
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the file contents.
+    pub sha256: String,
+}
+
+/// `export-canister` command entry point.
+pub fn do_export(checkpoint: PathBuf, canister_id: String, output: PathBuf) -> Result<(), String> {
+    let canister_id = parse_canister_id(&canister_id)?;
+    let metadata = export_canister(&checkpoint, canister_id, &output)?;
+    println!(
+        "Exported canister {} ({} files) from {} to {}",
+        metadata.canister_id,
+        metadata.files.len(),
+        checkpoint.display(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// `import-canister` command entry point.
+pub fn do_import(archive: PathBuf, checkpoint: PathBuf, replace: bool) -> Result<(), String> {
+    let metadata = import_canister(&archive, &checkpoint, replace)?;
+    println!(
+        "Imported canister {} into {}",
+        metadata.canister_id,
+        checkpoint.display()
+    );
+    println!(
+        "NOTE: the manifest of the checkpoint no longer matches its contents and the \
+         canister ID must be routed to the subnet of the checkpoint."
+    );
+    Ok(())
+}
+
+fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
+    let principal = PrincipalId::from_str(canister_id)
+        .map_err(|e| format!("failed to parse canister ID {}: {}", canister_id, e))?;
+    CanisterId::try_from(principal)
+        .map_err(|e| format!("invalid canister ID {}: {}", canister_id, e))
+}
+
+/// Writes the canister with the given ID from the checkpoint at `checkpoint`
+/// into a new archive at `output`.
+pub fn export_canister(
+    checkpoint: &Path,
+    canister_id: CanisterId,
+    output: &Path,
+) -> Result<CanisterArchiveMetadata, String> {
+    let source_height = checkpoint
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| u64::from_str_radix(name, 16).ok());
+    let cp_layout = CheckpointLayout::<ReadOnly>::new_untracked(
+        checkpoint.to_path_buf(),
+        Height::new(source_height.unwrap_or_default()),
+    )
+    .map_err(|e| format!("failed to open checkpoint {}: {}", checkpoint.display(), e))?;
+    let canister_dir = cp_layout
+        .canister(&canister_id)
+        .map_err(|e| format!("canister {} not found in checkpoint: {}", canister_id, e))?
+        .raw_path();
+
+    let mut files = vec![];
+    let mut names = list_files(&canister_dir)?;
+    names.sort();
+    for name in names {
+        let path = canister_dir.join(&name);
+        let (size, sha256) = hash_file(&path)?;
+        files.push(ArchivedFile { name, size, sha256 });
+    }
+
+    let bits_file: ProtoFileWith<pb_canister::CanisterStateBits, ReadOnly> =
+        canister_dir.join(CANISTER_FILE).into();
+    let bits = bits_file
+        .deserialize()
+        .map_err(|e| format!("failed to decode {}: {:?}", CANISTER_FILE, e))?;
+    let controllers = bits
+        .controllers
+        .into_iter()
+        .map(|controller| {
+            PrincipalId::try_from(controller)
+                .map(|principal| principal.to_string())
+                .map_err(|e| format!("failed to decode controller: {}", e))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let metadata = CanisterArchiveMetadata {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        canister_id: canister_id.to_string(),
+        source_height,
+        controllers,
+        cycles_balance: bits
+            .cycles_balance
+            .map(Cycles::from)
+            .unwrap_or_default()
+            .get(),
+        canister_version: bits.canister_version,
+        certified_data: hex::encode(bits.certified_data),
+        module_hash: files
+            .iter()
+            .find(|file| file.name == WASM_FILE)
+            .map(|file| file.sha256.clone()),
+        files,
+    };
+
+    let out = File::create(output)
+        .map_err(|e| format!("failed to create {}: {}", output.display(), e))?;
+    let mut builder = tar::Builder::new(out);
+    let metadata_json = serde_json::to_vec_pretty(&metadata)
+        .map_err(|e| format!("failed to serialize archive metadata: {}", e))?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(metadata_json.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, METADATA_FILE, metadata_json.as_slice())
+        .map_err(|e| format!("failed to write {}: {}", output.display(), e))?;
+    for file in &metadata.files {
+        builder
+            .append_path_with_name(
+                canister_dir.join(&file.name),
+                Path::new(FILES_DIR).join(&file.name),
+            )
+            .map_err(|e| format!("failed to add {} to the archive: {}", file.name, e))?;
+    }
+    builder
+        .into_inner()
+        .and_then(|mut out| out.flush())
+        .map_err(|e| format!("failed to write {}: {}", output.display(), e))?;
+
+    Ok(metadata)
+}
+
+/// Unpacks the canister archive at `archive` into the checkpoint at
+/// `checkpoint`, verifying the size and hash of every file.
+///
+/// Fails if the canister already exists in the checkpoint, unless `replace`
+/// is set.
+pub fn import_canister(
+    archive: &Path,
+    checkpoint: &Path,
+    replace: bool,
+) -> Result<CanisterArchiveMetadata, String> {
+    let file =
+        File::open(archive).map_err(|e| format!("failed to open {}: {}", archive.display(), e))?;
+    let mut tar_archive = tar::Archive::new(file);
+    let mut entries = tar_archive
+        .entries()
+        .map_err(|e| format!("failed to read archive: {}", e))?;
+
+    let metadata: CanisterArchiveMetadata = match entries.next() {
+        Some(Ok(entry)) if entry.path().ok().as_deref() == Some(Path::new(METADATA_FILE)) => {
+            serde_json::from_reader(entry)
+                .map_err(|e| format!("failed to parse {}: {}", METADATA_FILE, e))?
+        }
+        _ => return Err(format!("archive does not start with {}", METADATA_FILE)),
+    };
+    if metadata.format_version != ARCHIVE_FORMAT_VERSION {
+        return Err(format!(
+            "unsupported archive format version {}, expected {}",
+            metadata.format_version, ARCHIVE_FORMAT_VERSION
+        ));
+    }
+    let canister_id = parse_canister_id(&metadata.canister_id)?;
+
+    let states_dir = checkpoint.join(CANISTER_STATES_DIR);
+    let canister_dir = states_dir.join(hex::encode(canister_id.get_ref().as_slice()));
+    if canister_dir.exists() && !replace {
+        return Err(format!(
+            "canister {} already exists in {}, use --replace to overwrite it",
+            canister_id,
+            checkpoint.display()
+        ));
+    }
+    let staging_dir = canister_dir.with_extension("importing");
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir)
+            .map_err(|e| format!("failed to remove {}: {}", staging_dir.display(), e))?;
+    }
+    fs::create_dir_all(&staging_dir)
+        .map_err(|e| format!("failed to create {}: {}", staging_dir.display(), e))?;
+
+    let result = unpack_files(&mut entries, &metadata, &staging_dir).and_then(|()| {
+        if canister_dir.exists() {
+            fs::remove_dir_all(&canister_dir)
+                .map_err(|e| format!("failed to remove {}: {}", canister_dir.display(), e))?;
+        }
+        fs::rename(&staging_dir, &canister_dir)
+            .map_err(|e| format!("failed to move canister into place: {}", e))
+    });
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&staging_dir);
+    }
+    result.map(|()| metadata)
+}
+
+/// Writes the canister files of the archive into `dir`.
+fn unpack_files<R: Read>(
+    entries: &mut tar::Entries<'_, R>,
+    metadata: &CanisterArchiveMetadata,
+    dir: &Path,
+) -> Result<(), String> {
+    let mut unpacked = 0;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("failed to read archive entry: {}", e))?;
+        let path = entry
+            .path()
+            .map_err(|e| format!("invalid archive entry path: {}", e))?
+            .into_owned();
+        let name = match path.strip_prefix(FILES_DIR).map(|name| name.components()) {
+            Ok(mut components) => match (components.next(), components.next()) {
+                (Some(Component::Normal(name)), None) => name.to_string_lossy().into_owned(),
+                _ => return Err(format!("unexpected archive entry {}", path.display())),
+            },
+            Err(_) => return Err(format!("unexpected archive entry {}", path.display())),
+        };
+        let expected = metadata
+            .files
+            .iter()
+            .find(|file| file.name == name)
+            .ok_or_else(|| format!("file {} is not listed in {}", name, METADATA_FILE))?;
+
+        let target = dir.join(&name);
+        let mut out = HashingWriter::new(
+            File::create(&target)
+                .map_err(|e| format!("failed to create {}: {}", target.display(), e))?,
+        );
+        io::copy(&mut entry, &mut out)
+            .map_err(|e| format!("failed to write {}: {}", target.display(), e))?;
+        let (size, sha256) = out.finish();
+        if size != expected.size || sha256 != expected.sha256 {
+            return Err(format!(
+                "file {} is corrupted: expected {} bytes with hash {}, got {} bytes with hash {}",
+                name, expected.size, expected.sha256, size, sha256
+            ));
+        }
+        unpacked += 1;
+    }
+
+    if unpacked != metadata.files.len() {
+        return Err(format!(
+            "archive contains {} files, {} lists {}",
+            unpacked,
+            METADATA_FILE,
+            metadata.files.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the names of the regular files in `dir`.
+fn list_files(dir: &Path) -> Result<Vec<String>, String> {
+    let mut names = vec![];
+    for entry in
+        fs::read_dir(dir).map_err(|e| format!("failed to read {}: {}", dir.display(), e))?
+    {
+        let entry = entry.map_err(|e| format!("failed to read {}: {}", dir.display(), e))?;
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("failed to stat {}: {}", entry.path().display(), e))?;
+        if !file_type.is_file() {
+            return Err(format!(
+                "unexpected non-file entry {} in canister directory",
+                entry.path().display()
+            ));
+        }
+        names.push(entry.file_name().to_string_lossy().into_owned());
+    }
+    Ok(names)
+}
+
+/// Returns the size and hex-encoded SHA-256 of the file at `path`.
+fn hash_file(path: &Path) -> Result<(u64, String), String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("failed to open {}: {}", path.display(), e))?;
+    let mut hasher = HashingWriter::new(io::sink());
+    io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    Ok(hasher.finish())
+}
+
+/// A writer that computes the size and SHA-256 of everything written through it.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    size: u64,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+            size: 0,
+        }
+    }
+
+    fn finish(self) -> (u64, String) {
+        (self.size, hex::encode(self.hasher.finish()))
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        #[allow(clippy::disallowed_methods)]
+        let written = self.inner.write(buf)?;
+        self.hasher.write(&buf[..written]);
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_protobuf::state::queues::v1 as pb_queues;
+    use prost::Message;
+
+    const CANISTER_ID: &str = "rwlgt-iiaaa-aaaaa-aaaaa-cai";
+
+    fn write_canister(checkpoint: &Path, canister_id: CanisterId) -> PathBuf {
+        let dir = checkpoint
+            .join(CANISTER_STATES_DIR)
+            .join(hex::encode(canister_id.get_ref().as_slice()));
+        fs::create_dir_all(&dir).unwrap();
+        let bits = pb_canister::CanisterStateBits {
+            controllers: vec![PrincipalId::new_user_test_id(1).into()],
+            cycles_balance: Some(pb_queues::Cycles::from(Cycles::new(1_000))),
+            certified_data: vec![1, 2, 3],
+            canister_version: 7,
+            ..Default::default()
+        };
+        fs::write(dir.join(CANISTER_FILE), bits.encode_to_vec()).unwrap();
+        fs::write(dir.join(WASM_FILE), b"\0asm\x01\0\0\0").unwrap();
+        fs::write(dir.join("vmemory_0.bin"), vec![42; 4096]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("000000000000012c");
+        let target = tmp.path().join("target");
+        fs::create_dir_all(target.join(CANISTER_STATES_DIR)).unwrap();
+        let archive = tmp.path().join("canister.tar");
+        let canister_id = parse_canister_id(CANISTER_ID).unwrap();
+        let source_dir = write_canister(&source, canister_id);
+
+        let exported = export_canister(&source, canister_id, &archive).unwrap();
+        assert_eq!(exported.source_height, Some(300));
+        assert_eq!(
+            exported.controllers,
+            vec![PrincipalId::new_user_test_id(1).to_string()]
+        );
+        assert_eq!(exported.cycles_balance, 1_000);
+        assert_eq!(exported.certified_data, "010203");
+        assert!(exported.module_hash.is_some());
+        assert_eq!(exported.files.len(), 3);
+
+        let imported = import_canister(&archive, &target, false).unwrap();
+        assert_eq!(imported, exported);
+        let target_dir = target
+            .join(CANISTER_STATES_DIR)
+            .join(hex::encode(canister_id.get_ref().as_slice()));
+        for file in &exported.files {
+            assert_eq!(
+                fs::read(source_dir.join(&file.name)).unwrap(),
+                fs::read(target_dir.join(&file.name)).unwrap()
+            );
+        }
+
+        // Importing again requires an explicit replace.
+        assert!(import_canister(&archive, &target, false).is_err());
+        import_canister(&archive, &target, true).unwrap();
+    }
+
+    #[test]
+    fn import_rejects_corrupted_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("source");
+        let target = tmp.path().join("target");
+        let archive = tmp.path().join("canister.tar");
+        let canister_id = parse_canister_id(CANISTER_ID).unwrap();
+        write_canister(&source, canister_id);
+        let mut metadata = export_canister(&source, canister_id, &archive).unwrap();
+        assert_eq!(metadata.source_height, None);
+
+        // Rewrite the archive with metadata that does not match the files.
+        metadata.files[0].sha256 = hex::encode([0; 32]);
+        let json = serde_json::to_vec(&metadata).unwrap();
+        let mut original = tar::Archive::new(File::open(&archive).unwrap());
+        let mut builder = tar::Builder::new(File::create(tmp.path().join("bad.tar")).unwrap());
+        for (i, entry) in original.entries().unwrap().enumerate() {
+            let mut entry = entry.unwrap();
+            let mut header = entry.header().clone();
+            if i == 0 {
+                header.set_size(json.len() as u64);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, METADATA_FILE, json.as_slice())
+                    .unwrap();
+            } else {
+                let path = entry.path().unwrap().into_owned();
+                builder.append_data(&mut header, path, &mut entry).unwrap();
+            }
+        }
+        builder.finish().unwrap();
+
+        let err = import_canister(&tmp.path().join("bad.tar"), &target, false).unwrap_err();
+        assert!(err.contains("is corrupted"), "{}", err);
+        assert!(!target
+            .join(CANISTER_STATES_DIR)
+            .join(hex::encode(canister_id.get_ref().as_slice()))
+            .exists());
+    }
+}
@@ -0,0 +1,424 @@
+//! Computes a semantic, per-canister diff between two replicated states.
+
+use ic_replicated_state::{CanisterState, PageIndex, PageMap, ReplicatedState};
+use ic_sys::{PageBytes, PAGE_SIZE};
+use ic_types::CanisterId;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A value that differs between the two states.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Change<T> {
+    pub before: T,
+    pub after: T,
+}
+
+impl<T: PartialEq> Change<T> {
+    /// Returns a `Change` if `before` and `after` differ.
+    fn new(before: T, after: T) -> Option<Self> {
+        (before != after).then_some(Self { before, after })
+    }
+}
+
+/// The changes of a canister present in both states.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CanisterDiff {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub controllers: Option<Change<Vec<String>>>,
+    /// Changed settings and status, keyed by name, in debug representation.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub settings: BTreeMap<String, Change<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub module_hash: Option<Change<Option<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cycles_delta: Option<i128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certified_data: Option<Change<String>>,
+    /// The size of the Wasm memory, in Wasm pages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wasm_memory_size: Option<Change<usize>>,
+    /// The size of the stable memory, in Wasm pages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stable_memory_size: Option<Change<usize>>,
+    /// The number of 4 KiB pages of the Wasm memory whose contents changed.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub changed_wasm_memory_pages: usize,
+    /// The number of 4 KiB pages of the stable memory whose contents changed.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub changed_stable_memory_pages: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingress_queue_size: Option<Change<usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_queues_size: Option<Change<usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_queues_size: Option<Change<usize>>,
+    /// The number of ingress history entries addressed to the canister.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ingress_history_entries: Option<Change<usize>>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+impl CanisterDiff {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// The per-canister differences between two states.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StateDiff {
+    pub added_canisters: Vec<String>,
+    pub removed_canisters: Vec<String>,
+    pub changed_canisters: BTreeMap<String, CanisterDiff>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Compares the canisters of `state_a` and `state_b`.
+pub fn diff_states(state_a: &ReplicatedState, state_b: &ReplicatedState) -> StateDiff {
+    let ingress_a = ingress_history_entries(state_a);
+    let ingress_b = ingress_history_entries(state_b);
+
+    let mut diff = StateDiff::default();
+    for (canister_id, canister_a) in &state_a.canister_states {
+        match state_b.canister_state(canister_id) {
+            Some(canister_b) => {
+                let mut canister_diff = diff_canisters(canister_a, canister_b);
+                canister_diff.ingress_history_entries = Change::new(
+                    ingress_a.get(canister_id).copied().unwrap_or_default(),
+                    ingress_b.get(canister_id).copied().unwrap_or_default(),
+                );
+                if !canister_diff.is_empty() {
+                    diff.changed_canisters
+                        .insert(canister_id.to_string(), canister_diff);
+                }
+            }
+            None => diff.removed_canisters.push(canister_id.to_string()),
+        }
+    }
+    for canister_id in state_b.canister_states.keys() {
+        if !state_a.canister_states.contains_key(canister_id) {
+            diff.added_canisters.push(canister_id.to_string());
+        }
+    }
+    diff
+}
+
+fn diff_canisters(a: &CanisterState, b: &CanisterState) -> CanisterDiff {
+    let (sys_a, sys_b) = (&a.system_state, &b.system_state);
+
+    let mut settings = BTreeMap::new();
+    let mut setting = |name: &str, before: String, after: String| {
+        if let Some(change) = Change::new(before, after) {
+            settings.insert(name.to_string(), change);
+        }
+    };
+    setting(
+        "compute_allocation",
+        format!("{:?}", a.scheduler_state.compute_allocation),
+        format!("{:?}", b.scheduler_state.compute_allocation),
+    );
+    setting(
+        "memory_allocation",
+        format!("{:?}", sys_a.memory_allocation),
+        format!("{:?}", sys_b.memory_allocation),
+    );
+    setting(
+        "freezing_threshold",
+        format!("{:?}", sys_a.freeze_threshold),
+        format!("{:?}", sys_b.freeze_threshold),
+    );
+    setting(
+        "reserved_cycles_limit",
+        format!("{:?}", sys_a.reserved_balance_limit()),
+        format!("{:?}", sys_b.reserved_balance_limit()),
+    );
+    setting(
+        "wasm_memory_limit",
+        format!("{:?}", sys_a.wasm_memory_limit),
+        format!("{:?}", sys_b.wasm_memory_limit),
+    );
+    setting(
+        "wasm_memory_threshold",
+        format!("{:?}", sys_a.wasm_memory_threshold),
+        format!("{:?}", sys_b.wasm_memory_threshold),
+    );
+    setting(
+        "low_cycles_threshold_multiplier",
+        format!("{:?}", sys_a.low_cycles_threshold_multiplier),
+        format!("{:?}", sys_b.low_cycles_threshold_multiplier),
+    );
+    setting(
+        "log_visibility",
+        format!("{:?}", sys_a.log_visibility),
+        format!("{:?}", sys_b.log_visibility),
+    );
+    setting(
+        "status",
+        format!("{:?}", sys_a.status),
+        format!("{:?}", sys_b.status),
+    );
+
+    let controllers = |canister: &CanisterState| {
+        canister
+            .system_state
+            .controllers
+            .iter()
+            .map(|controller| controller.to_string())
+            .collect::<Vec<_>>()
+    };
+    let module_hash = |canister: &CanisterState| {
+        canister
+            .execution_state
+            .as_ref()
+            .map(|es| hex::encode(es.wasm_binary.binary.module_hash()))
+    };
+    let memory_sizes = |canister: &CanisterState| {
+        canister.execution_state.as_ref().map_or((0, 0), |es| {
+            (es.wasm_memory.size.get(), es.stable_memory.size.get())
+        })
+    };
+    let (wasm_size_a, stable_size_a) = memory_sizes(a);
+    let (wasm_size_b, stable_size_b) = memory_sizes(b);
+    let cycles_a = sys_a.balance().get() as i128;
+    let cycles_b = sys_b.balance().get() as i128;
+    let queues_a = sys_a.queues();
+    let queues_b = sys_b.queues();
+
+    CanisterDiff {
+        controllers: Change::new(controllers(a), controllers(b)),
+        settings,
+        module_hash: Change::new(module_hash(a), module_hash(b)),
+        cycles_delta: (cycles_a != cycles_b).then_some(cycles_b - cycles_a),
+        certified_data: Change::new(
+            hex::encode(&sys_a.certified_data),
+            hex::encode(&sys_b.certified_data),
+        ),
+        wasm_memory_size: Change::new(wasm_size_a, wasm_size_b),
+        stable_memory_size: Change::new(stable_size_a, stable_size_b),
+        changed_wasm_memory_pages: changed_pages(
+            a.execution_state
+                .as_ref()
+                .map(|es| &es.wasm_memory.page_map),
+            b.execution_state
+                .as_ref()
+                .map(|es| &es.wasm_memory.page_map),
+        ),
+        changed_stable_memory_pages: changed_pages(
+            a.execution_state
+                .as_ref()
+                .map(|es| &es.stable_memory.page_map),
+            b.execution_state
+                .as_ref()
+                .map(|es| &es.stable_memory.page_map),
+        ),
+        ingress_queue_size: Change::new(
+            queues_a.ingress_queue_message_count(),
+            queues_b.ingress_queue_message_count(),
+        ),
+        input_queues_size: Change::new(
+            queues_a.input_queues_message_count(),
+            queues_b.input_queues_message_count(),
+        ),
+        output_queues_size: Change::new(
+            queues_a.output_queues_message_count(),
+            queues_b.output_queues_message_count(),
+        ),
+        ingress_history_entries: None,
+    }
+}
+
+/// Returns the number of pages whose contents differ between the two page
+/// maps. A missing page map is treated as an empty one.
+fn changed_pages(a: Option<&PageMap>, b: Option<&PageMap>) -> usize {
+    const ZERO_PAGE: PageBytes = [0; PAGE_SIZE];
+    fn page(page_map: Option<&PageMap>, index: PageIndex) -> &PageBytes {
+        match page_map {
+            Some(page_map) => page_map.get_page(index),
+            None => &ZERO_PAGE,
+        }
+    }
+    let num_pages = a
+        .map_or(0, PageMap::num_host_pages)
+        .max(b.map_or(0, PageMap::num_host_pages));
+    (0..num_pages as u64)
+        .map(PageIndex::new)
+        .filter(|index| page(a, *index) != page(b, *index))
+        .count()
+}
+
+/// Returns the number of ingress history entries per receiving canister.
+fn ingress_history_entries(state: &ReplicatedState) -> BTreeMap<CanisterId, usize> {
+    let mut entries = BTreeMap::new();
+    for (_, status) in state.metadata.ingress_history.statuses() {
+        if let Some(receiver) = status.receiver() {
+            *entries.entry(receiver).or_default() += 1;
+        }
+    }
+    entries
+}
+
+impl fmt::Display for StateDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for canister_id in &self.added_canisters {
+            writeln!(f, "+ canister {}", canister_id)?;
+        }
+        for canister_id in &self.removed_canisters {
+            writeln!(f, "- canister {}", canister_id)?;
+        }
+        for (canister_id, diff) in &self.changed_canisters {
+            writeln!(f, "~ canister {}", canister_id)?;
+            if let Some(change) = &diff.controllers {
+                writeln!(
+                    f,
+                    "    controllers: [{}] -> [{}]",
+                    change.before.join(", "),
+                    change.after.join(", ")
+                )?;
+            }
+            for (name, change) in &diff.settings {
+                writeln!(f, "    {}: {} -> {}", name, change.before, change.after)?;
+            }
+            if let Some(change) = &diff.module_hash {
+                let hash = |h: &Option<String>| h.clone().unwrap_or_else(|| "<none>".to_string());
+                writeln!(
+                    f,
+                    "    module_hash: {} -> {}",
+                    hash(&change.before),
+                    hash(&change.after)
+                )?;
+            }
+            if let Some(delta) = diff.cycles_delta {
+                writeln!(f, "    cycles: {:+}", delta)?;
+            }
+            if let Some(change) = &diff.certified_data {
+                writeln!(
+                    f,
+                    "    certified_data: {} -> {}",
+                    change.before, change.after
+                )?;
+            }
+            let sizes = [
+                ("wasm_memory_size", &diff.wasm_memory_size),
+                ("stable_memory_size", &diff.stable_memory_size),
+                ("ingress_queue_size", &diff.ingress_queue_size),
+                ("input_queues_size", &diff.input_queues_size),
+                ("output_queues_size", &diff.output_queues_size),
+                ("ingress_history_entries", &diff.ingress_history_entries),
+            ];
+            for (name, change) in sizes {
+                if let Some(change) = change {
+                    writeln!(f, "    {}: {} -> {}", name, change.before, change.after)?;
+                }
+            }
+            if diff.changed_wasm_memory_pages > 0 {
+                writeln!(
+                    f,
+                    "    changed wasm memory pages: {}",
+                    diff.changed_wasm_memory_pages
+                )?;
+            }
+            if diff.changed_stable_memory_pages > 0 {
+                writeln!(
+                    f,
+                    "    changed stable memory pages: {}",
+                    diff.changed_stable_memory_pages
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_state::{CanisterStateBuilder, ReplicatedStateBuilder};
+    use ic_test_utilities_types::ids::{canister_test_id, user_test_id};
+    use ic_types::NumSeconds;
+
+    #[test]
+    fn identical_states_have_no_diff() {
+        let canister = CanisterStateBuilder::new()
+            .with_canister_id(canister_test_id(1))
+            .with_wasm(vec![1, 2, 3])
+            .build();
+        let state = ReplicatedStateBuilder::new()
+            .with_canister(canister)
+            .build();
+
+        assert!(diff_states(&state, &state.clone()).is_empty());
+    }
+
+    #[test]
+    fn reports_canister_changes() {
+        let state_a = ReplicatedStateBuilder::new()
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(1))
+                    .build(),
+            )
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(2))
+                    .with_controller(user_test_id(1).get())
+                    .with_cycles(1_000_u128)
+                    .with_wasm(vec![1, 2, 3])
+                    .with_stable_memory(vec![0; 4096])
+                    .build(),
+            )
+            .build();
+        let state_b = ReplicatedStateBuilder::new()
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(2))
+                    .with_controller(user_test_id(2).get())
+                    .with_cycles(400_u128)
+                    .with_wasm(vec![4, 5, 6])
+                    .with_stable_memory(vec![1; 4096])
+                    .with_freezing_threshold(NumSeconds::new(42))
+                    .build(),
+            )
+            .with_canister(
+                CanisterStateBuilder::new()
+                    .with_canister_id(canister_test_id(3))
+                    .build(),
+            )
+            .build();
+
+        let diff = diff_states(&state_a, &state_b);
+
+        assert_eq!(diff.added_canisters, vec![canister_test_id(3).to_string()]);
+        assert_eq!(
+            diff.removed_canisters,
+            vec![canister_test_id(1).to_string()]
+        );
+        let canister_diff = &diff.changed_canisters[&canister_test_id(2).to_string()];
+        assert_eq!(
+            canister_diff.controllers,
+            Some(Change {
+                before: vec![user_test_id(1).get().to_string()],
+                after: vec![user_test_id(2).get().to_string()],
+            })
+        );
+        assert_eq!(canister_diff.cycles_delta, Some(-600));
+        assert!(canister_diff.module_hash.is_some());
+        assert!(canister_diff.settings.contains_key("freezing_threshold"));
+        assert_eq!(canister_diff.changed_stable_memory_pages, 1);
+        assert_eq!(canister_diff.changed_wasm_memory_pages, 0);
+
+        let json = serde_json::to_value(&diff).unwrap();
+        assert_eq!(
+            json["changed_canisters"][canister_test_id(2).to_string()]["cycles_delta"],
+            -600
+        );
+    }
+}
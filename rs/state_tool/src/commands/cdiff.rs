@@ -1,7 +1,8 @@
-//! Computes diff of canonical trees between checkpoints.
+//! Computes diff of canonical trees, or of canisters, between checkpoints.
 
+use crate::commands::canister_diff::diff_states;
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::page_map::TestPageAllocatorFileDescriptorImpl;
+use ic_replicated_state::{page_map::TestPageAllocatorFileDescriptorImpl, ReplicatedState};
 use ic_state_layout::CompleteCheckpointLayout;
 use ic_state_manager::{
     checkpoint::load_checkpoint,
@@ -13,26 +14,25 @@ use ic_types::Height;
 use std::path::PathBuf;
 use std::sync::Arc;
 
-/// Loads the checkpoints at `path_a` and `path_b` and diffs them.
-fn diff_checkpoints(path_a: PathBuf, path_b: PathBuf) -> Result<Changes, CheckpointError> {
+/// Loads the checkpoint at `path`.
+fn load_state(path: PathBuf) -> Result<ReplicatedState, CheckpointError> {
     let unused_height = Height::from(0);
     let own_subnet_type = SubnetType::Application;
     let dummy_metrics_registry = ic_metrics::MetricsRegistry::new();
     let dummy_metrics = CheckpointMetrics::new(&dummy_metrics_registry, crate::commands::logger());
-    let state_a = load_checkpoint(
-        &CompleteCheckpointLayout::new_untracked(path_a, unused_height)?,
-        own_subnet_type,
-        &dummy_metrics,
-        None,
-        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
-    )?;
-    let state_b = load_checkpoint(
-        &CompleteCheckpointLayout::new_untracked(path_b, unused_height)?,
+    load_checkpoint(
+        &CompleteCheckpointLayout::new_untracked(path, unused_height)?,
         own_subnet_type,
         &dummy_metrics,
         None,
         Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
-    )?;
+    )
+}
+
+/// Loads the checkpoints at `path_a` and `path_b` and diffs them.
+fn diff_checkpoints(path_a: PathBuf, path_b: PathBuf) -> Result<Changes, CheckpointError> {
+    let state_a = load_state(path_a)?;
+    let state_b = load_state(path_b)?;
 
     let tree_a = hash_state(&state_a);
     let tree_b = hash_state(&state_b);
@@ -50,3 +50,21 @@ pub fn do_diff(path_a: PathBuf, path_b: PathBuf) -> Result<(), String> {
 
     Ok(())
 }
+
+/// `cdiff --canisters` command entry point: reports per-canister changes
+/// between the checkpoints, as text or as JSON.
+pub fn do_canister_diff(path_a: PathBuf, path_b: PathBuf, json: bool) -> Result<(), String> {
+    let load = |path| load_state(path).map_err(|err| format!("✗ Diff FAILED:\n\t{}", err));
+    let d = diff_states(&load(path_a)?, &load(path_b)?);
+    if json {
+        let out = serde_json::to_string_pretty(&d)
+            .map_err(|err| format!("failed to serialize diff: {}", err))?;
+        println!("{}", out);
+    } else if d.is_empty() {
+        println!("✓ Canisters are identical");
+    } else {
+        print!("{}", d);
+    }
+
+    Ok(())
+}
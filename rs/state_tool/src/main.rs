@@ -2,7 +2,8 @@
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
 //! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! checkpoint manifests, import state trees, export and import single
+//! canisters).
 
 use clap::Parser;
 use ic_registry_routing_table::CanisterIdRange;
@@ -17,7 +18,40 @@ use std::path::PathBuf;
 enum Opt {
     /// Computes diff of canonical trees between checkpoints.
     #[clap(name = "cdiff")]
-    CDiff { path_a: PathBuf, path_b: PathBuf },
+    CDiff {
+        path_a: PathBuf,
+        path_b: PathBuf,
+        /// Reports per-canister changes (settings, module, cycles, memory,
+        /// queues, ingress history) instead of canonical tree changes.
+        #[clap(long)]
+        canisters: bool,
+        /// Prints the per-canister changes as JSON.
+        #[clap(long, requires = "canisters")]
+        json: bool,
+    },
+
+    /// Exports a single canister from a checkpoint as a self-describing tar archive.
+    #[clap(name = "export-canister")]
+    ExportCanister {
+        /// Path to a checkpoint.
+        checkpoint: PathBuf,
+        /// ID of the canister to export.
+        canister_id: String,
+        /// Path of the archive to create.
+        output: PathBuf,
+    },
+
+    /// Imports a canister archive created by `export-canister` into a checkpoint.
+    #[clap(name = "import-canister")]
+    ImportCanister {
+        /// Path to the canister archive.
+        archive: PathBuf,
+        /// Path to the checkpoint to import the canister into.
+        checkpoint: PathBuf,
+        /// Overwrite the canister if it already exists in the checkpoint.
+        #[clap(long)]
+        replace: bool,
+    },
 
     /// Computes partial state hash that is used for certification.
     #[clap(name = "chash")]
@@ -152,7 +186,28 @@ enum Opt {
 fn main() {
     let opt = Parser::parse();
     let result = match opt {
-        Opt::CDiff { path_a, path_b } => commands::cdiff::do_diff(path_a, path_b),
+        Opt::CDiff {
+            path_a,
+            path_b,
+            canisters,
+            json,
+        } => {
+            if canisters {
+                commands::cdiff::do_canister_diff(path_a, path_b, json)
+            } else {
+                commands::cdiff::do_diff(path_a, path_b)
+            }
+        }
+        Opt::ExportCanister {
+            checkpoint,
+            canister_id,
+            output,
+        } => commands::canister_archive::do_export(checkpoint, canister_id, output),
+        Opt::ImportCanister {
+            archive,
+            checkpoint,
+            replace,
+        } => commands::canister_archive::do_import(archive, checkpoint, replace),
         Opt::CHash { path } => commands::chash::do_hash(path),
         Opt::ImportState {
             state,
@@ -619,12 +619,13 @@ impl ApiType {
                 SystemMethod::CanisterHeartbeat => "heartbeat",
                 SystemMethod::CanisterGlobalTimer => "global timer",
                 SystemMethod::CanisterOnLowWasmMemory => "on low Wasm memory",
+                SystemMethod::CanisterOnLowCycles => "on low cycles",
                 SystemMethod::CanisterStart
                 | SystemMethod::CanisterInit
                 | SystemMethod::CanisterPreUpgrade
                 | SystemMethod::CanisterPostUpgrade
                 | SystemMethod::CanisterInspectMessage => {
-                    panic!("Only `canister_heartbeat`, `canister_global_timer`, and `canister_on_low_wasm_memory` are allowed.")
+                    panic!("Only `canister_heartbeat`, `canister_global_timer`, `canister_on_low_wasm_memory`, and `canister_on_low_cycles` are allowed.")
                 }
             },
             ApiType::Update { .. } => "update",
@@ -1010,6 +1010,12 @@ impl ExecutionTest {
                     .task_queue
                     .push_front(ExecutionTask::OnLowWasmMemory);
             }
+            CanisterTask::OnLowCycles => {
+                canister
+                    .system_state
+                    .task_queue
+                    .push_front(ExecutionTask::OnLowCycles);
+            }
         }
         let result = execute_canister(
             &self.exec_env,
@@ -1595,6 +1601,15 @@ impl ExecutionTest {
         self.query_handler.query_stats_for_testing(canister_id)
     }
 
+    pub fn method_query_stats_for_testing(
+        &self,
+        canister_id: &CanisterId,
+        method_name: &str,
+    ) -> Option<QueryStats> {
+        self.query_handler
+            .method_query_stats_for_testing(canister_id, method_name)
+    }
+
     pub fn query_stats_set_epoch_for_testing(&mut self, epoch: QueryStatsEpoch) {
         self.query_handler.query_stats_set_epoch_for_testing(epoch);
     }
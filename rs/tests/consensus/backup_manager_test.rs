@@ -215,6 +215,7 @@ pub fn test(env: TestEnv) {
     let cold_storage = Some(ColdStorage {
         cold_storage_dir: cold_storage_dir.clone(),
         versions_hot: 1,
+        object_storage: None,
     });
     let config = Config {
         push_metrics: false,
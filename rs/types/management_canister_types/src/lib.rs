@@ -990,6 +990,157 @@ pub struct QueryStats {
     response_payload_bytes_total: candid::Nat,
 }
 
+impl QueryStats {
+    pub fn new(
+        num_calls_total: u128,
+        num_instructions_total: u128,
+        request_payload_bytes_total: u128,
+        response_payload_bytes_total: u128,
+    ) -> Self {
+        Self {
+            num_calls_total: candid::Nat::from(num_calls_total),
+            num_instructions_total: candid::Nat::from(num_instructions_total),
+            request_payload_bytes_total: candid::Nat::from(request_payload_bytes_total),
+            response_payload_bytes_total: candid::Nat::from(response_payload_bytes_total),
+        }
+    }
+
+    pub fn num_calls_total(&self) -> u128 {
+        self.num_calls_total.0.to_u128().unwrap()
+    }
+
+    pub fn num_instructions_total(&self) -> u128 {
+        self.num_instructions_total.0.to_u128().unwrap()
+    }
+
+    pub fn request_payload_bytes_total(&self) -> u128 {
+        self.request_payload_bytes_total.0.to_u128().unwrap()
+    }
+
+    pub fn response_payload_bytes_total(&self) -> u128 {
+        self.response_payload_bytes_total.0.to_u128().unwrap()
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     method_name: text;
+///     query_stats: query_stats;
+/// })`
+#[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
+pub struct MethodQueryStats {
+    method_name: String,
+    query_stats: QueryStats,
+}
+
+impl MethodQueryStats {
+    pub fn new(method_name: String, query_stats: QueryStats) -> Self {
+        Self {
+            method_name,
+            query_stats,
+        }
+    }
+
+    pub fn method_name(&self) -> &str {
+        &self.method_name
+    }
+
+    pub fn query_stats(&self) -> &QueryStats {
+        &self.query_stats
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     wasm_memory_size: nat;
+///     stable_memory_size: nat;
+///     global_memory_size: nat;
+///     wasm_binary_size: nat;
+///     custom_sections_size: nat;
+///     canister_history_size: nat;
+///     wasm_chunk_store_size: nat;
+///     snapshots_size: nat;
+///     message_memory_size: nat;
+/// })`
+///
+/// All fields except `message_memory_size` add up to the `memory_size` of the
+/// canister. Messages are charged for separately.
+#[derive(Clone, CandidType, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct MemoryMetrics {
+    wasm_memory_size: candid::Nat,
+    stable_memory_size: candid::Nat,
+    global_memory_size: candid::Nat,
+    wasm_binary_size: candid::Nat,
+    custom_sections_size: candid::Nat,
+    canister_history_size: candid::Nat,
+    wasm_chunk_store_size: candid::Nat,
+    snapshots_size: candid::Nat,
+    message_memory_size: candid::Nat,
+}
+
+impl MemoryMetrics {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        wasm_memory_size: NumBytes,
+        stable_memory_size: NumBytes,
+        global_memory_size: NumBytes,
+        wasm_binary_size: NumBytes,
+        custom_sections_size: NumBytes,
+        canister_history_size: NumBytes,
+        wasm_chunk_store_size: NumBytes,
+        snapshots_size: NumBytes,
+        message_memory_size: NumBytes,
+    ) -> Self {
+        Self {
+            wasm_memory_size: candid::Nat::from(wasm_memory_size.get()),
+            stable_memory_size: candid::Nat::from(stable_memory_size.get()),
+            global_memory_size: candid::Nat::from(global_memory_size.get()),
+            wasm_binary_size: candid::Nat::from(wasm_binary_size.get()),
+            custom_sections_size: candid::Nat::from(custom_sections_size.get()),
+            canister_history_size: candid::Nat::from(canister_history_size.get()),
+            wasm_chunk_store_size: candid::Nat::from(wasm_chunk_store_size.get()),
+            snapshots_size: candid::Nat::from(snapshots_size.get()),
+            message_memory_size: candid::Nat::from(message_memory_size.get()),
+        }
+    }
+
+    pub fn wasm_memory_size(&self) -> NumBytes {
+        NumBytes::from(self.wasm_memory_size.0.to_u64().unwrap())
+    }
+
+    pub fn stable_memory_size(&self) -> NumBytes {
+        NumBytes::from(self.stable_memory_size.0.to_u64().unwrap())
+    }
+
+    pub fn global_memory_size(&self) -> NumBytes {
+        NumBytes::from(self.global_memory_size.0.to_u64().unwrap())
+    }
+
+    pub fn wasm_binary_size(&self) -> NumBytes {
+        NumBytes::from(self.wasm_binary_size.0.to_u64().unwrap())
+    }
+
+    pub fn custom_sections_size(&self) -> NumBytes {
+        NumBytes::from(self.custom_sections_size.0.to_u64().unwrap())
+    }
+
+    pub fn canister_history_size(&self) -> NumBytes {
+        NumBytes::from(self.canister_history_size.0.to_u64().unwrap())
+    }
+
+    pub fn wasm_chunk_store_size(&self) -> NumBytes {
+        NumBytes::from(self.wasm_chunk_store_size.0.to_u64().unwrap())
+    }
+
+    pub fn snapshots_size(&self) -> NumBytes {
+        NumBytes::from(self.snapshots_size.0.to_u64().unwrap())
+    }
+
+    pub fn message_memory_size(&self) -> NumBytes {
+        NumBytes::from(self.message_memory_size.0.to_u64().unwrap())
+    }
+}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     status : variant { running; stopping; stopped };
@@ -997,6 +1148,7 @@ pub struct QueryStats {
 ///     module_hash: opt blob;
 ///     controller: principal;
 ///     memory_size: nat;
+///     memory_metrics: memory_metrics;
 ///     cycles: nat;
 ///     freezing_threshold: nat,
 ///     idle_cycles_burned_per_day: nat;
@@ -1006,7 +1158,11 @@ pub struct QueryStats {
 ///         num_instructions: nat;
 ///         ingress_payload_size: nat;
 ///         egress_payload_size: nat;
-///     }
+///     };
+///     method_query_stats: vec record {
+///         method_name: text;
+///         query_stats: query_stats;
+///     };
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -1015,6 +1171,7 @@ pub struct CanisterStatusResultV2 {
     controller: candid::Principal,
     settings: DefiniteCanisterSettingsArgs,
     memory_size: candid::Nat,
+    memory_metrics: MemoryMetrics,
     cycles: candid::Nat,
     // this is for compat with Spec 0.12/0.13
     balance: Vec<(Vec<u8>, candid::Nat)>,
@@ -1022,6 +1179,7 @@ pub struct CanisterStatusResultV2 {
     idle_cycles_burned_per_day: candid::Nat,
     reserved_cycles: candid::Nat,
     query_stats: QueryStats,
+    method_query_stats: Vec<MethodQueryStats>,
 }
 
 impl CanisterStatusResultV2 {
@@ -1032,6 +1190,7 @@ impl CanisterStatusResultV2 {
         controller: PrincipalId,
         controllers: Vec<PrincipalId>,
         memory_size: NumBytes,
+        memory_metrics: MemoryMetrics,
         cycles: u128,
         compute_allocation: u64,
         memory_allocation: Option<u64>,
@@ -1044,6 +1203,7 @@ impl CanisterStatusResultV2 {
         query_num_instructions: u128,
         query_ingress_payload_size: u128,
         query_egress_payload_size: u128,
+        method_query_stats: Vec<MethodQueryStats>,
         wasm_memory_limit: Option<u64>,
     ) -> Self {
         Self {
@@ -1051,6 +1211,7 @@ impl CanisterStatusResultV2 {
             module_hash,
             controller: candid::Principal::from_text(controller.to_string()).unwrap(),
             memory_size: candid::Nat::from(memory_size.get()),
+            memory_metrics,
             cycles: candid::Nat::from(cycles),
             // the following is spec 0.12/0.13 compat;
             // "\x00" denotes cycles
@@ -1068,12 +1229,13 @@ impl CanisterStatusResultV2 {
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
             reserved_cycles: candid::Nat::from(reserved_cycles),
-            query_stats: QueryStats {
-                num_calls_total: candid::Nat::from(query_num_calls),
-                num_instructions_total: candid::Nat::from(query_num_instructions),
-                request_payload_bytes_total: candid::Nat::from(query_ingress_payload_size),
-                response_payload_bytes_total: candid::Nat::from(query_egress_payload_size),
-            },
+            query_stats: QueryStats::new(
+                query_num_calls,
+                query_num_instructions,
+                query_ingress_payload_size,
+                query_egress_payload_size,
+            ),
+            method_query_stats,
         }
     }
 
@@ -1097,6 +1259,10 @@ impl CanisterStatusResultV2 {
         NumBytes::from(self.memory_size.0.to_u64().unwrap())
     }
 
+    pub fn memory_metrics(&self) -> &MemoryMetrics {
+        &self.memory_metrics
+    }
+
     pub fn cycles(&self) -> u128 {
         self.cycles.0.to_u128().unwrap()
     }
@@ -1124,6 +1290,14 @@ impl CanisterStatusResultV2 {
     pub fn settings(&self) -> DefiniteCanisterSettingsArgs {
         self.settings.clone()
     }
+
+    pub fn query_stats(&self) -> &QueryStats {
+        &self.query_stats
+    }
+
+    pub fn method_query_stats(&self) -> &[MethodQueryStats] {
+        &self.method_query_stats
+    }
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -1713,6 +1887,7 @@ impl DataSize for PrincipalId {
 ///     log_visibility : opt log_visibility;
 ///     wasm_memory_limit: opt nat;
 ///     wasm_memory_threshold: opt nat;
+///     low_cycles_threshold_multiplier: opt nat;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
 pub struct CanisterSettingsArgs {
@@ -1724,6 +1899,7 @@ pub struct CanisterSettingsArgs {
     pub log_visibility: Option<LogVisibilityV2>,
     pub wasm_memory_limit: Option<candid::Nat>,
     pub wasm_memory_threshold: Option<candid::Nat>,
+    pub low_cycles_threshold_multiplier: Option<candid::Nat>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -1741,6 +1917,7 @@ impl CanisterSettingsArgs {
             log_visibility: None,
             wasm_memory_limit: None,
             wasm_memory_threshold: None,
+            low_cycles_threshold_multiplier: None,
         }
     }
 }
@@ -1755,6 +1932,7 @@ pub struct CanisterSettingsArgsBuilder {
     log_visibility: Option<LogVisibilityV2>,
     wasm_memory_limit: Option<candid::Nat>,
     wasm_memory_threshold: Option<candid::Nat>,
+    low_cycles_threshold_multiplier: Option<candid::Nat>,
 }
 
 #[allow(dead_code)]
@@ -1773,6 +1951,7 @@ impl CanisterSettingsArgsBuilder {
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
             wasm_memory_threshold: self.wasm_memory_threshold,
+            low_cycles_threshold_multiplier: self.low_cycles_threshold_multiplier,
         }
     }
 
@@ -1857,6 +2036,15 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets the multiple of the freezing threshold below which the
+    /// `canister_on_low_cycles` hook is activated.
+    pub fn with_low_cycles_threshold_multiplier(self, multiplier: u64) -> Self {
+        Self {
+            low_cycles_threshold_multiplier: Some(candid::Nat::from(multiplier)),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -20,6 +20,13 @@
 //! by taking for each [`CanisterId`] the median of the statistics reported by each node.
 //! The aggregated statistics are then added to the [`TotalQueryStats`], from where they can
 //! be accessed by canisters.
+//!
+//! Next to the statistics of the whole canister, a breakdown per query method is collected
+//! and aggregated the same way. Only calls to exported query and composite query methods that
+//! executed successfully are broken down. To bound the size of payloads and of the replicated
+//! state, at most [`MAX_QUERY_STATS_METHODS_PER_CANISTER`] methods are tracked per canister.
+//! A further method replaces the least used tracked method if it is used more, otherwise its
+//! calls only contribute to the statistics of the whole canister.
 
 use crate::{node_id_into_protobuf, node_id_try_from_option, QueryStatsEpoch};
 use ic_base_types::{CanisterId, NodeId, NumBytes};
@@ -27,13 +34,20 @@ use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
     state::{
         canister_state_bits::v1::{TotalQueryStats as TotalQueryStatsProto, Unsigned128},
-        stats::v1::{QueryStats as QueryStatsProto, QueryStatsInner},
+        stats::v1::{
+            MethodQueryStats as MethodQueryStatsProto, QueryStats as QueryStatsProto,
+            QueryStatsInner,
+        },
     },
     types::v1::{self as pb},
 };
 use prost::{bytes::BufMut, Message};
 use std::{collections::BTreeMap, hash::Hash};
 
+/// The maximum number of query methods per canister for which statistics are
+/// tracked individually.
+pub const MAX_QUERY_STATS_METHODS_PER_CANISTER: usize = 32;
+
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct QueryStats {
     pub num_calls: u32,
@@ -55,6 +69,36 @@ impl QueryStats {
     }
 }
 
+/// Adds `stats` to the entry of `method_name` in `method_stats`.
+///
+/// If there are [`MAX_QUERY_STATS_METHODS_PER_CANISTER`] entries already, a new entry only
+/// replaces the least used one, i.e., the one with the fewest calls and instructions, if the
+/// new entry is used more. Otherwise the stats are dropped.
+fn saturating_accumulate_method_stats(
+    method_stats: &mut BTreeMap<String, QueryStats>,
+    method_name: &str,
+    stats: &QueryStats,
+) {
+    if let Some(entry) = method_stats.get_mut(method_name) {
+        entry.saturating_accumulate(stats);
+        return;
+    }
+    if method_stats.len() >= MAX_QUERY_STATS_METHODS_PER_CANISTER {
+        let usage = |stats: &QueryStats| (stats.num_calls, stats.num_instructions);
+        match method_stats
+            .iter()
+            .min_by_key(|(_, stats)| usage(stats))
+            .map(|(method_name, stats)| (method_name.clone(), usage(stats)))
+        {
+            Some((least_used_method, least_usage)) if least_usage < usage(stats) => {
+                method_stats.remove(&least_used_method);
+            }
+            _ => return,
+        }
+    }
+    method_stats.insert(method_name.to_string(), stats.clone());
+}
+
 /// Total number of query stats collected since creation of the canister.
 ///
 /// This is a separate struct since values contained in here are accumulated
@@ -73,6 +117,17 @@ pub struct TotalQueryStats {
     pub egress_payload_size: u128,
 }
 
+impl TotalQueryStats {
+    /// Adds aggregated [`QueryStats`], which are scaled by `num_nodes` to account
+    /// for the queries executed by all nodes of the subnet.
+    pub fn add_scaled(&mut self, stats: &QueryStats, num_nodes: u128) {
+        self.num_calls += stats.num_calls as u128 * num_nodes;
+        self.num_instructions += stats.num_instructions as u128 * num_nodes;
+        self.ingress_payload_size += stats.ingress_payload_size as u128 * num_nodes;
+        self.egress_payload_size += stats.egress_payload_size as u128 * num_nodes;
+    }
+}
+
 fn get_u128_from_protobuf(proto: Option<Unsigned128>) -> Result<u128, ProxyDecodeError> {
     let array: [u8; 16] = proto
         .ok_or(ProxyDecodeError::MissingField(
@@ -135,7 +190,8 @@ pub struct LocalQueryStats {
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
 pub struct RawQueryStats {
     pub highest_aggregated_epoch: Option<QueryStatsEpoch>,
-    pub stats: BTreeMap<NodeId, BTreeMap<QueryStatsEpoch, BTreeMap<CanisterId, QueryStats>>>,
+    pub stats:
+        BTreeMap<NodeId, BTreeMap<QueryStatsEpoch, BTreeMap<CanisterId, CanisterQueryStats>>>,
 }
 
 impl RawQueryStats {
@@ -150,10 +206,21 @@ impl RawQueryStats {
                         proposer: Some(node_id_into_protobuf(*node_id)),
                         epoch: epoch.get(),
                         canister: Some(pb::CanisterId::from(*canister_id)),
-                        num_calls: stats.num_calls,
-                        num_instructions: stats.num_instructions,
-                        ingress_payload_size: stats.ingress_payload_size,
-                        egress_payload_size: stats.egress_payload_size,
+                        num_calls: stats.stats.num_calls,
+                        num_instructions: stats.stats.num_instructions,
+                        ingress_payload_size: stats.stats.ingress_payload_size,
+                        egress_payload_size: stats.stats.egress_payload_size,
+                        method_stats: stats
+                            .method_stats
+                            .iter()
+                            .map(|(method_name, stats)| MethodQueryStatsProto {
+                                method_name: method_name.clone(),
+                                num_calls: stats.num_calls,
+                                num_instructions: stats.num_instructions,
+                                ingress_payload_size: stats.ingress_payload_size,
+                                egress_payload_size: stats.egress_payload_size,
+                            })
+                            .collect(),
                     });
                 }
             }
@@ -191,11 +258,29 @@ impl TryFrom<QueryStatsProto> for RawQueryStats {
                     .or_default()
                     .insert(
                         canister,
-                        QueryStats {
-                            num_calls: entry.num_calls,
-                            num_instructions: entry.num_instructions,
-                            ingress_payload_size: entry.ingress_payload_size,
-                            egress_payload_size: entry.egress_payload_size,
+                        CanisterQueryStats {
+                            canister_id: canister,
+                            stats: QueryStats {
+                                num_calls: entry.num_calls,
+                                num_instructions: entry.num_instructions,
+                                ingress_payload_size: entry.ingress_payload_size,
+                                egress_payload_size: entry.egress_payload_size,
+                            },
+                            method_stats: entry
+                                .method_stats
+                                .into_iter()
+                                .map(|method| {
+                                    (
+                                        method.method_name,
+                                        QueryStats {
+                                            num_calls: method.num_calls,
+                                            num_instructions: method.num_instructions,
+                                            ingress_payload_size: method.ingress_payload_size,
+                                            egress_payload_size: method.egress_payload_size,
+                                        },
+                                    )
+                                })
+                                .collect(),
                         },
                     );
             }
@@ -304,6 +389,33 @@ impl QueryStatsPayload {
 pub struct CanisterQueryStats {
     pub canister_id: CanisterId,
     pub stats: QueryStats,
+    /// Breakdown of `stats` per query method.
+    ///
+    /// Contains at most [`MAX_QUERY_STATS_METHODS_PER_CANISTER`] entries.
+    pub method_stats: BTreeMap<String, QueryStats>,
+}
+
+impl CanisterQueryStats {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id,
+            stats: QueryStats::default(),
+            method_stats: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the `stats` of executing `method_name` to the stats of the canister.
+    pub fn saturating_accumulate_method(&mut self, method_name: &str, stats: &QueryStats) {
+        self.stats.saturating_accumulate(stats);
+        saturating_accumulate_method_stats(&mut self.method_stats, method_name, stats);
+    }
+
+    pub fn saturating_accumulate(&mut self, rhs: &Self) {
+        self.stats.saturating_accumulate(&rhs.stats);
+        for (method_name, stats) in &rhs.method_stats {
+            saturating_accumulate_method_stats(&mut self.method_stats, method_name, stats);
+        }
+    }
 }
 
 impl From<&CanisterQueryStats> for pb::CanisterQueryStats {
@@ -314,6 +426,17 @@ impl From<&CanisterQueryStats> for pb::CanisterQueryStats {
             num_instructions: entry.stats.num_instructions,
             ingress_payload_size: entry.stats.ingress_payload_size,
             egress_payload_size: entry.stats.egress_payload_size,
+            method_stats: entry
+                .method_stats
+                .iter()
+                .map(|(method_name, stats)| pb::MethodQueryStats {
+                    method_name: method_name.clone(),
+                    num_calls: stats.num_calls,
+                    num_instructions: stats.num_instructions,
+                    ingress_payload_size: stats.ingress_payload_size,
+                    egress_payload_size: stats.egress_payload_size,
+                })
+                .collect(),
         }
     }
 }
@@ -333,6 +456,21 @@ impl TryFrom<&pb::CanisterQueryStats> for CanisterQueryStats {
                 ingress_payload_size: entry.ingress_payload_size,
                 egress_payload_size: entry.egress_payload_size,
             },
+            method_stats: entry
+                .method_stats
+                .iter()
+                .map(|method| {
+                    (
+                        method.method_name.clone(),
+                        QueryStats {
+                            num_calls: method.num_calls,
+                            num_instructions: method.num_instructions,
+                            ingress_payload_size: method.ingress_payload_size,
+                            egress_payload_size: method.egress_payload_size,
+                        },
+                    )
+                })
+                .collect(),
         })
     }
 }
@@ -389,6 +527,10 @@ mod tests {
                 .map(|idx| CanisterQueryStats {
                     canister_id: CanisterId::from(idx),
                     stats: rng_epoch_stats(&mut rng),
+                    method_stats: BTreeMap::from([
+                        ("get".to_string(), rng_epoch_stats(&mut rng)),
+                        ("list".to_string(), rng_epoch_stats(&mut rng)),
+                    ]),
                 })
                 .collect(),
         }
@@ -400,7 +542,14 @@ mod tests {
         let mut rng = ChaCha8Rng::seed_from_u64(1454);
 
         let mut inner = BTreeMap::new();
-        inner.insert(canister_test_id(1), rng_epoch_stats(&mut rng));
+        inner.insert(
+            canister_test_id(1),
+            CanisterQueryStats {
+                canister_id: canister_test_id(1),
+                stats: rng_epoch_stats(&mut rng),
+                method_stats: BTreeMap::from([("get".to_string(), rng_epoch_stats(&mut rng))]),
+            },
+        );
         let mut record = BTreeMap::new();
         record.insert(QueryStatsEpoch::new(0), inner);
         let mut stats = BTreeMap::new();
@@ -417,6 +566,67 @@ mod tests {
         assert_eq!(test, check_test);
     }
 
+    /// Method stats are only tracked for a bounded number of methods
+    #[test]
+    fn method_stats_are_bounded() {
+        let stats = QueryStats {
+            num_calls: 1,
+            num_instructions: 10,
+            ingress_payload_size: 100,
+            egress_payload_size: 1000,
+        };
+
+        let mut canister_stats = CanisterQueryStats::new(canister_test_id(1));
+        for idx in 0..MAX_QUERY_STATS_METHODS_PER_CANISTER + 10 {
+            canister_stats.saturating_accumulate_method(&format!("method_{}", idx), &stats);
+        }
+        canister_stats.saturating_accumulate_method("method_0", &stats);
+
+        assert_eq!(
+            canister_stats.method_stats.len(),
+            MAX_QUERY_STATS_METHODS_PER_CANISTER
+        );
+        assert_eq!(
+            canister_stats.stats.num_calls as usize,
+            MAX_QUERY_STATS_METHODS_PER_CANISTER + 11
+        );
+        assert_eq!(canister_stats.method_stats["method_0"].num_calls, 2);
+        assert!(!canister_stats
+            .method_stats
+            .contains_key(&format!("method_{}", MAX_QUERY_STATS_METHODS_PER_CANISTER)));
+    }
+
+    /// Once the method stats are full, a method that is used more replaces the least used one
+    #[test]
+    fn method_stats_evict_least_used_method() {
+        let stats = |num_calls, num_instructions| QueryStats {
+            num_calls,
+            num_instructions,
+            ingress_payload_size: 0,
+            egress_payload_size: 0,
+        };
+
+        let mut canister_stats = CanisterQueryStats::new(canister_test_id(1));
+        for idx in 0..MAX_QUERY_STATS_METHODS_PER_CANISTER {
+            canister_stats.saturating_accumulate_method(&format!("method_{}", idx), &stats(2, 100));
+        }
+        canister_stats.saturating_accumulate_method("method_1", &stats(1, 0));
+
+        // Used less than every tracked method.
+        canister_stats.saturating_accumulate_method("rare", &stats(1, 10));
+        assert!(!canister_stats.method_stats.contains_key("rare"));
+
+        // Used more than the least used method.
+        canister_stats.saturating_accumulate_method("popular", &stats(3, 10));
+        assert_eq!(
+            canister_stats.method_stats.len(),
+            MAX_QUERY_STATS_METHODS_PER_CANISTER
+        );
+        assert!(!canister_stats.method_stats.contains_key("method_0"));
+        assert_eq!(canister_stats.method_stats["popular"], stats(3, 10));
+        assert_eq!(canister_stats.method_stats["method_1"], stats(3, 100));
+    }
+
     fn rng_epoch_stats<R>(rng: &mut R) -> QueryStats
     where
         R: RngCore,
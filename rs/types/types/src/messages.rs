@@ -444,12 +444,14 @@ impl TryFrom<CanisterMessage> for CanisterCall {
 }
 
 /// A canister task can be thought of as a special system message that the IC
-/// sends to the canister to execute its heartbeat or the global timer method.
+/// sends to the canister to execute its heartbeat, the global timer method or
+/// one of its low resource hooks.
 #[derive(Clone, Debug, PartialEq, Eq, EnumIter, Hash)]
 pub enum CanisterTask {
     Heartbeat = 1,
     GlobalTimer = 2,
     OnLowWasmMemory = 3,
+    OnLowCycles = 4,
 }
 
 impl From<CanisterTask> for SystemMethod {
@@ -458,6 +460,7 @@ impl From<CanisterTask> for SystemMethod {
             CanisterTask::Heartbeat => SystemMethod::CanisterHeartbeat,
             CanisterTask::GlobalTimer => SystemMethod::CanisterGlobalTimer,
             CanisterTask::OnLowWasmMemory => SystemMethod::CanisterOnLowWasmMemory,
+            CanisterTask::OnLowCycles => SystemMethod::CanisterOnLowCycles,
         }
     }
 }
@@ -468,6 +471,7 @@ impl Display for CanisterTask {
             Self::Heartbeat => write!(f, "Heartbeat task"),
             Self::GlobalTimer => write!(f, "Global timer task"),
             Self::OnLowWasmMemory => write!(f, "On low Wasm memory task"),
+            Self::OnLowCycles => write!(f, "On low cycles task"),
         }
     }
 }
@@ -478,6 +482,7 @@ impl From<&CanisterTask> for pb::execution_task::CanisterTask {
             CanisterTask::Heartbeat => pb::execution_task::CanisterTask::Heartbeat,
             CanisterTask::GlobalTimer => pb::execution_task::CanisterTask::Timer,
             CanisterTask::OnLowWasmMemory => pb::execution_task::CanisterTask::OnLowWasmMemory,
+            CanisterTask::OnLowCycles => pb::execution_task::CanisterTask::OnLowCycles,
         }
     }
 }
@@ -496,6 +501,7 @@ impl TryFrom<pb::execution_task::CanisterTask> for CanisterTask {
             pb::execution_task::CanisterTask::Heartbeat => Ok(CanisterTask::Heartbeat),
             pb::execution_task::CanisterTask::Timer => Ok(CanisterTask::GlobalTimer),
             pb::execution_task::CanisterTask::OnLowWasmMemory => Ok(CanisterTask::OnLowWasmMemory),
+            pb::execution_task::CanisterTask::OnLowCycles => Ok(CanisterTask::OnLowCycles),
         }
     }
 }
@@ -846,7 +852,7 @@ mod tests {
         // See note [Handling changes to Enums in Replicated State] for how to proceed.
         assert_eq!(
             CanisterTask::iter().map(|x| x as i32).collect::<Vec<i32>>(),
-            [1, 2, 3]
+            [1, 2, 3, 4]
         );
     }
 
@@ -149,6 +149,9 @@ pub enum SystemMethod {
     CanisterGlobalTimer = 7,
     /// A system method that runs when the available Wasm memory is below threshold.
     CanisterOnLowWasmMemory = 8,
+    /// A system method that runs when the cycles balance drops below a
+    /// multiple of the freezing threshold.
+    CanisterOnLowCycles = 9,
 }
 
 impl TryFrom<&str> for SystemMethod {
@@ -164,6 +167,7 @@ impl TryFrom<&str> for SystemMethod {
             "canister_heartbeat" => Ok(SystemMethod::CanisterHeartbeat),
             "canister_global_timer" => Ok(SystemMethod::CanisterGlobalTimer),
             "canister_on_low_wasm_memory" => Ok(SystemMethod::CanisterOnLowWasmMemory),
+            "canister_on_low_cycles" => Ok(SystemMethod::CanisterOnLowCycles),
             _ => Err(format!("Cannot convert {} to SystemMethod.", value)),
         }
     }
@@ -180,6 +184,7 @@ impl fmt::Display for SystemMethod {
             Self::CanisterHeartbeat => write!(f, "canister_heartbeat"),
             Self::CanisterGlobalTimer => write!(f, "canister_global_timer"),
             Self::CanisterOnLowWasmMemory => write!(f, "canister_on_low_wasm_memory"),
+            Self::CanisterOnLowCycles => write!(f, "canister_on_low_cycles"),
         }
     }
 }
@@ -197,6 +202,7 @@ impl From<&SystemMethod> for pb::wasm_method::SystemMethod {
             SystemMethod::CanisterHeartbeat => PbSystemMethod::CanisterHeartbeat,
             SystemMethod::CanisterGlobalTimer => PbSystemMethod::CanisterGlobalTimer,
             SystemMethod::CanisterOnLowWasmMemory => PbSystemMethod::CanisterOnLowWasmMemory,
+            SystemMethod::CanisterOnLowCycles => PbSystemMethod::CanisterOnLowCycles,
         }
     }
 }
@@ -220,6 +226,7 @@ impl TryFrom<pb::wasm_method::SystemMethod> for SystemMethod {
             PbSystemMethod::CanisterHeartbeat => Ok(SystemMethod::CanisterHeartbeat),
             PbSystemMethod::CanisterGlobalTimer => Ok(SystemMethod::CanisterGlobalTimer),
             PbSystemMethod::CanisterOnLowWasmMemory => Ok(SystemMethod::CanisterOnLowWasmMemory),
+            PbSystemMethod::CanisterOnLowCycles => Ok(SystemMethod::CanisterOnLowCycles),
         }
     }
 }
@@ -417,7 +424,7 @@ mod tests {
         // See note [Handling changes to Enums in Replicated State] for how to proceed.
         assert_eq!(
             SystemMethod::iter().map(|x| x as i32).collect::<Vec<i32>>(),
-            [1, 2, 3, 4, 5, 6, 7, 8]
+            [1, 2, 3, 4, 5, 6, 7, 8, 9]
         );
     }
 
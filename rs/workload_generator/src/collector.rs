@@ -1,6 +1,6 @@
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 
-use crate::{message::Message, plan::Plan};
+use crate::message::Message;
 use std::{
     sync::mpsc::{channel, Receiver, Sender},
     thread,
@@ -11,9 +11,9 @@ use std::{
 /// capture all data sent to the sender and then will return on the handle the
 /// entire dataset.
 ///
-/// The plan is essential to pre-allocating the array.
+/// The number of expected requests is essential to pre-allocating the array.
 pub fn start<T>(
-    plan: Plan,
+    requests: usize,
     periodic_output: bool,
 ) -> (Sender<Message<T>>, thread::JoinHandle<Vec<T>>)
 where
@@ -22,7 +22,7 @@ where
     let (sender, receiver) = channel::<Message<T>>();
     (
         sender,
-        thread::spawn(move || collect(&receiver, plan, periodic_output)),
+        thread::spawn(move || collect(&receiver, requests, periodic_output)),
     )
 }
 
@@ -30,13 +30,13 @@ pub trait RequestInfo {
     fn is_succ(&self) -> bool;
 }
 
-fn collect<T>(receiver: &Receiver<Message<T>>, plan: Plan, periodic_output: bool) -> Vec<T>
+fn collect<T>(receiver: &Receiver<Message<T>>, requests: usize, periodic_output: bool) -> Vec<T>
 where
     T: 'static + Send + RequestInfo,
 {
-    let num_expected = plan.requests;
+    let num_expected = requests;
     let mut eof_received = false;
-    let mut messages: Vec<T> = Vec::with_capacity(plan.requests);
+    let mut messages: Vec<T> = Vec::with_capacity(requests);
 
     let m = MultiProgress::new();
 
@@ -3,7 +3,7 @@ use crate::{
     content_length::ContentLength,
     message::Message,
     metrics::{FUTURE_STARTED, REQUEST_STARTING},
-    plan::{EngineCall, Plan},
+    plan::{EngineCall, Plan, ScenarioCall, ScenarioPlan},
     stats::Fact,
     RequestType,
 };
@@ -25,10 +25,11 @@ use std::{
     collections::HashMap,
     env, fs,
     str::FromStr,
+    sync::Arc,
     time::{Duration, Instant},
 };
 use tokio::{
-    sync::mpsc::{channel, Receiver, Sender},
+    sync::mpsc::{channel, error::SendError, Receiver, Sender},
     time::sleep_until,
 };
 use url::{Host, Url};
@@ -63,6 +64,25 @@ pub struct CallResult {
     err_msg: Option<String>,
 }
 
+/// Sends the results of the calls of a request to the evaluation, labeling
+/// their facts with the scenario step of the request.
+#[derive(Clone)]
+struct ResultSender {
+    tx: Sender<CallResult>,
+    step: Option<String>,
+}
+
+impl ResultSender {
+    fn new(tx: Sender<CallResult>) -> Self {
+        Self { tx, step: None }
+    }
+
+    async fn send(&self, mut result: CallResult) -> Result<(), SendError<CallResult>> {
+        result.fact = result.fact.with_step(self.step.clone());
+        self.tx.send(result).await
+    }
+}
+
 /// The engine of making requests. The engine implements making the requests and
 /// producing facts for the stats collector to process.
 #[derive(Clone)]
@@ -195,7 +215,7 @@ impl Engine {
             request_type,
             canister_method_name,
         );
-        let (collector, rec_handle) = collector::start::<Fact>(plan.clone(), periodic_output);
+        let (collector, rec_handle) = collector::start::<Fact>(requests, periodic_output);
 
         let (tx, rx) = channel(requests);
         let time_origin = Instant::now();
@@ -216,7 +236,7 @@ impl Engine {
             let target_instant =
                 time_origin + START_OFFSET + Duration::from_secs_f64(inter_arrival_time * n as f64);
             sleep_until(tokio::time::Instant::from_std(target_instant)).await;
-            let tx = tx.clone();
+            let tx = ResultSender::new(tx.clone());
             let plan = plan.clone();
             let agent = self.agents[n % self.agents.len()].clone();
             FUTURE_STARTED.inc();
@@ -239,10 +259,101 @@ impl Engine {
         rec_handle.join().unwrap()
     }
 
+    /// Execute the requests of a scenario, issuing each request at the time
+    /// planned by its stages.
+    pub async fn execute_scenario(
+        &self,
+        scenario: ScenarioPlan,
+        periodic_output: bool,
+    ) -> Vec<Fact> {
+        let requests = scenario.requests();
+        if requests == 0 {
+            debug!("Not executing any requests");
+            return vec![];
+        }
+        debug!(
+            "⏱️  Executing {} requests of {} steps from {} identities",
+            requests,
+            scenario.steps.len(),
+            scenario.identities.len()
+        );
+
+        let scenario = Arc::new(scenario);
+        let (collector, rec_handle) = collector::start::<Fact>(requests, periodic_output);
+
+        let (tx, rx) = channel(requests);
+        let time_origin = Instant::now();
+
+        let rx_handle =
+            tokio::task::spawn(Engine::evaluate_requests(rx, collector, None, time_origin));
+
+        let mut tx_handles = vec![];
+        for n in 0..requests {
+            let target_instant = time_origin + START_OFFSET + scenario.schedule[n];
+            sleep_until(tokio::time::Instant::from_std(target_instant)).await;
+            let ScenarioCall {
+                step,
+                identity,
+                call,
+            } = scenario.generate_call(n);
+            let tx = ResultSender {
+                tx: tx.clone(),
+                step: Some(step.name.clone()),
+            };
+            let canister_id = step.canister_id;
+            let mut agent = self.agents[n % self.agents.len()].clone();
+            agent.sender = identity.sender.clone();
+            agent.sender_field = identity.sender_field.clone();
+            let scenario = scenario.clone();
+            FUTURE_STARTED.inc();
+            tx_handles.push(tokio::task::spawn(async move {
+                REQUEST_STARTING.inc();
+                match call {
+                    EngineCall::Read { method, arg } => {
+                        Engine::execute_query(
+                            &agent,
+                            tx,
+                            time_origin,
+                            &canister_id,
+                            method,
+                            arg,
+                            n,
+                        )
+                        .await;
+                    }
+                    EngineCall::Write { method, arg } => {
+                        Engine::execute_update(
+                            &agent,
+                            tx,
+                            time_origin,
+                            &canister_id,
+                            &scenario.nonce,
+                            method,
+                            arg,
+                            n,
+                        )
+                        .await;
+                    }
+                }
+            }));
+        }
+        for tx_handle in tx_handles {
+            tx_handle.await.unwrap_or_else(|_| {
+                panic!("Await the tx failed.");
+            });
+        }
+        std::mem::drop(tx);
+        rx_handle.await.unwrap_or_else(|_| {
+            panic!("Await the rx failed.");
+        });
+
+        rec_handle.join().unwrap()
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn execute_request(
         agent: Agent,
-        tx: Sender<CallResult>,
+        tx: ResultSender,
         time_origin: Instant,
         plan: &Plan,
         n: usize,
@@ -250,12 +361,22 @@ impl Engine {
     ) -> bool {
         match plan.generate_call(n, random_query_payload) {
             EngineCall::Read { method, arg } => {
-                Engine::execute_query(&agent, tx, time_origin, plan, method, arg, n)
+                Engine::execute_query(&agent, tx, time_origin, &plan.canister_id, method, arg, n)
                     .await
                     .is_some()
             }
             EngineCall::Write { method, arg } => {
-                Engine::execute_update(&agent, tx, time_origin, plan, method, arg, n).await
+                Engine::execute_update(
+                    &agent,
+                    tx,
+                    time_origin,
+                    &plan.canister_id,
+                    &plan.nonce,
+                    method,
+                    arg,
+                    n,
+                )
+                .await
             }
         }
     }
@@ -263,15 +384,15 @@ impl Engine {
     #[allow(clippy::too_many_arguments)]
     async fn execute_query(
         agent: &Agent,
-        tx: Sender<CallResult>,
+        tx: ResultSender,
         _time_origin: Instant,
-        plan: &Plan,
+        canister_id: &CanisterId,
         method: String,
         arg: Vec<u8>,
         n: usize,
     ) -> Option<u32> {
         let time_query_start = Instant::now();
-        let response = agent.execute_query(&plan.canister_id, &method, arg).await;
+        let response = agent.execute_query(canister_id, &method, arg).await;
         let time_query_end = Instant::now();
         debug!("Sent query ({}). Response was: {:?}", n, response);
 
@@ -334,19 +455,19 @@ impl Engine {
     #[allow(clippy::too_many_arguments)]
     async fn execute_update(
         agent: &Agent,
-        tx: Sender<CallResult>,
+        tx: ResultSender,
         time_origin: Instant,
-        plan: &Plan,
+        canister_id: &CanisterId,
+        nonce: &str,
         method: String,
         arg: Vec<u8>,
         n: usize,
     ) -> bool {
-        let nonce =
-            ic_crypto_sha2::Sha256::hash(&format!("inc {} {}", plan.nonce.clone(), n).into_bytes());
+        let nonce = ic_crypto_sha2::Sha256::hash(&format!("inc {} {}", nonce, n).into_bytes());
         let deadline = Instant::now() + agent.ingress_timeout;
         let (content, request_id) = prepare_update(
             &agent.sender,
-            &plan.canister_id,
+            canister_id,
             method,
             arg,
             nonce.to_vec(),
@@ -357,7 +478,7 @@ impl Engine {
 
         debug!("Sending signed update. request id: {}.", request_id);
 
-        let path = update_path(plan.canister_id);
+        let path = update_path(*canister_id);
         let time_start = std::time::Instant::now();
         debug!(
             "Sending update() call ({}) after {}ms since origin",
@@ -467,7 +588,7 @@ impl Engine {
                     let wait = Engine::wait_ingress_for_counter_canister(
                         agent,
                         request_id.clone(),
-                        &plan.canister_id,
+                        canister_id,
                         deadline,
                     )
                     .await;
@@ -582,7 +703,7 @@ impl Engine {
 
     async fn check_query(
         resp: Option<Vec<u8>>,
-        tx: Sender<CallResult>,
+        tx: ResultSender,
         time_query_start: Instant,
         time_query_end: Instant,
     ) -> Option<u32> {
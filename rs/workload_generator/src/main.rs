@@ -120,7 +120,7 @@ async fn main() {
         .arg(
             Arg::new("rps")
                 .short('r')
-                .required(true)
+                .required_unless_present("scenario")
                 .takes_value(true)
                 .help("Requests per second to generate. Accepts fractional values, e.g. 1.5 rps."),
         )
@@ -268,6 +268,14 @@ async fn main() {
                 .takes_value(true)
                 .help("The number of seconds to wait before timing out ingress messages."),
         )
+        .arg(
+            Arg::new("scenario")
+                .long("scenario")
+                .value_name("FILE")
+                .takes_value(true)
+                .conflicts_with_all(&["rps", "updates", "canister", "canister-id"])
+                .help("YAML file describing a scenario: a weighted mix of calls to run in stages at changing rates from a pool of generated identities. Replaces --rps, --method and the canister options, see README.md."),
+        )
         .arg(
             Arg::new("random-query-payload")
                 .long("random-query-payload")
@@ -317,7 +325,9 @@ async fn main() {
         .unwrap()
         .parse::<usize>()
         .unwrap();
-    let rps = matches.value_of("rps").unwrap().parse::<f64>().unwrap();
+    let rps = matches
+        .value_of("rps")
+        .map_or(0f64, |rps| rps.parse::<f64>().unwrap());
     let rpms = (rps * 1000f64).floor() as usize;
 
     let principal_id = matches
@@ -333,6 +343,11 @@ async fn main() {
         }
     };
 
+    let scenario = matches.value_of("scenario").map(|path| {
+        plan::ScenarioPlan::load(Path::new(path), nonce.clone())
+            .unwrap_or_else(|err| panic!("Failed to load the scenario: {}", err))
+    });
+
     let log = get_logger();
     let _guard = slog_scope::set_global_logger(log);
 
@@ -471,50 +486,6 @@ async fn main() {
                 eng.wait_for_all_agents_to_be_healthy().await;
             }
 
-            // use id of install canister if no id specified
-            let canister_id = if let Some(s) = matches.value_of("canister-id") {
-                let canister_id =
-                    CanisterId::try_from(PrincipalId::from_str(s).unwrap_or_else(|_| {
-                        panic!("Illegal value for option --canister-id: '{}'", s);
-                    }))
-                    .unwrap();
-                if let Some(wasm_file_path) = matches.value_of_os("canister").map(Path::new) {
-                    let mut install_succeeded = false;
-                    for url in install_endpoint {
-                        match canister::install_canister(
-                            http_client.clone(),
-                            sender.clone(),
-                            url,
-                            canister_id,
-                            Some(wasm_file_path),
-                        )
-                        .await
-                        {
-                            Ok(()) => {
-                                install_succeeded = true;
-                                break;
-                            }
-                            Err(err) => println!(
-                                "⚠️  Could not install canister at replica url {}. {}",
-                                url, err
-                            ),
-                        }
-                    }
-
-                    if !install_succeeded {
-                        panic!("Failed to install wasm to existing canister");
-                    }
-                }
-                canister_id
-            } else {
-                let wasm_file_path = matches.value_of_os("canister").map(Path::new);
-                canister::setup_canister(http_client, sender, install_endpoint, wasm_file_path)
-                    .await
-                    .unwrap_or_else(|err| {
-                        panic!("Failed to create canister: {}", err);
-                    })
-            };
-
             // case insensitive
             let chart_size = ChartSize::from_str(
                 matches
@@ -527,14 +498,66 @@ async fn main() {
             // Hold all summaries so we can serialize them later if needed
             let mut summaries: Vec<Summary> = Vec::new();
 
-            // Make sure to save the guard, see documentation for more information
-            println!(
-                "Running {:?} rps for {} seconds, req_type = {:?}",
-                rps, duration, request_type
-            );
+            let facts = if let Some(scenario) = scenario {
+                println!(
+                    "Running scenario of {} steps with {} requests from {} identities",
+                    scenario.steps.len(),
+                    scenario.requests(),
+                    scenario.identities.len()
+                );
+                eng.execute_scenario(scenario, periodic_output).await
+            } else {
+                // use id of install canister if no id specified
+                let canister_id = if let Some(s) = matches.value_of("canister-id") {
+                    let canister_id =
+                        CanisterId::try_from(PrincipalId::from_str(s).unwrap_or_else(|_| {
+                            panic!("Illegal value for option --canister-id: '{}'", s);
+                        }))
+                        .unwrap();
+                    if let Some(wasm_file_path) = matches.value_of_os("canister").map(Path::new) {
+                        let mut install_succeeded = false;
+                        for url in install_endpoint {
+                            match canister::install_canister(
+                                http_client.clone(),
+                                sender.clone(),
+                                url,
+                                canister_id,
+                                Some(wasm_file_path),
+                            )
+                            .await
+                            {
+                                Ok(()) => {
+                                    install_succeeded = true;
+                                    break;
+                                }
+                                Err(err) => println!(
+                                    "⚠️  Could not install canister at replica url {}. {}",
+                                    url, err
+                                ),
+                            }
+                        }
 
-            let facts = eng
-                .execute_rps(
+                        if !install_succeeded {
+                            panic!("Failed to install wasm to existing canister");
+                        }
+                    }
+                    canister_id
+                } else {
+                    let wasm_file_path = matches.value_of_os("canister").map(Path::new);
+                    canister::setup_canister(http_client, sender, install_endpoint, wasm_file_path)
+                        .await
+                        .unwrap_or_else(|err| {
+                            panic!("Failed to create canister: {}", err);
+                        })
+                };
+
+                // Make sure to save the guard, see documentation for more information
+                println!(
+                    "Running {:?} rps for {} seconds, req_type = {:?}",
+                    rps, duration, request_type
+                );
+
+                eng.execute_rps(
                     rpms,
                     request_type,
                     canister_method_name,
@@ -546,7 +569,8 @@ async fn main() {
                     periodic_output,
                     random_query_payload,
                 )
-                .await;
+                .await
+            };
 
             // Drop the engine with the hope that all client connections will be closed.
             // Sometimes we may end up in situation where all file descriptors
@@ -1,8 +1,12 @@
 use crate::RequestType;
 use byte_unit::Byte;
 use candid::Encode;
-use ic_types::CanisterId;
-use rand::RngCore;
+use candid_parser::parse_idl_args;
+use ic_canister_client::{Ed25519KeyPair, Sender as AgentSender};
+use ic_types::{messages::Blob, CanisterId, PrincipalId};
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use serde::Deserialize;
+use std::{convert::TryFrom, path::Path, str::FromStr, time::Duration};
 
 #[derive(Clone)]
 pub struct Plan {
@@ -98,3 +102,394 @@ impl Plan {
         }
     }
 }
+
+/// A load test scenario, as read from the YAML file given with `--scenario`.
+///
+/// Each request picks one of the `steps` at random, proportionally to their
+/// weights, and is sent on behalf of one of the identities of the pool. The
+/// rate at which requests are issued changes linearly over each stage, from
+/// the target rate of the previous stage (or zero for the first one) to the
+/// target rate of the stage, so that ramp-up, steady and ramp-down phases are
+/// stages with an increasing, equal and decreasing target rate respectively.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub steps: Vec<ScenarioStep>,
+    pub stages: Vec<ScenarioStage>,
+    #[serde(default)]
+    pub identities: IdentityPoolConfig,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepKind {
+    Query,
+    Update,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioStep {
+    /// The name of the step in the summary. Defaults to the method name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The canister to call, in text format (xxxxx-xxx).
+    pub canister_id: String,
+    pub kind: StepKind,
+    pub method: String,
+    #[serde(default = "default_weight")]
+    pub weight: u64,
+    /// The arguments of the call in the Candid text format. The placeholders
+    /// `{{n}}` (the index of the request), `{{random}}` (a random `nat64`),
+    /// `{{nonce}}` and `{{caller}}` (the principal of the sending identity) are
+    /// substituted for each request.
+    #[serde(default = "default_args")]
+    pub args: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioStage {
+    pub duration_secs: u64,
+    pub target_rps: f64,
+}
+
+/// The pool of identities requests are sent from. The key pairs are generated
+/// deterministically from the seed, so the principals are the same across runs.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IdentityPoolConfig {
+    pub count: usize,
+    #[serde(default)]
+    pub seed: u64,
+}
+
+impl Default for IdentityPoolConfig {
+    fn default() -> Self {
+        Self { count: 1, seed: 0 }
+    }
+}
+
+fn default_weight() -> u64 {
+    1
+}
+
+fn default_args() -> String {
+    "()".to_string()
+}
+
+#[derive(Clone)]
+pub struct Identity {
+    pub sender: AgentSender,
+    pub sender_field: Blob,
+    pub principal: PrincipalId,
+}
+
+impl Identity {
+    fn generate(rng: &mut StdRng) -> Self {
+        let sender = AgentSender::from_keypair(&Ed25519KeyPair::generate(rng));
+        let principal = sender.get_principal_id();
+        Self {
+            sender,
+            sender_field: Blob(principal.into_vec()),
+            principal,
+        }
+    }
+}
+
+/// A step of a scenario, ready to be executed.
+#[derive(Clone)]
+pub struct PlannedStep {
+    pub name: String,
+    pub canister_id: CanisterId,
+    pub kind: StepKind,
+    pub method: String,
+    pub weight: u64,
+    args: String,
+}
+
+/// A call of a scenario step on behalf of an identity of the pool.
+pub struct ScenarioCall<'a> {
+    pub step: &'a PlannedStep,
+    pub identity: &'a Identity,
+    pub call: EngineCall,
+}
+
+/// The plan of a scenario: the steps to pick from, the identities to send
+/// from, and the time at which each request is issued.
+#[derive(Clone)]
+pub struct ScenarioPlan {
+    pub steps: Vec<PlannedStep>,
+    pub identities: Vec<Identity>,
+    /// The offset of each request from the start of the run, in order.
+    pub schedule: Vec<Duration>,
+    pub nonce: String,
+}
+
+impl ScenarioPlan {
+    /// Reads the scenario from the YAML file at `path` and plans it.
+    pub fn load(path: &Path, nonce: String) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Couldn't read the scenario file {:?}: {}", path, err))?;
+        let scenario: Scenario = serde_yaml::from_str(&contents)
+            .map_err(|err| format!("Couldn't parse the scenario file {:?}: {}", path, err))?;
+        Self::new(scenario, nonce)
+    }
+
+    pub fn new(scenario: Scenario, nonce: String) -> Result<Self, String> {
+        if scenario.steps.is_empty() {
+            return Err("The scenario has no steps".to_string());
+        }
+        if scenario.identities.count == 0 {
+            return Err("The identity pool of the scenario is empty".to_string());
+        }
+        let mut steps = Vec::with_capacity(scenario.steps.len());
+        for step in scenario.steps {
+            let name = step.name.unwrap_or_else(|| step.method.clone());
+            let canister_id = PrincipalId::from_str(&step.canister_id)
+                .map_err(|err| err.to_string())
+                .and_then(|id| CanisterId::try_from(id).map_err(|err| err.to_string()))
+                .map_err(|err| {
+                    format!(
+                        "Invalid canister id {} of step {}: {}",
+                        step.canister_id, name, err
+                    )
+                })?;
+            // Catch errors in the argument template before sending any request.
+            encode_args(&step.args, 0, 0, &nonce, &PrincipalId::new_anonymous())
+                .map_err(|err| format!("Invalid arguments of step {}: {}", name, err))?;
+            steps.push(PlannedStep {
+                name,
+                canister_id,
+                kind: step.kind,
+                method: step.method,
+                weight: step.weight,
+                args: step.args,
+            });
+        }
+        if steps.iter().all(|step| step.weight == 0) {
+            return Err("All steps of the scenario have a weight of zero".to_string());
+        }
+        for stage in &scenario.stages {
+            if !stage.target_rps.is_finite() || stage.target_rps < 0.0 {
+                return Err(format!("Invalid target rate: {}", stage.target_rps));
+            }
+        }
+
+        let mut rng = StdRng::seed_from_u64(scenario.identities.seed);
+        let identities = (0..scenario.identities.count)
+            .map(|_| Identity::generate(&mut rng))
+            .collect();
+
+        Ok(Self {
+            steps,
+            identities,
+            schedule: schedule(&scenario.stages),
+            nonce,
+        })
+    }
+
+    pub fn requests(&self) -> usize {
+        self.schedule.len()
+    }
+
+    fn total_weight(&self) -> u64 {
+        self.steps.iter().map(|step| step.weight).sum()
+    }
+
+    /// Returns the step picked by `choice`, a number taken uniformly from
+    /// `0..total_weight`.
+    fn pick_step(&self, mut choice: u64) -> &PlannedStep {
+        for step in &self.steps {
+            if choice < step.weight {
+                return step;
+            }
+            choice -= step.weight;
+        }
+        unreachable!("The choice is smaller than the total weight")
+    }
+
+    /// Generates the `n`-th call of the scenario.
+    pub fn generate_call(&self, n: usize) -> ScenarioCall<'_> {
+        let mut rng = rand::thread_rng();
+        let step = self.pick_step(rng.next_u64() % self.total_weight());
+        let identity = &self.identities[n % self.identities.len()];
+        let arg = encode_args(
+            &step.args,
+            n,
+            rng.next_u64(),
+            &self.nonce,
+            &identity.principal,
+        )
+        .expect("Arguments were validated when loading the scenario");
+        let method = step.method.clone();
+        let call = match step.kind {
+            StepKind::Query => EngineCall::Read { method, arg },
+            StepKind::Update => EngineCall::Write { method, arg },
+        };
+        ScenarioCall {
+            step,
+            identity,
+            call,
+        }
+    }
+}
+
+/// Substitutes the placeholders of the Candid text `template` and encodes it.
+fn encode_args(
+    template: &str,
+    n: usize,
+    random: u64,
+    nonce: &str,
+    caller: &PrincipalId,
+) -> Result<Vec<u8>, String> {
+    let text = template
+        .replace("{{n}}", &n.to_string())
+        .replace("{{random}}", &random.to_string())
+        .replace("{{nonce}}", nonce)
+        .replace("{{caller}}", &caller.to_string());
+    parse_idl_args(&text)
+        .map_err(|err| format!("Couldn't parse {:?}: {}", text, err))?
+        .to_bytes()
+        .map_err(|err| format!("Couldn't encode {:?}: {}", text, err))
+}
+
+/// Returns the offsets from the start at which requests are issued so that
+/// the rate changes linearly over each stage.
+fn schedule(stages: &[ScenarioStage]) -> Vec<Duration> {
+    let mut schedule = vec![];
+    let mut start_rps = 0f64;
+    let mut stage_start = Duration::ZERO;
+    // The fraction of a request carried over to the next millisecond.
+    let mut pending = 0f64;
+    for stage in stages {
+        let millis = stage.duration_secs * 1000;
+        for t in 0..millis {
+            // The rate in the middle of the millisecond is its average rate.
+            let rps = start_rps + (stage.target_rps - start_rps) * (t as f64 + 0.5) / millis as f64;
+            pending += rps / 1000f64;
+            // Tolerate the rounding errors accumulated over the stage.
+            while pending >= 1f64 - 1e-6 {
+                schedule.push(stage_start + Duration::from_millis(t));
+                pending -= 1f64;
+            }
+        }
+        start_rps = stage.target_rps;
+        stage_start += Duration::from_secs(stage.duration_secs);
+    }
+    schedule
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCENARIO: &str = r#"
+steps:
+  - name: balance
+    canister_id: rwlgt-iiaaa-aaaaa-aaaaa-cai
+    kind: query
+    method: icrc1_balance_of
+    weight: 3
+    args: '(record { owner = principal "{{caller}}" })'
+  - canister_id: rwlgt-iiaaa-aaaaa-aaaaa-cai
+    kind: update
+    method: write
+    args: '("{{nonce}}", {{n}} : nat64, {{random}} : nat64)'
+stages:
+  - duration_secs: 10
+    target_rps: 10
+  - duration_secs: 10
+    target_rps: 10
+  - duration_secs: 5
+    target_rps: 0
+identities:
+  count: 4
+  seed: 7
+"#;
+
+    fn plan() -> ScenarioPlan {
+        ScenarioPlan::new(serde_yaml::from_str(SCENARIO).unwrap(), "nonce".into()).unwrap()
+    }
+
+    #[test]
+    fn schedule_follows_stages() {
+        let plan = plan();
+        // 50 requests ramping up, 100 at a steady rate and 25 ramping down.
+        assert_eq!(plan.requests(), 175);
+        let in_range = |from: u64, to: u64| {
+            plan.schedule
+                .iter()
+                .filter(|t| **t >= Duration::from_secs(from) && **t < Duration::from_secs(to))
+                .count()
+        };
+        assert_eq!(in_range(0, 10), 50);
+        assert_eq!(in_range(10, 20), 100);
+        assert_eq!(in_range(20, 25), 25);
+        // The rate increases during the ramp-up.
+        assert!(in_range(0, 5) < in_range(5, 10));
+        assert!(plan.schedule.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn identities_are_deterministic() {
+        let principals = |plan: ScenarioPlan| {
+            plan.identities
+                .iter()
+                .map(|identity| identity.principal)
+                .collect::<Vec<_>>()
+        };
+        let first = principals(plan());
+        assert_eq!(first.len(), 4);
+        assert_eq!(first, principals(plan()));
+        assert_ne!(first[0], first[1]);
+    }
+
+    #[test]
+    fn steps_are_picked_by_weight() {
+        let plan = plan();
+        assert_eq!(plan.total_weight(), 4);
+        let names: Vec<_> = (0..4).map(|c| plan.pick_step(c).name.clone()).collect();
+        assert_eq!(names, vec!["balance", "balance", "balance", "write"]);
+    }
+
+    #[test]
+    fn calls_substitute_placeholders() {
+        let mut scenario: Scenario = serde_yaml::from_str(SCENARIO).unwrap();
+        scenario.steps[1].weight = 0;
+        let plan = ScenarioPlan::new(scenario, "nonce".into()).unwrap();
+
+        let call = plan.generate_call(5);
+        assert_eq!(call.step.name, "balance");
+        assert_eq!(call.identity.principal, plan.identities[1].principal);
+        let expected = format!(
+            "(record {{ owner = principal \"{}\" }})",
+            plan.identities[1].principal
+        );
+        match call.call {
+            EngineCall::Read { method, arg } => {
+                assert_eq!(method, "icrc1_balance_of");
+                assert_eq!(arg, parse_idl_args(&expected).unwrap().to_bytes().unwrap());
+            }
+            EngineCall::Write { .. } => panic!("Expected a query"),
+        }
+    }
+
+    #[test]
+    fn invalid_scenarios_are_rejected() {
+        let mut scenario: Scenario = serde_yaml::from_str(SCENARIO).unwrap();
+        scenario.steps[0].args = "(record {".to_string();
+        assert!(ScenarioPlan::new(scenario, "nonce".into())
+            .err()
+            .unwrap()
+            .contains("Invalid arguments of step balance"));
+
+        let mut scenario: Scenario = serde_yaml::from_str(SCENARIO).unwrap();
+        scenario.steps[1].canister_id = "not-a-principal".to_string();
+        assert!(ScenarioPlan::new(scenario, "nonce".into()).is_err());
+
+        let mut scenario: Scenario = serde_yaml::from_str(SCENARIO).unwrap();
+        scenario.identities.count = 0;
+        assert!(ScenarioPlan::new(scenario, "nonce".into()).is_err());
+    }
+}
@@ -1,6 +1,11 @@
 use crate::{chart::Chart, collector::RequestInfo, content_length::ContentLength, ChartSize};
 use std::time::Instant;
-use std::{cmp, collections::HashMap, fmt, time::Duration};
+use std::{
+    cmp,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    time::Duration,
+};
 
 use serde::Serialize;
 
@@ -43,6 +48,8 @@ pub struct Fact {
     time_request_end: Instant,
     content_length: ContentLength,
     success: bool,
+    // The scenario step the request belongs to, if any.
+    step: Option<String>,
 }
 
 impl Fact {
@@ -64,8 +71,14 @@ impl Fact {
             time_request_end,
             content_length,
             success,
+            step: None,
         }
     }
+
+    pub fn with_step(mut self, step: Option<String>) -> Fact {
+        self.step = step;
+        self
+    }
 }
 impl RequestInfo for Fact {
     fn is_succ(&self) -> bool {
@@ -78,9 +91,9 @@ struct DurationStats {
 }
 
 impl DurationStats {
-    fn from_facts(facts: &[Fact]) -> DurationStats {
+    fn from_facts<'a>(facts: impl IntoIterator<Item = &'a Fact>) -> DurationStats {
         let mut sorted: Vec<Duration> = facts
-            .iter()
+            .into_iter()
             .filter(|f| f.success)
             .map(|f| f.time_request_end - f.time_request_start)
             .collect();
@@ -162,6 +175,15 @@ impl DurationStats {
             .collect()
     }
 
+    /// The nearest-rank `p`-th percentile.
+    fn percentile(&self, p: f64) -> Option<Duration> {
+        if self.sorted.is_empty() {
+            return None;
+        }
+        let rank = ((p / 100.0) * self.sorted.len() as f64).ceil() as usize;
+        Some(self.sorted[rank.clamp(1, self.sorted.len()) - 1])
+    }
+
     fn total(&self) -> Duration {
         self.sorted.iter().sum()
     }
@@ -181,10 +203,53 @@ pub struct Summary {
     latency_histogram: Vec<u32>,
     succ_rate_histogram: HashMap<usize, u32>,
     status_counts: HashMap<u16, u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    steps: Vec<StepSummary>,
     #[serde(skip_serializing)]
     chart_size: ChartSize,
 }
 
+/// The latency percentiles of the successful requests of a scenario step.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StepSummary {
+    step: String,
+    count: u32,
+    succeeded: u32,
+    p50: Duration,
+    p90: Duration,
+    p95: Duration,
+    p99: Duration,
+    max: Duration,
+}
+
+impl StepSummary {
+    fn from_facts(facts: &[Fact]) -> Vec<StepSummary> {
+        let mut by_step: BTreeMap<&str, Vec<&Fact>> = BTreeMap::new();
+        for fact in facts {
+            if let Some(step) = &fact.step {
+                by_step.entry(step).or_default().push(fact);
+            }
+        }
+        by_step
+            .into_iter()
+            .map(|(step, facts)| {
+                let stats = DurationStats::from_facts(facts.iter().copied());
+                let percentile = |p| stats.percentile(p).unwrap_or_default();
+                StepSummary {
+                    step: step.to_string(),
+                    count: facts.len() as u32,
+                    succeeded: stats.sorted.len() as u32,
+                    p50: percentile(50.0),
+                    p90: percentile(90.0),
+                    p95: percentile(95.0),
+                    p99: percentile(99.0),
+                    max: stats.max().unwrap_or_default(),
+                }
+            })
+            .collect()
+    }
+}
+
 impl Summary {
     /// From a set of facts, calculate the statistics.
     pub fn from_facts(facts: &[Fact]) -> Summary {
@@ -211,6 +276,7 @@ impl Summary {
             content_length,
             status_counts,
             succ_rate_histogram: Summary::get_succ_rate_histogram(facts),
+            steps: StepSummary::from_facts(facts),
             ..Summary::from_durations(&DurationStats::from_facts(facts))
         }
     }
@@ -286,6 +352,7 @@ impl Summary {
             latency_histogram: vec![0; 0],
             succ_rate_histogram: HashMap::new(),
             status_counts: HashMap::new(),
+            steps: vec![],
             chart_size: ChartSize::Medium,
         }
     }
@@ -352,6 +419,29 @@ impl fmt::Display for Summary {
             };
             writeln!(f, "  {:4}: {:10}   {}", k, v, desc)?;
         }
+        if !self.steps.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Steps (latencies of successful requests in ms):")?;
+            writeln!(
+                f,
+                "  {:24} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                "step", "requests", "succeeded", "p50", "p90", "p95", "p99", "max"
+            )?;
+            for step in &self.steps {
+                writeln!(
+                    f,
+                    "  {:24} {:>10} {:>10} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>10.1}",
+                    step.step,
+                    step.count,
+                    step.succeeded,
+                    step.p50.to_ms(),
+                    step.p90.to_ms(),
+                    step.p95.to_ms(),
+                    step.p99.to_ms(),
+                    step.max.to_ms()
+                )?;
+            }
+        }
         if self.chart_size != ChartSize::None {
             writeln!(f)?;
             writeln!(f, "Latency Percentiles (2% of requests per bar):")?;
@@ -364,3 +454,70 @@ impl fmt::Display for Summary {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fact(step: &str, latency_ms: u64, success: bool) -> Fact {
+        let start = Instant::now();
+        Fact::record(
+            ContentLength::new(0),
+            if success { 200 } else { 0 },
+            start,
+            start + Duration::from_millis(latency_ms),
+            success,
+        )
+        .with_step(Some(step.to_string()))
+    }
+
+    #[test]
+    fn summarizes_latencies_per_step() {
+        let mut facts: Vec<Fact> = (1..=100).map(|ms| fact("query", ms, true)).collect();
+        facts.push(fact("query", 1_000, false));
+        facts.push(fact("update", 2_000, true));
+
+        let summary = Summary::from_facts(&facts);
+        assert_eq!(
+            summary.steps,
+            vec![
+                StepSummary {
+                    step: "query".to_string(),
+                    count: 101,
+                    succeeded: 100,
+                    p50: Duration::from_millis(50),
+                    p90: Duration::from_millis(90),
+                    p95: Duration::from_millis(95),
+                    p99: Duration::from_millis(99),
+                    max: Duration::from_millis(100),
+                },
+                StepSummary {
+                    step: "update".to_string(),
+                    count: 1,
+                    succeeded: 1,
+                    p50: Duration::from_millis(2_000),
+                    p90: Duration::from_millis(2_000),
+                    p95: Duration::from_millis(2_000),
+                    p99: Duration::from_millis(2_000),
+                    max: Duration::from_millis(2_000),
+                },
+            ]
+        );
+        assert!(summary.to_string().contains("Steps"));
+    }
+
+    #[test]
+    fn facts_without_steps_have_no_step_summaries() {
+        let start = Instant::now();
+        let facts = vec![Fact::record(
+            ContentLength::new(0),
+            200,
+            start,
+            start + Duration::from_millis(1),
+            true,
+        )];
+        let summary = Summary::from_facts(&facts);
+        assert!(summary.steps.is_empty());
+        assert!(!summary.to_string().contains("Steps"));
+    }
+}